@@ -1,18 +1,128 @@
-use command_argus_logic::{Command, CommandStorage, EnvironmentVariable, CommandExecutor, CommandParameter, ParameterType};
-use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
-use std::collections::HashMap;
-use tauri::State;
+use command_argus_logic::command::{
+    Command, EnvironmentVariable, CommandParameter, ParameterType, CommandSource,
+};
+use command_argus_logic::error::CommandArgusError;
+use command_argus_logic::storage::{CommandStorage, data_dir};
+use command_argus_logic::executor::{CommandExecutor, ExecutionResult};
+use command_argus_logic::batch::{
+    BatchPlan, BatchRunner, BatchReport, PromptRequest, Prompter, resolve_parameters,
+};
+use command_argus_logic::ci::{CiTarget, export_ci_snippet};
+use command_argus_logic::importer::import_vscode_tasks;
+use command_argus_logic::project::{ProjectCommandSet, load_project_commands, find_project_root};
+use command_argus_logic::sync::{SyncConfig, SyncClient, SyncReport, HttpTransport};
+use command_argus_logic::settings::{SettingsStorage, WorkingDirectoryBase};
+use command_argus_logic::restricted::EditAccess;
+use command_argus_logic::audit::{current_user_name, AuditLog, AuditEvent};
+use command_argus_logic::approval::{ApprovalStore, ApprovalRequest};
+use command_argus_logic::scrub::OutputScrubber;
+use command_argus_logic::history::{
+    HistoryStorage, ExecutionRecord, ExecutionSummary, OutputStream, OutputChunk, line_count,
+    RecentFailure, CommandHealth,
+};
+use command_argus_logic::sandbox::SandboxPolicy;
+use command_argus_logic::expand::expand_env;
+use command_argus_logic::encoding::OutputEncoding;
+use command_argus_logic::shell::{ShellKind, ExecutionMode};
+use command_argus_logic::platform::{Platform, PlatformOverride};
+use command_argus_logic::tools::ToolCheck;
+use command_argus_logic::preflight::{Precondition, PreflightFailure};
+use command_argus_logic::assertions::{Assertion, AssertionResult};
+use command_argus_logic::queue::{ExecutionQueue, QueuedExecution};
+use command_argus_logic::ratelimit::RateLimit;
+use command_argus_logic::impact::Impact;
+use command_argus_logic::bundle::{BundleChangelog, load_bundle, diff_bundles};
+use command_argus_logic::saved_search::{SavedSearch, SavedSearchStorage};
+use command_argus_logic::usage::{StorageStats, storage_stats};
+use command_argus_logic::onboarding::{
+    StarterCommand, available_starters, needs_onboarding, EnvironmentReport,
+};
+use command_argus_logic::notify::{
+    NotificationRule, NotificationTrigger, NotificationTarget, Notification, Notifier,
+    notify_execution, NotificationSettings, CommandNotifications,
+};
+use command_argus_logic::variables::{Variable, VariableScope, VariableStorage, VariableTrace};
+use command_argus_logic::profile::{Profile, ProfileStorage};
+use command_argus_logic::foreach::{ForEachRunner, DirectoryOutcome, matching_directories};
+use command_argus_logic::artifacts::Artifact;
+use command_argus_logic::scratch::ScratchDirPolicy;
+use command_argus_logic::open_output::OpenOutput;
+use command_argus_logic::links::{OutputLink, LinkKind, extract_links};
+use command_argus_logic::highlight::{Highlight, HighlightRule, Highlighter};
+use command_argus_logic::problems::{ProblemMatcher, ProblemFinder, Problem};
+use command_argus_logic::share::{ShareTarget, share_text, share_filename};
+use command_argus_logic::launch::{LaunchRequest, parse_launch_args};
+use command_argus_logic::shutdown::{
+    RunningJobs, RunningJob, ShutdownPolicy, ShutdownSettings, reconcile_interrupted,
+    RecoveryNotice,
+};
+use command_argus_logic::agent::{
+    Agent, AgentInfo, AutostartEntry, AGENT_ARG, find_agent, stop_on_signal,
+};
+use command_argus_logic::ipc::{AgentClient, RunRequest};
+use command_argus_logic::benchmark::{
+    BenchmarkRunner, BenchmarkRun, BenchmarkReport, BenchmarkSide, Confidence,
+};
+use command_argus_logic::lint::LintSuggestion;
+use command_argus_logic::parameterize::{ParameterCandidate, ParameterSelection};
+use command_argus_logic::explain::CommandExplanation;
+use command_argus_logic::help::{HelpCache, HelpText};
+use command_argus_logic::aliases::{ShellAlias, load_shell_aliases, find_alias, expand_alias};
+use command_argus_logic::validation::{SyntaxWarning, validate_command};
+use command_argus_logic::builders::CommandBuilder;
+use command_argus_logic::replace::{ReplaceScope, ReplaceChange};
+use command_argus_logic::migrate::{
+    StateArchive, StateManifest, MigrationSource, MigrationPreview, MigrationOptions, StateCategory,
+};
+use command_argus_logic::remap::{PathRemapRule, PathRemapper, PathRemapReport};
+use command_argus_logic::dead_paths::DeadPath;
+use command_argus_logic::env_preview::EnvironmentPreview;
+use command_argus_logic::replay::{ReplayOverrides, prepare_replay};
+use command_argus_logic::tail::OutputSpool;
+use command_argus_logic::timeline::{OutputEvent, Phase};
+use command_argus_logic::guard::{Clearance, GuardedRun};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State, WindowEvent};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
 use uuid::Uuid;
 
 // State to hold the CommandStorage instance
 struct AppState {
     storage: Mutex<CommandStorage>,
-    executor: CommandExecutor,
+    // Commands from the currently opened project's .argus.yaml (read-only overlay)
+    project: Mutex<Option<ProjectCommandSet>>,
+    settings: Mutex<SettingsStorage>,
+    approvals: Mutex<ApprovalStore>,
+    history: Mutex<HistoryStorage>,
+    saved_searches: Mutex<SavedSearchStorage>,
+    variables: Mutex<VariableStorage>,
+    profiles: Mutex<ProfileStorage>,
+    // Limits concurrent executions; shared with the listener that emits queue events
+    queue: Arc<ExecutionQueue>,
+    // Batch runs waiting for the answer to a prompt, by run id. None cancels the prompt.
+    prompts: Mutex<HashMap<Uuid, mpsc::Sender<Option<String>>>>,
+    // What this process was started with, until the frontend has taken it
+    launch_request: Mutex<Option<LaunchRequest>>,
+    // Processes of running commands, stopped or left behind when the app quits
+    jobs: Arc<RunningJobs>,
+    // Set once the shutdown policy is being applied; the exit that follows goes through
+    shutting_down: AtomicBool,
+    // Runs the previous session left behind, until the frontend has taken the notice
+    recovery: Mutex<Option<RecoveryNotice>>,
+    // Output of the runs going on, by run id, for tailing it before the run is recorded
+    live_outputs: Mutex<HashMap<Uuid, Arc<OutputSpool>>>,
+    // Runs whose output is being followed with "execution-output-tail" events
+    followed_outputs: Mutex<HashSet<Uuid>>,
 }
 
 // DTOs for frontend communication
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct CommandDto {
     id: String,
     name: String,
@@ -28,15 +138,55 @@ struct CommandDto {
     use_count: u32,
     parameters: Vec<CommandParameterDto>,
     mise_enabled: bool,
+    requires_approval: bool,
+    no_network: bool,
+    output_encoding: OutputEncoding,
+    shell: ShellKind,
+    load_shell_profile: bool,
+    default_execution_mode: ExecutionMode,
+    platforms: Vec<Platform>,
+    platform_overrides: Vec<PlatformOverride>,
+    pinned_executable: Option<String>,
+    pin_warning: Option<String>,
+    tool_checks: Vec<ToolCheck>,
+    preconditions: Vec<Precondition>,
+    assertions: Vec<Assertion>,
+    priority: i32,
+    mutex_group: Option<String>,
+    rate_limit: Option<RateLimit>,
+    notifications: Option<CommandNotifications>,
+    impact: Impact,
+    author: Option<String>,
+    source: CommandSource,
+    maintainer_note: Option<String>,
+    profiles: Vec<String>,
+    forbidden_profiles: Vec<String>,
+    collect_artifacts: Vec<String>,
+    scratch_dir: ScratchDirPolicy,
+    stdin_template: Option<String>,
+    record_stdin: bool,
+    capture_timeline: bool,
+    phase_marker: Option<String>,
+    open_output_with: Option<OpenOutput>,
+    problem_matchers: Vec<ProblemMatcher>,
+    sandbox: SandboxPolicy,
+    capabilities: Vec<String>,
+    read_only: bool,
+    project_root: Option<String>,
+    // Derived from the history by the list and search commands
+    #[serde(default)]
+    health: CommandHealth,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct EnvironmentVariableDto {
     key: String,
     value: String,
+    #[serde(default)]
+    secret: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct CommandParameterDto {
     name: String,
     placeholder: String,
@@ -44,6 +194,8 @@ struct CommandParameterDto {
     required: bool,
     default_value: Option<String>,
     options: Option<Vec<String>>,
+    #[serde(default)]
+    secret: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,6 +209,36 @@ struct CreateCommandRequest {
     tags: Vec<String>,
     parameters: Vec<CommandParameterDto>,
     mise_enabled: Option<bool>,
+    requires_approval: Option<bool>,
+    no_network: Option<bool>,
+    output_encoding: Option<OutputEncoding>,
+    shell: Option<ShellKind>,
+    load_shell_profile: Option<bool>,
+    default_execution_mode: Option<ExecutionMode>,
+    platforms: Option<Vec<Platform>>,
+    platform_overrides: Option<Vec<PlatformOverride>>,
+    // Some(true) pins the program to its current absolute path, Some(false) unpins it
+    pin_executable: Option<bool>,
+    tool_checks: Option<Vec<ToolCheck>>,
+    preconditions: Option<Vec<Precondition>>,
+    assertions: Option<Vec<Assertion>>,
+    priority: Option<i32>,
+    mutex_group: Option<String>,
+    rate_limit: Option<RateLimit>,
+    notifications: Option<CommandNotifications>,
+    impact: Option<Impact>,
+    maintainer_note: Option<String>,
+    profiles: Option<Vec<String>>,
+    forbidden_profiles: Option<Vec<String>>,
+    collect_artifacts: Option<Vec<String>>,
+    scratch_dir: Option<ScratchDirPolicy>,
+    stdin_template: Option<String>,
+    record_stdin: Option<bool>,
+    capture_timeline: Option<bool>,
+    phase_marker: Option<String>,
+    open_output_with: Option<OpenOutput>,
+    problem_matchers: Option<Vec<ProblemMatcher>>,
+    sandbox: Option<SandboxPolicy>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -70,14 +252,117 @@ struct UpdateCommandRequest {
     tags: Option<Vec<String>>,
     parameters: Option<Vec<CommandParameterDto>>,
     mise_enabled: Option<bool>,
+    requires_approval: Option<bool>,
+    no_network: Option<bool>,
+    output_encoding: Option<OutputEncoding>,
+    shell: Option<ShellKind>,
+    load_shell_profile: Option<bool>,
+    default_execution_mode: Option<ExecutionMode>,
+    platforms: Option<Vec<Platform>>,
+    platform_overrides: Option<Vec<PlatformOverride>>,
+    // Some(true) pins the program to its current absolute path, Some(false) unpins it
+    pin_executable: Option<bool>,
+    tool_checks: Option<Vec<ToolCheck>>,
+    preconditions: Option<Vec<Precondition>>,
+    assertions: Option<Vec<Assertion>>,
+    priority: Option<i32>,
+    mutex_group: Option<String>,
+    // Missing leaves the limit unchanged, null removes it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    rate_limit: Option<Option<RateLimit>>,
+    // Missing leaves them unchanged, null removes them
+    #[serde(default, deserialize_with = "deserialize_some")]
+    notifications: Option<Option<CommandNotifications>>,
+    impact: Option<Impact>,
+    maintainer_note: Option<String>,
+    profiles: Option<Vec<String>>,
+    forbidden_profiles: Option<Vec<String>>,
+    collect_artifacts: Option<Vec<String>>,
+    scratch_dir: Option<ScratchDirPolicy>,
+    stdin_template: Option<String>,
+    record_stdin: Option<bool>,
+    capture_timeline: Option<bool>,
+    phase_marker: Option<String>,
+    // Missing leaves it unchanged, null removes it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    open_output_with: Option<Option<OpenOutput>>,
+    problem_matchers: Option<Vec<ProblemMatcher>>,
+    sandbox: Option<SandboxPolicy>,
+}
+
+// Wraps a present value in `Some`, so that together with `#[serde(default)]` an
+// `Option<Option<T>>` tells a missing field apart from an explicit null
+fn deserialize_some<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
 }
 
 #[derive(Serialize, Deserialize)]
 struct ExecutionResultDto {
+    // History record holding the full output
+    execution_id: String,
+    // At most OUTPUT_PREVIEW_LINES lines of each stream; the rest is fetched with get_execution_output
     stdout: String,
     stderr: String,
+    stdout_lines: usize,
+    stderr_lines: usize,
     exit_code: i32,
     success: bool,
+    warnings: Vec<String>,
+    duration_ms: u64,
+    assertion_results: Vec<AssertionResult>,
+    artifacts: Vec<Artifact>,
+    // Folder the artifacts were copied into, if any were collected
+    artifacts_dir: Option<String>,
+    // URLs and files mentioned in the output
+    links: Vec<OutputLink>,
+    // Errors, warnings and passes in the previews of stdout and stderr
+    highlights: Vec<Highlight>,
+    // Found by the command's problem matchers in the whole output
+    problems: Vec<Problem>,
+    // Both streams in the order they were written, for commands capturing a timeline;
+    // at most OUTPUT_PREVIEW_LINES lines per stream
+    timeline: Vec<OutputEvent>,
+    // Named by the command's phase markers, with how long each took
+    phases: Vec<Phase>,
+}
+
+// A change to the data all windows share, broadcast as "store-updated" so that edits
+// made in one window show up in the others. Entities carry their new value, or none
+// when they were removed; `Commands` and `History` mean a list changed as a whole.
+#[derive(Clone, Serialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+enum StoreDelta {
+    Command { id: String, command: Option<CommandDto> },
+    Commands,
+    SavedSearch { id: String, saved_search: Option<SavedSearch> },
+    Setting { name: &'static str, value: serde_json::Value },
+    Approval { request: ApprovalRequest },
+    Variable { name: String, scope: VariableScope, variable: Option<Variable> },
+    Profile { name: String, profile: Option<Profile> },
+    NotificationRule { id: String, rule: Option<NotificationRule> },
+    Execution { summary: ExecutionSummary },
+    History,
+}
+
+fn broadcast(app: &AppHandle, delta: StoreDelta) {
+    let _ = app.emit("store-updated", delta);
+}
+
+fn broadcast_setting(app: &AppHandle, name: &'static str, value: impl Serialize) {
+    let value = serde_json::to_value(value).unwrap_or_default();
+    broadcast(app, StoreDelta::Setting { name, value });
+}
+
+// Label of the window opened at startup
+const MAIN_WINDOW: &str = "main";
+
+// Sends an event of a run to the window that started it, or to all windows when the
+// run did not name one
+fn emit_to_window<S: Serialize + Clone>(app: &AppHandle, window: Option<&str>, event: &str, payload: S) -> tauri::Result<()> {
+    match window {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit(event, payload),
+    }
 }
 
 // Convert ParameterType to string
@@ -114,6 +399,7 @@ fn command_to_dto(cmd: &Command) -> CommandDto {
             .map(|ev| EnvironmentVariableDto {
                 key: ev.key.clone(),
                 value: ev.value.clone(),
+                secret: ev.secret,
             })
             .collect(),
         tags: cmd.tags.clone(),
@@ -130,32 +416,497 @@ fn command_to_dto(cmd: &Command) -> CommandDto {
                 required: p.required,
                 default_value: p.default_value.clone(),
                 options: p.options.clone(),
+                secret: p.secret,
             })
             .collect(),
         mise_enabled: cmd.mise_enabled,
+        requires_approval: cmd.requires_approval,
+        no_network: cmd.no_network,
+        output_encoding: cmd.output_encoding,
+        shell: cmd.shell,
+        load_shell_profile: cmd.load_shell_profile,
+        default_execution_mode: cmd.default_execution_mode,
+        platforms: cmd.platforms.clone(),
+        platform_overrides: cmd.platform_overrides.clone(),
+        pinned_executable: cmd.pinned_executable.clone(),
+        pin_warning: cmd.pinned_executable_warning(),
+        tool_checks: cmd.tool_checks.clone(),
+        preconditions: cmd.preconditions.clone(),
+        assertions: cmd.assertions.clone(),
+        priority: cmd.priority,
+        mutex_group: cmd.mutex_group.clone(),
+        rate_limit: cmd.rate_limit,
+        notifications: cmd.notifications.clone(),
+        impact: cmd.impact,
+        author: cmd.author.clone(),
+        source: cmd.source.clone(),
+        maintainer_note: cmd.maintainer_note.clone(),
+        profiles: cmd.profiles.clone(),
+        forbidden_profiles: cmd.forbidden_profiles.clone(),
+        collect_artifacts: cmd.collect_artifacts.clone(),
+        scratch_dir: cmd.scratch_dir.clone(),
+        stdin_template: cmd.stdin_template.clone(),
+        record_stdin: cmd.record_stdin,
+        capture_timeline: cmd.capture_timeline,
+        phase_marker: cmd.phase_marker.clone(),
+        open_output_with: cmd.open_output_with.clone(),
+        problem_matchers: cmd.problem_matchers.clone(),
+        sandbox: cmd.sandbox.clone(),
+        capabilities: cmd.sandbox.capabilities(),
+        read_only: false,
+        project_root: None,
+        health: CommandHealth::Unknown,
+    }
+}
+
+// Convert a project-local command to CommandDto, marking it as read-only
+fn project_command_to_dto(cmd: &Command, project: &ProjectCommandSet) -> CommandDto {
+    CommandDto {
+        read_only: true,
+        project_root: Some(project.root.to_string_lossy().to_string()),
+        ..command_to_dto(cmd)
+    }
+}
+
+// Look up a command in the library, falling back to the opened project's commands.
+// The returned flag tells whether the command came from the project overlay.
+fn find_command(uuid: Uuid, storage: &CommandStorage, state: &AppState) -> Result<(Command, bool), String> {
+    let project = state.project.lock().map_err(|e| e.to_string())?;
+    if let Some(cmd) = project.as_ref().and_then(|p| p.get(uuid)) {
+        return Ok((cmd.clone(), true));
+    }
+    storage.read(uuid)
+        .map(|cmd| (cmd, false))
+        .map_err(|e| e.to_string())
+}
+
+// Restricted mode is enforced by the logic crate; these helpers only load the settings
+fn check_edit_allowed(admin_passphrase: Option<&str>, state: &AppState) -> Result<EditAccess, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.edit_access(admin_passphrase).map_err(|e| e.to_string())
+}
+
+fn check_execute_allowed(command: &Command, state: &AppState) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let settings = settings.load().map_err(|e| e.to_string())?;
+    settings.restricted_mode.check_execute(command).map_err(|e| e.to_string())
+}
+
+// Refuses a run that restricted mode, the approval workflow or the command's impact
+// doesn't allow, before anything is done for it. The run checks again as it starts.
+fn ensure_cleared(command: &Command, clearance: &Clearance, state: &AppState) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let settings = settings.load().map_err(|e| e.to_string())?;
+    clearance.check(command, &settings.restricted_mode).map_err(|e| e.to_string())
+}
+
+fn ensure_not_project_command(uuid: Uuid, state: &AppState) -> Result<(), String> {
+    let project = state.project.lock().map_err(|e| e.to_string())?;
+    if project.as_ref().is_some_and(|p| p.contains(uuid)) {
+        return Err("Project commands are read-only; edit .argus.yaml in the repository instead".to_string());
+    }
+    Ok(())
+}
+
+// Executor that resolves relative working directories against the configured base,
+// names the context variables with the configured prefix and keeps to restricted mode.
+fn configured_executor(state: &AppState) -> Result<CommandExecutor, String> {
+    // {project_root} is the opened project, if any
+    let workspace = current_workspace(state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let settings = settings.load().map_err(|e| e.to_string())?;
+    let executor = CommandExecutor::new()
+        .with_context_prefix(settings.context_env_prefix.clone())
+        .with_restricted_mode(settings.restricted_mode.clone())
+        .with_secret_patterns(settings.secret_patterns.clone())
+        .with_jobs(state.jobs.clone());
+    let executor = match workspace {
+        Some(root) => executor.with_project_root(root),
+        None => executor,
+    };
+    Ok(match settings.working_directory_base_dir() {
+        Some(base_dir) => executor.with_base_dir(base_dir),
+        None => executor,
+    })
+}
+
+// Root of the opened project, whose workspace variables override the global ones
+fn current_workspace(state: &AppState) -> Result<Option<PathBuf>, String> {
+    let project = state.project.lock().map_err(|e| e.to_string())?;
+    Ok(project.as_ref().map(|p| p.root.clone()))
+}
+
+// Replace the command's {{var:NAME}} references with the values of the profile and
+// the current workspace
+fn resolve_variables(command: &Command, profile: Option<&Profile>, state: &AppState) -> Result<Command, String> {
+    let workspace = current_workspace(state)?;
+    let variables = state.variables.lock().map_err(|e| e.to_string())?;
+    variables.resolve_command(command, workspace.as_deref(), profile).map_err(|e| e.to_string())
+}
+
+// Runs with a forbidden profile are kept in the audit log
+fn record_profile_override(command: &Command, profile: &Profile, typed: &str) -> Result<(), String> {
+    if !command.forbidden_profiles.contains(&profile.name) || typed != command.name {
+        return Ok(());
+    }
+    let event = AuditEvent::new(
+        &current_user_name(),
+        "forbidden_profile_override",
+        Some(command.id),
+        format!("ran '{}' with the forbidden profile '{}'", command.name, profile.name),
+    );
+    AuditLog::new().and_then(|log| log.record(&event)).map_err(|e| e.to_string())
+}
+
+fn load_profile(name: Option<&str>, state: &AppState) -> Result<Option<Profile>, String> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    profiles.get(name).map(Some).map_err(|e| e.to_string())
+}
+
+// Lines of output sent along with an execution result
+const OUTPUT_PREVIEW_LINES: usize = 500;
+
+// The start of a timeline, up to OUTPUT_PREVIEW_LINES lines of each stream like the
+// previews
+fn timeline_preview(timeline: Vec<OutputEvent>) -> Vec<OutputEvent> {
+    let mut counts = [0; 2];
+    timeline
+        .into_iter()
+        .filter(|event| {
+            let count = &mut counts[event.stream as usize];
+            *count += 1;
+            *count <= OUTPUT_PREVIEW_LINES
+        })
+        .collect()
+}
+
+fn output_preview(output: &str) -> String {
+    if line_count(output) <= OUTPUT_PREVIEW_LINES {
+        return output.to_string();
+    }
+    OutputChunk::from_text(output, 0, OUTPUT_PREVIEW_LINES).lines.join("\n")
+}
+
+// Scrub secrets from the output, record the run in history and convert it for the frontend.
+// The id of the history record is the id the command saw as ARGUS_RUN_ID.
+fn finish_execution(
+    run: &GuardedRun,
+    mut result: Result<ExecutionResult, CommandArgusError>,
+    app: &AppHandle,
+) -> Result<ExecutionResultDto, String> {
+    let state = app.state::<AppState>();
+    let settings = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.load().map_err(|e| e.to_string())?
+    };
+    let highlighter = Highlighter::new(&settings.highlight_rules).map_err(|e| e.to_string())?;
+    let record = run.finish(&mut result);
+    
+    // Taken from the whole output, before large outputs are moved out of the record
+    let links = match &result {
+        Ok(exec_result) => {
+            let base_dir = exec_result.working_directory.clone().or_else(|| std::env::current_dir().ok());
+            let mut links = extract_links(&record.stdout, OutputStream::Stdout, base_dir.as_deref());
+            for link in extract_links(&record.stderr, OutputStream::Stderr, base_dir.as_deref()) {
+                if !links.iter().any(|l| l.target == link.target) {
+                    links.push(link);
+                }
+            }
+            links
+        }
+        Err(_) => Vec::new(),
+    };
+    
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let record = history.record(record).map_err(|e| e.to_string())?;
+    broadcast(app, StoreDelta::Execution { summary: record.summary() });
+    notify_in_background(app, run.command(), &record, settings.notifications);
+    
+    match result {
+        Ok(exec_result) => Ok(ExecutionResultDto {
+            execution_id: record.id.to_string(),
+            stdout: output_preview(&record.stdout),
+            stderr: output_preview(&record.stderr),
+            highlights: OutputStream::ALL
+                .into_iter()
+                .flat_map(|stream| highlighter.highlight(&output_preview(record.output(stream)), stream))
+                .collect(),
+            stdout_lines: line_count(&record.stdout),
+            stderr_lines: line_count(&record.stderr),
+            exit_code: exec_result.exit_code,
+            success: exec_result.success,
+            warnings: exec_result.warnings,
+            duration_ms: exec_result.duration.as_millis() as u64,
+            assertion_results: exec_result.assertion_results,
+            artifacts_dir: (!exec_result.artifacts.is_empty())
+                .then(|| history.artifacts_dir(record.id).to_string_lossy().to_string()),
+            artifacts: exec_result.artifacts,
+            links,
+            problems: exec_result.problems,
+            timeline: timeline_preview(exec_result.timeline),
+            phases: record.phases.clone(),
+        }),
+        Err(_) => Err(record.error.unwrap_or_default()),
+    }
+}
+
+// Shows desktop notifications by handing them to the frontend
+struct AppNotifier {
+    app: AppHandle,
+}
+
+impl Notifier for AppNotifier {
+    fn desktop(&self, notification: &Notification) -> Result<(), CommandArgusError> {
+        // Shown by one window only, not once per open window
+        let windows = self.app.webview_windows();
+        let window = match windows.contains_key(MAIN_WINDOW) {
+            true => Some(MAIN_WINDOW.to_string()),
+            false => windows.into_keys().min(),
+        };
+        emit_to_window(&self.app, window.as_deref(), "desktop-notification", notification)
+            .map_err(|e| CommandArgusError::Notification(e.to_string()))
+    }
+}
+
+// Evaluate the notification rules on another thread, so slow webhooks don't delay the result
+fn notify_in_background(app: &AppHandle, command: &Command, record: &ExecutionRecord, settings: NotificationSettings) {
+    if settings.rules.is_empty() && command.notifications.is_none() {
+        return;
+    }
+    let notifier = AppNotifier { app: app.clone() };
+    let (command, record) = (command.clone(), record.clone());
+    std::thread::spawn(move || {
+        for error in notify_execution(&settings, &command, &record, &notifier) {
+            eprintln!("{}", error);
+        }
+    });
+}
+
+// Wait for a slot in the execution queue, then run the command. Called from a blocking
+// thread so that queued executions do not hold up other commands.
+fn run_queued(
+    command: &Command,
+    parameters: &HashMap<String, String>,
+    mode: ExecutionMode,
+    profile: Option<Profile>,
+    profile_override: Option<String>,
+    options: RunOptions,
+    app: &AppHandle,
+) -> Result<ExecutionResultDto, String> {
+    let state = app.state::<AppState>();
+    let command = &resolve_variables(command, profile.as_ref(), &state)?;
+    let _slot = state.queue.acquire(command).map_err(|e| e.to_string())?;
+    
+    // The executor checks the profile and applies it; the guarded command gets its
+    // environment variables too, so secret ones are scrubbed from the output
+    let (executor, applied) = match &profile {
+        Some(profile) => (configured_executor(&state)?.with_profile(profile.clone()), profile.apply(command)),
+        None => (configured_executor(&state)?, command.clone()),
+    };
+    // Checked once the command's turn has come, so queued runs count against the limit
+    let run_id = Uuid::new_v4();
+    let settings = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.load().map_err(|e| e.to_string())?
+    };
+    let (run, artifacts_dir, spool) = {
+        let history = state.history.lock().map_err(|e| e.to_string())?;
+        let run = GuardedRun::begin(run_id, &applied, parameters, &options.clearance, &settings, &history)
+            .map_err(|e| e.to_string())?;
+        (run, history.artifacts_dir(run_id), Arc::new(history.output_spool(run_id)))
+    };
+    let executor = match (profile, profile_override) {
+        (Some(profile), Some(typed)) => {
+            record_profile_override(command, &profile, &typed)?;
+            executor.with_profile_override(typed)
+        }
+        _ => executor,
+    };
+    // The executor scrubs the spool like the recorded output, as it may be written to disk
+    state.live_outputs.lock().map_err(|e| e.to_string())?.insert(run_id, spool.clone());
+    let executor = executor
+        .with_scrubber(run.scrubber().clone())
+        .with_run_id(run_id)
+        .with_artifacts_dir(artifacts_dir)
+        .with_output_listener(Arc::new(move |stream, text| spool.write(stream, text)));
+    let executor = match options.stdin {
+        Some(input) => executor.with_stdin(input),
+        None => executor,
+    };
+    let mut result = executor.execute_in_mode(&applied, mode);
+    // Post-run actions only follow runs that went well
+    if let Some(result) = result.as_mut().ok().filter(|result| result.success && result.assertions_passed()) {
+        if options.copy_output_to_clipboard {
+            copy_output_to_clipboard(app, result);
+        }
+        if let Some(open) = &applied.open_output_with {
+            open_output(app, open, run_id, result);
+        }
+    }
+    
+    let finished = finish_execution(&run, result, app);
+    // Recorded by now, so tails read the history from here on
+    if let Ok(mut live_outputs) = state.live_outputs.lock() {
+        live_outputs.remove(&run_id);
+    }
+    finished
+}
+
+// Per-run choices made when a command is started
+#[derive(Default)]
+struct RunOptions {
+    // Written to the command's standard input
+    stdin: Option<Vec<u8>>,
+    // Put the output on the clipboard after a successful run
+    copy_output_to_clipboard: bool,
+    // The confirmation the user gave, or the approval the run was started with
+    clearance: Clearance,
+}
+
+impl RunOptions {
+    // Reads the clipboard when the run is requested, for runs that take it as their input
+    fn from_request(
+        app: &AppHandle,
+        clearance: Clearance,
+        stdin_from_clipboard: Option<bool>,
+        copy_output_to_clipboard: Option<bool>,
+    ) -> Result<Self, String> {
+        let stdin = match stdin_from_clipboard.unwrap_or(false) {
+            true => Some(
+                app.clipboard()
+                    .read_text()
+                    .map_err(|e| format!("Could not read the clipboard: {}", e))?
+                    .into_bytes(),
+            ),
+            false => None,
+        };
+        Ok(Self { stdin, copy_output_to_clipboard: copy_output_to_clipboard.unwrap_or(false), clearance })
+    }
+}
+
+// Output larger than this is cut off before it is copied
+const CLIPBOARD_OUTPUT_LIMIT: usize = 64 * 1024;
+
+// Puts the trimmed output on the clipboard. Problems are reported as warnings, as the
+// run itself went fine
+fn copy_output_to_clipboard(app: &AppHandle, result: &mut ExecutionResult) {
+    let mut text = result.stdout.trim();
+    if text.len() > CLIPBOARD_OUTPUT_LIMIT {
+        let mut end = CLIPBOARD_OUTPUT_LIMIT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text = &text[..end];
+        result.warnings.push(format!("only the first {} KiB of the output was copied", CLIPBOARD_OUTPUT_LIMIT / 1024));
+    }
+    if let Err(e) = app.clipboard().write_text(text.to_string()) {
+        result.warnings.push(format!("could not copy the output to the clipboard: {}", e));
+    }
+}
+
+// Writes the output to a file in the temporary directory and opens it the way the
+// command asks, reporting problems as warnings
+fn open_output(app: &AppHandle, open: &OpenOutput, run_id: Uuid, result: &mut ExecutionResult) {
+    let opened = open
+        .write_output(&result.stdout, &std::env::temp_dir().join("command-argus"), run_id)
+        .map_err(|e| e.to_string())
+        .and_then(|path| {
+            app.opener()
+                .open_path(path.to_string_lossy(), open.program.as_deref())
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = opened {
+        result.warnings.push(format!("could not open the output: {}", e));
+    }
+}
+
+// Keep commands of at least `min_impact`, most dangerous first if `sort_by_impact` is set
+fn filter_by_impact(mut commands: Vec<CommandDto>, min_impact: Option<Impact>, sort_by_impact: Option<bool>) -> Vec<CommandDto> {
+    if let Some(min_impact) = min_impact {
+        commands.retain(|cmd| cmd.impact >= min_impact);
+    }
+    if sort_by_impact.unwrap_or(false) {
+        commands.sort_by(|a, b| b.impact.cmp(&a.impact));
+    }
+    commands
+}
+
+// Keep commands by `author` and by source kind ("manual", "imported" or "synced")
+fn filter_by_provenance(mut commands: Vec<CommandDto>, author: Option<String>, source: Option<String>) -> Vec<CommandDto> {
+    if let Some(author) = author {
+        commands.retain(|cmd| cmd.author.as_deref() == Some(author.as_str()));
+    }
+    if let Some(source) = source {
+        commands.retain(|cmd| cmd.source.kind() == source);
+    }
+    commands
+}
+
+// Fill in each command's health from the history, keeping only those in `health` if given
+fn filter_by_health(mut commands: Vec<CommandDto>, health: Option<CommandHealth>, state: &AppState) -> Result<Vec<CommandDto>, String> {
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let by_command = history.health_by_command().map_err(|e| e.to_string())?;
+    for cmd in &mut commands {
+        if let Ok(id) = Uuid::parse_str(&cmd.id) {
+            cmd.health = by_command.get(&id).copied().unwrap_or_default();
+        }
     }
+    if let Some(health) = health {
+        commands.retain(|cmd| cmd.health == health);
+    }
+    Ok(commands)
 }
 
 // Tauri commands
 #[tauri::command]
-fn list_commands(state: State<AppState>) -> Result<Vec<CommandDto>, String> {
+fn list_commands(
+    all_platforms: Option<bool>,
+    min_impact: Option<Impact>,
+    sort_by_impact: Option<bool>,
+    author: Option<String>,
+    source: Option<String>,
+    health: Option<CommandHealth>,
+    state: State<AppState>
+) -> Result<Vec<CommandDto>, String> {
+    // Commands declared for other platforms are hidden unless explicitly asked for
+    let platform = Platform::current();
+    let visible = |cmd: &Command| all_platforms.unwrap_or(false) || cmd.supports_platform(platform);
+    
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    storage.list()
-        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
-        .map_err(|e| e.to_string())
+    let mut commands: Vec<CommandDto> = storage.list()
+        .map(|commands| commands.iter().filter(|cmd| visible(cmd)).map(command_to_dto).collect())
+        .map_err(|e| e.to_string())?;
+    
+    let project = state.project.lock().map_err(|e| e.to_string())?;
+    if let Some(project) = project.as_ref() {
+        commands.extend(project.commands.iter().filter(|cmd| visible(cmd)).map(|cmd| project_command_to_dto(cmd, project)));
+    }
+    let commands = filter_by_impact(filter_by_provenance(commands, author, source), min_impact, sort_by_impact);
+    filter_by_health(commands, health, &state)
 }
 
 #[tauri::command]
 fn get_command(id: String, state: State<AppState>) -> Result<CommandDto, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let project = state.project.lock().map_err(|e| e.to_string())?;
+    if let Some(project) = project.as_ref() {
+        if let Some(cmd) = project.get(uuid) {
+            return Ok(project_command_to_dto(cmd, project));
+        }
+    }
     storage.read(uuid)
         .map(|cmd| command_to_dto(&cmd))
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn create_command(request: CreateCommandRequest, state: State<AppState>) -> Result<CommandDto, String> {
+fn create_command(request: CreateCommandRequest, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<CommandDto, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    
     let mut cmd = Command::new(request.name, request.command)
         .with_args(request.args);
     
@@ -168,7 +919,11 @@ fn create_command(request: CreateCommandRequest, state: State<AppState>) -> Resu
     }
     
     for env_var in request.environment_variables {
-        cmd.add_environment_variable(env_var.key, env_var.value);
+        cmd.environment_variables.push(EnvironmentVariable {
+            key: env_var.key,
+            value: env_var.value,
+            secret: env_var.secret,
+        });
     }
     
     for tag in request.tags {
@@ -183,6 +938,7 @@ fn create_command(request: CreateCommandRequest, state: State<AppState>) -> Resu
             required: param_dto.required,
             default_value: param_dto.default_value,
             options: param_dto.options,
+            secret: param_dto.secret,
         });
     }
     
@@ -190,22 +946,111 @@ fn create_command(request: CreateCommandRequest, state: State<AppState>) -> Resu
         cmd.mise_enabled = mise_enabled;
     }
     
+    if let Some(requires_approval) = request.requires_approval {
+        cmd.requires_approval = requires_approval;
+    }
+    if let Some(no_network) = request.no_network {
+        cmd.no_network = no_network;
+    }
+    if let Some(output_encoding) = request.output_encoding {
+        cmd.output_encoding = output_encoding;
+    }
+    if let Some(shell) = request.shell {
+        cmd.shell = shell;
+    }
+    if let Some(load_shell_profile) = request.load_shell_profile {
+        cmd.load_shell_profile = load_shell_profile;
+    }
+    if let Some(mode) = request.default_execution_mode {
+        cmd.default_execution_mode = mode;
+    }
+    if let Some(platforms) = request.platforms {
+        cmd.platforms = platforms;
+    }
+    if let Some(platform_overrides) = request.platform_overrides {
+        cmd.platform_overrides = platform_overrides;
+    }
+    if let Some(tool_checks) = request.tool_checks {
+        cmd.tool_checks = tool_checks;
+    }
+    if let Some(preconditions) = request.preconditions {
+        cmd.preconditions = preconditions;
+    }
+    if let Some(assertions) = request.assertions {
+        cmd.assertions = assertions;
+    }
+    if let Some(priority) = request.priority {
+        cmd.priority = priority;
+    }
+    cmd.mutex_group = request.mutex_group.filter(|group| !group.is_empty());
+    cmd.rate_limit = request.rate_limit;
+    cmd.notifications = request.notifications;
+    if let Some(impact) = request.impact {
+        cmd.impact = impact;
+    }
+    cmd.author = Some(current_user_name());
+    cmd.maintainer_note = request.maintainer_note.filter(|note| !note.is_empty());
+    if let Some(profiles) = request.profiles {
+        cmd.profiles = profiles;
+    }
+    if let Some(forbidden_profiles) = request.forbidden_profiles {
+        cmd.forbidden_profiles = forbidden_profiles;
+    }
+    if let Some(collect_artifacts) = request.collect_artifacts {
+        cmd.collect_artifacts = collect_artifacts;
+    }
+    if let Some(scratch_dir) = request.scratch_dir {
+        cmd.scratch_dir = scratch_dir;
+    }
+    cmd.stdin_template = request.stdin_template.filter(|template| !template.is_empty());
+    if let Some(record_stdin) = request.record_stdin {
+        cmd.record_stdin = record_stdin;
+    }
+    if let Some(capture_timeline) = request.capture_timeline {
+        cmd.capture_timeline = capture_timeline;
+    }
+    cmd.phase_marker = request.phase_marker.filter(|marker| !marker.is_empty());
+    cmd.open_output_with = request.open_output_with;
+    if let Some(problem_matchers) = request.problem_matchers {
+        ProblemFinder::new(&problem_matchers).map_err(|e| e.to_string())?;
+        cmd.problem_matchers = problem_matchers;
+    }
+    if let Some(sandbox) = request.sandbox {
+        cmd.sandbox = sandbox;
+    }
+    if request.pin_executable == Some(true) {
+        cmd.pin_executable().map_err(|e| e.to_string())?;
+    }
+    
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    storage.create(cmd)
+    let dto = storage.create(access, cmd)
         .map(|created_cmd| command_to_dto(&created_cmd))
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Command { id: dto.id.clone(), command: Some(dto.clone()) });
+    Ok(dto)
 }
 
 #[tauri::command]
-fn update_command(id: String, request: UpdateCommandRequest, state: State<AppState>) -> Result<CommandDto, String> {
+fn update_command(id: String, request: UpdateCommandRequest, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<CommandDto, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
     
-    storage.update(uuid, |cmd| {
+    if let Some(problem_matchers) = &request.problem_matchers {
+        ProblemFinder::new(problem_matchers).map_err(|e| e.to_string())?;
+    }
+    
+    let mut pin_result = Ok(());
+    storage.update(access, uuid, |cmd| {
         if let Some(name) = &request.name {
             cmd.name = name.clone();
         }
         if let Some(command) = &request.command {
+            // A pinned path for a different program would be wrong
+            if *command != cmd.command {
+                cmd.pinned_executable = None;
+            }
             cmd.command = command.clone();
         }
         if let Some(args) = &request.args {
@@ -222,6 +1067,7 @@ fn update_command(id: String, request: UpdateCommandRequest, state: State<AppSta
                 .map(|ev| EnvironmentVariable {
                     key: ev.key.clone(),
                     value: ev.value.clone(),
+                    secret: ev.secret,
                 })
                 .collect();
         }
@@ -237,135 +1083,2569 @@ fn update_command(id: String, request: UpdateCommandRequest, state: State<AppSta
                     required: p.required,
                     default_value: p.default_value.clone(),
                     options: p.options.clone(),
+                    secret: p.secret,
                 })
                 .collect();
         }
         if let Some(mise_enabled) = request.mise_enabled {
             cmd.mise_enabled = mise_enabled;
         }
+        if let Some(requires_approval) = request.requires_approval {
+            cmd.requires_approval = requires_approval;
+        }
+        if let Some(no_network) = request.no_network {
+            cmd.no_network = no_network;
+        }
+        if let Some(output_encoding) = request.output_encoding {
+            cmd.output_encoding = output_encoding;
+        }
+        if let Some(shell) = request.shell {
+            cmd.shell = shell;
+        }
+        if let Some(load_shell_profile) = request.load_shell_profile {
+            cmd.load_shell_profile = load_shell_profile;
+        }
+        if let Some(mode) = request.default_execution_mode {
+            cmd.default_execution_mode = mode;
+        }
+        if let Some(platforms) = &request.platforms {
+            cmd.platforms = platforms.clone();
+        }
+        if let Some(platform_overrides) = &request.platform_overrides {
+            cmd.platform_overrides = platform_overrides.clone();
+        }
+        if let Some(tool_checks) = &request.tool_checks {
+            cmd.tool_checks = tool_checks.clone();
+        }
+        if let Some(preconditions) = &request.preconditions {
+            cmd.preconditions = preconditions.clone();
+        }
+        if let Some(assertions) = &request.assertions {
+            cmd.assertions = assertions.clone();
+        }
+        if let Some(priority) = request.priority {
+            cmd.priority = priority;
+        }
+        if let Some(mutex_group) = &request.mutex_group {
+            // An empty group clears it
+            cmd.mutex_group = Some(mutex_group.clone()).filter(|group| !group.is_empty());
+        }
+        if let Some(rate_limit) = request.rate_limit {
+            cmd.rate_limit = rate_limit;
+        }
+        if let Some(notifications) = &request.notifications {
+            cmd.notifications = notifications.clone();
+        }
+        if let Some(impact) = request.impact {
+            cmd.impact = impact;
+        }
+        if let Some(maintainer_note) = &request.maintainer_note {
+            cmd.maintainer_note = Some(maintainer_note.clone()).filter(|note| !note.is_empty());
+        }
+        if let Some(profiles) = &request.profiles {
+            cmd.profiles = profiles.clone();
+        }
+        if let Some(forbidden_profiles) = &request.forbidden_profiles {
+            cmd.forbidden_profiles = forbidden_profiles.clone();
+        }
+        if let Some(collect_artifacts) = &request.collect_artifacts {
+            cmd.collect_artifacts = collect_artifacts.clone();
+        }
+        if let Some(scratch_dir) = &request.scratch_dir {
+            cmd.scratch_dir = scratch_dir.clone();
+        }
+        if let Some(stdin_template) = &request.stdin_template {
+            cmd.stdin_template = Some(stdin_template.clone()).filter(|template| !template.is_empty());
+        }
+        if let Some(record_stdin) = request.record_stdin {
+            cmd.record_stdin = record_stdin;
+        }
+        if let Some(capture_timeline) = request.capture_timeline {
+            cmd.capture_timeline = capture_timeline;
+        }
+        if let Some(phase_marker) = &request.phase_marker {
+            cmd.phase_marker = Some(phase_marker.clone()).filter(|marker| !marker.is_empty());
+        }
+        if let Some(open_output_with) = &request.open_output_with {
+            cmd.open_output_with = open_output_with.clone();
+        }
+        if let Some(problem_matchers) = &request.problem_matchers {
+            cmd.problem_matchers = problem_matchers.clone();
+        }
+        if let Some(sandbox) = &request.sandbox {
+            cmd.sandbox = sandbox.clone();
+        }
+        match request.pin_executable {
+            Some(true) => pin_result = cmd.pin_executable().map(|_| ()),
+            Some(false) => cmd.pinned_executable = None,
+            None => {}
+        }
         cmd.update();
     })
-    .and_then(|_| storage.read(uuid))
-    .map(|cmd| command_to_dto(&cmd))
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    pin_result.map_err(|e| e.to_string())?;
+    
+    let dto = storage.read(uuid)
+        .map(|cmd| command_to_dto(&cmd))
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Command { id, command: Some(dto.clone()) });
+    Ok(dto)
 }
 
 #[tauri::command]
-fn delete_command(id: String, state: State<AppState>) -> Result<(), String> {
+fn repin_executable(id: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<CommandDto, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    storage.delete(uuid).map_err(|e| e.to_string())
+    
+    let mut pin_result = Ok(());
+    let cmd = storage.update(access, uuid, |cmd| {
+        pin_result = cmd.pin_executable().map(|_| ());
+    })
+    .map_err(|e| e.to_string())?;
+    pin_result.map_err(|e| e.to_string())?;
+    let dto = command_to_dto(&cmd);
+    broadcast(&app, StoreDelta::Command { id, command: Some(dto.clone()) });
+    Ok(dto)
 }
 
+// Working directories, pinned executables and parameter defaults pointing nowhere,
+// with how to fix each
 #[tauri::command]
-fn search_commands_by_name(query: String, state: State<AppState>) -> Result<Vec<CommandDto>, String> {
+fn scan_dead_paths(state: State<AppState>) -> Result<Vec<DeadPath>, String> {
+    let base_dir = state.settings.lock().map_err(|e| e.to_string())?
+        .load()
+        .map_err(|e| e.to_string())?
+        .working_directory_base_dir();
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    storage.search_by_name(&query)
-        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
-        .map_err(|e| e.to_string())
+    let commands = storage.list().map_err(|e| e.to_string())?;
+    Ok(command_argus_logic::dead_paths::scan_dead_paths(&commands, base_dir.as_deref()))
+}
+
+// The environment the command would run with, to debug failures that depend on it
+#[tauri::command]
+fn preview_environment(id: String, profile: Option<String>, state: State<AppState>) -> Result<EnvironmentPreview, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let command = {
+        let storage = state.storage.lock().map_err(|e| e.to_string())?;
+        find_command(uuid, &storage, &state)?.0
+    };
+    let profile = load_profile(profile.as_deref(), &state)?;
+    let command = resolve_variables(&command, profile.as_ref(), &state)?;
+    let executor = match &profile {
+        Some(profile) => configured_executor(&state)?.with_profile(profile.clone()),
+        None => configured_executor(&state)?,
+    };
+    command_argus_logic::env_preview::preview_environment(&executor, &command, profile.as_ref()).map_err(|e| e.to_string())
 }
 
+// Common mistakes in how the command is written, project commands included
 #[tauri::command]
-fn search_commands_by_tags(tags: Vec<String>, state: State<AppState>) -> Result<Vec<CommandDto>, String> {
+fn lint_command(id: String, state: State<AppState>) -> Result<Vec<LintSuggestion>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    storage.search_by_tags(&tags)
-        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
-        .map_err(|e| e.to_string())
+    let (command, _) = find_command(uuid, &storage, &state)?;
+    Ok(command_argus_logic::lint::lint_command(&command))
 }
 
+// Apply the fix of the command's `index`th lint suggestion, as `lint_command` listed them
 #[tauri::command]
-fn execute_command(id: String, use_shell: bool, state: State<AppState>) -> Result<ExecutionResultDto, String> {
+fn apply_lint_fix(id: String, index: usize, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<CommandDto, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    
-    // Get the command and mark it as used
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    let command = storage.read(uuid).map_err(|e| e.to_string())?;
-    
-    // Mark the command as used
-    storage.update(uuid, |cmd| {
-        cmd.mark_as_used();
-    }).map_err(|e| e.to_string())?;
     
-    // Execute the command
-    let result = if use_shell {
-        state.executor.execute_with_shell(&command)
-    } else {
-        state.executor.execute(&command)
-    };
+    let command = storage.read(uuid).map_err(|e| e.to_string())?;
+    let fix = command_argus_logic::lint::lint_command(&command)
+        .into_iter()
+        .nth(index)
+        .and_then(|suggestion| suggestion.fix)
+        .ok_or("The suggestion has no fix; the command may have changed")?;
+    let cmd = storage.update(access, uuid, |cmd| {
+        let program = cmd.command.clone();
+        fix.apply(cmd);
+        // A pinned path for a different program would be wrong
+        if cmd.command != program {
+            cmd.pinned_executable = None;
+        }
+        cmd.update();
+    })
+    .map_err(|e| e.to_string())?;
     
-    result
-        .map(|exec_result| ExecutionResultDto {
-            stdout: exec_result.stdout,
-            stderr: exec_result.stderr,
-            exit_code: exec_result.exit_code,
-            success: exec_result.success,
-        })
-        .map_err(|e| e.to_string())
+    let dto = command_to_dto(&cmd);
+    broadcast(&app, StoreDelta::Command { id, command: Some(dto.clone()) });
+    Ok(dto)
 }
 
+// What the command's parts and placeholders mean, to read before running it
 #[tauri::command]
-fn execute_command_with_parameters(
-    id: String,
-    parameters: HashMap<String, String>,
-    use_shell: bool,
-    state: State<AppState>
-) -> Result<ExecutionResultDto, String> {
+fn explain_command(id: String, state: State<AppState>) -> Result<CommandExplanation, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    
-    // Get the command and mark it as used
     let storage = state.storage.lock().map_err(|e| e.to_string())?;
-    let mut command = storage.read(uuid).map_err(|e| e.to_string())?;
-    
-    // Replace placeholders with parameter values
-    let (new_command, new_args) = command.replace_placeholders(&parameters);
-    command.command = new_command;
-    command.args = new_args;
-    
-    // Mark the command as used
-    storage.update(uuid, |cmd| {
-        cmd.mark_as_used();
-    }).map_err(|e| e.to_string())?;
-    
-    // Execute the command with replaced parameters
-    let result = if use_shell {
-        state.executor.execute_with_shell(&command)
-    } else {
-        state.executor.execute(&command)
-    };
-    
-    result
-        .map(|exec_result| ExecutionResultDto {
-            stdout: exec_result.stdout,
-            stderr: exec_result.stderr,
-            exit_code: exec_result.exit_code,
-            success: exec_result.success,
-        })
-        .map_err(|e| e.to_string())
+    let (command, _) = find_command(uuid, &storage, &state)?;
+    Ok(command_argus_logic::explain::explain_command(&command))
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    let app_state = AppState {
-        storage: Mutex::new(CommandStorage::new().expect("Failed to initialize storage")),
-        executor: CommandExecutor::new(),
+// The program's --help (or man page), shown beside the command editor
+#[tauri::command]
+async fn fetch_help(id: String, refresh: Option<bool>, app: AppHandle) -> Result<HelpText, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        let command = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            find_command(uuid, &storage, &state)?.0
+        };
+        check_execute_allowed(&command, &state)?;
+        let cache = HelpCache::new().map_err(|e| e.to_string())?;
+        cache.fetch(&command, refresh.unwrap_or(false)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Regex find & replace across the library; with dry_run only lists what would change
+#[tauri::command]
+fn bulk_replace(
+    pattern: String,
+    replacement: String,
+    scope: ReplaceScope,
+    dry_run: bool,
+    admin_passphrase: Option<String>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<Vec<ReplaceChange>, String> {
+    let save = match dry_run {
+        true => None,
+        false => Some(check_edit_allowed(admin_passphrase.as_deref(), &state)?),
     };
-    
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_dialog::init())
-        .manage(app_state)
-        .invoke_handler(tauri::generate_handler![
-            list_commands,
-            get_command,
-            create_command,
-            update_command,
-            delete_command,
-            search_commands_by_name,
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let changes = command_argus_logic::replace::bulk_replace(&storage, &pattern, &replacement, &scope, save).map_err(|e| e.to_string())?;
+    if !dry_run {
+        let mut changed: Vec<Uuid> = changes.iter().map(|change| change.command_id).collect();
+        changed.dedup();
+        for id in changed {
+            if let Ok(cmd) = storage.read(id) {
+                broadcast(&app, StoreDelta::Command { id: id.to_string(), command: Some(command_to_dto(&cmd)) });
+            }
+        }
+    }
+    Ok(changes)
+}
+
+// The command line a builder form currently describes
+#[tauri::command]
+fn preview_builder(builder: CommandBuilder) -> Result<String, String> {
+    let command = builder.build(String::new()).map_err(|e| e.to_string())?;
+    Ok(command.full_command())
+}
+
+// Saves the command a builder form describes
+#[tauri::command]
+fn create_from_builder(name: String, builder: CommandBuilder, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<CommandDto, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    if name.trim().is_empty() {
+        return Err("Name is required".to_string());
+    }
+    let mut cmd = builder.build(name.trim().to_string()).map_err(|e| e.to_string())?;
+    cmd.author = Some(current_user_name());
+
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let dto = storage.create(access, cmd)
+        .map(|created_cmd| command_to_dto(&created_cmd))
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Command { id: dto.id.clone(), command: Some(dto.clone()) });
+    Ok(dto)
+}
+
+// The arguments typed as one line, for the form's raw editing mode
+#[tauri::command]
+fn split_args(line: String) -> Result<Vec<String>, String> {
+    command_argus_logic::args::split_args(&line).map_err(|e| e.to_string())
+}
+
+// The line that splits back into exactly these arguments
+#[tauri::command]
+fn join_args(args: Vec<String>) -> String {
+    command_argus_logic::args::join_args(&args)
+}
+
+// Syntax warnings for the line the form's command would run as in shell mode
+#[tauri::command]
+fn validate_command_request(request: CreateCommandRequest) -> Result<Vec<SyntaxWarning>, String> {
+    let mut command = Command::new(request.name, request.command).with_args(request.args);
+    if let Some(shell) = request.shell {
+        command.shell = shell;
+    }
+    Ok(validate_command(&command))
+}
+
+#[derive(Serialize)]
+struct AliasOffer {
+    alias: ShellAlias,
+    // The command with the alias expanded, unless the alias needs a shell
+    command: Option<String>,
+    args: Vec<String>,
+    error: Option<String>,
+}
+
+// Whether the program about to be saved is only a shell alias, and what it expands to
+#[tauri::command]
+fn detect_alias(command: String, args: Vec<String>) -> Result<Option<AliasOffer>, String> {
+    let aliases = load_shell_aliases();
+    let mut candidate = Command::new(String::new(), command).with_args(args);
+    let Some(alias) = find_alias(&candidate, &aliases).cloned() else {
+        return Ok(None);
+    };
+    Ok(Some(match expand_alias(&mut candidate, &alias) {
+        Ok(()) => AliasOffer { alias, command: Some(candidate.command), args: candidate.args, error: None },
+        Err(e) => AliasOffer { alias, command: None, args: Vec::new(), error: Some(e.to_string()) },
+    }))
+}
+
+// Paths, hostnames and versions in the command that could become parameters
+#[tauri::command]
+fn suggest_parameters(id: String, state: State<AppState>) -> Result<Vec<ParameterCandidate>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let (command, _) = find_command(uuid, &storage, &state)?;
+    Ok(command_argus_logic::parameterize::suggest_parameters(&command))
+}
+
+// Turn the selected parts of the command into parameters defaulting to them
+#[tauri::command]
+fn parameterize_command(
+    id: String,
+    selections: Vec<ParameterSelection>,
+    admin_passphrase: Option<String>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<CommandDto, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    
+    let mut result = Ok(());
+    let cmd = storage.update(access, uuid, |cmd| result = command_argus_logic::parameterize::parameterize_command(cmd, &selections))
+        .map_err(|e| e.to_string())?;
+    result.map_err(|e| e.to_string())?;
+    
+    let dto = command_to_dto(&cmd);
+    broadcast(&app, StoreDelta::Command { id, command: Some(dto.clone()) });
+    Ok(dto)
+}
+
+// Renames a parameter together with every placeholder that refers to it
+#[tauri::command]
+fn rename_parameter(
+    id: String,
+    old: String,
+    new: String,
+    admin_passphrase: Option<String>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<CommandDto, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    
+    let mut result = Ok(());
+    let cmd = storage.update(access, uuid, |cmd| result = command_argus_logic::parameterize::rename_parameter(cmd, &old, &new))
+        .map_err(|e| e.to_string())?;
+    result.map_err(|e| e.to_string())?;
+    
+    let dto = command_to_dto(&cmd);
+    broadcast(&app, StoreDelta::Command { id, command: Some(dto.clone()) });
+    Ok(dto)
+}
+
+// Run the command's preconditions without executing it
+#[tauri::command]
+fn preflight_command(id: String, state: State<AppState>) -> Result<Vec<PreflightFailure>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let command = {
+        let storage = state.storage.lock().map_err(|e| e.to_string())?;
+        find_command(uuid, &storage, &state)?.0
+    };
+    configured_executor(&state)?.preflight(&command).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_command(id: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    ensure_not_project_command(uuid, &state)?;
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.delete(access, uuid).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Command { id, command: None });
+    Ok(())
+}
+
+#[tauri::command]
+fn search_commands_by_name(
+    query: String,
+    min_impact: Option<Impact>,
+    sort_by_impact: Option<bool>,
+    author: Option<String>,
+    source: Option<String>,
+    health: Option<CommandHealth>,
+    state: State<AppState>
+) -> Result<Vec<CommandDto>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.search_by_name(&query)
+        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
+        .map(|commands| filter_by_impact(filter_by_provenance(commands, author, source), min_impact, sort_by_impact))
+        .map_err(|e| e.to_string())
+        .and_then(|commands| filter_by_health(commands, health, &state))
+}
+
+// Searches with the query language, e.g. `tag:deploy used:>10 updated:<30d name:~build`
+#[tauri::command]
+fn search_commands_by_query(
+    query: String,
+    min_impact: Option<Impact>,
+    sort_by_impact: Option<bool>,
+    author: Option<String>,
+    source: Option<String>,
+    health: Option<CommandHealth>,
+    state: State<AppState>
+) -> Result<Vec<CommandDto>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.search_by_query(&query)
+        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
+        .map(|commands| filter_by_impact(filter_by_provenance(commands, author, source), min_impact, sort_by_impact))
+        .map_err(|e| e.to_string())
+        .and_then(|commands| filter_by_health(commands, health, &state))
+}
+
+#[tauri::command]
+fn list_saved_searches(state: State<AppState>) -> Result<Vec<SavedSearch>, String> {
+    let saved_searches = state.saved_searches.lock().map_err(|e| e.to_string())?;
+    saved_searches.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn create_saved_search(name: String, query: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<SavedSearch, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let search = SavedSearch::new(name, query).map_err(|e| e.to_string())?;
+    let saved_searches = state.saved_searches.lock().map_err(|e| e.to_string())?;
+    let search = saved_searches.create(access, search).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::SavedSearch { id: search.id.to_string(), saved_search: Some(search.clone()) });
+    Ok(search)
+}
+
+#[tauri::command]
+fn update_saved_search(id: String, name: Option<String>, query: Option<String>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<SavedSearch, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let saved_searches = state.saved_searches.lock().map_err(|e| e.to_string())?;
+    let search = saved_searches.update(access, uuid, name, query).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::SavedSearch { id, saved_search: Some(search.clone()) });
+    Ok(search)
+}
+
+#[tauri::command]
+fn delete_saved_search(id: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let saved_searches = state.saved_searches.lock().map_err(|e| e.to_string())?;
+    saved_searches.delete(access, uuid).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::SavedSearch { id, saved_search: None });
+    Ok(())
+}
+
+// The commands currently in a saved search's virtual folder
+#[tauri::command]
+fn run_saved_search(id: String, state: State<AppState>) -> Result<Vec<CommandDto>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let saved_searches = state.saved_searches.lock().map_err(|e| e.to_string())?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    saved_searches.run_saved_search(uuid, &storage)
+        .map(|commands| commands.iter().map(command_to_dto).collect())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn search_commands_by_tags(
+    tags: Vec<String>,
+    min_impact: Option<Impact>,
+    sort_by_impact: Option<bool>,
+    author: Option<String>,
+    source: Option<String>,
+    health: Option<CommandHealth>,
+    state: State<AppState>
+) -> Result<Vec<CommandDto>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.search_by_tags(&tags)
+        .map(|commands| commands.into_iter().map(|cmd| command_to_dto(&cmd)).collect())
+        .map(|commands| filter_by_impact(filter_by_provenance(commands, author, source), min_impact, sort_by_impact))
+        .map_err(|e| e.to_string())
+        .and_then(|commands| filter_by_health(commands, health, &state))
+}
+
+#[tauri::command]
+async fn execute_command(
+    id: String,
+    // Overrides the command's default execution mode when given
+    use_shell: Option<bool>,
+    confirmation: Option<String>,
+    profile: Option<String>,
+    // The command's name, typed to run it with a profile it forbids
+    profile_override: Option<String>,
+    // Pipe the clipboard's text to the command's standard input
+    stdin_from_clipboard: Option<bool>,
+    copy_output_to_clipboard: Option<bool>,
+    app: AppHandle
+) -> Result<ExecutionResultDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let clearance = Clearance::from(confirmation);
+        let options = RunOptions::from_request(&app, clearance.clone(), stdin_from_clipboard, copy_output_to_clipboard)?;
+        
+        // Get the command and mark it as used
+        let command = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let (command, from_project) = find_command(uuid, &storage, &state)?;
+            ensure_cleared(&command, &clearance, &state)?;
+            
+            // Mark the command as used (project commands are never written to storage)
+            if !from_project {
+                storage.record_use(uuid).map_err(|e| e.to_string())?;
+            }
+            command
+        };
+        
+        let mode = use_shell.map_or(command.default_execution_mode, ExecutionMode::from_use_shell);
+        run_queued(&command, &HashMap::new(), mode, profile, profile_override, options, &app)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn execute_command_with_parameters(
+    id: String,
+    parameters: HashMap<String, String>,
+    // Overrides the command's default execution mode when given
+    use_shell: Option<bool>,
+    confirmation: Option<String>,
+    profile: Option<String>,
+    // The command's name, typed to run it with a profile it forbids
+    profile_override: Option<String>,
+    // Pipe the clipboard's text to the command's standard input
+    stdin_from_clipboard: Option<bool>,
+    copy_output_to_clipboard: Option<bool>,
+    app: AppHandle
+) -> Result<ExecutionResultDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let clearance = Clearance::from(confirmation);
+        let options = RunOptions::from_request(&app, clearance.clone(), stdin_from_clipboard, copy_output_to_clipboard)?;
+        
+        // Get the command and mark it as used
+        let command = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let (command, from_project) = find_command(uuid, &storage, &state)?;
+            ensure_cleared(&command, &clearance, &state)?;
+            
+            // Resolve the platform variant first so its arguments get the parameter values too
+            let mut command = command.for_platform(Platform::current()).map_err(|e| e.to_string())?;
+            
+            // Replace placeholders with parameter values
+            let (new_command, new_args) = command.replace_placeholders(&parameters);
+            command.command = new_command;
+            command.args = new_args;
+            command.working_directory = command.replace_working_directory_placeholders(&parameters);
+            command.stdin_template = command.render_stdin(&parameters);
+            
+            // Mark the command as used (project commands are never written to storage)
+            if !from_project {
+                storage.record_use(uuid).map_err(|e| e.to_string())?;
+            }
+            command
+        };
+        
+        let mode = use_shell.map_or(command.default_execution_mode, ExecutionMode::from_use_shell);
+        run_queued(&command, &parameters, mode, profile, profile_override, options, &app)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Runs a command again with the parameter values of a past run, changed by the overrides,
+// e.g. the same deploy as last time but with --force
+#[tauri::command]
+async fn replay_with_changes(
+    history_id: String,
+    overrides: ReplayOverrides,
+    confirmation: Option<String>,
+    profile: Option<String>,
+    profile_override: Option<String>,
+    app: AppHandle
+) -> Result<ExecutionResultDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let history_uuid = Uuid::parse_str(&history_id).map_err(|e| e.to_string())?;
+        let record = {
+            let history = state.history.lock().map_err(|e| e.to_string())?;
+            history.get(history_uuid).map_err(|e| e.to_string())?
+        };
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let clearance = Clearance::from(confirmation);
+        let options = RunOptions::from_request(&app, clearance.clone(), None, None)?;
+        
+        let (command, parameters) = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let (command, from_project) = find_command(record.command_id, &storage, &state)?;
+            ensure_cleared(&command, &clearance, &state)?;
+            
+            // Resolve the platform variant first so the extra arguments follow its own
+            let command = command.for_platform(Platform::current()).map_err(|e| e.to_string())?;
+            let (mut command, parameters) = prepare_replay(&record, &command, &overrides).map_err(|e| e.to_string())?;
+            let (new_command, new_args) = command.replace_placeholders(&parameters);
+            command.command = new_command;
+            command.args = new_args;
+            command.working_directory = command.replace_working_directory_placeholders(&parameters);
+            command.stdin_template = command.render_stdin(&parameters);
+            
+            if !from_project {
+                storage.record_use(command.id).map_err(|e| e.to_string())?;
+            }
+            (command, parameters)
+        };
+        
+        let mode = command.default_execution_mode;
+        run_queued(&command, &parameters, mode, profile, profile_override, options, &app)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn export_ci(ids: Vec<String>, target: CiTarget, state: State<AppState>) -> Result<String, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let mut commands = Vec::new();
+    for id in ids {
+        let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        commands.push(storage.read(uuid).map_err(|e| e.to_string())?);
+    }
+    export_ci_snippet(&commands, target).map_err(|e| e.to_string())
+}
+
+// Writes usage counts, last use, success rate and tags of every command to a CSV file
+// for review; returns how many commands it lists
+#[tauri::command]
+fn export_stats_csv(path: String, state: State<AppState>) -> Result<usize, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    command_argus_logic::stats::export_stats_csv(&storage, &history, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+// Packs commands, variables, profiles, settings, saved searches and optionally the
+// history into one zip file, to move them to another machine
+#[tauri::command]
+async fn export_everything(path: String, include_history: bool) -> Result<StateManifest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let archive = StateArchive::new().map_err(|e| e.to_string())?;
+        archive.export_everything(Path::new(&path), include_history).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Replaces the state with an archive made by export_everything; what it replaces is
+// kept under backups/
+#[tauri::command]
+async fn import_everything(path: String, include_history: bool, admin_passphrase: Option<String>, app: AppHandle) -> Result<StateManifest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+        // Hold the stores so nothing is written halfway through the import
+        let _storage = state.storage.lock().map_err(|e| e.to_string())?;
+        let _history = state.history.lock().map_err(|e| e.to_string())?;
+        let archive = StateArchive::new().map_err(|e| e.to_string())?;
+        let manifest = archive.import_everything(access, Path::new(&path), include_history).map_err(|e| e.to_string())?;
+        broadcast(&app, StoreDelta::Commands);
+        broadcast(&app, StoreDelta::History);
+        Ok(manifest)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Exports the given commands, or the whole library, as a bundle file. Returns the changes
+// since the bundle was last exported to the same file, if it was.
+#[tauri::command]
+fn export_bundle(path: String, ids: Option<Vec<String>>, state: State<AppState>) -> Result<Option<BundleChangelog>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let commands = match ids {
+        Some(ids) => {
+            let mut commands = Vec::new();
+            for id in ids {
+                let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+                commands.push(storage.read(uuid).map_err(|e| e.to_string())?);
+            }
+            commands
+        }
+        None => storage.list().map_err(|e| e.to_string())?,
+    };
+    command_argus_logic::bundle::export_bundle(commands, Path::new(&path))
+        .map(|bundle| bundle.changelog)
+        .map_err(|e| e.to_string())
+}
+
+// The changelog embedded in a bundle, for review before importing it
+#[tauri::command]
+fn read_bundle_changelog(path: String) -> Result<Option<BundleChangelog>, String> {
+    load_bundle(Path::new(&path))
+        .map(|bundle| bundle.changelog)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn diff_bundle_files(old_path: String, new_path: String) -> Result<BundleChangelog, String> {
+    let old = load_bundle(Path::new(&old_path)).map_err(|e| e.to_string())?;
+    let new = load_bundle(Path::new(&new_path)).map_err(|e| e.to_string())?;
+    diff_bundles(&old, &new).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct ImportOutcome {
+    created: Vec<CommandDto>,
+    // How the path remapping rules rewrote the imported commands
+    paths: PathRemapReport,
+}
+
+// Remaps the paths of commands from another machine and adds those whose name (or id)
+// isn't in the library yet, so importing the same commands again is harmless.
+fn import_remapped(access: EditAccess, mut commands: Vec<Command>, state: &AppState, app: &AppHandle) -> Result<ImportOutcome, String> {
+    let rules = state.settings.lock().map_err(|e| e.to_string())?
+        .load()
+        .map_err(|e| e.to_string())?
+        .path_remap_rules;
+    let paths = PathRemapper::new(rules).remap_commands(&mut commands);
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+
+    let mut created = Vec::new();
+    for cmd in commands {
+        if storage.read(cmd.id).is_ok() {
+            continue;
+        }
+        match storage.create(access, cmd) {
+            Ok(cmd) => created.push(command_to_dto(&cmd)),
+            Err(CommandArgusError::DuplicateName(_)) => continue,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    if !created.is_empty() {
+        broadcast(app, StoreDelta::Commands);
+    }
+    Ok(ImportOutcome { created, paths })
+}
+
+// Imports the tasks defined in a project folder. Tasks whose name is already
+// taken in the library are skipped so re-importing a project is harmless.
+#[tauri::command]
+fn import_project(path: String, sandbox: Option<bool>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<ImportOutcome, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let mut commands = import_vscode_tasks(Path::new(&path)).map_err(|e| e.to_string())?;
+    // Untrusted project tasks may only write inside the project and get no network
+    if sandbox.unwrap_or(false) {
+        for cmd in &mut commands {
+            cmd.sandbox = SandboxPolicy::confined_to(Path::new(&path));
+        }
+    }
+    import_remapped(access, commands, &state, &app)
+}
+
+// Adds the commands of a bundle someone else exported
+#[tauri::command]
+fn import_bundle(path: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<ImportOutcome, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let bundle = load_bundle(Path::new(&path)).map_err(|e| e.to_string())?;
+    import_remapped(access, bundle.commands, &state, &app)
+}
+
+#[tauri::command]
+fn get_path_remap_rules(state: State<AppState>) -> Result<Vec<PathRemapRule>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.path_remap_rules)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_path_remap_rules(rules: Vec<PathRemapRule>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.path_remap_rules = rules.clone();
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "path_remap_rules", rules);
+    Ok(())
+}
+
+#[tauri::command]
+fn open_project(path: String, state: State<AppState>, app: AppHandle) -> Result<Vec<CommandDto>, String> {
+    let project_set = load_project_commands(Path::new(&path)).map_err(|e| e.to_string())?;
+    let commands = project_set.commands.iter()
+        .map(|cmd| project_command_to_dto(cmd, &project_set))
+        .collect();
+    
+    let root = project_set.root.to_string_lossy().to_string();
+    let mut project = state.project.lock().map_err(|e| e.to_string())?;
+    *project = Some(project_set);
+    
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.remember_project_root(root).map_err(|e| e.to_string())?;
+    // The project's commands are listed in every window
+    broadcast(&app, StoreDelta::Commands);
+    Ok(commands)
+}
+
+// The project root above `path`, or above the opened project or the current directory,
+// suggested as the working directory of new commands
+#[tauri::command]
+fn suggest_project_root(path: Option<String>, state: State<AppState>) -> Result<Option<String>, String> {
+    let start = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => PathBuf::from(expand_env(&path)),
+        None => match current_workspace(&state)? {
+            Some(root) => root,
+            None => std::env::current_dir().map_err(|e| e.to_string())?,
+        },
+    };
+    Ok(find_project_root(&start).map(|root| root.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+fn close_project(state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let mut project = state.project.lock().map_err(|e| e.to_string())?;
+    *project = None;
+    broadcast(&app, StoreDelta::Commands);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_sync_config() -> Result<Option<SyncConfig>, String> {
+    let path = data_dir().map_err(|e| e.to_string())?.join("sync.json");
+    SyncConfig::load(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn save_sync_config(config: SyncConfig, admin_passphrase: Option<String>, state: State<AppState>) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let path = data_dir().map_err(|e| e.to_string())?.join("sync.json");
+    config.save(access, &path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sync_commands(admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<SyncReport, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let dir = data_dir().map_err(|e| e.to_string())?;
+    let config = SyncConfig::load(&dir.join("sync.json"))
+        .map_err(|e| e.to_string())?
+        .ok_or("Sync is not configured")?;
+    
+    let user = config.user.clone();
+    let client = SyncClient::new(HttpTransport::new(config), user, dir.join("sync_state.json"));
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let report = client.sync(&storage, access).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Commands);
+    Ok(report)
+}
+
+#[tauri::command]
+fn get_working_directory_base(state: State<AppState>) -> Result<WorkingDirectoryBase, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.working_directory_base)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_working_directory_base(base: WorkingDirectoryBase, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.working_directory_base = base;
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "working_directory_base", base);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_max_concurrent_executions(state: State<AppState>) -> Result<usize, String> {
+    Ok(state.queue.max_concurrent())
+}
+
+#[tauri::command]
+fn set_max_concurrent_executions(max: usize, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.max_concurrent_executions = max.max(1);
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    state.queue.set_max_concurrent(max);
+    broadcast_setting(&app, "max_concurrent_executions", state.queue.max_concurrent());
+    Ok(())
+}
+
+#[tauri::command]
+fn get_context_env_prefix(state: State<AppState>) -> Result<String, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.context_env_prefix)
+        .map_err(|e| e.to_string())
+}
+
+// An empty prefix stops passing ARGUS_* style variables to commands
+#[tauri::command]
+fn set_context_env_prefix(prefix: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.context_env_prefix = prefix.trim().to_string();
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "context_env_prefix", prefix.trim());
+    Ok(())
+}
+
+#[tauri::command]
+fn get_highlight_rules(state: State<AppState>) -> Result<Vec<HighlightRule>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.highlight_rules)
+        .map_err(|e| e.to_string())
+}
+
+// Rules are checked before they are saved, as one bad pattern would stop all highlighting
+#[tauri::command]
+fn set_highlight_rules(rules: Vec<HighlightRule>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    Highlighter::new(&rules).map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.highlight_rules = rules.clone();
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "highlight_rules", rules);
+    Ok(())
+}
+
+// One of the matchers VS Code ships, such as $tsc, to start a command's matcher from
+#[tauri::command]
+fn get_builtin_problem_matcher(name: String) -> Result<ProblemMatcher, String> {
+    ProblemMatcher::builtin(&name).ok_or_else(|| format!("Unknown problem matcher: {}", name))
+}
+
+#[tauri::command]
+fn get_share_targets(state: State<AppState>) -> Result<Vec<ShareTarget>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.share_targets)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_share_targets(targets: Vec<ShareTarget>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.share_targets = targets.clone();
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "share_targets", targets);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_shutdown_settings(state: State<AppState>) -> Result<ShutdownSettings, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.shutdown)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_shutdown_settings(shutdown: ShutdownSettings, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.shutdown = shutdown.clone();
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "shutdown", shutdown);
+    Ok(())
+}
+
+#[tauri::command]
+fn list_running_jobs(state: State<AppState>) -> Result<Vec<RunningJob>, String> {
+    Ok(state.jobs.list())
+}
+
+#[derive(Serialize)]
+struct AgentStatusDto {
+    // The background agent running now, if any
+    running: Option<AgentInfo>,
+    // Whether the agent starts at login
+    autostart: bool,
+}
+
+fn autostart_entry() -> Result<AutostartEntry, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    AutostartEntry::new(Platform::current(), &exe).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_agent_status() -> Result<AgentStatusDto, String> {
+    Ok(AgentStatusDto {
+        running: find_agent().map_err(|e| e.to_string())?,
+        autostart: autostart_entry()?.is_installed(),
+    })
+}
+
+// Starts the background agent at login, with this binary and `--agent`, or stops doing so
+#[tauri::command]
+fn set_agent_autostart(enabled: bool, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let entry = autostart_entry()?;
+    match enabled {
+        true => entry.install(),
+        false => entry.uninstall(),
+    }
+    .map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "agent_autostart", enabled);
+    Ok(())
+}
+
+// Starts the background agent now, unless one is running. It outlives the app.
+#[tauri::command]
+fn start_agent() -> Result<(), String> {
+    if find_agent().map_err(|e| e.to_string())?.is_some() {
+        return Ok(());
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    std::process::Command::new(exe)
+        .arg(AGENT_ARG)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_agent_jobs() -> Result<Vec<RunningJob>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        AgentClient::connect().and_then(|mut client| client.list_jobs()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Clone, Serialize)]
+struct AgentOutputEvent {
+    run_id: Uuid,
+    stream: OutputStream,
+    text: String,
+}
+
+// Runs a saved command in the background agent, so it survives this window. Its
+// output is sent to the window that asked as "agent-output" events.
+#[tauri::command]
+async fn run_in_agent(
+    id: String,
+    parameters: HashMap<String, String>,
+    use_shell: bool,
+    confirmation: Option<String>,
+    window: tauri::Window,
+) -> Result<ExecutionSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let command_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        let request = RunRequest { command_id, parameters, use_shell, confirmation };
+        let mut client = AgentClient::connect().map_err(|e| e.to_string())?;
+        let summary = client
+            .run(request, |run_id, stream, text| {
+                let _ = window.emit("agent-output", AgentOutputEvent { run_id, stream, text: text.to_string() });
+            })
+            .map_err(|e| e.to_string())?;
+        // The agent recorded the run in the shared history
+        broadcast(window.app_handle(), StoreDelta::Execution { summary: summary.clone() });
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// The binary started with `--agent`: the background agent, without a window or the
+// rest of the app
+fn run_agent() {
+    let served = Agent::new().and_then(|agent| {
+        agent.start()?;
+        agent.serve(stop_on_signal())
+    });
+    if let Err(error) = served {
+        eprintln!("{}", error);
+        std::process::exit(2);
+    }
+}
+
+// Runs cut short by a crash or left running by the previous session, recorded in history
+// on start. Handed out once, so only the first window shows the notice.
+#[tauri::command]
+fn take_recovery_notice(state: State<AppState>) -> Result<Option<RecoveryNotice>, String> {
+    let mut recovery = state.recovery.lock().map_err(|e| e.to_string())?;
+    Ok(recovery.take())
+}
+
+// Quits with the policy picked when the app asked what to do with running commands
+#[tauri::command]
+fn shut_down(policy: ShutdownPolicy, app: AppHandle) -> Result<(), String> {
+    if policy == ShutdownPolicy::Ask {
+        return Err("Choose whether to wait for, terminate or detach the running commands".to_string());
+    }
+    start_shutdown(&app, policy);
+    Ok(())
+}
+
+// How long runs stopped at shutdown get to be recorded in history before the app exits
+const RECORD_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Whether quitting has to wait because commands are running. If so, the shutdown policy
+// is applied, or `window` is asked which one to apply; without a window to ask the
+// commands are terminated.
+fn hold_quit(app: &AppHandle, window: Option<&str>) -> bool {
+    let state = app.state::<AppState>();
+    if state.shutting_down.load(Ordering::SeqCst) || state.jobs.is_empty() {
+        return false;
+    }
+    let policy = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|settings| settings.load().ok())
+        .map(|settings| settings.shutdown.policy)
+        .unwrap_or_default();
+    match (policy, window) {
+        (ShutdownPolicy::Ask, Some(label)) => {
+            let _ = app.emit_to(label, "shutdown-requested", state.jobs.list());
+        }
+        (ShutdownPolicy::Ask, None) => start_shutdown(app, ShutdownPolicy::Terminate),
+        (policy, _) => start_shutdown(app, policy),
+    }
+    true
+}
+
+// Applies `policy` to the running commands on another thread and exits once it is done.
+// Queued executions are cancelled and nothing new is started meanwhile.
+fn start_shutdown(app: &AppHandle, policy: ShutdownPolicy) {
+    let state = app.state::<AppState>();
+    if state.shutting_down.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    state.jobs.close();
+    for queued in state.queue.waiting() {
+        state.queue.cancel(queued.id);
+    }
+    let grace = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|settings| settings.load().ok())
+        .map(|settings| settings.shutdown.grace_secs)
+        .unwrap_or_else(|| ShutdownSettings::default().grace_secs);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        match policy {
+            ShutdownPolicy::Wait => {
+                state.jobs.wait_idle(None);
+            }
+            ShutdownPolicy::Terminate => state.jobs.terminate_all(Duration::from_secs(grace)),
+            ShutdownPolicy::Ask | ShutdownPolicy::Detach => state.jobs.detach_all(),
+        }
+        // Finished and terminated runs are recorded by the threads that ran them, which
+        // hold their queue slot until then; detached ones are recorded on the next start
+        if policy != ShutdownPolicy::Detach {
+            let started = Instant::now();
+            while state.queue.running() > 0 && started.elapsed() < RECORD_TIMEOUT {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        }
+        app.exit(0);
+    });
+}
+
+// The request the app was started with, such as `--run <command>`, handed out once.
+// Requests from later launches arrive as "launch-request" events instead.
+#[tauri::command]
+fn take_launch_request(state: State<AppState>) -> Result<Option<LaunchRequest>, String> {
+    let mut launch_request = state.launch_request.lock().map_err(|e| e.to_string())?;
+    Ok(launch_request.take())
+}
+
+// A second launch of the app ends right away and passes its arguments here, so only one
+// process works on the library. The request goes to the main window, which is brought
+// to the front.
+fn forward_launch(app: &AppHandle, args: Vec<String>) {
+    let windows = app.webview_windows();
+    let Some(window) = windows.get(MAIN_WINDOW).or_else(|| windows.values().next()) else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    if let Some(request) = parse_launch_args(&args) {
+        let _ = app.emit_to(window.label(), "launch-request", request);
+    }
+}
+
+// Opens another window on the same library. Windows keep each other current through
+// "store-updated" events; returns the new window's label.
+#[tauri::command]
+async fn open_window(app: AppHandle) -> Result<String, String> {
+    let label = format!("window-{}", Uuid::new_v4().simple());
+    tauri::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App("index.html".into()))
+        .title("Command Argus")
+        .inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(label)
+}
+
+// Upload a run's command line and output to the share target with this name and return
+// the URL. The history is already scrubbed; the current secret patterns are applied again.
+#[tauri::command]
+async fn share_execution(id: String, target: String, app: AppHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+        let settings = {
+            let settings = state.settings.lock().map_err(|e| e.to_string())?;
+            settings.load().map_err(|e| e.to_string())?
+        };
+        let share_target = settings
+            .share_targets
+            .into_iter()
+            .find(|t| t.name == target)
+            .ok_or_else(|| format!("No share target named '{}'", target))?;
+        let record = {
+            let history = state.history.lock().map_err(|e| e.to_string())?;
+            history.get(uuid).map_err(|e| e.to_string())?
+        };
+        let mut scrubber = OutputScrubber::new().with_default_patterns();
+        for pattern in &settings.secret_patterns {
+            scrubber.add_pattern(pattern).map_err(|e| e.to_string())?;
+        }
+        share_target
+            .service
+            .upload(&share_filename(&record), &share_text(&record, &scrubber))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn is_restricted_mode(state: State<AppState>) -> Result<bool, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.load()
+        .map(|s| s.restricted_mode.enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn enable_restricted_mode(admin_passphrase: String, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let access = settings.edit_access(Some(&admin_passphrase)).map_err(|e| e.to_string())?;
+    settings.update(access, |s| s.restricted_mode.enable(&admin_passphrase))
+        .map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "restricted_mode", true);
+    Ok(())
+}
+
+#[tauri::command]
+fn disable_restricted_mode(admin_passphrase: String, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let access = settings.edit_access(Some(&admin_passphrase)).map_err(|e| e.to_string())?;
+    settings.update(access, |s| s.restricted_mode.disable(&admin_passphrase))
+        .map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "restricted_mode", false);
+    Ok(())
+}
+
+#[tauri::command]
+fn request_execution(
+    id: String,
+    parameters: HashMap<String, String>,
+    use_shell: bool,
+    state: State<AppState>,
+    app: AppHandle
+) -> Result<ApprovalRequest, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let (command, _) = find_command(uuid, &storage, &state)?;
+    check_execute_allowed(&command, &state)?;
+    
+    let approvals = state.approvals.lock().map_err(|e| e.to_string())?;
+    let request = approvals.request(&command, parameters, use_shell, &current_user_name())
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Approval { request: request.clone() });
+    Ok(request)
+}
+
+#[tauri::command]
+fn list_approval_requests(state: State<AppState>) -> Result<Vec<ApprovalRequest>, String> {
+    let approvals = state.approvals.lock().map_err(|e| e.to_string())?;
+    approvals.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn approve_execution(
+    request_id: String,
+    approver: Option<String>,
+    admin_passphrase: Option<String>,
+    state: State<AppState>,
+    app: AppHandle
+) -> Result<ApprovalRequest, String> {
+    let uuid = Uuid::parse_str(&request_id).map_err(|e| e.to_string())?;
+    let restricted_mode = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.load().map_err(|e| e.to_string())?.restricted_mode
+    };
+    let approver = approver.unwrap_or_else(current_user_name);
+    
+    let approvals = state.approvals.lock().map_err(|e| e.to_string())?;
+    let request = approvals.approve(uuid, &approver, admin_passphrase.as_deref(), &restricted_mode)
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Approval { request: request.clone() });
+    Ok(request)
+}
+
+#[tauri::command]
+fn reject_execution(request_id: String, approver: Option<String>, state: State<AppState>, app: AppHandle) -> Result<ApprovalRequest, String> {
+    let uuid = Uuid::parse_str(&request_id).map_err(|e| e.to_string())?;
+    let approver = approver.unwrap_or_else(current_user_name);
+    let approvals = state.approvals.lock().map_err(|e| e.to_string())?;
+    let request = approvals.reject(uuid, &approver).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Approval { request: request.clone() });
+    Ok(request)
+}
+
+#[tauri::command]
+async fn run_approved_execution(request_id: String, app: AppHandle) -> Result<ExecutionResultDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&request_id).map_err(|e| e.to_string())?;
+        let request = {
+            let approvals = state.approvals.lock().map_err(|e| e.to_string())?;
+            approvals.take_approved(uuid, &current_user_name()).map_err(|e| e.to_string())?
+        };
+        broadcast(&app, StoreDelta::Approval { request: request.clone() });
+        
+        let command = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let (command, from_project) = find_command(request.command_id, &storage, &state)?;
+            ensure_cleared(&command, &Clearance::Approved, &state)?;
+            
+            let mut command = command.for_platform(Platform::current()).map_err(|e| e.to_string())?;
+            let (new_command, new_args) = command.replace_placeholders(&request.parameters);
+            command.command = new_command;
+            command.args = new_args;
+            command.working_directory = command.replace_working_directory_placeholders(&request.parameters);
+            command.stdin_template = command.render_stdin(&request.parameters);
+            
+            if !from_project {
+                storage.record_use(request.command_id).map_err(|e| e.to_string())?;
+            }
+            command
+        };
+        
+        let options = RunOptions { clearance: Clearance::Approved, ..RunOptions::default() };
+        run_queued(&command, &request.parameters, ExecutionMode::from_use_shell(request.use_shell), None, None, options, &app)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Cancel an execution that is still waiting in the queue
+#[tauri::command]
+fn cancel_queued(id: String, state: State<AppState>) -> Result<bool, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    Ok(state.queue.cancel(uuid))
+}
+
+#[tauri::command]
+fn list_queued(state: State<AppState>) -> Vec<QueuedExecution> {
+    state.queue.waiting()
+}
+
+#[tauri::command]
+fn list_history(command_id: Option<String>, limit: Option<usize>, state: State<AppState>) -> Result<Vec<ExecutionSummary>, String> {
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(100);
+    let records = match command_id {
+        Some(id) => {
+            let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+            history.list_for_command(uuid, limit).map_err(|e| e.to_string())?
+        }
+        None => history.list()
+            .map(|records| records.into_iter().rev().take(limit).collect())
+            .map_err(|e| e.to_string())?,
+    };
+    Ok(records.iter().map(ExecutionRecord::summary).collect())
+}
+
+// Past runs whose command, note or tags match the query, newest first
+#[tauri::command]
+fn search_history(query: String, limit: Option<usize>, state: State<AppState>) -> Result<Vec<ExecutionSummary>, String> {
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let records = history.search(&query, limit.unwrap_or(100)).map_err(|e| e.to_string())?;
+    Ok(records.iter().map(ExecutionRecord::summary).collect())
+}
+
+// Sets the note and tags of a past run, e.g. "the run that fixed prod"
+#[tauri::command]
+fn annotate_execution(id: String, note: Option<String>, tags: Vec<String>, app: AppHandle) -> Result<ExecutionSummary, String> {
+    let state = app.state::<AppState>();
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let record = history.annotate(uuid, note, tags).map_err(|e| e.to_string())?;
+    let summary = record.summary();
+    broadcast(&app, StoreDelta::Execution { summary: summary.clone() });
+    Ok(summary)
+}
+
+#[derive(Serialize)]
+struct RecentFailureDto {
+    command: CommandDto,
+    execution: ExecutionSummary,
+    stderr_snippet: String,
+}
+
+// Saved commands whose latest run failed, most recent first
+#[tauri::command]
+fn list_recent_failures(limit: Option<usize>, state: State<AppState>) -> Result<Vec<RecentFailureDto>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let failures = history.list_recent_failures(&storage, limit.unwrap_or(20)).map_err(|e| e.to_string())?;
+    Ok(failures
+        .into_iter()
+        .map(|RecentFailure { command, execution, stderr_snippet }| RecentFailureDto {
+            command: command_to_dto(&command),
+            execution,
+            stderr_snippet,
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+struct StepOutcomeDto {
+    step_id: String,
+    command: String,
+    success: bool,
+    skipped: Option<String>,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    stdout: String,
+    stderr: String,
+    error: Option<String>,
+    assertion_results: Vec<AssertionResult>,
+}
+
+#[derive(Serialize)]
+struct BatchReportDto {
+    run_id: String,
+    outcomes: Vec<StepOutcomeDto>,
+    duration_ms: u64,
+    summary: String,
+}
+
+fn batch_report_to_dto(report: BatchReport) -> BatchReportDto {
+    BatchReportDto {
+        run_id: report.run_id.to_string(),
+        duration_ms: report.duration.as_millis() as u64,
+        summary: report.summary(),
+        outcomes: report
+            .outcomes
+            .into_iter()
+            .map(|outcome| StepOutcomeDto {
+                step_id: outcome.step_id,
+                command: outcome.command,
+                success: outcome.success,
+                skipped: outcome.skipped,
+                exit_code: outcome.exit_code,
+                duration_ms: outcome.duration.as_millis() as u64,
+                stdout: output_preview(&outcome.stdout),
+                stderr: output_preview(&outcome.stderr),
+                error: outcome.error,
+                assertion_results: outcome.assertion_results,
+            })
+            .collect(),
+    }
+}
+
+// Asks prompt steps' questions in the frontend with a "batch-prompt" event and waits
+// for answer_prompt
+struct AppPrompter {
+    app: AppHandle,
+    // Window that started the batch and is asked
+    window: Option<String>,
+}
+
+impl Prompter for AppPrompter {
+    fn ask(&self, request: &PromptRequest) -> Result<String, CommandArgusError> {
+        let state = self.app.state::<AppState>();
+        let (sender, receiver) = mpsc::channel();
+        state.prompts.lock()
+            .map_err(|e| CommandArgusError::ExecutionFailed(e.to_string()))?
+            .insert(request.run_id, sender);
+        emit_to_window(&self.app, self.window.as_deref(), "batch-prompt", request)
+            .map_err(|e| CommandArgusError::ExecutionFailed(e.to_string()))?;
+        let answer = receiver.recv().ok().flatten();
+        if let Ok(mut prompts) = state.prompts.lock() {
+            prompts.remove(&request.run_id);
+        }
+        answer.ok_or_else(|| CommandArgusError::Cancelled("the prompt was not answered".to_string()))
+    }
+}
+
+// Answers the prompt a batch run is waiting on; no value cancels it, failing the step
+#[tauri::command]
+fn answer_prompt(run_id: String, value: Option<String>, state: State<AppState>) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&run_id).map_err(|e| e.to_string())?;
+    let prompts = state.prompts.lock().map_err(|e| e.to_string())?;
+    let sender = prompts.get(&uuid).ok_or_else(|| format!("Run {} is not waiting for an answer", run_id))?;
+    sender.send(value).map_err(|e| e.to_string())
+}
+
+// A command of a batch plan that must be confirmed before the plan runs
+#[derive(Serialize)]
+struct ImpactStepDto {
+    command: String,
+    impact: Impact,
+}
+
+// The commands of a batch plan whose impact asks for a confirmation, so they can all be
+// confirmed at once before it runs
+#[tauri::command]
+fn batch_impact(plan: String, state: State<AppState>) -> Result<Vec<ImpactStepDto>, String> {
+    let plan = BatchPlan::from_yaml_str(&plan).map_err(|e| e.to_string())?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let mut steps = Vec::new();
+    for name in plan.command_names() {
+        let command = storage.read_by_name(name).map_err(|e| e.to_string())?;
+        if command.impact != Impact::Safe {
+            steps.push(ImpactStepDto { command: command.name, impact: command.impact });
+        }
+    }
+    Ok(steps)
+}
+
+// Run a batch plan given as YAML. Every command of the plan is checked like a single run
+// before the plan starts, with `confirmations` holding the confirmation given for each
+// command by name. Progress is reported with "batch-progress" events so the frontend can
+// draw a timeline while the plan runs; they go to `window` only when a window label is
+// given.
+#[tauri::command]
+async fn run_batch(
+    plan: String,
+    confirmations: Option<HashMap<String, String>>,
+    profile: Option<String>,
+    window: Option<String>,
+    app: AppHandle,
+) -> Result<BatchReportDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let plan = BatchPlan::from_yaml_str(&plan).map_err(|e| e.to_string())?;
+        let confirmations = confirmations.unwrap_or_default();
+        {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            // Every run the plan may make counts against the command's rate limit
+            for (name, runs) in plan.planned_runs() {
+                let command = storage.read_by_name(name).map_err(|e| e.to_string())?;
+                ensure_cleared(&command, &Clearance::from(confirmations.get(name).cloned()), &state)?;
+                let history = state.history.lock().map_err(|e| e.to_string())?;
+                history.check_rate_limit_for(&command, runs).map_err(|e| e.to_string())?;
+            }
+        }
+        
+        // A storage of its own, so the library stays usable while the plan runs
+        let storage = CommandStorage::new().map_err(|e| e.to_string())?;
+        let variables = VariableStorage::new().map_err(|e| e.to_string())?;
+        let workspace = current_workspace(&state)?;
+        let executor = match load_profile(profile.as_deref(), &state)? {
+            Some(profile) => configured_executor(&state)?.with_profile(profile),
+            None => configured_executor(&state)?,
+        };
+        let handle = app.clone();
+        let prompter = AppPrompter { app: app.clone(), window: window.clone() };
+        let report = BatchRunner::new(&storage, &executor)
+            .with_progress(move |event| {
+                let _ = emit_to_window(&handle, window.as_deref(), "batch-progress", event);
+            })
+            .with_prompter(&prompter)
+            .with_variables(&variables, workspace.as_deref())
+            .run(&plan)
+            .map_err(|e| e.to_string())?;
+        Ok(batch_report_to_dto(report))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize, Deserialize)]
+struct ForEachRequest {
+    id: String,
+    // Glob of the directories, or of files whose directories are meant
+    pattern: String,
+    // Searched for matches; the opened project when missing
+    root: Option<String>,
+    parallelism: usize,
+    use_shell: bool,
+    parameters: HashMap<String, String>,
+}
+
+#[derive(Clone, Serialize)]
+struct DirectoryOutcomeDto {
+    directory: String,
+    success: bool,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    stdout: String,
+    stderr: String,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ForEachReportDto {
+    root: String,
+    outcomes: Vec<DirectoryOutcomeDto>,
+    duration_ms: u64,
+    summary: String,
+}
+
+fn directory_outcome_to_dto(root: &Path, outcome: &DirectoryOutcome) -> DirectoryOutcomeDto {
+    DirectoryOutcomeDto {
+        directory: outcome.relative_to(root),
+        success: outcome.success,
+        exit_code: outcome.exit_code,
+        duration_ms: outcome.duration.as_millis() as u64,
+        stdout: output_preview(&outcome.stdout),
+        stderr: output_preview(&outcome.stderr),
+        error: outcome.error.clone(),
+    }
+}
+
+// Runs one directory of a for-each run the way a single run goes: in its turn in the
+// queue, through GuardedRun, scrubbed and recorded in history
+fn run_directory(
+    command: &Command,
+    mode: ExecutionMode,
+    parameters: &HashMap<String, String>,
+    clearance: &Clearance,
+    executor: &CommandExecutor,
+    app: &AppHandle,
+) -> Result<ExecutionResult, CommandArgusError> {
+    let state = app.state::<AppState>();
+    let poisoned = |e: String| CommandArgusError::Storage(e);
+    let _slot = state.queue.acquire(command)?;
+    let settings = state.settings.lock().map_err(|e| poisoned(e.to_string()))?.load()?;
+    let run_id = Uuid::new_v4();
+    let run = {
+        let history = state.history.lock().map_err(|e| poisoned(e.to_string()))?;
+        GuardedRun::begin(run_id, command, parameters, clearance, &settings, &history)?
+    };
+    let mut result = executor
+        .clone()
+        .with_scrubber(run.scrubber().clone())
+        .with_run_id(run_id)
+        .execute_in_mode(command, mode);
+    let record = run.finish(&mut result);
+    let record = state.history.lock().map_err(|e| poisoned(e.to_string()))?.record(record)?;
+    broadcast(app, StoreDelta::Execution { summary: record.summary() });
+    notify_in_background(app, run.command(), &record, settings.notifications);
+    result
+}
+
+// Run a command in every directory matching a glob. Each directory runs like a single
+// run (see run_directory), and the rate limit has to allow all of them before the first
+// starts. Each finished directory is reported with a "foreach-progress" event, to
+// `window` only when a window label is given.
+#[tauri::command]
+async fn run_in_directories(
+    request: ForEachRequest,
+    confirmation: Option<String>,
+    profile: Option<String>,
+    window: Option<String>,
+    app: AppHandle,
+) -> Result<ForEachReportDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let uuid = Uuid::parse_str(&request.id).map_err(|e| e.to_string())?;
+        let clearance = Clearance::from(confirmation);
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let (command, values) = {
+            let storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let (command, _) = find_command(uuid, &storage, &state)?;
+            ensure_cleared(&command, &clearance, &state)?;
+            
+            // Parameters left out take their default values
+            let values = resolve_parameters(&command, &request.parameters).map_err(|e| e.to_string())?;
+            let mut command = command.for_platform(Platform::current()).map_err(|e| e.to_string())?;
+            let (new_command, new_args) = command.replace_placeholders(&values);
+            command.command = new_command;
+            command.args = new_args;
+            command.stdin_template = command.render_stdin(&values);
+            (command, values)
+        };
+        let command = resolve_variables(&command, profile.as_ref(), &state)?;
+        let root = match request.root.filter(|root| !root.trim().is_empty()) {
+            Some(root) => PathBuf::from(expand_env(&root)),
+            None => current_workspace(&state)?.ok_or("Open a project or choose the folder to search")?,
+        };
+        let directories = matching_directories(&root, &request.pattern).map_err(|e| e.to_string())?;
+        if directories.is_empty() {
+            return Err(format!("No directory under {} matches '{}'", root.display(), request.pattern));
+        }
+        {
+            let history = state.history.lock().map_err(|e| e.to_string())?;
+            history.check_rate_limit_for(&command, directories.len()).map_err(|e| e.to_string())?;
+        }
+        
+        // The guarded command gets the profile's environment variables too, so secret
+        // ones are scrubbed from the output
+        let (executor, command) = match &profile {
+            Some(profile) => (configured_executor(&state)?.with_profile(profile.clone()), profile.apply(&command)),
+            None => (configured_executor(&state)?, command),
+        };
+        let handle = app.clone();
+        let progress_root = root.clone();
+        let report = ForEachRunner::new(&executor)
+            .with_parallelism(request.parallelism)
+            .with_shell(request.use_shell)
+            .with_run(|command, mode| run_directory(command, mode, &values, &clearance, &executor, &app))
+            .with_progress(move |outcome| {
+                let _ = emit_to_window(&handle, window.as_deref(), "foreach-progress", directory_outcome_to_dto(&progress_root, outcome));
+            })
+            .run_in_directories(&command, &root, &directories)
+            .map_err(|e| e.to_string())?;
+        Ok(ForEachReportDto {
+            root: report.root.to_string_lossy().to_string(),
+            outcomes: report.outcomes.iter().map(|outcome| directory_outcome_to_dto(&report.root, outcome)).collect(),
+            duration_ms: report.duration.as_millis() as u64,
+            summary: report.summary(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Deserialize)]
+struct BenchmarkRequest {
+    id: String,
+    iterations: usize,
+    warmup: usize,
+    use_shell: bool,
+    parameters: HashMap<String, String>,
+}
+
+#[derive(Clone, Serialize)]
+struct BenchmarkRunDto {
+    iteration: usize,
+    exit_code: i32,
+    success: bool,
+    duration_ms: f64,
+}
+
+#[derive(Serialize)]
+struct BenchmarkStatsDto {
+    min_ms: f64,
+    max_ms: f64,
+    mean_ms: f64,
+    median_ms: f64,
+    p95_ms: f64,
+    stddev_ms: f64,
+}
+
+#[derive(Serialize)]
+struct BenchmarkReportDto {
+    command_name: String,
+    warmup: usize,
+    runs: Vec<BenchmarkRunDto>,
+    stats: BenchmarkStatsDto,
+    summary: String,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn benchmark_run_to_dto(run: &BenchmarkRun) -> BenchmarkRunDto {
+    BenchmarkRunDto {
+        iteration: run.iteration,
+        exit_code: run.exit_code,
+        success: run.success,
+        duration_ms: millis(run.duration),
+    }
+}
+
+fn benchmark_report_to_dto(report: &BenchmarkReport) -> BenchmarkReportDto {
+    let stats = &report.stats;
+    BenchmarkReportDto {
+        command_name: report.command_name.clone(),
+        warmup: report.warmup,
+        runs: report.runs.iter().map(benchmark_run_to_dto).collect(),
+        stats: BenchmarkStatsDto {
+            min_ms: millis(stats.min),
+            max_ms: millis(stats.max),
+            mean_ms: millis(stats.mean),
+            median_ms: millis(stats.median),
+            p95_ms: millis(stats.p95),
+            stddev_ms: millis(stats.stddev),
+        },
+        summary: report.summary(),
+    }
+}
+
+// The command of a benchmark, ready to run: checked, including that its rate limit
+// allows all `runs` of the benchmark, with its parameters and variables filled in
+fn benchmark_target(
+    id: &str,
+    parameters: &HashMap<String, String>,
+    confirmation: Option<&str>,
+    runs: usize,
+    profile: Option<&Profile>,
+    state: &AppState,
+) -> Result<Command, String> {
+    let uuid = Uuid::parse_str(id).map_err(|e| e.to_string())?;
+    let command = {
+        let storage = state.storage.lock().map_err(|e| e.to_string())?;
+        let (command, _) = find_command(uuid, &storage, state)?;
+        ensure_cleared(&command, &Clearance::from(confirmation.map(str::to_string)), state)?;
+        let history = state.history.lock().map_err(|e| e.to_string())?;
+        history.check_rate_limit_for(&command, runs).map_err(|e| e.to_string())?;
+
+        let values = resolve_parameters(&command, parameters).map_err(|e| e.to_string())?;
+        let mut command = command.for_platform(Platform::current()).map_err(|e| e.to_string())?;
+        let (new_command, new_args) = command.replace_placeholders(&values);
+        command.command = new_command;
+        command.args = new_args;
+        command.working_directory = command.replace_working_directory_placeholders(&values);
+        command.stdin_template = command.render_stdin(&values);
+        command
+    };
+    resolve_variables(&command, profile, state)
+}
+
+// Time a command over several runs after some unmeasured warmup runs. Every run waits
+// for its turn in the execution queue; the runs are not recorded in history. Each
+// measured run is reported with a "benchmark-progress" event, to `window` only when a
+// window label is given.
+#[tauri::command]
+async fn benchmark_command(
+    request: BenchmarkRequest,
+    confirmation: Option<String>,
+    profile: Option<String>,
+    window: Option<String>,
+    app: AppHandle,
+) -> Result<BenchmarkReportDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let runs = request.warmup + request.iterations;
+        let command = benchmark_target(&request.id, &request.parameters, confirmation.as_deref(), runs, profile.as_ref(), &state)?;
+        let executor = match profile {
+            Some(profile) => configured_executor(&state)?.with_profile(profile),
+            None => configured_executor(&state)?,
+        };
+        let handle = app.clone();
+        let report = BenchmarkRunner::new(&executor)
+            .with_shell(request.use_shell)
+            .with_queue(&state.queue)
+            .with_progress(move |run| {
+                let _ = emit_to_window(&handle, window.as_deref(), "benchmark-progress", benchmark_run_to_dto(run));
+            })
+            .run(&command, request.iterations, request.warmup)
+            .map_err(|e| e.to_string())?;
+        Ok(benchmark_report_to_dto(&report))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Deserialize)]
+struct CompareBenchmarksRequest {
+    id_a: String,
+    id_b: String,
+    iterations: usize,
+    warmup: usize,
+    use_shell: bool,
+}
+
+#[derive(Serialize)]
+struct BenchmarkComparisonDto {
+    a: BenchmarkReportDto,
+    b: BenchmarkReportDto,
+    faster: BenchmarkSide,
+    // How many times longer the slower command takes on average
+    ratio: f64,
+    ratio_stddev: f64,
+    confidence: Confidence,
+    summary: String,
+}
+
+// Benchmark two commands the same way, A first, and compare them. Parameters take their
+// defaults. Both rate limits have to allow all runs, and every run waits for its turn
+// in the execution queue. Every measured run of both is reported with a "benchmark-progress" event.
+#[tauri::command]
+async fn compare_benchmarks(
+    request: CompareBenchmarksRequest,
+    confirmation_a: Option<String>,
+    confirmation_b: Option<String>,
+    profile: Option<String>,
+    window: Option<String>,
+    app: AppHandle,
+) -> Result<BenchmarkComparisonDto, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let profile = load_profile(profile.as_deref(), &state)?;
+        let no_parameters = HashMap::new();
+        // Comparing a command with itself runs it twice as often
+        let runs = request.warmup + request.iterations;
+        let runs_a = if request.id_a == request.id_b { runs * 2 } else { runs };
+        let a = benchmark_target(&request.id_a, &no_parameters, confirmation_a.as_deref(), runs_a, profile.as_ref(), &state)?;
+        let b = benchmark_target(&request.id_b, &no_parameters, confirmation_b.as_deref(), runs, profile.as_ref(), &state)?;
+        let executor = match profile {
+            Some(profile) => configured_executor(&state)?.with_profile(profile),
+            None => configured_executor(&state)?,
+        };
+        let handle = app.clone();
+        let comparison = BenchmarkRunner::new(&executor)
+            .with_shell(request.use_shell)
+            .with_queue(&state.queue)
+            .with_progress(move |run| {
+                let _ = emit_to_window(&handle, window.as_deref(), "benchmark-progress", benchmark_run_to_dto(run));
+            })
+            .compare(&a, &b, request.iterations, request.warmup)
+            .map_err(|e| e.to_string())?;
+        Ok(BenchmarkComparisonDto {
+            a: benchmark_report_to_dto(&comparison.a),
+            b: benchmark_report_to_dto(&comparison.b),
+            faster: comparison.faster,
+            ratio: comparison.ratio,
+            ratio_stddev: comparison.ratio_stddev,
+            confidence: comparison.confidence,
+            summary: comparison.summary(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize)]
+struct VariablesDto {
+    // Root of the opened project, if any
+    workspace: Option<String>,
+    variables: Vec<Variable>,
+}
+
+#[tauri::command]
+fn list_variables(state: State<AppState>) -> Result<VariablesDto, String> {
+    let workspace = current_workspace(&state)?.map(|root| root.to_string_lossy().to_string());
+    let variables = state.variables.lock().map_err(|e| e.to_string())?;
+    Ok(VariablesDto {
+        workspace,
+        variables: variables.list().map_err(|e| e.to_string())?,
+    })
+}
+
+// Sets a global variable, or one of the opened project when `in_workspace` is set
+#[tauri::command]
+fn set_variable(name: String, value: String, in_workspace: bool, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<Variable, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let scope = if in_workspace {
+        let root = current_workspace(&state)?.ok_or("Open a project to set workspace variables")?;
+        VariableScope::Workspace { root: root.to_string_lossy().to_string() }
+    } else {
+        VariableScope::Global
+    };
+    let variables = state.variables.lock().map_err(|e| e.to_string())?;
+    let variable = variables.set(access, &name, &value, scope).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Variable {
+        name: variable.name.clone(),
+        scope: variable.scope.clone(),
+        variable: Some(variable.clone()),
+    });
+    Ok(variable)
+}
+
+#[tauri::command]
+fn delete_variable(name: String, scope: VariableScope, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<bool, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let variables = state.variables.lock().map_err(|e| e.to_string())?;
+    let removed = variables.remove(access, &name, &scope).map_err(|e| e.to_string())?;
+    if removed {
+        broadcast(&app, StoreDelta::Variable { name, scope, variable: None });
+    }
+    Ok(removed)
+}
+
+// Where each {{var:NAME}} of a command gets its value from in the current workspace,
+// when run with `profile`
+#[tauri::command]
+fn trace_variables(id: String, profile: Option<String>, state: State<AppState>) -> Result<Vec<VariableTrace>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let profile = load_profile(profile.as_deref(), &state)?;
+    let command = {
+        let storage = state.storage.lock().map_err(|e| e.to_string())?;
+        find_command(uuid, &storage, &state)?.0
+    };
+    let workspace = current_workspace(&state)?;
+    let variables = state.variables.lock().map_err(|e| e.to_string())?;
+    variables.trace_command(&command, workspace.as_deref(), profile.as_ref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_profiles(state: State<AppState>) -> Result<Vec<Profile>, String> {
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    profiles.list().map_err(|e| e.to_string())
+}
+
+// Creates the profile or replaces the one with the same name
+#[tauri::command]
+fn save_profile(profile: Profile, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<Profile, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    let profile = profiles.save(access, profile).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Profile { name: profile.name.clone(), profile: Some(profile.clone()) });
+    Ok(profile)
+}
+
+#[tauri::command]
+fn delete_profile(name: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<bool, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    let removed = profiles.delete(access, &name).map_err(|e| e.to_string())?;
+    if removed {
+        broadcast(&app, StoreDelta::Profile { name, profile: None });
+    }
+    Ok(removed)
+}
+
+// Render a batch plan given as YAML as a Markdown runbook
+#[tauri::command]
+fn export_runbook(plan: String, title: String, state: State<AppState>) -> Result<String, String> {
+    let plan = BatchPlan::from_yaml_str(&plan).map_err(|e| e.to_string())?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    command_argus_logic::runbook::export_runbook(&plan, &storage, &title).map_err(|e| e.to_string())
+}
+
+// Outputs are left out; they are fetched in ranges with get_execution_output
+#[tauri::command]
+fn get_execution(id: String, state: State<AppState>) -> Result<ExecutionSummary, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    history.get(uuid).map(|record| record.summary()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_execution_output(id: String, stream: OutputStream, offset: usize, limit: usize, state: State<AppState>) -> Result<OutputChunk, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    history.get_output(uuid, stream, offset, limit).map_err(|e| e.to_string())
+}
+
+// The last lines of a run's output: while it runs from what it wrote so far, even when
+// that was spilled to disk for its size, and from the history once it finished
+#[tauri::command]
+fn tail_execution_output(execution_id: String, lines: usize, stream: Option<OutputStream>, state: State<AppState>) -> Result<OutputChunk, String> {
+    let uuid = Uuid::parse_str(&execution_id).map_err(|e| e.to_string())?;
+    let stream = stream.unwrap_or(OutputStream::Stdout);
+    let spool = state.live_outputs.lock().map_err(|e| e.to_string())?.get(&uuid).cloned();
+    match spool {
+        Some(spool) => spool.tail(stream, lines).map_err(|e| e.to_string()),
+        None => {
+            let history = state.history.lock().map_err(|e| e.to_string())?;
+            history.tail_output(uuid, stream, lines).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct OutputTailEvent {
+    execution_id: Uuid,
+    stream: OutputStream,
+    text: String,
+    // The run ended or the output is no longer followed; no more events follow
+    finished: bool,
+}
+
+// Sends the output a running command writes as "execution-output-tail" events, starting
+// with the end of what it wrote so far, until it finishes or unfollow_execution_output
+#[tauri::command]
+fn follow_execution_output(execution_id: String, stream: Option<OutputStream>, app: AppHandle) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&execution_id).map_err(|e| e.to_string())?;
+    let stream = stream.unwrap_or(OutputStream::Stdout);
+    let state = app.state::<AppState>();
+    if !state.followed_outputs.lock().map_err(|e| e.to_string())?.insert(uuid) {
+        return Ok(());
+    }
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let mut offset = 0;
+        loop {
+            let followed = state.followed_outputs.lock().map(|f| f.contains(&uuid)).unwrap_or(false);
+            let spool = state.live_outputs.lock().ok().and_then(|live| live.get(&uuid).cloned());
+            let Some(spool) = spool.filter(|_| followed) else {
+                break;
+            };
+            if let Ok((text, next)) = spool.read_from(stream, offset) {
+                offset = next;
+                if !text.is_empty() {
+                    let _ = app.emit("execution-output-tail", OutputTailEvent { execution_id: uuid, stream, text, finished: false });
+                }
+            }
+            drop(spool);
+            std::thread::sleep(Duration::from_millis(300));
+        }
+        if let Ok(mut followed) = state.followed_outputs.lock() {
+            followed.remove(&uuid);
+        }
+        let _ = app.emit("execution-output-tail", OutputTailEvent { execution_id: uuid, stream, text: String::new(), finished: true });
+    });
+    Ok(())
+}
+
+#[tauri::command]
+fn unfollow_execution_output(execution_id: String, state: State<AppState>) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&execution_id).map_err(|e| e.to_string())?;
+    state.followed_outputs.lock().map_err(|e| e.to_string())?.remove(&uuid);
+    Ok(())
+}
+
+// Writes the whole output of a past run to a file the user chose, straight from the
+// history, so large outputs don't go through the frontend; returns the bytes written
+#[tauri::command]
+fn save_execution_output(history_id: String, path: String, stream: OutputStream, state: State<AppState>) -> Result<u64, String> {
+    let uuid = Uuid::parse_str(&history_id).map_err(|e| e.to_string())?;
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    history.save_output(uuid, stream, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+// Open a link found in a command's output: web pages in the browser, files with their
+// default application
+#[tauri::command]
+fn open_link(kind: LinkKind, target: String, app: AppHandle) -> Result<(), String> {
+    match kind {
+        LinkKind::Url if target.starts_with("https://") || target.starts_with("http://") => {
+            app.opener().open_url(target, None::<&str>).map_err(|e| e.to_string())
+        }
+        LinkKind::Url => Err(format!("Not a web address: {}", target)),
+        LinkKind::Path if Path::new(&target).exists() => {
+            app.opener().open_path(target, None::<&str>).map_err(|e| e.to_string())
+        }
+        LinkKind::Path => Err(format!("{} no longer exists", target)),
+    }
+}
+
+#[tauri::command]
+fn get_storage_stats(state: State<AppState>) -> Result<StorageStats, String> {
+    let dir = data_dir().map_err(|e| e.to_string())?;
+    // Keep writers out while the files are measured
+    let _history = state.history.lock().map_err(|e| e.to_string())?;
+    storage_stats(&dir).map_err(|e| e.to_string())
+}
+
+// Keeps the newest `keep` executions (and their stored outputs); returns how many were removed
+#[tauri::command]
+fn prune_history(keep: usize, state: State<AppState>, app: AppHandle) -> Result<usize, String> {
+    let history = state.history.lock().map_err(|e| e.to_string())?;
+    let removed = history.prune(keep).map_err(|e| e.to_string())?;
+    if removed > 0 {
+        broadcast(&app, StoreDelta::History);
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+fn list_notification_rules(state: State<AppState>) -> Result<Vec<NotificationRule>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.load().map_err(|e| e.to_string())?.notifications.rules)
+}
+
+// Message template and output link used for Slack and Discord
+#[derive(Serialize)]
+struct NotificationFormatDto {
+    message_template: String,
+    output_link: Option<String>,
+}
+
+#[tauri::command]
+fn get_notification_format(state: State<AppState>) -> Result<NotificationFormatDto, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let notifications = settings.load().map_err(|e| e.to_string())?.notifications;
+    Ok(NotificationFormatDto {
+        message_template: notifications.message_template().to_string(),
+        output_link: notifications.output_link,
+    })
+}
+
+// An empty template or link resets it
+#[tauri::command]
+fn set_notification_format(message_template: String, output_link: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.notifications.message_template = Some(message_template).filter(|t| !t.trim().is_empty());
+        s.notifications.output_link = Some(output_link).filter(|l| !l.trim().is_empty());
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    let notifications = settings.load().map_err(|e| e.to_string())?.notifications;
+    broadcast_setting(&app, "notification_format", NotificationFormatDto {
+        message_template: notifications.message_template().to_string(),
+        output_link: notifications.output_link,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+fn create_notification_rule(
+    name: String,
+    trigger: NotificationTrigger,
+    targets: Vec<NotificationTarget>,
+    admin_passphrase: Option<String>,
+    state: State<AppState>,
+    app: AppHandle
+) -> Result<NotificationRule, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let rule = settings.update(access, |s| s.notifications.add_rule(NotificationRule::new(name, trigger, targets)))
+        .map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::NotificationRule { id: rule.id.to_string(), rule: Some(rule.clone()) });
+    Ok(rule)
+}
+
+#[tauri::command]
+fn update_notification_rule(rule: NotificationRule, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<NotificationRule, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let rule = settings.update(access, |s| s.notifications.update_rule(rule)).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::NotificationRule { id: rule.id.to_string(), rule: Some(rule.clone()) });
+    Ok(rule)
+}
+
+#[tauri::command]
+fn delete_notification_rule(id: String, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| s.notifications.remove_rule(uuid)).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::NotificationRule { id, rule: None });
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct OnboardingDto {
+    needed: bool,
+    // Only the starters whose tools are installed
+    starters: Vec<StarterCommand>,
+}
+
+#[tauri::command]
+fn get_onboarding(state: State<AppState>) -> Result<OnboardingDto, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let settings = settings.load().map_err(|e| e.to_string())?;
+    let needed = needs_onboarding(&storage, &settings).map_err(|e| e.to_string())?;
+    let starters = if needed { available_starters() } else { Vec::new() };
+    Ok(OnboardingDto { needed, starters })
+}
+
+#[tauri::command]
+fn seed_starter_commands(selection: Vec<String>, admin_passphrase: Option<String>, state: State<AppState>, app: AppHandle) -> Result<Vec<CommandDto>, String> {
+    let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let created = command_argus_logic::onboarding::seed_starter_commands(&storage, access, &selection).map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.update(access, |s| {
+        s.onboarding_completed = true;
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    broadcast(&app, StoreDelta::Commands);
+    broadcast_setting(&app, "onboarding_completed", true);
+    Ok(created.iter().map(command_to_dto).collect())
+}
+
+// Installed tools, with the suggestions already in the library left out
+#[tauri::command]
+fn detect_environment(state: State<AppState>) -> Result<EnvironmentReport, String> {
+    let mut report = command_argus_logic::onboarding::detect_environment();
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    let commands = storage.list().map_err(|e| e.to_string())?;
+    report.suggestions.retain(|s| !commands.iter().any(|c| c.name == s.name));
+    Ok(report)
+}
+
+// What an exported archive or an old data directory at `path` holds, for the first-launch
+// migration
+#[tauri::command]
+async fn preview_migration(path: String) -> Result<MigrationPreview, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = MigrationSource::detect(Path::new(&path)).map_err(|e| e.to_string())?;
+        command_argus_logic::migrate::preview_migration(&source).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Imports the chosen parts of an old installation and ends onboarding
+#[tauri::command]
+async fn migrate_from(
+    path: String,
+    options: MigrationOptions,
+    admin_passphrase: Option<String>,
+    app: AppHandle,
+) -> Result<Vec<StateCategory>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let access = check_edit_allowed(admin_passphrase.as_deref(), &state)?;
+        let source = MigrationSource::detect(Path::new(&path)).map_err(|e| e.to_string())?;
+        let imported = {
+            let _storage = state.storage.lock().map_err(|e| e.to_string())?;
+            let _history = state.history.lock().map_err(|e| e.to_string())?;
+            let archive = StateArchive::new().map_err(|e| e.to_string())?;
+            archive.migrate(access, &source, &options).map_err(|e| e.to_string())?
+        };
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.update(access, |s| {
+            s.onboarding_completed = true;
+            Ok(())
+        }).map_err(|e| e.to_string())?;
+        broadcast(&app, StoreDelta::Commands);
+        broadcast(&app, StoreDelta::History);
+        broadcast_setting(&app, "onboarding_completed", true);
+        Ok(imported)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn dismiss_onboarding(state: State<AppState>, app: AppHandle) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.complete_onboarding().map_err(|e| e.to_string())?;
+    broadcast_setting(&app, "onboarding_completed", true);
+    Ok(())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    if std::env::args().any(|arg| arg == AGENT_ARG) {
+        return run_agent();
+    }
+    let settings = SettingsStorage::new().expect("Failed to initialize settings");
+    let max_concurrent = settings.load().map(|s| s.max_concurrent_executions).unwrap_or(1);
+    let queue = Arc::new(ExecutionQueue::new(max_concurrent));
+    let app_state = AppState {
+        storage: Mutex::new(CommandStorage::new().expect("Failed to initialize storage")),
+        project: Mutex::new(None),
+        settings: Mutex::new(settings),
+        approvals: Mutex::new(ApprovalStore::new().expect("Failed to initialize approvals")),
+        history: Mutex::new(HistoryStorage::new().expect("Failed to initialize history")),
+        saved_searches: Mutex::new(SavedSearchStorage::new().expect("Failed to initialize saved searches")),
+        variables: Mutex::new(VariableStorage::new().expect("Failed to initialize variables")),
+        profiles: Mutex::new(ProfileStorage::new().expect("Failed to initialize profiles")),
+        queue: queue.clone(),
+        prompts: Mutex::new(HashMap::new()),
+        launch_request: Mutex::new(parse_launch_args(&std::env::args().collect::<Vec<_>>())),
+        jobs: Arc::new(RunningJobs::new().expect("Failed to initialize running jobs")),
+        shutting_down: AtomicBool::new(false),
+        recovery: Mutex::new(None),
+        live_outputs: Mutex::new(HashMap::new()),
+        followed_outputs: Mutex::new(HashSet::new()),
+    };
+    
+    tauri::Builder::default()
+        // Registered first, so a second launch exits before it touches anything else
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| forward_launch(app, args)))
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(app_state)
+        .setup(move |app| {
+            // Runs the last session left behind go to history. Done here rather than
+            // above, as a second instance has exited by now and the jobs are ours.
+            let state = app.state::<AppState>();
+            if let Ok(history) = state.history.lock() {
+                match reconcile_interrupted(&state.jobs, &history) {
+                    Ok(records) => *state.recovery.lock().unwrap() = RecoveryNotice::from_records(&records),
+                    Err(error) => eprintln!("Failed to record interrupted runs: {}", error),
+                }
+            }
+            
+            // Let the frontend show queue positions as they change
+            let handle = app.handle().clone();
+            queue.set_listener(move |waiting| {
+                let _ = handle.emit("execution-queue", waiting);
+            });
+            Ok(())
+        })
+        // Closing the last window quits, which is held back while commands run
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let app = window.app_handle();
+                if app.webview_windows().len() == 1 && hold_quit(app, Some(window.label())) {
+                    api.prevent_close();
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            list_commands,
+            get_command,
+            create_command,
+            update_command,
+            lint_command,
+            apply_lint_fix,
+            explain_command,
+            fetch_help,
+            detect_alias,
+            validate_command_request,
+            split_args,
+            join_args,
+            preview_builder,
+            bulk_replace,
+            create_from_builder,
+            suggest_parameters,
+            parameterize_command,
+            rename_parameter,
+            delete_command,
+            search_commands_by_name,
             search_commands_by_tags,
+            search_commands_by_query,
+            list_saved_searches,
+            create_saved_search,
+            update_saved_search,
+            delete_saved_search,
+            run_saved_search,
             execute_command,
-            execute_command_with_parameters
+            execute_command_with_parameters,
+            replay_with_changes,
+            export_ci,
+            export_bundle,
+            export_stats_csv,
+            export_everything,
+            import_everything,
+            preview_migration,
+            migrate_from,
+            read_bundle_changelog,
+            diff_bundle_files,
+            import_project,
+            import_bundle,
+            get_path_remap_rules,
+            set_path_remap_rules,
+            open_project,
+            suggest_project_root,
+            close_project,
+            get_sync_config,
+            save_sync_config,
+            sync_commands,
+            is_restricted_mode,
+            enable_restricted_mode,
+            disable_restricted_mode,
+            request_execution,
+            list_approval_requests,
+            approve_execution,
+            reject_execution,
+            run_approved_execution,
+            cancel_queued,
+            list_queued,
+            get_max_concurrent_executions,
+            set_max_concurrent_executions,
+            get_context_env_prefix,
+            set_context_env_prefix,
+            get_highlight_rules,
+            set_highlight_rules,
+            get_builtin_problem_matcher,
+            get_share_targets,
+            set_share_targets,
+            share_execution,
+            open_window,
+            take_launch_request,
+            get_shutdown_settings,
+            set_shutdown_settings,
+            list_running_jobs,
+            shut_down,
+            take_recovery_notice,
+            get_agent_status,
+            list_agent_jobs,
+            run_in_agent,
+            set_agent_autostart,
+            start_agent,
+            list_history,
+            search_history,
+            annotate_execution,
+            get_execution_output,
+            save_execution_output,
+            tail_execution_output,
+            follow_execution_output,
+            unfollow_execution_output,
+            open_link,
+            list_recent_failures,
+            batch_impact,
+            run_batch,
+            run_in_directories,
+            benchmark_command,
+            compare_benchmarks,
+            export_runbook,
+            answer_prompt,
+            list_variables,
+            set_variable,
+            delete_variable,
+            trace_variables,
+            list_profiles,
+            save_profile,
+            delete_profile,
+            get_storage_stats,
+            prune_history,
+            list_notification_rules,
+            create_notification_rule,
+            get_notification_format,
+            set_notification_format,
+            update_notification_rule,
+            delete_notification_rule,
+            get_onboarding,
+            seed_starter_commands,
+            dismiss_onboarding,
+            detect_environment,
+            get_execution,
+            get_working_directory_base,
+            set_working_directory_base,
+            repin_executable,
+            scan_dead_paths,
+            preview_environment,
+            preflight_command
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::ExitRequested { api, .. } = event {
+                let windows = app.webview_windows();
+                let window = windows.get(MAIN_WINDOW).or_else(|| windows.values().next());
+                if hold_quit(app, window.map(|window| window.label())) {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
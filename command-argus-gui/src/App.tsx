@@ -1,13 +1,108 @@
-import { useState } from "react";
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { CommandList } from "./components/CommandList";
 import { CommandForm } from "./components/CommandForm";
-import { Command } from "./types";
+import { SavedSearchSidebar } from "./components/SavedSearchSidebar";
+import { StorageUsagePanel } from "./components/StorageUsagePanel";
+import { AgentPanel } from "./components/AgentPanel";
+import { OnboardingModal } from "./components/OnboardingModal";
+import { ShutdownModal } from "./components/ShutdownModal";
+import { RecoveryBanner } from "./components/RecoveryBanner";
+import { SuggestedCommands } from "./components/SuggestedCommands";
+import { RecentFailuresPanel } from "./components/RecentFailuresPanel";
+import { NotificationRulesPanel } from "./components/NotificationRulesPanel";
+import { BatchRunPanel } from "./components/BatchRunPanel";
+import { VariablesPanel } from "./components/VariablesPanel";
+import { ForEachPanel } from "./components/ForEachPanel";
+import { BenchmarkPanel } from "./components/BenchmarkPanel";
+import { BuilderPanel } from "./components/BuilderPanel";
+import { ReplacePanel } from "./components/ReplacePanel";
+import { ProfilesPanel } from "./components/ProfilesPanel";
+import { ImportPanel } from "./components/ImportPanel";
+import { DeadPathsPanel } from "./components/DeadPathsPanel";
+import { HistoryPanel } from "./components/HistoryPanel";
+import { AppNotification, Command, LaunchRequest, Profile, SavedSearch } from "./types";
+import { useStoreUpdates } from "./storeUpdates";
 import "./App.css";
 
 function App() {
   const [showForm, setShowForm] = useState(false);
   const [editingCommand, setEditingCommand] = useState<Command | null>(null);
   const [refreshTrigger, setRefreshTrigger] = useState(0);
+  const [savedSearch, setSavedSearch] = useState<SavedSearch | null>(null);
+  const [showUsage, setShowUsage] = useState(false);
+  const [showAgent, setShowAgent] = useState(false);
+  const [showFailures, setShowFailures] = useState(false);
+  const [showNotifications, setShowNotifications] = useState(false);
+  const [showBatch, setShowBatch] = useState(false);
+  const [showVariables, setShowVariables] = useState(false);
+  const [showForEach, setShowForEach] = useState(false);
+  const [showBenchmark, setShowBenchmark] = useState(false);
+  const [showBuilder, setShowBuilder] = useState(false);
+  const [showReplace, setShowReplace] = useState(false);
+  const [showProfiles, setShowProfiles] = useState(false);
+  const [showImport, setShowImport] = useState(false);
+  const [showDeadPaths, setShowDeadPaths] = useState(false);
+  const [showHistory, setShowHistory] = useState(false);
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  // Profile commands and plans are run with; empty for none
+  const [activeProfile, setActiveProfile] = useState("");
+  // Command a launch asked to run, numbered so the same command can be asked for twice
+  const [runRequest, setRunRequest] = useState<{ command: string; seq: number } | null>(null);
+
+  const loadProfiles = async () => {
+    const loaded = await invoke<Profile[]>("list_profiles");
+    setProfiles(loaded);
+    if (!loaded.some(p => p.name === activeProfile)) {
+      setActiveProfile("");
+    }
+  };
+
+  useEffect(() => {
+    loadProfiles();
+  }, []);
+
+  useStoreUpdates(["profile"], () => loadProfiles());
+
+  const handleNewWindow = async () => {
+    try {
+      await invoke("open_window");
+    } catch (err) {
+      alert(`Failed to open a window: ${err}`);
+    }
+  };
+
+  // The request this instance was started with, then those of later launches, which
+  // the backend forwards here instead of starting a second instance
+  useEffect(() => {
+    const handleLaunch = (request: LaunchRequest) => {
+      setShowForm(false);
+      setRunRequest(prev => ({ command: request.command, seq: (prev?.seq ?? 0) + 1 }));
+    };
+    invoke<LaunchRequest | null>("take_launch_request").then(request => {
+      if (request) handleLaunch(request);
+    });
+    const unlisten = listen<LaunchRequest>("launch-request", event => handleLaunch(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Notification rules with a desktop target are delivered through this event
+  useEffect(() => {
+    const unlisten = listen<AppNotification>("desktop-notification", async event => {
+      if (Notification.permission === "default") {
+        await Notification.requestPermission();
+      }
+      if (Notification.permission === "granted") {
+        new Notification(event.payload.title, { body: event.payload.body });
+      }
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
 
   const handleNewCommand = () => {
     setEditingCommand(null);
@@ -32,20 +127,213 @@ function App() {
 
   return (
     <div className="min-h-screen bg-gray-50">
+      <OnboardingModal onDone={() => setRefreshTrigger(prev => prev + 1)} />
+      <ShutdownModal />
       <div className="max-w-6xl mx-auto p-6">
+        <RecoveryBanner />
         <div className="bg-white rounded-lg shadow-md p-6">
           <div className="flex justify-between items-center mb-6">
             <h1 className="text-3xl font-bold text-gray-800">Command Argus</h1>
             {!showForm && (
-              <button
-                onClick={handleNewCommand}
-                className="px-4 py-2 bg-green-500 text-white rounded-md hover:bg-green-600 focus:outline-none focus:ring-2 focus:ring-green-500"
-              >
-                New Command
-              </button>
+              <div className="flex gap-2">
+                <select
+                  value={activeProfile}
+                  onChange={e => setActiveProfile(e.target.value)}
+                  title="Profile commands are run with"
+                  className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  <option value="">No profile</option>
+                  {profiles.map(profile => (
+                    <option key={profile.name} value={profile.name}>
+                      {profile.name}
+                    </option>
+                  ))}
+                </select>
+                <button
+                  onClick={() => setShowProfiles(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Profiles
+                </button>
+                <button
+                  onClick={() => setShowVariables(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Variables
+                </button>
+                <button
+                  onClick={() => setShowBatch(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Run Plan
+                </button>
+                <button
+                  onClick={() => setShowForEach(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  For Each Directory
+                </button>
+                <button
+                  onClick={() => setShowBenchmark(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Benchmark
+                </button>
+                <button
+                  onClick={() => setShowNotifications(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Notifications
+                </button>
+                <button
+                  onClick={() => setShowFailures(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Recent Failures
+                </button>
+                <button
+                  onClick={() => setShowUsage(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Disk Usage
+                </button>
+                <button
+                  onClick={() => setShowDeadPaths(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Dead Paths
+                </button>
+                <button
+                  onClick={() => setShowHistory(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  History
+                </button>
+                <button
+                  onClick={() => setShowAgent(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Agent
+                </button>
+                <button
+                  onClick={handleNewWindow}
+                  title="Another window on the same library; changes show up in both"
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  New Window
+                </button>
+                <button
+                  onClick={() => setShowReplace(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Find &amp; Replace
+                </button>
+                <button
+                  onClick={() => setShowBuilder(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Builder
+                </button>
+                <button
+                  onClick={() => setShowImport(prev => !prev)}
+                  className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 focus:outline-none focus:ring-2 focus:ring-gray-400"
+                >
+                  Import
+                </button>
+                <button
+                  onClick={handleNewCommand}
+                  className="px-4 py-2 bg-green-500 text-white rounded-md hover:bg-green-600 focus:outline-none focus:ring-2 focus:ring-green-500"
+                >
+                  New Command
+                </button>
+              </div>
             )}
           </div>
 
+          {showUsage && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <StorageUsagePanel />
+            </div>
+          )}
+
+          {showDeadPaths && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <DeadPathsPanel />
+            </div>
+          )}
+
+          {showHistory && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <HistoryPanel />
+            </div>
+          )}
+
+          {showAgent && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <AgentPanel />
+            </div>
+          )}
+
+          {showVariables && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <VariablesPanel profile={activeProfile || null} />
+            </div>
+          )}
+
+          {showProfiles && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <ProfilesPanel onChange={loadProfiles} />
+            </div>
+          )}
+
+          {showBatch && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <BatchRunPanel profile={activeProfile || null} />
+            </div>
+          )}
+
+          {showForEach && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <ForEachPanel profile={activeProfile || null} />
+            </div>
+          )}
+
+          {showImport && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <ImportPanel />
+            </div>
+          )}
+
+          {showReplace && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <ReplacePanel />
+            </div>
+          )}
+
+          {showBuilder && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <BuilderPanel />
+            </div>
+          )}
+
+          {showBenchmark && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <BenchmarkPanel profile={activeProfile || null} />
+            </div>
+          )}
+
+          {showNotifications && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <NotificationRulesPanel />
+            </div>
+          )}
+
+          {showFailures && !showForm && (
+            <div className="mb-6 p-4 border border-gray-200 rounded-lg">
+              <RecentFailuresPanel onEdit={handleEditCommand} />
+            </div>
+          )}
+
           {showForm ? (
             <CommandForm
               editingCommand={editingCommand}
@@ -53,10 +341,22 @@ function App() {
               onCancel={handleCancel}
             />
           ) : (
-            <CommandList
-              onEdit={handleEditCommand}
-              refreshTrigger={refreshTrigger}
-            />
+            <div className="flex gap-6">
+              <SavedSearchSidebar selected={savedSearch} onSelect={setSavedSearch} />
+              <div className="flex-1 min-w-0">
+                <SuggestedCommands
+                  refreshTrigger={refreshTrigger}
+                  onAdded={() => setRefreshTrigger(prev => prev + 1)}
+                />
+                <CommandList
+                  onEdit={handleEditCommand}
+                  refreshTrigger={refreshTrigger}
+                  savedSearch={savedSearch}
+                  profile={activeProfile || null}
+                  runRequest={runRequest}
+                />
+              </div>
+            </div>
           )}
         </div>
       </div>
@@ -0,0 +1,185 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { useStoreUpdates } from '../storeUpdates';
+import { AgentOutput, AgentStatus, Command, ExecutionSummary, RunningJob } from '../types';
+
+// The background agent, which runs without a window and can start at login
+export function AgentPanel() {
+  const [status, setStatus] = useState<AgentStatus | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+  const [jobs, setJobs] = useState<RunningJob[]>([]);
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [commandId, setCommandId] = useState('');
+  const [output, setOutput] = useState('');
+  const [running, setRunning] = useState(false);
+
+  const loadStatus = async () => {
+    try {
+      setStatus(await invoke<AgentStatus>('get_agent_status'));
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to read the agent status');
+    }
+  };
+
+  const loadJobs = async () => {
+    try {
+      setJobs(await invoke<RunningJob[]>('list_agent_jobs'));
+    } catch (err) {
+      setJobs([]);
+    }
+  };
+
+  useEffect(() => {
+    loadStatus();
+    invoke<Command[]>('list_commands', { source: null, health: null }).then(all => {
+      // The agent runs commands from the library, not a project's
+      setCommands(all.filter(c => !c.project_root));
+    });
+    const unlisten = listen<AgentOutput>('agent-output', ({ payload }) => {
+      setOutput(current => current + payload.text);
+    });
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (status?.running) loadJobs();
+  }, [status]);
+
+  useStoreUpdates(['setting'], delta => {
+    if (delta.entity === 'setting' && delta.name === 'agent_autostart') loadStatus();
+  });
+
+  const handleAutostart = async (enabled: boolean) => {
+    try {
+      await invoke('set_agent_autostart', { enabled });
+      await loadStatus();
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to change autostart');
+    }
+  };
+
+  const handleStart = async () => {
+    try {
+      await invoke('start_agent');
+      // Give it a moment to announce itself
+      setTimeout(loadStatus, 1000);
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to start the agent');
+    }
+  };
+
+  // The confirmation the command's impact level asks for, or undefined if the user backed out
+  const confirmImpact = async (command: Command): Promise<string | null | undefined> => {
+    switch (command.impact) {
+      case 'caution': {
+        const confirmed = await ask(`Run "${command.name}" in the agent?`, { title: 'Confirm', kind: 'warning' });
+        return confirmed ? 'confirmed' : undefined;
+      }
+      case 'destructive': {
+        const typed = window.prompt(`"${command.name}" is destructive. Type its name to run it in the agent:`);
+        return typed === null ? undefined : typed;
+      }
+      default:
+        return null;
+    }
+  };
+
+  const handleRun = async () => {
+    const command = commands.find(c => c.id === commandId);
+    if (!command) return;
+    const confirmation = await confirmImpact(command);
+    if (confirmation === undefined) return;
+
+    setOutput('');
+    setRunning(true);
+    setMessage(null);
+    try {
+      const summary = await invoke<ExecutionSummary>('run_in_agent', { id: commandId, parameters: {}, useShell: false, confirmation });
+      setMessage(summary.success ? 'Finished' : `Failed${summary.exit_code !== undefined ? ` with exit code ${summary.exit_code}` : ''}`);
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to run in the agent');
+    } finally {
+      setRunning(false);
+      loadJobs();
+    }
+  };
+
+  if (!status) {
+    return <div className="text-sm text-gray-500">{message ?? 'Loading...'}</div>;
+  }
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center gap-3 text-sm text-gray-600">
+        {status.running ? (
+          <span>
+            Running as process {status.running.pid} since {new Date(status.running.started_at).toLocaleString()}
+          </span>
+        ) : (
+          <>
+            <span>Not running</span>
+            <button onClick={handleStart} className="text-blue-600 hover:underline">
+              Start now
+            </button>
+          </>
+        )}
+        <button onClick={loadStatus} className="text-blue-600 hover:underline">
+          Refresh
+        </button>
+      </div>
+      <label className="flex items-center gap-2 text-sm cursor-pointer">
+        <input type="checkbox" checked={status.autostart} onChange={e => handleAutostart(e.target.checked)} />
+        Start the agent at login
+      </label>
+      {status.running && (
+        <>
+          <div className="text-sm">
+            <div className="font-medium text-gray-700">Running in the agent</div>
+            {jobs.length === 0 ? (
+              <div className="text-gray-500">Nothing</div>
+            ) : (
+              <ul className="text-gray-600">
+                {jobs.map(job => (
+                  <li key={job.run_id}>
+                    {job.command_name} (process {job.pid}) since {new Date(job.started_at).toLocaleTimeString()}
+                  </li>
+                ))}
+              </ul>
+            )}
+          </div>
+          <div className="flex items-center gap-2 text-sm">
+            <select
+              value={commandId}
+              onChange={e => setCommandId(e.target.value)}
+              className="px-2 py-1 border border-gray-300 rounded"
+            >
+              <option value="">Choose a command...</option>
+              {commands.map(command => (
+                <option key={command.id} value={command.id}>
+                  {command.name}
+                </option>
+              ))}
+            </select>
+            <button
+              onClick={handleRun}
+              disabled={!commandId || running}
+              className="px-3 py-1 bg-blue-600 text-white rounded disabled:opacity-50"
+            >
+              {running ? 'Running...' : 'Run in Agent'}
+            </button>
+          </div>
+          {output && (
+            <pre className="text-xs bg-gray-900 text-gray-100 p-2 rounded max-h-64 overflow-auto whitespace-pre-wrap">
+              {output}
+            </pre>
+          )}
+        </>
+      )}
+      {message && <div className="text-sm text-gray-600">{message}</div>}
+    </div>
+  );
+}
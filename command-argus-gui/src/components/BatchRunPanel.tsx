@@ -0,0 +1,293 @@
+import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { BatchEvent, BatchReport, ImpactStep, PromptRequest } from '../types';
+
+type StepStatus = 'running' | 'passed' | 'failed' | 'skipped';
+
+interface TimelineEntry {
+  step_id: string;
+  command: string;
+  status: StepStatus;
+  duration_ms?: number;
+}
+
+const STATUS_STYLES: Record<StepStatus, string> = {
+  running: 'text-blue-700',
+  passed: 'text-green-700',
+  failed: 'text-red-700',
+  skipped: 'text-gray-500',
+};
+
+const EXAMPLE_PLAN = `steps:
+  - command: Git status
+  - parallel:
+      - command: npm install
+      - command: Cargo build
+    join: fail_fast
+`;
+
+interface BatchRunPanelProps {
+  // Environment profile the plan's commands are run with
+  profile?: string | null;
+}
+
+// Runs a YAML batch plan and draws its steps as they start and finish
+export function BatchRunPanel({ profile }: BatchRunPanelProps) {
+  const [plan, setPlan] = useState(EXAMPLE_PLAN);
+  const [running, setRunning] = useState(false);
+  const [timeline, setTimeline] = useState<TimelineEntry[]>([]);
+  const [percent, setPercent] = useState(0);
+  const [report, setReport] = useState<BatchReport | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [runbook, setRunbook] = useState<string | null>(null);
+  const [prompt, setPrompt] = useState<PromptRequest | null>(null);
+  const [answer, setAnswer] = useState('');
+  // Events of other runs are ignored once the first event of ours arrived
+  const runId = useRef<string | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<BatchEvent>('batch-progress', ({ payload }) => {
+      if (runId.current === null) {
+        runId.current = payload.run_id;
+      } else if (runId.current !== payload.run_id) {
+        return;
+      }
+      if (payload.kind === 'progress') {
+        setPercent(payload.percent);
+        return;
+      }
+      const status: StepStatus =
+        payload.kind === 'step_started'
+          ? 'running'
+          : payload.skipped
+            ? 'skipped'
+            : payload.success
+              ? 'passed'
+              : 'failed';
+      const entry: TimelineEntry = {
+        step_id: payload.step_id,
+        command: payload.command,
+        status,
+        duration_ms: payload.kind === 'step_finished' ? payload.duration_ms : undefined,
+      };
+      setTimeline(prev => {
+        const index = prev.findIndex(e => e.step_id === entry.step_id);
+        if (index === -1) return [...prev, entry];
+        return prev.map((e, i) => (i === index ? entry : e));
+      });
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<PromptRequest>('batch-prompt', ({ payload }) => {
+      if (runId.current !== null && runId.current !== payload.run_id) return;
+      setPrompt(payload);
+      setAnswer(payload.kind === 'input' ? payload.default ?? '' : '');
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // A null value cancels the prompt, failing its step
+  const handleAnswer = async (value: string | null) => {
+    if (!prompt) return;
+    setPrompt(null);
+    try {
+      await invoke('answer_prompt', { runId: prompt.run_id, value });
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to answer the prompt');
+    }
+  };
+
+  // Confirms every high-impact command of the plan at once: one dialog listing them all,
+  // then the name of each destructive one typed. Undefined when the user backed out.
+  const confirmSteps = async (): Promise<Record<string, string> | undefined> => {
+    const steps = await invoke<ImpactStep[]>('batch_impact', { plan });
+    if (steps.length === 0) return {};
+    const list = steps.map(step => `- ${step.command} (${step.impact})`).join('\n');
+    if (!(await ask(`The plan runs these commands:\n${list}\n\nRun it?`, { title: 'Confirm', kind: 'warning' }))) {
+      return undefined;
+    }
+    const confirmations: Record<string, string> = {};
+    for (const step of steps) {
+      if (step.impact === 'destructive') {
+        const typed = window.prompt(`"${step.command}" is destructive. Type its name to run the plan:`);
+        if (typed === null) return undefined;
+        confirmations[step.command] = typed;
+      } else {
+        confirmations[step.command] = 'confirmed';
+      }
+    }
+    return confirmations;
+  };
+
+  const handleRun = async () => {
+    setError(null);
+    let confirmations: Record<string, string> | undefined;
+    try {
+      confirmations = await confirmSteps();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to read the plan');
+      return;
+    }
+    if (confirmations === undefined) return;
+
+    runId.current = null;
+    setRunning(true);
+    setTimeline([]);
+    setPercent(0);
+    setReport(null);
+    setError(null);
+    try {
+      setReport(await invoke<BatchReport>('run_batch', { plan, confirmations, profile, window: getCurrentWindow().label }));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to run the plan');
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const handleExportRunbook = async () => {
+    setError(null);
+    try {
+      setRunbook(await invoke<string>('export_runbook', { plan, title: 'Runbook' }));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to export the runbook');
+    }
+  };
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Run Plan</h2>
+      <textarea
+        value={plan}
+        onChange={e => setPlan(e.target.value)}
+        rows={8}
+        spellCheck={false}
+        className="w-full px-3 py-2 border border-gray-300 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+      />
+      <div className="flex items-center gap-3 mt-2">
+        <button
+          onClick={handleRun}
+          disabled={running || !plan.trim()}
+          className="px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50"
+        >
+          {running ? 'Running...' : 'Run'}
+        </button>
+        <button
+          onClick={handleExportRunbook}
+          disabled={!plan.trim()}
+          className="px-4 py-2 bg-gray-200 text-gray-800 rounded-md hover:bg-gray-300 disabled:opacity-50"
+        >
+          Export Runbook
+        </button>
+        {(running || report) && (
+          <div className="flex-1 h-2 bg-gray-200 rounded">
+            <div className="h-2 bg-blue-500 rounded" style={{ width: `${percent}%` }} />
+          </div>
+        )}
+      </div>
+      {error && <p className="mt-2 text-sm text-red-600">{error}</p>}
+      {prompt && (
+        <div className="mt-3 p-3 border border-blue-200 bg-blue-50 rounded-md">
+          <p className="text-sm font-medium text-gray-800 mb-2">{prompt.question}</p>
+          {prompt.error && <p className="text-sm text-red-600 mb-2">{prompt.error}</p>}
+          <div className="flex flex-wrap items-center gap-2">
+            {prompt.kind === 'confirm' &&
+              ['yes', 'no'].map(value => (
+                <button
+                  key={value}
+                  onClick={() => handleAnswer(value)}
+                  className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600"
+                >
+                  {value === 'yes' ? 'Yes' : 'No'}
+                </button>
+              ))}
+            {prompt.kind === 'choose' &&
+              prompt.options.map(option => (
+                <button
+                  key={option}
+                  onClick={() => handleAnswer(option)}
+                  className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600"
+                >
+                  {option}
+                </button>
+              ))}
+            {prompt.kind === 'input' && (
+              <>
+                <input
+                  type="text"
+                  value={answer}
+                  onChange={e => setAnswer(e.target.value)}
+                  onKeyDown={e => e.key === 'Enter' && handleAnswer(answer)}
+                  autoFocus
+                  className="flex-1 px-3 py-1 border border-gray-300 rounded-md text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+                />
+                <button
+                  onClick={() => handleAnswer(answer)}
+                  className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600"
+                >
+                  Submit
+                </button>
+              </>
+            )}
+            <button
+              onClick={() => handleAnswer(null)}
+              className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+            >
+              Cancel
+            </button>
+          </div>
+        </div>
+      )}
+      {timeline.length > 0 && (
+        <ol className="mt-3 space-y-1 text-sm">
+          {timeline.map(entry => (
+            <li key={entry.step_id} className="flex gap-3">
+              <span className="w-10 text-gray-400">{entry.step_id}</span>
+              <span className="flex-1 text-gray-800">{entry.command}</span>
+              <span className={STATUS_STYLES[entry.status]}>{entry.status}</span>
+              <span className="w-16 text-right text-gray-500">
+                {entry.duration_ms !== undefined ? `${(entry.duration_ms / 1000).toFixed(2)}s` : ''}
+              </span>
+            </li>
+          ))}
+        </ol>
+      )}
+      {runbook !== null && (
+        <div className="mt-3">
+          <div className="flex justify-between items-center mb-1">
+            <span className="text-sm font-medium text-gray-700">Runbook (Markdown)</span>
+            <div className="flex gap-2">
+              <button
+                onClick={() => navigator.clipboard.writeText(runbook)}
+                className="px-3 py-1 text-xs bg-blue-500 text-white rounded hover:bg-blue-600"
+              >
+                Copy
+              </button>
+              <button
+                onClick={() => setRunbook(null)}
+                className="px-3 py-1 text-xs bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+              >
+                Close
+              </button>
+            </div>
+          </div>
+          <pre className="p-2 bg-gray-50 border border-gray-200 rounded text-xs whitespace-pre-wrap">{runbook}</pre>
+        </div>
+      )}
+      {report && (
+        <pre className="mt-3 p-2 bg-gray-50 border border-gray-200 rounded text-xs whitespace-pre-wrap">
+          {report.summary}
+        </pre>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,207 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { parseParameters } from './ForEachPanel';
+import { BenchmarkComparison, BenchmarkReport, BenchmarkRun, Command } from '../types';
+
+interface BenchmarkPanelProps {
+  // Environment profile the command is run with
+  profile?: string | null;
+}
+
+const formatMs = (ms: number) => `${ms.toFixed(1)} ms`;
+
+// Times a saved command over many runs, like hyperfine
+export function BenchmarkPanel({ profile }: BenchmarkPanelProps) {
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [commandId, setCommandId] = useState('');
+  // A second command to compare the first with
+  const [otherId, setOtherId] = useState('');
+  const [iterations, setIterations] = useState(10);
+  const [warmup, setWarmup] = useState(1);
+  const [useShell, setUseShell] = useState(false);
+  const [parameters, setParameters] = useState('');
+  const [running, setRunning] = useState(false);
+  const [finished, setFinished] = useState<BenchmarkRun[]>([]);
+  const [report, setReport] = useState<BenchmarkReport | null>(null);
+  const [comparison, setComparison] = useState<BenchmarkComparison | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<Command[]>('list_commands', { allPlatforms: false }).then(setCommands);
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<BenchmarkRun>('benchmark-progress', ({ payload }) => {
+      setFinished(prev => [...prev, payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // The confirmation the command's impact level asks for, or undefined if the user backed out
+  const confirmImpact = async (command: Command): Promise<string | null | undefined> => {
+    switch (command.impact) {
+      case 'caution': {
+        const confirmed = await ask(`Run "${command.name}" ${iterations + warmup} times?`, { title: 'Confirm', kind: 'warning' });
+        return confirmed ? 'confirmed' : undefined;
+      }
+      case 'destructive': {
+        const typed = window.prompt(`"${command.name}" is destructive. Type its name to run it ${iterations + warmup} times:`);
+        return typed === null ? undefined : typed;
+      }
+      default:
+        return null;
+    }
+  };
+
+  const handleRun = async () => {
+    const command = commands.find(c => c.id === commandId);
+    if (!command) return;
+    const confirmation = await confirmImpact(command);
+    if (confirmation === undefined) return;
+    const other = commands.find(c => c.id === otherId);
+    const otherConfirmation = other ? await confirmImpact(other) : null;
+    if (otherConfirmation === undefined) return;
+
+    setRunning(true);
+    setFinished([]);
+    setReport(null);
+    setComparison(null);
+    setError(null);
+    try {
+      if (other) {
+        const request = { id_a: commandId, id_b: otherId, iterations, warmup, use_shell: useShell };
+        setComparison(
+          await invoke<BenchmarkComparison>('compare_benchmarks', {
+            request,
+            confirmationA: confirmation,
+            confirmationB: otherConfirmation,
+            profile,
+            window: getCurrentWindow().label,
+          })
+        );
+        return;
+      }
+      const request = {
+        id: commandId,
+        iterations,
+        warmup,
+        use_shell: useShell,
+        parameters: parseParameters(parameters),
+      };
+      setReport(await invoke<BenchmarkReport>('benchmark_command', { request, confirmation, profile, window: getCurrentWindow().label }));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to benchmark the command');
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const runs = report ? report.runs : comparison ? [] : finished;
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Benchmark</h2>
+      <div className="grid grid-cols-2 gap-2 text-sm">
+        <select
+          value={commandId}
+          onChange={e => setCommandId(e.target.value)}
+          className="px-2 py-1 border border-gray-300 rounded-md"
+        >
+          <option value="">Choose a command...</option>
+          {commands.map(command => (
+            <option key={command.id} value={command.id}>
+              {command.name}
+            </option>
+          ))}
+        </select>
+        <select
+          value={otherId}
+          onChange={e => setOtherId(e.target.value)}
+          className="px-2 py-1 border border-gray-300 rounded-md"
+        >
+          <option value="">Compare with... (optional)</option>
+          {commands
+            .filter(command => command.id !== commandId)
+            .map(command => (
+              <option key={command.id} value={command.id}>
+                {command.name}
+              </option>
+            ))}
+        </select>
+        <div className="col-span-2 flex items-center gap-3">
+          <label className="flex items-center gap-1 text-gray-700">
+            Runs
+            <input
+              type="number"
+              min={1}
+              value={iterations}
+              onChange={e => setIterations(Math.max(1, Number(e.target.value)))}
+              className="w-16 px-2 py-1 border border-gray-300 rounded-md"
+            />
+          </label>
+          <label className="flex items-center gap-1 text-gray-700">
+            Warmup
+            <input
+              type="number"
+              min={0}
+              value={warmup}
+              onChange={e => setWarmup(Math.max(0, Number(e.target.value)))}
+              className="w-16 px-2 py-1 border border-gray-300 rounded-md"
+            />
+          </label>
+          <label className="flex items-center gap-1 text-gray-700">
+            <input type="checkbox" checked={useShell} onChange={e => setUseShell(e.target.checked)} />
+            Run in shell
+          </label>
+        </div>
+        {!otherId && (
+          <textarea
+            value={parameters}
+            onChange={e => setParameters(e.target.value)}
+            rows={2}
+            placeholder="Parameters, one name=value per line"
+            className="col-span-2 px-3 py-1 border border-gray-300 rounded-md font-mono focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+        )}
+      </div>
+      <button
+        onClick={handleRun}
+        disabled={running || !commandId}
+        className="mt-2 px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50"
+      >
+        {running ? `Running ${finished.length}/${otherId ? iterations * 2 : iterations}...` : otherId ? 'Compare' : 'Run Benchmark'}
+      </button>
+      {error && <p className="mt-2 text-sm text-red-600">{error}</p>}
+      {comparison && (
+        <pre className="mt-3 p-2 bg-gray-50 border border-gray-200 rounded text-xs whitespace-pre-wrap">
+          {comparison.summary}
+        </pre>
+      )}
+      {report && (
+        <div className="mt-3 grid grid-cols-3 gap-2 text-sm">
+          <div>Mean: {formatMs(report.stats.mean_ms)} ± {formatMs(report.stats.stddev_ms)}</div>
+          <div>Median: {formatMs(report.stats.median_ms)}</div>
+          <div>p95: {formatMs(report.stats.p95_ms)}</div>
+          <div>Min: {formatMs(report.stats.min_ms)}</div>
+          <div>Max: {formatMs(report.stats.max_ms)}</div>
+        </div>
+      )}
+      {runs.length > 0 && (
+        <ul className="mt-3 space-y-1 text-sm">
+          {runs.map((run, index) => (
+            <li key={index} className="flex gap-3">
+              <span className="w-12 text-gray-500">#{run.iteration}</span>
+              <span className={run.success ? 'text-green-700' : 'text-red-700'}>exit {run.exit_code}</span>
+              <span className="flex-1 text-right text-gray-700">{formatMs(run.duration_ms)}</span>
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
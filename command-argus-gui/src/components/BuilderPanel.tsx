@@ -0,0 +1,164 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { CommandBuilder } from '../types';
+
+type Tool = CommandBuilder['tool'];
+
+const lines = (text: string) => text.split('\n').map(l => l.trim()).filter(l => l);
+
+const inputClass = "w-full px-2 py-1 border border-gray-300 rounded-md text-sm";
+
+// Creates a docker run or ssh command from a form instead of typing its flags
+export function BuilderPanel() {
+  const [tool, setTool] = useState<Tool>('docker_run');
+  const [name, setName] = useState('');
+  // docker run
+  const [image, setImage] = useState('');
+  const [tag, setTag] = useState('');
+  const [containerName, setContainerName] = useState('');
+  const [ports, setPorts] = useState('');
+  const [volumes, setVolumes] = useState('');
+  const [environment, setEnvironment] = useState('');
+  const [remove, setRemove] = useState(true);
+  const [detach, setDetach] = useState(false);
+  const [interactive, setInteractive] = useState(false);
+  const [containerCommand, setContainerCommand] = useState('');
+  // ssh
+  const [host, setHost] = useState('');
+  const [user, setUser] = useState('');
+  const [port, setPort] = useState('');
+  const [identityFile, setIdentityFile] = useState('');
+  const [jumpHost, setJumpHost] = useState('');
+  const [forwards, setForwards] = useState('');
+  const [remoteCommand, setRemoteCommand] = useState('');
+
+  const [preview, setPreview] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [saved, setSaved] = useState<string | null>(null);
+
+  const builder = (): CommandBuilder => {
+    if (tool === 'docker_run') {
+      return {
+        tool,
+        image,
+        tag: tag || undefined,
+        container_name: containerName || undefined,
+        // host:container[/protocol]
+        ports: lines(ports).map(line => {
+          const [mapping, protocol] = line.split('/');
+          const [hostPort, container] = mapping.split(':');
+          return { host: hostPort, container: container ?? hostPort, protocol };
+        }),
+        // source:target[:ro]
+        volumes: lines(volumes).map(line => {
+          const readOnly = line.endsWith(':ro');
+          const [source, target] = (readOnly ? line.slice(0, -3) : line).split(':');
+          return { source, target: target ?? '', read_only: readOnly };
+        }),
+        environment: lines(environment).map(line => {
+          const [key, ...value] = line.split('=');
+          return { key, value: value.join('='), secret: false };
+        }),
+        remove,
+        detach,
+        interactive,
+        command: containerCommand.split(' ').filter(a => a)
+      };
+    }
+    return {
+      tool,
+      host,
+      user: user || undefined,
+      port: port || undefined,
+      identity_file: identityFile || undefined,
+      jump_host: jumpHost || undefined,
+      // local_port:remote_host:remote_port
+      forwards: lines(forwards).map(line => {
+        const [local_port, remote_host, remote_port] = line.split(':');
+        return { local_port, remote_host: remote_host ?? '', remote_port: remote_port ?? '' };
+      }),
+      remote_command: remoteCommand || undefined
+    };
+  };
+
+  useEffect(() => {
+    const timeoutId = setTimeout(() => {
+      invoke<string>('preview_builder', { builder: builder() })
+        .then(line => {
+          setPreview(line);
+          setError(null);
+        })
+        .catch(e => {
+          setPreview('');
+          setError(String(e));
+        });
+    }, 300);
+    return () => clearTimeout(timeoutId);
+  }, [tool, image, tag, containerName, ports, volumes, environment, remove, detach, interactive, containerCommand,
+      host, user, port, identityFile, jumpHost, forwards, remoteCommand]);
+
+  const handleCreate = async () => {
+    setSaved(null);
+    try {
+      await invoke('create_from_builder', { name, builder: builder() });
+      setSaved(name);
+      setName('');
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center gap-2">
+        <h3 className="font-semibold">Command Builder</h3>
+        <select value={tool} onChange={e => setTool(e.target.value as Tool)} className="px-2 py-1 border border-gray-300 rounded-md text-sm">
+          <option value="docker_run">docker run</option>
+          <option value="ssh">ssh</option>
+        </select>
+      </div>
+
+      {tool === 'docker_run' ? (
+        <div className="grid grid-cols-2 gap-2 text-sm">
+          <label>Image<input value={image} onChange={e => setImage(e.target.value)} placeholder="postgres" className={inputClass} /></label>
+          <label>Tag<input value={tag} onChange={e => setTag(e.target.value)} placeholder="16" className={inputClass} /></label>
+          <label>Container name<input value={containerName} onChange={e => setContainerName(e.target.value)} className={inputClass} /></label>
+          <label>Command<input value={containerCommand} onChange={e => setContainerCommand(e.target.value)} placeholder="Image default" className={inputClass} /></label>
+          <label>Ports (host:container per line)<textarea value={ports} onChange={e => setPorts(e.target.value)} rows={2} className={inputClass} /></label>
+          <label>Volumes (source:target[:ro] per line)<textarea value={volumes} onChange={e => setVolumes(e.target.value)} rows={2} className={inputClass} /></label>
+          <label className="col-span-2">Environment (KEY=value per line, kept off the command line)<textarea value={environment} onChange={e => setEnvironment(e.target.value)} rows={2} className={inputClass} /></label>
+          <div className="col-span-2 flex gap-4">
+            <label className="flex items-center gap-1"><input type="checkbox" checked={remove} onChange={e => setRemove(e.target.checked)} />--rm</label>
+            <label className="flex items-center gap-1"><input type="checkbox" checked={detach} onChange={e => setDetach(e.target.checked)} />Detach</label>
+            <label className="flex items-center gap-1"><input type="checkbox" checked={interactive} onChange={e => setInteractive(e.target.checked)} />Interactive</label>
+          </div>
+        </div>
+      ) : (
+        <div className="grid grid-cols-2 gap-2 text-sm">
+          <label>Host<input value={host} onChange={e => setHost(e.target.value)} placeholder="db.internal or {host}" className={inputClass} /></label>
+          <label>User<input value={user} onChange={e => setUser(e.target.value)} className={inputClass} /></label>
+          <label>Port<input value={port} onChange={e => setPort(e.target.value)} placeholder="22" className={inputClass} /></label>
+          <label>Identity file<input value={identityFile} onChange={e => setIdentityFile(e.target.value)} placeholder="~/.ssh/id_ed25519" className={inputClass} /></label>
+          <label>Jump host<input value={jumpHost} onChange={e => setJumpHost(e.target.value)} className={inputClass} /></label>
+          <label>Remote command<input value={remoteCommand} onChange={e => setRemoteCommand(e.target.value)} placeholder="Open a shell" className={inputClass} /></label>
+          <label className="col-span-2">Port forwards (local:remote_host:remote_port per line)<textarea value={forwards} onChange={e => setForwards(e.target.value)} rows={2} className={inputClass} /></label>
+        </div>
+      )}
+
+      {preview && <pre className="p-2 bg-gray-50 border border-gray-200 rounded-md text-xs font-mono whitespace-pre-wrap">{preview}</pre>}
+      {error && <p className="text-sm text-red-600">{error}</p>}
+
+      <div className="flex items-center gap-2">
+        <input value={name} onChange={e => setName(e.target.value)} placeholder="Command name" className="px-2 py-1 border border-gray-300 rounded-md text-sm" />
+        <button
+          onClick={handleCreate}
+          disabled={!name.trim() || !preview}
+          className="px-3 py-1 bg-green-500 text-white rounded-md hover:bg-green-600 disabled:opacity-50 text-sm"
+        >
+          Create
+        </button>
+        {saved && <span className="text-sm text-green-700">Created "{saved}"</span>}
+      </div>
+    </div>
+  );
+}
@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Command, CreateCommandRequest, UpdateCommandRequest, EnvironmentVariable } from '../types';
+import { Command, CreateCommandRequest, UpdateCommandRequest, EnvironmentVariable, OutputEncoding, ShellKind, ExecutionMode, Platform, PlatformOverride, ToolCheck, RateLimit, Impact, CommandNotifications, NotificationTarget, OpenOutput, ProblemMatcher, HelpText, AliasOffer, SyntaxWarning } from '../types';
 
 interface CommandFormProps {
   editingCommand?: Command | null;
@@ -12,33 +12,228 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
   const [name, setName] = useState('');
   const [command, setCommand] = useState('');
   const [args, setArgs] = useState('');
+  // Arguments are edited either as one line (quoted like in a shell) or one per field
+  const [argsMode, setArgsMode] = useState<'raw' | 'list'>('raw');
+  const [argList, setArgList] = useState<string[]>([]);
+  const [argsError, setArgsError] = useState<string | null>(null);
   const [description, setDescription] = useState('');
   const [workingDirectory, setWorkingDirectory] = useState('');
   const [tags, setTags] = useState('');
   const [envVars, setEnvVars] = useState<EnvironmentVariable[]>([]);
   const [miseEnabled, setMiseEnabled] = useState(false);
+  const [noNetwork, setNoNetwork] = useState(false);
+  const [outputEncoding, setOutputEncoding] = useState<OutputEncoding>('auto');
+  const [shell, setShell] = useState<ShellKind>('system');
+  const [loadShellProfile, setLoadShellProfile] = useState(false);
+  const [executionMode, setExecutionMode] = useState<ExecutionMode>('shell');
+  const [platforms, setPlatforms] = useState<Platform[]>([]);
+  const [platformOverrides, setPlatformOverrides] = useState<PlatformOverride[]>([]);
+  const [pinExecutable, setPinExecutable] = useState(false);
+  const [priority, setPriority] = useState(0);
+  const [mutexGroup, setMutexGroup] = useState('');
+  const [profiles, setProfiles] = useState('');
+  const [forbiddenProfiles, setForbiddenProfiles] = useState('');
+  const [collectArtifacts, setCollectArtifacts] = useState('');
+  const [scratchDir, setScratchDir] = useState(false);
+  const [keepScratchOnFailure, setKeepScratchOnFailure] = useState(false);
+  const [stdinTemplate, setStdinTemplate] = useState('');
+  const [recordStdin, setRecordStdin] = useState(false);
+  const [captureTimeline, setCaptureTimeline] = useState(false);
+  const [phaseMarker, setPhaseMarker] = useState('');
+  const [openOutput, setOpenOutput] = useState<'' | 'default' | 'program'>('');
+  const [openOutputProgram, setOpenOutputProgram] = useState('');
+  const [openOutputExtension, setOpenOutputExtension] = useState('txt');
+  const [openOutputBase64, setOpenOutputBase64] = useState(false);
+  const [problemMatchers, setProblemMatchers] = useState<ProblemMatcher[]>([]);
+  const [maintainerNote, setMaintainerNote] = useState('');
+  const [impact, setImpact] = useState<Impact>('safe');
+  const [rateLimitRuns, setRateLimitRuns] = useState('');
+  const [rateLimitPer, setRateLimitPer] = useState<RateLimit['per']>('minute');
+  const [notifyOn, setNotifyOn] = useState<'' | CommandNotifications['on']>('');
+  const [slackUrl, setSlackUrl] = useState('');
+  const [discordUrl, setDiscordUrl] = useState('');
+  const [toolChecks, setToolChecks] = useState('');
+  const [blockOnToolMismatch, setBlockOnToolMismatch] = useState(false);
   const [saving, setSaving] = useState(false);
+  const [suggestedRoot, setSuggestedRoot] = useState<string | null>(null);
+  const [help, setHelp] = useState<HelpText | null>(null);
+  const [helpError, setHelpError] = useState<string | null>(null);
+  const [loadingHelp, setLoadingHelp] = useState(false);
+  const [aliasOffer, setAliasOffer] = useState<AliasOffer | null>(null);
+  const [keptAlias, setKeptAlias] = useState<string | null>(null);
+
+  const [syntaxWarnings, setSyntaxWarnings] = useState<SyntaxWarning[]>([]);
+
+  useEffect(() => {
+    setAliasOffer(null);
+  }, [command]);
+
+  const currentArgs = (): Promise<string[]> =>
+    argsMode === 'list' ? Promise.resolve(argList) : invoke<string[]>('split_args', { line: args });
+
+  const switchArgsMode = async () => {
+    try {
+      if (argsMode === 'raw') {
+        setArgList(await invoke<string[]>('split_args', { line: args }));
+        setArgsMode('list');
+      } else {
+        setArgs(await invoke<string>('join_args', { args: argList }));
+        setArgsMode('raw');
+      }
+      setArgsError(null);
+    } catch (error) {
+      setArgsError(String(error));
+    }
+  };
+
+  const showArgs = async (values: string[]) => {
+    setArgList(values);
+    setArgs(await invoke<string>('join_args', { args: values }));
+  };
+
+  // Check the line the command runs as in shell mode while it is typed
+  useEffect(() => {
+    if (!command.trim()) {
+      setSyntaxWarnings([]);
+      return;
+    }
+    const timeoutId = setTimeout(async () => {
+      try {
+        const parsed = await currentArgs();
+        setArgsError(null);
+        const request: CreateCommandRequest = {
+          name,
+          command: command.trim(),
+          args: parsed,
+          environment_variables: [],
+          tags: [],
+          parameters: [],
+          shell
+        };
+        setSyntaxWarnings(await invoke<SyntaxWarning[]>('validate_command_request', { request }));
+      } catch (error) {
+        if (argsMode === 'raw') setArgsError(String(error));
+        setSyntaxWarnings([]);
+      }
+    }, 400);
+    return () => clearTimeout(timeoutId);
+  }, [name, command, args, argList, argsMode, shell]);
+
+  const loadHelp = async (refresh: boolean) => {
+    if (!editingCommand) return;
+    setLoadingHelp(true);
+    setHelpError(null);
+    try {
+      setHelp(await invoke<HelpText>('fetch_help', { id: editingCommand.id, refresh }));
+    } catch (error) {
+      setHelp(null);
+      setHelpError(String(error));
+    } finally {
+      setLoadingHelp(false);
+    }
+  };
+
+  // Suggest the project root above what was typed, or above the opened project, for new commands
+  useEffect(() => {
+    if (editingCommand) return;
+    const timeoutId = setTimeout(() => {
+      const path = workingDirectory.includes('{') ? null : workingDirectory;
+      invoke<string | null>('suggest_project_root', { path })
+        .then(setSuggestedRoot)
+        .catch(() => setSuggestedRoot(null));
+    }, 300);
+    return () => clearTimeout(timeoutId);
+  }, [editingCommand, workingDirectory]);
 
   useEffect(() => {
     if (editingCommand) {
       setName(editingCommand.name);
       setCommand(editingCommand.command);
-      setArgs(editingCommand.args.join(' '));
+      showArgs(editingCommand.args);
       setDescription(editingCommand.description || '');
       setWorkingDirectory(editingCommand.working_directory || '');
       setTags(editingCommand.tags.join(', '));
       setEnvVars(editingCommand.environment_variables);
       setMiseEnabled(editingCommand.mise_enabled || false);
+      setNoNetwork(editingCommand.no_network || false);
+      setOutputEncoding(editingCommand.output_encoding || 'auto');
+      setShell(editingCommand.shell || 'system');
+      setLoadShellProfile(editingCommand.load_shell_profile || false);
+      setExecutionMode(editingCommand.default_execution_mode || 'shell');
+      setPlatforms(editingCommand.platforms || []);
+      setPlatformOverrides(editingCommand.platform_overrides || []);
+      setPinExecutable(!!editingCommand.pinned_executable);
+      setPriority(editingCommand.priority || 0);
+      setMutexGroup(editingCommand.mutex_group || '');
+      setProfiles((editingCommand.profiles || []).join(', '));
+      setForbiddenProfiles((editingCommand.forbidden_profiles || []).join(', '));
+      setCollectArtifacts((editingCommand.collect_artifacts || []).join(', '));
+      setScratchDir(!!editingCommand.scratch_dir?.enabled);
+      setKeepScratchOnFailure(!!editingCommand.scratch_dir?.keep_on_failure);
+      setStdinTemplate(editingCommand.stdin_template || '');
+      setRecordStdin(!!editingCommand.record_stdin);
+      setCaptureTimeline(!!editingCommand.capture_timeline);
+      setPhaseMarker(editingCommand.phase_marker || '');
+      const open = editingCommand.open_output_with;
+      setOpenOutput(open ? (open.program ? 'program' : 'default') : '');
+      setOpenOutputProgram(open?.program || '');
+      setOpenOutputExtension(open?.extension || 'txt');
+      setOpenOutputBase64(!!open?.decode_base64);
+      setProblemMatchers(editingCommand.problem_matchers || []);
+      setMaintainerNote(editingCommand.maintainer_note || '');
+      setImpact(editingCommand.impact || 'safe');
+      setRateLimitRuns(editingCommand.rate_limit ? String(editingCommand.rate_limit.max_runs) : '');
+      setRateLimitPer(editingCommand.rate_limit?.per || 'minute');
+      const targets = editingCommand.notifications?.targets || [];
+      setNotifyOn(editingCommand.notifications?.on || '');
+      setSlackUrl(targets.flatMap(t => (t.kind === 'slack' ? [t.url] : []))[0] || '');
+      setDiscordUrl(targets.flatMap(t => (t.kind === 'discord' ? [t.url] : []))[0] || '');
+      setToolChecks((editingCommand.tool_checks || []).map(c => `${c.tool} ${c.requirement}`).join('\n'));
+      setBlockOnToolMismatch((editingCommand.tool_checks || []).some(c => c.block));
     } else {
       // Reset form for new command
       setName('');
       setCommand('');
       setArgs('');
+      setArgList([]);
       setDescription('');
       setWorkingDirectory('');
       setTags('');
       setEnvVars([]);
       setMiseEnabled(false);
+      setNoNetwork(false);
+      setOutputEncoding('auto');
+      setShell('system');
+      setLoadShellProfile(false);
+      setExecutionMode('shell');
+      setPlatforms([]);
+      setToolChecks('');
+      setBlockOnToolMismatch(false);
+      setPlatformOverrides([]);
+      setPinExecutable(false);
+      setPriority(0);
+      setMutexGroup('');
+      setProfiles('');
+      setForbiddenProfiles('');
+      setCollectArtifacts('');
+      setScratchDir(false);
+      setKeepScratchOnFailure(false);
+      setStdinTemplate('');
+      setRecordStdin(false);
+      setCaptureTimeline(false);
+      setPhaseMarker('');
+      setOpenOutput('');
+      setOpenOutputProgram('');
+      setOpenOutputExtension('txt');
+      setOpenOutputBase64(false);
+      setProblemMatchers([]);
+      setMaintainerNote('');
+      setImpact('safe');
+      setRateLimitRuns('');
+      setRateLimitPer('minute');
+      setNotifyOn('');
+      setSlackUrl('');
+      setDiscordUrl('');
     }
   }, [editingCommand]);
 
@@ -52,10 +247,51 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
     setEnvVars(updated);
   };
 
+  const handleToggleEnvVarSecret = (index: number) => {
+    const updated = [...envVars];
+    updated[index] = { ...updated[index], secret: !updated[index].secret };
+    setEnvVars(updated);
+  };
+
+  const handleTogglePlatform = (platform: Platform) => {
+    setPlatforms(platforms.includes(platform)
+      ? platforms.filter(p => p !== platform)
+      : [...platforms, platform]);
+  };
+
+  const overrideFor = (platform: Platform) =>
+    platformOverrides.find(o => o.platform === platform);
+
+  const handleUpdateOverride = (platform: Platform, field: 'command' | 'args', value: string) => {
+    const existing = overrideFor(platform) || { platform, command: '' };
+    const updated: PlatformOverride = field === 'command'
+      ? { ...existing, command: value }
+      : { ...existing, args: value.trim() ? value.split(' ').filter(arg => arg) : undefined };
+    setPlatformOverrides([...platformOverrides.filter(o => o.platform !== platform), updated]);
+  };
+
   const handleRemoveEnvVar = (index: number) => {
     setEnvVars(envVars.filter((_, i) => i !== index));
   };
 
+  const handleAddProblemMatcher = async (builtin: string) => {
+    try {
+      const matcher = builtin
+        ? await invoke<ProblemMatcher>('get_builtin_problem_matcher', { name: builtin })
+        : { pattern: '', file: 1, line: 2, column: 3, message: 4 };
+      setProblemMatchers([...problemMatchers, matcher]);
+    } catch (err) {
+      alert(`Failed to load problem matcher: ${err}`);
+    }
+  };
+
+  const handleUpdateProblemMatcher = (index: number, update: Partial<ProblemMatcher>) => {
+    setProblemMatchers(problemMatchers.map((m, i) => (i === index ? { ...m, ...update } : m)));
+  };
+
+  // Empty group fields mean the matcher has no such group
+  const groupIndex = (value: string) => (value.trim() ? parseInt(value, 10) || undefined : undefined);
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     
@@ -64,12 +300,66 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
       return;
     }
 
+    let argsArray: string[];
+    try {
+      argsArray = await currentArgs();
+    } catch (error) {
+      setArgsError(String(error));
+      return;
+    }
+
+    // A shell alias copied from a terminal doesn't exist for the executor; offer to expand it first
+    if (keptAlias !== command.trim()) {
+      const offer = await invoke<AliasOffer | null>('detect_alias', {
+        command: command.trim(),
+        args: argsArray
+      }).catch(() => null);
+      if (offer) {
+        setAliasOffer(offer);
+        return;
+      }
+    }
+
     setSaving(true);
 
     try {
-      const argsArray = args.trim() ? args.split(' ').filter(a => a) : [];
       const tagsArray = tags.trim() ? tags.split(',').map(t => t.trim()).filter(t => t) : [];
+      const profilesArray = profiles.split(',').map(p => p.trim()).filter(p => p);
+      const forbiddenProfilesArray = forbiddenProfiles.split(',').map(p => p.trim()).filter(p => p);
+      const collectArtifactsArray = collectArtifacts.split(',').map(p => p.trim()).filter(p => p);
       const validEnvVars = envVars.filter(ev => ev.key && ev.value);
+      const openOutputWith: OpenOutput | null = openOutput
+        ? {
+            program: openOutput === 'program' && openOutputProgram.trim() ? openOutputProgram.trim() : undefined,
+            extension: openOutputExtension.trim() || 'txt',
+            decode_base64: openOutputBase64
+          }
+        : null;
+      const rateLimit: RateLimit | null = rateLimitRuns.trim()
+        ? { max_runs: Math.max(0, parseInt(rateLimitRuns, 10) || 0), per: rateLimitPer }
+        : null;
+      // Targets other than Slack and Discord can't be edited here and are kept as they are
+      const notifyTargets: NotificationTarget[] = (editingCommand?.notifications?.targets || [])
+        .filter(t => t.kind !== 'slack' && t.kind !== 'discord');
+      if (slackUrl.trim()) notifyTargets.push({ kind: 'slack', url: slackUrl.trim() });
+      if (discordUrl.trim()) notifyTargets.push({ kind: 'discord', url: discordUrl.trim() });
+      const notifications: CommandNotifications | null = notifyOn && notifyTargets.length > 0
+        ? { on: notifyOn, targets: notifyTargets }
+        : null;
+      // One `tool requirement` per line; custom version arguments of existing checks are kept
+      const toolChecksArray: ToolCheck[] = toolChecks.split('\n')
+        .map(line => line.trim())
+        .filter(line => line)
+        .map(line => {
+          const [tool, ...requirement] = line.split(/\s+/);
+          const existing = editingCommand?.tool_checks?.find(c => c.tool === tool);
+          return {
+            tool,
+            requirement: requirement.join(' '),
+            version_args: existing?.version_args,
+            block: blockOnToolMismatch
+          };
+        });
 
       if (editingCommand) {
         const updateRequest: UpdateCommandRequest = {
@@ -80,7 +370,32 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
           working_directory: workingDirectory || undefined,
           environment_variables: validEnvVars,
           tags: tagsArray,
-          mise_enabled: miseEnabled
+          mise_enabled: miseEnabled,
+          no_network: noNetwork,
+          output_encoding: outputEncoding,
+          shell,
+          load_shell_profile: loadShellProfile,
+          default_execution_mode: executionMode,
+          platforms,
+          platform_overrides: platformOverrides.filter(o => o.command.trim()),
+          pin_executable: pinExecutable,
+          tool_checks: toolChecksArray,
+          priority,
+          mutex_group: mutexGroup.trim(),
+          profiles: profilesArray,
+          forbidden_profiles: forbiddenProfilesArray,
+          collect_artifacts: collectArtifactsArray,
+          scratch_dir: { enabled: scratchDir, keep_on_failure: keepScratchOnFailure },
+          stdin_template: stdinTemplate,
+          record_stdin: recordStdin,
+          capture_timeline: captureTimeline,
+          phase_marker: phaseMarker,
+          open_output_with: openOutputWith,
+          problem_matchers: problemMatchers.filter(m => m.pattern.trim()),
+          maintainer_note: maintainerNote.trim(),
+          rate_limit: rateLimit,
+          notifications,
+          impact
         };
         
         await invoke('update_command', {
@@ -97,7 +412,32 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
           environment_variables: validEnvVars,
           tags: tagsArray,
           parameters: [],
-          mise_enabled: miseEnabled
+          mise_enabled: miseEnabled,
+          no_network: noNetwork,
+          output_encoding: outputEncoding,
+          shell,
+          load_shell_profile: loadShellProfile,
+          default_execution_mode: executionMode,
+          platforms,
+          platform_overrides: platformOverrides.filter(o => o.command.trim()),
+          pin_executable: pinExecutable,
+          tool_checks: toolChecksArray,
+          priority,
+          mutex_group: mutexGroup.trim(),
+          profiles: profilesArray,
+          forbidden_profiles: forbiddenProfilesArray,
+          collect_artifacts: collectArtifactsArray,
+          scratch_dir: { enabled: scratchDir, keep_on_failure: keepScratchOnFailure },
+          stdin_template: stdinTemplate,
+          record_stdin: recordStdin,
+          capture_timeline: captureTimeline,
+          phase_marker: phaseMarker,
+          open_output_with: openOutputWith,
+          problem_matchers: problemMatchers.filter(m => m.pattern.trim()),
+          maintainer_note: maintainerNote.trim(),
+          rate_limit: rateLimit,
+          notifications,
+          impact
         };
         
         await invoke('create_command', { request: createRequest });
@@ -137,17 +477,85 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
           required
           className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
         />
+        {editingCommand && (
+          <div className="mt-1 flex items-center gap-2 text-xs">
+            <button
+              type="button"
+              onClick={() => (help ? setHelp(null) : loadHelp(false))}
+              disabled={loadingHelp}
+              className="text-blue-600 hover:text-blue-800 disabled:opacity-50"
+            >
+              {loadingHelp ? 'Loading help...' : help ? 'Hide help' : 'Show help'}
+            </button>
+            {help && (
+              <button type="button" onClick={() => loadHelp(true)} disabled={loadingHelp} className="text-blue-600 hover:text-blue-800 disabled:opacity-50">
+                Refresh
+              </button>
+            )}
+            {helpError && <span className="text-red-600">{helpError}</span>}
+          </div>
+        )}
+        {help && (
+          <div className="mt-2 border border-gray-200 rounded-md">
+            <div className="px-2 py-1 text-xs text-gray-500 bg-gray-50 border-b border-gray-200 truncate">
+              {help.source === 'man' ? 'man' : '--help'} · <span className="font-mono">{help.program}</span>
+              {help.version && <> · {help.version}</>}
+            </div>
+            <pre className="p-2 text-xs font-mono whitespace-pre-wrap max-h-64 overflow-y-auto">{help.text}</pre>
+          </div>
+        )}
       </div>
 
       <div>
-        <label className="block text-sm font-medium mb-1">Arguments</label>
-        <input
-          type="text"
-          value={args}
-          onChange={(e) => setArgs(e.target.value)}
-          placeholder="Space-separated arguments"
-          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
-        />
+        <div className="flex items-center justify-between mb-1">
+          <label className="block text-sm font-medium">Arguments</label>
+          <button type="button" onClick={switchArgsMode} className="text-xs text-blue-600 hover:underline">
+            {argsMode === 'raw' ? 'Edit as list' : 'Edit as one line'}
+          </button>
+        </div>
+        {argsMode === 'raw' ? (
+          <input
+            type="text"
+            value={args}
+            onChange={(e) => setArgs(e.target.value)}
+            placeholder={`Quote arguments with spaces, e.g. -m "fix build"`}
+            className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+        ) : (
+          <div className="space-y-1">
+            {argList.map((arg, index) => (
+              <div key={index} className="flex gap-2">
+                <input
+                  type="text"
+                  value={arg}
+                  onChange={(e) => setArgList(prev => prev.map((a, i) => (i === index ? e.target.value : a)))}
+                  className="flex-1 px-3 py-1 border border-gray-300 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+                />
+                <button
+                  type="button"
+                  onClick={() => setArgList(prev => prev.filter((_, i) => i !== index))}
+                  className="px-2 text-red-600 hover:underline text-sm"
+                >
+                  Remove
+                </button>
+              </div>
+            ))}
+            <button type="button" onClick={() => setArgList(prev => [...prev, ''])} className="text-sm text-blue-600 hover:underline">
+              Add argument
+            </button>
+          </div>
+        )}
+        {argsError && <p className="mt-1 text-xs text-red-600">{argsError}</p>}
+        {syntaxWarnings.length > 0 && (
+          <ul className="mt-1 text-xs text-yellow-700 space-y-0.5">
+            {syntaxWarnings.map((warning, index) => (
+              <li key={index}>
+                In shell mode: {warning.message}
+                {warning.offset !== undefined && <span className="text-gray-500"> (at character {warning.offset + 1})</span>}
+              </li>
+            ))}
+          </ul>
+        )}
       </div>
 
       <div>
@@ -166,8 +574,20 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
           type="text"
           value={workingDirectory}
           onChange={(e) => setWorkingDirectory(e.target.value)}
+          placeholder="e.g. ~/src/{service} or {{var:checkout}}/api"
           className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
         />
+        {!editingCommand && suggestedRoot && workingDirectory !== suggestedRoot && (
+          <div className="mt-1 flex items-center gap-2 text-xs text-gray-600">
+            <span className="truncate">Project root: <span className="font-mono">{suggestedRoot}</span></span>
+            <button type="button" onClick={() => setWorkingDirectory(suggestedRoot)} className="text-blue-600 hover:text-blue-800">
+              Use it
+            </button>
+            <button type="button" onClick={() => setWorkingDirectory('{project_root}')} className="text-blue-600 hover:text-blue-800">
+              Use {'{project_root}'}
+            </button>
+          </div>
+        )}
       </div>
 
       <div>
@@ -193,6 +613,474 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
         </label>
       </div>
 
+      <div>
+        <label className="flex items-center space-x-2">
+          <input
+            type="checkbox"
+            checked={pinExecutable}
+            onChange={(e) => setPinExecutable(e.target.checked)}
+            className="rounded border-gray-300 text-blue-600 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-200 focus:ring-opacity-50"
+          />
+          <span className="text-sm font-medium">
+            Pin executable (run the program by its absolute path, independent of PATH)
+          </span>
+        </label>
+        {editingCommand?.pinned_executable && (
+          <p className="text-xs text-gray-500 mt-1">Pinned to {editingCommand.pinned_executable}</p>
+        )}
+      </div>
+
+      <div>
+        <label className="flex items-center space-x-2">
+          <input
+            type="checkbox"
+            checked={noNetwork}
+            onChange={(e) => setNoNetwork(e.target.checked)}
+            className="rounded border-gray-300 text-blue-600 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-200 focus:ring-opacity-50"
+          />
+          <span className="text-sm font-medium">No network (block network access while the command runs)</span>
+        </label>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Impact</label>
+        <select
+          value={impact}
+          onChange={(e) => setImpact(e.target.value as Impact)}
+          className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="safe">Safe (runs without confirmation)</option>
+          <option value="caution">Caution (confirm with a click)</option>
+          <option value="destructive">Destructive (type the name to confirm)</option>
+        </select>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Queue Priority (higher runs first when executions are queued)</label>
+        <input
+          type="number"
+          value={priority}
+          onChange={(e) => setPriority(parseInt(e.target.value, 10) || 0)}
+          className="w-32 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Rate Limit (leave empty for none)</label>
+        <div className="flex items-center gap-2">
+          <input
+            type="number"
+            min={0}
+            value={rateLimitRuns}
+            onChange={(e) => setRateLimitRuns(e.target.value)}
+            className="w-24 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <span className="text-sm">runs per</span>
+          <select
+            value={rateLimitPer}
+            onChange={(e) => setRateLimitPer(e.target.value as RateLimit['per'])}
+            className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          >
+            <option value="minute">minute</option>
+            <option value="hour">hour</option>
+          </select>
+        </div>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Chat Notifications</label>
+        <div className="space-y-2">
+          <select
+            value={notifyOn}
+            onChange={(e) => setNotifyOn(e.target.value as '' | CommandNotifications['on'])}
+            className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          >
+            <option value="">Never</option>
+            <option value="failure">When it fails</option>
+            <option value="always">After every run</option>
+          </select>
+          {notifyOn && (
+            <>
+              <input
+                type="url"
+                value={slackUrl}
+                onChange={(e) => setSlackUrl(e.target.value)}
+                placeholder="Slack webhook URL"
+                className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+              <input
+                type="url"
+                value={discordUrl}
+                onChange={(e) => setDiscordUrl(e.target.value)}
+                placeholder="Discord webhook URL"
+                className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </>
+          )}
+        </div>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Mutual Exclusion Group</label>
+        <input
+          type="text"
+          value={mutexGroup}
+          onChange={(e) => setMutexGroup(e.target.value)}
+          placeholder="Commands in the same group never run at the same time, e.g. terraform"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Profiles</label>
+        <input
+          type="text"
+          value={profiles}
+          onChange={(e) => setProfiles(e.target.value)}
+          placeholder="Comma-separated profiles the command must run with, e.g. staging, prod"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Forbidden Profiles</label>
+        <input
+          type="text"
+          value={forbiddenProfiles}
+          onChange={(e) => setForbiddenProfiles(e.target.value)}
+          placeholder="Profiles the command must not run with, e.g. prod; overriding takes typing its name"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Collect Artifacts</label>
+        <input
+          type="text"
+          value={collectArtifacts}
+          onChange={(e) => setCollectArtifacts(e.target.value)}
+          placeholder="Comma-separated globs relative to the working directory, e.g. dist/*.zip, reports/**/*.xml"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Standard Input</label>
+        <textarea
+          value={stdinTemplate}
+          onChange={(e) => setStdinTemplate(e.target.value)}
+          placeholder={'Piped to the command when it runs, e.g. SELECT count(*) FROM {table};'}
+          rows={3}
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 font-mono text-sm"
+        />
+        {stdinTemplate && (
+          <label className="flex items-center space-x-2 mt-1">
+            <input
+              type="checkbox"
+              checked={recordStdin}
+              onChange={(e) => setRecordStdin(e.target.checked)}
+            />
+            <span className="text-sm">Record the input in history (secrets masked)</span>
+          </label>
+        )}
+      </div>
+
+      <label className="flex items-center space-x-2">
+        <input
+          type="checkbox"
+          checked={captureTimeline}
+          onChange={(e) => setCaptureTimeline(e.target.checked)}
+        />
+        <span className="text-sm">Capture a timeline of stdout and stderr in the order they were written</span>
+      </label>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Phase Marker</label>
+        <input
+          type="text"
+          value={phaseMarker}
+          onChange={(e) => setPhaseMarker(e.target.value)}
+          placeholder="^==> (.*)"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 font-mono text-sm"
+        />
+        <p className="text-xs text-gray-500 mt-1">
+          Lines matching this regex start a phase named by its first group, so runs show how long each phase took
+        </p>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Open Output</label>
+        <div className="flex items-center gap-2">
+          <select
+            value={openOutput}
+            onChange={(e) => setOpenOutput(e.target.value as '' | 'default' | 'program')}
+            className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          >
+            <option value="">Don't open</option>
+            <option value="default">With the default application</option>
+            <option value="program">With a program</option>
+          </select>
+          {openOutput === 'program' && (
+            <input
+              type="text"
+              value={openOutputProgram}
+              onChange={(e) => setOpenOutputProgram(e.target.value)}
+              placeholder="e.g. firefox"
+              className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          )}
+          {openOutput && (
+            <input
+              type="text"
+              value={openOutputExtension}
+              onChange={(e) => setOpenOutputExtension(e.target.value)}
+              placeholder="Extension, e.g. html"
+              className="w-32 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          )}
+        </div>
+        {openOutput && (
+          <label className="flex items-center space-x-2 mt-1">
+            <input
+              type="checkbox"
+              checked={openOutputBase64}
+              onChange={(e) => setOpenOutputBase64(e.target.checked)}
+            />
+            <span className="text-sm">The output is base64, e.g. an image; decode it first</span>
+          </label>
+        )}
+        <p className="text-xs text-gray-500 mt-1">After a successful run the output is written to a file with this extension and opened.</p>
+      </div>
+
+      <div>
+        <div className="flex justify-between items-center mb-2">
+          <label className="text-sm font-medium">Problem Matchers</label>
+          <select
+            value=""
+            onChange={(e) => handleAddProblemMatcher(e.target.value === 'custom' ? '' : e.target.value)}
+            className="px-3 py-1 text-sm border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+          >
+            <option value="" disabled>Add Matcher</option>
+            <option value="$tsc">TypeScript ($tsc)</option>
+            <option value="$gcc">GCC / Clang ($gcc)</option>
+            <option value="$go">Go ($go)</option>
+            <option value="custom">Custom</option>
+          </select>
+        </div>
+        {problemMatchers.map((matcher, index) => (
+          <div key={index} className="flex gap-2 mb-2">
+            <input
+              type="text"
+              value={matcher.pattern}
+              onChange={(e) => handleUpdateProblemMatcher(index, { pattern: e.target.value })}
+              placeholder="Regex, e.g. ^(.*):(\d+):(\d+): (.*)$"
+              className="flex-1 px-3 py-2 border border-gray-300 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={0}
+              value={matcher.file ?? ''}
+              onChange={(e) => handleUpdateProblemMatcher(index, { file: groupIndex(e.target.value) ?? 1 })}
+              title="Group of the file"
+              placeholder="File"
+              className="w-16 px-2 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={0}
+              value={matcher.line ?? ''}
+              onChange={(e) => handleUpdateProblemMatcher(index, { line: groupIndex(e.target.value) })}
+              title="Group of the line"
+              placeholder="Line"
+              className="w-16 px-2 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={0}
+              value={matcher.column ?? ''}
+              onChange={(e) => handleUpdateProblemMatcher(index, { column: groupIndex(e.target.value) })}
+              title="Group of the column"
+              placeholder="Col"
+              className="w-16 px-2 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={0}
+              value={matcher.severity ?? ''}
+              onChange={(e) => handleUpdateProblemMatcher(index, { severity: groupIndex(e.target.value) })}
+              title="Group of the severity"
+              placeholder="Sev"
+              className="w-16 px-2 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={0}
+              value={matcher.message ?? ''}
+              onChange={(e) => handleUpdateProblemMatcher(index, { message: groupIndex(e.target.value) ?? 1 })}
+              title="Group of the message"
+              placeholder="Msg"
+              className="w-16 px-2 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <button
+              type="button"
+              onClick={() => setProblemMatchers(problemMatchers.filter((_, i) => i !== index))}
+              className="px-3 py-2 bg-red-500 text-white rounded hover:bg-red-600"
+            >
+              Remove
+            </button>
+          </div>
+        ))}
+        <p className="text-xs text-gray-500 mt-1">Each line of output matching a regex becomes a problem; the numbers are its capture groups for the file, line, column, severity and message.</p>
+      </div>
+
+      <div>
+        <label className="flex items-center space-x-2">
+          <input
+            type="checkbox"
+            checked={scratchDir}
+            onChange={(e) => setScratchDir(e.target.checked)}
+            className="rounded border-gray-300 text-blue-600 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-200 focus:ring-opacity-50"
+          />
+          <span className="text-sm font-medium">
+            Scratch directory (a fresh temporary directory per run as {'{run_tmp}'}, removed afterwards)
+          </span>
+        </label>
+        {scratchDir && (
+          <label className="flex items-center space-x-2 mt-1 ml-6">
+            <input
+              type="checkbox"
+              checked={keepScratchOnFailure}
+              onChange={(e) => setKeepScratchOnFailure(e.target.checked)}
+            />
+            <span className="text-sm">Keep it when the run fails</span>
+          </label>
+        )}
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Maintainer Note</label>
+        <input
+          type="text"
+          value={maintainerNote}
+          onChange={(e) => setMaintainerNote(e.target.value)}
+          placeholder="e.g. Owned by the platform team, ask in #infra before changing"
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Required Tool Versions</label>
+        <textarea
+          value={toolChecks}
+          onChange={(e) => setToolChecks(e.target.value)}
+          placeholder={'One per line, e.g. terraform >= 1.5'}
+          rows={2}
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 font-mono text-sm"
+        />
+        <label className="flex items-center space-x-2 mt-1">
+          <input
+            type="checkbox"
+            checked={blockOnToolMismatch}
+            onChange={(e) => setBlockOnToolMismatch(e.target.checked)}
+          />
+          <span className="text-sm">Refuse to run on a version mismatch (otherwise only warn)</span>
+        </label>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Platforms (none selected = all)</label>
+        <div className="flex gap-4">
+          {(['macos', 'linux', 'windows'] as Platform[]).map(platform => (
+            <label key={platform} className="flex items-center space-x-2">
+              <input
+                type="checkbox"
+                checked={platforms.includes(platform)}
+                onChange={() => handleTogglePlatform(platform)}
+              />
+              <span className="text-sm">{platform}</span>
+            </label>
+          ))}
+        </div>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Platform Variants (leave empty to use the command above)</label>
+        {(['macos', 'linux', 'windows'] as Platform[]).map(platform => (
+          <div key={platform} className="flex gap-2 mb-2 items-center">
+            <span className="w-20 text-sm">{platform}</span>
+            <input
+              type="text"
+              value={overrideFor(platform)?.command || ''}
+              onChange={(e) => handleUpdateOverride(platform, 'command', e.target.value)}
+              placeholder="Command"
+              className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="text"
+              value={overrideFor(platform)?.args?.join(' ') || ''}
+              onChange={(e) => handleUpdateOverride(platform, 'args', e.target.value)}
+              placeholder="Arguments (default: same as above)"
+              className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </div>
+        ))}
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Shell</label>
+        <select
+          value={shell}
+          onChange={(e) => setShell(e.target.value as ShellKind)}
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="system">System default (zsh / cmd)</option>
+          <option value="powershell">PowerShell</option>
+        </select>
+        {shell === 'powershell' && (
+          <label className="flex items-center space-x-2 mt-2">
+            <input
+              type="checkbox"
+              checked={loadShellProfile}
+              onChange={(e) => setLoadShellProfile(e.target.checked)}
+              className="rounded border-gray-300 text-blue-600 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-200 focus:ring-opacity-50"
+            />
+            <span className="text-sm font-medium">Load PowerShell profile</span>
+          </label>
+        )}
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Run By Default</label>
+        <select
+          value={executionMode}
+          onChange={(e) => setExecutionMode(e.target.value as ExecutionMode)}
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="shell">In the shell</option>
+          <option value="direct">Directly, without a shell</option>
+          <option value="pty">Attached to a pseudo-terminal (colors, progress bars)</option>
+          <option value="terminal">In a terminal window (output not captured)</option>
+        </select>
+      </div>
+
+      <div>
+        <label className="block text-sm font-medium mb-1">Output Encoding</label>
+        <select
+          value={outputEncoding}
+          onChange={(e) => setOutputEncoding(e.target.value as OutputEncoding)}
+          className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="auto">Auto</option>
+          <option value="utf8">UTF-8</option>
+          <option value="shift_jis">Shift_JIS (CP932)</option>
+          <option value="euc_jp">EUC-JP</option>
+          <option value="gbk">GBK</option>
+          <option value="big5">Big5</option>
+          <option value="euc_kr">EUC-KR</option>
+          <option value="windows1252">Windows-1252</option>
+        </select>
+      </div>
+
       <div>
         <div className="flex justify-between items-center mb-2">
           <label className="text-sm font-medium">Environment Variables</label>
@@ -215,12 +1103,20 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
               className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
             />
             <input
-              type="text"
+              type={envVar.secret ? 'password' : 'text'}
               value={envVar.value}
               onChange={(e) => handleUpdateEnvVar(index, 'value', e.target.value)}
               placeholder="Value"
               className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
             />
+            <label className="flex items-center gap-1 text-sm">
+              <input
+                type="checkbox"
+                checked={envVar.secret ?? false}
+                onChange={() => handleToggleEnvVarSecret(index)}
+              />
+              Secret
+            </label>
             <button
               type="button"
               onClick={() => handleRemoveEnvVar(index)}
@@ -232,6 +1128,41 @@ export function CommandForm({ editingCommand, onSave, onCancel }: CommandFormPro
         ))}
       </div>
 
+      {aliasOffer && (
+        <div className="p-3 bg-yellow-50 border border-yellow-200 rounded-md text-sm space-y-2">
+          <p>
+            <span className="font-mono">{aliasOffer.alias.name}</span> is a shell alias for{' '}
+            <span className="font-mono">{aliasOffer.alias.expansion}</span> (from{' '}
+            <span className="font-mono">{aliasOffer.alias.source}</span>) and won't be found when the command runs.
+          </p>
+          {aliasOffer.error && <p className="text-red-600">{aliasOffer.error}</p>}
+          <div className="flex gap-2">
+            {aliasOffer.command && (
+              <button
+                type="button"
+                onClick={() => {
+                  setCommand(aliasOffer.command!);
+                  showArgs(aliasOffer.args);
+                }}
+                className="px-3 py-1 bg-blue-500 text-white rounded-md hover:bg-blue-600"
+              >
+                Expand
+              </button>
+            )}
+            <button
+              type="button"
+              onClick={() => {
+                setKeptAlias(aliasOffer.alias.name);
+                setAliasOffer(null);
+              }}
+              className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300"
+            >
+              Keep as is
+            </button>
+          </div>
+        </div>
+      )}
+
       <div className="flex gap-2 pt-4">
         <button
           type="submit"
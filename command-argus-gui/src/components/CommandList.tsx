@@ -1,28 +1,80 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { ask } from '@tauri-apps/plugin-dialog';
-import { Command, ExecutionResult } from '../types';
+import { listen } from '@tauri-apps/api/event';
+import { ask, save } from '@tauri-apps/plugin-dialog';
+import { revealItemInDir } from '@tauri-apps/plugin-opener';
+import { Command, CommandExplanation, ExecutionResult, LintSuggestion, OutputChunk, OutputLink, OutputStream, QueuedExecution, ReplayOverrides, SavedSearch, ShareTarget } from '../types';
 import { ParameterInputModal } from './ParameterInputModal';
+import { HighlightedOutput } from './HighlightedOutput';
+import { EnvironmentPreviewPanel } from './EnvironmentPreviewPanel';
+import { ReplayPanel } from './ReplayPanel';
+import { LiveTail } from './LiveTail';
+import { PhaseBar } from './PhaseBar';
+import { ParameterizePanel } from './ParameterizePanel';
+import { useStoreUpdates } from '../storeUpdates';
 
 interface CommandListProps {
   onEdit: (command: Command) => void;
   refreshTrigger?: number;
+  // Saved search whose virtual folder is shown instead of the whole library
+  savedSearch?: SavedSearch | null;
+  // Environment profile commands are run with
+  profile?: string | null;
+  // Command to run by name or id, asked for when the app was launched
+  runRequest?: { command: string; seq: number } | null;
 }
 
-export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
+export function CommandList({ onEdit, refreshTrigger, savedSearch, profile, runRequest }: CommandListProps) {
   const [commands, setCommands] = useState<Command[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [searchQuery, setSearchQuery] = useState('');
+  const [sourceFilter, setSourceFilter] = useState('');
+  const [healthFilter, setHealthFilter] = useState('');
+  // Shown under the search box, so a half-typed query doesn't replace the list
+  const [queryError, setQueryError] = useState<string | null>(null);
   const [executingCommands, setExecutingCommands] = useState<Set<string>>(new Set());
   const [executionResults, setExecutionResults] = useState<Map<string, ExecutionResult>>(new Map());
   const [parameterModalCommand, setParameterModalCommand] = useState<Command | null>(null);
+  const [queued, setQueued] = useState<QueuedExecution[]>([]);
+  // Commands whose next runs get the clipboard's text as their standard input
+  const [clipboardStdin, setClipboardStdin] = useState<Set<string>>(new Set());
+  // Commands whose output is put on the clipboard after a successful run
+  const [copyOutput, setCopyOutput] = useState<Set<string>>(new Set());
+  const [shareTargets, setShareTargets] = useState<ShareTarget[]>([]);
+  // URL each shared execution was uploaded to
+  const [sharedUrls, setSharedUrls] = useState<Map<string, string>>(new Map());
+  // Lint suggestions of the commands they are shown for
+  const [lintResults, setLintResults] = useState<Map<string, LintSuggestion[]>>(new Map());
+  // Breakdowns of the commands they are shown for
+  const [explanations, setExplanations] = useState<Map<string, CommandExplanation>>(new Map());
+  // The command whose environment is shown
+  const [envPreview, setEnvPreview] = useState<string | null>(null);
+  // The command whose past runs can be replayed
+  const [replaying, setReplaying] = useState<string | null>(null);
+  // Running commands whose output is being watched
+  const [tailing, setTailing] = useState<Set<string>>(new Set());
+  // The command whose inline values are being turned into parameters
+  const [parameterizing, setParameterizing] = useState<string | null>(null);
+
+  const toggleRunOption = (setOption: typeof setCopyOutput, commandId: string) => {
+    setOption(prev => {
+      const next = new Set(prev);
+      if (!next.delete(commandId)) next.add(commandId);
+      return next;
+    });
+  };
 
   const loadCommands = async () => {
     try {
       setLoading(true);
       setError(null);
-      const result = await invoke<Command[]>('list_commands');
+      const result = savedSearch
+        ? await invoke<Command[]>('run_saved_search', { id: savedSearch.id })
+        : await invoke<Command[]>('list_commands', {
+            source: sourceFilter || null,
+            health: healthFilter || null
+          });
       setCommands(result);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to load commands');
@@ -33,7 +85,43 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
 
   useEffect(() => {
     loadCommands();
-  }, [refreshTrigger]);
+  }, [refreshTrigger, savedSearch]);
+
+  useEffect(() => {
+    invoke<ShareTarget[]>('get_share_targets').then(setShareTargets).catch(() => setShareTargets([]));
+  }, []);
+
+  // Edits from other windows: changed commands are replaced in place, anything else
+  // reruns the current listing
+  useStoreUpdates(['command', 'commands'], delta => {
+    if (delta.entity === 'command' && commands.some(c => c.id === delta.id)) {
+      setCommands(prev => delta.command
+        ? prev.map(c => (c.id === delta.id ? delta.command! : c))
+        : prev.filter(c => c.id !== delta.id));
+    } else {
+      handleSearch(searchQuery);
+    }
+  });
+  useStoreUpdates(['setting'], delta => {
+    if (delta.entity === 'setting' && delta.name === 'share_targets') setShareTargets(delta.value as ShareTarget[]);
+  });
+
+  // Executions waiting for a free slot, pushed by the backend whenever the queue changes
+  useEffect(() => {
+    invoke<QueuedExecution[]>('list_queued').then(setQueued);
+    const unlisten = listen<QueuedExecution[]>('execution-queue', event => setQueued(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const handleCancelQueued = async (id: string) => {
+    try {
+      await invoke('cancel_queued', { id });
+    } catch (err) {
+      alert(`Failed to cancel: ${err}`);
+    }
+  };
 
   const handleDelete = async (id: string) => {
     const confirmed = await ask('Are you sure you want to delete this command?', {
@@ -53,7 +141,65 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
     }
   };
 
+  const handleRepin = async (id: string) => {
+    try {
+      await invoke('repin_executable', { id });
+      await loadCommands();
+    } catch (err) {
+      alert(`Failed to re-resolve executable: ${err}`);
+    }
+  };
+
+  const showLint = async (id: string) => {
+    try {
+      const suggestions = await invoke<LintSuggestion[]>('lint_command', { id });
+      setLintResults(prev => new Map(prev).set(id, suggestions));
+    } catch (err) {
+      alert(`Failed to lint command: ${err}`);
+    }
+  };
+
+  const handleLint = (id: string) => {
+    if (lintResults.has(id)) {
+      setLintResults(prev => {
+        const next = new Map(prev);
+        next.delete(id);
+        return next;
+      });
+    } else {
+      showLint(id);
+    }
+  };
+
+  const handleExplain = async (id: string) => {
+    if (explanations.has(id)) {
+      setExplanations(prev => {
+        const next = new Map(prev);
+        next.delete(id);
+        return next;
+      });
+      return;
+    }
+    try {
+      const explanation = await invoke<CommandExplanation>('explain_command', { id });
+      setExplanations(prev => new Map(prev).set(id, explanation));
+    } catch (err) {
+      alert(`Failed to explain command: ${err}`);
+    }
+  };
+
+  const handleLintFix = async (id: string, index: number) => {
+    try {
+      await invoke('apply_lint_fix', { id, index });
+      await loadCommands();
+      await showLint(id);
+    } catch (err) {
+      alert(`Failed to apply the fix: ${err}`);
+    }
+  };
+
   const handleSearch = async (query: string) => {
+    setQueryError(null);
     if (!query.trim()) {
       await loadCommands();
       return;
@@ -61,12 +207,15 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
 
     try {
       setLoading(true);
-      const result = await invoke<Command[]>('search_commands_by_name', {
-        query: query
+      // Inside a saved search, the typed query narrows down its folder
+      const result = await invoke<Command[]>('search_commands_by_query', {
+        query: savedSearch ? `${savedSearch.query} ${query}` : query,
+        source: sourceFilter || null,
+        health: healthFilter || null
       });
       setCommands(result);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Search failed');
+      setQueryError(typeof err === 'string' ? err : 'Search failed');
     } finally {
       setLoading(false);
     }
@@ -78,17 +227,41 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
     }, 300); // 300ms debounce
 
     return () => clearTimeout(timeoutId);
-  }, [searchQuery]);
+  }, [searchQuery, sourceFilter, healthFilter]);
+
+  // Looked up in the whole library, whatever the list is filtered to
+  useEffect(() => {
+    if (!runRequest) return;
+    invoke<Command[]>('list_commands', { source: null, health: null }).then(all => {
+      const command = all.find(c => c.id === runRequest.command)
+        ?? all.find(c => c.name === runRequest.command);
+      if (command) {
+        handleExecute(command);
+      } else {
+        alert(`No command named "${runRequest.command}" to run`);
+      }
+    });
+  }, [runRequest]);
 
-  const handleExecute = async (command: Command, useShell: boolean = true) => {
+  // Without useShell the command runs the way it is set to by default
+  const handleExecute = async (command: Command, useShell?: boolean) => {
     // Check if command has parameters that need input
-    const fullCommand = `${command.command} ${command.args.join(' ')}`;
+    // {{var:NAME}} references are template variables, filled in by the backend
+    const withoutVariables = (text: string) => text.replace(/\{\{var:[^}]*\}\}/g, '');
+    const fullCommand = withoutVariables(`${command.command} ${command.args.join(' ')}`);
     const placeholderRegex = /\$?\{([^}]+)\}/g;
     const matches = [...fullCommand.matchAll(placeholderRegex)];
+    // In the working directory ${NAME} is an environment variable, only {name} is a placeholder
+    const directoryMatches = [...withoutVariables(command.working_directory || '').matchAll(placeholderRegex)]
+      .filter(match => !match[0].startsWith('$'));
+    matches.push(...directoryMatches);
+    // Only {identifier} is a placeholder in the stdin template, which may well contain JSON
+    matches.push(...withoutVariables(command.stdin_template || '').matchAll(/\{([A-Za-z_][A-Za-z0-9_]*)\}/g));
+    // {project_root} and {run_tmp} are built in and filled in by the backend
+    const detectedPlaceholders = [...new Set(matches.map(match => match[1]))].filter(name => name !== 'project_root' && name !== 'run_tmp');
     
-    if (matches.length > 0) {
+    if (detectedPlaceholders.length > 0) {
       // Create parameter objects from detected placeholders if command.parameters is empty or incomplete
-      const detectedPlaceholders = matches.map(match => match[1]);
       const existingParamNames = command.parameters.map(p => p.name);
       const missingPlaceholders = detectedPlaceholders.filter(name => !existingParamNames.includes(name));
       
@@ -115,22 +288,85 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
     await executeCommand(command.id, {}, useShell);
   };
   
-  const executeCommand = async (commandId: string, parameters: Record<string, string>, useShell: boolean = true) => {
-    setExecutingCommands(prev => new Set(prev).add(commandId));
-    
-    try {
+  // Ask for the confirmation the command's impact level requires. Resolves to the value
+  // sent to the backend, or undefined if the user backed out.
+  const confirmImpact = async (command: Command | undefined): Promise<string | null | undefined> => {
+    switch (command?.impact) {
+      case 'caution': {
+        const confirmed = await ask(`Run "${command.name}"?`, { title: 'Confirm', kind: 'warning' });
+        return confirmed ? 'confirmed' : undefined;
+      }
+      case 'destructive': {
+        const typed = window.prompt(`"${command.name}" is destructive. Type its name to run it:`);
+        return typed === null ? undefined : typed;
+      }
+      default:
+        return null;
+    }
+  };
+
+  // A profile the command forbids takes typing the command's name. Resolves to the
+  // override sent to the backend, or undefined if the user backed out.
+  const confirmProfileOverride = (command: Command | undefined): string | null | undefined => {
+    if (!command || !profile || !(command.forbidden_profiles || []).includes(profile)) {
+      return null;
+    }
+    const typed = window.prompt(
+      `"${command.name}" must not run with the profile "${profile}". Type its name to run it anyway:`
+    );
+    return typed === null ? undefined : typed;
+  };
+
+  const executeCommand = (commandId: string, parameters: Record<string, string>, useShell?: boolean) =>
+    runCommand(commandId, (confirmation, profileOverride) => {
       const hasParameters = Object.keys(parameters).length > 0;
-      const result = await invoke<ExecutionResult>(
+      return invoke<ExecutionResult>(
         hasParameters ? 'execute_command_with_parameters' : 'execute_command',
         hasParameters ? {
           id: commandId,
           parameters,
-          useShell
+          useShell,
+          confirmation,
+          profile,
+          profileOverride,
+          stdinFromClipboard: clipboardStdin.has(commandId),
+          copyOutputToClipboard: copyOutput.has(commandId)
         } : {
           id: commandId,
-          useShell
+          useShell,
+          confirmation,
+          profile,
+          profileOverride,
+          stdinFromClipboard: clipboardStdin.has(commandId),
+          copyOutputToClipboard: copyOutput.has(commandId)
         }
       );
+    });
+
+  const replayCommand = (commandId: string, historyId: string, overrides: ReplayOverrides) =>
+    runCommand(commandId, (confirmation, profileOverride) =>
+      invoke<ExecutionResult>('replay_with_changes', { historyId, overrides, confirmation, profile, profileOverride })
+    );
+
+  // Asks for what running the command takes, runs it and shows the result
+  const runCommand = async (
+    commandId: string,
+    run: (confirmation: string | null, profileOverride: string | null) => Promise<ExecutionResult>
+  ) => {
+    const command = commands.find(c => c.id === commandId);
+    const confirmation = await confirmImpact(command);
+    if (confirmation === undefined) {
+      return;
+    }
+    const profileOverride = confirmProfileOverride(command);
+    if (profileOverride === undefined) {
+      return;
+    }
+
+    setExecutingCommands(prev => new Set(prev).add(commandId));
+    
+    try {
+      const result = await run(confirmation, profileOverride);
       
       setExecutionResults(prev => new Map(prev).set(commandId, result));
       
@@ -140,8 +376,13 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
       const errorResult: ExecutionResult = {
         stdout: '',
         stderr: err instanceof Error ? err.message : 'Command execution failed',
+        stdout_lines: 0,
+        stderr_lines: 0,
         exit_code: -1,
-        success: false
+        success: false,
+        warnings: [],
+        duration_ms: 0,
+        assertion_results: []
       };
       setExecutionResults(prev => new Map(prev).set(commandId, errorResult));
     } finally {
@@ -153,9 +394,63 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
     }
   };
   
+  const loadedLines = (text: string) => (text ? text.split('\n').length : 0);
+
+  // Fetch the next page of a long output from history and append it to the result
+  const loadMoreOutput = async (commandId: string, stream: OutputStream) => {
+    const result = executionResults.get(commandId);
+    if (!result?.execution_id) return;
+    try {
+      const chunk = await invoke<OutputChunk>('get_execution_output', {
+        id: result.execution_id,
+        stream,
+        offset: loadedLines(result[stream]),
+        limit: 500
+      });
+      setExecutionResults(prev => {
+        const current = prev.get(commandId);
+        if (!current) return prev;
+        const text = [current[stream], ...chunk.lines].join('\n');
+        return new Map(prev).set(commandId, { ...current, [stream]: text });
+      });
+    } catch (err) {
+      console.error('Failed to load output:', err);
+    }
+  };
+
+  // Write a whole stream to a file; the backend copies it from history, so large
+  // outputs never pass through here
+  const saveOutput = async (executionId: string, stream: OutputStream) => {
+    const path = await save({ defaultPath: `${stream}.txt` });
+    if (!path) return;
+    try {
+      await invoke('save_execution_output', { historyId: executionId, path, stream });
+    } catch (err) {
+      alert(`Failed to save the output: ${err}`);
+    }
+  };
+
+  const handleOpenLink = async (link: OutputLink) => {
+    try {
+      await invoke('open_link', { kind: link.kind, target: link.target });
+    } catch (err) {
+      alert(`Failed to open ${link.text}: ${err}`);
+    }
+  };
+
+  const handleShare = async (executionId: string, target: string) => {
+    try {
+      const url = await invoke<string>('share_execution', { id: executionId, target });
+      setSharedUrls(prev => new Map(prev).set(executionId, url));
+      await navigator.clipboard.writeText(url);
+    } catch (err) {
+      alert(`Failed to share to ${target}: ${err}`);
+    }
+  };
+
   const handleParameterSubmit = async (values: Record<string, string>) => {
     if (parameterModalCommand) {
-      await executeCommand(parameterModalCommand.id, values, true);
+      await executeCommand(parameterModalCommand.id, values);
       setParameterModalCommand(null);
     }
   };
@@ -181,7 +476,7 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
       <div className="flex gap-2">
         <input
           type="text"
-          placeholder="Search commands..."
+          placeholder="Search, e.g. tag:deploy used:>10 updated:<30d name:~build"
           value={searchQuery}
           onChange={(e) => setSearchQuery(e.target.value)}
           onKeyDown={(e) => {
@@ -192,6 +487,27 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
           }}
           className="flex-1 px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
         />
+        <select
+          value={sourceFilter}
+          onChange={(e) => setSourceFilter(e.target.value)}
+          className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="">All sources</option>
+          <option value="manual">Manual</option>
+          <option value="imported">Imported</option>
+          <option value="synced">Synced</option>
+        </select>
+        <select
+          value={healthFilter}
+          onChange={(e) => setHealthFilter(e.target.value)}
+          className="px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="">Any health</option>
+          <option value="healthy">Healthy</option>
+          <option value="flaky">Flaky</option>
+          <option value="broken">Broken</option>
+          <option value="unknown">Never run</option>
+        </select>
         <button
           onClick={() => {
             setSearchQuery('');
@@ -202,6 +518,7 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
           Clear
         </button>
       </div>
+      {queryError && <div className="text-sm text-red-500">{queryError}</div>}
 
       {commands.length === 0 ? (
         <div className="text-center py-8 text-gray-500">
@@ -215,27 +532,144 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
               className="border border-gray-200 rounded-lg p-4 hover:shadow-md transition-shadow overflow-hidden"
             >
               <div className="flex justify-between items-start mb-2">
-                <h3 className="text-lg font-semibold">{command.name}</h3>
+                <h3 className="text-lg font-semibold">
+                  {command.name}
+                  {command.read_only && (
+                    <span
+                      title={command.project_root}
+                      className="ml-2 px-2 py-0.5 bg-purple-100 text-purple-700 rounded-md text-xs font-normal"
+                    >
+                      project
+                    </span>
+                  )}
+                  {command.pin_warning && (
+                    <button
+                      onClick={() => handleRepin(command.id)}
+                      title={command.pin_warning}
+                      className="ml-2 px-2 py-0.5 bg-red-100 text-red-700 rounded-md text-xs font-normal"
+                    >
+                      pinned binary missing – re-resolve
+                    </button>
+                  )}
+                  {command.sandbox.enabled && (
+                    <span
+                      title={command.capabilities.join(', ')}
+                      className="ml-2 px-2 py-0.5 bg-yellow-100 text-yellow-800 rounded-md text-xs font-normal"
+                    >
+                      sandboxed
+                    </span>
+                  )}
+                  {command.impact !== 'safe' && (
+                    <span
+                      className={`ml-2 px-2 py-0.5 rounded-md text-xs font-normal ${
+                        command.impact === 'destructive' ? 'bg-red-100 text-red-800' : 'bg-orange-100 text-orange-800'
+                      }`}
+                    >
+                      {command.impact}
+                    </span>
+                  )}
+                  {(command.health === 'flaky' || command.health === 'broken') && (
+                    <span
+                      title="Based on the latest runs"
+                      className={`ml-2 px-2 py-0.5 rounded-md text-xs font-normal ${
+                        command.health === 'broken' ? 'bg-red-100 text-red-800' : 'bg-yellow-100 text-yellow-800'
+                      }`}
+                    >
+                      {command.health}
+                    </span>
+                  )}
+                </h3>
                 <div className="flex gap-2">
                   <button
                     onClick={() => handleExecute(command)}
                     disabled={executingCommands.has(command.id)}
                     className="px-3 py-1 text-sm bg-green-500 text-white rounded hover:bg-green-600 disabled:bg-gray-400"
                   >
-                    {executingCommands.has(command.id) ? 'Running...' : 'Run'}
+                    {(() => {
+                      const entry = queued.find(q => q.command_id === command.id);
+                      if (entry) return entry.waiting_on ? `Queued (${entry.waiting_on})` : `Queued #${entry.position + 1}`;
+                      return executingCommands.has(command.id) ? 'Running...' : 'Run';
+                    })()}
                   </button>
+                  {executingCommands.has(command.id) && (
+                    <button
+                      onClick={() => toggleRunOption(setTailing, command.id)}
+                      className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                    >
+                      {tailing.has(command.id) ? 'Hide Output' : 'Watch Output'}
+                    </button>
+                  )}
+                  <label className="flex items-center space-x-1 text-xs text-gray-600" title="Pipe the clipboard's text to the command's standard input">
+                    <input
+                      type="checkbox"
+                      checked={clipboardStdin.has(command.id)}
+                      onChange={() => toggleRunOption(setClipboardStdin, command.id)}
+                    />
+                    <span>Clipboard as stdin</span>
+                  </label>
+                  <label className="flex items-center space-x-1 text-xs text-gray-600" title="Put the trimmed output on the clipboard after a successful run">
+                    <input
+                      type="checkbox"
+                      checked={copyOutput.has(command.id)}
+                      onChange={() => toggleRunOption(setCopyOutput, command.id)}
+                    />
+                    <span>Copy output</span>
+                  </label>
+                  {queued.filter(q => q.command_id === command.id).map(q => (
+                    <button
+                      key={q.id}
+                      onClick={() => handleCancelQueued(q.id)}
+                      className="px-3 py-1 text-sm bg-gray-500 text-white rounded hover:bg-gray-600"
+                    >
+                      Cancel
+                    </button>
+                  ))}
                   <button
-                    onClick={() => onEdit(command)}
-                    className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600"
+                    onClick={() => handleExplain(command.id)}
+                    className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
                   >
-                    Edit
+                    Explain
                   </button>
                   <button
-                    onClick={() => handleDelete(command.id)}
-                    className="px-3 py-1 text-sm bg-red-500 text-white rounded hover:bg-red-600"
+                    onClick={() => setEnvPreview(prev => (prev === command.id ? null : command.id))}
+                    className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
                   >
-                    Delete
+                    Env
                   </button>
+                  <button
+                    onClick={() => setReplaying(prev => (prev === command.id ? null : command.id))}
+                    className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                  >
+                    Replay…
+                  </button>
+                  <button
+                    onClick={() => handleLint(command.id)}
+                    className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                  >
+                    Lint
+                  </button>
+                  {!command.read_only && (
+                    <>
+                      <button
+                        onClick={() => setParameterizing(prev => (prev === command.id ? null : command.id))}
+                        className="px-3 py-1 text-sm bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                      >
+                        Parameterize
+                      </button>
+                      <button
+                        onClick={() => onEdit(command)}
+                        className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600"
+                      >
+                        Edit
+                      </button>
+                      <button
+                        onClick={() => handleDelete(command.id)}
+                        className="px-3 py-1 text-sm bg-red-500 text-white rounded hover:bg-red-600"
+                      >
+                        Delete
+                      </button>
+                    </>
+                  )}
                 </div>
               </div>
               
@@ -244,10 +678,105 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
                   <span className="break-all">{command.command} {command.args.join(' ')}</span>
                 </div>
                 
+                {explanations.has(command.id) && (() => {
+                  const explanation = explanations.get(command.id)!;
+                  return (
+                    <div className="p-2 bg-gray-50 border border-gray-200 rounded text-xs space-y-1">
+                      <ul>
+                        {explanation.parts.map((part, index) => (
+                          <li key={index} className={part.kind === 'program' ? '' : 'ml-4'}>
+                            <span className="font-mono">{part.text}</span>
+                            {part.meaning && <span className="text-gray-600"> — {part.meaning}</span>}
+                          </li>
+                        ))}
+                      </ul>
+                      {explanation.placeholders.length > 0 && (
+                        <ul>
+                          {explanation.placeholders.map(placeholder => (
+                            <li key={placeholder.name}>
+                              <span className="font-mono">
+                                {placeholder.name.startsWith('var:') ? `{{${placeholder.name}}}` : `{${placeholder.name}}`}
+                              </span>
+                              <span className="text-gray-600"> — {placeholder.role}</span>
+                            </li>
+                          ))}
+                        </ul>
+                      )}
+                      {explanation.working_directory && (
+                        <div className="text-gray-600">Runs in {explanation.working_directory}</div>
+                      )}
+                    </div>
+                  );
+                })()}
+                
+                {executingCommands.has(command.id) && tailing.has(command.id) && (
+                  <LiveTail commandId={command.id} />
+                )}
+                
+                {envPreview === command.id && (
+                  <EnvironmentPreviewPanel commandId={command.id} profile={profile} />
+                )}
+                
+                {replaying === command.id && (
+                  <ReplayPanel
+                    command={command}
+                    onReplay={(historyId, overrides) => replayCommand(command.id, historyId, overrides)}
+                  />
+                )}
+                
+                {parameterizing === command.id && (
+                  <ParameterizePanel
+                    command={command}
+                    onDone={() => {
+                      setParameterizing(null);
+                      loadCommands();
+                    }}
+                  />
+                )}
+                
+                {lintResults.has(command.id) && (
+                  <ul className="space-y-1 text-xs">
+                    {lintResults.get(command.id)!.length === 0 && <li className="text-green-700">No problems found</li>}
+                    {lintResults.get(command.id)!.map((suggestion, index) => (
+                      <li key={index} className="flex items-start gap-2">
+                        <span
+                          className={
+                            suggestion.severity === 'error'
+                              ? 'text-red-700'
+                              : suggestion.severity === 'warning'
+                                ? 'text-orange-700'
+                                : 'text-blue-700'
+                          }
+                        >
+                          {suggestion.severity}
+                        </span>
+                        <span className="flex-1 text-gray-700">{suggestion.message}</span>
+                        {suggestion.fix && !command.read_only && (
+                          <button onClick={() => handleLintFix(command.id, index)} className="text-blue-600 hover:underline">
+                            Fix
+                          </button>
+                        )}
+                      </li>
+                    ))}
+                  </ul>
+                )}
+                
                 {command.description && (
                   <p className="text-gray-600">{command.description}</p>
                 )}
                 
+                {(command.author || command.source.kind !== 'manual') && (
+                  <p className="text-xs text-gray-500">
+                    {command.author && <>by {command.author}</>}
+                    {command.source.kind !== 'manual' && (
+                      <> · {command.source.kind} from {command.source.from}</>
+                    )}
+                  </p>
+                )}
+                {command.maintainer_note && (
+                  <p className="text-xs text-gray-500 italic">{command.maintainer_note}</p>
+                )}
+                
                 {command.tags.length > 0 && (
                   <div className="flex gap-2">
                     {command.tags.map((tag) => (
@@ -281,21 +810,168 @@ export function CommandList({ onEdit, refreshTrigger }: CommandListProps) {
                       const result = executionResults.get(command.id)!;
                       return (
                         <div className={`text-xs space-y-2 ${result.success ? '' : 'text-red-600'}`}>
+                          {result.warnings.length > 0 && (
+                            <div className="bg-yellow-50 text-yellow-800 p-2 rounded">
+                              {result.warnings.map((warning, i) => (
+                                <div key={i}>⚠ {warning}</div>
+                              ))}
+                            </div>
+                          )}
+                          {result.phases && result.phases.length > 0 && (
+                            <div>
+                              <div className="font-semibold">Phases:</div>
+                              <PhaseBar phases={result.phases} />
+                            </div>
+                          )}
+                          {result.timeline && result.timeline.length > 0 && (
+                            <div>
+                              <div className="font-semibold">Timeline:</div>
+                              <div className="bg-gray-100 p-2 rounded font-mono whitespace-pre-wrap break-words">
+                                {result.timeline.map((event, i) => (
+                                  <div key={i} className={event.stream === 'stderr' ? 'text-red-700' : 'text-gray-800'}>
+                                    <span className="text-gray-400">{(event.at_ms / 1000).toFixed(3)}s </span>
+                                    {event.line}
+                                  </div>
+                                ))}
+                              </div>
+                            </div>
+                          )}
                           {result.stdout && (
                             <div>
                               <div className="font-semibold">Output:</div>
-                              <pre className="bg-gray-100 p-2 rounded whitespace-pre-wrap break-words">{result.stdout}</pre>
+                              <HighlightedOutput
+                                text={result.stdout}
+                                highlights={(result.highlights || []).filter(h => h.stream === 'stdout')}
+                                className="bg-gray-100 p-2 rounded whitespace-pre-wrap break-words"
+                              />
+                              {loadedLines(result.stdout) < result.stdout_lines && (
+                                <button onClick={() => loadMoreOutput(command.id, 'stdout')} className="text-blue-600 hover:underline">
+                                  Load more ({loadedLines(result.stdout)} of {result.stdout_lines} lines shown)
+                                </button>
+                              )}
+                              {result.execution_id && (
+                                <button onClick={() => saveOutput(result.execution_id!, 'stdout')} className="ml-2 text-blue-600 hover:underline">
+                                  Save to file…
+                                </button>
+                              )}
                             </div>
                           )}
                           {result.stderr && (
                             <div>
                               <div className="font-semibold">Error:</div>
-                              <pre className="bg-red-50 p-2 rounded whitespace-pre-wrap break-words">{result.stderr}</pre>
+                              <HighlightedOutput
+                                text={result.stderr}
+                                highlights={(result.highlights || []).filter(h => h.stream === 'stderr')}
+                                className="bg-red-50 p-2 rounded whitespace-pre-wrap break-words"
+                              />
+                              {loadedLines(result.stderr) < result.stderr_lines && (
+                                <button onClick={() => loadMoreOutput(command.id, 'stderr')} className="text-blue-600 hover:underline">
+                                  Load more ({loadedLines(result.stderr)} of {result.stderr_lines} lines shown)
+                                </button>
+                              )}
+                              {result.execution_id && (
+                                <button onClick={() => saveOutput(result.execution_id!, 'stderr')} className="ml-2 text-blue-600 hover:underline">
+                                  Save to file…
+                                </button>
+                              )}
+                            </div>
+                          )}
+                          {result.assertion_results.length > 0 && (
+                            <div>
+                              <div className="font-semibold">Assertions:</div>
+                              {result.assertion_results.map((assertion, i) => (
+                                <div key={i} className={assertion.passed ? 'text-green-700' : 'text-red-600'}>
+                                  {assertion.passed ? '✓' : '✗'} {assertion.assertion.kind}
+                                  {assertion.message && ` — ${assertion.message}`}
+                                </div>
+                              ))}
+                            </div>
+                          )}
+                          {result.problems && result.problems.length > 0 && (
+                            <div>
+                              <div className="font-semibold">Problems ({result.problems.length}):</div>
+                              {result.problems.map((problem, i) => {
+                                const location = [problem.file, problem.line, problem.column].filter(p => p !== undefined && p !== null).join(':');
+                                return (
+                                  <div key={i} className={problem.severity === 'error' ? 'text-red-600' : problem.severity === 'warning' ? 'text-amber-700' : 'text-gray-700'}>
+                                    {problem.path ? (
+                                      <button
+                                        onClick={() => handleOpenLink({ kind: 'path', target: problem.path!, text: location, stream: problem.stream, line: problem.output_line })}
+                                        className="font-mono text-blue-600 hover:underline break-all text-left"
+                                      >
+                                        {location}
+                                      </button>
+                                    ) : (
+                                      <span className="font-mono">{location}</span>
+                                    )}
+                                    {' '}{problem.message}
+                                  </div>
+                                );
+                              })}
+                            </div>
+                          )}
+                          {result.links && result.links.length > 0 && (
+                            <div>
+                              <div className="font-semibold">Links:</div>
+                              {result.links.map(link => (
+                                <div key={link.target}>
+                                  <button
+                                    onClick={() => handleOpenLink(link)}
+                                    title={`${link.stream}, line ${link.line}`}
+                                    className="font-mono text-blue-600 hover:underline break-all text-left"
+                                  >
+                                    {link.text}
+                                  </button>
+                                </div>
+                              ))}
+                            </div>
+                          )}
+                          {result.artifacts && result.artifacts.length > 0 && (
+                            <div>
+                              <div className="font-semibold">
+                                Artifacts:
+                                {result.artifacts_dir && (
+                                  <button
+                                    onClick={() => revealItemInDir(result.artifacts_dir!)}
+                                    className="ml-2 font-normal text-blue-600 hover:underline"
+                                  >
+                                    Show folder
+                                  </button>
+                                )}
+                              </div>
+                              {result.artifacts.map(artifact => (
+                                <div key={artifact.path} className="font-mono">
+                                  {artifact.path} <span className="text-gray-500">({artifact.bytes} bytes)</span>
+                                </div>
+                              ))}
                             </div>
                           )}
                           <div className="text-gray-500">
-                            Exit code: {result.exit_code} • Status: {result.success ? 'Success' : 'Failed'}
+                            Exit code: {result.exit_code} • Status: {result.success ? 'Success' : 'Failed'} • {result.duration_ms} ms
                           </div>
+                          {result.execution_id && shareTargets.length > 0 && (
+                            <div className="flex flex-wrap items-center gap-2">
+                              <span className="font-semibold">Share to:</span>
+                              {shareTargets.map(target => (
+                                <button
+                                  key={target.name}
+                                  onClick={() => handleShare(result.execution_id!, target.name)}
+                                  className="px-2 py-0.5 bg-gray-200 rounded hover:bg-gray-300"
+                                >
+                                  {target.name}
+                                </button>
+                              ))}
+                              {sharedUrls.has(result.execution_id) && (
+                                <button
+                                  onClick={() => handleOpenLink({ kind: 'url', target: sharedUrls.get(result.execution_id!)!, text: 'the shared run', stream: 'stdout', line: 0 })}
+                                  title="Copied to the clipboard"
+                                  className="font-mono text-blue-600 hover:underline break-all text-left"
+                                >
+                                  {sharedUrls.get(result.execution_id)}
+                                </button>
+                              )}
+                            </div>
+                          )}
                         </div>
                       );
                     })()}
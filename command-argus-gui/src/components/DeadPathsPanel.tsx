@@ -0,0 +1,76 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { DeadPath } from '../types';
+
+const describeKind = (entry: DeadPath) => {
+  switch (entry.kind) {
+    case 'working_directory':
+      return 'working directory';
+    case 'pinned_executable':
+      return 'pinned executable';
+    case 'parameter_default':
+      return `default of {${entry.parameter}}`;
+  }
+};
+
+// Paths in the library that no longer exist, e.g. after a project moved
+export function DeadPathsPanel() {
+  const [deadPaths, setDeadPaths] = useState<DeadPath[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const scan = async () => {
+    setError(null);
+    try {
+      setDeadPaths(await invoke<DeadPath[]>('scan_dead_paths'));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to scan');
+    }
+  };
+
+  useEffect(() => {
+    scan();
+  }, []);
+
+  const handleRepin = async (id: string) => {
+    try {
+      await invoke('repin_executable', { id });
+      await scan();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to re-pin');
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <div className="flex items-center justify-between">
+        <h3 className="font-semibold">Dead Paths</h3>
+        <button onClick={scan} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300 text-sm">
+          Rescan
+        </button>
+      </div>
+      {error && <p className="text-sm text-red-600">{error}</p>}
+      {deadPaths && deadPaths.length === 0 && <p className="text-sm text-gray-500">Every path in the library exists.</p>}
+      {deadPaths && deadPaths.length > 0 && (
+        <table className="w-full text-sm">
+          <tbody>
+            {deadPaths.map((entry, index) => (
+              <tr key={index} className="border-t border-gray-100">
+                <td className="py-1 pr-2">{entry.command_name}</td>
+                <td className="py-1 pr-2 text-gray-500">{describeKind(entry)}</td>
+                <td className="py-1 pr-2 font-mono text-xs break-all">{entry.path}</td>
+                <td className="py-1 text-xs text-gray-600">{entry.fix}</td>
+                <td className="py-1 text-right">
+                  {entry.kind === 'pinned_executable' && entry.replacement && (
+                    <button onClick={() => handleRepin(entry.command_id)} className="px-2 py-1 bg-blue-500 text-white rounded text-xs hover:bg-blue-600">
+                      Re-pin
+                    </button>
+                  )}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+    </div>
+  );
+}
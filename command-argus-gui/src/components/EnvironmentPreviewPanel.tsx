@@ -0,0 +1,77 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { EnvironmentPreview, EnvSource } from '../types';
+
+interface EnvironmentPreviewPanelProps {
+  commandId: string;
+  profile?: string | null;
+}
+
+const SOURCE_LABELS: Record<EnvSource, string> = {
+  inherited: 'inherited',
+  app: 'app',
+  context: 'run context',
+  command: 'command',
+  profile: 'profile',
+};
+
+const SOURCE_STYLES: Record<EnvSource, string> = {
+  inherited: 'text-gray-500',
+  app: 'text-gray-600',
+  context: 'text-gray-600',
+  command: 'text-blue-700',
+  profile: 'text-purple-700',
+};
+
+// The exact environment the command would get, with what the command and profile change on top
+export function EnvironmentPreviewPanel({ commandId, profile }: EnvironmentPreviewPanelProps) {
+  const [preview, setPreview] = useState<EnvironmentPreview | null>(null);
+  const [showInherited, setShowInherited] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setError(null);
+    invoke<EnvironmentPreview>('preview_environment', { id: commandId, profile: profile || null })
+      .then(setPreview)
+      .catch(err => setError(String(err)));
+  }, [commandId, profile]);
+
+  if (error) return <div className="text-xs text-red-600">{error}</div>;
+  if (!preview) return null;
+
+  return (
+    <div className="p-2 bg-gray-50 border border-gray-200 rounded text-xs space-y-1">
+      <table className="w-full font-mono">
+        <tbody>
+          {preview.overrides.map(entry => (
+            <tr key={entry.key} className="align-top">
+              <td className="pr-2 whitespace-nowrap">{entry.key}</td>
+              <td className="pr-2 break-all">
+                {entry.value}
+                {entry.replaces !== undefined && entry.replaces !== null && (
+                  <div className="text-gray-400 line-through">{entry.replaces}</div>
+                )}
+              </td>
+              <td className={`whitespace-nowrap font-sans ${SOURCE_STYLES[entry.source]}`}>{SOURCE_LABELS[entry.source]}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <button onClick={() => setShowInherited(prev => !prev)} className="text-blue-600 hover:underline">
+        {showInherited ? 'Hide' : 'Show'} {preview.inherited.length} inherited variables
+      </button>
+      {showInherited && (
+        <table className="w-full font-mono text-gray-500">
+          <tbody>
+            {preview.inherited.map(entry => (
+              <tr key={entry.key} className="align-top">
+                <td className="pr-2 whitespace-nowrap">{entry.key}</td>
+                <td className="break-all">{entry.value}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+    </div>
+  );
+}
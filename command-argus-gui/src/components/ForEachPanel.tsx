@@ -0,0 +1,192 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { Command, DirectoryOutcome, ForEachReport } from '../types';
+
+interface ForEachPanelProps {
+  // Environment profile the command is run with
+  profile?: string | null;
+}
+
+// name=value lines, as typed in the parameters field
+export function parseParameters(text: string): Record<string, string> {
+  return Object.fromEntries(
+    text
+      .split('\n')
+      .filter(line => line.includes('='))
+      .map(line => {
+        const index = line.indexOf('=');
+        return [line.slice(0, index).trim(), line.slice(index + 1)];
+      })
+  );
+}
+
+// Runs one command in every directory matching a glob, e.g. each npm package of a monorepo
+export function ForEachPanel({ profile }: ForEachPanelProps) {
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [commandId, setCommandId] = useState('');
+  const [pattern, setPattern] = useState('**/package.json');
+  const [root, setRoot] = useState('');
+  const [parallelism, setParallelism] = useState(4);
+  const [useShell, setUseShell] = useState(false);
+  const [parameters, setParameters] = useState('');
+  const [running, setRunning] = useState(false);
+  const [finished, setFinished] = useState<DirectoryOutcome[]>([]);
+  const [report, setReport] = useState<ForEachReport | null>(null);
+  const [expanded, setExpanded] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<Command[]>('list_commands', { allPlatforms: false }).then(setCommands);
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<DirectoryOutcome>('foreach-progress', ({ payload }) => {
+      setFinished(prev => [...prev, payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // The confirmation the command's impact level asks for, or undefined if the user backed out
+  const confirmImpact = async (command: Command): Promise<string | null | undefined> => {
+    switch (command.impact) {
+      case 'caution': {
+        const confirmed = await ask(`Run "${command.name}" in every matching directory?`, { title: 'Confirm', kind: 'warning' });
+        return confirmed ? 'confirmed' : undefined;
+      }
+      case 'destructive': {
+        const typed = window.prompt(`"${command.name}" is destructive. Type its name to run it in every matching directory:`);
+        return typed === null ? undefined : typed;
+      }
+      default:
+        return null;
+    }
+  };
+
+  const handleRun = async () => {
+    const command = commands.find(c => c.id === commandId);
+    if (!command) return;
+    const confirmation = await confirmImpact(command);
+    if (confirmation === undefined) return;
+
+    setRunning(true);
+    setFinished([]);
+    setReport(null);
+    setExpanded(null);
+    setError(null);
+    try {
+      const request = {
+        id: commandId,
+        pattern,
+        root: root.trim() || null,
+        parallelism,
+        use_shell: useShell,
+        parameters: parseParameters(parameters),
+      };
+      setReport(await invoke<ForEachReport>('run_in_directories', { request, confirmation, profile, window: getCurrentWindow().label }));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to run the command');
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const outcomes = report ? report.outcomes : finished;
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Run in Directories</h2>
+      <div className="grid grid-cols-2 gap-2 text-sm">
+        <select
+          value={commandId}
+          onChange={e => setCommandId(e.target.value)}
+          className="px-2 py-1 border border-gray-300 rounded-md"
+        >
+          <option value="">Choose a command...</option>
+          {commands.map(command => (
+            <option key={command.id} value={command.id}>
+              {command.name}
+            </option>
+          ))}
+        </select>
+        <input
+          type="text"
+          value={pattern}
+          onChange={e => setPattern(e.target.value)}
+          placeholder="Glob, e.g. packages/* or **/Cargo.toml"
+          className="px-3 py-1 border border-gray-300 rounded-md font-mono focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        <input
+          type="text"
+          value={root}
+          onChange={e => setRoot(e.target.value)}
+          placeholder="Folder to search (default: the opened project)"
+          className="px-3 py-1 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        <div className="flex items-center gap-3">
+          <label className="flex items-center gap-1 text-gray-700">
+            Parallel
+            <input
+              type="number"
+              min={1}
+              value={parallelism}
+              onChange={e => setParallelism(Math.max(1, Number(e.target.value)))}
+              className="w-16 px-2 py-1 border border-gray-300 rounded-md"
+            />
+          </label>
+          <label className="flex items-center gap-1 text-gray-700">
+            <input type="checkbox" checked={useShell} onChange={e => setUseShell(e.target.checked)} />
+            Run in shell
+          </label>
+        </div>
+        <textarea
+          value={parameters}
+          onChange={e => setParameters(e.target.value)}
+          rows={2}
+          placeholder="Parameters, one name=value per line"
+          className="col-span-2 px-3 py-1 border border-gray-300 rounded-md font-mono focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+      </div>
+      <button
+        onClick={handleRun}
+        disabled={running || !commandId || !pattern.trim()}
+        className="mt-2 px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50"
+      >
+        {running ? 'Running...' : 'Run'}
+      </button>
+      {error && <p className="mt-2 text-sm text-red-600">{error}</p>}
+      {outcomes.length > 0 && (
+        <ul className="mt-3 space-y-1 text-sm">
+          {outcomes.map(outcome => (
+            <li key={outcome.directory}>
+              <button
+                onClick={() => setExpanded(prev => (prev === outcome.directory ? null : outcome.directory))}
+                className="flex w-full gap-3 text-left"
+              >
+                <span className="flex-1 font-mono text-gray-800">{outcome.directory}</span>
+                <span className={outcome.success ? 'text-green-700' : 'text-red-700'}>
+                  {outcome.success ? 'passed' : outcome.error ? 'error' : `exit ${outcome.exit_code}`}
+                </span>
+                <span className="w-16 text-right text-gray-500">{`${(outcome.duration_ms / 1000).toFixed(2)}s`}</span>
+              </button>
+              {expanded === outcome.directory && (
+                <pre className="mt-1 p-2 bg-gray-50 border border-gray-200 rounded text-xs whitespace-pre-wrap">
+                  {outcome.error || `${outcome.stdout}${outcome.stderr}` || '(no output)'}
+                </pre>
+              )}
+            </li>
+          ))}
+        </ul>
+      )}
+      {report && (
+        <pre className="mt-3 p-2 bg-gray-50 border border-gray-200 rounded text-xs whitespace-pre-wrap">
+          {report.summary}
+        </pre>
+      )}
+    </div>
+  );
+}
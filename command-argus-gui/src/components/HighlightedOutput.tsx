@@ -0,0 +1,49 @@
+import { Highlight, Severity } from '../types';
+
+const SEVERITY_CLASSES: Record<Severity, string> = {
+  error: 'text-red-700 font-semibold',
+  warning: 'text-amber-700',
+  info: 'text-blue-700',
+  success: 'text-green-700'
+};
+
+interface HighlightedOutputProps {
+  text: string;
+  // Spans of this stream, with 1-based lines
+  highlights: Highlight[];
+  className: string;
+}
+
+// Captured output with the spans matched by the highlight rules colored in
+export function HighlightedOutput({ text, highlights, className }: HighlightedOutputProps) {
+  const lines = text.split('\n');
+  return (
+    <pre className={className}>
+      {lines.map((line, index) => {
+        const spans = highlights.filter(h => h.line === index + 1);
+        const parts = [];
+        let column = 0;
+        for (const span of spans) {
+          if (span.start > column) parts.push(line.slice(column, span.start));
+          parts.push(
+            <span
+              key={span.start}
+              className={SEVERITY_CLASSES[span.severity]}
+              style={span.color ? { color: span.color } : undefined}
+            >
+              {line.slice(span.start, span.end)}
+            </span>
+          );
+          column = span.end;
+        }
+        parts.push(line.slice(column));
+        return (
+          <span key={index}>
+            {parts}
+            {index < lines.length - 1 && '\n'}
+          </span>
+        );
+      })}
+    </pre>
+  );
+}
@@ -0,0 +1,118 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { PhaseBar } from './PhaseBar';
+import { ExecutionSummary } from '../types';
+
+// Past runs, searchable by command, note and tag, with notes and tags to find them again
+export function HistoryPanel() {
+  const [query, setQuery] = useState('');
+  const [runs, setRuns] = useState<ExecutionSummary[]>([]);
+  // The run whose note and tags are being edited
+  const [editing, setEditing] = useState<string | null>(null);
+  const [note, setNote] = useState('');
+  const [tags, setTags] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  const search = () => {
+    invoke<ExecutionSummary[]>('search_history', { query, limit: 50 })
+      .then(runs => {
+        setRuns(runs);
+        setError(null);
+      })
+      .catch(err => setError(String(err)));
+  };
+
+  useEffect(() => {
+    search();
+  }, [query]);
+
+  useStoreUpdates(['execution', 'history'], () => search());
+
+  const startEditing = (run: ExecutionSummary) => {
+    setEditing(run.id);
+    setNote(run.note ?? '');
+    setTags((run.tags ?? []).join(', '));
+  };
+
+  const handleSave = async (id: string) => {
+    try {
+      await invoke('annotate_execution', { id, note, tags: tags.split(',') });
+      setEditing(null);
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <h3 className="font-semibold">History</h3>
+      <input
+        value={query}
+        onChange={e => setQuery(e.target.value)}
+        placeholder='Search runs, e.g. deploy tag:prod "fixed prod"'
+        className="w-full px-3 py-1 border border-gray-300 rounded-md text-sm"
+      />
+      {error && <p className="text-sm text-red-600">{error}</p>}
+      {runs.length === 0 && !error && <p className="text-sm text-gray-500">No runs found.</p>}
+      <ul className="divide-y divide-gray-100 text-sm">
+        {runs.map(run => (
+          <li key={run.id} className="py-2">
+            <div className="flex items-center justify-between gap-2">
+              <div className="min-w-0">
+                <span className={run.success ? 'text-green-700' : 'text-red-700'}>{run.success ? '✓' : '✗'}</span>{' '}
+                <span className="font-medium">{run.command_name}</span>{' '}
+                <span className="text-xs text-gray-500">{new Date(run.started_at).toLocaleString()}</span>
+                <div className="font-mono text-xs text-gray-600 truncate">{run.command_line}</div>
+                {run.phases && run.phases.length > 0 && (
+                  <div className="text-xs mt-1">
+                    <PhaseBar phases={run.phases} />
+                  </div>
+                )}
+              </div>
+              <button onClick={() => startEditing(run)} className="px-2 py-1 bg-gray-200 rounded text-xs hover:bg-gray-300">
+                Annotate
+              </button>
+            </div>
+            {editing === run.id ? (
+              <div className="mt-1 space-y-1">
+                <textarea
+                  value={note}
+                  onChange={e => setNote(e.target.value)}
+                  placeholder="Note"
+                  rows={2}
+                  className="w-full px-2 py-1 border border-gray-300 rounded text-xs"
+                />
+                <input
+                  value={tags}
+                  onChange={e => setTags(e.target.value)}
+                  placeholder="Tags, separated by commas"
+                  className="w-full px-2 py-1 border border-gray-300 rounded text-xs"
+                />
+                <div className="flex gap-2">
+                  <button onClick={() => handleSave(run.id)} className="px-2 py-1 bg-blue-500 text-white rounded text-xs hover:bg-blue-600">
+                    Save
+                  </button>
+                  <button onClick={() => setEditing(null)} className="px-2 py-1 bg-gray-200 rounded text-xs hover:bg-gray-300">
+                    Cancel
+                  </button>
+                </div>
+              </div>
+            ) : (
+              (run.note || (run.tags ?? []).length > 0) && (
+                <div className="mt-1 text-xs">
+                  {run.note && <p className="text-gray-700 whitespace-pre-wrap">{run.note}</p>}
+                  {(run.tags ?? []).map(tag => (
+                    <span key={tag} className="inline-block mr-1 px-2 py-0.5 bg-blue-100 text-blue-800 rounded-full">
+                      {tag}
+                    </span>
+                  ))}
+                </div>
+              )
+            )}
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
@@ -0,0 +1,126 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { open } from '@tauri-apps/plugin-dialog';
+import { ImportOutcome, PathField, PathRemapRule } from '../types';
+
+const describeField = (field: PathField, index?: number, key?: string) => {
+  switch (field) {
+    case 'program':
+      return 'program';
+    case 'argument':
+      return `argument ${(index ?? 0) + 1}`;
+    case 'environment':
+      return `$${key}`;
+    case 'working_directory':
+      return 'working directory';
+  }
+};
+
+// Imports commands made on other machines, rewriting their absolute paths with the remapping rules
+export function ImportPanel() {
+  const [rules, setRules] = useState<PathRemapRule[]>([]);
+  const [outcome, setOutcome] = useState<ImportOutcome | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<PathRemapRule[]>('get_path_remap_rules').then(setRules).catch(() => setRules([]));
+  }, []);
+
+  const updateRule = (index: number, patch: Partial<PathRemapRule>) => {
+    setRules(prev => prev.map((rule, i) => (i === index ? { ...rule, ...patch } : rule)));
+  };
+
+  const saveRules = async () => {
+    try {
+      await invoke('set_path_remap_rules', { rules: rules.filter(rule => rule.from) });
+      setMessage('Saved the remapping rules');
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to save the rules');
+    }
+  };
+
+  const runImport = async (kind: 'bundle' | 'project') => {
+    const path = kind === 'bundle'
+      ? await open({ filters: [{ name: 'Bundle', extensions: ['json'] }] })
+      : await open({ directory: true });
+    if (typeof path !== 'string') return;
+    setMessage(null);
+    try {
+      const result = await invoke<ImportOutcome>(kind === 'bundle' ? 'import_bundle' : 'import_project', { path });
+      setOutcome(result);
+      setMessage(`Imported ${result.created.length} command${result.created.length === 1 ? '' : 's'}`);
+    } catch (err) {
+      setOutcome(null);
+      setMessage(typeof err === 'string' ? err : 'Failed to import');
+    }
+  };
+
+  return (
+    <div className="space-y-3">
+      <h3 className="font-semibold">Import</h3>
+
+      <div className="space-y-1 text-sm">
+        <div className="text-gray-600">Path remapping (a target starting with ~ is your home directory)</div>
+        {rules.map((rule, index) => (
+          <div key={index} className="flex items-center gap-2">
+            <input
+              value={rule.from}
+              onChange={e => updateRule(index, { from: e.target.value })}
+              placeholder="/Users/someone"
+              className="flex-1 px-2 py-1 border border-gray-300 rounded-md font-mono"
+            />
+            <span>→</span>
+            <input
+              value={rule.to}
+              onChange={e => updateRule(index, { to: e.target.value })}
+              placeholder="~"
+              className="flex-1 px-2 py-1 border border-gray-300 rounded-md font-mono"
+            />
+            <button onClick={() => setRules(prev => prev.filter((_, i) => i !== index))} className="text-red-600 hover:underline">
+              Remove
+            </button>
+          </div>
+        ))}
+        <div className="flex gap-2">
+          <button onClick={() => setRules(prev => [...prev, { from: '', to: '' }])} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300">
+            Add rule
+          </button>
+          <button onClick={saveRules} className="px-3 py-1 bg-blue-500 text-white rounded-md hover:bg-blue-600">
+            Save rules
+          </button>
+        </div>
+      </div>
+
+      <div className="flex gap-2 text-sm">
+        <button onClick={() => runImport('bundle')} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300">
+          Import bundle...
+        </button>
+        <button onClick={() => runImport('project')} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300">
+          Import project tasks...
+        </button>
+      </div>
+
+      {message && <p className="text-sm text-gray-600">{message}</p>}
+      {outcome && outcome.paths.remapped.length > 0 && (
+        <div className="text-xs space-y-1">
+          <div className="font-medium">Remapped</div>
+          {outcome.paths.remapped.map((change, index) => (
+            <div key={index} className="font-mono">
+              {change.command_name} · {describeField(change.field, change.index, change.key)}: {change.before} → {change.after}
+            </div>
+          ))}
+        </div>
+      )}
+      {outcome && outcome.paths.unmapped.length > 0 && (
+        <div className="text-xs space-y-1 p-2 bg-yellow-50 border border-yellow-200 rounded-md">
+          <div className="font-medium">Paths that don't exist here and no rule matched</div>
+          {outcome.paths.unmapped.map((entry, index) => (
+            <div key={index} className="font-mono">
+              {entry.command_name} · {describeField(entry.field, entry.index, entry.key)}: {entry.path}
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,83 @@
+import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { OutputStream, OutputTailEvent, RunningJob } from '../types';
+
+interface LiveTailProps {
+  commandId: string;
+}
+
+// Lines kept on screen; the whole output is in history once the run finishes
+const MAX_LINES = 1000;
+
+// The end of a running command's output as it is written, even when it is too large
+// to be kept in memory
+export function LiveTail({ commandId }: LiveTailProps) {
+  const [stream, setStream] = useState<OutputStream>('stdout');
+  const [lines, setLines] = useState<string[]>([]);
+  const [status, setStatus] = useState('Waiting for the run to start...');
+  const bottomRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    let runId: string | null = null;
+    let cancelled = false;
+    setLines([]);
+
+    const unlisten = listen<OutputTailEvent>('execution-output-tail', event => {
+      if (event.payload.execution_id !== runId) return;
+      if (event.payload.finished) {
+        setStatus('The run finished.');
+        return;
+      }
+      const added = event.payload.text.replace(/\n$/, '').split('\n');
+      setLines(prev => [...prev, ...added].slice(-MAX_LINES));
+    });
+
+    // The run registers its process shortly after it starts
+    const findRun = async () => {
+      while (!cancelled) {
+        const jobs = await invoke<RunningJob[]>('list_running_jobs');
+        const job = jobs.find(j => j.command_id === commandId);
+        if (job) {
+          runId = job.run_id;
+          setStatus('');
+          await invoke('follow_execution_output', { executionId: job.run_id, stream });
+          return;
+        }
+        await new Promise(resolve => setTimeout(resolve, 500));
+      }
+    };
+    findRun().catch(err => setStatus(String(err)));
+
+    return () => {
+      cancelled = true;
+      unlisten.then(f => f());
+      if (runId) invoke('unfollow_execution_output', { executionId: runId });
+    };
+  }, [commandId, stream]);
+
+  useEffect(() => {
+    bottomRef.current?.scrollIntoView({ block: 'nearest' });
+  }, [lines]);
+
+  return (
+    <div className="p-2 bg-gray-900 text-gray-100 rounded text-xs">
+      <div className="flex items-center gap-2 mb-1">
+        {(['stdout', 'stderr'] as OutputStream[]).map(s => (
+          <button
+            key={s}
+            onClick={() => setStream(s)}
+            className={`px-2 py-0.5 rounded ${stream === s ? 'bg-gray-600' : 'bg-gray-800 hover:bg-gray-700'}`}
+          >
+            {s}
+          </button>
+        ))}
+        {status && <span className="text-gray-400">{status}</span>}
+      </div>
+      <pre className="max-h-64 overflow-auto whitespace-pre-wrap break-words font-mono">
+        {lines.join('\n')}
+        <div ref={bottomRef} />
+      </pre>
+    </div>
+  );
+}
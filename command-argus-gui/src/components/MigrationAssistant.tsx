@@ -0,0 +1,150 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { open } from '@tauri-apps/plugin-dialog';
+import { MigrationPreview, StateCategory } from '../types';
+import { formatBytes } from './StorageUsagePanel';
+
+interface MigrationAssistantProps {
+  onDone: () => void;
+  onCancel: () => void;
+}
+
+const CATEGORY_LABELS: Record<StateCategory, string> = {
+  commands: 'Commands',
+  variables: 'Variables',
+  profiles: 'Profiles',
+  settings: 'Settings',
+  saved_searches: 'Saved searches',
+  history: 'Execution history',
+};
+
+// Brings the state of an old machine over from an exported archive or a copied data directory
+export function MigrationAssistant({ onDone, onCancel }: MigrationAssistantProps) {
+  const [path, setPath] = useState('');
+  const [preview, setPreview] = useState<MigrationPreview | null>(null);
+  const [selected, setSelected] = useState<Set<StateCategory>>(new Set());
+  const [remap, setRemap] = useState(true);
+  const [remapFrom, setRemapFrom] = useState('');
+  const [remapTo, setRemapTo] = useState('');
+  const [importing, setImporting] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadPreview = async (chosen: string) => {
+    setPath(chosen);
+    setError(null);
+    try {
+      const result = await invoke<MigrationPreview>('preview_migration', { path: chosen });
+      setPreview(result);
+      setSelected(new Set(result.items.map(item => item.category).filter(category => category !== 'history')));
+      setRemapFrom(result.old_home ?? '');
+      setRemapTo(result.new_home ?? '');
+      setRemap(!!result.old_home);
+    } catch (err) {
+      setPreview(null);
+      setError(typeof err === 'string' ? err : 'Nothing to migrate found there');
+    }
+  };
+
+  const choose = async (directory: boolean) => {
+    const chosen = await open(directory ? { directory: true } : { filters: [{ name: 'Zip', extensions: ['zip'] }] });
+    if (typeof chosen === 'string') {
+      await loadPreview(chosen);
+    }
+  };
+
+  const toggle = (category: StateCategory) => {
+    setSelected(prev => {
+      const next = new Set(prev);
+      if (next.has(category)) {
+        next.delete(category);
+      } else {
+        next.add(category);
+      }
+      return next;
+    });
+  };
+
+  const handleImport = async () => {
+    setImporting(true);
+    setError(null);
+    try {
+      await invoke('migrate_from', {
+        path,
+        options: {
+          categories: Array.from(selected),
+          remap_home: remap && remapFrom && remapTo ? { from: remapFrom, to: remapTo } : null
+        }
+      });
+      onDone();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to import');
+    } finally {
+      setImporting(false);
+    }
+  };
+
+  return (
+    <div className="space-y-3">
+      <p className="text-sm text-gray-600">
+        Choose the zip made by "Export everything" on the old machine, or a copy of its data directory.
+      </p>
+      <div className="flex gap-2">
+        <button onClick={() => choose(false)} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300 text-sm">
+          Choose archive...
+        </button>
+        <button onClick={() => choose(true)} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300 text-sm">
+          Choose data directory...
+        </button>
+      </div>
+      {path && <div className="text-xs font-mono text-gray-500 truncate">{path}</div>}
+
+      {preview && (
+        <>
+          {preview.exported_at && (
+            <div className="text-xs text-gray-500">Exported {new Date(preview.exported_at).toLocaleString()}</div>
+          )}
+          <div className="space-y-1">
+            {preview.items.map(item => (
+              <label key={item.category} className="flex items-center gap-2 text-sm">
+                <input type="checkbox" checked={selected.has(item.category)} onChange={() => toggle(item.category)} />
+                <span className="flex-1">{CATEGORY_LABELS[item.category]}</span>
+                <span className="text-xs text-gray-500">
+                  {item.entries !== undefined && item.entries !== null ? `${item.entries} · ` : ''}
+                  {formatBytes(item.bytes)}
+                </span>
+              </label>
+            ))}
+          </div>
+          <div className="p-2 bg-gray-50 border border-gray-200 rounded-md text-sm space-y-1">
+            <label className="flex items-center gap-2">
+              <input type="checkbox" checked={remap} onChange={e => setRemap(e.target.checked)} />
+              Rewrite paths under the old home directory
+            </label>
+            {remap && (
+              <div className="flex items-center gap-2 text-xs">
+                <input value={remapFrom} onChange={e => setRemapFrom(e.target.value)} placeholder="/home/old" className="flex-1 px-2 py-1 border border-gray-300 rounded font-mono" />
+                <span>→</span>
+                <input value={remapTo} onChange={e => setRemapTo(e.target.value)} className="flex-1 px-2 py-1 border border-gray-300 rounded font-mono" />
+              </div>
+            )}
+          </div>
+        </>
+      )}
+
+      {error && <div className="text-sm text-red-600">{error}</div>}
+
+      <div className="flex justify-end gap-2">
+        <button onClick={onCancel} className="px-4 py-2 bg-gray-300 text-gray-700 rounded-md hover:bg-gray-400">
+          Back
+        </button>
+        <button
+          onClick={handleImport}
+          disabled={!preview || selected.size === 0 || importing}
+          className="px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50"
+        >
+          {importing ? 'Importing...' : 'Import'}
+        </button>
+      </div>
+    </div>
+  );
+}
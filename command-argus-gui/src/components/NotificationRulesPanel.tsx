@@ -0,0 +1,246 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { Command, NotificationFormat, NotificationRule, NotificationTarget, NotificationTrigger } from '../types';
+
+type TriggerKind = NotificationTrigger['kind'];
+type UrlTargetKind = Exclude<NotificationTarget['kind'], 'desktop'>;
+
+function describeTrigger(trigger: NotificationTrigger, commands: Command[]): string {
+  switch (trigger.kind) {
+    case 'command_failed': {
+      const command = commands.find(c => c.id === trigger.command_id);
+      return `When ${command ? command.name : 'a deleted command'} fails`;
+    }
+    case 'any_failure':
+      return 'When any command fails';
+    case 'tag_ran':
+      return `When a command tagged "${trigger.tag}" runs`;
+    case 'duration_exceeded':
+      return `When a run takes longer than ${trigger.seconds}s`;
+  }
+}
+
+function describeTargets(targets: NotificationTarget[]): string {
+  return targets.map(t => (t.kind === 'desktop' ? 'desktop' : `${t.kind} ${t.url}`)).join(', ') || 'nowhere';
+}
+
+export function NotificationRulesPanel() {
+  const [rules, setRules] = useState<NotificationRule[]>([]);
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const [name, setName] = useState('');
+  const [kind, setKind] = useState<TriggerKind>('any_failure');
+  const [commandId, setCommandId] = useState('');
+  const [tag, setTag] = useState('');
+  const [seconds, setSeconds] = useState(300);
+  const [desktop, setDesktop] = useState(true);
+  const [urlKind, setUrlKind] = useState<UrlTargetKind>('webhook');
+  const [webhookUrl, setWebhookUrl] = useState('');
+  const [format, setFormat] = useState<NotificationFormat>({ message_template: '' });
+  const [formatSaved, setFormatSaved] = useState(false);
+
+  const loadRules = async () => {
+    try {
+      setRules(await invoke<NotificationRule[]>('list_notification_rules'));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to load notification rules');
+    }
+  };
+
+  useEffect(() => {
+    loadRules();
+    invoke<Command[]>('list_commands', { allPlatforms: true }).then(setCommands);
+    invoke<NotificationFormat>('get_notification_format').then(setFormat);
+  }, []);
+
+  useStoreUpdates(['notification_rule', 'setting'], delta => {
+    if (delta.entity === 'notification_rule') loadRules();
+    if (delta.entity === 'setting' && delta.name === 'notification_format') setFormat(delta.value as NotificationFormat);
+  });
+
+  const buildTrigger = (): NotificationTrigger => {
+    switch (kind) {
+      case 'command_failed':
+        return { kind, command_id: commandId };
+      case 'tag_ran':
+        return { kind, tag: tag.trim() };
+      case 'duration_exceeded':
+        return { kind, seconds };
+      default:
+        return { kind: 'any_failure' };
+    }
+  };
+
+  const handleCreate = async (e: React.FormEvent) => {
+    e.preventDefault();
+    const targets: NotificationTarget[] = [];
+    if (desktop) targets.push({ kind: 'desktop' });
+    if (webhookUrl.trim()) targets.push({ kind: urlKind, url: webhookUrl.trim() });
+    try {
+      setError(null);
+      await invoke('create_notification_rule', { name: name.trim(), trigger: buildTrigger(), targets });
+      setName('');
+      setWebhookUrl('');
+      await loadRules();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to create rule');
+    }
+  };
+
+  const handleSaveFormat = async (e: React.FormEvent) => {
+    e.preventDefault();
+    try {
+      setError(null);
+      await invoke('set_notification_format', {
+        messageTemplate: format.message_template,
+        outputLink: format.output_link || ''
+      });
+      setFormat(await invoke<NotificationFormat>('get_notification_format'));
+      setFormatSaved(true);
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to save message format');
+    }
+  };
+
+  const handleToggle = async (rule: NotificationRule) => {
+    try {
+      await invoke('update_notification_rule', { rule: { ...rule, enabled: !rule.enabled } });
+      await loadRules();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to update rule');
+    }
+  };
+
+  const handleDelete = async (id: string) => {
+    try {
+      await invoke('delete_notification_rule', { id });
+      await loadRules();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to delete rule');
+    }
+  };
+
+  const inputClass = 'px-2 py-1 border border-gray-300 rounded-md text-sm';
+  const incomplete =
+    !name.trim() ||
+    (!desktop && !webhookUrl.trim()) ||
+    (kind === 'command_failed' && !commandId) ||
+    (kind === 'tag_ran' && !tag.trim());
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Notifications</h2>
+      {error && <div className="mb-3 text-sm text-red-600">{error}</div>}
+
+      {rules.length === 0 ? (
+        <p className="text-sm text-gray-500 mb-4">No notification rules yet.</p>
+      ) : (
+        <ul className="mb-4 divide-y divide-gray-200">
+          {rules.map(rule => (
+            <li key={rule.id} className="py-2 flex justify-between items-center">
+              <div className={rule.enabled ? '' : 'opacity-50'}>
+                <div className="text-sm font-medium">{rule.name}</div>
+                <div className="text-xs text-gray-500">
+                  {describeTrigger(rule.trigger, commands)} → {describeTargets(rule.targets)}
+                </div>
+              </div>
+              <div className="flex gap-2">
+                <button onClick={() => handleToggle(rule)} className="px-2 py-1 text-xs bg-gray-200 rounded hover:bg-gray-300">
+                  {rule.enabled ? 'Disable' : 'Enable'}
+                </button>
+                <button onClick={() => handleDelete(rule.id)} className="px-2 py-1 text-xs bg-red-500 text-white rounded hover:bg-red-600">
+                  Delete
+                </button>
+              </div>
+            </li>
+          ))}
+        </ul>
+      )}
+
+      <form onSubmit={handleCreate} className="flex flex-wrap gap-2 items-center">
+        <input value={name} onChange={e => setName(e.target.value)} placeholder="Rule name" className={inputClass} />
+        <select value={kind} onChange={e => setKind(e.target.value as TriggerKind)} className={inputClass}>
+          <option value="any_failure">Any command fails</option>
+          <option value="command_failed">A command fails</option>
+          <option value="tag_ran">A tagged command runs</option>
+          <option value="duration_exceeded">A run takes longer than</option>
+        </select>
+        {kind === 'command_failed' && (
+          <select value={commandId} onChange={e => setCommandId(e.target.value)} className={inputClass}>
+            <option value="">Select command</option>
+            {commands.map(c => (
+              <option key={c.id} value={c.id}>{c.name}</option>
+            ))}
+          </select>
+        )}
+        {kind === 'tag_ran' && (
+          <input value={tag} onChange={e => setTag(e.target.value)} placeholder="Tag, e.g. prod" className={inputClass} />
+        )}
+        {kind === 'duration_exceeded' && (
+          <input
+            type="number"
+            min={1}
+            value={seconds}
+            onChange={e => setSeconds(Number(e.target.value))}
+            className={`${inputClass} w-24`}
+            title="Seconds"
+          />
+        )}
+        <label className="flex items-center gap-1 text-sm">
+          <input type="checkbox" checked={desktop} onChange={e => setDesktop(e.target.checked)} />
+          Desktop
+        </label>
+        <select value={urlKind} onChange={e => setUrlKind(e.target.value as UrlTargetKind)} className={inputClass}>
+          <option value="webhook">Webhook</option>
+          <option value="slack">Slack</option>
+          <option value="discord">Discord</option>
+        </select>
+        <input
+          value={webhookUrl}
+          onChange={e => setWebhookUrl(e.target.value)}
+          placeholder="Webhook URL (optional)"
+          className={`${inputClass} flex-1 min-w-[12rem]`}
+        />
+        <button
+          type="submit"
+          disabled={incomplete}
+          className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600 disabled:opacity-50"
+        >
+          Add rule
+        </button>
+      </form>
+
+      <form onSubmit={handleSaveFormat} className="mt-6 space-y-2">
+        <h3 className="text-sm font-semibold text-gray-700">Slack and Discord messages</h3>
+        <textarea
+          value={format.message_template}
+          onChange={e => {
+            setFormat({ ...format, message_template: e.target.value });
+            setFormatSaved(false);
+          }}
+          rows={2}
+          className={`${inputClass} w-full font-mono`}
+        />
+        <p className="text-xs text-gray-500">
+          Placeholders: {'{icon} {command} {status} {exit_code} {duration} {rule} {link}'}. Leave empty for the default.
+        </p>
+        <input
+          value={format.output_link || ''}
+          onChange={e => {
+            setFormat({ ...format, output_link: e.target.value });
+            setFormatSaved(false);
+          }}
+          placeholder="Output link, e.g. https://argus.example.com/runs/{execution_id}"
+          className={`${inputClass} w-full`}
+        />
+        <div className="flex items-center gap-2">
+          <button type="submit" className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600">
+            Save format
+          </button>
+          {formatSaved && <span className="text-xs text-green-700">Saved</span>}
+        </div>
+      </form>
+    </div>
+  );
+}
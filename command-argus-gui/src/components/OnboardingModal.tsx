@@ -0,0 +1,136 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Onboarding, StarterCommand } from '../types';
+import { MigrationAssistant } from './MigrationAssistant';
+
+interface OnboardingModalProps {
+  onDone: () => void;
+}
+
+// Offers starter commands for the tools found on the PATH, on first launch only
+export function OnboardingModal({ onDone }: OnboardingModalProps) {
+  const [starters, setStarters] = useState<StarterCommand[] | null>(null);
+  const [selected, setSelected] = useState<Set<string>>(new Set());
+  const [error, setError] = useState<string | null>(null);
+  // Moving from another machine instead of starting fresh
+  const [migrating, setMigrating] = useState(false);
+
+  useEffect(() => {
+    invoke<Onboarding>('get_onboarding')
+      .then(onboarding => {
+        if (!onboarding.needed) return;
+        setStarters(onboarding.starters);
+        setSelected(new Set(onboarding.starters.filter(s => s.impact === 'safe').map(s => s.key)));
+      })
+      .catch(err => console.error('Failed to load onboarding:', err));
+  }, []);
+
+  if (!starters) return null;
+
+  const toggle = (key: string) => {
+    setSelected(prev => {
+      const next = new Set(prev);
+      if (next.has(key)) {
+        next.delete(key);
+      } else {
+        next.add(key);
+      }
+      return next;
+    });
+  };
+
+  const handleAdd = async () => {
+    try {
+      await invoke('seed_starter_commands', { selection: Array.from(selected) });
+      setStarters(null);
+      onDone();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to add starter commands');
+    }
+  };
+
+  const handleSkip = async () => {
+    try {
+      await invoke('dismiss_onboarding');
+    } catch (err) {
+      console.error('Failed to dismiss onboarding:', err);
+    }
+    setStarters(null);
+  };
+
+  const tools = Array.from(new Set(starters.map(s => s.tool)));
+
+  return (
+    <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+      <div className="bg-white rounded-lg p-6 w-full max-w-lg max-h-[80vh] overflow-y-auto">
+        <h2 className="text-xl font-bold mb-2">Welcome to Command Argus</h2>
+        {migrating ? (
+          <MigrationAssistant
+            onDone={() => {
+              setStarters(null);
+              onDone();
+            }}
+            onCancel={() => setMigrating(false)}
+          />
+        ) : (
+        <>
+        <button onClick={() => setMigrating(true)} className="mb-3 text-sm text-blue-600 hover:underline">
+          Moving from another machine? Import your old commands and settings
+        </button>
+        {tools.length === 0 ? (
+          <p className="text-sm text-gray-600 mb-4">
+            None of the tools we have starter commands for was found on your PATH. Start by adding your own commands.
+          </p>
+        ) : (
+          <>
+            <p className="text-sm text-gray-600 mb-4">
+              Pick some starter commands for the tools found on this machine. You can edit or delete them later.
+            </p>
+            {tools.map(tool => (
+              <div key={tool} className="mb-4">
+                <h3 className="text-sm font-semibold text-gray-700 mb-1">{tool}</h3>
+                {starters.filter(s => s.tool === tool).map(starter => (
+                  <label key={starter.key} className="flex items-start gap-2 py-1 cursor-pointer">
+                    <input
+                      type="checkbox"
+                      checked={selected.has(starter.key)}
+                      onChange={() => toggle(starter.key)}
+                      className="mt-1"
+                    />
+                    <span>
+                      <span className="text-sm font-medium">{starter.name}</span>
+                      <code className="ml-2 text-xs text-gray-500">{[starter.command, ...starter.args].join(' ')}</code>
+                      <span className="block text-xs text-gray-500">{starter.description}</span>
+                    </span>
+                  </label>
+                ))}
+              </div>
+            ))}
+          </>
+        )}
+
+        {error && <div className="mb-4 text-sm text-red-600">{error}</div>}
+
+        <div className="flex justify-end gap-2">
+          <button
+            onClick={handleSkip}
+            className="px-4 py-2 bg-gray-300 text-gray-700 rounded-md hover:bg-gray-400"
+          >
+            Skip
+          </button>
+          {tools.length > 0 && (
+            <button
+              onClick={handleAdd}
+              disabled={selected.size === 0}
+              className="px-4 py-2 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50"
+            >
+              Add {selected.size} commands
+            </button>
+          )}
+        </div>
+        </>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,132 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Command, ParameterCandidate, ParameterSelection } from '../types';
+
+interface ParameterizePanelProps {
+  command: Command;
+  onDone: () => void;
+}
+
+interface Row extends ParameterCandidate {
+  selected: boolean;
+}
+
+// Turns paths, hostnames and versions written into a command into parameters
+export function ParameterizePanel({ command, onDone }: ParameterizePanelProps) {
+  const [rows, setRows] = useState<Row[]>([]);
+  // Any other text of the command, with the name to give it
+  const [customText, setCustomText] = useState('');
+  const [customName, setCustomName] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  // New names for the parameters the command already has
+  const [renames, setRenames] = useState<Record<string, string>>({});
+
+  const existing = Array.from(new Set([
+    ...command.parameters.map(p => p.name),
+    ...[command.command, ...command.args].flatMap(text => Array.from(text.matchAll(/\{([A-Za-z_][A-Za-z0-9_]*)\}/g), m => m[1]))
+  ]));
+
+  const handleRename = async (old: string) => {
+    const name = renames[old]?.trim();
+    if (!name || name === old) return;
+    try {
+      await invoke('rename_parameter', { id: command.id, old, new: name });
+      onDone();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to rename the parameter');
+    }
+  };
+
+  useEffect(() => {
+    invoke<ParameterCandidate[]>('suggest_parameters', { id: command.id })
+      .then(candidates => setRows(candidates.map(candidate => ({ ...candidate, selected: false }))))
+      .catch(err => setError(typeof err === 'string' ? err : 'Failed to find parameters'));
+  }, [command.id]);
+
+  const updateRow = (index: number, change: Partial<Row>) => {
+    setRows(prev => prev.map((row, i) => (i === index ? { ...row, ...change } : row)));
+  };
+
+  const handleApply = async () => {
+    const selections: ParameterSelection[] = rows
+      .filter(row => row.selected)
+      .map(row => ({ text: row.text, name: row.name.trim(), parameter_type: row.parameter_type }));
+    if (customText && customName.trim()) {
+      selections.push({ text: customText, name: customName.trim() });
+    }
+    if (selections.length === 0) return;
+    try {
+      await invoke('parameterize_command', { id: command.id, selections });
+      onDone();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to add the parameters');
+    }
+  };
+
+  return (
+    <div className="p-2 bg-gray-50 border border-gray-200 rounded space-y-2 text-xs">
+      {rows.length === 0 && <div className="text-gray-500">No paths, hostnames or versions found</div>}
+      {rows.map((row, index) => (
+        <label key={row.text} className="flex items-center gap-2">
+          <input type="checkbox" checked={row.selected} onChange={e => updateRow(index, { selected: e.target.checked })} />
+          <span className="flex-1 font-mono">{row.text}</span>
+          <span className="text-gray-500">{row.kind}</span>
+          <input
+            type="text"
+            value={row.name}
+            onChange={e => updateRow(index, { name: e.target.value })}
+            className="w-28 px-2 py-0.5 border border-gray-300 rounded"
+          />
+        </label>
+      ))}
+      <div className="flex items-center gap-2">
+        <input
+          type="text"
+          value={customText}
+          onChange={e => setCustomText(e.target.value)}
+          placeholder="Other text of the command"
+          className="flex-1 px-2 py-0.5 border border-gray-300 rounded font-mono"
+        />
+        <input
+          type="text"
+          value={customName}
+          onChange={e => setCustomName(e.target.value)}
+          placeholder="Parameter name"
+          className="w-28 px-2 py-0.5 border border-gray-300 rounded"
+        />
+      </div>
+      {existing.length > 0 && (
+        <div className="pt-2 border-t border-gray-200 space-y-1">
+          <div className="text-gray-500">Rename a parameter everywhere it is used</div>
+          {existing.map(name => (
+            <div key={name} className="flex items-center gap-2">
+              <span className="flex-1 font-mono">{`{${name}}`}</span>
+              <input
+                type="text"
+                value={renames[name] ?? name}
+                onChange={e => setRenames(prev => ({ ...prev, [name]: e.target.value }))}
+                className="w-28 px-2 py-0.5 border border-gray-300 rounded"
+              />
+              <button
+                onClick={() => handleRename(name)}
+                disabled={!renames[name]?.trim() || renames[name].trim() === name}
+                className="px-2 py-0.5 bg-gray-200 rounded hover:bg-gray-300 disabled:opacity-50"
+              >
+                Rename
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+      {error && <div className="text-red-600">{error}</div>}
+      <div className="flex gap-2">
+        <button onClick={handleApply} className="px-3 py-1 bg-blue-500 text-white rounded hover:bg-blue-600">
+          Make Parameters
+        </button>
+        <button onClick={onDone} className="px-3 py-1 bg-gray-200 text-gray-800 rounded hover:bg-gray-300">
+          Cancel
+        </button>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,34 @@
+import { Phase } from '../types';
+
+const COLORS = ['bg-blue-400', 'bg-green-400', 'bg-yellow-400', 'bg-purple-400', 'bg-pink-400', 'bg-teal-400'];
+
+// Like "2m 5s", "30s" or "40ms"
+const describeDuration = (ms: number) => {
+  const seconds = Math.floor(ms / 1000);
+  const [h, m, s] = [Math.floor(seconds / 3600), Math.floor(seconds / 60) % 60, seconds % 60];
+  if (h > 0) return `${h}h ${m}m`;
+  if (m > 0) return s > 0 ? `${m}m ${s}s` : `${m}m`;
+  return s > 0 ? `${s}s` : `${ms}ms`;
+};
+
+// The phases of a run side by side, each as wide as the share of time it took
+export function PhaseBar({ phases }: { phases: Phase[] }) {
+  const total = phases.reduce((sum, phase) => sum + phase.duration_ms, 0) || 1;
+  return (
+    <div className="space-y-1">
+      <div className="flex h-2 rounded overflow-hidden">
+        {phases.map((phase, i) => (
+          <div
+            key={i}
+            className={COLORS[i % COLORS.length]}
+            style={{ width: `${(phase.duration_ms / total) * 100}%` }}
+            title={`${phase.name}: ${describeDuration(phase.duration_ms)}`}
+          />
+        ))}
+      </div>
+      <div className="text-gray-600">
+        {phases.map(phase => `${phase.name} ${describeDuration(phase.duration_ms)}`).join(', ')}
+      </div>
+    </div>
+  );
+}
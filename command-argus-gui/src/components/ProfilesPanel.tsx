@@ -0,0 +1,153 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { Profile } from '../types';
+
+interface ProfilesPanelProps {
+  // Called after profiles were added or removed, so the profile picker can reload
+  onChange: () => void;
+}
+
+// KEY=value lines, as typed in the profile form
+function parseLines(text: string): [string, string][] {
+  return text
+    .split('\n')
+    .map(line => line.trim())
+    .filter(line => line.includes('='))
+    .map(line => {
+      const index = line.indexOf('=');
+      return [line.slice(0, index).trim(), line.slice(index + 1)];
+    });
+}
+
+function toLines(entries: [string, string][]): string {
+  return entries.map(([key, value]) => `${key}=${value}`).join('\n');
+}
+
+// Environment profiles such as dev, staging and prod that commands can be run with
+export function ProfilesPanel({ onChange }: ProfilesPanelProps) {
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const [name, setName] = useState('');
+  const [description, setDescription] = useState('');
+  const [envVars, setEnvVars] = useState('');
+  const [variables, setVariables] = useState('');
+
+  const loadProfiles = async () => {
+    try {
+      setProfiles(await invoke<Profile[]>('list_profiles'));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to load profiles');
+    }
+  };
+
+  useEffect(() => {
+    loadProfiles();
+  }, []);
+
+  useStoreUpdates(['profile'], () => loadProfiles());
+
+  const handleEdit = (profile: Profile) => {
+    setName(profile.name);
+    setDescription(profile.description || '');
+    setEnvVars(toLines(profile.environment_variables.map(v => [v.key, v.value])));
+    setVariables(toLines(Object.entries(profile.variables)));
+  };
+
+  const handleSave = async (e: React.FormEvent) => {
+    e.preventDefault();
+    const profile: Profile = {
+      name: name.trim(),
+      description: description.trim() || undefined,
+      environment_variables: parseLines(envVars).map(([key, value]) => ({ key, value })),
+      variables: Object.fromEntries(parseLines(variables)),
+    };
+    try {
+      setError(null);
+      await invoke('save_profile', { profile });
+      setName('');
+      setDescription('');
+      setEnvVars('');
+      setVariables('');
+      await loadProfiles();
+      onChange();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to save the profile');
+    }
+  };
+
+  const handleDelete = async (profile: Profile) => {
+    try {
+      await invoke('delete_profile', { name: profile.name });
+      await loadProfiles();
+      onChange();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to delete the profile');
+    }
+  };
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Profiles</h2>
+      {error && <p className="mb-2 text-sm text-red-600">{error}</p>}
+      <ul className="mb-3 divide-y divide-gray-100 text-sm">
+        {profiles.map(profile => (
+          <li key={profile.name} className="flex items-center gap-3 py-1">
+            <span className="w-32 font-medium text-gray-800">{profile.name}</span>
+            <span className="flex-1 text-gray-500 truncate">
+              {profile.description ||
+                `${profile.environment_variables.length} env vars, ${Object.keys(profile.variables).length} variables`}
+            </span>
+            <button onClick={() => handleEdit(profile)} className="text-blue-600 hover:text-blue-800">
+              Edit
+            </button>
+            <button onClick={() => handleDelete(profile)} className="text-red-600 hover:text-red-800">
+              Delete
+            </button>
+          </li>
+        ))}
+      </ul>
+      <form onSubmit={handleSave} className="space-y-2">
+        <div className="flex gap-2">
+          <input
+            type="text"
+            value={name}
+            onChange={e => setName(e.target.value)}
+            placeholder="Name, e.g. staging"
+            className="w-48 px-3 py-1 border border-gray-300 rounded-md text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <input
+            type="text"
+            value={description}
+            onChange={e => setDescription(e.target.value)}
+            placeholder="Description"
+            className="flex-1 px-3 py-1 border border-gray-300 rounded-md text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+        </div>
+        <div className="flex gap-2">
+          <textarea
+            value={envVars}
+            onChange={e => setEnvVars(e.target.value)}
+            rows={3}
+            placeholder={'Environment variables, one KEY=value per line'}
+            className="flex-1 px-3 py-1 border border-gray-300 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <textarea
+            value={variables}
+            onChange={e => setVariables(e.target.value)}
+            rows={3}
+            placeholder={'Variables for {{var:NAME}}, one NAME=value per line'}
+            className="flex-1 px-3 py-1 border border-gray-300 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+        </div>
+        <button
+          type="submit"
+          disabled={!name.trim()}
+          className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600 disabled:opacity-50"
+        >
+          Save Profile
+        </button>
+      </form>
+    </div>
+  );
+}
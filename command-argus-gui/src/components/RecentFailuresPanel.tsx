@@ -0,0 +1,71 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { Command, RecentFailure } from '../types';
+
+interface RecentFailuresPanelProps {
+  onEdit: (command: Command) => void;
+}
+
+// Saved commands whose latest run failed, so broken entries are easy to find and fix
+export function RecentFailuresPanel({ onEdit }: RecentFailuresPanelProps) {
+  const [failures, setFailures] = useState<RecentFailure[] | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadFailures = () => {
+    invoke<RecentFailure[]>('list_recent_failures', { limit: 20 })
+      .then(setFailures)
+      .catch(err => setError(typeof err === 'string' ? err : 'Failed to load recent failures'));
+  };
+
+  useEffect(() => {
+    loadFailures();
+  }, []);
+
+  useStoreUpdates(['execution', 'history', 'command', 'commands'], () => loadFailures());
+
+  if (!failures) {
+    return <div className="text-sm text-gray-500">{error ?? 'Loading...'}</div>;
+  }
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-3">Recent Failures</h2>
+      {failures.length === 0 ? (
+        <p className="text-sm text-gray-500">Every command succeeded the last time it ran.</p>
+      ) : (
+        <div className="space-y-3">
+          {failures.map(({ command, execution, stderr_snippet }) => (
+            <div key={command.id} className="p-3 border border-red-200 rounded-md bg-red-50">
+              <div className="flex justify-between items-start">
+                <div>
+                  <span className="font-medium text-gray-800">{command.name}</span>
+                  <span className="ml-2 text-xs text-red-700">
+                    {execution.exit_code !== undefined && execution.exit_code !== null
+                      ? `exit ${execution.exit_code}`
+                      : 'did not start'}
+                  </span>
+                  <span className="ml-2 text-xs text-gray-500">
+                    {new Date(execution.finished_at).toLocaleString()}
+                  </span>
+                </div>
+                <button
+                  onClick={() => onEdit(command)}
+                  className="px-3 py-1 text-xs bg-blue-500 text-white rounded hover:bg-blue-600"
+                >
+                  Edit
+                </button>
+              </div>
+              <code className="block text-xs text-gray-600 mt-1">{execution.command_line}</code>
+              {stderr_snippet && (
+                <pre className="mt-2 p-2 bg-white border border-red-100 rounded text-xs text-red-800 whitespace-pre-wrap">
+                  {stderr_snippet}
+                </pre>
+              )}
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,50 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { RecoveryNotice } from '../types';
+
+// Points out, once, the runs the previous session was in the middle of
+export function RecoveryBanner() {
+  const [notice, setNotice] = useState<RecoveryNotice | null>(null);
+
+  useEffect(() => {
+    invoke<RecoveryNotice | null>('take_recovery_notice')
+      .then(setNotice)
+      .catch(err => console.error('Failed to load recovery notice:', err));
+  }, []);
+
+  if (!notice) return null;
+
+  return (
+    <div className="mb-4 p-4 bg-amber-50 border border-amber-300 rounded-md">
+      <div className="flex justify-between items-start">
+        <div>
+          <h2 className="text-sm font-semibold text-amber-800">
+            {notice.crashed
+              ? 'Command Argus closed unexpectedly while commands were running'
+              : 'Commands were left running when Command Argus quit'}
+          </h2>
+          <p className="text-xs text-amber-700 mb-2">
+            These runs are recorded in history as interrupted; how they ended is unknown.
+          </p>
+          <ul className="text-sm">
+            {notice.runs.map(run => (
+              <li key={run.id}>
+                <span className="font-medium">{run.command_name}</span>
+                <span className="ml-2 text-xs text-gray-500">
+                  started {new Date(run.started_at).toLocaleString()}
+                  {run.interrupted === 'detached' && ', left running'}
+                </span>
+              </li>
+            ))}
+          </ul>
+        </div>
+        <button
+          onClick={() => setNotice(null)}
+          className="px-3 py-1 text-sm bg-amber-200 text-amber-800 rounded-md hover:bg-amber-300"
+        >
+          Dismiss
+        </button>
+      </div>
+    </div>
+  );
+}
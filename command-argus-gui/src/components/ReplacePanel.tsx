@@ -0,0 +1,152 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { Command, ReplaceChange, ReplaceScope } from '../types';
+
+const describeField = (change: ReplaceChange) => {
+  switch (change.field) {
+    case 'program':
+      return 'program';
+    case 'argument':
+      return `argument ${(change.index ?? 0) + 1}`;
+    case 'environment':
+      return `$${change.key}`;
+  }
+};
+
+// Regex find & replace across the library, previewed before it is applied
+export function ReplacePanel() {
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [pattern, setPattern] = useState('');
+  const [replacement, setReplacement] = useState('');
+  const [scopeKind, setScopeKind] = useState<ReplaceScope['kind']>('all');
+  const [tag, setTag] = useState('');
+  const [selected, setSelected] = useState<string[]>([]);
+  const [changes, setChanges] = useState<ReplaceChange[] | null>(null);
+  const [applied, setApplied] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<Command[]>('list_commands', { allPlatforms: false }).then(setCommands);
+  }, []);
+
+  const scope = (): ReplaceScope => {
+    switch (scopeKind) {
+      case 'tag':
+        return { kind: 'tag', tag };
+      case 'commands':
+        return { kind: 'commands', ids: selected };
+      default:
+        return { kind: 'all' };
+    }
+  };
+
+  const run = async (dryRun: boolean) => {
+    setError(null);
+    try {
+      const result = await invoke<ReplaceChange[]>('bulk_replace', { pattern, replacement, scope: scope(), dryRun });
+      setChanges(result);
+      setApplied(!dryRun);
+    } catch (e) {
+      setChanges(null);
+      setError(String(e));
+    }
+  };
+
+  const handleApply = async () => {
+    const count = changes?.length ?? 0;
+    const confirmed = await ask(`Apply ${count} change${count === 1 ? '' : 's'} to the library?`, { title: 'Replace', kind: 'warning' });
+    if (confirmed) {
+      await run(false);
+    }
+  };
+
+  const tags = Array.from(new Set(commands.flatMap(c => c.tags))).sort();
+
+  return (
+    <div className="space-y-3">
+      <h3 className="font-semibold">Find &amp; Replace</h3>
+      <div className="grid grid-cols-2 gap-2 text-sm">
+        <label>
+          Pattern (regex)
+          <input
+            value={pattern}
+            onChange={e => { setPattern(e.target.value); setChanges(null); }}
+            placeholder="registry\.old\.example\.com"
+            className="w-full px-2 py-1 border border-gray-300 rounded-md font-mono"
+          />
+        </label>
+        <label>
+          Replacement ($1 for groups)
+          <input
+            value={replacement}
+            onChange={e => { setReplacement(e.target.value); setChanges(null); }}
+            className="w-full px-2 py-1 border border-gray-300 rounded-md font-mono"
+          />
+        </label>
+      </div>
+
+      <div className="flex items-center gap-2 text-sm">
+        <span>In</span>
+        <select value={scopeKind} onChange={e => { setScopeKind(e.target.value as ReplaceScope['kind']); setChanges(null); }} className="px-2 py-1 border border-gray-300 rounded-md">
+          <option value="all">All commands</option>
+          <option value="tag">Commands tagged</option>
+          <option value="commands">Selected commands</option>
+        </select>
+        {scopeKind === 'tag' && (
+          <select value={tag} onChange={e => { setTag(e.target.value); setChanges(null); }} className="px-2 py-1 border border-gray-300 rounded-md">
+            <option value="">Choose a tag</option>
+            {tags.map(t => <option key={t} value={t}>{t}</option>)}
+          </select>
+        )}
+      </div>
+      {scopeKind === 'commands' && (
+        <div className="max-h-40 overflow-y-auto border border-gray-200 rounded-md p-2 text-sm">
+          {commands.map(command => (
+            <label key={command.id} className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={selected.includes(command.id)}
+                onChange={e => {
+                  setSelected(prev => e.target.checked ? [...prev, command.id] : prev.filter(id => id !== command.id));
+                  setChanges(null);
+                }}
+              />
+              {command.name}
+            </label>
+          ))}
+        </div>
+      )}
+
+      <div className="flex gap-2">
+        <button onClick={() => run(true)} disabled={!pattern} className="px-3 py-1 bg-gray-200 rounded-md hover:bg-gray-300 disabled:opacity-50 text-sm">
+          Preview
+        </button>
+        <button
+          onClick={handleApply}
+          disabled={!changes || changes.length === 0 || applied}
+          className="px-3 py-1 bg-blue-500 text-white rounded-md hover:bg-blue-600 disabled:opacity-50 text-sm"
+        >
+          Apply
+        </button>
+      </div>
+
+      {error && <p className="text-sm text-red-600">{error}</p>}
+      {changes && changes.length === 0 && <p className="text-sm text-gray-500">No matches.</p>}
+      {changes && changes.length > 0 && (
+        <div className="space-y-2 text-xs">
+          {applied && <p className="text-sm text-green-700">Applied {changes.length} change{changes.length === 1 ? '' : 's'}.</p>}
+          {changes.map((change, index) => (
+            <div key={index} className="border border-gray-200 rounded-md">
+              <div className="px-2 py-1 bg-gray-50 border-b border-gray-200">
+                {change.command_name} · {describeField(change)}
+              </div>
+              <pre className="px-2 py-1 font-mono whitespace-pre-wrap bg-red-50 text-red-800">- {change.before}</pre>
+              <pre className="px-2 py-1 font-mono whitespace-pre-wrap bg-green-50 text-green-800">+ {change.after}</pre>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,94 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Command, ExecutionSummary, ReplayOverrides } from '../types';
+
+interface ReplayPanelProps {
+  command: Command;
+  onReplay: (historyId: string, overrides: ReplayOverrides) => void;
+}
+
+// Runs the command again like one of its recent runs, with some values changed
+export function ReplayPanel({ command, onReplay }: ReplayPanelProps) {
+  const [runs, setRuns] = useState<ExecutionSummary[]>([]);
+  const [selected, setSelected] = useState<string>('');
+  const [values, setValues] = useState<Record<string, string>>({});
+  const [extraArgs, setExtraArgs] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<ExecutionSummary[]>('list_history', { commandId: command.id, limit: 10 })
+      .then(runs => {
+        setRuns(runs);
+        if (runs.length > 0) selectRun(runs[0]);
+      })
+      .catch(err => setError(String(err)));
+  }, [command.id]);
+
+  const selectRun = (run: ExecutionSummary) => {
+    setSelected(run.id);
+    setValues(run.parameters || {});
+  };
+
+  const handleReplay = async () => {
+    setError(null);
+    try {
+      const run = runs.find(r => r.id === selected);
+      const recorded = run?.parameters || {};
+      // Only the values that differ from the run are sent as overrides
+      const parameters = Object.fromEntries(
+        Object.entries(values).filter(([name, value]) => recorded[name] !== value)
+      );
+      const extra_args = extraArgs.trim() ? await invoke<string[]>('split_args', { line: extraArgs }) : [];
+      onReplay(selected, { parameters, extra_args });
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  if (error && runs.length === 0) return <div className="text-xs text-red-600">{error}</div>;
+  if (runs.length === 0) return <div className="text-xs text-gray-500">No runs to replay yet.</div>;
+
+  return (
+    <div className="p-2 bg-gray-50 border border-gray-200 rounded text-xs space-y-2">
+      <select
+        value={selected}
+        onChange={e => {
+          const run = runs.find(r => r.id === e.target.value);
+          if (run) selectRun(run);
+        }}
+        className="w-full px-2 py-1 border border-gray-300 rounded"
+      >
+        {runs.map(run => (
+          <option key={run.id} value={run.id}>
+            {new Date(run.started_at).toLocaleString()} — {run.success ? 'succeeded' : 'failed'} — {run.command_line}
+          </option>
+        ))}
+      </select>
+      {command.parameters.map(parameter => (
+        <div key={parameter.name} className="flex items-center gap-2">
+          <label className="w-32 font-mono">{parameter.name}</label>
+          <input
+            type={parameter.secret ? 'password' : 'text'}
+            value={values[parameter.name] ?? ''}
+            placeholder={parameter.secret ? 'not kept in history' : parameter.default_value}
+            onChange={e => setValues(prev => ({ ...prev, [parameter.name]: e.target.value }))}
+            className="flex-1 px-2 py-1 border border-gray-300 rounded font-mono"
+          />
+        </div>
+      ))}
+      <div className="flex items-center gap-2">
+        <label className="w-32">Extra arguments</label>
+        <input
+          value={extraArgs}
+          placeholder="--force"
+          onChange={e => setExtraArgs(e.target.value)}
+          className="flex-1 px-2 py-1 border border-gray-300 rounded font-mono"
+        />
+      </div>
+      {error && <div className="text-red-600">{error}</div>}
+      <button onClick={handleReplay} className="px-3 py-1 bg-blue-500 text-white rounded hover:bg-blue-600">
+        Replay
+      </button>
+    </div>
+  );
+}
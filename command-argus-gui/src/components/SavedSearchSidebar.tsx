@@ -0,0 +1,105 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { SavedSearch } from '../types';
+
+interface SavedSearchSidebarProps {
+  selected: SavedSearch | null;
+  onSelect: (search: SavedSearch | null) => void;
+}
+
+export function SavedSearchSidebar({ selected, onSelect }: SavedSearchSidebarProps) {
+  const [searches, setSearches] = useState<SavedSearch[]>([]);
+  const [name, setName] = useState('');
+  const [query, setQuery] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  const loadSearches = async () => {
+    try {
+      setSearches(await invoke<SavedSearch[]>('list_saved_searches'));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to load saved searches');
+    }
+  };
+
+  useEffect(() => {
+    loadSearches();
+  }, []);
+
+  useStoreUpdates(['saved_search'], delta => {
+    loadSearches();
+    // The folder being shown was changed or removed in another window
+    if (delta.entity === 'saved_search' && selected?.id === delta.id) onSelect(delta.saved_search ?? null);
+  });
+
+  const handleCreate = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!name.trim() || !query.trim()) return;
+    try {
+      const created = await invoke<SavedSearch>('create_saved_search', { name: name.trim(), query: query.trim() });
+      setName('');
+      setQuery('');
+      setError(null);
+      await loadSearches();
+      onSelect(created);
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to save search');
+    }
+  };
+
+  const handleDelete = async (search: SavedSearch) => {
+    try {
+      await invoke('delete_saved_search', { id: search.id });
+      if (selected?.id === search.id) onSelect(null);
+      await loadSearches();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to delete search');
+    }
+  };
+
+  const itemClass = (active: boolean) =>
+    `flex-1 text-left px-2 py-1 rounded text-sm truncate ${active ? 'bg-blue-100 text-blue-800' : 'hover:bg-gray-100'}`;
+
+  return (
+    <div className="w-56 shrink-0 space-y-2">
+      <button onClick={() => onSelect(null)} className={`w-full ${itemClass(selected === null)}`}>
+        All commands
+      </button>
+      {searches.map((search) => (
+        <div key={search.id} className="flex items-center gap-1">
+          <button onClick={() => onSelect(search)} title={search.query} className={itemClass(selected?.id === search.id)}>
+            {search.name}
+          </button>
+          <button
+            onClick={() => handleDelete(search)}
+            title="Delete saved search"
+            className="px-1 text-gray-400 hover:text-red-500 text-sm"
+          >
+            ×
+          </button>
+        </div>
+      ))}
+
+      <form onSubmit={handleCreate} className="space-y-1 pt-2 border-t border-gray-200">
+        <input
+          type="text"
+          value={name}
+          onChange={(e) => setName(e.target.value)}
+          placeholder="Folder name"
+          className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+        />
+        <input
+          type="text"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          placeholder="e.g. command:~kubectl last_used:>90d"
+          className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+        />
+        <button type="submit" className="w-full px-2 py-1 bg-blue-500 text-white rounded-md text-sm hover:bg-blue-600">
+          Save search
+        </button>
+        {error && <div className="text-xs text-red-500">{error}</div>}
+      </form>
+    </div>
+  );
+}
@@ -0,0 +1,99 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { RunningJob, ShutdownPolicy, ShutdownSettings } from '../types';
+
+const CHOICES: { policy: Exclude<ShutdownPolicy, 'ask'>; label: string; description: string }[] = [
+  { policy: 'wait', label: 'Wait', description: 'Quit once the commands have finished' },
+  { policy: 'terminate', label: 'Terminate', description: 'Stop the commands, killing those that do not exit in time' },
+  { policy: 'detach', label: 'Leave Running', description: 'Quit now; how the commands end is not recorded' }
+];
+
+// Asks what to do with running commands when the app is quit, if the shutdown policy
+// is to ask
+export function ShutdownModal() {
+  const [jobs, setJobs] = useState<RunningJob[] | null>(null);
+  const [remember, setRemember] = useState(false);
+  const [quitting, setQuitting] = useState<ShutdownPolicy | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<RunningJob[]>('shutdown-requested', event => {
+      setJobs(event.payload);
+      setError(null);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  if (!jobs) return null;
+
+  const handleChoose = async (policy: ShutdownPolicy) => {
+    try {
+      if (remember) {
+        const settings = await invoke<ShutdownSettings>('get_shutdown_settings');
+        await invoke('set_shutdown_settings', { shutdown: { ...settings, policy } });
+      }
+      await invoke('shut_down', { policy });
+      setQuitting(policy);
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to quit');
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+      <div className="bg-white rounded-lg p-6 w-full max-w-lg max-h-[80vh] overflow-y-auto">
+        <h2 className="text-xl font-bold mb-2">Commands are still running</h2>
+        <ul className="mb-4 text-sm">
+          {jobs.map(job => (
+            <li key={job.run_id} className="py-1">
+              <span className="font-medium">{job.command_name}</span>
+              <code className="ml-2 text-xs text-gray-500">{job.command_line}</code>
+              <span className="block text-xs text-gray-500">
+                Started {new Date(job.started_at).toLocaleTimeString()}, process {job.pid}
+              </span>
+            </li>
+          ))}
+        </ul>
+
+        {quitting ? (
+          <p className="text-sm text-gray-600">
+            {quitting === 'wait' ? 'Quitting once the commands have finished…' : 'Quitting…'}
+          </p>
+        ) : (
+          <>
+            <div className="flex flex-col gap-2 mb-4">
+              {CHOICES.map(choice => (
+                <button
+                  key={choice.policy}
+                  onClick={() => handleChoose(choice.policy)}
+                  className="px-4 py-2 text-left border border-gray-300 rounded-md hover:bg-gray-100"
+                >
+                  <span className="font-medium">{choice.label}</span>
+                  <span className="block text-xs text-gray-500">{choice.description}</span>
+                </button>
+              ))}
+            </div>
+            <label className="flex items-center gap-2 mb-4 text-sm cursor-pointer">
+              <input type="checkbox" checked={remember} onChange={e => setRemember(e.target.checked)} />
+              Do this every time
+            </label>
+
+            {error && <div className="mb-4 text-sm text-red-600">{error}</div>}
+
+            <div className="flex justify-end">
+              <button
+                onClick={() => setJobs(null)}
+                className="px-4 py-2 bg-gray-300 text-gray-700 rounded-md hover:bg-gray-400"
+              >
+                Keep Open
+              </button>
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  );
+}
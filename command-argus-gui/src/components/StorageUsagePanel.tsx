@@ -0,0 +1,149 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { ask, open, save } from '@tauri-apps/plugin-dialog';
+import { StateManifest, StorageStats, UsageCategory } from '../types';
+
+const CATEGORY_LABELS: Record<UsageCategory, string> = {
+  commands: 'Commands',
+  history: 'Execution history',
+  outputs: 'Stored outputs',
+  logs: 'Logs',
+  backups: 'Backups',
+  artifacts: 'Artifacts',
+  other: 'Other',
+};
+
+// How many executions "Prune history" keeps
+const KEEP_RUNS = 100;
+
+export function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+export function StorageUsagePanel() {
+  const [stats, setStats] = useState<StorageStats | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const loadStats = async () => {
+    try {
+      setStats(await invoke<StorageStats>('get_storage_stats'));
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to read storage usage');
+    }
+  };
+
+  useEffect(() => {
+    loadStats();
+  }, []);
+
+  useStoreUpdates(['execution', 'history'], () => loadStats());
+
+  const handlePrune = async () => {
+    const confirmed = await ask(`Delete all but the newest ${KEEP_RUNS} executions and their outputs?`, {
+      title: 'Prune history',
+      kind: 'warning',
+    });
+    if (!confirmed) return;
+    try {
+      const removed = await invoke<number>('prune_history', { keep: KEEP_RUNS });
+      setMessage(`Removed ${removed} executions`);
+      await loadStats();
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to prune history');
+    }
+  };
+
+  // Usage statistics of every command as CSV, for whoever reviews what the team runs
+  const handleExportStats = async () => {
+    const path = await save({ defaultPath: 'command-stats.csv', filters: [{ name: 'CSV', extensions: ['csv'] }] });
+    if (!path) return;
+    try {
+      const count = await invoke<number>('export_stats_csv', { path });
+      setMessage(`Exported statistics of ${count} commands`);
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to export statistics');
+    }
+  };
+
+  // Everything in one zip, for moving to another machine
+  const handleExportEverything = async () => {
+    const path = await save({ defaultPath: 'command-argus-state.zip', filters: [{ name: 'Zip', extensions: ['zip'] }] });
+    if (!path) return;
+    const includeHistory = await ask('Include the execution history with its outputs and artifacts?', { title: 'Export everything' });
+    try {
+      const manifest = await invoke<StateManifest>('export_everything', { path, includeHistory });
+      setMessage(`Exported ${manifest.files.length} files. The archive holds secrets as stored; keep it safe.`);
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to export');
+    }
+  };
+
+  const handleImportEverything = async () => {
+    const path = await open({ filters: [{ name: 'Zip', extensions: ['zip'] }] });
+    if (typeof path !== 'string') return;
+    const confirmed = await ask('Replace the commands, variables, profiles, settings and saved searches with the ones in the archive? The current ones are moved to backups.', {
+      title: 'Import everything',
+      kind: 'warning',
+    });
+    if (!confirmed) return;
+    const includeHistory = await ask('Also replace the execution history, if the archive has it?', { title: 'Import everything' });
+    try {
+      const manifest = await invoke<StateManifest>('import_everything', { path, includeHistory });
+      setMessage(`Imported the state exported on ${new Date(manifest.exported_at).toLocaleString()}`);
+      await loadStats();
+    } catch (err) {
+      setMessage(typeof err === 'string' ? err : 'Failed to import');
+    }
+  };
+
+  if (!stats) {
+    return <div className="text-sm text-gray-500">{message ?? 'Loading...'}</div>;
+  }
+
+  return (
+    <div className="space-y-3">
+      <div className="text-sm text-gray-600">
+        {formatBytes(stats.total_bytes)} in <span className="font-mono">{stats.data_dir}</span>
+      </div>
+      <table className="w-full text-sm">
+        <tbody>
+          {stats.entries.map((entry) => (
+            <tr key={entry.category} className="border-b border-gray-100">
+              <td className="py-1">{CATEGORY_LABELS[entry.category]}</td>
+              <td className="py-1 text-right">{entry.items}</td>
+              <td className="py-1 text-right">{formatBytes(entry.bytes)}</td>
+              <td className="py-1 text-right">
+                {(entry.category === 'history' || entry.category === 'outputs') && entry.items > 0 && (
+                  <button onClick={handlePrune} className="text-blue-600 hover:underline">
+                    Keep last {KEEP_RUNS}
+                  </button>
+                )}
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      {stats.history.compressed_outputs > 0 && (
+        <div className="text-xs text-gray-500">
+          {stats.history.compressed_outputs} outputs compressed from {formatBytes(stats.history.uncompressed_bytes)} to{' '}
+          {formatBytes(stats.history.compressed_bytes)}
+        </div>
+      )}
+      <div className="flex gap-4">
+        <button onClick={handleExportStats} className="text-sm text-blue-600 hover:underline">
+          Export command statistics (CSV)
+        </button>
+        <button onClick={handleExportEverything} className="text-sm text-blue-600 hover:underline">
+          Export everything
+        </button>
+        <button onClick={handleImportEverything} className="text-sm text-blue-600 hover:underline">
+          Import everything
+        </button>
+      </div>
+      {message && <div className="text-sm text-gray-600">{message}</div>}
+    </div>
+  );
+}
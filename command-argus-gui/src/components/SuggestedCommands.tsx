@@ -0,0 +1,58 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { EnvironmentReport, StarterCommand } from '../types';
+
+interface SuggestedCommandsProps {
+  refreshTrigger: number;
+  onAdded: () => void;
+}
+
+// "Suggested for you": starter commands for the installed tools that are not in the library yet
+export function SuggestedCommands({ refreshTrigger, onAdded }: SuggestedCommandsProps) {
+  const [report, setReport] = useState<EnvironmentReport | null>(null);
+  const [hidden, setHidden] = useState(false);
+
+  useEffect(() => {
+    invoke<EnvironmentReport>('detect_environment')
+      .then(setReport)
+      .catch(err => console.error('Failed to detect environment:', err));
+  }, [refreshTrigger]);
+
+  if (!report || hidden || report.suggestions.length === 0) return null;
+
+  const handleAdd = async (starter: StarterCommand) => {
+    try {
+      await invoke('seed_starter_commands', { selection: [starter.key] });
+      onAdded();
+    } catch (err) {
+      console.error('Failed to add suggested command:', err);
+    }
+  };
+
+  const installed = report.tools.filter(t => t.path).map(t => t.name);
+
+  return (
+    <div className="mb-4 p-3 bg-blue-50 border border-blue-200 rounded-lg">
+      <div className="flex justify-between items-center mb-2">
+        <h3 className="text-sm font-semibold text-blue-900">
+          Suggested for you <span className="font-normal text-blue-700">({installed.join(', ')} detected)</span>
+        </h3>
+        <button onClick={() => setHidden(true)} className="text-xs text-blue-700 hover:underline">
+          Hide
+        </button>
+      </div>
+      <div className="flex flex-wrap gap-2">
+        {report.suggestions.map(starter => (
+          <button
+            key={starter.key}
+            onClick={() => handleAdd(starter)}
+            title={`${[starter.command, ...starter.args].join(' ')}\n${starter.description}`}
+            className="px-2 py-1 text-xs bg-white border border-blue-300 rounded hover:bg-blue-100"
+          >
+            + {starter.name}
+          </button>
+        ))}
+      </div>
+    </div>
+  );
+}
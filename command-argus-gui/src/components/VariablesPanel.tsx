@@ -0,0 +1,175 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStoreUpdates } from '../storeUpdates';
+import { Command, Variable, Variables, VariableScope, VariableTrace } from '../types';
+
+function describeScope(scope?: VariableScope): string {
+  if (!scope) return 'undefined';
+  switch (scope.kind) {
+    case 'global':
+      return 'global';
+    case 'workspace':
+      return `workspace ${scope.root}`;
+    case 'profile':
+      return `profile ${scope.name}`;
+  }
+}
+
+interface VariablesPanelProps {
+  // Profile whose variables take part in traces
+  profile?: string | null;
+}
+
+// Template variables used as {{var:NAME}}, and where a command's values come from
+export function VariablesPanel({ profile }: VariablesPanelProps) {
+  const [variables, setVariables] = useState<Variables>({ variables: [] });
+  const [commands, setCommands] = useState<Command[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const [name, setName] = useState('');
+  const [value, setValue] = useState('');
+  const [inWorkspace, setInWorkspace] = useState(false);
+  const [commandId, setCommandId] = useState('');
+  const [traces, setTraces] = useState<VariableTrace[] | null>(null);
+
+  const loadVariables = async () => {
+    try {
+      setVariables(await invoke<Variables>('list_variables'));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to load variables');
+    }
+  };
+
+  useEffect(() => {
+    loadVariables();
+    invoke<Command[]>('list_commands', { allPlatforms: true }).then(setCommands);
+  }, []);
+
+  // Opening or closing a project changes which workspace variables apply
+  useStoreUpdates(['variable', 'commands'], () => loadVariables());
+
+  const handleSet = async (e: React.FormEvent) => {
+    e.preventDefault();
+    try {
+      setError(null);
+      await invoke('set_variable', { name: name.trim(), value, inWorkspace });
+      setName('');
+      setValue('');
+      await loadVariables();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to set the variable');
+    }
+  };
+
+  const handleDelete = async (variable: Variable) => {
+    try {
+      await invoke('delete_variable', { name: variable.name, scope: variable.scope });
+      await loadVariables();
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to delete the variable');
+    }
+  };
+
+  const handleTrace = async (id: string) => {
+    setCommandId(id);
+    setTraces(null);
+    if (!id) return;
+    try {
+      setTraces(await invoke<VariableTrace[]>('trace_variables', { id, profile }));
+    } catch (err) {
+      setError(typeof err === 'string' ? err : 'Failed to trace the variables');
+    }
+  };
+
+  return (
+    <div>
+      <h2 className="text-lg font-semibold text-gray-800 mb-1">Variables</h2>
+      <p className="text-sm text-gray-500 mb-3">
+        {variables.workspace
+          ? `Variables of ${variables.workspace} override global ones.`
+          : 'Open a project to set variables for its workspace.'}
+      </p>
+      {error && <p className="mb-2 text-sm text-red-600">{error}</p>}
+      <ul className="mb-3 divide-y divide-gray-100 text-sm">
+        {variables.variables.map(variable => (
+          <li key={`${describeScope(variable.scope)}:${variable.name}`} className="flex items-center gap-3 py-1">
+            <span className="w-40 font-mono text-gray-800">{variable.name}</span>
+            <span className="flex-1 font-mono text-gray-700 truncate">{variable.value}</span>
+            <span className="text-gray-500">{describeScope(variable.scope)}</span>
+            <button onClick={() => handleDelete(variable)} className="text-red-600 hover:text-red-800">
+              Delete
+            </button>
+          </li>
+        ))}
+      </ul>
+      <form onSubmit={handleSet} className="flex items-center gap-2 mb-4">
+        <input
+          type="text"
+          value={name}
+          onChange={e => setName(e.target.value)}
+          placeholder="NAME"
+          className="w-40 px-3 py-1 border border-gray-300 rounded-md text-sm font-mono focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        <input
+          type="text"
+          value={value}
+          onChange={e => setValue(e.target.value)}
+          placeholder="value"
+          className="flex-1 px-3 py-1 border border-gray-300 rounded-md text-sm font-mono focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        <label className="flex items-center gap-1 text-sm text-gray-700">
+          <input
+            type="checkbox"
+            checked={inWorkspace}
+            disabled={!variables.workspace}
+            onChange={e => setInWorkspace(e.target.checked)}
+          />
+          Workspace
+        </label>
+        <button
+          type="submit"
+          disabled={!name.trim()}
+          className="px-3 py-1 text-sm bg-blue-500 text-white rounded hover:bg-blue-600 disabled:opacity-50"
+        >
+          Set
+        </button>
+      </form>
+      <div className="flex items-center gap-2 text-sm">
+        <span className="text-gray-700">Trace</span>
+        <select
+          value={commandId}
+          onChange={e => handleTrace(e.target.value)}
+          className="px-2 py-1 border border-gray-300 rounded-md"
+        >
+          <option value="">Choose a command...</option>
+          {commands.map(command => (
+            <option key={command.id} value={command.id}>
+              {command.name}
+            </option>
+          ))}
+        </select>
+      </div>
+      {traces && traces.length === 0 && <p className="mt-2 text-sm text-gray-500">The command uses no variables.</p>}
+      {traces && traces.length > 0 && (
+        <ul className="mt-2 space-y-1 text-sm">
+          {traces.map(trace => (
+            <li key={trace.name}>
+              <span className="font-mono">{`{{var:${trace.name}}}`}</span>
+              {trace.value != null ? (
+                <span className="text-gray-700">
+                  {' '}= <span className="font-mono">{trace.value}</span> from {describeScope(trace.scope)}
+                </span>
+              ) : (
+                <span className="text-red-600"> is undefined</span>
+              )}
+              {trace.shadowed.map(variable => (
+                <span key={describeScope(variable.scope)} className="text-gray-400">
+                  {' '}(overrides <span className="font-mono">{variable.value}</span> from {describeScope(variable.scope)})
+                </span>
+              ))}
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,20 @@
+import { useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { StoreDelta } from './types';
+
+// Calls `onUpdate` for changes to the given kinds of data, wherever they were made,
+// so that a window shows what another window changed
+export function useStoreUpdates(entities: StoreDelta['entity'][], onUpdate: (delta: StoreDelta) => void) {
+  // The latest callback, so the listener does not see stale state
+  const handler = useRef(onUpdate);
+  handler.current = onUpdate;
+
+  useEffect(() => {
+    const unlisten = listen<StoreDelta>('store-updated', ({ payload }) => {
+      if (entities.includes(payload.entity)) handler.current(payload);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [entities.join()]);
+}
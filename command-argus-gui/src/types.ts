@@ -1,6 +1,7 @@
 export interface EnvironmentVariable {
   key: string;
   value: string;
+  secret?: boolean;
 }
 
 export interface CommandParameter {
@@ -10,6 +11,137 @@ export interface CommandParameter {
   required: boolean;
   default_value?: string;
   options?: string[];
+  secret?: boolean;
+}
+
+export type Platform = 'macos' | 'linux' | 'windows';
+
+export interface PlatformOverride {
+  platform: Platform;
+  command: string;
+  args?: string[];
+  working_directory?: string;
+  environment_variables?: EnvironmentVariable[];
+}
+
+export type ShellKind = 'system' | 'powershell';
+
+export type ExecutionMode = 'direct' | 'shell' | 'terminal' | 'pty';
+
+export type OutputEncoding =
+  | 'auto'
+  | 'utf8'
+  | 'shift_jis'
+  | 'euc_jp'
+  | 'gbk'
+  | 'big5'
+  | 'euc_kr'
+  | 'windows1252';
+
+export interface SandboxPolicy {
+  enabled: boolean;
+  network: boolean;
+  writable_paths: string[];
+}
+
+export interface OpenOutput {
+  // Opened with the default application when missing
+  program?: string;
+  extension: string;
+  decode_base64: boolean;
+}
+
+// Indexes of the regex capture groups holding each part of a problem
+export interface ProblemMatcher {
+  pattern: string;
+  file: number;
+  line?: number;
+  column?: number;
+  severity?: number;
+  message: number;
+}
+
+export interface ScratchDirPolicy {
+  enabled: boolean;
+  keep_on_failure: boolean;
+}
+
+export interface ToolCheck {
+  tool: string;
+  requirement: string;
+  version_args?: string[];
+  block: boolean;
+}
+
+export type Precondition =
+  | { kind: 'path_exists'; path: string }
+  | { kind: 'env_set'; name: string }
+  | { kind: 'port_free'; port: number; host?: string }
+  | { kind: 'disk_space'; path: string; min_bytes: number };
+
+export interface PreflightFailure {
+  check: Precondition;
+  message: string;
+}
+
+export type Assertion =
+  | { kind: 'stdout_contains'; text: string }
+  | { kind: 'stdout_matches'; pattern: string }
+  | { kind: 'exit_code_in'; codes: number[] }
+  | { kind: 'duration_under'; seconds: number };
+
+export interface AssertionResult {
+  assertion: Assertion;
+  passed: boolean;
+  message?: string;
+}
+
+export interface QueuedExecution {
+  id: string;
+  command_id: string;
+  command_name: string;
+  priority: number;
+  position: number;
+  mutex_group?: string;
+  waiting_on?: string;
+}
+
+export type Impact = 'safe' | 'caution' | 'destructive';
+
+export interface RateLimit {
+  max_runs: number;
+  per: 'minute' | 'hour';
+}
+
+export type CommandSource =
+  | { kind: 'manual' }
+  | { kind: 'imported'; from: string }
+  | { kind: 'synced'; from: string };
+
+export interface BundleEntry {
+  id: string;
+  name: string;
+}
+
+export interface FieldChange {
+  field: string;
+  before: unknown;
+  after: unknown;
+}
+
+export interface BundleChangelog {
+  since: string;
+  added: BundleEntry[];
+  removed: BundleEntry[];
+  modified: { id: string; name: string; fields: FieldChange[] }[];
+}
+
+export interface SavedSearch {
+  id: string;
+  name: string;
+  query: string;
+  created_at: string;
+  updated_at: string;
 }
 
 export type ParameterType = 'text' | 'file' | 'directory' | 'select';
@@ -29,8 +161,47 @@ export interface Command {
   use_count: number;
   parameters: CommandParameter[];
   mise_enabled: boolean;
+  requires_approval: boolean;
+  no_network: boolean;
+  output_encoding: OutputEncoding;
+  shell: ShellKind;
+  load_shell_profile: boolean;
+  default_execution_mode: ExecutionMode;
+  platforms: Platform[];
+  platform_overrides: PlatformOverride[];
+  pinned_executable?: string;
+  pin_warning?: string;
+  tool_checks: ToolCheck[];
+  preconditions: Precondition[];
+  assertions: Assertion[];
+  priority: number;
+  mutex_group?: string;
+  rate_limit?: RateLimit;
+  notifications?: CommandNotifications;
+  impact: Impact;
+  author?: string;
+  source: CommandSource;
+  maintainer_note?: string;
+  profiles: string[];
+  forbidden_profiles: string[];
+  collect_artifacts: string[];
+  scratch_dir: ScratchDirPolicy;
+  stdin_template?: string;
+  record_stdin: boolean;
+  capture_timeline?: boolean;
+  // Regex for lines that start a named phase, e.g. ^==> (.*)
+  phase_marker?: string;
+  open_output_with?: OpenOutput;
+  problem_matchers: ProblemMatcher[];
+  sandbox: SandboxPolicy;
+  capabilities: string[];
+  read_only: boolean;
+  project_root?: string;
+  health: CommandHealth;
 }
 
+export type CommandHealth = 'healthy' | 'flaky' | 'broken' | 'unknown';
+
 export interface CreateCommandRequest {
   name: string;
   command: string;
@@ -41,6 +212,36 @@ export interface CreateCommandRequest {
   tags: string[];
   parameters: CommandParameter[];
   mise_enabled?: boolean;
+  requires_approval?: boolean;
+  no_network?: boolean;
+  output_encoding?: OutputEncoding;
+  shell?: ShellKind;
+  load_shell_profile?: boolean;
+  default_execution_mode?: ExecutionMode;
+  platforms?: Platform[];
+  platform_overrides?: PlatformOverride[];
+  pin_executable?: boolean;
+  tool_checks?: ToolCheck[];
+  preconditions?: Precondition[];
+  assertions?: Assertion[];
+  priority?: number;
+  mutex_group?: string;
+  rate_limit?: RateLimit | null;
+  notifications?: CommandNotifications | null;
+  impact?: Impact;
+  maintainer_note?: string;
+  profiles?: string[];
+  forbidden_profiles?: string[];
+  collect_artifacts?: string[];
+  scratch_dir?: ScratchDirPolicy;
+  stdin_template?: string;
+  record_stdin?: boolean;
+  capture_timeline?: boolean;
+  // Regex for lines that start a named phase, e.g. ^==> (.*)
+  phase_marker?: string;
+  open_output_with?: OpenOutput | null;
+  problem_matchers?: ProblemMatcher[];
+  sandbox?: SandboxPolicy;
 }
 
 export interface UpdateCommandRequest {
@@ -53,11 +254,708 @@ export interface UpdateCommandRequest {
   tags?: string[];
   parameters?: CommandParameter[];
   mise_enabled?: boolean;
+  requires_approval?: boolean;
+  no_network?: boolean;
+  output_encoding?: OutputEncoding;
+  shell?: ShellKind;
+  load_shell_profile?: boolean;
+  default_execution_mode?: ExecutionMode;
+  platforms?: Platform[];
+  platform_overrides?: PlatformOverride[];
+  pin_executable?: boolean;
+  tool_checks?: ToolCheck[];
+  preconditions?: Precondition[];
+  assertions?: Assertion[];
+  priority?: number;
+  mutex_group?: string;
+  rate_limit?: RateLimit | null;
+  notifications?: CommandNotifications | null;
+  impact?: Impact;
+  maintainer_note?: string;
+  profiles?: string[];
+  forbidden_profiles?: string[];
+  collect_artifacts?: string[];
+  scratch_dir?: ScratchDirPolicy;
+  stdin_template?: string;
+  record_stdin?: boolean;
+  capture_timeline?: boolean;
+  // Regex for lines that start a named phase, e.g. ^==> (.*)
+  phase_marker?: string;
+  open_output_with?: OpenOutput | null;
+  problem_matchers?: ProblemMatcher[];
+  sandbox?: SandboxPolicy;
 }
 
 export interface ExecutionResult {
+  execution_id?: string;
+  // The first lines of each stream; the rest is loaded with get_execution_output
   stdout: string;
   stderr: string;
+  stdout_lines: number;
+  stderr_lines: number;
   exit_code: number;
   success: boolean;
-}
\ No newline at end of file
+  warnings: string[];
+  duration_ms: number;
+  assertion_results: AssertionResult[];
+  artifacts?: Artifact[];
+  // Folder the artifacts were copied into
+  artifacts_dir?: string;
+  // URLs and files mentioned in the output
+  links?: OutputLink[];
+  // Errors, warnings and passes in stdout and stderr
+  highlights?: Highlight[];
+  // Found by the command's problem matchers
+  problems?: Problem[];
+  // Both streams in the order they were written, for commands capturing a timeline
+  timeline?: OutputEvent[];
+  // Named by the command's phase markers
+  phases?: Phase[];
+}
+
+// A file copied out of the working directory after a run
+export interface Artifact {
+  path: string;
+  bytes: number;
+}
+export type OutputStream = 'stdout' | 'stderr';
+
+export type LinkKind = 'url' | 'path';
+
+export type Severity = 'error' | 'warning' | 'info' | 'success';
+
+// A span of output matched by a highlight rule; columns count UTF-16 code units
+export interface Highlight {
+  stream: OutputStream;
+  line: number;
+  start: number;
+  end: number;
+  severity: Severity;
+  color?: string;
+}
+
+export interface HighlightRule {
+  pattern: string;
+  severity: Severity;
+  color?: string;
+}
+
+// A change broadcast by the backend as "store-updated" to every window. Entities carry
+// their new value, or none when removed; 'commands' and 'history' mean a whole list changed.
+export type StoreDelta =
+  | { entity: 'command'; id: string; command?: Command }
+  | { entity: 'commands' }
+  | { entity: 'saved_search'; id: string; saved_search?: SavedSearch }
+  | { entity: 'setting'; name: string; value: unknown }
+  | { entity: 'approval'; request: ApprovalRequest }
+  | { entity: 'variable'; name: string; scope: VariableScope; variable?: Variable }
+  | { entity: 'profile'; name: string; profile?: Profile }
+  | { entity: 'notification_rule'; id: string; rule?: NotificationRule }
+  | { entity: 'execution'; summary: ExecutionSummary }
+  | { entity: 'history' };
+
+// A gist or paste service runs can be shared to, picked by name
+export type ShareTarget =
+  | { name: string; kind: 'gist'; token: string; public?: boolean }
+  | { name: string; kind: 'paste'; url: string };
+
+// An error or warning a tool reported, pointing at a place in a file
+export interface Problem {
+  // The file as the tool named it, and its absolute path if it exists
+  file: string;
+  path?: string;
+  line?: number;
+  column?: number;
+  severity: Severity;
+  message: string;
+  stream: OutputStream;
+  output_line: number;
+}
+
+// A URL or existing file found in a command's output
+export interface OutputLink {
+  kind: LinkKind;
+  // The URL, or the absolute path of the file
+  target: string;
+  // The text as it appeared in the output
+  text: string;
+  stream: OutputStream;
+  line: number;
+}
+
+export interface OutputChunk {
+  offset: number;
+  lines: string[];
+  total_lines: number;
+}
+
+export interface ExecutionSummary {
+  id: string;
+  command_id: string;
+  command_name: string;
+  command_line: string;
+  started_at: string;
+  finished_at: string;
+  exit_code?: number;
+  success: boolean;
+  error?: string;
+  // Set for runs a quit of the app cut short or left behind
+  interrupted?: Interruption;
+  // Values the parameters were given; secret ones are left out
+  parameters?: Record<string, string>;
+  phases?: Phase[];
+  // Added afterwards to find the run again
+  note?: string;
+  tags?: string[];
+  stdout_lines: number;
+  stderr_lines: number;
+  stdout_bytes: number;
+  stderr_bytes: number;
+}
+
+export interface RecentFailure {
+  command: Command;
+  execution: ExecutionSummary;
+  stderr_snippet: string;
+}
+
+export type UsageCategory = 'commands' | 'history' | 'outputs' | 'logs' | 'backups' | 'artifacts' | 'other';
+
+export interface UsageEntry {
+  category: UsageCategory;
+  bytes: number;
+  items: number;
+}
+
+export interface StorageStats {
+  data_dir: string;
+  total_bytes: number;
+  entries: UsageEntry[];
+  history: {
+    records: number;
+    history_file_bytes: number;
+    compressed_outputs: number;
+    compressed_bytes: number;
+    uncompressed_bytes: number;
+  };
+}
+
+export interface StarterCommand {
+  key: string;
+  tool: string;
+  name: string;
+  command: string;
+  args: string[];
+  description: string;
+  impact: Impact;
+}
+
+export interface DetectedTool {
+  name: string;
+  path?: string;
+}
+
+export interface EnvironmentReport {
+  tools: DetectedTool[];
+  suggestions: StarterCommand[];
+}
+
+export interface Onboarding {
+  needed: boolean;
+  starters: StarterCommand[];
+}
+
+export type NotificationTrigger =
+  | { kind: 'command_failed'; command_id: string }
+  | { kind: 'any_failure' }
+  | { kind: 'tag_ran'; tag: string }
+  | { kind: 'duration_exceeded'; seconds: number };
+
+export type NotificationTarget =
+  | { kind: 'desktop' }
+  | { kind: 'webhook'; url: string }
+  | { kind: 'slack'; url: string }
+  | { kind: 'discord'; url: string };
+
+export interface CommandNotifications {
+  on: 'failure' | 'always';
+  targets: NotificationTarget[];
+}
+
+export interface NotificationFormat {
+  message_template: string;
+  output_link?: string;
+}
+
+export interface NotificationRule {
+  id: string;
+  name: string;
+  trigger: NotificationTrigger;
+  targets: NotificationTarget[];
+  enabled: boolean;
+}
+
+export interface AppNotification {
+  rule_id?: string;
+  rule_name?: string;
+  title: string;
+  body: string;
+  command_id: string;
+  command_name: string;
+  execution_id: string;
+  success: boolean;
+  exit_code?: number;
+  duration_ms: number;
+}
+
+export interface StepOutcome {
+  step_id: string;
+  command: string;
+  success: boolean;
+  skipped?: string;
+  exit_code?: number;
+  duration_ms: number;
+  stdout: string;
+  stderr: string;
+  error?: string;
+  assertion_results: AssertionResult[];
+}
+
+export interface BatchReport {
+  run_id: string;
+  outcomes: StepOutcome[];
+  duration_ms: number;
+  summary: string;
+}
+
+// Result of a command run in one of the directories matching a glob
+export interface DirectoryOutcome {
+  // Relative to the folder that was searched
+  directory: string;
+  success: boolean;
+  exit_code?: number;
+  duration_ms: number;
+  stdout: string;
+  stderr: string;
+  error?: string;
+}
+
+export interface ForEachReport {
+  root: string;
+  outcomes: DirectoryOutcome[];
+  duration_ms: number;
+  summary: string;
+}
+
+export interface BenchmarkRun {
+  iteration: number;
+  exit_code: number;
+  success: boolean;
+  duration_ms: number;
+}
+
+export interface BenchmarkStats {
+  min_ms: number;
+  max_ms: number;
+  mean_ms: number;
+  median_ms: number;
+  p95_ms: number;
+  stddev_ms: number;
+}
+
+export interface BenchmarkReport {
+  command_name: string;
+  warmup: number;
+  runs: BenchmarkRun[];
+  stats: BenchmarkStats;
+  summary: string;
+}
+
+export interface BenchmarkComparison {
+  a: BenchmarkReport;
+  b: BenchmarkReport;
+  faster: 'a' | 'b';
+  // How many times longer the slower command takes on average
+  ratio: number;
+  ratio_stddev: number;
+  confidence: 'high' | 'medium' | 'low';
+  summary: string;
+}
+
+export type LintRule = 'unquoted_placeholder' | 'cd_chain' | 'hardcoded_secret' | 'deprecated';
+
+export type LintFix =
+  | { kind: 'set_working_directory'; directory: string; command: string; args: string[] }
+  | { kind: 'replace_arg'; index: number; with?: string }
+  | { kind: 'replace_program'; command: string; prepend_args: string[] }
+  | { kind: 'mark_secret'; variable: string };
+
+export interface LintSuggestion {
+  rule: LintRule;
+  severity: Severity;
+  message: string;
+  // Set when the suggestion can be applied as it is
+  fix?: LintFix;
+}
+
+// A part of a command that could become a parameter
+export interface ParameterCandidate {
+  text: string;
+  kind: 'path' | 'hostname' | 'version';
+  // A free name for the parameter
+  name: string;
+  parameter_type: ParameterType;
+}
+
+export interface ParameterSelection {
+  text: string;
+  name: string;
+  parameter_type?: ParameterType;
+}
+
+export interface ExplainedPart {
+  text: string;
+  kind: 'program' | 'subcommand' | 'flag' | 'value' | 'argument' | 'operator';
+  // Missing where the built-in database does not know the part
+  meaning?: string;
+}
+
+export interface CommandExplanation {
+  parts: ExplainedPart[];
+  // name is `var:NAME` for a template variable
+  placeholders: { name: string; role: string }[];
+  working_directory?: string;
+}
+
+export interface HelpText {
+  program: string;
+  version?: string;
+  source: 'help' | 'man';
+  text: string;
+  fetched_at: string;
+}
+
+export interface ShellAlias {
+  name: string;
+  expansion: string;
+  source: string;
+}
+
+export interface AliasOffer {
+  alias: ShellAlias;
+  command?: string;
+  args: string[];
+  error?: string;
+}
+
+export interface SyntaxWarning {
+  message: string;
+  offset?: number;
+}
+
+export interface DockerRunBuilder {
+  tool: 'docker_run';
+  image: string;
+  tag?: string;
+  container_name?: string;
+  ports: { host: string; container: string; protocol?: string }[];
+  volumes: { source: string; target: string; read_only: boolean }[];
+  environment: EnvironmentVariable[];
+  workdir?: string;
+  remove: boolean;
+  detach: boolean;
+  interactive: boolean;
+  command: string[];
+}
+
+export interface SshBuilder {
+  tool: 'ssh';
+  host: string;
+  user?: string;
+  port?: string;
+  identity_file?: string;
+  jump_host?: string;
+  forwards: { local_port: string; remote_host: string; remote_port: string }[];
+  remote_command?: string;
+}
+
+export type CommandBuilder = DockerRunBuilder | SshBuilder;
+
+export type ReplaceScope =
+  | { kind: 'all' }
+  | { kind: 'commands'; ids: string[] }
+  | { kind: 'tag'; tag: string };
+
+export interface ReplaceChange {
+  command_id: string;
+  command_name: string;
+  field: 'program' | 'argument' | 'environment';
+  index?: number;
+  key?: string;
+  before: string;
+  after: string;
+}
+
+export interface StateManifest {
+  format_version: number;
+  app_version: string;
+  exported_at: string;
+  includes_history: boolean;
+  files: { path: string; bytes: number; sha256: string }[];
+}
+
+export type StateCategory = 'commands' | 'variables' | 'profiles' | 'settings' | 'saved_searches' | 'history';
+
+export interface MigrationPreview {
+  source: { kind: 'archive' | 'data_dir'; path: string };
+  exported_at?: string;
+  items: { category: StateCategory; files: number; bytes: number; entries?: number }[];
+  old_home?: string;
+  new_home?: string;
+}
+
+export interface PathRemapRule {
+  from: string;
+  to: string;
+}
+
+export type PathField = 'program' | 'argument' | 'environment' | 'working_directory';
+
+export interface PathRemapReport {
+  remapped: { command_name: string; field: PathField; index?: number; key?: string; before: string; after: string }[];
+  unmapped: { command_name: string; field: PathField; index?: number; key?: string; path: string }[];
+}
+
+export interface ImportOutcome {
+  created: Command[];
+  paths: PathRemapReport;
+}
+
+export interface DeadPath {
+  command_id: string;
+  command_name: string;
+  kind: 'working_directory' | 'pinned_executable' | 'parameter_default';
+  parameter?: string;
+  path: string;
+  fix: string;
+  replacement?: string;
+}
+
+export type EnvSource = 'inherited' | 'app' | 'context' | 'command' | 'profile';
+
+export interface EnvEntry {
+  key: string;
+  value: string;
+  source: EnvSource;
+  replaces?: string;
+  secret: boolean;
+}
+
+export interface EnvironmentPreview {
+  overrides: EnvEntry[];
+  inherited: EnvEntry[];
+}
+
+// What to change when running a past run again
+export interface ReplayOverrides {
+  parameters: Record<string, string>;
+  extra_args: string[];
+}
+
+// Output a followed run wrote since the last event
+export interface OutputTailEvent {
+  execution_id: string;
+  stream: OutputStream;
+  text: string;
+  // The run ended or is no longer followed; no more events follow
+  finished: boolean;
+}
+
+// A line of output with when it was written, counted from the start of the process
+export interface OutputEvent {
+  at_ms: number;
+  stream: OutputStream;
+  line: string;
+}
+
+// A stretch of a run started by a phase marker line, e.g. compile or test
+export interface Phase {
+  name: string;
+  started_ms: number;
+  duration_ms: number;
+}
+
+// A command of a batch plan that must be confirmed before the plan runs
+export interface ImpactStep {
+  command: string;
+  impact: Impact;
+}
+
+export type BatchEvent =
+  | { kind: 'step_started'; run_id: string; step_id: string; command: string }
+  | {
+      kind: 'step_finished';
+      run_id: string;
+      step_id: string;
+      command: string;
+      success: boolean;
+      skipped: boolean;
+      exit_code?: number;
+      duration_ms: number;
+    }
+  | { kind: 'progress'; run_id: string; completed: number; total: number; percent: number };
+
+export type PromptKind =
+  | { kind: 'confirm' }
+  | { kind: 'choose'; options: string[] }
+  | { kind: 'input'; default?: string };
+
+export type PromptRequest = PromptKind & {
+  run_id: string;
+  step_id: string;
+  question: string;
+  error?: string;
+};
+
+export type VariableScope =
+  | { kind: 'global' }
+  | { kind: 'workspace'; root: string }
+  | { kind: 'profile'; name: string };
+
+export interface Variable {
+  name: string;
+  value: string;
+  scope: VariableScope;
+  updated_at: string;
+}
+
+export interface Variables {
+  workspace?: string;
+  variables: Variable[];
+}
+
+export interface VariableTrace {
+  name: string;
+  value?: string;
+  scope?: VariableScope;
+  shadowed: Variable[];
+}
+
+export interface Profile {
+  name: string;
+  description?: string;
+  environment_variables: EnvironmentVariable[];
+  variables: Record<string, string>;
+  updated_at?: string;
+}
+
+export type WorkingDirectoryBase = 'home' | 'last_project';
+
+export type CiTarget = 'github_actions' | 'gitlab_ci';
+
+export interface SyncConfig {
+  url: string;
+  token: string;
+  user: string;
+}
+
+export interface SyncConflict {
+  id: string;
+  name: string;
+  owner: string;
+  local_updated_at: string;
+  remote_updated_at: string;
+  resolution: 'kept_local' | 'took_remote';
+}
+
+export interface SyncReport {
+  pulled: string[];
+  pushed: string[];
+  rejected: string[];
+  conflicts: SyncConflict[];
+}
+
+export type ApprovalStatus = 'pending' | 'approved' | 'rejected' | 'executed';
+
+export interface ApprovalRequest {
+  id: string;
+  command_id: string;
+  command_name: string;
+  parameters: Record<string, string>;
+  use_shell: boolean;
+  requested_by: string;
+  requested_at: string;
+  status: ApprovalStatus;
+  decided_by?: string;
+  decided_at?: string;
+}
+
+export interface ExecutionRecord {
+  id: string;
+  command_id: string;
+  command_name: string;
+  command_line: string;
+  started_at: string;
+  finished_at: string;
+  exit_code?: number;
+  success: boolean;
+  stdout: string;
+  stderr: string;
+  error?: string;
+}
+
+// What a launch of the app asks for, e.g. `--run <command>` or a command-argus://run/ link
+export type LaunchRequest = { kind: 'run'; command: string };
+
+// What happens to running commands when the app quits
+export type ShutdownPolicy = 'ask' | 'wait' | 'terminate' | 'detach';
+
+export interface ShutdownSettings {
+  policy: ShutdownPolicy;
+  // How long terminated commands get to exit before they are killed
+  grace_secs: number;
+}
+
+// Why a recorded run ended without its outcome
+export type Interruption = 'terminated' | 'detached' | 'lost';
+
+// A command running in a child process
+export interface RunningJob {
+  run_id: string;
+  command_id: string;
+  command_name: string;
+  command_line: string;
+  pid: number;
+  started_at: string;
+  interruption?: Interruption;
+}
+
+// Runs the previous session left behind, recorded in history as interrupted
+export interface RecoveryNotice {
+  // The session ended without shutting down, rather than only leaving commands running
+  crashed: boolean;
+  runs: ExecutionSummary[];
+}
+
+// The background agent, announced while it runs
+export interface AgentInfo {
+  pid: number;
+  started_at: string;
+  version: string;
+  // The agent's socket, or on Windows its loopback address
+  endpoint: string;
+  token: string;
+}
+
+// A line of output from a run in the background agent
+export interface AgentOutput {
+  run_id: string;
+  stream: OutputStream;
+  text: string;
+}
+
+export interface AgentStatus {
+  running?: AgentInfo;
+  // Whether the agent starts at login
+  autostart: boolean;
+}
@@ -0,0 +1,39 @@
+use command_argus_logic::{Command, CommandStorage, Query, SearchIndex, SettingsStorage};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// A library of `size` commands with a handful of tags
+fn library(size: usize) -> Vec<Command> {
+    let tags = ["deploy", "build", "test", "db", "k8s", "docs"];
+    (0..size)
+        .map(|i| {
+            let mut cmd = Command::new(format!("Task {} {}", i, tags[i % tags.len()]), "make".to_string())
+                .with_args(vec![format!("target-{}", i)]);
+            cmd.add_tag(tags[i % tags.len()].to_string());
+            cmd.use_count = (i % 50) as u32;
+            cmd
+        })
+        .collect()
+}
+
+fn bench_search(c: &mut Criterion) {
+    let commands = library(5_000);
+    let index = SearchIndex::build(commands.clone());
+    let query = Query::parse("tag:deploy name:~\"task 12\" used:>10").unwrap();
+
+    c.bench_function("query/linear_scan_5000", |b| {
+        b.iter(|| query.filter(black_box(commands.clone())))
+    });
+    c.bench_function("query/index_5000", |b| b.iter(|| index.search(black_box(&query))));
+    c.bench_function("name/index_5000", |b| b.iter(|| index.search_name(black_box("task 4999"))));
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let storage = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+    let access = SettingsStorage::with_path(temp.path().join("settings.json")).unwrap().edit_access(None).unwrap();
+    storage.replace_all(access, &commands).unwrap();
+    c.bench_function("storage/search_by_query_5000", |b| {
+        b.iter(|| storage.search_by_query(black_box("tag:deploy name:~\"task 12\" used:>10")).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
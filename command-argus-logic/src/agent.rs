@@ -0,0 +1,427 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::batch::resolve_parameters;
+use crate::error::{CommandArgusError, Result};
+use crate::executor::CommandExecutor;
+use crate::guard::{Clearance, GuardedRun};
+use crate::history::{ExecutionRecord, ExecutionSummary, HistoryStorage};
+use crate::ipc::{write_message, AgentMessage, AgentStream, ClientMessage, MessageReader, RunRequest, PROTOCOL_VERSION};
+use crate::platform::Platform;
+use crate::settings::SettingsStorage;
+use crate::shutdown::{reconcile_interrupted, RunningJobs};
+use crate::storage::{data_dir, CommandStorage};
+
+/// Argument that starts the GUI's binary as the background agent, without a window.
+pub const AGENT_ARG: &str = "--agent";
+
+/// Written as `agent.json` in the data directory while a background agent runs, so
+/// the GUI and a second agent can find it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentInfo {
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    /// Version of the binary hosting the agent.
+    pub version: String,
+    /// Where the agent listens: the path of its socket, or on Windows a loopback address.
+    pub endpoint: String,
+    /// Proof a client may read this file, sent in its `Hello`.
+    pub token: String,
+}
+
+#[cfg(unix)]
+type AgentListener = std::os::unix::net::UnixListener;
+#[cfg(windows)]
+type AgentListener = std::net::TcpListener;
+
+/// Runs commands outside the windowed app, so they keep their job registry and history
+/// when no window is open. Started at login when autostart is on, or by hand with
+/// `argus agent`, and driven by clients such as the GUI over `ipc`. Its running jobs
+/// are kept in `agent-running.json`, apart from the GUI's, so each process reconciles
+/// only the runs it left behind. It only runs what clients submit: the app has no
+/// schedules or file triggers yet for it to host.
+pub struct Agent {
+    info_path: PathBuf,
+    commands_path: PathBuf,
+    jobs: Arc<RunningJobs>,
+    history: Mutex<HistoryStorage>,
+    settings: SettingsStorage,
+    listener: Mutex<Option<(AgentListener, String)>>,
+}
+
+impl Agent {
+    pub fn new() -> Result<Self> {
+        Self::with_dir(&data_dir()?)
+    }
+
+    /// An agent keeping its files, and reading the command library, in `dir`.
+    pub fn with_dir(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            info_path: dir.join("agent.json"),
+            commands_path: dir.join("commands.json"),
+            jobs: Arc::new(RunningJobs::with_path(dir.join("agent-running.json"))),
+            history: Mutex::new(HistoryStorage::with_path(dir.join("history.json"))?),
+            settings: SettingsStorage::with_path(dir.join("settings.json"))?,
+            listener: Mutex::new(None),
+        })
+    }
+
+    /// The agent running now, if any.
+    pub fn running(&self) -> Option<AgentInfo> {
+        running_agent(&self.info_path)
+    }
+
+    pub fn jobs(&self) -> &Arc<RunningJobs> {
+        &self.jobs
+    }
+
+    /// Starts listening, announces the agent and records the runs the last session left
+    /// behind. Fails if another agent is running.
+    pub fn start(&self) -> Result<Vec<ExecutionRecord>> {
+        if let Some(other) = self.running() {
+            return Err(CommandArgusError::Agent(format!("already running as process {}", other.pid)));
+        }
+        let (listener, endpoint) = self.bind()?;
+        // Accepting is polled, so `serve` notices when it should stop
+        listener.set_nonblocking(true)?;
+        let info = AgentInfo {
+            pid: std::process::id(),
+            started_at: Utc::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            endpoint: endpoint.clone(),
+            token: Uuid::new_v4().to_string(),
+        };
+        fs::write(&self.info_path, serde_json::to_string_pretty(&info)?)?;
+        *self.listener.lock().unwrap() = Some((listener, info.token));
+        reconcile_interrupted(&self.jobs, &self.history.lock().unwrap())
+    }
+
+    // A socket left by an agent that died is replaced; `start` checked none is running
+    #[cfg(unix)]
+    fn bind(&self) -> Result<(AgentListener, String)> {
+        let path = self.info_path.with_file_name("agent.sock");
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let listener = AgentListener::bind(&path)?;
+        Ok((listener, path.to_string_lossy().to_string()))
+    }
+
+    #[cfg(windows)]
+    fn bind(&self) -> Result<(AgentListener, String)> {
+        let listener = AgentListener::bind("127.0.0.1:0")?;
+        let endpoint = listener.local_addr()?.to_string();
+        Ok((listener, endpoint))
+    }
+
+    /// Serves clients until `stop` is set, then terminates the commands still running,
+    /// giving them the shutdown grace period, and withdraws the announcement.
+    pub fn serve(&self, stop: &AtomicBool) -> Result<()> {
+        let (listener, token) = self
+            .listener
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| CommandArgusError::Agent("not started".to_string()))?;
+        std::thread::scope(|scope| {
+            while !stop.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let token = token.as_str();
+                        scope.spawn(move || {
+                            // A client that goes away only ends its own connection
+                            let _ = self.handle(stream, token, stop);
+                        });
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(100)),
+                    Err(e) => return Err(CommandArgusError::from(e)),
+                }
+            }
+            self.jobs.close();
+            let grace = self.settings.load()?.shutdown.grace_secs;
+            self.jobs.terminate_all(Duration::from_secs(grace));
+            Ok(())
+        })?;
+        #[cfg(unix)]
+        let _ = fs::remove_file(self.info_path.with_file_name("agent.sock"));
+        fs::remove_file(&self.info_path)?;
+        Ok(())
+    }
+
+    // One client's connection. Runs it submits run on threads of their own, sending
+    // their messages through the shared writer.
+    fn handle(&self, stream: AgentStream, token: &str, stop: &AtomicBool) -> Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+        let send = |message: &AgentMessage| write_message(&mut *writer.lock().unwrap(), message);
+        let mut reader = MessageReader::new(stream);
+
+        match reader.next::<ClientMessage>(Some(stop))? {
+            Some(ClientMessage::Hello { version, token: given }) if version == PROTOCOL_VERSION && given == token => {
+                let agent = self.running().ok_or_else(|| CommandArgusError::Agent("not announced".to_string()))?;
+                send(&AgentMessage::Welcome { version: PROTOCOL_VERSION, agent })?;
+            }
+            Some(ClientMessage::Hello { version, .. }) if version != PROTOCOL_VERSION => {
+                let reason = format!("the agent speaks protocol version {}, not {}", PROTOCOL_VERSION, version);
+                return send(&AgentMessage::Refused { version: PROTOCOL_VERSION, reason });
+            }
+            Some(_) => {
+                let reason = "expected a hello with the token from agent.json".to_string();
+                return send(&AgentMessage::Refused { version: PROTOCOL_VERSION, reason });
+            }
+            None => return Ok(()),
+        }
+
+        std::thread::scope(|scope| {
+            while let Some(message) = reader.next::<ClientMessage>(Some(stop))? {
+                match message {
+                    ClientMessage::Submit { request } => {
+                        let writer = writer.clone();
+                        scope.spawn(move || {
+                            let send = |message: &AgentMessage| {
+                                let _ = write_message(&mut *writer.lock().unwrap(), message);
+                            };
+                            let run_id = Uuid::new_v4();
+                            match self.run(run_id, &request, &writer) {
+                                Ok(summary) => send(&AgentMessage::Finished { run_id, summary }),
+                                Err(e) => send(&AgentMessage::Error { message: e.to_string(), run_id: Some(run_id) }),
+                            }
+                        });
+                    }
+                    ClientMessage::ListJobs => send(&AgentMessage::Jobs { jobs: self.jobs.list() })?,
+                    ClientMessage::Hello { .. } | ClientMessage::Unsupported => send(&AgentMessage::Error {
+                        message: "unsupported message".to_string(),
+                        run_id: None,
+                    })?,
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Runs a saved command through the same checks as the GUI, streaming its scrubbed
+    // output to the client, and records it in history.
+    fn run(&self, run_id: Uuid, request: &RunRequest, writer: &Arc<Mutex<AgentStream>>) -> Result<ExecutionSummary> {
+        let storage = CommandStorage::with_path(self.commands_path.clone())?;
+        let command = storage.read(request.command_id)?;
+        let values = resolve_parameters(&command, &request.parameters)?;
+        let settings = self.settings.load()?;
+
+        let mut command = command.for_platform(Platform::current())?;
+        let (new_command, new_args) = command.replace_placeholders(&values);
+        command.command = new_command;
+        command.args = new_args;
+        command.working_directory = command.replace_working_directory_placeholders(&values);
+        command.stdin_template = command.render_stdin(&values);
+        let clearance = Clearance::from(request.confirmation.clone());
+        let run = GuardedRun::begin(run_id, &command, &values, &clearance, &settings, &self.history.lock().unwrap())?;
+        storage.record_use(request.command_id)?;
+
+        write_message(&mut *writer.lock().unwrap(), &AgentMessage::Accepted { run_id })?;
+        let output_writer = writer.clone();
+        let executor = CommandExecutor::new()
+            .with_context_prefix(settings.context_env_prefix.clone())
+            .with_restricted_mode(settings.restricted_mode.clone())
+            .with_scrubber(run.scrubber().clone())
+            .with_jobs(self.jobs.clone())
+            .with_run_id(run_id)
+            .with_artifacts_dir(self.history.lock().unwrap().artifacts_dir(run_id))
+            .with_output_listener(Arc::new(move |stream, text| {
+                let text = text.to_string();
+                let _ = write_message(&mut *output_writer.lock().unwrap(), &AgentMessage::Output { run_id, stream, text });
+            }));
+        let executor = match settings.working_directory_base_dir() {
+            Some(base_dir) => executor.with_base_dir(base_dir),
+            None => executor,
+        };
+        let mut result = match request.use_shell {
+            true => executor.execute_with_shell(&command),
+            false => executor.execute(&command),
+        };
+        let record = run.finish(&mut result);
+        let record = self.history.lock().unwrap().record(record)?;
+        Ok(record.summary())
+    }
+}
+
+/// The background agent running for this user, if any.
+pub fn find_agent() -> Result<Option<AgentInfo>> {
+    Ok(running_agent(&data_dir()?.join("agent.json")))
+}
+
+/// The agent announced in `agent.json` at `path`, unless the file is stale: the agent
+/// died without removing it.
+fn running_agent(path: &Path) -> Option<AgentInfo> {
+    let info: AgentInfo = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    process_alive(info.pid).then_some(info)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks; EPERM means it exists but belongs to someone else
+    let found = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+/// Set by SIGTERM and SIGINT once `stop_on_signal` was called.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// The flag to pass to `Agent::serve` so the agent stops cleanly when the session
+/// ends or it is interrupted. On Windows the agent is ended by the session instead.
+pub fn stop_on_signal() -> &'static AtomicBool {
+    #[cfg(unix)]
+    {
+        extern "C" fn handle(_: libc::c_int) {
+            STOP.store(true, Ordering::SeqCst);
+        }
+        let handler: extern "C" fn(libc::c_int) = handle;
+        unsafe {
+            libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+            libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+        }
+    }
+    &STOP
+}
+
+/// A file that starts the agent at login: an XDG autostart entry on Linux, a launch
+/// agent on macOS and a script in the Startup folder on Windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutostartEntry {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl AutostartEntry {
+    /// The entry starting `exe` with `AGENT_ARG` on `platform`, for the current user.
+    pub fn new(platform: Platform, exe: &Path) -> Result<Self> {
+        let dirs = BaseDirs::new().ok_or_else(|| CommandArgusError::Agent("no home directory".to_string()))?;
+        let exe = exe.to_string_lossy();
+        let (path, contents) = match platform {
+            Platform::Linux => (
+                dirs.config_dir().join("autostart/command-argus-agent.desktop"),
+                format!(
+                    "[Desktop Entry]\nType=Application\nName=Command Argus Agent\nExec=\"{}\" {}\nNoDisplay=true\nX-GNOME-Autostart-enabled=true\n",
+                    exe, AGENT_ARG
+                ),
+            ),
+            Platform::Macos => (
+                dirs.home_dir().join("Library/LaunchAgents/com.command-argus.agent.plist"),
+                format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                     <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+                     <plist version=\"1.0\">\n<dict>\n\
+                     \t<key>Label</key>\n\t<string>com.command-argus.agent</string>\n\
+                     \t<key>ProgramArguments</key>\n\t<array>\n\t\t<string>{}</string>\n\t\t<string>{}</string>\n\t</array>\n\
+                     \t<key>RunAtLoad</key>\n\t<true/>\n\
+                     </dict>\n</plist>\n",
+                    xml_escape(&exe),
+                    AGENT_ARG
+                ),
+            ),
+            Platform::Windows => (
+                dirs.config_dir().join("Microsoft/Windows/Start Menu/Programs/Startup/command-argus-agent.cmd"),
+                format!("@start \"\" \"{}\" {}\r\n", exe, AGENT_ARG),
+            ),
+        };
+        Ok(Self { path, contents })
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn install(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, &self.contents)?;
+        Ok(())
+    }
+
+    pub fn uninstall(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_agent_lifecycle() {
+        let temp = TempDir::new().unwrap();
+        let agent = Agent::with_dir(temp.path()).unwrap();
+        assert_eq!(agent.running(), None);
+
+        agent.start().unwrap();
+        assert_eq!(agent.running().unwrap().pid, std::process::id());
+        assert!(matches!(Agent::with_dir(temp.path()).unwrap().start(), Err(CommandArgusError::Agent(_))));
+
+        // An agent that died leaves a stale announcement behind, which does not count
+        let stale = AgentInfo {
+            pid: i32::MAX as u32,
+            started_at: Utc::now(),
+            version: "0.1.0".to_string(),
+            endpoint: String::new(),
+            token: String::new(),
+        };
+        fs::write(temp.path().join("agent.json"), serde_json::to_string(&stale).unwrap()).unwrap();
+        assert_eq!(agent.running(), None);
+
+        let stop = AtomicBool::new(true);
+        agent.start().unwrap();
+        agent.serve(&stop).unwrap();
+        assert_eq!(agent.running(), None);
+        assert!(agent.jobs().is_closed());
+    }
+
+    #[test]
+    fn test_autostart_entries() {
+        let exe = Path::new("/opt/Command Argus/command-argus-gui");
+        let linux = AutostartEntry::new(Platform::Linux, exe).unwrap();
+        assert!(linux.path.ends_with("autostart/command-argus-agent.desktop"));
+        assert!(linux.contents.contains("Exec=\"/opt/Command Argus/command-argus-gui\" --agent\n"));
+        let macos = AutostartEntry::new(Platform::Macos, exe).unwrap();
+        assert!(macos.path.ends_with("Library/LaunchAgents/com.command-argus.agent.plist"));
+        assert!(macos.contents.contains("<string>--agent</string>"));
+        let windows = AutostartEntry::new(Platform::Windows, exe).unwrap();
+        assert!(windows.path.ends_with("Startup/command-argus-agent.cmd"));
+
+        let temp = TempDir::new().unwrap();
+        let entry = AutostartEntry { path: temp.path().join("autostart/agent.desktop"), ..linux };
+        assert!(!entry.is_installed());
+        entry.install().unwrap();
+        assert!(entry.is_installed());
+        entry.uninstall().unwrap();
+        entry.uninstall().unwrap();
+        assert!(!entry.is_installed());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executable::find_executable;
+
+/// An alias defined in one of the user's shell startup files.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShellAlias {
+    pub name: String,
+    /// What the alias stands for, unquoted, e.g. `git checkout`.
+    pub expansion: String,
+    /// The file that defines it.
+    pub source: PathBuf,
+}
+
+impl ShellAlias {
+    /// The expansion as a program and its arguments. Fails when it needs a shell to
+    /// mean anything, e.g. `cd .. && ls` or `ls $HOME`.
+    pub fn split(&self) -> Result<(String, Vec<String>)> {
+        let mut words = split_words(&self.expansion).ok_or_else(|| {
+            CommandArgusError::InvalidCommand(format!(
+                "alias '{}' uses shell syntax and can't be expanded into a plain command: {}",
+                self.name, self.expansion
+            ))
+        })?;
+        if words.is_empty() {
+            return Err(CommandArgusError::InvalidCommand(format!("alias '{}' is empty", self.name)));
+        }
+        let program = words.remove(0);
+        Ok((program, words))
+    }
+}
+
+/// The startup files aliases are read from, later ones overriding earlier ones.
+const RC_FILES: &[&str] = &[".profile", ".bashrc", ".bash_aliases", ".zshrc", ".zsh_aliases", ".config/fish/config.fish"];
+
+/// The aliases in the current user's shell startup files.
+pub fn load_shell_aliases() -> Vec<ShellAlias> {
+    match BaseDirs::new() {
+        Some(dirs) => load_aliases_from(dirs.home_dir()),
+        None => Vec::new(),
+    }
+}
+
+/// The aliases in the startup files under `home`, one per name.
+pub fn load_aliases_from(home: &Path) -> Vec<ShellAlias> {
+    let mut aliases: Vec<ShellAlias> = Vec::new();
+    for file in RC_FILES {
+        let path = home.join(file);
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        for (name, expansion) in parse_aliases(&content) {
+            aliases.retain(|a| a.name != name);
+            aliases.push(ShellAlias { name, expansion, source: path.clone() });
+        }
+    }
+    aliases
+}
+
+/// The `alias` definitions in a bash, zsh or fish script, as `(name, expansion)`.
+/// Both `alias ll='ls -l'` (several per line allowed) and fish's `alias ll 'ls -l'`
+/// are understood. Definitions inside functions or conditionals are taken as well.
+pub fn parse_aliases(content: &str) -> Vec<(String, String)> {
+    let mut aliases = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix("alias ") else { continue };
+        let Some(words) = split_words_lossy(rest) else { continue };
+
+        if words.len() >= 2 && !words[0].contains('=') && !words[0].starts_with('-') {
+            // fish: alias name 'expansion'
+            aliases.push((words[0].clone(), words[1..].join(" ")));
+            continue;
+        }
+        for word in words {
+            if let Some((name, expansion)) = word.split_once('=') {
+                if !name.is_empty() && !name.starts_with('-') {
+                    aliases.push((name.to_string(), expansion.to_string()));
+                }
+            }
+        }
+    }
+    aliases
+}
+
+/// The alias `command` relies on: its program isn't an executable but is the name of one
+/// of `aliases`.
+pub fn find_alias<'a>(command: &Command, aliases: &'a [ShellAlias]) -> Option<&'a ShellAlias> {
+    let program = command.command.as_str();
+    if program.is_empty() || program.contains(['/', '\\', '{']) || find_executable(program).is_some() {
+        return None;
+    }
+    aliases.iter().find(|a| a.name == program)
+}
+
+/// Replaces the program of `command` with what `alias` stands for, keeping the
+/// command's own arguments after the alias's.
+pub fn expand_alias(command: &mut Command, alias: &ShellAlias) -> Result<()> {
+    let (program, mut args) = alias.split()?;
+    args.append(&mut command.args);
+    command.command = program;
+    command.args = args;
+    Ok(())
+}
+
+/// Splits on whitespace honouring single and double quotes and backslashes, or `None`
+/// when the text has anything a shell would interpret: operators, redirections,
+/// variables, substitutions or an unclosed quote.
+fn split_words(text: &str) -> Option<Vec<String>> {
+    let words = split_words_lossy(text)?;
+    let shell_syntax = ['|', '&', ';', '<', '>', '$', '`', '(', ')', '*', '?'];
+    let raw_has_syntax = {
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        text.chars().any(|c| {
+            if escaped {
+                escaped = false;
+                return false;
+            }
+            match (quote, c) {
+                (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+                (Some('\''), _) => {}
+                (Some('"'), '$') | (Some('"'), '`') => return true,
+                (Some(_), _) => {}
+                (None, '\'') | (None, '"') => quote = Some(c),
+                (None, '\\') => escaped = true,
+                (None, c) => return shell_syntax.contains(&c),
+            }
+            false
+        })
+    };
+    (!raw_has_syntax).then_some(words)
+}
+
+fn split_words_lossy(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                Some(next @ ('"' | '\\' | '$' | '`')) => current.push(next),
+                Some(next) => {
+                    current.push('\\');
+                    current.push(next);
+                }
+                None => return None,
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                current.push(chars.next()?);
+                in_word = true;
+            }
+            (None, '#') if !in_word => break,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(current);
+    }
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_and_expand_aliases() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join(".bashrc"),
+            "# aliases\nalias ll='ls -alF' la=\"ls -A\"\nalias gco='git checkout'\nalias up='cd .. && ls'\nexport PATH=$PATH:~/bin\n",
+        )
+        .unwrap();
+        fs::create_dir_all(temp.path().join(".config/fish")).unwrap();
+        fs::write(temp.path().join(".config/fish/config.fish"), "alias gco 'git switch'\n").unwrap();
+
+        let aliases = load_aliases_from(temp.path());
+        let named: Vec<(&str, &str)> = aliases.iter().map(|a| (a.name.as_str(), a.expansion.as_str())).collect();
+        assert_eq!(
+            named,
+            vec![("ll", "ls -alF"), ("la", "ls -A"), ("up", "cd .. && ls"), ("gco", "git switch")]
+        );
+
+        let mut command = Command::new("Switch".to_string(), "gco".to_string()).with_args(vec!["{branch}".to_string()]);
+        let alias = find_alias(&command, &aliases).unwrap().clone();
+        expand_alias(&mut command, &alias).unwrap();
+        assert_eq!(command.command, "git");
+        assert_eq!(command.args, vec!["switch", "{branch}"]);
+        assert!(find_alias(&command, &aliases).is_none());
+
+        let mut up = Command::new("Up".to_string(), "up".to_string());
+        assert!(expand_alias(&mut up, &aliases[2]).is_err());
+        assert_eq!(up.command, "up");
+    }
+}
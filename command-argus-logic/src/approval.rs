@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::{AuditEvent, AuditLog};
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::RestrictedMode;
+use crate::storage::data_dir;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Executed,
+}
+
+/// A request to run a command that `requires_approval`.
+///
+/// Lifecycle: `Pending` -> `Approved` | `Rejected`, and `Approved` -> `Executed`
+/// once the run has been started. An approval is good for exactly one run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub command_id: Uuid,
+    pub command_name: String,
+    pub parameters: HashMap<String, String>,
+    pub use_shell: bool,
+    pub requested_by: String,
+    pub requested_at: DateTime<Utc>,
+    pub status: ApprovalStatus,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Refuses a direct run of a command that must go through the approval workflow.
+pub fn ensure_no_approval_needed(command: &Command) -> Result<()> {
+    if command.requires_approval {
+        return Err(CommandArgusError::AccessDenied(format!(
+            "'{}' requires an approved run request",
+            command.name
+        )));
+    }
+    Ok(())
+}
+
+pub struct ApprovalStore {
+    path: PathBuf,
+    audit: AuditLog,
+}
+
+impl ApprovalStore {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("approvals.json"),
+            audit: AuditLog::new()?,
+        })
+    }
+
+    pub fn with_paths(path: PathBuf, audit: AuditLog) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path, audit })
+    }
+
+    pub fn request(
+        &self,
+        command: &Command,
+        parameters: HashMap<String, String>,
+        use_shell: bool,
+        requested_by: &str,
+    ) -> Result<ApprovalRequest> {
+        let request = ApprovalRequest {
+            id: Uuid::new_v4(),
+            command_id: command.id,
+            command_name: command.name.clone(),
+            parameters,
+            use_shell,
+            requested_by: requested_by.to_string(),
+            requested_at: Utc::now(),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            decided_at: None,
+        };
+
+        let mut requests = self.load_all()?;
+        requests.push(request.clone());
+        self.save_all(&requests)?;
+        self.audit.record(&AuditEvent::new(
+            requested_by,
+            "approval_requested",
+            Some(command.id),
+            format!("request {} for '{}'", request.id, command.name),
+        ))?;
+        Ok(request)
+    }
+
+    /// Approves a pending request. The approver must be someone other than the
+    /// requester, unless they confirm a second time with the admin passphrase.
+    pub fn approve(
+        &self,
+        id: Uuid,
+        approver: &str,
+        passphrase: Option<&str>,
+        restricted_mode: &RestrictedMode,
+    ) -> Result<ApprovalRequest> {
+        self.decide(id, approver, ApprovalStatus::Approved, |request| {
+            if request.requested_by != approver {
+                return Ok(());
+            }
+            match passphrase {
+                Some(passphrase) if restricted_mode.enabled => restricted_mode.verify(passphrase),
+                _ => Err(CommandArgusError::AccessDenied(
+                    "A request cannot be approved by its requester without the admin passphrase"
+                        .to_string(),
+                )),
+            }
+        })
+    }
+
+    pub fn reject(&self, id: Uuid, approver: &str) -> Result<ApprovalRequest> {
+        self.decide(id, approver, ApprovalStatus::Rejected, |_| Ok(()))
+    }
+
+    /// Consumes an approved request so it can be run; fails unless it is `Approved`.
+    pub fn take_approved(&self, id: Uuid, actor: &str) -> Result<ApprovalRequest> {
+        let mut requests = self.load_all()?;
+        let request = requests
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        if request.status != ApprovalStatus::Approved {
+            return Err(CommandArgusError::AccessDenied(format!(
+                "Request {} is {:?}, not approved",
+                id, request.status
+            )));
+        }
+        request.status = ApprovalStatus::Executed;
+        let request = request.clone();
+        self.save_all(&requests)?;
+        self.audit.record(&AuditEvent::new(
+            actor,
+            "approved_run_started",
+            Some(request.command_id),
+            format!("request {} for '{}'", request.id, request.command_name),
+        ))?;
+        Ok(request)
+    }
+
+    pub fn list(&self) -> Result<Vec<ApprovalRequest>> {
+        self.load_all()
+    }
+
+    pub fn list_pending(&self) -> Result<Vec<ApprovalRequest>> {
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .filter(|r| r.status == ApprovalStatus::Pending)
+            .collect())
+    }
+
+    fn decide(
+        &self,
+        id: Uuid,
+        actor: &str,
+        status: ApprovalStatus,
+        check: impl FnOnce(&ApprovalRequest) -> Result<()>,
+    ) -> Result<ApprovalRequest> {
+        let mut requests = self.load_all()?;
+        let request = requests
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        if request.status != ApprovalStatus::Pending {
+            return Err(CommandArgusError::AccessDenied(format!(
+                "Request {} was already decided",
+                id
+            )));
+        }
+        check(request)?;
+
+        request.status = status;
+        request.decided_by = Some(actor.to_string());
+        request.decided_at = Some(Utc::now());
+        let request = request.clone();
+        self.save_all(&requests)?;
+
+        let action = match status {
+            ApprovalStatus::Approved => "approval_granted",
+            _ => "approval_rejected",
+        };
+        self.audit.record(&AuditEvent::new(
+            actor,
+            action,
+            Some(request.command_id),
+            format!("request {} for '{}'", request.id, request.command_name),
+        ))?;
+        Ok(request)
+    }
+
+    fn load_all(&self) -> Result<Vec<ApprovalRequest>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_all(&self, requests: &[ApprovalRequest]) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(requests)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn temp_store() -> (ApprovalStore, TempDir) {
+        let temp = TempDir::new().unwrap();
+        let audit = AuditLog::with_path(temp.path().join("audit.jsonl")).unwrap();
+        let store = ApprovalStore::with_paths(temp.path().join("approvals.json"), audit).unwrap();
+        (store, temp)
+    }
+
+    fn dangerous() -> Command {
+        let mut cmd = Command::new("Drop DB".to_string(), "dropdb".to_string());
+        cmd.requires_approval = true;
+        cmd
+    }
+
+    #[test]
+    fn test_approve_by_other_user_then_run_once() {
+        let (store, temp) = temp_store();
+        let request = store.request(&dangerous(), HashMap::new(), false, "alice").unwrap();
+        assert_eq!(store.list_pending().unwrap().len(), 1);
+
+        assert!(store.take_approved(request.id, "alice").is_err());
+        store.approve(request.id, "bob", None, &RestrictedMode::default()).unwrap();
+        assert!(store.list_pending().unwrap().is_empty());
+
+        let taken = store.take_approved(request.id, "alice").unwrap();
+        assert_eq!(taken.status, ApprovalStatus::Executed);
+        assert!(store.take_approved(request.id, "alice").is_err());
+
+        let audit = AuditLog::with_path(temp.path().join("audit.jsonl")).unwrap();
+        let actions: Vec<String> = audit.list().unwrap().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec!["approval_requested", "approval_granted", "approved_run_started"]);
+    }
+
+    #[test]
+    fn test_self_approval_needs_passphrase() {
+        let (store, _temp) = temp_store();
+        let mut restricted = RestrictedMode::default();
+        restricted.enable("letmein").unwrap();
+
+        let request = store.request(&dangerous(), HashMap::new(), false, "alice").unwrap();
+        assert!(store.approve(request.id, "alice", None, &restricted).is_err());
+        assert!(store.approve(request.id, "alice", Some("nope"), &restricted).is_err());
+        let approved = store.approve(request.id, "alice", Some("letmein"), &restricted).unwrap();
+        assert_eq!(approved.status, ApprovalStatus::Approved);
+    }
+
+    #[test]
+    fn test_direct_run_refused() {
+        assert!(ensure_no_approval_needed(&dangerous()).is_err());
+        assert!(ensure_no_approval_needed(&Command::new("Ls".to_string(), "ls".to_string())).is_ok());
+    }
+
+    #[test]
+    fn test_rejected_request_cannot_run() {
+        let (store, _temp) = temp_store();
+        let request = store.request(&dangerous(), HashMap::new(), false, "alice").unwrap();
+        store.reject(request.id, "bob").unwrap();
+        assert!(store.approve(request.id, "bob", None, &RestrictedMode::default()).is_err());
+        assert!(store.take_approved(request.id, "alice").is_err());
+    }
+}
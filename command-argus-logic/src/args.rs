@@ -0,0 +1,109 @@
+use crate::error::{CommandArgusError, Result};
+
+/// Characters that make an argument need quotes in [`join_args`]. Besides what
+/// [`split_args`] itself treats specially, shell syntax is quoted too, so the line reads
+/// the same when pasted into a terminal.
+const NEEDS_QUOTES: &[char] = &['\'', '"', '\\', '|', '&', ';', '<', '>', '(', ')', '`', '*', '?', '#', '~', '!'];
+
+/// Splits an argument line typed as one string into the arguments it stands for.
+/// Whitespace separates arguments; single quotes keep everything literally, and a
+/// backslash escapes the next character, or within double quotes only `"` and `\`.
+/// Nothing is expanded, so `$HOME` and `{name}` placeholders stay as written.
+pub fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<(char, usize)> = None;
+    let mut chars = line.char_indices();
+    while let Some((offset, c)) = chars.next() {
+        match (quote, c) {
+            (Some((q, _)), c) if c == q => quote = None,
+            (Some(('"', _)), '\\') => match chars.next() {
+                Some((_, next @ ('"' | '\\'))) => current.push(next),
+                Some((_, next)) => {
+                    current.push('\\');
+                    current.push(next);
+                }
+                None => current.push('\\'),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some((c, offset));
+                in_arg = true;
+            }
+            (None, '\\') => {
+                current.push(chars.next().map_or('\\', |(_, next)| next));
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if let Some((q, offset)) = quote {
+        return Err(CommandArgusError::InvalidCommand(format!(
+            "the {} at character {} is never closed",
+            if q == '\'' { "single quote" } else { "double quote" },
+            line[..offset].chars().count() + 1
+        )));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// The arguments as one line that [`split_args`] turns back into the same arguments.
+/// Arguments that need it are single-quoted, with embedded single quotes escaped.
+pub fn join_args(args: &[String]) -> String {
+    args.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" ")
+}
+
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || NEEDS_QUOTES.contains(&c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_join_args_round_trip() {
+        let args: Vec<String> = [
+            "commit",
+            "-m",
+            "fix the build; again",
+            "",
+            "it's",
+            "C:\\Program Files\\app",
+            "--path={dir}/$HOME",
+            "a\"b",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let line = join_args(&args);
+        assert_eq!(
+            line,
+            r#"commit -m 'fix the build; again' '' 'it'\''s' 'C:\Program Files\app' --path={dir}/$HOME 'a"b'"#
+        );
+        assert_eq!(split_args(&line).unwrap(), args);
+
+        assert_eq!(
+            split_args(r#"  -v "a \"b\" \$c"  d\ e "#).unwrap(),
+            vec!["-v".to_string(), r#"a "b" \$c"#.to_string(), "d e".to_string()]
+        );
+        let err = split_args("echo 'unclosed").unwrap_err().to_string();
+        assert!(err.contains("single quote at character 6"), "{}", err);
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::glob::{find_glob_matches, GlobMatch};
+
+/// A file copied out of a command's working directory after it ran.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Artifact {
+    /// Relative to the working directory, and to the run's artifact folder it was
+    /// copied into.
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Copies the files under `working_dir` matching any of `patterns` into `destination`,
+/// keeping their relative paths. A matching directory is copied with everything in it.
+/// Patterns use the syntax of [`find_glob_matches`]; `.git` is not searched.
+pub fn collect_artifacts(patterns: &[String], working_dir: &Path, destination: &Path) -> Result<Vec<Artifact>> {
+    let descend = |name: &str| name != ".git";
+    let mut files: Vec<GlobMatch> = Vec::new();
+    for pattern in patterns {
+        for found in find_glob_matches(working_dir, pattern, &descend)? {
+            if !found.is_dir {
+                files.push(found);
+                continue;
+            }
+            for inner in find_glob_matches(&found.path, "**", &descend)?.into_iter().filter(|f| !f.is_dir) {
+                files.push(GlobMatch {
+                    relative: format!("{}/{}", found.relative, inner.relative),
+                    ..inner
+                });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    files.dedup_by(|a, b| a.relative == b.relative);
+
+    let mut artifacts = Vec::new();
+    for file in files {
+        let target = destination.join(&file.relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let bytes = fs::copy(&file.path, &target)?;
+        artifacts.push(Artifact { path: file.relative, bytes });
+    }
+    Ok(artifacts)
+}
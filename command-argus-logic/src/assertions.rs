@@ -0,0 +1,90 @@
+use std::fmt;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// An expectation about a finished execution, turning a saved command into a smoke test.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Assertion {
+    StdoutContains { text: String },
+    StdoutMatches { pattern: String },
+    ExitCodeIn { codes: Vec<i32> },
+    DurationUnder { seconds: f64 },
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::StdoutContains { text } => write!(f, "stdout contains {:?}", text),
+            Assertion::StdoutMatches { pattern } => write!(f, "stdout matches /{}/", pattern),
+            Assertion::ExitCodeIn { codes } => write!(f, "exit code in {:?}", codes),
+            Assertion::DurationUnder { seconds } => write!(f, "finishes in under {}s", seconds),
+        }
+    }
+}
+
+/// The outcome of one assertion.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AssertionResult {
+    pub assertion: Assertion,
+    pub passed: bool,
+    /// What was observed, for failed assertions.
+    pub message: Option<String>,
+}
+
+impl Assertion {
+    pub fn evaluate(&self, stdout: &str, exit_code: i32, duration: Duration) -> AssertionResult {
+        let failure = match self {
+            Assertion::StdoutContains { text } => {
+                (!stdout.contains(text.as_str())).then(|| format!("stdout does not contain {:?}", text))
+            }
+            Assertion::StdoutMatches { pattern } => match Regex::new(pattern) {
+                Ok(re) if re.is_match(stdout) => None,
+                Ok(_) => Some(format!("stdout does not match /{}/", pattern)),
+                Err(e) => Some(format!("invalid pattern /{}/: {}", pattern, e)),
+            },
+            Assertion::ExitCodeIn { codes } => {
+                (!codes.contains(&exit_code)).then(|| format!("exit code was {}", exit_code))
+            }
+            Assertion::DurationUnder { seconds } => (duration.as_secs_f64() >= *seconds)
+                .then(|| format!("took {:.2}s", duration.as_secs_f64())),
+        };
+        AssertionResult {
+            assertion: self.clone(),
+            passed: failure.is_none(),
+            message: failure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let duration = Duration::from_millis(1500);
+        let passed = |assertion: Assertion| assertion.evaluate("status: ok\n", 3, duration).passed;
+
+        assert!(passed(Assertion::StdoutContains { text: "ok".to_string() }));
+        assert!(!passed(Assertion::StdoutContains { text: "error".to_string() }));
+        assert!(passed(Assertion::StdoutMatches { pattern: r"(?m)^status: \w+$".to_string() }));
+        assert!(!passed(Assertion::StdoutMatches { pattern: "(".to_string() }));
+        assert!(passed(Assertion::ExitCodeIn { codes: vec![0, 3] }));
+        assert!(!passed(Assertion::ExitCodeIn { codes: vec![0] }));
+        assert!(passed(Assertion::DurationUnder { seconds: 2.0 }));
+
+        let result = Assertion::DurationUnder { seconds: 1.0 }.evaluate("", 0, duration);
+        assert_eq!(result.message.as_deref(), Some("took 1.50s"));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = r#"{"kind":"exit_code_in","codes":[0,2]}"#;
+        let assertion: Assertion = serde_json::from_str(json).unwrap();
+        assert_eq!(assertion, Assertion::ExitCodeIn { codes: vec![0, 2] });
+        assert_eq!(assertion.to_string(), "exit code in [0, 2]");
+    }
+}
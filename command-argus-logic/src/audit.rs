@@ -0,0 +1,99 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::storage::data_dir;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEvent {
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    /// Short machine-readable action name, e.g. `approval_requested`.
+    pub action: String,
+    pub command_id: Option<Uuid>,
+    pub details: String,
+}
+
+impl AuditEvent {
+    pub fn new(actor: &str, action: &str, command_id: Option<Uuid>, details: String) -> Self {
+        Self {
+            at: Utc::now(),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            command_id,
+            details,
+        }
+    }
+}
+
+/// Append-only log of security-relevant events, one JSON object per line.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("audit.jsonl"),
+        })
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn record(&self, event: &AuditEvent) -> Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<AuditEvent>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::into))
+            .collect()
+    }
+}
+
+/// Name of the OS user running the app, used as the default actor.
+pub fn current_user_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_list() {
+        let temp = TempDir::new().unwrap();
+        let log = AuditLog::with_path(temp.path().join("audit.jsonl")).unwrap();
+        assert!(log.list().unwrap().is_empty());
+
+        log.record(&AuditEvent::new("alice", "first", None, String::new())).unwrap();
+        log.record(&AuditEvent::new("bob", "second", None, "details".to_string())).unwrap();
+
+        let events = log.list().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].actor, "alice");
+        assert_eq!(events[1].details, "details");
+    }
+}
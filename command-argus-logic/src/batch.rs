@@ -0,0 +1,1412 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::approval::ensure_no_approval_needed;
+use crate::assertions::AssertionResult;
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executor::CommandExecutor;
+use crate::platform::Platform;
+use crate::queue::ExecutionQueue;
+use crate::scrub::OutputScrubber;
+use crate::storage::CommandStorage;
+use crate::variables::VariableStorage;
+
+/// A declarative list of saved commands to run headlessly, e.g. from CI.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchPlan {
+    #[serde(default = "default_parallelism")]
+    pub parallelism: usize,
+    #[serde(default)]
+    pub use_shell: bool,
+    pub steps: Vec<BatchStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchStep {
+    /// Name of the saved command to run. Left empty for parallel groups and prompts.
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+    /// Overrides the plan-wide `use_shell` for this step.
+    #[serde(default)]
+    pub use_shell: Option<bool>,
+    /// Pipes the previous step's stdout into this step's stdin.
+    #[serde(default)]
+    pub stdin_from_prev: bool,
+    /// How the previous step's stdout is prepared before this step gets it, either on
+    /// stdin or as the `{prev.stdout}` placeholder.
+    #[serde(default)]
+    pub prev_output: PassOutput,
+    /// Runs the step only if this holds for the previous step's outcome.
+    #[serde(default, rename = "if")]
+    pub condition: Option<StepCondition>,
+    /// Runs in place of the step when its condition does not hold. It may have a
+    /// condition of its own, which is checked against the same previous step.
+    #[serde(default, rename = "else")]
+    pub otherwise: Option<Box<BatchStep>>,
+    /// Makes the step a group of commands that run concurrently. Members are plain
+    /// commands: no conditions, no output from a previous step and no nested groups.
+    #[serde(default)]
+    pub parallel: Vec<BatchStep>,
+    /// When a parallel group is done.
+    #[serde(default)]
+    pub join: JoinPolicy,
+    /// Makes the step a question to the user instead of a command. The run waits for
+    /// the answer, which becomes the step's stdout for the steps after it.
+    #[serde(default)]
+    pub prompt: Option<StepPrompt>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StepPrompt {
+    pub question: String,
+    #[serde(flatten)]
+    pub kind: PromptKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PromptKind {
+    /// Yes or no. Answering no fails the step with exit code 1, so later steps can
+    /// branch on it.
+    Confirm,
+    Choose { options: Vec<String> },
+    Input {
+        #[serde(default)]
+        default: Option<String>,
+    },
+}
+
+impl StepPrompt {
+    /// Checks an answer and turns it into the step's outcome: whether the step
+    /// succeeded, and its stdout.
+    pub fn accept(&self, answer: &str) -> Result<(bool, String)> {
+        let answer = answer.trim();
+        let invalid = |expected: String| {
+            Err(CommandArgusError::InvalidCommand(format!("'{}' is not a valid answer, expected {}", answer, expected)))
+        };
+        match &self.kind {
+            PromptKind::Confirm => match answer.to_lowercase().as_str() {
+                "y" | "yes" | "true" => Ok((true, "yes".to_string())),
+                "n" | "no" | "false" => Ok((false, "no".to_string())),
+                _ => invalid("yes or no".to_string()),
+            },
+            PromptKind::Choose { options } => match options.iter().find(|option| option.as_str() == answer) {
+                Some(option) => Ok((true, option.clone())),
+                None => invalid(format!("one of {}", options.join(", "))),
+            },
+            PromptKind::Input { default } => match (answer, default) {
+                ("", Some(default)) => Ok((true, default.clone())),
+                (answer, _) => Ok((true, answer.to_string())),
+            },
+        }
+    }
+}
+
+/// A prompt step waiting for an answer.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PromptRequest {
+    pub run_id: Uuid,
+    pub step_id: String,
+    #[serde(flatten)]
+    pub prompt: StepPrompt,
+    /// Why the previous answer was rejected, when the question is asked again.
+    pub error: Option<String>,
+}
+
+/// Puts the questions of prompt steps to the user. `ask` blocks until the question is
+/// answered; an error, e.g. when the user cancelled, fails the step.
+pub trait Prompter: Sync {
+    fn ask(&self, request: &PromptRequest) -> Result<String>;
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinPolicy {
+    /// Every member runs, whatever happens to the others.
+    #[default]
+    WaitAll,
+    /// Once a member fails, members that have not started yet are skipped. Members
+    /// already running are left to finish.
+    FailFast,
+}
+
+/// A check of the previous step's outcome that decides whether a step runs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepCondition {
+    /// The previous step exited with this code. A step that was skipped or could not
+    /// be started has no exit code and never matches.
+    ExitCode { code: i32 },
+    StdoutMatches { pattern: String },
+}
+
+impl fmt::Display for StepCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepCondition::ExitCode { code } => write!(f, "the previous step exited with {}", code),
+            StepCondition::StdoutMatches { pattern } => {
+                write!(f, "the previous step's stdout matches /{}/", pattern)
+            }
+        }
+    }
+}
+
+impl StepCondition {
+    pub fn evaluate(&self, prev: &StepOutcome) -> Result<bool> {
+        match self {
+            StepCondition::ExitCode { code } => Ok(prev.exit_code == Some(*code)),
+            StepCondition::StdoutMatches { pattern } => {
+                let re = Regex::new(pattern).map_err(|e| {
+                    CommandArgusError::InvalidCommand(format!("invalid pattern /{}/: {}", pattern, e))
+                })?;
+                Ok(re.is_match(&prev.stdout))
+            }
+        }
+    }
+}
+
+/// Placeholder for the previous step's stdout. It is replaced in step parameter values
+/// and in the command's own arguments.
+pub const PREV_STDOUT: &str = "prev.stdout";
+
+/// Largest output passed from one step to the next unless the step sets its own limit.
+pub const DEFAULT_PASS_LIMIT: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trim {
+    /// Passed on byte for byte.
+    None,
+    /// Leading and trailing whitespace removed, so a trailing newline does not end up in
+    /// an argument.
+    #[default]
+    Whitespace,
+    /// Only the first non-blank line, trimmed.
+    FirstLine,
+    /// Only the last non-blank line, trimmed.
+    LastLine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PassOutput {
+    #[serde(default)]
+    pub trim: Trim,
+    /// The step fails instead of running if the trimmed output is larger than this.
+    #[serde(default = "default_pass_limit")]
+    pub max_bytes: usize,
+}
+
+impl Default for PassOutput {
+    fn default() -> Self {
+        Self {
+            trim: Trim::default(),
+            max_bytes: DEFAULT_PASS_LIMIT,
+        }
+    }
+}
+
+impl PassOutput {
+    /// The previous step's stdout as this step gets it. Output of a failed step is
+    /// never passed on.
+    pub fn apply(&self, prev: &StepOutcome) -> Result<String> {
+        if !prev.success {
+            return Err(CommandArgusError::ExecutionFailed(format!(
+                "previous step '{}' failed, its output is not passed on",
+                prev.command
+            )));
+        }
+        if prev.skipped.is_some() {
+            return Err(CommandArgusError::ExecutionFailed(format!(
+                "previous step '{}' was skipped and has no output",
+                prev.command
+            )));
+        }
+        let mut lines = prev.stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+        let output = match self.trim {
+            Trim::None => prev.stdout.as_str(),
+            Trim::Whitespace => prev.stdout.trim(),
+            Trim::FirstLine => lines.next().unwrap_or_default(),
+            Trim::LastLine => lines.next_back().unwrap_or_default(),
+        };
+        if output.len() > self.max_bytes {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "output of '{}' is {} bytes, more than the {} bytes that can be passed on",
+                prev.command,
+                output.len(),
+                self.max_bytes
+            )));
+        }
+        Ok(output.to_string())
+    }
+}
+
+impl BatchStep {
+    pub fn is_group(&self) -> bool {
+        !self.parallel.is_empty()
+    }
+
+    // Whether the step runs a saved command, rather than being a group or a prompt.
+    fn is_command(&self) -> bool {
+        !self.is_group() && self.prompt.is_none()
+    }
+
+    /// The name the step is reported under.
+    pub fn label(&self) -> String {
+        if let Some(ref prompt) = self.prompt {
+            return prompt.question.clone();
+        }
+        match self.is_group() {
+            true => format!(
+                "parallel: {}",
+                self.parallel.iter().map(|member| member.command.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+            false => self.command.clone(),
+        }
+    }
+
+    /// The step to run given the previous step's outcome: this one if its condition
+    /// holds, otherwise the first `else` step whose condition holds. None means the
+    /// step is skipped.
+    pub fn branch(&self, prev: Option<&StepOutcome>) -> Result<Option<&BatchStep>> {
+        let holds = match (&self.condition, prev) {
+            (None, _) => true,
+            (Some(condition), Some(prev)) => condition.evaluate(prev)?,
+            (Some(_), None) => false,
+        };
+        match (holds, &self.otherwise) {
+            (true, _) => Ok(Some(self)),
+            (false, Some(otherwise)) => otherwise.branch(prev),
+            (false, None) => Ok(None),
+        }
+    }
+
+    // This step and its `else` steps, outermost first.
+    fn branches(&self) -> impl Iterator<Item = &BatchStep> {
+        std::iter::successors(Some(self), |step| step.otherwise.as_deref())
+    }
+
+    // The steps that run a saved command: every branch, with groups replaced by their members.
+    fn command_steps(&self) -> impl Iterator<Item = &BatchStep> {
+        self.branches().flat_map(|branch| match (branch.is_group(), branch.prompt.is_some()) {
+            (true, _) => branch.parallel.iter().collect::<Vec<_>>(),
+            (false, true) => Vec::new(),
+            (false, false) => vec![branch],
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(CommandArgusError::InvalidCommand(message));
+        for branch in self.branches() {
+            let kinds = [!branch.command.is_empty(), branch.is_group(), branch.prompt.is_some()];
+            match kinds.iter().filter(|&&kind| kind).count() {
+                0 => return invalid("a step needs a command, parallel steps or a prompt".to_string()),
+                1 => {}
+                _ => {
+                    return invalid(format!(
+                        "'{}' must be only one of a command, parallel steps or a prompt",
+                        branch.label()
+                    ))
+                }
+            }
+            if let Some(PromptKind::Choose { options }) = branch.prompt.as_ref().map(|prompt| &prompt.kind) {
+                if options.is_empty() {
+                    return invalid(format!("prompt '{}' has no options to choose from", branch.label()));
+                }
+            }
+            for member in &branch.parallel {
+                let chained = member.condition.is_some()
+                    || member.otherwise.is_some()
+                    || member.stdin_from_prev
+                    || member.parameters.values().any(|value| value.contains(&format!("{{{}}}", PREV_STDOUT)));
+                if !member.is_command() || member.command.is_empty() || chained {
+                    return invalid(format!(
+                        "parallel step '{}' must be a plain command, without conditions, previous output, prompts or nested groups",
+                        member.label()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the step needs the previous step's output, on stdin or through the
+    /// `{prev.stdout}` placeholder in its parameters or in `command`'s arguments.
+    pub fn uses_prev_output(&self, command: &Command) -> bool {
+        let placeholder = format!("{{{}}}", PREV_STDOUT);
+        let in_command = |program: &str, args: &[String]| {
+            program.contains(&placeholder) || args.iter().any(|arg| arg.contains(&placeholder))
+        };
+        self.stdin_from_prev
+            || self.parameters.values().any(|value| value.contains(&placeholder))
+            || in_command(&command.command, &command.args)
+            || command
+                .platform_overrides
+                .iter()
+                .any(|o| in_command(&o.command, o.args.as_deref().unwrap_or_default()))
+    }
+}
+
+fn default_parallelism() -> usize {
+    1
+}
+
+fn default_pass_limit() -> usize {
+    DEFAULT_PASS_LIMIT
+}
+
+impl BatchPlan {
+    pub fn from_yaml_str(content: &str) -> Result<Self> {
+        let plan: BatchPlan = serde_yaml::from_str(content)?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.parallelism == 0 {
+            return Err(CommandArgusError::InvalidCommand(
+                "parallelism must be at least 1".to_string(),
+            ));
+        }
+        self.steps.iter().try_for_each(BatchStep::validate)
+    }
+
+    /// Names of the saved commands the plan may run, including `else` steps and members
+    /// of parallel groups, without duplicates.
+    pub fn command_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for step in self.steps.iter().flat_map(BatchStep::command_steps) {
+            if !names.contains(&step.command.as_str()) {
+                names.push(&step.command);
+            }
+        }
+        names
+    }
+
+    /// How many times the plan may run each saved command at most, in the order of
+    /// `command_names`. Every step naming it counts, members of parallel groups
+    /// included; of a step's branches only the one naming it most often counts, as a
+    /// single branch runs.
+    pub fn planned_runs(&self) -> Vec<(&str, usize)> {
+        self.command_names()
+            .into_iter()
+            .map(|name| {
+                let runs = self
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        step.branches()
+                            .map(|branch| match branch.is_group() {
+                                true => branch.parallel.iter().filter(|member| member.command == name).count(),
+                                false => usize::from(branch.prompt.is_none() && branch.command == name),
+                            })
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .sum();
+                (name, runs)
+            })
+            .collect()
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::from_yaml_str(&content)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutcome {
+    /// Steps are numbered from 1 in plan order and members of a parallel group get
+    /// `<step>.<member>`, so ids stay the same across runs of a plan.
+    pub step_id: String,
+    pub command: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the step could not be started at all (missing parameter, bad path, ...).
+    pub error: Option<String>,
+    /// A step with a failed assertion fails even if the command exited successfully.
+    pub assertion_results: Vec<AssertionResult>,
+    /// Why the step did not run, e.g. its condition did not hold and it had no `else`
+    /// step. Skipped steps count as neither passed nor failed.
+    pub skipped: Option<String>,
+}
+
+impl StepOutcome {
+    fn skipped(command: &str, reason: String) -> Self {
+        Self {
+            success: true,
+            skipped: Some(reason),
+            ..Self::failed(command, None, Duration::ZERO)
+        }
+    }
+
+    fn failed(command: &str, error: Option<String>, duration: Duration) -> Self {
+        Self {
+            step_id: String::new(),
+            command: command.to_string(),
+            success: false,
+            exit_code: None,
+            duration,
+            stdout: String::new(),
+            stderr: String::new(),
+            error,
+            assertion_results: Vec::new(),
+            skipped: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    /// Identifies the run in the `BatchEvent`s sent while it ran.
+    pub run_id: Uuid,
+    pub outcomes: Vec<StepOutcome>,
+    pub duration: Duration,
+}
+
+/// Progress of a batch run, sent to the runner's progress listener as it happens.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BatchEvent {
+    StepStarted {
+        run_id: Uuid,
+        step_id: String,
+        command: String,
+    },
+    /// Also sent for steps that were skipped or could not be started, without a
+    /// `StepStarted` before it.
+    StepFinished {
+        run_id: Uuid,
+        step_id: String,
+        command: String,
+        success: bool,
+        skipped: bool,
+        exit_code: Option<i32>,
+        duration_ms: u64,
+    },
+    /// Sent after each step of the plan, counting a parallel group as a single step.
+    Progress {
+        run_id: Uuid,
+        completed: usize,
+        total: usize,
+        percent: u8,
+    },
+}
+
+impl BatchEvent {
+    fn step_finished(run_id: Uuid, outcome: &StepOutcome) -> Self {
+        BatchEvent::StepFinished {
+            run_id,
+            step_id: outcome.step_id.clone(),
+            command: outcome.command.clone(),
+            success: outcome.success,
+            skipped: outcome.skipped.is_some(),
+            exit_code: outcome.exit_code,
+            duration_ms: outcome.duration.as_millis() as u64,
+        }
+    }
+}
+
+impl BatchReport {
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.success && o.skipped.is_none()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| !o.success).count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.skipped.is_some()).count()
+    }
+
+    pub fn success(&self) -> bool {
+        self.failed() == 0
+    }
+
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for outcome in &self.outcomes {
+            let status = match (&outcome.skipped, outcome.success) {
+                (Some(_), _) => "SKIP",
+                (None, true) => "PASS",
+                (None, false) => "FAIL",
+            };
+            let mut detail = match (&outcome.skipped, &outcome.error, outcome.exit_code) {
+                (Some(reason), _, _) => reason.clone(),
+                (None, Some(error), _) => error.clone(),
+                (None, None, Some(code)) => format!("exit {}", code),
+                (None, None, None) => "terminated".to_string(),
+            };
+            for result in outcome.assertion_results.iter().filter(|r| !r.passed) {
+                detail.push_str(&format!("; {}: {}", result.assertion, result.message.as_deref().unwrap_or("failed")));
+            }
+            lines.push(format!(
+                "[{}] {} ({}, {:.2}s)",
+                status,
+                outcome.command,
+                detail,
+                outcome.duration.as_secs_f64()
+            ));
+        }
+        let skipped = match self.skipped() {
+            0 => String::new(),
+            n => format!(", {} skipped", n),
+        };
+        lines.push(format!(
+            "{} passed, {} failed{} in {:.2}s",
+            self.passed(),
+            self.failed(),
+            skipped,
+            self.duration.as_secs_f64()
+        ));
+        lines.join("\n")
+    }
+}
+
+type ProgressListener<'a> = Box<dyn Fn(&BatchEvent) + Send + Sync + 'a>;
+
+pub struct BatchRunner<'a> {
+    storage: &'a CommandStorage,
+    executor: &'a CommandExecutor,
+    progress: Option<ProgressListener<'a>>,
+    prompter: Option<&'a dyn Prompter>,
+    variables: Option<(&'a VariableStorage, Option<&'a Path>)>,
+}
+
+// What the steps of one run share.
+struct RunContext<'r> {
+    id: Uuid,
+    commands: &'r HashMap<&'r str, Command>,
+    queue: &'r ExecutionQueue,
+    use_shell: bool,
+}
+
+impl<'a> BatchRunner<'a> {
+    pub fn new(storage: &'a CommandStorage, executor: &'a CommandExecutor) -> Self {
+        Self { storage, executor, progress: None, prompter: None, variables: None }
+    }
+
+    /// Called with every `BatchEvent` of the runs, from the threads running the steps.
+    pub fn with_progress(mut self, listener: impl Fn(&BatchEvent) + Send + Sync + 'a) -> Self {
+        self.progress = Some(Box::new(listener));
+        self
+    }
+
+    /// Answers prompt steps. Without one, prompt steps fail.
+    pub fn with_prompter(mut self, prompter: &'a dyn Prompter) -> Self {
+        self.prompter = Some(prompter);
+        self
+    }
+
+    /// Resolves the `{{var:NAME}}` references of the plan's commands as seen from
+    /// `workspace` and the executor's profile. A reference to an undefined variable
+    /// fails the whole run.
+    pub fn with_variables(mut self, variables: &'a VariableStorage, workspace: Option<&'a Path>) -> Self {
+        self.variables = Some((variables, workspace));
+        self
+    }
+
+    fn emit(&self, event: BatchEvent) {
+        if let Some(ref listener) = self.progress {
+            listener(&event);
+        }
+    }
+
+    /// Runs every step of the plan. Unknown command names are rejected up front so a
+    /// typo in the plan fails before anything is executed; failures of individual
+    /// steps are reported in the returned `BatchReport` instead.
+    ///
+    /// Steps with a condition or that use the previous step's output chain the plan,
+    /// which then has to run sequentially.
+    pub fn run(&self, plan: &BatchPlan) -> Result<BatchReport> {
+        plan.validate()?;
+        let mut commands: HashMap<&str, Command> = HashMap::new();
+        for name in plan.command_names() {
+            let command = self.storage.read_by_name(name)?;
+            let command = match self.variables {
+                Some((variables, workspace)) => {
+                    variables.resolve_command(&command, workspace, self.executor.profile())?
+                }
+                None => command,
+            };
+            commands.insert(name, command);
+        }
+        for branch in plan.steps.iter().flat_map(BatchStep::branches) {
+            if let Some(StepCondition::StdoutMatches { pattern }) = &branch.condition {
+                Regex::new(pattern).map_err(|e| {
+                    CommandArgusError::InvalidCommand(format!("invalid pattern /{}/: {}", pattern, e))
+                })?;
+            }
+        }
+        let chained = |step: &BatchStep| {
+            step.branches().any(|branch| {
+                branch.condition.is_some()
+                    || (branch.is_command() && branch.uses_prev_output(&commands[branch.command.as_str()]))
+            })
+        };
+        let prompts = plan.steps.iter().flat_map(BatchStep::branches).any(|branch| branch.prompt.is_some());
+        if prompts && plan.parallelism > 1 {
+            return Err(CommandArgusError::InvalidCommand(
+                "plans with prompts pause for the answers and need parallelism 1".to_string(),
+            ));
+        }
+        for (index, step) in plan.steps.iter().enumerate() {
+            if !chained(step) {
+                continue;
+            }
+            let problem = if index == 0 {
+                "but is the first step"
+            } else if plan.steps[index - 1].branches().any(BatchStep::is_group) {
+                "but that is a parallel group"
+            } else if plan.parallelism > 1 {
+                "which needs parallelism 1"
+            } else {
+                continue;
+            };
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "'{}' depends on the previous step, {}",
+                step.label(),
+                problem
+            )));
+        }
+
+        let started = Instant::now();
+        let next = AtomicUsize::new(0);
+        let outcomes: Mutex<Vec<Vec<StepOutcome>>> = Mutex::new(vec![Vec::new(); plan.steps.len()]);
+        let workers = plan.parallelism.max(1).min(plan.steps.len().max(1));
+        let widest_group = plan
+            .steps
+            .iter()
+            .flat_map(BatchStep::branches)
+            .map(|branch| branch.parallel.len())
+            .max()
+            .unwrap_or(0);
+        // Keeps commands that share a mutex group from running in parallel, including
+        // members of parallel groups
+        let queue = ExecutionQueue::new(workers * widest_group.max(1));
+        let run = RunContext {
+            id: Uuid::new_v4(),
+            commands: &commands,
+            queue: &queue,
+            use_shell: plan.use_shell,
+        };
+        let completed = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    if index >= plan.steps.len() {
+                        break;
+                    }
+                    let step = &plan.steps[index];
+                    // With a single worker the previous step has finished by now
+                    let prev = match chained(step) {
+                        true => outcomes.lock().unwrap()[index - 1].first().cloned(),
+                        false => None,
+                    };
+                    let step_id = (index + 1).to_string();
+                    let not_run = |mut outcome: StepOutcome| {
+                        outcome.step_id = step_id.clone();
+                        self.emit(BatchEvent::step_finished(run.id, &outcome));
+                        vec![outcome]
+                    };
+                    let step_outcomes = match step.branch(prev.as_ref()) {
+                        Ok(Some(group)) if group.is_group() => self.run_group(&run, group, &step_id),
+                        Ok(Some(prompt)) if prompt.prompt.is_some() => vec![self.run_prompt(&run, prompt, &step_id)],
+                        Ok(Some(branch)) => {
+                            let command = &commands[branch.command.as_str()];
+                            let prev = prev.as_ref().filter(|_| branch.uses_prev_output(command));
+                            vec![self.run_queued(&run, branch, &step_id, prev)]
+                        }
+                        Ok(None) => not_run(StepOutcome::skipped(&step.label(), "condition not met".to_string())),
+                        Err(e) => not_run(StepOutcome::failed(&step.label(), Some(e.to_string()), Duration::ZERO)),
+                    };
+                    outcomes.lock().unwrap()[index] = step_outcomes;
+                    let completed = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                    self.emit(BatchEvent::Progress {
+                        run_id: run.id,
+                        completed,
+                        total: plan.steps.len(),
+                        percent: (completed * 100 / plan.steps.len()) as u8,
+                    });
+                });
+            }
+        });
+
+        Ok(BatchReport {
+            run_id: run.id,
+            outcomes: outcomes.into_inner().unwrap().into_iter().flatten().collect(),
+            duration: started.elapsed(),
+        })
+    }
+
+    // Runs the members of a parallel group at the same time, reporting them in the order
+    // they are declared. Members are queued in that order too, so when mutex groups or
+    // priorities hold some back, fail-fast can cancel the ones still waiting.
+    fn run_group(&self, run: &RunContext, group: &BatchStep, step_id: &str) -> Vec<StepOutcome> {
+        let ids: Vec<Uuid> = group
+            .parallel
+            .iter()
+            .map(|member| run.queue.enqueue(&run.commands[member.command.as_str()]))
+            .collect();
+        let failed: Mutex<Option<String>> = Mutex::new(None);
+        thread::scope(|scope| {
+            let members: Vec<_> = group
+                .parallel
+                .iter()
+                .zip(&ids)
+                .enumerate()
+                .map(|(position, (member, &id))| {
+                    let (ids, failed) = (&ids, &failed);
+                    let member_id = format!("{}.{}", step_id, position + 1);
+                    scope.spawn(move || {
+                        let Ok(slot) = run.queue.wait(id) else {
+                            let name = failed.lock().unwrap().clone().unwrap_or_default();
+                            let mut outcome =
+                                StepOutcome::skipped(&member.command, format!("not started, '{}' failed", name));
+                            outcome.step_id = member_id;
+                            self.emit(BatchEvent::step_finished(run.id, &outcome));
+                            return outcome;
+                        };
+                        let outcome = self.run_started(run, member, member_id, None);
+                        // Cancel before releasing the slot, which would let the next member in
+                        if !outcome.success && group.join == JoinPolicy::FailFast {
+                            failed.lock().unwrap().get_or_insert_with(|| member.command.clone());
+                            for &other in ids {
+                                run.queue.cancel(other);
+                            }
+                        }
+                        drop(slot);
+                        outcome
+                    })
+                })
+                .collect();
+            members.into_iter().map(|member| member.join().unwrap()).collect()
+        })
+    }
+
+    // Asks the prompt's question until it gets a valid answer or the prompter gives up.
+    fn run_prompt(&self, run: &RunContext, step: &BatchStep, step_id: &str) -> StepOutcome {
+        let prompt = step.prompt.as_ref().expect("prompt steps have a prompt");
+        self.emit(BatchEvent::StepStarted {
+            run_id: run.id,
+            step_id: step_id.to_string(),
+            command: step.label(),
+        });
+        let started = Instant::now();
+        let mut request = PromptRequest {
+            run_id: run.id,
+            step_id: step_id.to_string(),
+            prompt: prompt.clone(),
+            error: None,
+        };
+        let answer = loop {
+            let Some(prompter) = self.prompter else {
+                break Err(CommandArgusError::InvalidCommand("prompts cannot be answered here".to_string()));
+            };
+            match prompter.ask(&request).map(|answer| prompt.accept(&answer)) {
+                Ok(Err(e)) => request.error = Some(e.to_string()),
+                Ok(accepted) => break accepted,
+                Err(e) => break Err(e),
+            }
+        };
+        let mut outcome = match answer {
+            Ok((success, answer)) => StepOutcome {
+                success,
+                exit_code: Some(if success { 0 } else { 1 }),
+                stdout: answer,
+                ..StepOutcome::failed(&step.label(), None, started.elapsed())
+            },
+            Err(e) => StepOutcome::failed(&step.label(), Some(e.to_string()), started.elapsed()),
+        };
+        outcome.step_id = step_id.to_string();
+        self.emit(BatchEvent::step_finished(run.id, &outcome));
+        outcome
+    }
+
+    fn run_queued(&self, run: &RunContext, step: &BatchStep, step_id: &str, prev: Option<&StepOutcome>) -> StepOutcome {
+        let command = &run.commands[step.command.as_str()];
+        let _slot = run.queue.acquire(command).expect("batch executions are never cancelled");
+        self.run_started(run, step, step_id.to_string(), prev)
+    }
+
+    // Runs a step that holds its queue slot, reporting its start and end.
+    fn run_started(&self, run: &RunContext, step: &BatchStep, step_id: String, prev: Option<&StepOutcome>) -> StepOutcome {
+        self.emit(BatchEvent::StepStarted {
+            run_id: run.id,
+            step_id: step_id.clone(),
+            command: step.command.clone(),
+        });
+        let command = &run.commands[step.command.as_str()];
+        let mut outcome = self.run_step(run.id, command, step, step.use_shell.unwrap_or(run.use_shell), prev);
+        outcome.step_id = step_id;
+        self.emit(BatchEvent::step_finished(run.id, &outcome));
+        outcome
+    }
+
+    /// Runs every saved command tagged `tag` as a smoke-test suite, in name order.
+    /// Commands for other platforms are left out; a command passes when it exits
+    /// successfully and all of its assertions hold.
+    pub fn run_suite(&self, tag: &str, parallelism: usize, use_shell: bool) -> Result<BatchReport> {
+        let mut commands = self.storage.search_by_tags(&[tag.to_string()])?;
+        commands.retain(|command| command.supports_platform(Platform::current()));
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        let plan = BatchPlan {
+            parallelism: parallelism.max(1),
+            use_shell,
+            steps: commands
+                .into_iter()
+                .map(|command| BatchStep {
+                    command: command.name,
+                    parameters: HashMap::new(),
+                    use_shell: None,
+                    stdin_from_prev: false,
+                    prev_output: PassOutput::default(),
+                    condition: None,
+                    otherwise: None,
+                    parallel: Vec::new(),
+                    join: JoinPolicy::default(),
+                    prompt: None,
+                })
+                .collect(),
+        };
+        self.run(&plan)
+    }
+
+    fn run_step(
+        &self,
+        run_id: Uuid,
+        command: &Command,
+        step: &BatchStep,
+        use_shell: bool,
+        prev: Option<&StepOutcome>,
+    ) -> StepOutcome {
+        let started = Instant::now();
+        let failed = |error: String| StepOutcome::failed(&step.command, Some(error), started.elapsed());
+
+        if let Err(e) = ensure_no_approval_needed(command) {
+            return failed(e.to_string());
+        }
+
+        let passed = match prev.map(|prev| step.prev_output.apply(prev)).transpose() {
+            Ok(passed) => passed,
+            Err(e) => return failed(e.to_string()),
+        };
+        let mut parameters = step.parameters.clone();
+        if let Some(ref passed) = passed {
+            let placeholder = format!("{{{}}}", PREV_STDOUT);
+            for value in parameters.values_mut() {
+                *value = value.replace(&placeholder, passed);
+            }
+        }
+
+        let mut values = match resolve_parameters(command, &parameters) {
+            Ok(values) => values,
+            Err(e) => return failed(e.to_string()),
+        };
+
+        let scrubber = match OutputScrubber::for_command(command, &values, &[]) {
+            Ok(scrubber) => scrubber,
+            Err(e) => return failed(e.to_string()),
+        };
+
+        // Resolve the platform variant first so its arguments get the parameter values too
+        let mut command = match command.for_platform(Platform::current()) {
+            Ok(command) => command,
+            Err(e) => return failed(e.to_string()),
+        };
+        if let Some(ref passed) = passed {
+            values.insert(PREV_STDOUT.to_string(), passed.clone());
+        }
+        let (new_command, new_args) = command.replace_placeholders(&values);
+        command.command = new_command;
+        command.args = new_args;
+        command.working_directory = command.replace_working_directory_placeholders(&values);
+        command.stdin_template = command.render_stdin(&values);
+
+        // Every step of a run sees the run's id
+        let executor = self.executor.clone().with_run_id(run_id);
+        let executor = match passed {
+            Some(passed) if step.stdin_from_prev => executor.with_stdin(passed.into_bytes()),
+            _ => executor,
+        };
+        let result = if use_shell {
+            executor.execute_with_shell(&command)
+        } else {
+            executor.execute(&command)
+        };
+
+        match result {
+            Ok(mut result) => {
+                scrubber.scrub_result(&mut result);
+                StepOutcome {
+                    step_id: String::new(),
+                    command: step.command.clone(),
+                    success: result.success && result.assertions_passed(),
+                    exit_code: Some(result.exit_code),
+                    duration: started.elapsed(),
+                    stdout: result.stdout,
+                    stderr: result.stderr,
+                    error: None,
+                    assertion_results: result.assertion_results,
+                    skipped: None,
+                }
+            }
+            Err(e) => failed(e.to_string()),
+        }
+    }
+}
+
+/// Fills in parameter defaults and checks that every required parameter has a value.
+pub fn resolve_parameters(
+    command: &Command,
+    values: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let mut resolved = values.clone();
+    for parameter in &command.parameters {
+        if resolved.contains_key(&parameter.name) {
+            continue;
+        }
+        match &parameter.default_value {
+            Some(default) => {
+                resolved.insert(parameter.name.clone(), default.clone());
+            }
+            None if parameter.required => {
+                return Err(CommandArgusError::InvalidCommand(format!(
+                    "missing value for required parameter '{}'",
+                    parameter.name
+                )));
+            }
+            None => {}
+        }
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use crate::assertions::Assertion;
+    use crate::command::{CommandParameter, ParameterType};
+    use tempfile::TempDir;
+
+    fn temp_storage() -> (CommandStorage, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = CommandStorage::with_path(temp_dir.path().join("commands.json")).unwrap();
+        (storage, temp_dir)
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = BatchPlan::from_yaml_str(
+            "parallelism: 2\nsteps:\n  - command: Greet\n    parameters:\n      who: CI\n  - command: List\n",
+        )
+        .unwrap();
+        assert_eq!(plan.parallelism, 2);
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].parameters.get("who").unwrap(), "CI");
+        assert!(!plan.use_shell);
+
+        assert!(BatchPlan::from_yaml_str("parallelism: 0\nsteps: []\n").is_err());
+    }
+
+    #[test]
+    fn test_run_plan() {
+        let (storage, _temp) = temp_storage();
+        let mut greet = Command::new("Greet".to_string(), "echo".to_string())
+            .with_args(vec!["hello {who}".to_string()]);
+        greet.add_parameter(CommandParameter {
+            name: "who".to_string(),
+            placeholder: "Name".to_string(),
+            parameter_type: ParameterType::Text,
+            required: true,
+            default_value: None,
+            options: None,
+            secret: false,
+        });
+        storage.create(EditAccess::unrestricted(), greet).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Fail".to_string(), "false".to_string())).unwrap();
+
+        let executor = CommandExecutor::new();
+        let runner = BatchRunner::new(&storage, &executor);
+        let plan = BatchPlan::from_yaml_str(
+            "parallelism: 2\nsteps:\n  - command: Greet\n    parameters:\n      who: CI\n  - command: Greet\n  - command: Fail\n",
+        )
+        .unwrap();
+
+        let report = runner.run(&plan).unwrap();
+        assert_eq!(report.outcomes.len(), 3);
+        assert!(report.outcomes[0].success);
+        assert!(report.outcomes[0].stdout.contains("hello CI"));
+        assert!(report.outcomes[1].error.as_ref().unwrap().contains("who"));
+        assert!(!report.outcomes[2].success);
+        assert_eq!(report.failed(), 2);
+        assert!(!report.success());
+        assert!(report.summary().contains("1 passed, 2 failed"));
+    }
+
+    #[test]
+    fn test_chain_passes_output() {
+        let (storage, _temp) = temp_storage();
+        storage.create(EditAccess::unrestricted(), Command::new("Pods".to_string(), "printf".to_string())
+            .with_args(vec!["\\n  web-1  \\nweb-2\\n".to_string()])).unwrap();
+        let mut describe = Command::new("Describe".to_string(), "echo".to_string())
+            .with_args(vec!["describing {pod}".to_string()]);
+        describe.add_parameter(CommandParameter {
+            name: "pod".to_string(),
+            placeholder: "Pod".to_string(),
+            parameter_type: ParameterType::Text,
+            required: true,
+            default_value: None,
+            options: None,
+            secret: false,
+        });
+        storage.create(EditAccess::unrestricted(), describe).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Upper".to_string(), "tr".to_string())
+            .with_args(vec!["a-z".to_string(), "A-Z".to_string()])).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Echo".to_string(), "echo".to_string())
+            .with_args(vec!["got {prev.stdout}".to_string()])).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Fail".to_string(), "false".to_string())).unwrap();
+
+        let executor = CommandExecutor::new();
+        let runner = BatchRunner::new(&storage, &executor);
+        let plan = BatchPlan::from_yaml_str(
+            "steps:\n  - command: Pods\n  - command: Describe\n    parameters:\n      pod: \"{prev.stdout}\"\n    prev_output:\n      trim: first_line\n  - command: Upper\n    stdin_from_prev: true\n  - command: Echo\n",
+        )
+        .unwrap();
+        let report = runner.run(&plan).unwrap();
+        assert!(report.success(), "{}", report.summary());
+        assert_eq!(report.outcomes[1].stdout, "describing web-1\n");
+        assert_eq!(report.outcomes[2].stdout, "DESCRIBING WEB-1");
+        assert_eq!(report.outcomes[3].stdout, "got DESCRIBING WEB-1\n");
+
+        // Too much output, or output of a failed step, fails the next step
+        let plan = BatchPlan::from_yaml_str(
+            "steps:\n  - command: Pods\n  - command: Echo\n    prev_output:\n      max_bytes: 4\n  - command: Fail\n  - command: Echo\n",
+        )
+        .unwrap();
+        let report = runner.run(&plan).unwrap();
+        assert!(report.outcomes[1].error.as_ref().unwrap().contains("more than the 4 bytes"));
+        assert!(report.outcomes[3].error.as_ref().unwrap().contains("'Fail' failed"));
+
+        let first = BatchPlan::from_yaml_str("steps:\n  - command: Echo\n").unwrap();
+        assert!(runner.run(&first).is_err());
+        let parallel = BatchPlan::from_yaml_str("parallelism: 2\nsteps:\n  - command: Pods\n  - command: Upper\n    stdin_from_prev: true\n").unwrap();
+        assert!(runner.run(&parallel).is_err());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let (storage, _temp) = temp_storage();
+        storage.create(EditAccess::unrestricted(), Command::new("Status".to_string(), "echo".to_string())
+            .with_args(vec!["phase: Pending".to_string()])).unwrap();
+        for name in ["Deploy", "Wait", "Alert"] {
+            storage.create(EditAccess::unrestricted(), Command::new(name.to_string(), "echo".to_string())
+                .with_args(vec![name.to_lowercase()])).unwrap();
+        }
+        storage.create(EditAccess::unrestricted(), Command::new("Fail".to_string(), "false".to_string())).unwrap();
+
+        let executor = CommandExecutor::new();
+        let runner = BatchRunner::new(&storage, &executor);
+        let plan = BatchPlan::from_yaml_str(
+            "steps:
+  - command: Status
+  - command: Deploy
+    if: {kind: stdout_matches, pattern: 'phase: Running'}
+    else:
+      command: Wait
+      if: {kind: exit_code, code: 0}
+  - command: Fail
+  - command: Deploy
+    if: {kind: exit_code, code: 0}
+  - command: Alert
+    if: {kind: exit_code, code: 0}
+    else:
+      command: Alert
+      if: {kind: exit_code, code: 1}
+",
+        )
+        .unwrap();
+        assert_eq!(plan.command_names(), vec!["Status", "Deploy", "Wait", "Fail", "Alert"]);
+        // Alert's branches belong to one step, of which only one runs
+        assert_eq!(
+            plan.planned_runs(),
+            vec![("Status", 1), ("Deploy", 2), ("Wait", 1), ("Fail", 1), ("Alert", 1)]
+        );
+        let report = runner.run(&plan).unwrap();
+        let ran: Vec<(&str, bool)> = report.outcomes.iter().map(|o| (o.command.as_str(), o.skipped.is_some())).collect();
+        assert_eq!(ran, vec![("Status", false), ("Wait", false), ("Fail", false), ("Deploy", true), ("Alert", true)]);
+        assert_eq!(report.outcomes[1].stdout, "wait\n");
+        assert_eq!((report.passed(), report.failed(), report.skipped()), (2, 1, 2));
+        assert!(report.summary().contains("[SKIP] Deploy (condition not met"));
+        assert!(report.summary().contains("2 passed, 1 failed, 2 skipped"));
+
+        let bad = BatchPlan::from_yaml_str(
+            "steps:\n  - command: Status\n  - command: Deploy\n    if: {kind: stdout_matches, pattern: '('}\n",
+        )
+        .unwrap();
+        assert!(runner.run(&bad).is_err());
+        let typo = BatchPlan::from_yaml_str(
+            "steps:\n  - command: Status\n  - command: Deploy\n    if: {kind: exit_code, code: 0}\n    else:\n      command: Rollbak\n",
+        )
+        .unwrap();
+        assert!(runner.run(&typo).is_err());
+    }
+
+    #[test]
+    fn test_parallel_group() {
+        let (storage, _temp) = temp_storage();
+        let sleep = |name: &str, mutex_group: Option<&str>| {
+            let mut cmd = Command::new(name.to_string(), "sleep".to_string())
+                .with_args(vec!["0.3".to_string()]);
+            cmd.mutex_group = mutex_group.map(String::from);
+            cmd
+        };
+        storage.create(EditAccess::unrestricted(), sleep("Install", None)).unwrap();
+        storage.create(EditAccess::unrestricted(), sleep("Start db", None)).unwrap();
+        let mut failing = sleep("Migrate", Some("db"));
+        failing.args = vec!["-c".to_string(), "sleep 0.1; exit 1".to_string()];
+        failing.command = "sh".to_string();
+        storage.create(EditAccess::unrestricted(), failing).unwrap();
+        storage.create(EditAccess::unrestricted(), sleep("Seed", Some("db"))).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Done".to_string(), "echo".to_string())).unwrap();
+
+        let executor = CommandExecutor::new();
+        let runner = BatchRunner::new(&storage, &executor);
+        let plan = BatchPlan::from_yaml_str(
+            "steps:
+  - parallel:
+      - command: Install
+      - command: Start db
+  - command: Done
+    if: {kind: exit_code, code: 0}
+",
+        );
+        // A condition needs a single previous outcome
+        assert!(runner.run(&plan.unwrap()).is_err());
+
+        let plan = BatchPlan::from_yaml_str(
+            "steps:\n  - parallel:\n      - command: Install\n      - command: Start db\n  - command: Done\n",
+        )
+        .unwrap();
+        let report = runner.run(&plan).unwrap();
+        let names: Vec<&str> = report.outcomes.iter().map(|o| o.command.as_str()).collect();
+        assert_eq!(names, vec!["Install", "Start db", "Done"]);
+        assert!(report.success(), "{}", report.summary());
+        assert!(report.duration < Duration::from_millis(550), "{:?}", report.duration);
+
+        // Seed waits for Migrate's mutex group and is cancelled once Migrate fails
+        let plan = BatchPlan::from_yaml_str(
+            "steps:\n  - parallel:\n      - command: Migrate\n      - command: Seed\n      - command: Install\n    join: fail_fast\n",
+        )
+        .unwrap();
+        let report = runner.run(&plan).unwrap();
+        assert!(!report.outcomes[0].success);
+        assert_eq!(report.outcomes[1].skipped.as_deref(), Some("not started, 'Migrate' failed"));
+        assert!(report.outcomes[2].success);
+
+        let mut wait_all = plan.clone();
+        wait_all.steps[0].join = JoinPolicy::WaitAll;
+        let report = runner.run(&wait_all).unwrap();
+        assert!(report.outcomes[1].success);
+
+        assert!(BatchPlan::from_yaml_str("steps:\n  - parallel: []\n").is_err());
+        let plan = BatchPlan::from_yaml_str("steps:\n  - parallel:\n      - command: Seed\n      - command: Seed\n  - command: Seed\n").unwrap();
+        assert_eq!(plan.planned_runs(), vec![("Seed", 3)]);
+        assert!(BatchPlan::from_yaml_str("steps:\n  - command: Done\n    parallel:\n      - command: Seed\n").is_err());
+        assert!(BatchPlan::from_yaml_str(
+            "steps:\n  - parallel:\n      - command: Seed\n        if: {kind: exit_code, code: 0}\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_progress_events() {
+        let (storage, _temp) = temp_storage();
+        for name in ["A", "B", "C"] {
+            storage.create(EditAccess::unrestricted(), Command::new(name.to_string(), "echo".to_string())).unwrap();
+        }
+        let plan = BatchPlan::from_yaml_str(
+            "steps:\n  - command: A\n  - command: A\n    if: {kind: exit_code, code: 3}\n  - parallel:\n      - command: B\n      - command: C\n",
+        )
+        .unwrap();
+        let events = Mutex::new(Vec::new());
+        let executor = CommandExecutor::new();
+        let report = BatchRunner::new(&storage, &executor)
+            .with_progress(|event| events.lock().unwrap().push(event.clone()))
+            .run(&plan)
+            .unwrap();
+        let events = events.into_inner().unwrap();
+
+        let ids: Vec<&str> = report.outcomes.iter().map(|o| o.step_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3.1", "3.2"]);
+        let started = events.iter().filter(|e| matches!(e, BatchEvent::StepStarted { .. })).count();
+        let finished = events.iter().filter(|e| matches!(e, BatchEvent::StepFinished { .. })).count();
+        assert_eq!((started, finished), (3, 4));
+        assert!(events.iter().all(|e| match e {
+            BatchEvent::StepStarted { run_id, .. }
+            | BatchEvent::StepFinished { run_id, .. }
+            | BatchEvent::Progress { run_id, .. } => *run_id == report.run_id,
+        }));
+        let percents: Vec<u8> = events
+            .iter()
+            .filter_map(|e| match e {
+                BatchEvent::Progress { percent, .. } => Some(*percent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(percents, vec![33, 66, 100]);
+        assert!(matches!(events.last(), Some(BatchEvent::Progress { completed: 3, total: 3, .. })));
+        assert!(matches!(&events[3], BatchEvent::StepFinished { step_id, skipped: true, .. } if step_id == "2"));
+    }
+
+    // Answers prompts from a script, recording the rejections
+    struct ScriptedPrompter {
+        answers: Mutex<Vec<&'static str>>,
+        rejections: Mutex<Vec<String>>,
+    }
+
+    impl Prompter for ScriptedPrompter {
+        fn ask(&self, request: &PromptRequest) -> Result<String> {
+            if let Some(ref error) = request.error {
+                self.rejections.lock().unwrap().push(error.clone());
+            }
+            let mut answers = self.answers.lock().unwrap();
+            match answers.is_empty() {
+                true => Err(CommandArgusError::Cancelled("no more answers".to_string())),
+                false => Ok(answers.remove(0).to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_prompts() {
+        let (storage, _temp) = temp_storage();
+        storage.create(EditAccess::unrestricted(), Command::new("Echo".to_string(), "echo".to_string())
+            .with_args(vec!["deploying to {prev.stdout}".to_string()])).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Abort".to_string(), "echo".to_string())
+            .with_args(vec!["aborted".to_string()])).unwrap();
+        let plan = BatchPlan::from_yaml_str(
+            "steps:
+  - prompt: {question: Which cluster?, kind: choose, options: [staging, prod]}
+  - command: Echo
+  - prompt: {question: Restart the pods?, kind: confirm}
+  - command: Abort
+    if: {kind: exit_code, code: 1}
+  - prompt: {question: Reason, kind: input, default: routine}
+",
+        )
+        .unwrap();
+        assert_eq!(plan.command_names(), vec!["Echo", "Abort"]);
+
+        let prompter = ScriptedPrompter {
+            answers: Mutex::new(vec!["dev", "prod", "maybe", "no", ""]),
+            rejections: Mutex::new(Vec::new()),
+        };
+        let executor = CommandExecutor::new();
+        let report = BatchRunner::new(&storage, &executor).with_prompter(&prompter).run(&plan).unwrap();
+        let outcomes = &report.outcomes;
+        assert_eq!(outcomes[0].command, "Which cluster?");
+        assert_eq!(outcomes[0].stdout, "prod");
+        assert_eq!(outcomes[1].stdout, "deploying to prod\n");
+        assert_eq!((outcomes[2].success, outcomes[2].exit_code), (false, Some(1)));
+        assert_eq!(outcomes[3].stdout, "aborted\n");
+        assert_eq!(outcomes[4].stdout, "routine");
+        let rejections = prompter.rejections.into_inner().unwrap();
+        assert_eq!(rejections.len(), 2);
+        assert!(rejections[0].contains("one of staging, prod"));
+
+        // Without a prompter, or once it gives up, prompt steps fail
+        let report = BatchRunner::new(&storage, &executor).run(&plan).unwrap();
+        assert!(report.outcomes[0].error.as_ref().unwrap().contains("cannot be answered"));
+
+        let mut parallel = plan.clone();
+        parallel.parallelism = 2;
+        assert!(BatchRunner::new(&storage, &executor).run(&parallel).is_err());
+        assert!(BatchPlan::from_yaml_str("steps:\n  - command: Echo\n    prompt: {question: Sure?, kind: confirm}\n").is_err());
+        assert!(BatchPlan::from_yaml_str("steps:\n  - prompt: {question: Which?, kind: choose, options: []}\n").is_err());
+    }
+
+    #[test]
+    fn test_run_suite() {
+        let (storage, _temp) = temp_storage();
+        let tagged = |mut command: Command| {
+            command.add_tag("smoke".to_string());
+            command
+        };
+        
+        let mut healthy = tagged(Command::new("Healthy".to_string(), "echo".to_string())
+            .with_args(vec!["ok".to_string()]));
+        healthy.assertions = vec![Assertion::StdoutContains { text: "ok".to_string() }];
+        let mut wrong_output = tagged(Command::new("Degraded".to_string(), "echo".to_string())
+            .with_args(vec!["degraded".to_string()]));
+        wrong_output.assertions = vec![Assertion::StdoutContains { text: "ok".to_string() }];
+        storage.create(EditAccess::unrestricted(), healthy).unwrap();
+        storage.create(EditAccess::unrestricted(), wrong_output).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Untagged".to_string(), "false".to_string())).unwrap();
+        
+        let executor = CommandExecutor::new();
+        let report = BatchRunner::new(&storage, &executor).run_suite("smoke", 2, false).unwrap();
+        let names: Vec<&str> = report.outcomes.iter().map(|o| o.command.as_str()).collect();
+        assert_eq!(names, vec!["Degraded", "Healthy"]);
+        assert!(!report.outcomes[0].success);
+        assert_eq!(report.outcomes[0].exit_code, Some(0));
+        assert!(report.outcomes[1].success);
+        assert!(report.summary().contains("stdout contains \"ok\": stdout does not contain"), "{}", report.summary());
+        
+        let empty = BatchRunner::new(&storage, &executor).run_suite("nightly", 1, false).unwrap();
+        assert!(empty.outcomes.is_empty() && empty.success());
+    }
+
+    #[test]
+    fn test_mutex_group_serializes_steps() {
+        let (storage, temp) = temp_storage();
+        // Each step fails if the other one's marker file exists while it runs
+        for name in ["A", "B"] {
+            let other = if name == "A" { "B" } else { "A" };
+            let script = format!(
+                "test ! -e {other} && touch {name} && sleep 0.2 && test ! -e {other} && rm {name}"
+            );
+            let mut cmd = Command::new(name.to_string(), "sh".to_string())
+                .with_args(vec!["-c".to_string(), script])
+                .with_working_directory(temp.path().to_string_lossy().to_string());
+            cmd.mutex_group = Some("exclusive".to_string());
+            storage.create(EditAccess::unrestricted(), cmd).unwrap();
+        }
+        
+        let executor = CommandExecutor::new();
+        let plan = BatchPlan::from_yaml_str("parallelism: 2\nsteps:\n  - command: A\n  - command: B\n").unwrap();
+        let report = BatchRunner::new(&storage, &executor).run(&plan).unwrap();
+        assert!(report.success(), "{}", report.summary());
+        assert!(report.duration >= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_unknown_command_rejected() {
+        let (storage, _temp) = temp_storage();
+        let executor = CommandExecutor::new();
+        let runner = BatchRunner::new(&storage, &executor);
+        let plan = BatchPlan::from_yaml_str("steps:\n  - command: Missing\n").unwrap();
+        assert!(runner.run(&plan).is_err());
+    }
+}
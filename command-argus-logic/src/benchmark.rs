@@ -0,0 +1,356 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executor::{CommandExecutor, ExecutionResult};
+use crate::queue::ExecutionQueue;
+
+/// One measured run of a benchmark.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BenchmarkRun {
+    /// 1-based, not counting the warmup runs.
+    pub iteration: usize,
+    pub exit_code: i32,
+    pub success: bool,
+    pub duration: Duration,
+}
+
+/// Statistics over the durations of a benchmark's runs.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BenchmarkStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Nearest-rank 95th percentile.
+    pub p95: Duration,
+    /// Sample standard deviation; zero for a single run.
+    pub stddev: Duration,
+}
+
+impl BenchmarkStats {
+    /// `None` without any durations.
+    pub fn from_durations(durations: &[Duration]) -> Option<Self> {
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+        let count = sorted.len();
+        let (min, max) = (*sorted.first()?, *sorted.last()?);
+        let seconds: Vec<f64> = sorted.iter().map(Duration::as_secs_f64).collect();
+        let mean = seconds.iter().sum::<f64>() / count as f64;
+        let median = match count % 2 {
+            1 => seconds[count / 2],
+            _ => (seconds[count / 2 - 1] + seconds[count / 2]) / 2.0,
+        };
+        let p95 = sorted[((count as f64 * 0.95).ceil() as usize).max(1) - 1];
+        let variance = match count {
+            1 => 0.0,
+            _ => seconds.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1) as f64,
+        };
+        Some(Self {
+            min,
+            max,
+            mean: Duration::from_secs_f64(mean),
+            median: Duration::from_secs_f64(median),
+            p95,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+/// The measured runs of a command and their statistics.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub command_name: String,
+    /// Runs made before measuring, to warm caches; not in `runs`.
+    pub warmup: usize,
+    pub runs: Vec<BenchmarkRun>,
+    pub stats: BenchmarkStats,
+}
+
+impl BenchmarkReport {
+    pub fn failed(&self) -> usize {
+        self.runs.iter().filter(|run| !run.success).count()
+    }
+
+    /// In the manner of hyperfine.
+    pub fn summary(&self) -> String {
+        let ms = |duration: Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+        let mut lines = vec![
+            format!("Benchmark: {} ({} runs, {} warmup)", self.command_name, self.runs.len(), self.warmup),
+            format!("  Time (mean ± σ):     {} ± {}", ms(self.stats.mean), ms(self.stats.stddev)),
+            format!("  Time (median, p95):  {}, {}", ms(self.stats.median), ms(self.stats.p95)),
+            format!("  Range (min … max):   {} … {}", ms(self.stats.min), ms(self.stats.max)),
+        ];
+        if self.failed() > 0 {
+            lines.push(format!("  {} of {} runs exited with a non-zero code", self.failed(), self.runs.len()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Which of two compared commands.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BenchmarkSide {
+    A,
+    B,
+}
+
+/// How sure a comparison is that one command is faster, from Welch's t statistic read
+/// against the normal distribution: `High` beyond the 99% level, `Medium` beyond 95%.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    High,
+    Medium,
+    /// The difference is within the noise of the runs.
+    Low,
+}
+
+/// Two commands benchmarked the same way, side by side.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkComparison {
+    pub a: BenchmarkReport,
+    pub b: BenchmarkReport,
+    pub faster: BenchmarkSide,
+    /// How many times longer the slower command takes on average; at least 1.
+    pub ratio: f64,
+    /// Uncertainty of `ratio`, from both commands' standard deviations.
+    pub ratio_stddev: f64,
+    /// Welch's t statistic of the difference of the means.
+    pub t: f64,
+    pub confidence: Confidence,
+}
+
+impl BenchmarkComparison {
+    pub fn new(a: BenchmarkReport, b: BenchmarkReport) -> Self {
+        let mean_and_sd = |report: &BenchmarkReport| {
+            (report.stats.mean.as_secs_f64(), report.stats.stddev.as_secs_f64(), report.runs.len() as f64)
+        };
+        let ((mean_a, sd_a, n_a), (mean_b, sd_b, n_b)) = (mean_and_sd(&a), mean_and_sd(&b));
+        let (faster, fast, slow, sd_fast, sd_slow) = match mean_a <= mean_b {
+            true => (BenchmarkSide::A, mean_a, mean_b, sd_a, sd_b),
+            false => (BenchmarkSide::B, mean_b, mean_a, sd_b, sd_a),
+        };
+        let ratio = match fast > 0.0 {
+            true => slow / fast,
+            false => 1.0,
+        };
+        // Relative errors add in quadrature for a quotient
+        let relative = |sd: f64, mean: f64| if mean > 0.0 { sd / mean } else { 0.0 };
+        let ratio_stddev = ratio * (relative(sd_fast, fast).powi(2) + relative(sd_slow, slow).powi(2)).sqrt();
+
+        let standard_error = (sd_a.powi(2) / n_a + sd_b.powi(2) / n_b).sqrt();
+        let t = match (standard_error > 0.0, mean_a == mean_b) {
+            (_, true) => 0.0,
+            (true, false) => (mean_a - mean_b) / standard_error,
+            // No spread at all, yet different means
+            (false, false) => (mean_a - mean_b).signum() * f64::INFINITY,
+        };
+        let confidence = match t.abs() {
+            t if t >= 2.576 => Confidence::High,
+            t if t >= 1.96 => Confidence::Medium,
+            _ => Confidence::Low,
+        };
+        Self { a, b, faster, ratio, ratio_stddev, t, confidence }
+    }
+
+    pub fn summary(&self) -> String {
+        let (fast, slow) = match self.faster {
+            BenchmarkSide::A => (&self.a, &self.b),
+            BenchmarkSide::B => (&self.b, &self.a),
+        };
+        let verdict = match self.confidence {
+            Confidence::High => "a clear difference",
+            Confidence::Medium => "a likely difference",
+            Confidence::Low => "within the noise of the runs",
+        };
+        [
+            self.a.summary(),
+            self.b.summary(),
+            format!(
+                "{} ran {:.2} ± {:.2} times faster than {} ({})",
+                fast.command_name, self.ratio, self.ratio_stddev, slow.command_name, verdict
+            ),
+        ]
+        .join("\n\n")
+    }
+}
+
+type RunListener<'a> = Box<dyn Fn(&BenchmarkRun) + Send + Sync + 'a>;
+
+/// Runs a command over and over to time it, a small hyperfine for saved commands.
+pub struct BenchmarkRunner<'a> {
+    executor: &'a CommandExecutor,
+    use_shell: bool,
+    progress: Option<RunListener<'a>>,
+    queue: Option<&'a ExecutionQueue>,
+}
+
+impl<'a> BenchmarkRunner<'a> {
+    pub fn new(executor: &'a CommandExecutor) -> Self {
+        Self { executor, use_shell: false, progress: None, queue: None }
+    }
+
+    /// Waits for a turn in `queue` before every run, warmups included, so runs respect
+    /// the concurrency limit and the command's mutex group like any other.
+    pub fn with_queue(mut self, queue: &'a ExecutionQueue) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    pub fn with_shell(mut self, use_shell: bool) -> Self {
+        self.use_shell = use_shell;
+        self
+    }
+
+    /// Called after each measured run.
+    pub fn with_progress(mut self, listener: impl Fn(&BenchmarkRun) + Send + Sync + 'a) -> Self {
+        self.progress = Some(Box::new(listener));
+        self
+    }
+
+    /// Runs `command` `warmup` times unmeasured, then `iterations` times one after the
+    /// other. Runs exiting with a non-zero code are measured all the same; a command
+    /// that cannot be started fails the benchmark.
+    pub fn run(&self, command: &Command, iterations: usize, warmup: usize) -> Result<BenchmarkReport> {
+        if iterations == 0 {
+            return Err(CommandArgusError::InvalidQuery("a benchmark needs at least one run".to_string()));
+        }
+        for _ in 0..warmup {
+            self.execute(command)?;
+        }
+        let mut runs = Vec::with_capacity(iterations);
+        for iteration in 1..=iterations {
+            let result = self.execute(command)?;
+            let run = BenchmarkRun {
+                iteration,
+                exit_code: result.exit_code,
+                success: result.success,
+                duration: result.duration,
+            };
+            if let Some(ref listener) = self.progress {
+                listener(&run);
+            }
+            runs.push(run);
+        }
+
+        let durations: Vec<Duration> = runs.iter().map(|run| run.duration).collect();
+        Ok(BenchmarkReport {
+            command_name: command.name.clone(),
+            warmup,
+            stats: BenchmarkStats::from_durations(&durations).expect("at least one run"),
+            runs,
+        })
+    }
+
+    /// Benchmarks `a`, then `b`, the same way.
+    pub fn compare(&self, a: &Command, b: &Command, iterations: usize, warmup: usize) -> Result<BenchmarkComparison> {
+        let a = self.run(a, iterations, warmup)?;
+        let b = self.run(b, iterations, warmup)?;
+        Ok(BenchmarkComparison::new(a, b))
+    }
+
+    fn execute(&self, command: &Command) -> Result<ExecutionResult> {
+        let _slot = self.queue.map(|queue| queue.acquire(command)).transpose()?;
+        match self.use_shell {
+            true => self.executor.execute_with_shell(command),
+            false => self.executor.execute(command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_benchmark_stats() {
+        let ms = Duration::from_millis;
+        let stats = BenchmarkStats::from_durations(&[ms(40), ms(10), ms(20), ms(30)]).unwrap();
+        assert_eq!((stats.min, stats.max), (ms(10), ms(40)));
+        assert_eq!(stats.mean, ms(25));
+        assert_eq!(stats.median, ms(25));
+        assert_eq!(stats.p95, ms(40));
+        assert_eq!(stats.stddev.as_micros(), 12_909);
+        assert_eq!(BenchmarkStats::from_durations(&[ms(7)]).unwrap().stddev, Duration::ZERO);
+        assert_eq!(BenchmarkStats::from_durations(&[]), None);
+    }
+
+    fn report(name: &str, millis: &[u64]) -> BenchmarkReport {
+        let runs: Vec<BenchmarkRun> = millis
+            .iter()
+            .enumerate()
+            .map(|(index, &ms)| BenchmarkRun {
+                iteration: index + 1,
+                exit_code: 0,
+                success: true,
+                duration: Duration::from_millis(ms),
+            })
+            .collect();
+        let durations: Vec<Duration> = runs.iter().map(|run| run.duration).collect();
+        BenchmarkReport {
+            command_name: name.to_string(),
+            warmup: 0,
+            stats: BenchmarkStats::from_durations(&durations).unwrap(),
+            runs,
+        }
+    }
+
+    #[test]
+    fn test_compare_benchmarks() {
+        let comparison = BenchmarkComparison::new(report("grep", &[98, 100, 102, 100]), report("rg", &[49, 50, 51, 50]));
+        assert_eq!(comparison.faster, BenchmarkSide::B);
+        assert!((comparison.ratio - 2.0).abs() < 1e-9);
+        assert!(comparison.ratio_stddev > 0.0 && comparison.ratio_stddev < 0.1);
+        assert_eq!(comparison.confidence, Confidence::High);
+        assert!(comparison.summary().ends_with("rg ran 2.00 ± 0.05 times faster than grep (a clear difference)"));
+
+        let noisy = BenchmarkComparison::new(report("a", &[50, 150, 90, 110]), report("b", &[60, 140, 100, 105]));
+        assert_eq!(noisy.faster, BenchmarkSide::A);
+        assert_eq!(noisy.confidence, Confidence::Low);
+        let same = BenchmarkComparison::new(report("a", &[10, 10]), report("b", &[10, 10]));
+        assert_eq!((same.ratio, same.t, same.confidence), (1.0, 0.0, Confidence::Low));
+    }
+
+    #[test]
+    fn test_benchmark_command() {
+        let executor = CommandExecutor::new();
+        let command = Command::new("Check".to_string(), "test".to_string())
+            .with_args(vec!["-e".to_string(), "/nonexistent-benchmark-file".to_string()]);
+        let seen = Mutex::new(Vec::new());
+        let report = BenchmarkRunner::new(&executor)
+            .with_progress(|run| seen.lock().unwrap().push(run.iteration))
+            .run(&command, 3, 1)
+            .unwrap();
+        assert_eq!(seen.into_inner().unwrap(), vec![1, 2, 3]);
+        assert_eq!(report.runs.len(), 3);
+        assert_eq!(report.failed(), 3);
+        assert!(report.runs.iter().all(|run| run.exit_code == 1));
+        assert!(report.stats.min <= report.stats.median && report.stats.median <= report.stats.max);
+        assert!(report.summary().contains("3 of 3 runs exited with a non-zero code"));
+
+        assert!(BenchmarkRunner::new(&executor).run(&command, 0, 0).is_err());
+        let comparison = BenchmarkRunner::new(&executor).compare(&command, &command, 2, 0).unwrap();
+        assert_eq!((comparison.a.runs.len(), comparison.b.runs.len()), (2, 2));
+        let missing = Command::new("Missing".to_string(), "/nonexistent/benchmark-tool".to_string());
+        assert!(BenchmarkRunner::new(&executor).run(&missing, 2, 0).is_err());
+
+        // Every run, warmups included, waits for its turn in the queue
+        let queue = ExecutionQueue::new(1);
+        let enqueued = Arc::new(AtomicUsize::new(0));
+        let counter = enqueued.clone();
+        queue.set_listener(move |waiting| {
+            if !waiting.is_empty() {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        BenchmarkRunner::new(&executor).with_queue(&queue).run(&command, 3, 1).unwrap();
+        assert_eq!(enqueued.load(Ordering::SeqCst), 4);
+        assert_eq!(queue.running(), 0);
+    }
+}
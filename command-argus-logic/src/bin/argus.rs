@@ -0,0 +1,503 @@
+use command_argus_logic::{stop_on_signal, Agent, diff_bundles, export_bundle, export_runbook, load_bundle, resolve_parameters, BatchPlan, BatchReport, BatchRunner, CommandArgusError, CommandExecutor, CommandStorage, ForEachRunner, Platform, Profile, ProfileStorage, PromptKind, PromptRequest, Prompter, ReportFormat, SettingsStorage, VariableStorage};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+  argus exec-file <plan.yaml> [--profile <name>] [--storage <commands.json>] [--report <file>]...
+  argus suite <tag> [--parallel <n>] [--shell] [--profile <name>] [--storage <commands.json>] [--report <file>]...
+  argus list [--query <query>] [--storage <commands.json>]
+  argus export <bundle.json> [--tag <tag>] [--storage <commands.json>]
+  argus diff <old-bundle.json> <new-bundle.json>
+  argus runbook <plan.yaml> [--title <title>] [--storage <commands.json>]
+  argus foreach <command> <pattern> [--root <dir>] [--parallel <n>] [--shell] [--profile <name>] [--storage <commands.json>]
+  argus agent
+
+Reports ending in .html are written as HTML, anything else as JUnit XML.
+foreach runs the command in every directory under --root (default: the current one)
+matching the glob, e.g. '**/package.json' for each npm package.
+agent runs the background agent until it is interrupted.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("exec-file") => exec_file(&args[1..]),
+        Some("suite") => suite(&args[1..]),
+        Some("list") => list(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("diff") => diff(&args[1..]),
+        Some("runbook") => runbook(&args[1..]),
+        Some("foreach") => foreach(&args[1..]),
+        Some("agent") if args.len() == 1 => agent(),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Opens the command library, either the default one or the file given via `--storage`.
+/// Returns the remaining positional arguments alongside the storage.
+fn open_storage(args: &[String]) -> Result<(CommandStorage, Vec<String>), String> {
+    let mut storage_path = None;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--storage" {
+            let path = iter.next().ok_or("--storage requires a path")?;
+            storage_path = Some(PathBuf::from(path));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    let storage = match storage_path {
+        Some(path) => CommandStorage::with_path(path),
+        None => CommandStorage::new(),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok((storage, positional))
+}
+
+/// Splits a `--profile <name>` option off the positional arguments and loads the profile.
+fn take_profile(args: Vec<String>) -> Result<(Option<Profile>, Vec<String>), String> {
+    let mut name = None;
+    let mut rest = Vec::new();
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            name = Some(iter.next().ok_or("--profile requires a name")?);
+        } else {
+            rest.push(arg);
+        }
+    }
+    let profile = match name {
+        Some(name) => Some(ProfileStorage::new().and_then(|profiles| profiles.get(&name)).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    Ok((profile, rest))
+}
+
+/// The executor for commands run from here, keeping to the app's restricted mode and
+/// scrubbing secrets from the output like the app does.
+fn executor_with(profile: Option<Profile>) -> Option<CommandExecutor> {
+    let settings = SettingsStorage::new().and_then(|settings| settings.load()).map_err(|e| eprintln!("{}", e)).ok()?;
+    let executor = CommandExecutor::new()
+        .with_restricted_mode(settings.restricted_mode)
+        .with_secret_patterns(settings.secret_patterns);
+    Some(match profile {
+        Some(profile) => executor.with_profile(profile),
+        None => executor,
+    })
+}
+
+/// Opens the template variables. `{{var:NAME}}` references resolve against the
+/// workspace of the current directory first, then the global variables.
+fn open_variables() -> Option<VariableStorage> {
+    VariableStorage::new().map_err(|e| eprintln!("{}", e)).ok()
+}
+
+/// Splits `--report <file>` options off the positional arguments.
+fn take_report_paths(args: Vec<String>) -> Result<(Vec<PathBuf>, Vec<String>), String> {
+    let mut reports = Vec::new();
+    let mut rest = Vec::new();
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--report" {
+            reports.push(PathBuf::from(iter.next().ok_or("--report requires a path")?));
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((reports, rest))
+}
+
+fn exec_file(args: &[String]) -> ExitCode {
+    let parsed = open_storage(args).and_then(|(storage, rest)| {
+        let (reports, rest) = take_report_paths(rest)?;
+        let (profile, rest) = take_profile(rest)?;
+        Ok((storage, reports, profile, rest))
+    });
+    let (storage, reports, profile, positional) = match parsed {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let [plan_path] = positional.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let plan = match BatchPlan::from_file(&PathBuf::from(plan_path)) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("Failed to read plan {}: {}", plan_path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(variables) = open_variables() else {
+        return ExitCode::from(2);
+    };
+    let workspace = env::current_dir().ok();
+    let Some(executor) = executor_with(profile) else {
+        return ExitCode::from(2);
+    };
+    let report = BatchRunner::new(&storage, &executor)
+        .with_prompter(&TerminalPrompter)
+        .with_variables(&variables, workspace.as_deref())
+        .run(&plan);
+    print_report(report, plan_path, &reports)
+}
+
+/// Asks the questions of prompt steps on the terminal. Without an answer (end of input,
+/// e.g. in CI) the prompt step fails.
+struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn ask(&self, request: &PromptRequest) -> Result<String, CommandArgusError> {
+        if let Some(ref error) = request.error {
+            eprintln!("{}", error);
+        }
+        let hint = match request.prompt.kind {
+            PromptKind::Confirm => " [y/n]".to_string(),
+            PromptKind::Choose { ref options } => format!(" [{}]", options.join("/")),
+            PromptKind::Input { default: Some(ref default) } => format!(" [{}]", default),
+            PromptKind::Input { default: None } => String::new(),
+        };
+        eprint!("{}{} ", request.prompt.question, hint);
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Err(CommandArgusError::Cancelled("no answer on stdin".to_string()));
+        }
+        Ok(answer)
+    }
+}
+
+fn suite(args: &[String]) -> ExitCode {
+    let parsed = open_storage(args).and_then(|(storage, rest)| {
+        let (reports, rest) = take_report_paths(rest)?;
+        let (profile, rest) = take_profile(rest)?;
+        Ok((storage, reports, profile, rest))
+    });
+    let (storage, reports, profile, positional) = match parsed {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut tag = None;
+    let mut parallelism = 1;
+    let mut use_shell = false;
+    let mut iter = positional.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--shell" => use_shell = true,
+            "--parallel" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => parallelism = n,
+                None => {
+                    eprintln!("--parallel requires a number");
+                    return ExitCode::from(2);
+                }
+            },
+            _ if tag.is_none() => tag = Some(arg.clone()),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(tag) = tag else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let Some(variables) = open_variables() else {
+        return ExitCode::from(2);
+    };
+    let workspace = env::current_dir().ok();
+    let Some(executor) = executor_with(profile) else {
+        return ExitCode::from(2);
+    };
+    let report = BatchRunner::new(&storage, &executor)
+        .with_variables(&variables, workspace.as_deref())
+        .run_suite(&tag, parallelism, use_shell);
+    print_report(report, &tag, &reports)
+}
+
+/// Prints the commands matching `--query`, or all of them, one per line.
+fn list(args: &[String]) -> ExitCode {
+    let (storage, positional) = match open_storage(args) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let commands = match positional.as_slice() {
+        [] => storage.list(),
+        [flag, query] if flag == "--query" => storage.search_by_query(query),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match commands {
+        Ok(commands) => {
+            for command in commands {
+                println!("{}\t{}", command.name, command.full_command());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Exports the library, or the commands with `--tag`, and prints what changed since the
+/// bundle was last exported to the same file.
+fn export(args: &[String]) -> ExitCode {
+    let (storage, positional) = match open_storage(args) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let (path, tag) = match positional.as_slice() {
+        [path] => (path, None),
+        [path, flag, tag] if flag == "--tag" => (path, Some(tag.clone())),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let commands = match tag {
+        Some(tag) => storage.search_by_tags(&[tag]),
+        None => storage.list(),
+    };
+    match commands.and_then(|commands| export_bundle(commands, &PathBuf::from(path))) {
+        Ok(bundle) => {
+            println!("Exported {} commands to {}", bundle.commands.len(), path);
+            if let Some(changelog) = bundle.changelog {
+                println!("{}", changelog.summary());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn diff(args: &[String]) -> ExitCode {
+    let [old, new] = args else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let changelog = load_bundle(&PathBuf::from(old))
+        .and_then(|old| Ok((old, load_bundle(&PathBuf::from(new))?)))
+        .and_then(|(old, new)| diff_bundles(&old, &new));
+    match changelog {
+        Ok(changelog) => {
+            println!("{}", changelog.summary());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Runs the background agent in the foreground until SIGTERM or Ctrl+C.
+fn agent() -> ExitCode {
+    let served = Agent::new().and_then(|agent| {
+        let interrupted = agent.start()?;
+        if !interrupted.is_empty() {
+            eprintln!("Recorded {} runs the last session left behind", interrupted.len());
+        }
+        agent.serve(stop_on_signal())
+    });
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints a plan as a Markdown runbook, titled after the plan file unless `--title` is given.
+fn runbook(args: &[String]) -> ExitCode {
+    let (storage, positional) = match open_storage(args) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let (plan_path, title) = match positional.as_slice() {
+        [path] => (path, None),
+        [path, flag, title] if flag == "--title" => (path, Some(title.clone())),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let path = PathBuf::from(plan_path);
+    let title = title.unwrap_or_else(|| {
+        path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| plan_path.clone())
+    });
+    match BatchPlan::from_file(&path).and_then(|plan| export_runbook(&plan, &storage, &title)) {
+        Ok(markdown) => {
+            print!("{}", markdown);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the stderr of failed steps and the summary and writes the requested report
+/// files; the exit code reflects the outcome.
+/// Runs a saved command in each directory matching a glob and prints a combined summary.
+fn foreach(args: &[String]) -> ExitCode {
+    let parsed = open_storage(args).and_then(|(storage, rest)| {
+        let (profile, rest) = take_profile(rest)?;
+        Ok((storage, profile, rest))
+    });
+    let (storage, profile, positional) = match parsed {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut names = Vec::new();
+    let mut root = None;
+    let mut parallelism = 1;
+    let mut use_shell = false;
+    let mut iter = positional.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--shell" => use_shell = true,
+            "--root" => match iter.next() {
+                Some(dir) => root = Some(PathBuf::from(dir)),
+                None => {
+                    eprintln!("--root requires a directory");
+                    return ExitCode::from(2);
+                }
+            },
+            "--parallel" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => parallelism = n,
+                None => {
+                    eprintln!("--parallel requires a number");
+                    return ExitCode::from(2);
+                }
+            },
+            _ => names.push(arg.clone()),
+        }
+    }
+    let [name, pattern] = names.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let Some(variables) = open_variables() else {
+        return ExitCode::from(2);
+    };
+    let workspace = env::current_dir().ok();
+    let root = match root.or_else(|| workspace.clone()) {
+        Some(root) => root,
+        None => {
+            eprintln!("the current directory is not available; pass --root");
+            return ExitCode::from(2);
+        }
+    };
+    let Some(executor) = executor_with(profile) else {
+        return ExitCode::from(2);
+    };
+    // Parameters take their default values, as there is nobody to ask for them
+    let command = storage
+        .read_by_name(name)
+        .and_then(|command| variables.resolve_command(&command, workspace.as_deref(), executor.profile()))
+        .and_then(|command| {
+            let values = resolve_parameters(&command, &HashMap::new())?;
+            let mut command = command.for_platform(Platform::current())?;
+            let (program, args) = command.replace_placeholders(&values);
+            command.command = program;
+            command.args = args;
+            command.stdin_template = command.render_stdin(&values);
+            Ok(command)
+        });
+    let report = command.and_then(|command| {
+        ForEachRunner::new(&executor)
+            .with_parallelism(parallelism)
+            .with_shell(use_shell)
+            .run(&command, &root, pattern)
+    });
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    for outcome in report.outcomes.iter().filter(|o| !o.success) {
+        if !outcome.stderr.is_empty() {
+            eprintln!("--- {} stderr ---\n{}", outcome.relative_to(&report.root), outcome.stderr.trim_end());
+        }
+    }
+    println!("{}", report.summary());
+    if report.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_report(report: Result<BatchReport, CommandArgusError>, name: &str, report_paths: &[PathBuf]) -> ExitCode {
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    for outcome in report.outcomes.iter().filter(|o| !o.success) {
+        if !outcome.stderr.is_empty() {
+            eprintln!("--- {} stderr ---\n{}", outcome.command, outcome.stderr.trim_end());
+        }
+    }
+    println!("{}", report.summary());
+
+    for path in report_paths {
+        if let Err(e) = report.write_to(path, ReportFormat::from_path(path), name) {
+            eprintln!("Failed to write report {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    }
+
+    if report.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
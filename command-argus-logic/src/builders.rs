@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+
+use crate::command::{Command, EnvironmentVariable};
+use crate::error::{CommandArgusError, Result};
+
+/// A command described field by field for a tool the GUI has a form for.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "tool", rename_all = "snake_case")]
+pub enum CommandBuilder {
+    DockerRun(DockerRunBuilder),
+    Ssh(SshBuilder),
+}
+
+impl CommandBuilder {
+    /// A new command named `name` with the program, arguments and environment the
+    /// builder describes. Fields may contain `{placeholders}`.
+    pub fn build(&self, name: String) -> Result<Command> {
+        match self {
+            CommandBuilder::DockerRun(builder) => builder.build(name),
+            CommandBuilder::Ssh(builder) => builder.build(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PortMapping {
+    pub host: String,
+    pub container: String,
+    /// `tcp` when not given.
+    #[serde(default)]
+    pub protocol: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeMount {
+    pub source: String,
+    pub target: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// `docker run`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DockerRunBuilder {
+    pub image: String,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// `--name` of the container.
+    #[serde(default)]
+    pub container_name: Option<String>,
+    #[serde(default)]
+    pub ports: Vec<PortMapping>,
+    #[serde(default)]
+    pub volumes: Vec<VolumeMount>,
+    /// Passed with `-e KEY` so the values, secret ones included, stay off the command
+    /// line; they become the command's environment variables.
+    #[serde(default)]
+    pub environment: Vec<EnvironmentVariable>,
+    #[serde(default)]
+    pub workdir: Option<String>,
+    /// `--rm`
+    #[serde(default)]
+    pub remove: bool,
+    /// `-d`
+    #[serde(default)]
+    pub detach: bool,
+    /// `-it`
+    #[serde(default)]
+    pub interactive: bool,
+    /// What to run in the container instead of the image's default.
+    #[serde(default)]
+    pub command: Vec<String>,
+}
+
+impl DockerRunBuilder {
+    fn build(&self, name: String) -> Result<Command> {
+        let image = required("image", &self.image)?;
+        let mut args = vec!["run".to_string()];
+        if self.remove {
+            args.push("--rm".to_string());
+        }
+        if self.detach {
+            args.push("-d".to_string());
+        }
+        if self.interactive {
+            args.push("-it".to_string());
+        }
+        if let Some(container_name) = present(&self.container_name) {
+            args.extend(["--name".to_string(), container_name.to_string()]);
+        }
+        for port in &self.ports {
+            let mapping = format!("{}:{}", required("host port", &port.host)?, required("container port", &port.container)?);
+            let mapping = match present(&port.protocol).filter(|p| *p != "tcp") {
+                Some(protocol) => format!("{}/{}", mapping, protocol),
+                None => mapping,
+            };
+            args.extend(["-p".to_string(), mapping]);
+        }
+        for volume in &self.volumes {
+            let mut mount = format!("{}:{}", required("volume source", &volume.source)?, required("volume target", &volume.target)?);
+            if volume.read_only {
+                mount.push_str(":ro");
+            }
+            args.extend(["-v".to_string(), mount]);
+        }
+        for variable in &self.environment {
+            args.extend(["-e".to_string(), required("environment variable name", &variable.key)?.to_string()]);
+        }
+        if let Some(workdir) = present(&self.workdir) {
+            args.extend(["-w".to_string(), workdir.to_string()]);
+        }
+        args.push(match present(&self.tag) {
+            Some(tag) => format!("{}:{}", image, tag),
+            None => image.to_string(),
+        });
+        args.extend(self.command.iter().filter(|a| !a.is_empty()).cloned());
+
+        let mut command = Command::new(name, "docker".to_string()).with_args(args);
+        command.environment_variables = self.environment.clone();
+        Ok(command)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PortForward {
+    pub local_port: String,
+    pub remote_host: String,
+    pub remote_port: String,
+}
+
+/// `ssh`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SshBuilder {
+    pub host: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<String>,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// `-J`
+    #[serde(default)]
+    pub jump_host: Option<String>,
+    /// `-L local:remote_host:remote_port`
+    #[serde(default)]
+    pub forwards: Vec<PortForward>,
+    /// Run on the remote host instead of opening a shell; passed as one argument.
+    #[serde(default)]
+    pub remote_command: Option<String>,
+}
+
+impl SshBuilder {
+    fn build(&self, name: String) -> Result<Command> {
+        let host = required("host", &self.host)?;
+        let mut args = Vec::new();
+        if let Some(port) = present(&self.port).filter(|p| *p != "22") {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(identity_file) = present(&self.identity_file) {
+            args.extend(["-i".to_string(), identity_file.to_string()]);
+        }
+        if let Some(jump_host) = present(&self.jump_host) {
+            args.extend(["-J".to_string(), jump_host.to_string()]);
+        }
+        for forward in &self.forwards {
+            args.extend([
+                "-L".to_string(),
+                format!(
+                    "{}:{}:{}",
+                    required("local port", &forward.local_port)?,
+                    required("remote host", &forward.remote_host)?,
+                    required("remote port", &forward.remote_port)?
+                ),
+            ]);
+        }
+        if !self.forwards.is_empty() && present(&self.remote_command).is_none() {
+            // Only forward, without opening a remote shell
+            args.push("-N".to_string());
+        }
+        args.push(match present(&self.user) {
+            Some(user) => format!("{}@{}", user, host),
+            None => host.to_string(),
+        });
+        if let Some(remote_command) = present(&self.remote_command) {
+            args.push(remote_command.to_string());
+        }
+        Ok(Command::new(name, "ssh".to_string()).with_args(args))
+    }
+}
+
+fn present(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn required<'a>(field: &str, value: &'a str) -> Result<&'a str> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(CommandArgusError::InvalidCommand(format!("{} is required", field)));
+    }
+    if value.contains(char::is_whitespace) {
+        return Err(CommandArgusError::InvalidCommand(format!("{} can't contain spaces: '{}'", field, value)));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_docker_run_and_ssh() {
+        let docker: CommandBuilder = serde_json::from_str(
+            r#"{"tool": "docker_run", "image": "postgres", "tag": "16", "container_name": "db", "remove": true,
+                "ports": [{"host": "{port}", "container": "5432"}],
+                "volumes": [{"source": "./data", "target": "/var/lib/postgresql/data"}, {"source": "./init", "target": "/docker-entrypoint-initdb.d", "read_only": true}],
+                "environment": [{"key": "POSTGRES_PASSWORD", "value": "hunter2", "secret": true}]}"#,
+        )
+        .unwrap();
+        let command = docker.build("Postgres".to_string()).unwrap();
+        assert_eq!(
+            command.full_command(),
+            "docker run --rm --name db -p {port}:5432 -v ./data:/var/lib/postgresql/data -v ./init:/docker-entrypoint-initdb.d:ro -e POSTGRES_PASSWORD postgres:16"
+        );
+        assert!(command.environment_variables[0].secret);
+
+        let ssh = CommandBuilder::Ssh(SshBuilder {
+            host: "db.internal".to_string(),
+            user: Some("deploy".to_string()),
+            port: Some("2222".to_string()),
+            jump_host: Some("bastion".to_string()),
+            forwards: vec![PortForward {
+                local_port: "15432".to_string(),
+                remote_host: "localhost".to_string(),
+                remote_port: "5432".to_string(),
+            }],
+            ..Default::default()
+        });
+        assert_eq!(
+            ssh.build("Tunnel".to_string()).unwrap().full_command(),
+            "ssh -p 2222 -J bastion -L 15432:localhost:5432 -N deploy@db.internal"
+        );
+
+        let missing = CommandBuilder::Ssh(SshBuilder::default());
+        assert!(missing.build("Nothing".to_string()).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Fields that change by merely using a command; they are not worth reviewing
+const IGNORED_FIELDS: &[&str] = &["updated_at", "last_used_at", "use_count"];
+
+/// A set of commands exported to a file to share with teammates.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub commands: Vec<Command>,
+    /// What changed since the bundle previously exported to the same file.
+    #[serde(default)]
+    pub changelog: Option<BundleChangelog>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleChangelog {
+    /// When the bundle compared against was exported.
+    pub since: DateTime<Utc>,
+    pub added: Vec<BundleEntry>,
+    pub removed: Vec<BundleEntry>,
+    pub modified: Vec<CommandChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleEntry {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandChange {
+    pub id: Uuid,
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// One changed field, with its JSON value before and after (`null` if absent).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl CommandBundle {
+    pub fn new(commands: Vec<Command>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            commands,
+            changelog: None,
+        }
+    }
+}
+
+impl BundleChangelog {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// A human readable list of the changes, one line per command.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "No changes".to_string();
+        }
+        let mut lines = Vec::new();
+        for entry in &self.added {
+            lines.push(format!("+ {}", entry.name));
+        }
+        for entry in &self.removed {
+            lines.push(format!("- {}", entry.name));
+        }
+        for change in &self.modified {
+            let fields: Vec<&str> = change.fields.iter().map(|f| f.field.as_str()).collect();
+            lines.push(format!("~ {} ({})", change.name, fields.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Computes what changed from bundle `a` to bundle `b`. Commands are matched by id, so
+/// a renamed command shows up as modified rather than removed and added.
+pub fn diff_bundles(a: &CommandBundle, b: &CommandBundle) -> Result<BundleChangelog> {
+    let before: HashMap<Uuid, &Command> = a.commands.iter().map(|c| (c.id, c)).collect();
+    let after_ids: Vec<Uuid> = b.commands.iter().map(|c| c.id).collect();
+
+    let mut changelog = BundleChangelog {
+        since: a.exported_at,
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+    };
+    for command in &b.commands {
+        let Some(old) = before.get(&command.id) else {
+            changelog.added.push(BundleEntry { id: command.id, name: command.name.clone() });
+            continue;
+        };
+        let fields = diff_fields(old, command)?;
+        if !fields.is_empty() {
+            changelog.modified.push(CommandChange {
+                id: command.id,
+                name: command.name.clone(),
+                fields,
+            });
+        }
+    }
+    for command in &a.commands {
+        if !after_ids.contains(&command.id) {
+            changelog.removed.push(BundleEntry { id: command.id, name: command.name.clone() });
+        }
+    }
+    Ok(changelog)
+}
+
+fn diff_fields(old: &Command, new: &Command) -> Result<Vec<FieldChange>> {
+    let (Value::Object(old), Value::Object(new)) = (serde_json::to_value(old)?, serde_json::to_value(new)?) else {
+        return Ok(Vec::new());
+    };
+    let mut fields: Vec<&String> = old.keys().chain(new.keys().filter(|k| !old.contains_key(*k))).collect();
+    fields.retain(|field| !IGNORED_FIELDS.contains(&field.as_str()));
+    fields.sort();
+
+    Ok(fields
+        .into_iter()
+        .filter_map(|field| {
+            let before = old.get(field).cloned().unwrap_or(Value::Null);
+            let after = new.get(field).cloned().unwrap_or(Value::Null);
+            (before != after).then(|| FieldChange { field: field.clone(), before, after })
+        })
+        .collect())
+}
+
+pub fn load_bundle(path: &Path) -> Result<CommandBundle> {
+    let content = fs::read_to_string(path)?;
+    let bundle: CommandBundle = serde_json::from_str(&content)
+        .map_err(|e| CommandArgusError::InvalidPath(format!("{} is not a command bundle: {}", path.display(), e)))?;
+    Ok(bundle)
+}
+
+/// Writes `commands` as a bundle to `path`. If the file already holds a bundle, the
+/// changes since then are embedded as the new bundle's changelog.
+pub fn export_bundle(commands: Vec<Command>, path: &Path) -> Result<CommandBundle> {
+    let mut bundle = CommandBundle::new(commands);
+    if path.exists() {
+        let previous = load_bundle(path)?;
+        bundle.changelog = Some(diff_bundles(&previous, &bundle)?);
+    }
+    fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_bundles() {
+        let deploy = Command::new("Deploy".to_string(), "make".to_string()).with_args(vec!["deploy".to_string()]);
+        let lint = Command::new("Lint".to_string(), "cargo".to_string());
+        let a = CommandBundle::new(vec![deploy.clone(), lint.clone()]);
+
+        let mut deploy_v2 = deploy.clone();
+        deploy_v2.args = vec!["deploy-all".to_string()];
+        deploy_v2.mark_as_used();
+        let test = Command::new("Test".to_string(), "cargo".to_string());
+        let b = CommandBundle::new(vec![deploy_v2, test.clone()]);
+
+        let changelog = diff_bundles(&a, &b).unwrap();
+        assert_eq!(changelog.added, vec![BundleEntry { id: test.id, name: "Test".to_string() }]);
+        assert_eq!(changelog.removed[0].name, "Lint");
+        assert_eq!(changelog.modified.len(), 1);
+        assert_eq!(
+            changelog.modified[0].fields,
+            vec![FieldChange {
+                field: "args".to_string(),
+                before: serde_json::json!(["deploy"]),
+                after: serde_json::json!(["deploy-all"]),
+            }]
+        );
+        assert_eq!(changelog.summary(), "+ Test\n- Lint\n~ Deploy (args)");
+    }
+
+    #[test]
+    fn test_export_embeds_changelog() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("team.json");
+        let deploy = Command::new("Deploy".to_string(), "make".to_string());
+
+        let first = export_bundle(vec![deploy.clone()], &path).unwrap();
+        assert!(first.changelog.is_none());
+
+        let second = export_bundle(vec![deploy, Command::new("Lint".to_string(), "cargo".to_string())], &path).unwrap();
+        let changelog = load_bundle(&path).unwrap().changelog.unwrap();
+        assert_eq!(changelog.since, first.exported_at);
+        assert_eq!(Some(changelog), second.changelog);
+
+        fs::write(&path, "not a bundle").unwrap();
+        assert!(export_bundle(Vec::new(), &path).is_err());
+    }
+}
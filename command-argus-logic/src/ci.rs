@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::platform::Platform;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CiTarget {
+    GithubActions,
+    GitlabCi,
+}
+
+/// Converts one command, or a chain of commands run in order, into a CI snippet.
+///
+/// For GitHub Actions every command becomes a step of the returned step list. For
+/// GitLab CI the chain becomes a single job named after the first command; each
+/// script line runs in a subshell so per-command working directories don't leak
+/// into the next line. GitLab has no per-line env, so when two commands set the same
+/// variable to different values each command gets its own job instead, chained with
+/// `needs` to keep their order. Placeholders such as `{name}` are emitted unchanged.
+///
+/// Secret environment variables are never written out: they become references to a
+/// CI secret of the same name, `${{ secrets.NAME }}` or `$NAME`, to be set up in the
+/// repository's CI settings.
+///
+/// CI jobs are assumed to run on Linux runners, so the Linux variant of each command
+/// is exported.
+pub fn export_ci_snippet(commands: &[Command], target: CiTarget) -> Result<String> {
+    if commands.is_empty() {
+        return Err(CommandArgusError::InvalidCommand(
+            "Nothing to export".to_string(),
+        ));
+    }
+    let commands = commands
+        .iter()
+        .map(|command| command.for_platform(Platform::Linux))
+        .collect::<Result<Vec<_>>>()?;
+    let commands = commands.as_slice();
+
+    let value = match target {
+        CiTarget::GithubActions => github_steps(commands),
+        CiTarget::GitlabCi => gitlab_jobs(commands),
+    };
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+fn github_steps(commands: &[Command]) -> Value {
+    let steps = commands
+        .iter()
+        .map(|command| {
+            let mut step = Mapping::new();
+            step.insert("name".into(), command.name.clone().into());
+            step.insert("run".into(), shell_line(command).into());
+            if let Some(ref dir) = command.working_directory {
+                step.insert("working-directory".into(), dir.clone().into());
+            }
+            let env = env_mapping(std::slice::from_ref(command), CiTarget::GithubActions);
+            if !env.is_empty() {
+                step.insert("env".into(), Value::Mapping(env));
+            }
+            Value::Mapping(step)
+        })
+        .collect();
+    Value::Sequence(steps)
+}
+
+fn gitlab_jobs(commands: &[Command]) -> Value {
+    let mut root = Mapping::new();
+    if !has_env_conflicts(commands) {
+        root.insert(job_name(&commands[0].name).into(), gitlab_job(commands));
+        return Value::Mapping(root);
+    }
+    let mut previous: Option<String> = None;
+    for command in commands {
+        let base = job_name(&command.name);
+        let mut name = base.clone();
+        let mut n = 2;
+        while root.contains_key(name.as_str()) {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        let mut job = gitlab_job(std::slice::from_ref(command));
+        if let (Some(previous), Value::Mapping(job)) = (previous, &mut job) {
+            job.insert("needs".into(), Value::Sequence(vec![previous.into()]));
+        }
+        root.insert(name.clone().into(), job);
+        previous = Some(name);
+    }
+    Value::Mapping(root)
+}
+
+// Whether two of the commands set the same variable to different values
+fn has_env_conflicts(commands: &[Command]) -> bool {
+    let mut seen = std::collections::HashMap::new();
+    commands.iter().flat_map(|command| &command.environment_variables).any(|var| {
+        let value = (var.secret, var.value.as_str());
+        *seen.entry(var.key.as_str()).or_insert(value) != value
+    })
+}
+
+fn gitlab_job(commands: &[Command]) -> Value {
+    let script = commands
+        .iter()
+        .map(|command| match command.working_directory {
+            Some(ref dir) => format!("(cd {} && {})", shell_quote(dir), shell_line(command)),
+            None => shell_line(command),
+        })
+        .map(Value::from)
+        .collect();
+
+    let mut job = Mapping::new();
+    job.insert("script".into(), Value::Sequence(script));
+    // Merged across the chain; the chain is split into jobs when the commands disagree
+    let variables = env_mapping(commands, CiTarget::GitlabCi);
+    if !variables.is_empty() {
+        job.insert("variables".into(), Value::Mapping(variables));
+    }
+    Value::Mapping(job)
+}
+
+fn env_mapping(commands: &[Command], target: CiTarget) -> Mapping {
+    let mut env = Mapping::new();
+    for command in commands {
+        for var in &command.environment_variables {
+            let value = match (var.secret, target) {
+                (false, _) => var.value.clone(),
+                (true, CiTarget::GithubActions) => format!("${{{{ secrets.{} }}}}", var.key),
+                (true, CiTarget::GitlabCi) => format!("${}", var.key),
+            };
+            env.insert(var.key.clone().into(), value.into());
+        }
+    }
+    env
+}
+
+fn job_name(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "command".to_string()
+    } else {
+        slug
+    }
+}
+
+pub(crate) fn shell_line(command: &Command) -> String {
+    let mut parts = vec![command.command.clone()];
+    parts.extend(command.args.iter().map(|arg| shell_quote(arg)));
+    parts.join(" ")
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+{}$".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::PlatformOverride;
+
+    fn sample() -> Command {
+        let mut cmd = Command::new("Run Tests".to_string(), "cargo".to_string())
+            .with_args(vec!["test".to_string(), "--features".to_string(), "a b".to_string()])
+            .with_working_directory("crates/core".to_string());
+        cmd.add_environment_variable("RUST_LOG".to_string(), "debug".to_string());
+        cmd
+    }
+
+    #[test]
+    fn test_github_actions_step() {
+        let snippet = export_ci_snippet(&[sample()], CiTarget::GithubActions).unwrap();
+        let steps: Vec<Mapping> = serde_yaml::from_str(&snippet).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0]["name"], Value::from("Run Tests"));
+        assert_eq!(steps[0]["run"], Value::from("cargo test --features 'a b'"));
+        assert_eq!(steps[0]["working-directory"], Value::from("crates/core"));
+        assert_eq!(steps[0]["env"]["RUST_LOG"], Value::from("debug"));
+    }
+
+    #[test]
+    fn test_gitlab_job_for_chain() {
+        let second = Command::new("Lint".to_string(), "cargo".to_string())
+            .with_args(vec!["clippy".to_string()]);
+        let snippet = export_ci_snippet(&[sample(), second], CiTarget::GitlabCi).unwrap();
+        let root: Mapping = serde_yaml::from_str(&snippet).unwrap();
+        let job = &root["run-tests"];
+        let script = job["script"].as_sequence().unwrap();
+        assert_eq!(script[0], Value::from("(cd crates/core && cargo test --features 'a b')"));
+        assert_eq!(script[1], Value::from("cargo clippy"));
+        assert_eq!(job["variables"]["RUST_LOG"], Value::from("debug"));
+    }
+
+    #[test]
+    fn test_gitlab_jobs_for_conflicting_env() {
+        let mut second = Command::new("Run Tests".to_string(), "cargo".to_string()).with_args(vec!["test".to_string()]);
+        second.add_environment_variable("RUST_LOG".to_string(), "info".to_string());
+        let snippet = export_ci_snippet(&[sample(), second], CiTarget::GitlabCi).unwrap();
+        let root: Mapping = serde_yaml::from_str(&snippet).unwrap();
+        assert_eq!(root.len(), 2);
+        assert_eq!(root["run-tests"]["variables"]["RUST_LOG"], Value::from("debug"));
+        assert!(root["run-tests"].get("needs").is_none());
+        let next = &root["run-tests-2"];
+        assert_eq!(next["script"][0], Value::from("cargo test"));
+        assert_eq!(next["variables"]["RUST_LOG"], Value::from("info"));
+        assert_eq!(next["needs"][0], Value::from("run-tests"));
+    }
+
+    #[test]
+    fn test_secrets_are_references() {
+        let mut cmd = sample();
+        cmd.add_secret_environment_variable("API_TOKEN".to_string(), "s3cr3t-token".to_string());
+        let cmds = [cmd];
+
+        let snippet = export_ci_snippet(&cmds, CiTarget::GitlabCi).unwrap();
+        assert!(!snippet.contains("s3cr3t-token"));
+        let root: Mapping = serde_yaml::from_str(&snippet).unwrap();
+        assert_eq!(root["run-tests"]["variables"]["API_TOKEN"], Value::from("$API_TOKEN"));
+
+        let snippet = export_ci_snippet(&cmds, CiTarget::GithubActions).unwrap();
+        assert!(!snippet.contains("s3cr3t-token"));
+        let steps: Vec<Mapping> = serde_yaml::from_str(&snippet).unwrap();
+        assert_eq!(steps[0]["env"]["API_TOKEN"], Value::from("${{ secrets.API_TOKEN }}"));
+    }
+
+    #[test]
+    fn test_exports_linux_variant() {
+        let mut cmd = Command::new("Open".to_string(), "open".to_string());
+        cmd.set_platform_override(PlatformOverride::new(Platform::Linux, "xdg-open".to_string(), None));
+        let snippet = export_ci_snippet(&[cmd], CiTarget::GithubActions).unwrap();
+        assert!(snippet.contains("run: xdg-open"));
+
+        let mut mac_only = Command::new("Brew".to_string(), "brew".to_string());
+        mac_only.platforms = vec![Platform::Macos];
+        assert!(export_ci_snippet(&[mac_only], CiTarget::GithubActions).is_err());
+    }
+
+    #[test]
+    fn test_empty_export_rejected() {
+        assert!(export_ci_snippet(&[], CiTarget::GitlabCi).is_err());
+    }
+}
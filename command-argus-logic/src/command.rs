@@ -2,6 +2,21 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::assertions::Assertion;
+use crate::encoding::OutputEncoding;
+use crate::impact::Impact;
+use crate::notify::CommandNotifications;
+use crate::open_output::OpenOutput;
+use crate::problems::ProblemMatcher;
+use crate::platform::{Platform, PlatformOverride};
+use crate::preflight::Precondition;
+use crate::project::PROJECT_ROOT_PLACEHOLDER;
+use crate::ratelimit::RateLimit;
+use crate::sandbox::SandboxPolicy;
+use crate::scratch::{ScratchDirPolicy, RUN_TMP_PLACEHOLDER};
+use crate::shell::{ExecutionMode, ShellKind};
+use crate::tools::ToolCheck;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Command {
     pub id: Uuid,
@@ -18,12 +33,134 @@ pub struct Command {
     pub use_count: u32,
     pub parameters: Vec<CommandParameter>,
     pub mise_enabled: bool,
+    /// Runs must be approved through an `ApprovalRequest` before they execute.
+    #[serde(default)]
+    pub requires_approval: bool,
+    /// Confines runs of the command to the declared capabilities.
+    #[serde(default)]
+    pub sandbox: SandboxPolicy,
+    /// The command is run without network access.
+    #[serde(default)]
+    pub no_network: bool,
+    #[serde(default)]
+    pub output_encoding: OutputEncoding,
+    /// Shell used when the command is run in shell mode.
+    #[serde(default)]
+    pub shell: ShellKind,
+    /// Load the shell's user profile (PowerShell only).
+    #[serde(default)]
+    pub load_shell_profile: bool,
+    /// How the command runs when it is started without choosing, e.g. `pty` for a tool
+    /// that needs a terminal.
+    #[serde(default)]
+    pub default_execution_mode: ExecutionMode,
+    /// Platforms the command can run on; empty means all of them.
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    #[serde(default)]
+    pub platform_overrides: Vec<PlatformOverride>,
+    /// Absolute path of the program, captured when the command was pinned.
+    #[serde(default)]
+    pub pinned_executable: Option<String>,
+    /// Tool versions checked before the command runs.
+    #[serde(default)]
+    pub tool_checks: Vec<ToolCheck>,
+    /// Conditions checked before the command runs.
+    #[serde(default)]
+    pub preconditions: Vec<Precondition>,
+    /// Expectations checked against the output after the command has run.
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+    /// Position in the execution queue relative to other commands; higher runs first.
+    #[serde(default)]
+    pub priority: i32,
+    /// Commands in the same group never run at the same time, e.g. two `terraform apply`s.
+    #[serde(default)]
+    pub mutex_group: Option<String>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// Where to report this command's runs, on top of the notification rules.
+    #[serde(default)]
+    pub notifications: Option<CommandNotifications>,
+    #[serde(default)]
+    pub impact: Impact,
+    /// Who created the command, when known.
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub source: CommandSource,
+    /// Free-form note from whoever maintains the command, e.g. who to ask about it.
+    #[serde(default)]
+    pub maintainer_note: Option<String>,
+    /// The environment profiles the command may run with. When set, running it
+    /// without one of them fails.
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// Profiles the command must not run with, e.g. `prod` for a database reset.
+    /// Overriding this takes typing the command's name.
+    #[serde(default)]
+    pub forbidden_profiles: Vec<String>,
+    /// Globs relative to the working directory, e.g. `target/*.tar.gz`. Matching files
+    /// are copied into the run's artifact folder after it finishes.
+    #[serde(default)]
+    pub collect_artifacts: Vec<String>,
+    /// A temporary directory made for each run, available as `{run_tmp}`.
+    #[serde(default)]
+    pub scratch_dir: ScratchDirPolicy,
+    /// Written to the standard input of the process, e.g. a SQL script, with its
+    /// `{name}` placeholders filled in like the arguments.
+    #[serde(default)]
+    pub stdin_template: Option<String>,
+    /// Keep the input a run was given in its history, with secrets masked.
+    #[serde(default)]
+    pub record_stdin: bool,
+    /// Capture stdout and stderr as one timestamped timeline too, in the order the
+    /// lines were written.
+    #[serde(default)]
+    pub capture_timeline: bool,
+    /// A regex for lines that start a named phase, like `^==> (.*)`; the first capture
+    /// group, or else the whole match, names it.
+    #[serde(default)]
+    pub phase_marker: Option<String>,
+    /// Write the output of successful runs to a file and open it.
+    #[serde(default)]
+    pub open_output_with: Option<OpenOutput>,
+    /// Turn compiler and linter messages in the output into a list of problems.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcher>,
+}
+
+/// Where a command in the library came from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandSource {
+    /// Created by hand in this library.
+    #[default]
+    Manual,
+    /// Imported from a file such as `.vscode/tasks.json` or `.argus.yaml`.
+    Imported { from: String },
+    /// Pulled from the sync server; `from` is the owner.
+    Synced { from: String },
+}
+
+impl CommandSource {
+    /// `manual`, `imported` or `synced`, for filtering.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CommandSource::Manual => "manual",
+            CommandSource::Imported { .. } => "imported",
+            CommandSource::Synced { .. } => "synced",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct EnvironmentVariable {
     pub key: String,
     pub value: String,
+    /// The value is masked in captured output and history.
+    #[serde(default)]
+    pub secret: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -34,6 +171,9 @@ pub struct CommandParameter {
     pub required: bool,
     pub default_value: Option<String>,
     pub options: Option<Vec<String>>,
+    /// Values entered for this parameter are masked in captured output and history.
+    #[serde(default)]
+    pub secret: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -63,6 +203,37 @@ impl Command {
             use_count: 0,
             parameters: Vec::new(),
             mise_enabled: false,
+            requires_approval: false,
+            sandbox: SandboxPolicy::default(),
+            no_network: false,
+            output_encoding: OutputEncoding::default(),
+            shell: ShellKind::default(),
+            load_shell_profile: false,
+            default_execution_mode: ExecutionMode::default(),
+            platforms: Vec::new(),
+            platform_overrides: Vec::new(),
+            pinned_executable: None,
+            tool_checks: Vec::new(),
+            preconditions: Vec::new(),
+            assertions: Vec::new(),
+            priority: 0,
+            mutex_group: None,
+            rate_limit: None,
+            notifications: None,
+            impact: Impact::default(),
+            author: None,
+            source: CommandSource::default(),
+            maintainer_note: None,
+            profiles: Vec::new(),
+            forbidden_profiles: Vec::new(),
+            collect_artifacts: Vec::new(),
+            scratch_dir: ScratchDirPolicy::default(),
+            stdin_template: None,
+            record_stdin: false,
+            capture_timeline: false,
+            phase_marker: None,
+            open_output_with: None,
+            problem_matchers: Vec::new(),
         }
     }
 
@@ -87,7 +258,11 @@ impl Command {
     }
 
     pub fn add_environment_variable(&mut self, key: String, value: String) {
-        self.environment_variables.push(EnvironmentVariable { key, value });
+        self.environment_variables.push(EnvironmentVariable { key, value, secret: false });
+    }
+
+    pub fn add_secret_environment_variable(&mut self, key: String, value: String) {
+        self.environment_variables.push(EnvironmentVariable { key, value, secret: true });
     }
 
     pub fn add_tag(&mut self, tag: String) {
@@ -127,21 +302,53 @@ impl Command {
         self.parameters.iter().find(|p| p.name == name)
     }
 
+    /// Names of the parameters the command's placeholders ask for. The built-in
+    /// `{project_root}` is filled in when the command runs and is not one of them.
     pub fn detect_placeholders(&self) -> Vec<String> {
         let mut placeholders = Vec::new();
-        let full_command = self.full_command();
+        // Platform variants may use parameters the default variant does not
+        let mut full_command = self.full_command();
+        for platform_override in &self.platform_overrides {
+            full_command.push(' ');
+            full_command.push_str(&platform_override.command);
+            for arg in platform_override.args.iter().flatten() {
+                full_command.push(' ');
+                full_command.push_str(arg);
+            }
+        }
+        
+        // {{var:NAME}} references are template variables, not parameters
+        let full_command = strip_variable_references(&full_command);
         
         // Match {variable} or ${variable} patterns
         let re = regex::Regex::new(r"\$?\{([^}]+)\}").unwrap();
         for cap in re.captures_iter(&full_command) {
             if let Some(name) = cap.get(1) {
                 let placeholder = name.as_str().to_string();
-                if !placeholders.contains(&placeholder) {
+                if !is_builtin_placeholder(&placeholder) && !placeholders.contains(&placeholder) {
                     placeholders.push(placeholder);
                 }
             }
         }
         
+        let working_directories = self
+            .working_directory
+            .iter()
+            .chain(self.platform_overrides.iter().filter_map(|o| o.working_directory.as_ref()));
+        for dir in working_directories {
+            for placeholder in working_directory_placeholders(dir) {
+                if !is_builtin_placeholder(&placeholder) && !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                }
+            }
+        }
+        
+        for placeholder in self.stdin_template.as_deref().map(stdin_placeholders).unwrap_or_default() {
+            if !is_builtin_placeholder(&placeholder) && !placeholders.contains(&placeholder) {
+                placeholders.push(placeholder);
+            }
+        }
+        
         placeholders
     }
 
@@ -165,6 +372,73 @@ impl Command {
         
         (command, args)
     }
+
+    /// The working directory with `{name}` placeholders replaced. `${NAME}` is left alone
+    /// there, as it is expanded from the environment when the directory is resolved.
+    pub fn replace_working_directory_placeholders(&self, values: &std::collections::HashMap<String, String>) -> Option<String> {
+        let dir = self.working_directory.as_ref()?;
+        let re = regex::Regex::new(r"\$?\{([^{}]+)\}").unwrap();
+        let replaced = re.replace_all(dir, |cap: &regex::Captures| match values.get(&cap[1]) {
+            Some(value) if !cap[0].starts_with('$') => value.clone(),
+            _ => cap[0].to_string(),
+        });
+        Some(replaced.into_owned())
+    }
+
+    /// The stdin template with its `{name}` placeholders replaced. Other braces are
+    /// left alone, so JSON or shell text can be piped as it is.
+    pub fn render_stdin(&self, values: &std::collections::HashMap<String, String>) -> Option<String> {
+        let template = self.stdin_template.as_ref()?;
+        let replaced = stdin_placeholder_regex().replace_all(template, |cap: &regex::Captures| match values.get(&cap[1]) {
+            Some(value) => value.clone(),
+            None => cap[0].to_string(),
+        });
+        Some(replaced.into_owned())
+    }
+}
+
+fn strip_variable_references(text: &str) -> std::borrow::Cow<'_, str> {
+    let variables = regex::Regex::new(r"\{\{var:[^}]*\}\}").unwrap();
+    variables.replace_all(text, "")
+}
+
+// Placeholders the executor fills in itself rather than asking for
+fn is_builtin_placeholder(name: &str) -> bool {
+    name == PROJECT_ROOT_PLACEHOLDER || name == RUN_TMP_PLACEHOLDER
+}
+
+/// The `{name}` placeholders in a working directory, e.g. `service` in
+/// `{project_root}/services/{service}`. Unlike in the command line, `${NAME}` is an
+/// environment variable there rather than a placeholder.
+pub fn working_directory_placeholders(dir: &str) -> Vec<String> {
+    let dir = strip_variable_references(dir);
+    let re = regex::Regex::new(r"\$?\{([^{}]+)\}").unwrap();
+    let mut placeholders: Vec<String> = Vec::new();
+    for cap in re.captures_iter(&dir) {
+        if cap[0].starts_with('$') || placeholders.iter().any(|p| p == &cap[1]) {
+            continue;
+        }
+        placeholders.push(cap[1].to_string());
+    }
+    placeholders
+}
+
+// Only `{identifier}` counts as a placeholder in stdin, as `{` is common in the input itself
+fn stdin_placeholder_regex() -> regex::Regex {
+    regex::Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap()
+}
+
+/// The `{name}` placeholders in a stdin template, e.g. `table` in
+/// `SELECT count(*) FROM {table};`.
+pub fn stdin_placeholders(template: &str) -> Vec<String> {
+    let template = strip_variable_references(template);
+    let mut placeholders: Vec<String> = Vec::new();
+    for cap in stdin_placeholder_regex().captures_iter(&template) {
+        if !placeholders.iter().any(|p| p == &cap[1]) {
+            placeholders.push(cap[1].to_string());
+        }
+    }
+    placeholders
 }
 
 #[cfg(test)]
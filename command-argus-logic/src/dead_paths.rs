@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::{Command, ParameterType};
+use crate::executable::find_executable;
+use crate::expand::{expand_env, resolve_working_directory};
+
+/// Where a command refers to a path that doesn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeadPathKind {
+    WorkingDirectory,
+    PinnedExecutable,
+    ParameterDefault { parameter: String },
+}
+
+/// A nonexistent path found by [`scan_dead_paths`], with what to do about it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadPath {
+    pub command_id: Uuid,
+    pub command_name: String,
+    #[serde(flatten)]
+    pub kind: DeadPathKind,
+    pub path: String,
+    pub fix: String,
+    /// A path that would work instead, when one could be found.
+    pub replacement: Option<String>,
+}
+
+/// Checks the working directory, pinned executable and file or directory parameter
+/// defaults of every command. Relative paths are resolved like at run time: working
+/// directories against `base_dir`, parameter defaults against the working directory.
+/// Values with placeholders are skipped, as they only become paths when the command runs.
+pub fn scan_dead_paths(commands: &[Command], base_dir: Option<&Path>) -> Vec<DeadPath> {
+    let mut dead = Vec::new();
+    for command in commands {
+        let mut report = |kind: DeadPathKind, path: &str, fix: String, replacement: Option<String>| {
+            dead.push(DeadPath {
+                command_id: command.id,
+                command_name: command.name.clone(),
+                kind,
+                path: path.to_string(),
+                fix,
+                replacement,
+            });
+        };
+
+        let mut working_dir = base_dir.map(Path::to_path_buf);
+        if let Some(dir) = command.working_directory.as_deref().filter(|dir| !dir.contains('{')) {
+            match resolve_working_directory(dir, base_dir) {
+                Ok(resolved) => working_dir = Some(resolved),
+                Err(_) => {
+                    report(DeadPathKind::WorkingDirectory, dir, "Choose an existing working directory".to_string(), None);
+                    working_dir = None;
+                }
+            }
+        }
+
+        if command.pinned_executable_warning().is_some() {
+            let pinned = command.pinned_executable.as_deref().unwrap_or_default();
+            let replacement = find_executable(&command.command)
+                .and_then(|path| path.canonicalize().ok())
+                .map(|path| path.to_string_lossy().to_string());
+            let fix = match &replacement {
+                Some(path) => format!("Re-pin to {}", path),
+                None => format!("'{}' is not in PATH either; install it or unpin the command", command.command),
+            };
+            report(DeadPathKind::PinnedExecutable, pinned, fix, replacement);
+        }
+
+        for parameter in &command.parameters {
+            let expect_dir = match parameter.parameter_type {
+                ParameterType::File => false,
+                ParameterType::Directory => true,
+                _ => continue,
+            };
+            let Some(default) = parameter.default_value.as_deref().filter(|v| !v.is_empty() && !v.contains('{')) else {
+                continue;
+            };
+            let path = PathBuf::from(expand_env(default));
+            let path = match &working_dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                None if path.is_relative() => continue,
+                _ => path,
+            };
+            let exists = if expect_dir { path.is_dir() } else { path.is_file() };
+            if !exists {
+                let what = if expect_dir { "directory" } else { "file" };
+                let fix = format!("Change or clear the default of '{}'; the {} doesn't exist", parameter.name, what);
+                report(DeadPathKind::ParameterDefault { parameter: parameter.name.clone() }, default, fix, None);
+            }
+        }
+    }
+    dead
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandParameter;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_dead_paths() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("app.toml"), "").unwrap();
+        let parameter = |name: &str, parameter_type: ParameterType, default: &str| CommandParameter {
+            name: name.to_string(),
+            placeholder: format!("{{{}}}", name),
+            parameter_type,
+            required: false,
+            default_value: Some(default.to_string()),
+            options: None,
+            secret: false,
+        };
+
+        let mut healthy = Command::new("Healthy".to_string(), "sh".to_string());
+        healthy.working_directory = Some(".".to_string());
+        healthy.parameters = vec![parameter("config", ParameterType::File, "app.toml")];
+        let mut broken = Command::new("Broken".to_string(), "sh".to_string());
+        broken.working_directory = Some("missing".to_string());
+        broken.pinned_executable = Some("/nonexistent/bin/sh".to_string());
+        broken.parameters = vec![
+            parameter("out", ParameterType::Directory, "/nonexistent/out"),
+            parameter("name", ParameterType::Text, "/nonexistent"),
+        ];
+
+        let dead = scan_dead_paths(&[healthy, broken], Some(temp.path()));
+        let kinds: Vec<&DeadPathKind> = dead.iter().map(|d| &d.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                &DeadPathKind::WorkingDirectory,
+                &DeadPathKind::PinnedExecutable,
+                &DeadPathKind::ParameterDefault { parameter: "out".to_string() },
+            ]
+        );
+        assert!(dead.iter().all(|d| d.command_name == "Broken"));
+        assert!(dead[1].replacement.is_some());
+    }
+
+    #[test]
+    fn test_dead_paths_without_a_fix() {
+        let temp = TempDir::new().unwrap();
+        let mut gone = Command::new("Gone".to_string(), "no-such-tool-5c1d".to_string());
+        gone.pinned_executable = Some("/nonexistent/bin/no-such-tool-5c1d".to_string());
+        gone.working_directory = Some("{project_root}/missing".to_string());
+        // A directory where a file is expected, and a relative default without a base
+        gone.parameters = vec![CommandParameter {
+            name: "config".to_string(),
+            placeholder: "{config}".to_string(),
+            parameter_type: ParameterType::File,
+            required: false,
+            default_value: Some(temp.path().to_string_lossy().to_string()),
+            options: None,
+            secret: false,
+        }];
+        let mut relative = gone.parameters[0].clone();
+        relative.default_value = Some("missing.toml".to_string());
+        gone.parameters.push(relative);
+
+        let dead = scan_dead_paths(&[gone], None);
+        assert_eq!(dead.len(), 2);
+        assert_eq!((&dead[0].kind, &dead[0].replacement), (&DeadPathKind::PinnedExecutable, &None));
+        assert!(dead[0].fix.contains("not in PATH"));
+        assert_eq!(dead[1].kind, DeadPathKind::ParameterDefault { parameter: "config".to_string() });
+        assert!(scan_dead_paths(&[], Some(temp.path())).is_empty());
+    }
+}
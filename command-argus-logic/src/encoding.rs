@@ -0,0 +1,70 @@
+use encoding_rs::{Encoding, BIG5, EUC_JP, EUC_KR, GBK, SHIFT_JIS, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// Character encoding of a command's stdout and stderr.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputEncoding {
+    /// UTF-8 when the output is valid UTF-8, otherwise Shift_JIS if it decodes
+    /// cleanly (the usual culprit on Japanese Windows), otherwise lossy UTF-8.
+    #[default]
+    Auto,
+    Utf8,
+    ShiftJis,
+    EucJp,
+    Gbk,
+    Big5,
+    EucKr,
+    Windows1252,
+}
+
+impl OutputEncoding {
+    fn encoding(self) -> Option<&'static Encoding> {
+        match self {
+            OutputEncoding::Auto => None,
+            OutputEncoding::Utf8 => Some(UTF_8),
+            OutputEncoding::ShiftJis => Some(SHIFT_JIS),
+            OutputEncoding::EucJp => Some(EUC_JP),
+            OutputEncoding::Gbk => Some(GBK),
+            OutputEncoding::Big5 => Some(BIG5),
+            OutputEncoding::EucKr => Some(EUC_KR),
+            OutputEncoding::Windows1252 => Some(WINDOWS_1252),
+        }
+    }
+
+    /// Decodes captured output, replacing malformed sequences with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        if let Some(encoding) = self.encoding() {
+            return encoding.decode_without_bom_handling(bytes).0.into_owned();
+        }
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return text.to_string();
+        }
+        match SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes) {
+            Some(text) => text.into_owned(),
+            None => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "日本語" in Shift_JIS
+    const SJIS: &[u8] = &[0x93, 0xfa, 0x96, 0x7b, 0x8c, 0xea];
+
+    #[test]
+    fn test_explicit_encoding() {
+        assert_eq!(OutputEncoding::ShiftJis.decode(SJIS), "日本語");
+        assert_eq!(OutputEncoding::Windows1252.decode(&[0x63, 0x61, 0x66, 0xe9]), "café");
+        assert!(OutputEncoding::Utf8.decode(SJIS).contains('\u{fffd}'));
+    }
+
+    #[test]
+    fn test_auto_detection() {
+        assert_eq!(OutputEncoding::Auto.decode("日本語".as_bytes()), "日本語");
+        assert_eq!(OutputEncoding::Auto.decode(SJIS), "日本語");
+        assert_eq!(OutputEncoding::Auto.decode(&[0x61, 0xff]), "a\u{fffd}");
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::executor::{child_path, CommandExecutor};
+use crate::expand::expand_env;
+use crate::lint::SECRET_NAME;
+use crate::platform::Platform;
+use crate::profile::Profile;
+use crate::scrub::{OutputScrubber, REDACTED};
+use crate::error::Result;
+
+/// Where a variable of a child process's environment comes from, in the order they are
+/// layered.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvSource {
+    /// The environment of the app itself.
+    Inherited,
+    /// Changed by the app for every command, like the `PATH` on macOS.
+    App,
+    /// The variables telling the command how it was invoked.
+    Context,
+    Command,
+    Profile,
+}
+
+/// One variable as the child process would get it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnvEntry {
+    pub key: String,
+    pub value: String,
+    pub source: EnvSource,
+    /// The value of a lower layer this one replaces.
+    pub replaces: Option<String>,
+    /// The value, and the one it replaces, are masked.
+    pub secret: bool,
+}
+
+/// The environment a command would run with, split into what is set for it and what is
+/// passed on unchanged from the app.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EnvironmentPreview {
+    pub overrides: Vec<EnvEntry>,
+    pub inherited: Vec<EnvEntry>,
+}
+
+/// The environment `command` would get from `executor` with `profile`, built the way a
+/// run builds it. Secret variables, those whose name looks like a credential and
+/// well-known token formats are masked.
+pub fn preview_environment(executor: &CommandExecutor, command: &Command, profile: Option<&Profile>) -> Result<EnvironmentPreview> {
+    preview_environment_with(executor, command, profile, env::vars())
+}
+
+/// Like [`preview_environment`], starting from the `inherited` variables.
+pub fn preview_environment_with(
+    executor: &CommandExecutor,
+    command: &Command,
+    profile: Option<&Profile>,
+    inherited: impl IntoIterator<Item = (String, String)>,
+) -> Result<EnvironmentPreview> {
+    let command = command.for_platform(Platform::current())?;
+    let command = match profile {
+        Some(profile) => profile.apply(&command),
+        None => command,
+    };
+
+    let mut entries: BTreeMap<String, EnvEntry> = BTreeMap::new();
+    let mut set = |key: String, value: String, source: EnvSource, secret: bool| {
+        // A value replacing a secret one is masked too, so the old one doesn't show
+        let previous = entries.get(&key);
+        let secret = secret || previous.is_some_and(|previous| previous.secret);
+        let replaces = previous.map(|previous| previous.value.clone());
+        entries.insert(key.clone(), EnvEntry { key, value, source, replaces, secret });
+    };
+    for (key, value) in inherited {
+        set(key, value, EnvSource::Inherited, false);
+    }
+    if let Some(path) = child_path() {
+        set("PATH".to_string(), path, EnvSource::App, false);
+    }
+    for (key, value) in executor.context_variables(&command) {
+        set(key, value, EnvSource::Context, false);
+    }
+    for var in &command.environment_variables {
+        let from_profile = profile.is_some_and(|p| p.environment_variables.iter().any(|v| v.key == var.key));
+        let source = if from_profile { EnvSource::Profile } else { EnvSource::Command };
+        set(var.key.clone(), expand_env(&var.value), source, var.secret);
+    }
+
+    let secret_name = Regex::new(SECRET_NAME).expect("secret name pattern is valid");
+    let scrubber = OutputScrubber::new().with_default_patterns();
+    let mut preview = EnvironmentPreview::default();
+    for mut entry in entries.into_values() {
+        entry.secret |= secret_name.is_match(&entry.key);
+        entry.value = mask(&entry.value, entry.secret, &scrubber);
+        entry.replaces = entry.replaces.map(|value| mask(&value, entry.secret, &scrubber));
+        match entry.source {
+            EnvSource::Inherited => preview.inherited.push(entry),
+            _ => preview.overrides.push(entry),
+        }
+    }
+    Ok(preview)
+}
+
+fn mask(value: &str, secret: bool, scrubber: &OutputScrubber) -> String {
+    if secret && !value.is_empty() {
+        REDACTED.to_string()
+    } else {
+        scrubber.scrub(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::EnvironmentVariable;
+
+    #[test]
+    fn test_preview_environment() {
+        let mut command = Command::new("Deploy".to_string(), "make".to_string());
+        command.add_environment_variable("STAGE".to_string(), "dev".to_string());
+        command.add_environment_variable("EDITOR".to_string(), "nano".to_string());
+        command.environment_variables.push(EnvironmentVariable {
+            key: "DSN".to_string(),
+            value: "postgres://u:p@db".to_string(),
+            secret: true,
+        });
+        let mut profile = Profile::new("prod".to_string());
+        profile.environment_variables.push(EnvironmentVariable {
+            key: "STAGE".to_string(),
+            value: "prod".to_string(),
+            secret: false,
+        });
+        let inherited = [("EDITOR", "vim"), ("GITHUB_TOKEN", "abc"), ("LANG", "C")]
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let executor = CommandExecutor::new().with_context_prefix(String::new());
+        let preview = preview_environment_with(&executor, &command, Some(&profile), inherited).unwrap();
+
+        let find = |key: &str| preview.overrides.iter().find(|e| e.key == key).unwrap();
+        assert_eq!(find("STAGE").value, "prod");
+        assert_eq!(find("STAGE").source, EnvSource::Profile);
+        assert_eq!(find("EDITOR").replaces.as_deref(), Some("vim"));
+        assert_eq!(find("EDITOR").source, EnvSource::Command);
+        assert_eq!(find("DSN").value, REDACTED);
+        let inherited: Vec<(&str, &str)> = preview.inherited.iter().map(|e| (e.key.as_str(), e.value.as_str())).collect();
+        assert_eq!(inherited, vec![("GITHUB_TOKEN", REDACTED), ("LANG", "C")]);
+    }
+}
@@ -1,6 +1,8 @@
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::preflight::PreflightFailure;
+
 #[derive(Error, Debug)]
 pub enum CommandArgusError {
     #[error("Command not found: {0}")]
@@ -18,6 +20,9 @@ pub enum CommandArgusError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
     
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    
     #[error("Storage error: {0}")]
     Storage(String),
     
@@ -26,6 +31,51 @@ pub enum CommandArgusError {
     
     #[error("Invalid path: {0}")]
     InvalidPath(String),
+    
+    #[error("Sync failed: {0}")]
+    Sync(String),
+    
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+    
+    #[error("Sandbox unavailable: {0}")]
+    SandboxUnavailable(String),
+    
+    #[error("Unsupported platform: {0}")]
+    UnsupportedPlatform(String),
+    
+    #[error("Check failed: {0}")]
+    CheckFailed(String),
+    
+    #[error("Preflight checks failed: {}", .0.iter().map(|f| f.message.as_str()).collect::<Vec<_>>().join("; "))]
+    PreflightFailed(Vec<PreflightFailure>),
+    
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+    
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+    
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String),
+    
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    
+    #[error("Notification failed: {0}")]
+    Notification(String),
+    
+    #[error("Wrong profile: {0}")]
+    WrongProfile(String),
+    
+    #[error("Sharing failed: {0}")]
+    Share(String),
+    
+    #[error("Background agent: {0}")]
+    Agent(String),
+    
+    #[error("Archive error: {0}")]
+    Archive(String),
 }
 
 pub type Result<T> = std::result::Result<T, CommandArgusError>;
\ No newline at end of file
@@ -0,0 +1,97 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+
+/// Directories a GUI app launched from Finder usually lacks in its `PATH`.
+#[cfg(target_os = "macos")]
+const EXTRA_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin", "/bin", "/usr/sbin", "/sbin"];
+#[cfg(not(target_os = "macos"))]
+const EXTRA_DIRS: &[&str] = &[];
+
+/// Locates `program` the way a shell would. A program containing a path separator is
+/// taken as is; a bare name is searched in `PATH` (plus, on macOS, the Homebrew and
+/// system directories). On Windows the usual executable extensions are tried too.
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.components().count() > 1 {
+        return is_executable(program_path).then(|| program_path.to_path_buf());
+    }
+
+    let mut dirs: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    dirs.extend(EXTRA_DIRS.iter().map(PathBuf::from));
+
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat", ".com"] } else { &[""] };
+    dirs.iter()
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", program, ext))))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+impl Command {
+    /// Pins the command to the absolute path its program currently resolves to, so
+    /// later runs don't depend on the `PATH` of the process running them. Calling it
+    /// again re-resolves the program.
+    pub fn pin_executable(&mut self) -> Result<PathBuf> {
+        let path = find_executable(&self.command)
+            .and_then(|path| path.canonicalize().ok())
+            .ok_or_else(|| {
+                CommandArgusError::InvalidPath(format!("'{}' was not found in PATH", self.command))
+            })?;
+        self.pinned_executable = Some(path.to_string_lossy().to_string());
+        Ok(path)
+    }
+
+    /// A warning when the pinned executable no longer exists.
+    pub fn pinned_executable_warning(&self) -> Option<String> {
+        let pinned = self.pinned_executable.as_ref()?;
+        if is_executable(Path::new(pinned)) {
+            None
+        } else {
+            Some(format!("Pinned executable {} no longer exists; re-resolve it", pinned))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_executable() {
+        let sh = find_executable("sh").unwrap();
+        assert!(sh.is_absolute());
+        assert_eq!(find_executable(&sh.to_string_lossy()), Some(sh));
+        assert!(find_executable("definitely-not-a-real-program").is_none());
+    }
+
+    #[test]
+    fn test_pin_executable() {
+        let mut cmd = Command::new("Shell".to_string(), "sh".to_string());
+        let pinned = cmd.pin_executable().unwrap();
+        assert!(pinned.is_absolute());
+        assert!(cmd.pinned_executable_warning().is_none());
+
+        cmd.pinned_executable = Some("/nonexistent/bin/sh".to_string());
+        assert!(cmd.pinned_executable_warning().unwrap().contains("/nonexistent/bin/sh"));
+
+        let mut missing = Command::new("Missing".to_string(), "definitely-not-a-real-program".to_string());
+        assert!(missing.pin_executable().is_err());
+        assert!(missing.pinned_executable.is_none());
+    }
+}
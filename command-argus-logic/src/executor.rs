@@ -1,7 +1,33 @@
-use std::process::{Command as ProcessCommand, Output};
-use std::path::Path;
-use crate::command::Command;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command as ProcessCommand, Output, Stdio};
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use crate::artifacts::{collect_artifacts, Artifact};
+use crate::assertions::AssertionResult;
+use crate::command::{stdin_placeholders, working_directory_placeholders, Command};
+use crate::encoding::OutputEncoding;
+use crate::history::OutputStream;
+use crate::platform::Platform;
 use crate::error::CommandArgusError;
+use crate::expand::{expand_env, resolve_working_directory};
+use crate::executable::find_executable;
+use crate::sandbox::{network_isolated_process, SandboxBackend};
+use crate::ci::{shell_line, shell_quote};
+use crate::shell::{powershell_args, powershell_script, pty_invocation, terminal_invocation, ExecutionMode, ShellKind};
+use crate::preflight::{PreflightChecker, PreflightFailure};
+use crate::problems::{Problem, ProblemFinder, MAX_PROBLEMS};
+use crate::profile::{check_profile, Profile};
+use crate::restricted::RestrictedMode;
+use crate::project::{find_project_root, PROJECT_ROOT_MARKERS, PROJECT_ROOT_PLACEHOLDER};
+use crate::scratch::{ScratchDir, RUN_TMP_PLACEHOLDER};
+use crate::scrub::OutputScrubber;
+use crate::shutdown::{Interruption, RunningJob, RunningJobs};
+use crate::timeline::{phase_marker_regex, OutputEvent, Phase, Timeline};
+use crate::tools::run_tool_checks;
+use crate::variables::texts_mut;
 
 #[derive(Debug)]
 pub struct ExecutionResult {
@@ -9,83 +35,392 @@ pub struct ExecutionResult {
     pub stderr: String,
     pub exit_code: i32,
     pub success: bool,
+    /// Problems found before running that did not stop the command, such as tool version mismatches.
+    pub warnings: Vec<String>,
+    pub duration: Duration,
+    /// Outcome of the command's assertions, in the order they are declared.
+    pub assertion_results: Vec<AssertionResult>,
+    /// Files matching the command's `collect_artifacts`, copied into the executor's
+    /// artifact folder.
+    pub artifacts: Vec<Artifact>,
+    /// The input the process was given, kept when the command records its stdin.
+    pub stdin: Option<String>,
+    /// Where the command ran, if not in the current directory of this process.
+    pub working_directory: Option<PathBuf>,
+    /// Errors and warnings the command's problem matchers found in its output.
+    pub problems: Vec<Problem>,
+    /// Set when the process was stopped by a shutdown rather than exiting on its own.
+    pub interrupted: Option<Interruption>,
+    /// Every line of both streams in the order it was written, for commands that
+    /// capture a timeline. Only returned to the caller; the history keeps the output
+    /// but not the timeline.
+    pub timeline: Vec<OutputEvent>,
+    /// The phases the command's phase markers started, with how long each took.
+    pub phases: Vec<Phase>,
 }
 
 impl ExecutionResult {
-    fn from_output(output: Output) -> Self {
+    fn from_output(output: Output, encoding: OutputEncoding, duration: Duration) -> Self {
         Self {
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            stdout: encoding.decode(&output.stdout),
+            stderr: encoding.decode(&output.stderr),
             exit_code: output.status.code().unwrap_or(-1),
             success: output.status.success(),
+            warnings: Vec::new(),
+            duration,
+            assertion_results: Vec::new(),
+            artifacts: Vec::new(),
+            stdin: None,
+            working_directory: None,
+            problems: Vec::new(),
+            interrupted: None,
+            timeline: Vec::new(),
+            phases: Vec::new(),
         }
     }
+    
+    /// Whether every assertion held. True when the command has none.
+    pub fn assertions_passed(&self) -> bool {
+        self.assertion_results.iter().all(|result| result.passed)
+    }
+}
+
+#[derive(Clone)]
+pub struct CommandExecutor {
+    base_dir: Option<PathBuf>,
+    stdin: Option<Vec<u8>>,
+    profile: Option<Profile>,
+    profile_override: Option<String>,
+    context_prefix: String,
+    run_id: Option<Uuid>,
+    project_root: Option<PathBuf>,
+    artifacts_dir: Option<PathBuf>,
+    jobs: Option<Arc<RunningJobs>>,
+    output_listener: Option<Arc<OutputListener>>,
+    restricted_mode: RestrictedMode,
+    scrubber: Option<OutputScrubber>,
+    secret_patterns: Vec<String>,
 }
 
-pub struct CommandExecutor;
+/// Called with each line of output as the process writes it, newline included.
+pub type OutputListener = dyn Fn(OutputStream, &str) + Send + Sync;
+
+/// Prefix of the variables that tell a command how it was invoked, unless configured
+/// otherwise.
+pub const DEFAULT_CONTEXT_PREFIX: &str = "ARGUS_";
+
+impl Default for CommandExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl CommandExecutor {
     pub fn new() -> Self {
-        Self
+        Self {
+            base_dir: None,
+            stdin: None,
+            profile: None,
+            profile_override: None,
+            context_prefix: DEFAULT_CONTEXT_PREFIX.to_string(),
+            run_id: None,
+            project_root: None,
+            artifacts_dir: None,
+            jobs: None,
+            output_listener: None,
+            restricted_mode: RestrictedMode::default(),
+            scrubber: None,
+            secret_patterns: Vec::new(),
+        }
+    }
+
+    /// Tracks the processes of this executor's runs in `jobs` while they run, so they can
+    /// be stopped or recorded when the app quits. Each runs in a process group of its own.
+    pub fn with_jobs(mut self, jobs: Arc<RunningJobs>) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+    
+    /// Streams the output of this executor's runs to `listener` while they run. The
+    /// result still holds all of it. Both are scrubbed.
+    pub fn with_output_listener(mut self, listener: Arc<OutputListener>) -> Self {
+        self.output_listener = Some(listener);
+        self
+    }
+
+    /// Masks secrets in the output of this executor's runs with `scrubber`, e.g. that
+    /// of a `GuardedRun`, which knows the values given for secret parameters. Without
+    /// it the output is scrubbed of the command's secret environment variables, the
+    /// built-in credential patterns and those of `with_secret_patterns`.
+    pub fn with_scrubber(mut self, scrubber: OutputScrubber) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+
+    /// Patterns masked in the output besides the built-in ones, such as the settings'
+    /// `secret_patterns`.
+    pub fn with_secret_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.secret_patterns = patterns;
+        self
+    }
+    
+    /// Directory that relative working directories are resolved against, instead of
+    /// the current directory of this process.
+    pub fn with_base_dir(mut self, base_dir: PathBuf) -> Self {
+        self.base_dir = Some(base_dir);
+        self
+    }
+
+    /// Data written to the standard input of the commands this executor runs. Without
+    /// it commands see a closed stdin.
+    pub fn with_stdin(mut self, input: Vec<u8>) -> Self {
+        self.stdin = Some(input);
+        self
+    }
+
+    /// Runs commands in the environment `profile`. Commands that declare their
+    /// profiles refuse to run with any other, or without one.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Lets commands run with a profile they forbid, if `typed_name` is the name of the
+    /// command being run.
+    pub fn with_profile_override(mut self, typed_name: String) -> Self {
+        self.profile_override = Some(typed_name);
+        self
+    }
+
+    /// Refuses to run commands `restricted_mode` doesn't allow, whichever way they are
+    /// started. Without it every command may run.
+    pub fn with_restricted_mode(mut self, restricted_mode: RestrictedMode) -> Self {
+        self.restricted_mode = restricted_mode;
+        self
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Prefix of the variables from `context_variables`. An empty prefix leaves them out.
+    pub fn with_context_prefix(mut self, prefix: String) -> Self {
+        self.context_prefix = prefix;
+        self
+    }
+
+    /// Id passed to commands as `<prefix>RUN_ID`, e.g. the id of a batch run or of the
+    /// history record. Without it every execution gets a new id.
+    pub fn with_run_id(mut self, run_id: Uuid) -> Self {
+        self.run_id = Some(run_id);
+        self
+    }
+
+    /// Value of the `{project_root}` placeholder, e.g. the project opened in the app.
+    /// Without it the root is looked for above the base directory.
+    pub fn with_project_root(mut self, root: PathBuf) -> Self {
+        self.project_root = Some(root);
+        self
+    }
+
+    /// Folder the files matching a command's `collect_artifacts` are copied into once
+    /// it has run, such as `HistoryStorage::artifacts_dir` of the run. Without it no
+    /// artifacts are collected.
+    pub fn with_artifacts_dir(mut self, dir: PathBuf) -> Self {
+        self.artifacts_dir = Some(dir);
+        self
+    }
+
+    /// The variables every child process gets so scripts can log or branch on how they
+    /// were invoked: `<prefix>COMMAND_NAME`, `<prefix>COMMAND_ID`, `<prefix>RUN_ID` and,
+    /// with a profile, `<prefix>PROFILE`.
+    ///
+    /// They are set on top of the inherited environment, and below the command's own
+    /// environment variables and the profile's, which override them.
+    pub fn context_variables(&self, command: &Command) -> Vec<(String, String)> {
+        self.context_variables_of(command, self.run_id())
+    }
+
+    // The id of a run about to start, so its variables and its job share it
+    fn run_id(&self) -> Uuid {
+        self.run_id.unwrap_or_else(Uuid::new_v4)
     }
 
+    fn context_variables_of(&self, command: &Command, run_id: Uuid) -> Vec<(String, String)> {
+        if self.context_prefix.is_empty() {
+            return Vec::new();
+        }
+        let mut variables = vec![
+            ("COMMAND_NAME", command.name.clone()),
+            ("COMMAND_ID", command.id.to_string()),
+            ("RUN_ID", run_id.to_string()),
+        ];
+        if let Some(profile) = &self.profile {
+            variables.push(("PROFILE", profile.name.clone()));
+        }
+        variables
+            .into_iter()
+            .map(|(name, value)| (format!("{}{}", self.context_prefix, name), value))
+            .collect()
+    }
+
+    // The command as it runs here: its variant for the current platform, with restricted
+    // mode and the profile checked and the profile's environment variables applied on top.
+    fn for_run(&self, command: &Command) -> Result<Command, CommandArgusError> {
+        self.restricted_mode.check_execute(command)?;
+        check_profile(command, self.profile.as_ref(), self.profile_override.as_deref())?;
+        let command = self.fill_project_root(command.for_platform(Platform::current())?)?;
+        Ok(match &self.profile {
+            Some(profile) => profile.apply(&command),
+            None => command,
+        })
+    }
+
+    // Replaces the built-in {project_root} placeholder in the program, arguments and
+    // working directory. Fails if the command uses it outside of any project.
+    fn fill_project_root(&self, mut command: Command) -> Result<Command, CommandArgusError> {
+        let placeholder = format!("{{{}}}", PROJECT_ROOT_PLACEHOLDER);
+        let uses_root = std::iter::once(&command.command)
+            .chain(&command.args)
+            .chain(&command.working_directory)
+            .chain(&command.stdin_template)
+            .any(|text| text.contains(&placeholder));
+        if !uses_root {
+            return Ok(command);
+        }
+        let root = match &self.project_root {
+            Some(root) => root.clone(),
+            None => {
+                let start = match &self.base_dir {
+                    Some(dir) => dir.clone(),
+                    None => std::env::current_dir()?,
+                };
+                find_project_root(&start).ok_or_else(|| {
+                    CommandArgusError::InvalidPath(format!(
+                        "'{}' uses {} but {} is not inside a project ({})",
+                        command.name,
+                        placeholder,
+                        start.display(),
+                        PROJECT_ROOT_MARKERS.join(", ")
+                    ))
+                })?
+            }
+        };
+        let values = HashMap::from([(PROJECT_ROOT_PLACEHOLDER.to_string(), root.to_string_lossy().to_string())]);
+        let (program, args) = command.replace_placeholders(&values);
+        command.command = program;
+        command.args = args;
+        command.working_directory = command.replace_working_directory_placeholders(&values);
+        command.stdin_template = command.render_stdin(&values);
+        Ok(command)
+    }
+
+    /// Evaluates the command's preconditions without running it, as `execute` would.
+    pub fn preflight(&self, command: &Command) -> Result<Vec<PreflightFailure>, CommandArgusError> {
+        let (command, _scratch) = Self::fill_run_tmp(command)?;
+        let command = self.for_run(&command)?;
+        let checker = match self.working_directory(&command)? {
+            Some(dir) => PreflightChecker::new().with_base_dir(dir),
+            None => PreflightChecker::new(),
+        };
+        Ok(checker.check(&command))
+    }
+    
     pub fn execute(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
-        let mut process = ProcessCommand::new(&command.command);
-        
-        // Add arguments
-        for arg in &command.args {
-            process.arg(arg);
+        self.in_scratch_dir(command, |command| self.execute_directly(command))
+    }
+    
+    pub fn execute_with_shell(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        self.in_scratch_dir(command, |command| self.execute_in_shell(command))
+    }
+    
+    /// Runs the command the way `mode` says. A terminal window is only opened: the result
+    /// reports a successful start, and a scratch directory would be gone before the
+    /// command gets to use it, so terminal runs may not have one.
+    pub fn execute_in_mode(&self, command: &Command, mode: ExecutionMode) -> Result<ExecutionResult, CommandArgusError> {
+        match mode {
+            ExecutionMode::Direct => self.execute(command),
+            ExecutionMode::Shell => self.execute_with_shell(command),
+            ExecutionMode::Pty => self.in_scratch_dir(command, |command| self.execute_in_pty(command)),
+            ExecutionMode::Terminal => self.execute_in_terminal(command),
         }
-        
-        // Set working directory if specified
-        if let Some(ref working_dir) = command.working_directory {
-            let path = Path::new(working_dir);
-            if !path.exists() {
-                return Err(CommandArgusError::InvalidPath(working_dir.clone()));
+    }
+    
+    // Runs the command with its scratch directory, if it has one. The directory is
+    // removed afterwards unless the run failed and the command keeps it.
+    fn in_scratch_dir(
+        &self,
+        command: &Command,
+        run: impl FnOnce(&Command) -> Result<ExecutionResult, CommandArgusError>,
+    ) -> Result<ExecutionResult, CommandArgusError> {
+        let (command, scratch) = Self::fill_run_tmp(command)?;
+        let Some(mut scratch) = scratch else {
+            return run(&command);
+        };
+        let mut result = run(&command);
+        let succeeded = matches!(&result, Ok(result) if result.success && result.assertions_passed());
+        if !succeeded && command.scratch_dir.keep_on_failure {
+            scratch.keep();
+            if let Ok(result) = &mut result {
+                result.warnings.push(format!("kept the scratch directory {}", scratch.path().display()));
             }
-            process.current_dir(path);
         }
-        
-        // On macOS, ensure common paths are included in PATH
-        #[cfg(target_os = "macos")]
-        {
-            use std::env;
-            
-            let mut path_env = env::var("PATH").unwrap_or_default();
-            let additional_paths = vec![
-                "/opt/homebrew/bin",
-                "/usr/local/bin",
-                "/usr/bin",
-                "/bin",
-                "/usr/sbin",
-                "/sbin",
-            ];
-            
-            for additional_path in additional_paths {
-                if !path_env.contains(additional_path) {
-                    if !path_env.is_empty() {
-                        path_env.push(':');
-                    }
-                    path_env.push_str(additional_path);
-                }
+        result
+    }
+    
+    // Creates the command's scratch directory, if it has one, and puts it in place of
+    // {run_tmp}. Fails if the command uses {run_tmp} without a scratch directory.
+    fn fill_run_tmp(command: &Command) -> Result<(Command, Option<ScratchDir>), CommandArgusError> {
+        let placeholder = format!("{{{}}}", RUN_TMP_PLACEHOLDER);
+        let mut command = command.clone();
+        if !command.scratch_dir.enabled {
+            if texts_mut(&mut command).iter().any(|text| text.contains(&placeholder)) {
+                return Err(CommandArgusError::InvalidCommand(format!(
+                    "'{}' uses {} but has no scratch directory",
+                    command.name, placeholder
+                )));
             }
-            
-            process.env("PATH", path_env);
+            return Ok((command, None));
         }
-        
-        // Set environment variables
-        for env_var in &command.environment_variables {
-            process.env(&env_var.key, &env_var.value);
+        let scratch = ScratchDir::create()?;
+        let dir = scratch.path().to_string_lossy().to_string();
+        for text in texts_mut(&mut command) {
+            *text = text.replace(&placeholder, &dir);
         }
+        Ok((command, Some(scratch)))
+    }
+    
+    fn execute_directly(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        let command = &Self::apply_pinned_executable(self.for_run(command)?, false)?;
         
-        // Execute the command
-        match process.output() {
-            Ok(output) => Ok(ExecutionResult::from_output(output)),
-            Err(e) => Err(CommandArgusError::ExecutionFailed(e.to_string())),
+        // Windows only appends `.exe` when searching PATH, so scripts are resolved here
+        #[cfg(windows)]
+        let (program, args) = match windows::find_program(&command.command) {
+            Some(path) => windows::script_invocation(&path, &command.args),
+            None => (command.command.clone(), command.args.clone()),
+        };
+        #[cfg(not(windows))]
+        let (program, args) = (command.command.clone(), command.args.clone());
+        
+        let mut process = Self::new_process(command, &program, &args)?;
+        
+        // Set working directory if specified
+        if let Some(path) = self.working_directory(command)? {
+            process.current_dir(path);
         }
+        
+        self.run(command, process)
     }
     
-    pub fn execute_with_shell(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+    fn execute_in_shell(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        let command = self.for_run(command)?;
+        
+        if command.shell == ShellKind::PowerShell {
+            // powershell_script quotes an absolute program path itself
+            return self.execute_with_powershell(&Self::apply_pinned_executable(command, false)?);
+        }
+        let command = &Self::apply_pinned_executable(command, true)?;
+        
         let shell_command = if cfg!(target_os = "windows") {
             "cmd"
         } else {
@@ -98,9 +433,6 @@ impl CommandExecutor {
             "-c"
         };
         
-        let mut process = ProcessCommand::new(shell_command);
-        process.arg(shell_arg);
-        
         // Build the command to execute
         let command_to_execute = if command.mise_enabled && !cfg!(target_os = "windows") {
             // Prepend mise activation for non-Windows systems
@@ -109,60 +441,596 @@ impl CommandExecutor {
             command.full_command()
         };
         
-        process.arg(&command_to_execute);
+        let working_dir = self.working_directory(command)?;
         
-        // Set working directory if specified
-        if let Some(ref working_dir) = command.working_directory {
-            let path = Path::new(working_dir);
-            if !path.exists() {
-                return Err(CommandArgusError::InvalidPath(working_dir.clone()));
+        // cmd.exe cannot use a UNC path as its current directory; pushd maps it to a drive
+        #[cfg(windows)]
+        let (working_dir, command_to_execute) = match working_dir {
+            Some(path) if windows::is_unc(&path) => {
+                (None, format!("pushd \"{}\" && {}", path.display(), command_to_execute))
             }
+            other => (other, command_to_execute),
+        };
+        
+        let mut process = Self::new_process(
+            command,
+            shell_command,
+            &[shell_arg.to_string(), command_to_execute],
+        )?;
+        
+        // Set working directory if specified
+        if let Some(path) = working_dir {
             process.current_dir(path);
         }
         
-        // On macOS, ensure common paths are included in PATH
-        #[cfg(target_os = "macos")]
+        self.run(command, process)
+    }
+    
+    fn execute_in_pty(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        let command = &Self::apply_pinned_executable(self.for_run(command)?, false)?;
+        let (program, args) = pty_invocation(&shell_line(command)).ok_or_else(|| {
+            CommandArgusError::InvalidCommand("running in a pseudo-terminal is not supported on Windows".to_string())
+        })?;
+        let mut process = Self::new_process(command, &program, &args)?;
+        if let Some(path) = self.working_directory(command)? {
+            process.current_dir(path);
+        }
+        
+        // The terminal turns every line ending into \r\n
+        let mut result = self.run(command, process)?;
+        result.stdout = result.stdout.replace("\r\n", "\n");
+        Ok(result)
+    }
+    
+    fn execute_in_terminal(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        // The window runs outside of any sandbox and the execution queue's view
+        if command.sandbox.enabled || command.no_network {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "'{}' is sandboxed and cannot run in a terminal window",
+                command.name
+            )));
+        }
+        if command.scratch_dir.enabled {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "'{}' has a scratch directory and cannot run in a terminal window",
+                command.name
+            )));
+        }
+        let command = &Self::apply_pinned_executable(self.for_run(command)?, true)?;
+        let working_dir = self.working_directory(command)?;
+        let mut warnings = Self::check_before_run(command, working_dir.as_deref())?;
+        warnings.push("opened in a terminal window; the output is not captured".to_string());
+        let variables: Vec<(String, String)> = self
+            .context_variables(command)
+            .into_iter()
+            .chain(command.environment_variables.iter().map(|var| (var.key.clone(), expand_env(&var.value))))
+            .collect();
+        
+        let mut prelude = String::new();
+        if let Some(dir) = &working_dir {
+            prelude.push_str(&format!("cd {} && ", shell_quote(&dir.to_string_lossy())));
+        }
+        // Terminal.app starts its shell outside of this process's environment, so the
+        // variables are handed over in a file only the user can read, which the window
+        // sources and removes. Their values never appear on the command line.
+        if cfg!(target_os = "macos") && !variables.is_empty() {
+            let path = shell_quote(&Self::write_env_file(&variables)?.to_string_lossy());
+            prelude.push_str(&format!(". {0} && rm -f {0} && ", path));
+        }
+        let (program, args) = terminal_invocation(&shell_line(command), &prelude);
+        let mut process = ProcessCommand::new(&program);
+        process.args(&args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        if let Some(dir) = &working_dir {
+            process.current_dir(dir);
+        }
+        process.envs(variables);
+        
+        let started = Instant::now();
+        let mut child = process
+            .spawn()
+            .map_err(|e| CommandArgusError::ExecutionFailed(format!("could not open a terminal with {}: {}", program, e)))?;
+        // Reaped in the background so the window can stay open as long as it likes
+        std::thread::spawn(move || child.wait());
+        Ok(ExecutionResult {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: 0,
+            success: true,
+            warnings,
+            duration: started.elapsed(),
+            assertion_results: Vec::new(),
+            artifacts: Vec::new(),
+            stdin: None,
+            working_directory: working_dir,
+            problems: Vec::new(),
+            interrupted: None,
+            timeline: Vec::new(),
+            phases: Vec::new(),
+        })
+    }
+    
+    // Writes `variables` as shell exports to a new file in the temporary directory
+    fn write_env_file(variables: &[(String, String)]) -> Result<PathBuf, CommandArgusError> {
+        let path = std::env::temp_dir().join(format!("argus-env-{}.sh", Uuid::new_v4()));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
         {
-            use std::env;
-            
-            let mut path_env = env::var("PATH").unwrap_or_default();
-            let additional_paths = vec![
-                "/opt/homebrew/bin",
-                "/usr/local/bin",
-                "/usr/bin",
-                "/bin",
-                "/usr/sbin",
-                "/sbin",
-            ];
-            
-            for additional_path in additional_paths {
-                if !path_env.contains(additional_path) {
-                    if !path_env.is_empty() {
-                        path_env.push(':');
-                    }
-                    path_env.push_str(additional_path);
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        for (key, value) in variables {
+            writeln!(file, "export {}={}", key, shell_quote(value))?;
+        }
+        Ok(path)
+    }
+    
+    // Checks the preconditions and tool versions of a command about to start, from
+    // `working_dir`. Returns the warnings of tool checks that don't block it.
+    fn check_before_run(command: &Command, working_dir: Option<&Path>) -> Result<Vec<String>, CommandArgusError> {
+        let checker = match working_dir {
+            Some(dir) => PreflightChecker::new().with_base_dir(dir.to_path_buf()),
+            None => PreflightChecker::new(),
+        };
+        let failures = checker.check(command);
+        if !failures.is_empty() {
+            return Err(CommandArgusError::PreflightFailed(failures));
+        }
+        // Blocking version mismatches fail here; the others are reported with the result
+        run_tool_checks(&command.tool_checks)
+    }
+    
+    fn execute_with_powershell(&self, command: &Command) -> Result<ExecutionResult, CommandArgusError> {
+        let program = if cfg!(target_os = "windows") && find_executable("pwsh").is_none() {
+            "powershell"
+        } else {
+            "pwsh"
+        };
+        let script = powershell_script(command);
+        let mut process = Self::new_process(command, program, &powershell_args(&script, command.load_shell_profile))?;
+        
+        // PowerShell, unlike cmd.exe, accepts UNC working directories
+        if let Some(path) = self.working_directory(command)? {
+            process.current_dir(path);
+        }
+        
+        self.run(command, process)
+    }
+    
+    // Applies the environment shared by all execution modes and runs the process to completion.
+    fn run(&self, command: &Command, mut process: ProcessCommand) -> Result<ExecutionResult, CommandArgusError> {
+        let working_dir = process.get_current_dir().map(Path::to_path_buf);
+        let mut warnings = Self::check_before_run(command, working_dir.as_deref())?;
+        let scrubber = match &self.scrubber {
+            Some(scrubber) => scrubber.clone(),
+            None => OutputScrubber::for_command(command, &HashMap::new(), &self.secret_patterns)?,
+        };
+        
+        // Input given to the executor, such as a previous step's output, wins over the template
+        let input = match (&self.stdin, &command.stdin_template) {
+            (Some(input), _) => Some(input.clone()),
+            (None, Some(template)) => {
+                let missing = stdin_placeholders(template);
+                if !missing.is_empty() {
+                    return Err(CommandArgusError::InvalidCommand(format!(
+                        "the standard input of '{}' has placeholders without a value: {}",
+                        command.name,
+                        missing.join(", ")
+                    )));
                 }
+                Some(template.clone().into_bytes())
             }
-            
+            (None, None) => None,
+        };
+        
+        // On macOS, ensure common paths are included in PATH
+        if let Some(path_env) = child_path() {
             process.env("PATH", path_env);
         }
         
-        // Set environment variables
+        // How the command was invoked; its own variables below may override these
+        let run_id = self.run_id();
+        for (key, value) in self.context_variables_of(command, run_id) {
+            process.env(key, value);
+        }
+        
+        // Set environment variables, expanding references to the parent environment
         for env_var in &command.environment_variables {
-            process.env(&env_var.key, &env_var.value);
+            process.env(&env_var.key, expand_env(&env_var.value));
         }
         
+        // Lines are only streamed into a timeline when it is captured or has phases to find
+        let phase_marker = match command.phase_marker.as_deref().map(phase_marker_regex).transpose() {
+            Ok(marker) => marker,
+            Err(e) => {
+                warnings.push(e.to_string());
+                None
+            }
+        };
+        let timeline = (command.capture_timeline || phase_marker.is_some())
+            .then(|| Timeline::new(command.capture_timeline, phase_marker));
+        
         // Execute the command
-        match process.output() {
-            Ok(output) => Ok(ExecutionResult::from_output(output)),
+        let started = Instant::now();
+        let output = self.output_of(command, run_id, process, input.as_deref(), timeline.as_ref(), &scrubber);
+        let (timeline, phases) = timeline.map(Timeline::finish).unwrap_or_default();
+        match output {
+            Ok((output, interrupted)) => {
+                let result = ExecutionResult::from_output(output, command.output_encoding, started.elapsed());
+                let assertion_results = command
+                    .assertions
+                    .iter()
+                    .map(|assertion| assertion.evaluate(&result.stdout, result.exit_code, result.duration))
+                    .collect();
+                let mut result = ExecutionResult {
+                    warnings,
+                    assertion_results,
+                    stdin: input
+                        .filter(|_| command.record_stdin)
+                        .map(|input| String::from_utf8_lossy(&input).into_owned()),
+                    working_directory: working_dir.clone(),
+                    interrupted,
+                    timeline,
+                    phases,
+                    ..result
+                };
+                if !command.problem_matchers.is_empty() {
+                    match ProblemFinder::new(&command.problem_matchers) {
+                        Ok(finder) => {
+                            let base_dir = working_dir.clone().or_else(|| std::env::current_dir().ok());
+                            let mut problems = finder.find(&result.stdout, OutputStream::Stdout, base_dir.as_deref());
+                            problems.extend(finder.find(&result.stderr, OutputStream::Stderr, base_dir.as_deref()));
+                            problems.truncate(MAX_PROBLEMS);
+                            result.problems = problems;
+                        }
+                        Err(e) => result.warnings.push(e.to_string()),
+                    }
+                }
+                // Collected whether or not the command succeeded, as reports of failed runs matter most
+                if let Some(artifacts_dir) = self.artifacts_dir.as_ref().filter(|_| !command.collect_artifacts.is_empty()) {
+                    let collected = working_dir
+                        .map_or_else(std::env::current_dir, Ok)
+                        .map_err(CommandArgusError::from)
+                        .and_then(|dir| collect_artifacts(&command.collect_artifacts, &dir, artifacts_dir));
+                    match collected {
+                        Ok(artifacts) => result.artifacts = artifacts,
+                        Err(e) => result.warnings.push(format!("could not collect artifacts: {}", e)),
+                    }
+                }
+                // Scrubbed last, so assertions and problem matchers see the output as it was
+                scrubber.scrub_result(&mut result);
+                Ok(result)
+            }
             Err(e) => Err(CommandArgusError::ExecutionFailed(e.to_string())),
         }
     }
+    
+    // Runs the process to completion, feeding it `input`. With a job registry the process
+    // is registered while it runs, and the result tells whether a shutdown stopped it.
+    // With a timeline the output is streamed into it, and the listener gets it scrubbed.
+    fn output_of(
+        &self,
+        command: &Command,
+        run_id: Uuid,
+        mut process: ProcessCommand,
+        input: Option<&[u8]>,
+        timeline: Option<&Timeline>,
+        scrubber: &OutputScrubber,
+    ) -> std::io::Result<(Output, Option<Interruption>)> {
+        if self.jobs.is_none() && self.output_listener.is_none() && timeline.is_none() {
+            let output = match input {
+                Some(input) => Self::output_with_input(process, input),
+                None => process.output(),
+            };
+            return output.map(|output| (output, None));
+        }
+        if self.jobs.as_ref().is_some_and(|jobs| jobs.is_closed()) {
+            return Err(std::io::Error::other("the app is quitting"));
+        }
+        // Signals for the job reach whatever a shell started too
+        #[cfg(unix)]
+        if self.jobs.is_some() {
+            use std::os::unix::process::CommandExt;
+            process.process_group(0);
+        }
+        let child = process
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(jobs) = &self.jobs {
+            // Only the record kept for the next start is lost if the registry cannot be saved
+            let _ = jobs.start(RunningJob {
+                run_id,
+                command_id: command.id,
+                command_name: command.name.clone(),
+                command_line: command.full_command(),
+                pid: child.id(),
+                started_at: chrono::Utc::now(),
+                interruption: None,
+            });
+        }
+        let output = match (&self.output_listener, timeline, input) {
+            (None, None, Some(input)) => Self::wait_with_input(child, input),
+            (None, None, None) => child.wait_with_output(),
+            (listener, timeline, _) => {
+                let listen = |stream: OutputStream, text: &str| {
+                    if let Some(timeline) = timeline {
+                        timeline.push(stream, text);
+                    }
+                    if let Some(listener) = listener {
+                        listener(stream, &scrubber.scrub(text));
+                    }
+                };
+                Self::wait_streaming(child, input, command.output_encoding, &listen)
+            }
+        };
+        let interrupted = self.jobs.as_ref().and_then(|jobs| jobs.finish(run_id));
+        output.map(|output| (output, interrupted))
+    }
+    
+    // Like `Child::wait_with_output`, but hands each line to `listener` as it is read.
+    fn wait_streaming(
+        mut child: Child,
+        input: Option<&[u8]>,
+        encoding: OutputEncoding,
+        listener: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> std::io::Result<Output> {
+        let writer = child.stdin.take().zip(input).map(|(mut stdin, input)| {
+            let input = input.to_vec();
+            std::thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            })
+        });
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let (stdout, stderr) = std::thread::scope(|scope| {
+            let stdout = scope.spawn(|| Self::read_lines(stdout, OutputStream::Stdout, encoding, listener));
+            let stderr = Self::read_lines(stderr, OutputStream::Stderr, encoding, listener);
+            (stdout.join().expect("stdout reader panicked"), stderr)
+        });
+        let status = child.wait()?;
+        if let Some(writer) = writer {
+            let _ = writer.join();
+        }
+        Ok(Output { status, stdout: stdout?, stderr: stderr? })
+    }
+    
+    fn read_lines(
+        pipe: impl Read,
+        stream: OutputStream,
+        encoding: OutputEncoding,
+        listener: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> std::io::Result<Vec<u8>> {
+        let mut reader = BufReader::new(pipe);
+        let mut output = Vec::new();
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            listener(stream, &encoding.decode(&line));
+            output.append(&mut line);
+        }
+        Ok(output)
+    }
+    
+    // Like `Command::output`, but feeds `input` to the child's stdin.
+    fn output_with_input(mut process: ProcessCommand, input: &[u8]) -> std::io::Result<Output> {
+        let child = process
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        Self::wait_with_input(child, input)
+    }
+    
+    // The input is written from a separate thread so a child that fills its stdout pipe
+    // before reading all of its input cannot deadlock.
+    fn wait_with_input(mut child: Child, input: &[u8]) -> std::io::Result<Output> {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = input.to_vec();
+        // A child that exits without reading everything closes the pipe; that is not an error
+        let writer = std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+        let output = child.wait_with_output();
+        let _ = writer.join();
+        output
+    }
+    
+    // Swaps in the pinned executable, quoted if it will be part of a shell command line.
+    // A missing pinned binary is an error rather than a silent fallback to PATH, which
+    // could run a different program.
+    fn apply_pinned_executable(mut command: Command, for_shell: bool) -> Result<Command, CommandArgusError> {
+        if let Some(warning) = command.pinned_executable_warning() {
+            return Err(CommandArgusError::InvalidPath(warning));
+        }
+        if let Some(pinned) = command.pinned_executable.take() {
+            command.command = if for_shell && pinned.contains(char::is_whitespace) {
+                format!("\"{}\"", pinned)
+            } else {
+                pinned
+            };
+        }
+        Ok(command)
+    }
+    
+    // Resolves the command's working directory, checking that it is an existing directory.
+    fn working_directory(&self, command: &Command) -> Result<Option<PathBuf>, CommandArgusError> {
+        let Some(ref working_dir) = command.working_directory else {
+            return Ok(None);
+        };
+        let unfilled = working_directory_placeholders(working_dir);
+        if !unfilled.is_empty() {
+            return Err(CommandArgusError::InvalidPath(format!(
+                "{} has placeholders without a value: {}",
+                working_dir,
+                unfilled.join(", ")
+            )));
+        }
+        let path = resolve_working_directory(working_dir, self.base_dir.as_deref())?;
+        if !path.is_dir() {
+            return Err(CommandArgusError::InvalidPath(format!(
+                "{} (resolved to {}) is not a directory",
+                working_dir,
+                path.display()
+            )));
+        }
+        #[cfg(windows)]
+        let path = windows::strip_verbatim_prefix(&path);
+        Ok(Some(path))
+    }
+    
+    // Builds the process for `program args`, wrapped in the platform sandbox when the
+    // command asks for one. Refuses to run if sandboxing was requested but is unavailable.
+    fn new_process(command: &Command, program: &str, args: &[String]) -> Result<ProcessCommand, CommandArgusError> {
+        if command.sandbox.enabled {
+            let mut policy = command.sandbox.clone();
+            policy.network &= !command.no_network;
+            let (program, args) = SandboxBackend::detect()?.wrap(&policy, program, args)?;
+            let mut process = ProcessCommand::new(program);
+            process.args(args);
+            return Ok(process);
+        }
+        if command.no_network {
+            return network_isolated_process(program, args);
+        }
+        
+        let mut process = ProcessCommand::new(program);
+        process.args(args);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            process.creation_flags(windows::CREATE_NO_WINDOW);
+        }
+        Ok(process)
+    }
+}
+
+/// The `PATH` child processes get instead of the inherited one, if it is changed. A GUI
+/// app launched from Finder lacks the Homebrew and system directories, so on macOS they
+/// are appended.
+pub(crate) fn child_path() -> Option<String> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let mut path_env = std::env::var("PATH").unwrap_or_default();
+    let additional_paths = [
+        "/opt/homebrew/bin",
+        "/usr/local/bin",
+        "/usr/bin",
+        "/bin",
+        "/usr/sbin",
+        "/sbin",
+    ];
+    
+    for additional_path in additional_paths {
+        if !path_env.contains(additional_path) {
+            if !path_env.is_empty() {
+                path_env.push(':');
+            }
+            path_env.push_str(additional_path);
+        }
+    }
+    Some(path_env)
+}
+
+// Windows specifics. The helpers are plain path manipulation, so they are also built
+// for tests on other platforms.
+#[cfg(any(windows, test))]
+mod windows {
+    use std::path::{Path, PathBuf};
+    
+    /// Keeps a console window from flashing up for every command run from the GUI.
+    #[cfg(windows)]
+    pub const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    
+    /// Looks `program` up the way cmd.exe does, honouring `PATHEXT` (plus `.ps1`).
+    #[cfg(windows)]
+    pub fn find_program(program: &str) -> Option<PathBuf> {
+        let dirs: Vec<PathBuf> = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).collect())
+            .unwrap_or_default();
+        let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+        let mut extensions: Vec<String> = pathext.split(';').filter(|e| !e.is_empty()).map(String::from).collect();
+        extensions.push(".PS1".to_string());
+        resolve_program(program, &dirs, &extensions)
+    }
+    
+    /// Finds `program` in `dirs`, trying each of `extensions` when it has none. A
+    /// program given with a directory component is only checked where it points.
+    pub fn resolve_program(program: &str, dirs: &[PathBuf], extensions: &[String]) -> Option<PathBuf> {
+        let program_path = Path::new(program);
+        let candidates: Vec<PathBuf> = if program_path.components().count() > 1 {
+            vec![program_path.to_path_buf()]
+        } else {
+            dirs.iter().map(|dir| dir.join(program)).collect()
+        };
+        
+        for candidate in candidates {
+            if candidate.extension().is_some() {
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+                continue;
+            }
+            for extension in extensions {
+                let mut with_extension = candidate.clone().into_os_string();
+                with_extension.push(extension.to_ascii_lowercase());
+                let with_extension = PathBuf::from(with_extension);
+                if with_extension.is_file() {
+                    return Some(with_extension);
+                }
+            }
+        }
+        None
+    }
+    
+    /// Program and arguments to launch a resolved file with. PowerShell scripts need an
+    /// interpreter; `.bat`/`.cmd` files are passed to `std::process::Command` as is,
+    /// which runs them through cmd.exe with its own argument escaping.
+    pub fn script_invocation(path: &Path, args: &[String]) -> (String, Vec<String>) {
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        let path_str = path.to_string_lossy().to_string();
+        if extension.as_deref() == Some("ps1") {
+            let mut ps_args: Vec<String> = ["-NoLogo", "-NoProfile", "-ExecutionPolicy", "Bypass", "-File"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            ps_args.push(path_str);
+            ps_args.extend(args.iter().cloned());
+            ("powershell".to_string(), ps_args)
+        } else {
+            (path_str, args.to_vec())
+        }
+    }
+    
+    /// `canonicalize` yields verbatim paths (`\\?\C:\x`, `\\?\UNC\server\share`) that
+    /// cmd.exe and many tools reject; turn them back into the usual form.
+    pub fn strip_verbatim_prefix(path: &Path) -> PathBuf {
+        let path_str = path.to_string_lossy();
+        if let Some(rest) = path_str.strip_prefix(r"\\?\UNC\") {
+            PathBuf::from(format!(r"\\{}", rest))
+        } else if let Some(rest) = path_str.strip_prefix(r"\\?\") {
+            PathBuf::from(rest)
+        } else {
+            path.to_path_buf()
+        }
+    }
+    
+    pub fn is_unc(path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+        path_str.starts_with(r"\\") && !path_str.starts_with(r"\\?\")
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
+    use crate::assertions::Assertion;
+    use crate::preflight::Precondition;
+    use crate::restricted::APPROVED_TAG;
+    use crate::tools::ToolCheck;
     
     #[test]
     fn test_simple_command_execution() {
@@ -175,6 +1043,19 @@ mod tests {
         assert!(result.stdout.contains("Hello, World!"));
     }
     
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_in_pty_mode() {
+        let executor = CommandExecutor::new();
+        let cmd = Command::new("Tty".to_string(), "sh".to_string())
+            .with_args(vec!["-c".to_string(), "if [ -t 1 ]; then echo tty; else echo pipe; fi".to_string()]);
+        
+        assert_eq!(executor.execute_in_mode(&cmd, ExecutionMode::Direct).unwrap().stdout, "pipe\n");
+        let result = executor.execute_in_mode(&cmd, ExecutionMode::Pty).unwrap();
+        assert!(result.success);
+        assert_eq!(result.stdout, "tty\n");
+    }
+    
     #[test]
     fn test_command_with_invalid_working_dir() {
         let executor = CommandExecutor::new();
@@ -184,4 +1065,384 @@ mod tests {
         let result = executor.execute(&cmd);
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_relative_working_dir_uses_base() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("project")).unwrap();
+        let executor = CommandExecutor::new().with_base_dir(temp.path().to_path_buf());
+        let cmd = Command::new("Pwd".to_string(), "pwd".to_string())
+            .with_working_directory("project".to_string());
+        
+        let result = executor.execute(&cmd).unwrap();
+        let expected = temp.path().join("project").canonicalize().unwrap();
+        assert_eq!(result.stdout.trim_end(), expected.to_string_lossy());
+    }
+    
+    #[test]
+    fn test_templated_working_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("services/billing")).unwrap();
+        let cmd = Command::new("Pwd".to_string(), "pwd".to_string())
+            .with_working_directory("{root}/services/{service}".to_string());
+        assert_eq!(cmd.detect_placeholders(), vec!["root", "service"]);
+        
+        let executor = CommandExecutor::new();
+        assert!(matches!(
+            executor.execute(&cmd),
+            Err(CommandArgusError::InvalidPath(message))
+                if message == "{root}/services/{service} has placeholders without a value: root, service"
+        ));
+        
+        let mut values = std::collections::HashMap::new();
+        values.insert("root".to_string(), temp.path().to_string_lossy().to_string());
+        values.insert("service".to_string(), "billing".to_string());
+        let mut filled = cmd.clone();
+        filled.working_directory = cmd.replace_working_directory_placeholders(&values);
+        let result = executor.execute(&filled).unwrap();
+        let expected = temp.path().join("services/billing").canonicalize().unwrap();
+        assert_eq!(result.stdout.trim_end(), expected.to_string_lossy());
+        
+        // ${NAME} is still an environment variable in the working directory
+        let cmd = Command::new("Pwd".to_string(), "pwd".to_string())
+            .with_working_directory("${HOME}/{service}".to_string());
+        assert_eq!(cmd.detect_placeholders(), vec!["service"]);
+        let filled = cmd.replace_working_directory_placeholders(&values).unwrap();
+        assert_eq!(filled, "${HOME}/billing");
+    }
+    
+    #[test]
+    fn test_project_root_placeholder() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().join("repo");
+        std::fs::create_dir_all(root.join("crates/api")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "").unwrap();
+        let cmd = Command::new("Pwd".to_string(), "pwd".to_string())
+            .with_working_directory("{project_root}".to_string());
+        assert!(cmd.detect_placeholders().is_empty());
+        
+        let executor = CommandExecutor::new().with_base_dir(root.join("crates/api"));
+        let result = executor.execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim_end(), root.canonicalize().unwrap().to_string_lossy());
+        
+        let executor = CommandExecutor::new().with_base_dir(temp.path().to_path_buf());
+        assert!(matches!(executor.execute(&cmd), Err(CommandArgusError::InvalidPath(_))));
+        let result = executor.with_project_root(root.join("crates")).execute(&cmd).unwrap();
+        assert!(result.stdout.trim_end().ends_with("crates"));
+    }
+    
+    #[test]
+    fn test_stdin_template() {
+        let mut cmd = Command::new("Query".to_string(), "cat".to_string());
+        cmd.stdin_template = Some("SELECT count(*) FROM {table};\n-- {\"limit\": 1}\n".to_string());
+        assert_eq!(cmd.detect_placeholders(), vec!["table".to_string()]);
+        assert!(matches!(CommandExecutor::new().execute(&cmd), Err(CommandArgusError::InvalidCommand(_))));
+        
+        let values = HashMap::from([("table".to_string(), "users".to_string())]);
+        cmd.stdin_template = cmd.render_stdin(&values);
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert_eq!(result.stdout, "SELECT count(*) FROM users;\n-- {\"limit\": 1}\n");
+        assert_eq!(result.stdin, None);
+        
+        cmd.record_stdin = true;
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert_eq!(result.stdin.as_deref(), Some(result.stdout.as_str()));
+        let result = CommandExecutor::new().with_stdin(b"piped".to_vec()).execute(&cmd).unwrap();
+        assert_eq!(result.stdout, "piped");
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_timeline_and_phases() {
+        let mut cmd = Command::new("Interleaved".to_string(), "sh".to_string()).with_args(vec![
+            "-c".to_string(),
+            "echo one; sleep 0.2; echo two >&2; sleep 0.2; echo three".to_string(),
+        ]);
+        assert!(CommandExecutor::new().execute(&cmd).unwrap().timeline.is_empty());
+        
+        cmd.capture_timeline = true;
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert_eq!((result.stdout.as_str(), result.stderr.as_str()), ("one\nthree\n", "two\n"));
+        let lines: Vec<(OutputStream, &str)> = result.timeline.iter().map(|e| (e.stream, e.line.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![(OutputStream::Stdout, "one"), (OutputStream::Stderr, "two"), (OutputStream::Stdout, "three")]
+        );
+        assert!(result.timeline[2].at_ms >= 300);
+        
+        cmd.capture_timeline = false;
+        cmd.phase_marker = Some("^==> (.*)".to_string());
+        cmd.args[1] = "echo '==> compile'; sleep 0.2; echo '==> test' >&2".to_string();
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert!(result.timeline.is_empty());
+        let names: Vec<&str> = result.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["compile", "test"]);
+        assert!(result.phases[0].duration_ms >= 150);
+        
+        cmd.phase_marker = Some("(".to_string());
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert!(result.phases.is_empty());
+        assert!(result.warnings[0].contains("Invalid phase marker"));
+    }
+    
+    #[test]
+    fn test_scratch_dir() {
+        let mut cmd = Command::new("Scratch".to_string(), "pwd".to_string())
+            .with_working_directory("{run_tmp}".to_string());
+        assert!(cmd.detect_placeholders().is_empty());
+        assert!(matches!(CommandExecutor::new().execute(&cmd), Err(CommandArgusError::InvalidCommand(_))));
+        
+        cmd.scratch_dir.enabled = true;
+        let first = CommandExecutor::new().execute(&cmd).unwrap();
+        let second = CommandExecutor::new().execute(&cmd).unwrap();
+        let dir = std::path::PathBuf::from(first.stdout.trim_end());
+        assert_ne!(first.stdout, second.stdout);
+        assert!(!dir.exists());
+        assert!(first.warnings.is_empty());
+        
+        cmd.command = "test".to_string();
+        cmd.args = vec!["-e".to_string(), "{run_tmp}/missing".to_string()];
+        cmd.scratch_dir.keep_on_failure = true;
+        let failed = CommandExecutor::new().execute(&cmd).unwrap();
+        assert!(!failed.success);
+        let kept = failed.warnings[0].strip_prefix("kept the scratch directory ").unwrap();
+        assert!(Path::new(kept).is_dir());
+        std::fs::remove_dir(kept).unwrap();
+    }
+    
+    #[test]
+    fn test_windows_program_resolution() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("build.cmd"), "").unwrap();
+        std::fs::write(temp.path().join("deploy.ps1"), "").unwrap();
+        let dirs = vec![temp.path().to_path_buf()];
+        let extensions = vec![".EXE".to_string(), ".CMD".to_string(), ".PS1".to_string()];
+        
+        let build = windows::resolve_program("build", &dirs, &extensions).unwrap();
+        assert_eq!(build, temp.path().join("build.cmd"));
+        let (program, args) = windows::script_invocation(&build, &["x".to_string()]);
+        assert_eq!((program, args), (build.to_string_lossy().to_string(), vec!["x".to_string()]));
+        
+        let deploy = windows::resolve_program("deploy", &dirs, &extensions).unwrap();
+        let (program, args) = windows::script_invocation(&deploy, &[]);
+        assert_eq!(program, "powershell");
+        assert_eq!(args.last().unwrap(), &deploy.to_string_lossy().to_string());
+        
+        assert!(windows::resolve_program("missing", &dirs, &extensions).is_none());
+    }
+    
+    #[test]
+    fn test_windows_unc_paths() {
+        let unc = windows::strip_verbatim_prefix(Path::new(r"\\?\UNC\server\share\dir"));
+        assert_eq!(unc, PathBuf::from(r"\\server\share\dir"));
+        assert!(windows::is_unc(&unc));
+        
+        let local = windows::strip_verbatim_prefix(Path::new(r"\\?\C:\work"));
+        assert_eq!(local, PathBuf::from(r"C:\work"));
+        assert!(!windows::is_unc(&local));
+    }
+    
+    #[test]
+    fn test_pinned_executable() {
+        let executor = CommandExecutor::new();
+        let mut cmd = Command::new("Echo".to_string(), "echo".to_string())
+            .with_args(vec!["pinned".to_string()]);
+        cmd.pin_executable().unwrap();
+        cmd.command = "not-on-path".to_string();
+        assert!(executor.execute(&cmd).unwrap().stdout.contains("pinned"));
+        
+        cmd.pinned_executable = Some("/nonexistent/bin/echo".to_string());
+        let err = executor.execute(&cmd).unwrap_err().to_string();
+        assert!(err.contains("no longer exists"), "{}", err);
+    }
+    
+    #[test]
+    fn test_tool_checks() {
+        let executor = CommandExecutor::new();
+        let mut cmd = Command::new("Echo".to_string(), "echo".to_string())
+            .with_args(vec!["ran".to_string()]);
+        let mut check = ToolCheck::parse("echo >= 2").unwrap();
+        check.version_args = vec!["1.0".to_string()];
+        cmd.tool_checks = vec![check];
+        
+        let result = executor.execute(&cmd).unwrap();
+        assert!(result.stdout.contains("ran"));
+        assert_eq!(result.warnings, vec!["echo 1.0 does not satisfy >= 2"]);
+        
+        cmd.tool_checks[0].block = true;
+        assert!(matches!(executor.execute_with_shell(&cmd), Err(CommandArgusError::CheckFailed(_))));
+        // Checked before a terminal window is opened too
+        assert!(matches!(executor.execute_in_mode(&cmd, ExecutionMode::Terminal), Err(CommandArgusError::CheckFailed(_))));
+    }
+    
+    #[test]
+    fn test_preconditions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let executor = CommandExecutor::new();
+        let mut cmd = Command::new("List".to_string(), "ls".to_string())
+            .with_working_directory(temp.path().to_string_lossy().to_string());
+        cmd.preconditions = vec![Precondition::PathExists { path: "input.txt".to_string() }];
+        
+        match executor.execute(&cmd) {
+            Err(CommandArgusError::PreflightFailed(failures)) => {
+                assert_eq!(failures.len(), 1);
+                assert!(failures[0].message.contains("input.txt"));
+            }
+            other => panic!("expected a preflight failure, got {:?}", other),
+        }
+        assert!(matches!(
+            executor.execute_in_mode(&cmd, ExecutionMode::Terminal),
+            Err(CommandArgusError::PreflightFailed(_))
+        ));
+        
+        std::fs::write(temp.path().join("input.txt"), "").unwrap();
+        assert!(executor.preflight(&cmd).unwrap().is_empty());
+        assert!(executor.execute(&cmd).unwrap().stdout.contains("input.txt"));
+    }
+    
+    #[test]
+    fn test_assertions() {
+        let executor = CommandExecutor::new();
+        let mut cmd = Command::new("Echo".to_string(), "echo".to_string())
+            .with_args(vec!["healthy".to_string()]);
+        let result = executor.execute(&cmd).unwrap();
+        assert!(result.assertion_results.is_empty());
+        assert!(result.assertions_passed());
+        
+        cmd.assertions = vec![
+            Assertion::StdoutContains { text: "healthy".to_string() },
+            Assertion::ExitCodeIn { codes: vec![1] },
+            Assertion::DurationUnder { seconds: 60.0 },
+        ];
+        let result = executor.execute(&cmd).unwrap();
+        assert!(result.success);
+        let passed: Vec<bool> = result.assertion_results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, vec![true, false, true]);
+        assert!(!result.assertions_passed());
+    }
+    
+    #[test]
+    fn test_env_values_expanded() {
+        let executor = CommandExecutor::new();
+        let mut cmd = Command::new("Env".to_string(), "printenv".to_string())
+            .with_args(vec!["GREETING".to_string()]);
+        cmd.add_environment_variable("GREETING".to_string(), "${PATH}|$$".to_string());
+        
+        let result = executor.execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim_end(), format!("{}|$", std::env::var("PATH").unwrap()));
+    }
+    
+    #[test]
+    fn test_stdin() {
+        let cmd = Command::new("Count".to_string(), "wc".to_string())
+            .with_args(vec!["-l".to_string()]);
+        let result = CommandExecutor::new().with_stdin(b"a\nb\nc\n".to_vec()).execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim(), "3");
+        
+        // Without input the command sees an empty stdin instead of hanging
+        let result = CommandExecutor::new().execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim(), "0");
+    }
+    
+    #[test]
+    fn test_profile() {
+        let mut cmd = Command::new("Target".to_string(), "printenv".to_string())
+            .with_args(vec!["TARGET".to_string()]);
+        cmd.profiles = vec!["staging".to_string()];
+        assert!(matches!(CommandExecutor::new().execute(&cmd), Err(CommandArgusError::WrongProfile(_))));
+        
+        let mut staging = Profile::new("staging".to_string());
+        staging.environment_variables.push(crate::command::EnvironmentVariable {
+            key: "TARGET".to_string(),
+            value: "staging.example.com".to_string(),
+            secret: false,
+        });
+        let result = CommandExecutor::new().with_profile(staging.clone()).execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim(), "staging.example.com");
+        
+        cmd.forbidden_profiles = vec!["staging".to_string()];
+        let executor = CommandExecutor::new().with_profile(staging);
+        assert!(matches!(executor.execute(&cmd), Err(CommandArgusError::WrongProfile(_))));
+        assert!(executor.with_profile_override("Target".to_string()).execute(&cmd).is_ok());
+    }
+    
+    #[test]
+    fn test_restricted_mode() {
+        let mut locked = RestrictedMode::default();
+        locked.enable("letmein").unwrap();
+        let executor = CommandExecutor::new().with_restricted_mode(locked);
+        let mut cmd = Command::new("Echo".to_string(), "echo".to_string()).with_args(vec!["ran".to_string()]);
+        for mode in [ExecutionMode::Direct, ExecutionMode::Shell, ExecutionMode::Terminal] {
+            assert!(matches!(executor.execute_in_mode(&cmd, mode), Err(CommandArgusError::AccessDenied(_))));
+        }
+        assert!(matches!(executor.preflight(&cmd), Err(CommandArgusError::AccessDenied(_))));
+        
+        cmd.add_tag(APPROVED_TAG.to_string());
+        assert!(executor.execute(&cmd).unwrap().stdout.contains("ran"));
+    }
+    
+    #[test]
+    fn test_context_variables() {
+        let mut cmd = Command::new("Context".to_string(), "sh".to_string()).with_args(vec![
+            "-c".to_string(),
+            "echo $ARGUS_COMMAND_NAME $ARGUS_COMMAND_ID $ARGUS_RUN_ID $ARGUS_PROFILE".to_string(),
+        ]);
+        let run_id = Uuid::new_v4();
+        let executor = CommandExecutor::new()
+            .with_profile(Profile::new("dev".to_string()))
+            .with_run_id(run_id);
+        let result = executor.execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim(), format!("Context {} {} dev", cmd.id, run_id));
+        
+        // The command's own variables take precedence
+        cmd.environment_variables.push(crate::command::EnvironmentVariable {
+            key: "ARGUS_PROFILE".to_string(),
+            value: "mine".to_string(),
+            secret: false,
+        });
+        let result = executor.execute(&cmd).unwrap();
+        assert!(result.stdout.trim().ends_with(" mine"));
+        
+        let result = CommandExecutor::new().with_context_prefix(String::new()).execute(&cmd).unwrap();
+        assert_eq!(result.stdout.trim(), "mine");
+    }
+    
+    #[test]
+    fn test_output_is_scrubbed() {
+        let mut cmd = Command::new("Leak".to_string(), "sh".to_string()).with_args(vec![
+            "-c".to_string(),
+            "echo $API_TOKEN build-4711 hunter22".to_string(),
+        ]);
+        cmd.add_secret_environment_variable("API_TOKEN".to_string(), "s3cr3t-token".to_string());
+        let streamed = Arc::new(std::sync::Mutex::new(String::new()));
+        let sink = streamed.clone();
+        let executor = CommandExecutor::new()
+            .with_secret_patterns(vec![r"build-\d+".to_string()])
+            .with_output_listener(Arc::new(move |_, text| sink.lock().unwrap().push_str(text)));
+        let result = executor.execute(&cmd).unwrap();
+        assert_eq!(result.stdout, "[REDACTED] [REDACTED] hunter22\n");
+        assert_eq!(*streamed.lock().unwrap(), result.stdout);
+
+        // A run's own scrubber also knows the values of secret parameters
+        let mut scrubber = OutputScrubber::new();
+        scrubber.add_secret("hunter22");
+        let result = CommandExecutor::new().with_scrubber(scrubber).execute(&cmd).unwrap();
+        assert_eq!(result.stdout, "s3cr3t-token build-4711 [REDACTED]\n");
+    }
+    
+    #[test]
+    fn test_run_id_without_with_run_id() {
+        // The job registered for the run has the id the command sees
+        let temp = tempfile::TempDir::new().unwrap();
+        let registry = temp.path().join("running.json");
+        let cmd = Command::new("Context".to_string(), "sh".to_string()).with_args(vec![
+            "-c".to_string(),
+            format!("echo $ARGUS_RUN_ID; cat '{}'", registry.display()),
+        ]);
+        let executor = CommandExecutor::new().with_jobs(Arc::new(RunningJobs::with_path(registry)));
+        let result = executor.execute(&cmd).unwrap();
+        let (run_id, jobs) = result.stdout.split_once('\n').unwrap();
+        let jobs: Vec<RunningJob> = serde_json::from_str(jobs).unwrap();
+        assert_eq!(jobs[0].run_id.to_string(), run_id);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+
+use crate::error::{CommandArgusError, Result};
+
+/// Expands `$VAR`, `${VAR}` and a leading `~` against the current process environment.
+///
+/// Unset variables expand to an empty string, as in a shell. `$$` produces a literal `$`.
+pub fn expand_env(value: &str) -> String {
+    let home = BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().to_string());
+    expand_env_with(value, home.as_deref(), |name| env::var(name).ok())
+}
+
+/// Like [`expand_env`], but resolves variables with `lookup` and `~` with `home`.
+pub fn expand_env_with(value: &str, home: Option<&str>, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if let Some(home) = home {
+        if rest == "~" || rest.starts_with("~/") {
+            expanded.push_str(home);
+            rest = &rest[1..];
+        }
+    }
+
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(after_escape) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = after_escape;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) if is_var_name(&braced[..end]) => {
+                    expanded.push_str(&lookup(&braced[..end]).unwrap_or_default());
+                    rest = &braced[end + 1..];
+                }
+                _ => {
+                    expanded.push('$');
+                    rest = after;
+                }
+            }
+        } else {
+            let len = after
+                .char_indices()
+                .find(|&(i, c)| !(c == '_' || c.is_ascii_alphanumeric()) || (i == 0 && c.is_ascii_digit()))
+                .map(|(i, _)| i)
+                .unwrap_or(after.len());
+            if len == 0 {
+                expanded.push('$');
+            } else {
+                expanded.push_str(&lookup(&after[..len]).unwrap_or_default());
+            }
+            rest = &after[len..];
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Resolves a command's `working_directory`: expands variables and `~`, joins relative
+/// paths onto `base` (the current directory when `None`) and canonicalizes the result.
+pub fn resolve_working_directory(dir: &str, base: Option<&Path>) -> Result<PathBuf> {
+    let expanded = PathBuf::from(expand_env(dir));
+    let resolved = match base {
+        Some(base) if expanded.is_relative() => base.join(&expanded),
+        _ => expanded,
+    };
+    resolved.canonicalize().map_err(|e| {
+        let resolved = resolved.to_string_lossy();
+        if resolved == dir {
+            CommandArgusError::InvalidPath(format!("{}: {}", dir, e))
+        } else {
+            CommandArgusError::InvalidPath(format!("{} (resolved to {}): {}", dir, resolved, e))
+        }
+    })
+}
+
+fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(value: &str) -> String {
+        expand_env_with(value, Some("/home/alice"), |name| match name {
+            "HOME" => Some("/home/alice".to_string()),
+            "PROFILE" => Some("dev".to_string()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_expands_variables() {
+        assert_eq!(expand("${HOME}/bin"), "/home/alice/bin");
+        assert_eq!(expand("$HOME/bin:$PROFILE"), "/home/alice/bin:dev");
+        assert_eq!(expand("x${MISSING}y$MISSING"), "xy");
+    }
+
+    #[test]
+    fn test_resolve_working_directory() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("sub")).unwrap();
+        let canonical = temp.path().canonicalize().unwrap();
+
+        assert_eq!(
+            resolve_working_directory("sub/../sub", Some(temp.path())).unwrap(),
+            canonical.join("sub")
+        );
+        let absolute = canonical.to_string_lossy().to_string();
+        assert_eq!(resolve_working_directory(&absolute, None).unwrap(), canonical);
+
+        let err = resolve_working_directory("missing", Some(temp.path())).unwrap_err().to_string();
+        assert!(err.contains("resolved to"), "{}", err);
+        assert!(err.contains(&temp.path().join("missing").to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_tilde_and_escapes() {
+        assert_eq!(expand("~/work"), "/home/alice/work");
+        assert_eq!(expand("a~b"), "a~b");
+        assert_eq!(expand("cost: $$5"), "cost: $5");
+        assert_eq!(expand("$ ${ ${1x} $5"), "$ ${ ${1x} $5");
+    }
+}
@@ -0,0 +1,538 @@
+use regex::Regex;
+use serde::Serialize;
+
+use crate::command::{Command, ParameterType};
+use crate::project::PROJECT_ROOT_PLACEHOLDER;
+use crate::scratch::RUN_TMP_PLACEHOLDER;
+use crate::variables::variable_references;
+
+/// What a part of a command line is.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartKind {
+    Program,
+    Subcommand,
+    Flag,
+    /// The value a flag takes.
+    Value,
+    Argument,
+    /// `&&`, `|` and the like, which only mean something when run in a shell.
+    Operator,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExplainedPart {
+    pub text: String,
+    pub kind: PartKind,
+    /// Missing for parts the built-in database knows nothing about.
+    pub meaning: Option<String>,
+}
+
+/// What a placeholder of the command stands for.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PlaceholderRole {
+    /// The name between the braces: `var:NAME` for a template variable.
+    pub name: String,
+    pub role: String,
+}
+
+/// A readable breakdown of a command, for whoever is about to run it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CommandExplanation {
+    pub parts: Vec<ExplainedPart>,
+    pub placeholders: Vec<PlaceholderRole>,
+    pub working_directory: Option<String>,
+}
+
+impl CommandExplanation {
+    pub fn text(&self) -> String {
+        let mut lines: Vec<String> = self
+            .parts
+            .iter()
+            .map(|part| {
+                let indent = if part.kind == PartKind::Program { "" } else { "  " };
+                match &part.meaning {
+                    Some(meaning) => format!("{}{} — {}", indent, part.text, meaning),
+                    None => format!("{}{}", indent, part.text),
+                }
+            })
+            .collect();
+        if !self.placeholders.is_empty() {
+            lines.push("Placeholders:".to_string());
+            lines.extend(self.placeholders.iter().map(|p| match p.name.starts_with("var:") {
+                true => format!("  {{{{{}}}}} — {}", p.name, p.role),
+                false => format!("  {{{}}} — {}", p.name, p.role),
+            }));
+        }
+        if let Some(directory) = &self.working_directory {
+            lines.push(format!("Runs in {}", directory));
+        }
+        lines.join("\n")
+    }
+}
+
+// A flag of a tool: the subcommand it belongs to (any when `None`), its spellings,
+// whether it takes a value and what it does
+type Flag = (Option<&'static str>, &'static [&'static str], bool, &'static str);
+
+struct Tool {
+    program: &'static str,
+    summary: &'static str,
+    subcommands: &'static [(&'static str, &'static str)],
+    flags: &'static [Flag],
+}
+
+/// What the common tools and their flags do.
+const TOOLS: &[Tool] = &[
+    Tool {
+        program: "git",
+        summary: "version control",
+        subcommands: &[
+            ("add", "stage changes for the next commit"),
+            ("branch", "list, create or delete branches"),
+            ("checkout", "switch branches or restore files"),
+            ("clone", "copy a repository"),
+            ("commit", "record the staged changes"),
+            ("diff", "show changes"),
+            ("fetch", "download objects and refs from a remote"),
+            ("log", "show the commit history"),
+            ("merge", "join another branch into the current one"),
+            ("pull", "fetch and integrate a remote branch"),
+            ("push", "upload commits to a remote"),
+            ("rebase", "reapply commits on top of another base"),
+            ("reset", "move the current branch to another commit"),
+            ("stash", "set changes aside"),
+            ("status", "show the state of the working tree"),
+            ("switch", "switch branches"),
+            ("tag", "list, create or delete tags"),
+        ],
+        flags: &[
+            (None, &["-C"], true, "run as if started in this directory"),
+            (Some("commit"), &["-m", "--message"], true, "the commit message"),
+            (Some("commit"), &["-a", "--all"], false, "stage every modified file first"),
+            (Some("commit"), &["--amend"], false, "replace the last commit"),
+            (Some("checkout"), &["-b"], true, "create a branch with this name and switch to it"),
+            (Some("switch"), &["-c", "--create"], true, "create a branch with this name and switch to it"),
+            (Some("clone"), &["--depth"], true, "fetch only this many recent commits"),
+            (Some("pull"), &["--rebase"], false, "rebase instead of merging"),
+            (Some("push"), &["-f", "--force"], false, "overwrite the remote branch even if it has other commits"),
+            (Some("push"), &["--force-with-lease"], false, "force, but only if the remote branch is as last fetched"),
+            (Some("push"), &["-u", "--set-upstream"], false, "make the remote branch the upstream of the local one"),
+            (Some("reset"), &["--hard"], false, "discard changes in the working tree too"),
+            (Some("log"), &["--oneline"], false, "one line per commit"),
+            (None, &["--tags"], false, "include tags"),
+            (None, &["--no-verify"], false, "skip the hooks"),
+            (None, &["-v", "--verbose"], false, "more output"),
+        ],
+    },
+    Tool {
+        program: "docker",
+        summary: "build and run containers",
+        subcommands: &[
+            ("build", "build an image from a Dockerfile"),
+            ("compose", "run a multi-container application"),
+            ("exec", "run a command in a running container"),
+            ("images", "list images"),
+            ("logs", "show a container's output"),
+            ("ps", "list containers"),
+            ("pull", "download an image"),
+            ("push", "upload an image"),
+            ("rm", "remove containers"),
+            ("rmi", "remove images"),
+            ("run", "start a new container"),
+            ("stop", "stop running containers"),
+        ],
+        flags: &[
+            (Some("build"), &["-t", "--tag"], true, "name and tag of the image"),
+            (Some("build"), &["-f", "--file"], true, "the Dockerfile to use"),
+            (Some("build"), &["--build-arg"], true, "a build-time variable, NAME=value"),
+            (Some("build"), &["--no-cache"], false, "do not reuse layers from earlier builds"),
+            (Some("run"), &["-t", "--tty"], false, "allocate a terminal"),
+            (Some("exec"), &["-t", "--tty"], false, "allocate a terminal"),
+            (None, &["-i", "--interactive"], false, "keep standard input open"),
+            (None, &["-d", "--detach"], false, "run in the background"),
+            (None, &["-e", "--env"], true, "set an environment variable, NAME=value"),
+            (Some("run"), &["-p", "--publish"], true, "publish a container port on the host, host:container"),
+            (Some("run"), &["-v", "--volume"], true, "mount a host path or volume, source:target"),
+            (Some("run"), &["--rm"], false, "remove the container when it exits"),
+            (Some("run"), &["--name"], true, "name of the container"),
+            (Some("logs"), &["-f", "--follow"], false, "keep printing new output"),
+        ],
+    },
+    Tool {
+        program: "npm",
+        summary: "Node.js package manager",
+        subcommands: &[
+            ("ci", "install exactly what the lockfile lists"),
+            ("i", "install dependencies"),
+            ("install", "install dependencies"),
+            ("publish", "publish the package to the registry"),
+            ("run", "run a script from package.json"),
+            ("start", "run the start script"),
+            ("test", "run the test script"),
+            ("update", "update dependencies"),
+        ],
+        flags: &[
+            (None, &["-g", "--global"], false, "install globally rather than in the project"),
+            (None, &["-D", "--save-dev"], false, "save as a development dependency"),
+            (None, &["--omit"], true, "leave out this kind of dependency, e.g. dev"),
+            (None, &["--prefix"], true, "run in this directory"),
+            (None, &["-w", "--workspace"], true, "run in this workspace"),
+            (None, &["--silent"], false, "no output but errors"),
+        ],
+    },
+    Tool {
+        program: "kubectl",
+        summary: "control Kubernetes clusters",
+        subcommands: &[
+            ("apply", "create or update resources from files"),
+            ("delete", "delete resources"),
+            ("describe", "show details of resources"),
+            ("exec", "run a command in a container"),
+            ("get", "list resources"),
+            ("logs", "show a container's output"),
+            ("port-forward", "forward local ports to a pod"),
+            ("rollout", "manage the rollout of a resource"),
+            ("scale", "set the number of replicas"),
+        ],
+        flags: &[
+            (None, &["-n", "--namespace"], true, "the namespace"),
+            (None, &["--context"], true, "the kubeconfig context, i.e. which cluster"),
+            (None, &["-f", "--filename"], true, "file or directory of resource definitions"),
+            (None, &["-o", "--output"], true, "output format, e.g. yaml or wide"),
+            (None, &["-l", "--selector"], true, "only resources with these labels"),
+            (None, &["-A", "--all-namespaces"], false, "across all namespaces"),
+            (None, &["-w", "--watch"], false, "keep watching for changes"),
+            (Some("scale"), &["--replicas"], true, "the number of replicas"),
+            (None, &["-c", "--container"], true, "the container within the pod"),
+            (Some("exec"), &["-i", "--stdin"], false, "pass standard input to the container"),
+            (Some("exec"), &["-t", "--tty"], false, "allocate a terminal"),
+            (None, &["--dry-run"], false, "only show what would be done"),
+        ],
+    },
+    Tool {
+        program: "curl",
+        summary: "transfer data from or to a URL",
+        subcommands: &[],
+        flags: &[
+            (None, &["-X", "--request"], true, "the HTTP method"),
+            (None, &["-H", "--header"], true, "an extra request header"),
+            (None, &["-d", "--data"], true, "the request body"),
+            (None, &["-o", "--output"], true, "write the response to this file"),
+            (None, &["-O", "--remote-name"], false, "write the response to a file named like the URL"),
+            (None, &["-L", "--location"], false, "follow redirects"),
+            (None, &["-s", "--silent"], false, "no progress or error messages"),
+            (None, &["-S", "--show-error"], false, "show errors even when silent"),
+            (None, &["-f", "--fail"], false, "fail on HTTP errors instead of printing the error page"),
+            (None, &["-u", "--user"], true, "user and password for authentication"),
+            (None, &["-k", "--insecure"], false, "do not verify the TLS certificate"),
+            (None, &["-I", "--head"], false, "fetch the headers only"),
+            (None, &["-v", "--verbose"], false, "show the request and response in detail"),
+        ],
+    },
+    Tool {
+        program: "grep",
+        summary: "search text for a pattern",
+        subcommands: &[],
+        flags: &[
+            (None, &["-r", "--recursive"], false, "search directories recursively"),
+            (None, &["-i", "--ignore-case"], false, "ignore case"),
+            (None, &["-n", "--line-number"], false, "show line numbers"),
+            (None, &["-v", "--invert-match"], false, "show lines that do not match"),
+            (None, &["-E", "--extended-regexp"], false, "the pattern is an extended regular expression"),
+            (None, &["-F", "--fixed-strings"], false, "the pattern is plain text"),
+            (None, &["-l", "--files-with-matches"], false, "only list the matching files"),
+            (None, &["-c", "--count"], false, "count the matching lines"),
+            (None, &["-w", "--word-regexp"], false, "match whole words only"),
+            (None, &["--include"], true, "only search files matching this glob"),
+            (None, &["--exclude"], true, "skip files matching this glob"),
+        ],
+    },
+    Tool {
+        program: "tar",
+        summary: "create and extract archives",
+        subcommands: &[],
+        flags: &[
+            (None, &["-c", "--create"], false, "create an archive"),
+            (None, &["-x", "--extract"], false, "extract an archive"),
+            (None, &["-t", "--list"], false, "list the contents of an archive"),
+            (None, &["-z", "--gzip"], false, "gzip compression"),
+            (None, &["-j", "--bzip2"], false, "bzip2 compression"),
+            (None, &["-J", "--xz"], false, "xz compression"),
+            (None, &["-v", "--verbose"], false, "list the files processed"),
+            (None, &["-f", "--file"], true, "the archive file"),
+            (None, &["-C", "--directory"], true, "change to this directory first"),
+        ],
+    },
+    Tool {
+        program: "ssh",
+        summary: "log in to or run commands on a remote machine",
+        subcommands: &[],
+        flags: &[
+            (None, &["-i"], true, "the private key to authenticate with"),
+            (None, &["-p"], true, "the port to connect to"),
+            (None, &["-L"], true, "forward a local port, local:host:remote"),
+            (None, &["-J"], true, "connect through this jump host"),
+            (None, &["-N"], false, "do not run a remote command, only forward ports"),
+            (None, &["-o"], true, "a configuration option, Name=value"),
+            (None, &["-v"], false, "print debugging messages"),
+        ],
+    },
+    Tool {
+        program: "rsync",
+        summary: "synchronize files, locally or with a remote machine",
+        subcommands: &[],
+        flags: &[
+            (None, &["-a", "--archive"], false, "copy recursively, keeping permissions, times and links"),
+            (None, &["-v", "--verbose"], false, "list the files transferred"),
+            (None, &["-z", "--compress"], false, "compress data in transit"),
+            (None, &["-n", "--dry-run"], false, "only show what would be transferred"),
+            (None, &["-h", "--human-readable"], false, "readable sizes"),
+            (None, &["-P"], false, "show progress and keep partial transfers"),
+            (None, &["-e", "--rsh"], true, "the remote shell to use"),
+            (None, &["--delete"], false, "delete files that are not in the source"),
+            (None, &["--exclude"], true, "skip files matching this pattern"),
+        ],
+    },
+    Tool {
+        program: "cargo",
+        summary: "Rust package manager",
+        subcommands: &[
+            ("add", "add a dependency"),
+            ("build", "compile the package"),
+            ("check", "check the package for errors without building it"),
+            ("clippy", "run the linter"),
+            ("doc", "build the documentation"),
+            ("fmt", "format the code"),
+            ("install", "install a binary"),
+            ("publish", "publish the package to the registry"),
+            ("run", "build and run a binary"),
+            ("test", "run the tests"),
+        ],
+        flags: &[
+            (None, &["--release"], false, "build with optimizations"),
+            (None, &["-p", "--package"], true, "the package of the workspace"),
+            (None, &["--workspace"], false, "every package of the workspace"),
+            (None, &["--all-targets"], false, "tests, benches and examples as well"),
+            (None, &["-F", "--features"], true, "features to enable"),
+            (None, &["--bin"], true, "the binary to build or run"),
+            (None, &["-q", "--quiet"], false, "no output but errors"),
+            (None, &["--offline"], false, "do not access the network"),
+            (None, &["--locked"], false, "fail if the lockfile needs updating"),
+        ],
+    },
+];
+
+const OPERATORS: &[(&str, &str)] = &[
+    ("&&", "then, if that succeeded"),
+    ("||", "then, if that failed"),
+    (";", "then"),
+    ("|", "passing its output to"),
+    (">", "writing the output to the file that follows"),
+    (">>", "appending the output to the file that follows"),
+    ("<", "reading the input from the file that follows"),
+];
+
+fn find_tool(program: &str) -> Option<&'static Tool> {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    TOOLS.iter().find(|tool| tool.program == name)
+}
+
+fn find_flag(tool: &Tool, subcommand: Option<&str>, name: &str) -> Option<&'static Flag> {
+    let flags = tool.flags.iter().filter(|(_, names, _, _)| names.contains(&name));
+    // A subcommand's own meaning wins over the general one
+    flags.clone().find(|flag| flag.0.is_some() && flag.0 == subcommand).or_else(|| flags.into_iter().find(|flag| flag.0.is_none()))
+}
+
+/// Breaks the command down into its program, subcommand, flags and arguments, with
+/// what each means where the built-in database knows the tool, and says what its
+/// placeholders stand for. Only the default platform variant is explained.
+pub fn explain_command(command: &Command) -> CommandExplanation {
+    // The whole line may be in the program field
+    let mut tokens: Vec<String> = match command.command.contains(char::is_whitespace) {
+        true if !command.command.contains(['"', '\'']) => command.command.split_whitespace().map(str::to_string).collect(),
+        _ => vec![command.command.clone()],
+    };
+    tokens.extend(command.args.iter().cloned());
+
+    let mut parts = Vec::new();
+    let mut tool = None;
+    let mut subcommand = None;
+    let mut expects_program = true;
+    let mut value_of: Option<String> = None;
+    for token in &tokens {
+        let part = |kind: PartKind, meaning: Option<String>| ExplainedPart { text: token.clone(), kind, meaning };
+        if let Some((_, meaning)) = OPERATORS.iter().find(|(operator, _)| operator == token) {
+            parts.push(part(PartKind::Operator, Some(meaning.to_string())));
+            expects_program = !token.starts_with(['>', '<']);
+            value_of = None;
+            continue;
+        }
+        if expects_program {
+            tool = find_tool(token);
+            subcommand = None;
+            expects_program = false;
+            parts.push(part(PartKind::Program, tool.map(|tool| tool.summary.to_string())));
+            continue;
+        }
+        if let Some(flag) = value_of.take() {
+            parts.push(part(PartKind::Value, Some(format!("value of {}", flag))));
+            continue;
+        }
+        if token.starts_with('-') && token.len() > 1 {
+            let (name, inline_value) = match token.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+                _ => (token.as_str(), None),
+            };
+            let found = tool.and_then(|tool| find_flag(tool, subcommand, name));
+            if let Some(&(_, _, takes_value, meaning)) = found {
+                if takes_value && inline_value.is_none() {
+                    value_of = Some(name.to_string());
+                }
+                parts.push(part(PartKind::Flag, Some(meaning.to_string())));
+                continue;
+            }
+            // Short flags run together, like tar's -xzf
+            let combined: Option<Vec<&Flag>> = match (tool, token.starts_with("--")) {
+                (Some(tool), false) => token[1..].chars().map(|c| find_flag(tool, subcommand, &format!("-{}", c))).collect(),
+                _ => None,
+            };
+            match combined {
+                Some(flags) if token.len() > 2 => {
+                    if flags.last().is_some_and(|flag| flag.2) {
+                        value_of = Some(format!("-{}", token.chars().last().unwrap()));
+                    }
+                    let meanings: Vec<&str> = flags.iter().map(|flag| flag.3).collect();
+                    parts.push(part(PartKind::Flag, Some(meanings.join("; "))));
+                }
+                _ => parts.push(part(PartKind::Flag, None)),
+            }
+            continue;
+        }
+        let known = tool.filter(|_| subcommand.is_none()).and_then(|tool| {
+            tool.subcommands.iter().find(|(name, _)| name == token)
+        });
+        match known {
+            Some((name, meaning)) => {
+                subcommand = Some(*name);
+                parts.push(part(PartKind::Subcommand, Some(meaning.to_string())));
+            }
+            None => parts.push(part(PartKind::Argument, None)),
+        }
+    }
+
+    CommandExplanation {
+        parts,
+        placeholders: placeholder_roles(command),
+        working_directory: command.working_directory.clone(),
+    }
+}
+
+fn placeholder_roles(command: &Command) -> Vec<PlaceholderRole> {
+    let mut roles = Vec::new();
+    let mut texts = command.full_command();
+    if let Some(directory) = &command.working_directory {
+        texts.push(' ');
+        texts.push_str(directory);
+    }
+    for name in variable_references(&texts) {
+        roles.push(PlaceholderRole {
+            role: "a template variable, filled in from the profile, the project or your own variables".to_string(),
+            name: format!("var:{}", name),
+        });
+    }
+    let placeholder = Regex::new(r"\$?\{([^{}]+)\}").unwrap();
+    for found in placeholder.captures_iter(&texts) {
+        let name = &found[1];
+        // Template variables are listed above
+        if roles.iter().any(|role: &PlaceholderRole| role.name == name) || name.starts_with("var:") {
+            continue;
+        }
+        let role = if name == PROJECT_ROOT_PLACEHOLDER {
+            "the root of the opened project".to_string()
+        } else if name == RUN_TMP_PLACEHOLDER {
+            "a scratch directory made for the run".to_string()
+        } else if let Some(parameter) = command.get_parameter(name) {
+            let kind = match parameter.parameter_type {
+                ParameterType::Text => "text",
+                ParameterType::File => "file",
+                ParameterType::Directory => "directory",
+                ParameterType::Select => "choice",
+            };
+            let mut role = format!("a {} parameter", kind);
+            match (&parameter.default_value, parameter.required) {
+                (Some(default), _) if !default.is_empty() && !parameter.secret => {
+                    role.push_str(&format!(", '{}' by default", default))
+                }
+                (_, true) => role.push_str(", asked for on every run"),
+                (_, false) => role.push_str(", optional"),
+            }
+            if let Some(options) = parameter.options.as_ref().filter(|_| parameter.parameter_type == ParameterType::Select) {
+                role.push_str(&format!(", one of {}", options.join(", ")));
+            }
+            if parameter.secret {
+                role.push_str(", kept out of output and history");
+            }
+            role
+        } else if found[0].starts_with('$') {
+            "an environment variable".to_string()
+        } else {
+            "not a parameter of the command; it is passed on as written".to_string()
+        };
+        roles.push(PlaceholderRole { name: name.to_string(), role });
+    }
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandParameter;
+
+    #[test]
+    fn test_explain_command() {
+        let mut command = Command::new("Ship".to_string(), "docker".to_string()).with_args(
+            ["build", "-t", "app:{version}", "--no-cache", ".", "&&", "tar", "-czf", "out.tgz", "dist", "&&", "mystery", "--flag"]
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+        );
+        command.add_parameter(CommandParameter {
+            name: "version".to_string(),
+            placeholder: "Version".to_string(),
+            parameter_type: ParameterType::Text,
+            required: false,
+            default_value: Some("1.0".to_string()),
+            options: None,
+            secret: false,
+        });
+        command.working_directory = Some("{project_root}/services/{{var:SERVICE}}".to_string());
+
+        let explanation = explain_command(&command);
+        let kinds: Vec<PartKind> = explanation.parts.iter().map(|part| part.kind).collect();
+        use PartKind::*;
+        assert_eq!(
+            kinds,
+            vec![Program, Subcommand, Flag, Value, Flag, Argument, Operator, Program, Flag, Value, Argument, Operator, Program, Flag]
+        );
+        assert_eq!(explanation.parts[2].meaning.as_deref(), Some("name and tag of the image"));
+        assert_eq!(explanation.parts[3].meaning.as_deref(), Some("value of -t"));
+        assert_eq!(explanation.parts[8].meaning.as_deref(), Some("create an archive; gzip compression; the archive file"));
+        assert_eq!(explanation.parts[12].meaning, None);
+        let names: Vec<&str> = explanation.placeholders.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["var:SERVICE", "version", "project_root"]);
+        assert_eq!(explanation.placeholders[1].role, "a text parameter, '1.0' by default");
+
+        let text = explanation.text();
+        assert!(text.starts_with("docker — build and run containers\n  build — build an image from a Dockerfile\n"));
+        assert!(text.contains("\n  {{var:SERVICE}} — a template variable"));
+        assert!(text.ends_with("Runs in {project_root}/services/{{var:SERVICE}}"));
+
+        // -t means something else to docker run
+        let run = Command::new("Shell".to_string(), "docker run -it --rm alpine".to_string());
+        let explanation = explain_command(&run);
+        assert_eq!(explanation.parts[2].meaning.as_deref(), Some("keep standard input open; allocate a terminal"));
+        assert_eq!(explanation.parts[4].kind, PartKind::Argument);
+    }
+}
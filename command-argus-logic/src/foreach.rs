@@ -0,0 +1,300 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executor::{CommandExecutor, ExecutionResult};
+use crate::glob::find_glob_matches;
+use crate::shell::ExecutionMode;
+
+/// Directories that are never searched for matches: dependencies and build output.
+/// Hidden directories are skipped as well.
+pub const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target"];
+
+/// The result of running the command in one directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryOutcome {
+    pub directory: PathBuf,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the command could not be started in the directory.
+    pub error: Option<String>,
+}
+
+impl DirectoryOutcome {
+    /// Where the command ran, relative to `root`, the folder it was found under.
+    pub fn relative_to(&self, root: &Path) -> String {
+        match self.directory.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative.to_string_lossy().to_string(),
+            Err(_) => self.directory.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// The outcomes of a run across directories, in path order.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForEachReport {
+    pub root: PathBuf,
+    pub outcomes: Vec<DirectoryOutcome>,
+    pub duration: Duration,
+}
+
+impl ForEachReport {
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.success).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| !o.success).count()
+    }
+
+    pub fn success(&self) -> bool {
+        self.failed() == 0
+    }
+
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for outcome in &self.outcomes {
+            let detail = match (&outcome.error, outcome.exit_code) {
+                (Some(error), _) => error.clone(),
+                (None, Some(code)) => format!("exit {}", code),
+                (None, None) => "terminated".to_string(),
+            };
+            lines.push(format!(
+                "[{}] {} ({}, {:.2}s)",
+                if outcome.success { "PASS" } else { "FAIL" },
+                outcome.relative_to(&self.root),
+                detail,
+                outcome.duration.as_secs_f64()
+            ));
+        }
+        lines.push(format!(
+            "{} of {} directories passed, {} failed in {:.2}s",
+            self.passed(),
+            self.outcomes.len(),
+            self.failed(),
+            self.duration.as_secs_f64()
+        ));
+        lines.join("\n")
+    }
+}
+
+type DirectoryListener<'a> = Box<dyn Fn(&DirectoryOutcome) + Send + Sync + 'a>;
+type DirectoryRun<'a> = Box<dyn Fn(&Command, ExecutionMode) -> Result<ExecutionResult> + Send + Sync + 'a>;
+
+/// Runs one command in every directory matching a glob, like `mr` or `foreach`.
+pub struct ForEachRunner<'a> {
+    executor: &'a CommandExecutor,
+    parallelism: usize,
+    use_shell: bool,
+    progress: Option<DirectoryListener<'a>>,
+    run: Option<DirectoryRun<'a>>,
+}
+
+impl<'a> ForEachRunner<'a> {
+    pub fn new(executor: &'a CommandExecutor) -> Self {
+        Self { executor, parallelism: 1, use_shell: false, progress: None, run: None }
+    }
+
+    /// How many directories run at the same time.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    pub fn with_shell(mut self, use_shell: bool) -> Self {
+        self.use_shell = use_shell;
+        self
+    }
+
+    /// Called as each directory finishes, from the thread that ran it.
+    pub fn with_progress(mut self, listener: impl Fn(&DirectoryOutcome) + Send + Sync + 'a) -> Self {
+        self.progress = Some(Box::new(listener));
+        self
+    }
+
+    /// Starts each directory's run in place of the executor, e.g. to wait for its turn
+    /// in a queue and record it in the history. Given the command with the directory as
+    /// its working directory.
+    pub fn with_run(mut self, run: impl Fn(&Command, ExecutionMode) -> Result<ExecutionResult> + Send + Sync + 'a) -> Self {
+        self.run = Some(Box::new(run));
+        self
+    }
+
+    /// Runs `command` in each directory under `root` that matches `pattern`, in place
+    /// of its own working directory. Fails only if no directory matches.
+    pub fn run(&self, command: &Command, root: &Path, pattern: &str) -> Result<ForEachReport> {
+        let directories = matching_directories(root, pattern)?;
+        if directories.is_empty() {
+            return Err(CommandArgusError::InvalidPath(format!(
+                "no directory under {} matches '{}'",
+                root.display(),
+                pattern
+            )));
+        }
+        self.run_in_directories(command, root, &directories)
+    }
+
+    /// Runs `command` in each of `directories`, found under `root` beforehand, e.g. to
+    /// check first that the command may run that many times.
+    pub fn run_in_directories(&self, command: &Command, root: &Path, directories: &[PathBuf]) -> Result<ForEachReport> {
+        let started = Instant::now();
+        let next = AtomicUsize::new(0);
+        let outcomes: Mutex<Vec<Option<DirectoryOutcome>>> = Mutex::new(vec![None; directories.len()]);
+        let workers = self.parallelism.min(directories.len());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(directory) = directories.get(index) else {
+                        break;
+                    };
+                    let outcome = self.run_in(command, directory);
+                    if let Some(ref listener) = self.progress {
+                        listener(&outcome);
+                    }
+                    outcomes.lock().unwrap()[index] = Some(outcome);
+                });
+            }
+        });
+
+        Ok(ForEachReport {
+            root: root.to_path_buf(),
+            outcomes: outcomes.into_inner().unwrap().into_iter().flatten().collect(),
+            duration: started.elapsed(),
+        })
+    }
+
+    fn run_in(&self, command: &Command, directory: &Path) -> DirectoryOutcome {
+        let mut command = command.clone();
+        command.working_directory = Some(directory.to_string_lossy().to_string());
+        for platform_override in &mut command.platform_overrides {
+            platform_override.working_directory = None;
+        }
+
+        let started = Instant::now();
+        let mode = if self.use_shell { ExecutionMode::Shell } else { ExecutionMode::Direct };
+        let result = match &self.run {
+            Some(run) => run(&command, mode),
+            None => self.executor.execute_in_mode(&command, mode),
+        };
+        match result {
+            Ok(result) => DirectoryOutcome {
+                directory: directory.to_path_buf(),
+                success: result.success && result.assertions_passed(),
+                exit_code: Some(result.exit_code),
+                duration: result.duration,
+                stdout: result.stdout,
+                stderr: result.stderr,
+                error: None,
+            },
+            Err(e) => DirectoryOutcome {
+                directory: directory.to_path_buf(),
+                success: false,
+                exit_code: None,
+                duration: started.elapsed(),
+                stdout: String::new(),
+                stderr: String::new(),
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// The directories under `root` whose path relative to it matches the glob `pattern`,
+/// sorted. A matching file stands for the directory containing it, so
+/// `**/package.json` finds every npm package and `packages/*` every folder in
+/// `packages`. Hidden and [`SKIPPED_DIRECTORIES`] are not searched.
+pub fn matching_directories(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let descend = |name: &str| !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name);
+    let mut found: Vec<PathBuf> = find_glob_matches(root, pattern, &descend)?
+        .into_iter()
+        .filter_map(|found| match found.is_dir {
+            true => Some(found.path),
+            false => found.path.parent().map(Path::to_path_buf),
+        })
+        .collect();
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_in_matching_directories() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for package in ["packages/api", "packages/web", "tools/lint", "packages/web/node_modules/dep"] {
+            fs::create_dir_all(root.join(package)).unwrap();
+            fs::write(root.join(package).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("packages/.cache")).unwrap();
+        fs::write(root.join("packages/api/broken"), "").unwrap();
+
+        let packages = matching_directories(&root, "**/package.json").unwrap();
+        assert_eq!(packages, vec![root.join("packages/api"), root.join("packages/web"), root.join("tools/lint")]);
+        let children = matching_directories(&root, "packages/*").unwrap();
+        assert_eq!(children, vec![root.join("packages/api"), root.join("packages/web")]);
+        assert!(matching_directories(&root, "docs/*").unwrap().is_empty());
+
+        let executor = CommandExecutor::new();
+        let command = Command::new("Check".to_string(), "test".to_string())
+            .with_args(vec!["!".to_string(), "-e".to_string(), "broken".to_string()]);
+        let report = ForEachRunner::new(&executor)
+            .with_parallelism(2)
+            .run(&command, &root, "**/package.json")
+            .unwrap();
+        assert_eq!(report.outcomes.len(), 3);
+        assert_eq!((report.passed(), report.failed()), (2, 1));
+        assert!(!report.outcomes[0].success);
+        assert_eq!(report.outcomes[0].relative_to(&report.root), "packages/api");
+        assert!(report.summary().ends_with(&format!(
+            "2 of 3 directories passed, 1 failed in {:.2}s",
+            report.duration.as_secs_f64()
+        )));
+
+        assert!(ForEachRunner::new(&executor).run(&command, &root, "docs/*").is_err());
+    }
+
+    #[test]
+    fn test_runs_go_through_the_hook() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for package in ["api", "web"] {
+            fs::create_dir_all(root.join(package)).unwrap();
+        }
+        let directories = matching_directories(&root, "*").unwrap();
+
+        let executor = CommandExecutor::new();
+        let command = Command::new("Deploy".to_string(), "true".to_string());
+        let started = Mutex::new(Vec::new());
+        let report = ForEachRunner::new(&executor)
+            .with_run(|command, mode| {
+                let directory = command.working_directory.clone().unwrap();
+                started.lock().unwrap().push((directory.clone(), mode));
+                match directory.ends_with("web") {
+                    true => Err(CommandArgusError::RateLimited("'Deploy' may run at most 1 times per hour".to_string())),
+                    false => executor.execute_in_mode(command, mode),
+                }
+            })
+            .run_in_directories(&command, &root, &directories)
+            .unwrap();
+        assert_eq!(started.into_inner().unwrap().iter().map(|(_, mode)| *mode).collect::<Vec<_>>(), vec![ExecutionMode::Direct; 2]);
+        assert!(report.outcomes[0].success);
+        assert!(report.outcomes[1].error.as_deref().unwrap().contains("at most 1 times"));
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::error::{CommandArgusError, Result};
+
+/// A file or directory found by [`find_glob_matches`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobMatch {
+    pub path: PathBuf,
+    /// Relative to the folder searched, with `/` separators.
+    pub relative: String,
+    pub is_dir: bool,
+}
+
+/// The entries under `root` whose relative path matches the glob `pattern`, sorted by
+/// path. `*` and `?` stay within one path component and `*` does not match hidden
+/// names; `**` spans any number of components. Only directories whose name `descend`
+/// accepts are searched, and symbolic links are not followed, so cycles cannot occur.
+pub fn find_glob_matches(root: &Path, pattern: &str, descend: &dyn Fn(&str) -> bool) -> Result<Vec<GlobMatch>> {
+    let re = glob_regex(pattern)?;
+    let mut found = Vec::new();
+    collect_matches(root, "", &re, descend, &mut found)?;
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(found)
+}
+
+fn collect_matches(
+    dir: &Path,
+    relative: &str,
+    re: &Regex,
+    descend: &dyn Fn(&str) -> bool,
+    found: &mut Vec<GlobMatch>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = match relative {
+            "" => name.clone(),
+            _ => format!("{}/{}", relative, name),
+        };
+        let is_dir = entry.file_type()?.is_dir();
+        if re.is_match(&path) {
+            found.push(GlobMatch { path: entry.path(), relative: path.clone(), is_dir });
+        }
+        if is_dir && descend(&name) {
+            collect_matches(&entry.path(), &path, re, descend, found)?;
+        }
+    }
+    Ok(())
+}
+
+fn glob_regex(pattern: &str) -> Result<Regex> {
+    let pattern = pattern.trim_start_matches("./").replace('\\', "/");
+    let mut re = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    let mut component_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                    continue;
+                }
+                re.push_str(".*");
+            }
+            '*' if component_start => re.push_str("(?:[^/.][^/]*)?"),
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        component_start = c == '/';
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| CommandArgusError::InvalidQuery(format!("invalid pattern '{}': {}", pattern, e)))
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::approval::ensure_no_approval_needed;
+use crate::command::Command;
+use crate::error::Result;
+use crate::executor::ExecutionResult;
+use crate::history::{ExecutionRecord, HistoryStorage};
+use crate::impact::ensure_confirmed;
+use crate::restricted::RestrictedMode;
+use crate::scrub::OutputScrubber;
+use crate::settings::Settings;
+
+/// What lets a run of a saved command go ahead.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Clearance {
+    /// Started directly, without a confirmation.
+    #[default]
+    Unconfirmed,
+    /// Started directly and confirmed with this: anything for a click, the command's
+    /// name where it has to be typed.
+    Confirmed(String),
+    /// An approved run request, which stands in for the confirmation.
+    Approved,
+}
+
+impl Clearance {
+    /// Refuses to run `command` unless `restricted_mode` allows it and, for runs that
+    /// were not approved, it needs no approval and was confirmed as its impact asks.
+    pub fn check(&self, command: &Command, restricted_mode: &RestrictedMode) -> Result<()> {
+        restricted_mode.check_execute(command)?;
+        let confirmation = match self {
+            Clearance::Approved => return Ok(()),
+            Clearance::Unconfirmed => None,
+            Clearance::Confirmed(confirmation) => Some(confirmation.as_str()),
+        };
+        ensure_no_approval_needed(command)?;
+        ensure_confirmed(command, confirmation)
+    }
+}
+
+impl From<Option<String>> for Clearance {
+    fn from(confirmation: Option<String>) -> Self {
+        confirmation.map_or(Clearance::Unconfirmed, Clearance::Confirmed)
+    }
+}
+
+/// A run of a saved command, from the checks that may refuse it to its scrubbed record
+/// for the history. The GUI and the background agent both run commands through it, so
+/// they refuse, scrub and record runs alike.
+pub struct GuardedRun {
+    run_id: Uuid,
+    command: Command,
+    parameters: HashMap<String, String>,
+    started_at: DateTime<Utc>,
+    scrubber: OutputScrubber,
+}
+
+impl GuardedRun {
+    /// Checks that `command`, as it is about to run with `parameters`, may start now:
+    /// `Clearance::check` and the command's rate limit. Called once the run's turn has
+    /// come, so runs waiting in a queue count against the limit. `run_id` becomes the
+    /// id of the history record.
+    pub fn begin(
+        run_id: Uuid,
+        command: &Command,
+        parameters: &HashMap<String, String>,
+        clearance: &Clearance,
+        settings: &Settings,
+        history: &HistoryStorage,
+    ) -> Result<Self> {
+        clearance.check(command, &settings.restricted_mode)?;
+        history.check_rate_limit(command)?;
+        Ok(Self {
+            run_id,
+            command: command.clone(),
+            parameters: parameters.clone(),
+            started_at: Utc::now(),
+            scrubber: OutputScrubber::for_command(command, parameters, &settings.secret_patterns)?,
+        })
+    }
+
+    pub fn run_id(&self) -> Uuid {
+        self.run_id
+    }
+
+    /// The command as it runs.
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Masks the run's secrets, for output passed on while it runs.
+    pub fn scrubber(&self) -> &OutputScrubber {
+        &self.scrubber
+    }
+
+    /// The run's record for `HistoryStorage::record`, with the parameter values it was
+    /// given, scrubbed. The result is scrubbed as well, for the caller to pass on.
+    pub fn finish(&self, result: &mut Result<ExecutionResult>) -> ExecutionRecord {
+        let scrubber = &self.scrubber;
+        if let Ok(result) = result {
+            scrubber.scrub_result(result);
+        }
+        let mut record = match result {
+            Ok(result) => ExecutionRecord::from_result(&self.command, self.started_at, result),
+            Err(e) => ExecutionRecord::from_error(&self.command, self.started_at, e.to_string()),
+        }
+        .with_parameters(&self.command, &self.parameters);
+        record.id = self.run_id;
+        record.command_line = scrubber.scrub(&record.command_line);
+        record.error = record.error.map(|e| scrubber.scrub(&e));
+        for value in record.parameters.values_mut() {
+            *value = scrubber.scrub(value);
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CommandArgusError;
+    use crate::executor::CommandExecutor;
+    use crate::impact::Impact;
+    use crate::ratelimit::{RateLimit, RatePeriod};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_checks_and_scrubbed_record() {
+        let temp = TempDir::new().unwrap();
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+        let mut settings = Settings::default();
+        let mut cmd = Command::new("Login".to_string(), "echo".to_string())
+            .with_args(vec!["token=s3cr3t-token".to_string()]);
+        cmd.add_secret_environment_variable("API_TOKEN".to_string(), "s3cr3t-token".to_string());
+        cmd.impact = Impact::Caution;
+        let values = HashMap::new();
+        let begin = |cmd: &Command, clearance: &Clearance, settings: &Settings| {
+            GuardedRun::begin(Uuid::new_v4(), cmd, &values, clearance, settings, &history)
+        };
+
+        assert!(matches!(begin(&cmd, &Clearance::Unconfirmed, &settings), Err(CommandArgusError::ConfirmationRequired(_))));
+        let confirmed = Clearance::from(Some("yes".to_string()));
+        settings.restricted_mode.enable("letmein").unwrap();
+        assert!(matches!(begin(&cmd, &confirmed, &settings), Err(CommandArgusError::AccessDenied(_))));
+        settings = Settings::default();
+
+        let run = begin(&cmd, &confirmed, &settings).unwrap();
+        let mut result = CommandExecutor::new().execute(&cmd);
+        let record = run.finish(&mut result);
+        assert_eq!(record.id, run.run_id());
+        assert_eq!(result.unwrap().stdout.trim(), "token=[REDACTED]");
+        assert_eq!(record.stdout.trim(), "token=[REDACTED]");
+        assert!(!record.command_line.contains("s3cr3t-token"));
+        history.record(record).unwrap();
+
+        // The recorded run counts against the limit
+        cmd.rate_limit = Some(RateLimit { max_runs: 1, per: RatePeriod::Hour });
+        assert!(begin(&cmd, &Clearance::Approved, &settings).is_err());
+
+        cmd.rate_limit = None;
+        cmd.requires_approval = true;
+        assert!(matches!(begin(&cmd, &confirmed, &settings), Err(CommandArgusError::AccessDenied(_))));
+        assert!(begin(&cmd, &Clearance::Approved, &settings).is_ok());
+    }
+}
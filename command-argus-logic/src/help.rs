@@ -0,0 +1,208 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command as ProcessCommand, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executable::find_executable;
+use crate::storage::data_dir;
+use crate::tools::Version;
+
+/// How long a program gets to print its help before it is killed.
+pub const HELP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Help longer than this is cut off.
+const MAX_HELP_BYTES: usize = 256 * 1024;
+
+/// Where a help text came from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HelpSource {
+    /// `<program> --help`
+    Help,
+    /// `man -P cat <program>`
+    Man,
+}
+
+/// The help of a program, as shown beside the command editor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HelpText {
+    /// The resolved executable.
+    pub program: String,
+    /// The first version number in the help, if it mentions one.
+    pub version: Option<String>,
+    pub source: HelpSource,
+    pub text: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Help texts cached on disk. An entry is keyed by the executable's path, size and
+/// modification time, so upgrading a program fetches its help again.
+pub struct HelpCache {
+    dir: PathBuf,
+}
+
+impl HelpCache {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_dir(data_dir()?.join("help")))
+    }
+
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The help of the program `command` runs, from the cache unless `refresh` is set.
+    /// The program is run directly with `--help`, no shell, no stdin and a scratch
+    /// working directory; when that prints nothing, `man` is tried on unix.
+    pub fn fetch(&self, command: &Command, refresh: bool) -> Result<HelpText> {
+        let program = command.pinned_executable.as_deref().unwrap_or(&command.command);
+        if program.contains('{') || program.contains('$') {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "'{}' depends on a parameter; fill it in before asking for help",
+                program
+            )));
+        }
+        let path = find_executable(program)
+            .and_then(|path| path.canonicalize().ok())
+            .ok_or_else(|| CommandArgusError::InvalidPath(format!("'{}' was not found in PATH", program)))?;
+
+        let cache_file = self.dir.join(cache_key(&path)?);
+        if !refresh {
+            if let Some(cached) = fs::read_to_string(&cache_file).ok().and_then(|json| serde_json::from_str(&json).ok()) {
+                return Ok(cached);
+            }
+        }
+
+        let mut found = run_quietly(&path, &["--help"]).map(|text| (HelpSource::Help, text));
+        if found.is_none() && cfg!(unix) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            found = run_quietly(Path::new("man"), &["-P", "cat", &name]).map(|text| (HelpSource::Man, strip_overstrike(&text)));
+        }
+        let (source, text) = found
+            .ok_or_else(|| CommandArgusError::InvalidCommand(format!("'{}' printed no help", program)))?;
+
+        let help = HelpText {
+            program: path.to_string_lossy().to_string(),
+            version: Version::find_in(&text).map(|v| v.to_string()),
+            source,
+            text,
+            fetched_at: Utc::now(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&cache_file, serde_json::to_string(&help)?)?;
+        Ok(help)
+    }
+}
+
+fn cache_key(path: &Path) -> Result<String> {
+    let metadata = path.metadata()?;
+    let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    (path, metadata.len(), modified.as_secs()).hash(&mut hasher);
+    let name: String = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Ok(format!("{}-{:016x}.json", name, hasher.finish()))
+}
+
+/// What `program` prints, stdout or else stderr since many tools print their usage
+/// there, or `None` when it prints nothing or doesn't finish within [`HELP_TIMEOUT`].
+fn run_quietly(program: &Path, args: &[&str]) -> Option<String> {
+    let mut child = ProcessCommand::new(program)
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .env("PAGER", "cat")
+        .env("MANPAGER", "cat")
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(pipe) = pipe {
+                let _ = pipe.take(MAX_HELP_BYTES as u64).read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    };
+    let stdout = read(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = read(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + HELP_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    let stdout = stdout.join().unwrap_or_default();
+    let bytes = if stdout.iter().all(u8::is_ascii_whitespace) { stderr.join().unwrap_or_default() } else { stdout };
+    let text = String::from_utf8_lossy(&bytes).trim_end().to_string();
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Removes the backspace overstrikes `man` uses for bold and underline.
+fn strip_overstrike(text: &str) -> String {
+    let mut out: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            out.pop();
+        } else {
+            out.push(c);
+        }
+    }
+    out.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fetch_help_caches_per_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let tool = temp.path().join("tool");
+        fs::write(&tool, "#!/bin/sh\necho \"usage: tool [-v] FILE (tool 2.4.1)\" >&2\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+        let command = Command::new("Tool".to_string(), tool.to_string_lossy().to_string());
+
+        let cache = HelpCache::with_dir(temp.path().join("help"));
+        let help = cache.fetch(&command, false).unwrap();
+        assert_eq!(help.source, HelpSource::Help);
+        assert_eq!(help.text, "usage: tool [-v] FILE (tool 2.4.1)");
+        assert_eq!(help.version.as_deref(), Some("2.4.1"));
+
+        let cached = cache.fetch(&command, false).unwrap();
+        assert_eq!(cached.fetched_at, help.fetched_at);
+        assert!(cache.fetch(&command, true).unwrap().fetched_at >= help.fetched_at);
+
+        let templated = Command::new("Tool".to_string(), "{tool}".to_string());
+        assert!(cache.fetch(&templated, false).is_err());
+        assert_eq!(strip_overstrike("N\u{8}NA\u{8}AME"), "NAME");
+    }
+}
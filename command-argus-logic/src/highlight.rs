@@ -0,0 +1,165 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandArgusError, Result};
+use crate::history::OutputStream;
+
+/// Spans beyond this many are left out, so a log full of warnings stays cheap to show.
+pub const MAX_HIGHLIGHTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Success,
+}
+
+/// Output matching `pattern` is shown with the color of its severity, or `color` if
+/// set, e.g. `#d97706`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HighlightRule {
+    pub pattern: String,
+    pub severity: Severity,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl HighlightRule {
+    fn new(pattern: &str, severity: Severity) -> Self {
+        Self { pattern: pattern.to_string(), severity, color: None }
+    }
+}
+
+/// Rules for the messages of common compilers and test runners: rustc and cargo, tsc,
+/// gcc and clang, Go, Jest, pytest and Python tracebacks.
+pub fn default_highlight_rules() -> Vec<HighlightRule> {
+    vec![
+        HighlightRule::new(r"(?i)^\s*(?:fatal )?error(?:\[\w+\])?:.*", Severity::Error),
+        HighlightRule::new(r"\berror TS\d+:.*", Severity::Error),
+        HighlightRule::new(r":\d+:\d+: (?:fatal )?error:.*", Severity::Error),
+        HighlightRule::new(r"^\s*(?:FAIL|--- FAIL:)\s.*", Severity::Error),
+        HighlightRule::new(r"^E\s{3}.*", Severity::Error),
+        HighlightRule::new(r"\bpanicked at\b.*", Severity::Error),
+        HighlightRule::new(r"^Traceback \(most recent call last\):", Severity::Error),
+        HighlightRule::new(r"\b[1-9]\d* failed\b", Severity::Error),
+        HighlightRule::new(r"(?i)^\s*warning(?:\[\w+\])?:.*", Severity::Warning),
+        HighlightRule::new(r":\d+:\d+: warning:.*", Severity::Warning),
+        HighlightRule::new(r"\b\w*DeprecationWarning\b.*", Severity::Warning),
+        HighlightRule::new(r"^\s*(?:PASS|--- PASS:)\s.*", Severity::Success),
+        HighlightRule::new(r"\btest result: ok\..*", Severity::Success),
+        HighlightRule::new(r"\b\d+ passed\b", Severity::Success),
+    ]
+}
+
+/// A span of output matched by a highlight rule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Highlight {
+    pub stream: OutputStream,
+    /// 1-based line of the output.
+    pub line: usize,
+    /// Columns of the span in the line, counted in UTF-16 code units as the GUI
+    /// indexes strings, end exclusive.
+    pub start: usize,
+    pub end: usize,
+    pub severity: Severity,
+    pub color: Option<String>,
+}
+
+/// Applies highlight rules to captured output.
+pub struct Highlighter {
+    rules: Vec<(Regex, HighlightRule)>,
+}
+
+impl Highlighter {
+    /// Fails on the first rule whose pattern is not a valid regex.
+    pub fn new(rules: &[HighlightRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|regex| (regex, rule.clone()))
+                    .map_err(|e| {
+                        CommandArgusError::InvalidQuery(format!("Invalid highlight pattern '{}': {}", rule.pattern, e))
+                    })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The spans of `text` matched by the rules, by line and column. Where matches
+    /// overlap the earlier rule wins.
+    pub fn highlight(&self, text: &str, stream: OutputStream) -> Vec<Highlight> {
+        let mut highlights = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let mut spans: Vec<(usize, usize, &HighlightRule)> = Vec::new();
+            for (regex, rule) in &self.rules {
+                for found in regex.find_iter(line).filter(|found| !found.is_empty()) {
+                    if !spans.iter().any(|(start, end, _)| found.start() < *end && *start < found.end()) {
+                        spans.push((found.start(), found.end(), rule));
+                    }
+                }
+            }
+            spans.sort_by_key(|(start, ..)| *start);
+            for (start, end, rule) in spans {
+                if highlights.len() == MAX_HIGHLIGHTS {
+                    return highlights;
+                }
+                highlights.push(Highlight {
+                    stream,
+                    line: index + 1,
+                    start: utf16_len(&line[..start]),
+                    end: utf16_len(&line[..end]),
+                    severity: rule.severity,
+                    color: rule.color.clone(),
+                });
+            }
+        }
+        highlights
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_output() {
+        let highlighter = Highlighter::new(&default_highlight_rules()).unwrap();
+        let output = "   Compiling app v0.1.0\n\
+                      warning: unused variable: `x`\n\
+                      error[E0308]: mismatched types\n\
+                      test result: ok. 3 passed; 0 failed\n\
+                      → FAIL src/app.test.ts\n";
+        let found: Vec<(usize, usize, usize, Severity)> = highlighter
+            .highlight(output, OutputStream::Stderr)
+            .iter()
+            .map(|h| (h.line, h.start, h.end, h.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, 0, 29, Severity::Warning),
+                (3, 0, 30, Severity::Error),
+                (4, 0, 35, Severity::Success),
+            ]
+        );
+
+        let custom = Highlighter::new(&[HighlightRule {
+            pattern: "FAIL".to_string(),
+            severity: Severity::Info,
+            color: Some("#7c3aed".to_string()),
+        }])
+        .unwrap();
+        let highlights = custom.highlight("→ FAIL src/app.test.ts", OutputStream::Stdout);
+        assert_eq!((highlights[0].start, highlights[0].end), (2, 6));
+        assert_eq!(highlights[0].color.as_deref(), Some("#7c3aed"));
+
+        assert!(Highlighter::new(&[HighlightRule::new("(", Severity::Error)]).is_err());
+    }
+}
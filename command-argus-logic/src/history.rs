@@ -0,0 +1,859 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::artifacts::Artifact;
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executor::ExecutionResult;
+use crate::query::tokenize;
+use crate::shutdown::Interruption;
+use crate::tail::OutputSpool;
+use crate::timeline::Phase;
+use crate::storage::{data_dir, CommandStorage};
+
+/// One finished execution of a command. Output is stored already scrubbed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionRecord {
+    pub id: Uuid,
+    pub command_id: Uuid,
+    pub command_name: String,
+    /// The command line as it was run, with placeholders substituted.
+    pub command_line: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// `None` when the process could not be started or was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the command could not be run, if it never started, or how a quit of the app
+    /// interrupted it.
+    pub error: Option<String>,
+    /// Streams stored compressed next to the history file. Their text is left out of
+    /// `stdout`/`stderr` and is read back with `HistoryStorage::get` or `get_output`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compressed_outputs: Vec<CompressedOutput>,
+    /// Files collected after the run, kept in `HistoryStorage::artifacts_dir`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Artifact>,
+    /// The input the run was given, for commands that record it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    /// Set for runs a quit of the app cut short or left behind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupted: Option<Interruption>,
+    /// The values the command's parameters were given; secret ones are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, String>,
+    /// Added afterwards with `HistoryStorage::annotate`, like "the run that fixed prod".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The phases the command's phase markers started.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<Phase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompressedOutput {
+    pub stream: OutputStream,
+    /// Size of the output before compression.
+    pub bytes: usize,
+    pub lines: usize,
+    pub compressed_bytes: u64,
+}
+
+/// Outputs larger than this are compressed when recorded.
+pub const COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// Whether a command still works, judged from its latest `HEALTH_WINDOW` runs.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandHealth {
+    /// Every recent run succeeded.
+    Healthy,
+    /// Some recent runs failed.
+    Flaky,
+    /// The last `BROKEN_STREAK` runs failed, or every run so far when there were fewer.
+    Broken,
+    /// The command was never run.
+    #[default]
+    Unknown,
+}
+
+pub const HEALTH_WINDOW: usize = 10;
+pub const BROKEN_STREAK: usize = 3;
+
+impl CommandHealth {
+    /// Health from the outcomes of a command's runs, newest first.
+    pub fn from_outcomes(outcomes: impl IntoIterator<Item = bool>) -> Self {
+        let outcomes: Vec<bool> = outcomes.into_iter().take(HEALTH_WINDOW).collect();
+        if outcomes.is_empty() {
+            CommandHealth::Unknown
+        } else if outcomes.iter().take(BROKEN_STREAK).all(|success| !success) {
+            CommandHealth::Broken
+        } else if outcomes.iter().all(|success| *success) {
+            CommandHealth::Healthy
+        } else {
+            CommandHealth::Flaky
+        }
+    }
+}
+
+/// How many trailing lines of stderr a `RecentFailure` shows.
+pub const STDERR_SNIPPET_LINES: usize = 5;
+
+/// A saved command whose most recent execution failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentFailure {
+    pub command: Command,
+    pub execution: ExecutionSummary,
+    /// The last lines of stderr, or why the command could not be started.
+    pub stderr_snippet: String,
+}
+
+impl ExecutionRecord {
+    pub fn from_result(command: &Command, started_at: DateTime<Utc>, result: &ExecutionResult) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            command_id: command.id,
+            command_name: command.name.clone(),
+            command_line: command.full_command(),
+            started_at,
+            finished_at: Utc::now(),
+            exit_code: Some(result.exit_code),
+            success: result.success,
+            stdout: result.stdout.clone(),
+            stderr: result.stderr.clone(),
+            error: result.interrupted.map(|interruption| interruption.describe().to_string()),
+            compressed_outputs: Vec::new(),
+            artifacts: result.artifacts.clone(),
+            stdin: result.stdin.clone(),
+            interrupted: result.interrupted,
+            parameters: BTreeMap::new(),
+            note: None,
+            tags: Vec::new(),
+            phases: result.phases.clone(),
+        }
+    }
+
+    pub fn from_error(command: &Command, started_at: DateTime<Utc>, error: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            command_id: command.id,
+            command_name: command.name.clone(),
+            command_line: command.full_command(),
+            started_at,
+            finished_at: Utc::now(),
+            exit_code: None,
+            success: false,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(error),
+            compressed_outputs: Vec::new(),
+            artifacts: Vec::new(),
+            stdin: None,
+            interrupted: None,
+            parameters: BTreeMap::new(),
+            note: None,
+            tags: Vec::new(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Keeps the `values` of `command`'s parameters with the record, leaving out secret
+    /// parameters and values for names the command doesn't have.
+    pub fn with_parameters(mut self, command: &Command, values: &HashMap<String, String>) -> Self {
+        self.parameters = command
+            .parameters
+            .iter()
+            .filter(|parameter| !parameter.secret)
+            .filter_map(|parameter| Some((parameter.name.clone(), values.get(&parameter.name)?.clone())))
+            .collect();
+        self
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        (self.finished_at - self.started_at).num_milliseconds()
+    }
+
+    pub fn summary(&self) -> ExecutionSummary {
+        let size = |stream: OutputStream| match self.compressed(stream) {
+            Some(compressed) => (compressed.lines, compressed.bytes),
+            None => (line_count(self.output(stream)), self.output(stream).len()),
+        };
+        let (stdout_lines, stdout_bytes) = size(OutputStream::Stdout);
+        let (stderr_lines, stderr_bytes) = size(OutputStream::Stderr);
+        ExecutionSummary {
+            id: self.id,
+            command_id: self.command_id,
+            command_name: self.command_name.clone(),
+            command_line: self.command_line.clone(),
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            exit_code: self.exit_code,
+            success: self.success,
+            error: self.error.clone(),
+            interrupted: self.interrupted,
+            parameters: self.parameters.clone(),
+            note: self.note.clone(),
+            tags: self.tags.clone(),
+            phases: self.phases.clone(),
+            stdout_lines,
+            stderr_lines,
+            stdout_bytes,
+            stderr_bytes,
+        }
+    }
+
+    pub fn compressed(&self, stream: OutputStream) -> Option<&CompressedOutput> {
+        self.compressed_outputs.iter().find(|c| c.stream == stream)
+    }
+
+    pub fn output(&self, stream: OutputStream) -> &str {
+        match stream {
+            OutputStream::Stdout => &self.stdout,
+            OutputStream::Stderr => &self.stderr,
+        }
+    }
+
+    fn matches_term(&self, term: &str) -> bool {
+        match term.strip_prefix("tag:") {
+            Some(tag) => self.tags.iter().any(|t| t.to_lowercase() == tag),
+            None => [Some(&self.command_name), Some(&self.command_line), self.note.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(term)),
+        }
+    }
+
+    fn output_mut(&mut self, stream: OutputStream) -> &mut String {
+        match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        }
+    }
+}
+
+/// An execution without its output, but with the output's size, so a list of runs
+/// stays small however much they printed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionSummary {
+    pub id: Uuid,
+    pub command_id: Uuid,
+    pub command_name: String,
+    pub command_line: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub interrupted: Option<Interruption>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<Phase>,
+    pub stdout_lines: usize,
+    pub stderr_lines: usize,
+    pub stdout_bytes: usize,
+    pub stderr_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub const ALL: [OutputStream; 2] = [OutputStream::Stdout, OutputStream::Stderr];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Disk usage of the execution history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HistoryStats {
+    pub records: usize,
+    /// Size of the history file, including outputs stored inline.
+    pub history_file_bytes: u64,
+    pub compressed_outputs: usize,
+    /// Size of the compressed output files on disk.
+    pub compressed_bytes: u64,
+    /// What the compressed outputs would take up uncompressed.
+    pub uncompressed_bytes: u64,
+}
+
+/// A range of lines of an output, for views that load it as it is scrolled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutputChunk {
+    /// Index of the first line in `lines`.
+    pub offset: usize,
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+}
+
+impl OutputChunk {
+    /// Up to `limit` lines of `text`, starting at line `offset`.
+    pub fn from_text(text: &str, offset: usize, limit: usize) -> Self {
+        Self {
+            offset,
+            lines: text.lines().skip(offset).take(limit).map(String::from).collect(),
+            total_lines: line_count(text),
+        }
+    }
+
+    pub fn has_more(&self) -> bool {
+        self.offset + self.lines.len() < self.total_lines
+    }
+}
+
+/// Number of lines in `text`; a trailing newline does not start another line.
+pub fn line_count(text: &str) -> usize {
+    text.lines().count()
+}
+
+/// Execution history, persisted as `history.json` in the data directory. Outputs over
+/// `COMPRESSION_THRESHOLD` are gzipped into `history-outputs/` next to it, and collected
+/// artifacts are kept in `history-artifacts/<run id>/`. Runs still going spill large
+/// outputs into `history-live/`.
+pub struct HistoryStorage {
+    path: PathBuf,
+    outputs_dir: PathBuf,
+    artifacts_root: PathBuf,
+    live_dir: PathBuf,
+}
+
+impl HistoryStorage {
+    pub fn new() -> Result<Self> {
+        Self::with_path(data_dir()?.join("history.json"))
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        fs::create_dir_all(&parent)?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let outputs_dir = parent.join(format!("{}-outputs", stem));
+        let artifacts_root = parent.join(format!("{}-artifacts", stem));
+        let live_dir = parent.join(format!("{}-live", stem));
+        Ok(Self { path, outputs_dir, artifacts_root, live_dir })
+    }
+
+    /// A spool for the output of the run `id` while it runs, to be passed to
+    /// `CommandExecutor::with_output_listener`.
+    pub fn output_spool(&self, id: Uuid) -> OutputSpool {
+        OutputSpool::new(self.live_dir.clone(), id)
+    }
+
+    /// Where the artifacts of the run `id` are kept. Runs are recorded with the id the
+    /// executor was given, so this is passed to `CommandExecutor::with_artifacts_dir`
+    /// before the run.
+    pub fn artifacts_dir(&self, id: Uuid) -> PathBuf {
+        self.artifacts_root.join(id.to_string())
+    }
+
+    /// Stores `record`, compressing large outputs. Returns it as passed in.
+    pub fn record(&self, record: ExecutionRecord) -> Result<ExecutionRecord> {
+        let mut stored = record.clone();
+        for stream in OutputStream::ALL {
+            let output = record.output(stream);
+            if output.len() > COMPRESSION_THRESHOLD {
+                let compressed_bytes = self.write_compressed(record.id, stream, output)?;
+                stored.compressed_outputs.push(CompressedOutput {
+                    stream,
+                    bytes: output.len(),
+                    lines: line_count(output),
+                    compressed_bytes,
+                });
+                stored.output_mut(stream).clear();
+            }
+        }
+
+        let mut records = self.load_all()?;
+        records.push(stored);
+        self.save_all(&records)?;
+        Ok(record)
+    }
+
+    /// The record with its outputs, decompressing them if needed.
+    pub fn get(&self, id: Uuid) -> Result<ExecutionRecord> {
+        let mut record = self
+            .load_all()?
+            .into_iter()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        for compressed in std::mem::take(&mut record.compressed_outputs) {
+            let mut output = String::new();
+            self.read_compressed(id, compressed.stream)?.read_to_string(&mut output)?;
+            *record.output_mut(compressed.stream) = output;
+        }
+        Ok(record)
+    }
+
+    /// Lines `offset..offset + limit` of one stream of a recorded execution. Compressed
+    /// outputs are decompressed only as far as needed.
+    pub fn get_output(&self, id: Uuid, stream: OutputStream, offset: usize, limit: usize) -> Result<OutputChunk> {
+        let record = self
+            .load_all()?
+            .into_iter()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        self.output_range(&record, stream, offset, limit)
+    }
+
+    /// The last `lines` lines of one stream of a recorded execution.
+    pub fn tail_output(&self, id: Uuid, stream: OutputStream, lines: usize) -> Result<OutputChunk> {
+        let record = self
+            .load_all()?
+            .into_iter()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        let total_lines = match record.compressed(stream) {
+            Some(compressed) => compressed.lines,
+            None => line_count(record.output(stream)),
+        };
+        self.output_range(&record, stream, total_lines.saturating_sub(lines), lines)
+    }
+
+    /// Writes one whole stream of a recorded execution to `path`, decompressing it on the
+    /// way rather than reading it into memory. Returns the number of bytes written.
+    pub fn save_output(&self, id: Uuid, stream: OutputStream, path: &Path) -> Result<u64> {
+        let record = self
+            .load_all()?
+            .into_iter()
+            .find(|r| r.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        let mut file = File::create(path)?;
+        let written = match record.compressed(stream) {
+            Some(_) => std::io::copy(&mut self.read_compressed(id, stream)?, &mut file)?,
+            None => {
+                file.write_all(record.output(stream).as_bytes())?;
+                record.output(stream).len() as u64
+            }
+        };
+        file.flush()?;
+        Ok(written)
+    }
+
+    /// Commands in `commands` whose latest execution failed, most recent failure first.
+    /// Commands that were deleted since are left out.
+    pub fn list_recent_failures(&self, commands: &CommandStorage, limit: usize) -> Result<Vec<RecentFailure>> {
+        let saved = commands.list()?;
+        let mut seen = std::collections::HashSet::new();
+        let mut failures = Vec::new();
+        for record in self.load_all()?.into_iter().rev() {
+            if failures.len() == limit {
+                break;
+            }
+            if !seen.insert(record.command_id) || record.success {
+                continue;
+            }
+            let Some(command) = saved.iter().find(|c| c.id == record.command_id) else {
+                continue;
+            };
+            let execution = record.summary();
+            let stderr_snippet = match &record.error {
+                Some(error) => error.clone(),
+                None => {
+                    let offset = execution.stderr_lines.saturating_sub(STDERR_SNIPPET_LINES);
+                    self.output_range(&record, OutputStream::Stderr, offset, STDERR_SNIPPET_LINES)?
+                        .lines
+                        .join("\n")
+                }
+            };
+            failures.push(RecentFailure {
+                command: command.clone(),
+                execution,
+                stderr_snippet,
+            });
+        }
+        Ok(failures)
+    }
+
+    fn output_range(&self, record: &ExecutionRecord, stream: OutputStream, offset: usize, limit: usize) -> Result<OutputChunk> {
+        let Some(compressed) = record.compressed(stream) else {
+            return Ok(OutputChunk::from_text(record.output(stream), offset, limit));
+        };
+        let lines = self
+            .read_compressed(record.id, stream)?
+            .lines()
+            .skip(offset)
+            .take(limit)
+            .collect::<std::io::Result<Vec<String>>>()?;
+        Ok(OutputChunk {
+            offset,
+            lines,
+            total_lines: compressed.lines,
+        })
+    }
+
+    pub fn stats(&self) -> Result<HistoryStats> {
+        let records = self.load_all()?;
+        let mut stats = HistoryStats {
+            records: records.len(),
+            history_file_bytes: fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0),
+            ..HistoryStats::default()
+        };
+        for compressed in records.iter().flat_map(|r| &r.compressed_outputs) {
+            stats.compressed_outputs += 1;
+            stats.compressed_bytes += compressed.compressed_bytes;
+            stats.uncompressed_bytes += compressed.bytes as u64;
+        }
+        Ok(stats)
+    }
+
+    /// Records in chronological order, oldest first. Compressed outputs are left out;
+    /// use `get` or `get_output` to read them.
+    pub fn list(&self) -> Result<Vec<ExecutionRecord>> {
+        self.load_all()
+    }
+
+    /// The most recent executions of one command, newest first. Like `list`, without
+    /// compressed outputs.
+    pub fn list_for_command(&self, command_id: Uuid, limit: usize) -> Result<Vec<ExecutionRecord>> {
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .rev()
+            .filter(|r| r.command_id == command_id)
+            .take(limit)
+            .collect())
+    }
+
+    /// Sets the note and tags of a recorded execution, replacing earlier ones. A blank
+    /// note removes it; tags are trimmed and kept once each.
+    pub fn annotate(&self, id: Uuid, note: Option<String>, tags: Vec<String>) -> Result<ExecutionRecord> {
+        let mut records = self.load_all()?;
+        let record = records.iter_mut().find(|r| r.id == id).ok_or(CommandArgusError::NotFound(id))?;
+        record.note = note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty());
+        record.tags.clear();
+        for tag in tags {
+            let tag = tag.trim().to_string();
+            if !tag.is_empty() && !record.tags.contains(&tag) {
+                record.tags.push(tag);
+            }
+        }
+        let annotated = record.clone();
+        self.save_all(&records)?;
+        Ok(annotated)
+    }
+
+    /// Records matching `query`, newest first, without compressed outputs. Words of the
+    /// query are looked for in the command name, the command line and the note, `tag:x`
+    /// requires the tag `x`; every term has to match, case-insensitively. Values with
+    /// spaces are quoted, as in `"fixed prod"`.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<ExecutionRecord>> {
+        let terms: Vec<String> = tokenize(query)?.into_iter().map(|(term, _)| term.to_lowercase()).collect();
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .rev()
+            .filter(|record| terms.iter().all(|term| record.matches_term(term)))
+            .take(limit)
+            .collect())
+    }
+
+    /// The health of every command that was run. Commands missing from the map are
+    /// `CommandHealth::Unknown`.
+    pub fn health_by_command(&self) -> Result<HashMap<Uuid, CommandHealth>> {
+        let mut outcomes: HashMap<Uuid, Vec<bool>> = HashMap::new();
+        for record in self.load_all()?.into_iter().rev() {
+            outcomes.entry(record.command_id).or_default().push(record.success);
+        }
+        Ok(outcomes
+            .into_iter()
+            .map(|(id, outcomes)| (id, CommandHealth::from_outcomes(outcomes)))
+            .collect())
+    }
+
+    /// Keeps only the newest `keep` records and returns how many were removed.
+    pub fn prune(&self, keep: usize) -> Result<usize> {
+        let mut records = self.load_all()?;
+        let removed = records.len().saturating_sub(keep);
+        let pruned: Vec<ExecutionRecord> = records.drain(..removed).collect();
+        self.save_all(&records)?;
+        for record in &pruned {
+            for compressed in &record.compressed_outputs {
+                let _ = fs::remove_file(self.output_path(record.id, compressed.stream));
+            }
+            if !record.artifacts.is_empty() {
+                let _ = fs::remove_dir_all(self.artifacts_dir(record.id));
+            }
+        }
+        Ok(removed)
+    }
+
+    fn output_path(&self, id: Uuid, stream: OutputStream) -> PathBuf {
+        self.outputs_dir.join(format!("{}.{}.gz", id, stream.name()))
+    }
+
+    // Returns the size of the written file
+    fn write_compressed(&self, id: Uuid, stream: OutputStream, output: &str) -> Result<u64> {
+        fs::create_dir_all(&self.outputs_dir)?;
+        let path = self.output_path(id, stream);
+        let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+        encoder.write_all(output.as_bytes())?;
+        encoder.finish()?;
+        Ok(fs::metadata(&path)?.len())
+    }
+
+    fn read_compressed(&self, id: Uuid, stream: OutputStream) -> Result<impl BufRead> {
+        Ok(BufReader::new(GzDecoder::new(File::open(self.output_path(id, stream))?)))
+    }
+
+    fn load_all(&self) -> Result<Vec<ExecutionRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_all(&self, records: &[ExecutionRecord]) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(records)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use tempfile::TempDir;
+
+    fn temp_history() -> (HistoryStorage, TempDir) {
+        let temp = TempDir::new().unwrap();
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+        (history, temp)
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let (history, _temp) = temp_history();
+        let echo = Command::new("Echo".to_string(), "echo".to_string());
+        let ls = Command::new("Ls".to_string(), "ls".to_string());
+
+        let first = history
+            .record(ExecutionRecord::from_error(&echo, Utc::now(), "boom".to_string()))
+            .unwrap();
+        history.record(ExecutionRecord::from_error(&ls, Utc::now(), "boom".to_string())).unwrap();
+        let third = history
+            .record(ExecutionRecord::from_error(&echo, Utc::now(), "boom".to_string()))
+            .unwrap();
+
+        assert_eq!(history.get(first.id).unwrap().command_name, "Echo");
+        let recent: Vec<Uuid> = history.list_for_command(echo.id, 10).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(recent, vec![third.id, first.id]);
+        assert_eq!(history.list_for_command(echo.id, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_annotate_and_search() {
+        let (history, _temp) = temp_history();
+        let deploy = Command::new("Deploy".to_string(), "deploy".to_string());
+        let first = history.record(ExecutionRecord::from_error(&deploy, Utc::now(), String::new())).unwrap();
+        let second = history.record(ExecutionRecord::from_error(&deploy, Utc::now(), String::new())).unwrap();
+
+        let annotated = history
+            .annotate(first.id, Some(" This is the run that fixed prod ".to_string()), vec!["prod".to_string(), " prod".to_string(), String::new()])
+            .unwrap();
+        assert_eq!(annotated.note.as_deref(), Some("This is the run that fixed prod"));
+        assert_eq!(annotated.tags, vec!["prod"]);
+        assert_eq!(history.get(first.id).unwrap().summary().tags, vec!["prod"]);
+
+        let ids = |query: &str| -> Vec<Uuid> { history.search(query, 10).unwrap().iter().map(|r| r.id).collect() };
+        assert_eq!(ids("deploy"), vec![second.id, first.id]);
+        assert_eq!(ids("tag:PROD \"fixed prod\""), vec![first.id]);
+        assert!(ids("tag:staging").is_empty());
+        assert!(history.annotate(Uuid::new_v4(), None, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_ranged_output() {
+        let (history, _temp) = temp_history();
+        let echo = Command::new("Echo".to_string(), "echo".to_string());
+        let mut record = ExecutionRecord::from_error(&echo, Utc::now(), String::new());
+        record.stdout = (0..10).map(|i| format!("line {}\n", i)).collect();
+        let record = history.record(record).unwrap();
+
+        let summary = record.summary();
+        assert_eq!((summary.stdout_lines, summary.stderr_lines), (10, 0));
+        assert_eq!(summary.stdout_bytes, record.stdout.len());
+
+        let chunk = history.get_output(record.id, OutputStream::Stdout, 8, 5).unwrap();
+        assert_eq!(chunk.lines, vec!["line 8", "line 9"]);
+        assert_eq!(chunk.total_lines, 10);
+        assert!(!chunk.has_more());
+        assert!(history.get_output(record.id, OutputStream::Stdout, 0, 5).unwrap().has_more());
+        assert!(history.get_output(record.id, OutputStream::Stderr, 0, 5).unwrap().lines.is_empty());
+    }
+
+    #[test]
+    fn test_large_outputs_are_compressed() {
+        let (history, temp) = temp_history();
+        let echo = Command::new("Echo".to_string(), "echo".to_string());
+        let mut record = ExecutionRecord::from_error(&echo, Utc::now(), String::new());
+        record.stdout = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        record.stdout.push_str("no trailing newline\r\nat the end");
+        record.stderr = "small\n".to_string();
+        let record = history.record(record).unwrap();
+
+        // The history file only holds the metadata
+        let stored = &history.list().unwrap()[0];
+        assert!(stored.stdout.is_empty());
+        assert_eq!(stored.stderr, "small\n");
+        assert_eq!(stored.summary().stdout_lines, 20_002);
+        assert_eq!(stored.summary().stdout_bytes, record.stdout.len());
+        assert!(fs::metadata(temp.path().join("history.json")).unwrap().len() < 10_000);
+
+        assert_eq!(history.get(record.id).unwrap(), record);
+        let chunk = history.get_output(record.id, OutputStream::Stdout, 19_998, 10).unwrap();
+        assert_eq!(chunk.lines, vec!["line 19998", "line 19999", "no trailing newline", "at the end"]);
+        assert_eq!(chunk.total_lines, 20_002);
+        let tail = history.tail_output(record.id, OutputStream::Stdout, 2).unwrap();
+        assert_eq!((tail.offset, tail.lines.len()), (20_000, 2));
+
+        let saved = temp.path().join("saved.txt");
+        for stream in OutputStream::ALL {
+            let written = history.save_output(record.id, stream, &saved).unwrap();
+            assert_eq!(written as usize, record.output(stream).len());
+            assert_eq!(fs::read_to_string(&saved).unwrap(), record.output(stream));
+        }
+
+        let stats = history.stats().unwrap();
+        assert_eq!((stats.records, stats.compressed_outputs), (1, 1));
+        assert!(stats.compressed_bytes < stats.uncompressed_bytes / 4);
+
+        history.prune(0).unwrap();
+        assert!(!temp.path().join("history-outputs").join(format!("{}.stdout.gz", record.id)).exists());
+    }
+
+    #[test]
+    fn test_list_recent_failures() {
+        let (history, temp) = temp_history();
+        let commands = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let build = commands.create(EditAccess::unrestricted(), Command::new("Build".to_string(), "make".to_string())).unwrap();
+        let test = commands.create(EditAccess::unrestricted(), Command::new("Test".to_string(), "make".to_string())).unwrap();
+        let deploy = commands.create(EditAccess::unrestricted(), Command::new("Deploy".to_string(), "deploy".to_string())).unwrap();
+        let deleted = Command::new("Gone".to_string(), "gone".to_string());
+
+        let run = |command: &Command, exit_code: i32, stderr: &str| {
+            let result = ExecutionResult {
+                stdout: String::new(),
+                stderr: stderr.to_string(),
+                exit_code,
+                success: exit_code == 0,
+                warnings: Vec::new(),
+                duration: std::time::Duration::ZERO,
+                assertion_results: Vec::new(),
+                artifacts: Vec::new(),
+                stdin: None,
+                working_directory: None,
+                problems: Vec::new(),
+                interrupted: None,
+                timeline: Vec::new(),
+                phases: Vec::new(),
+            };
+            history.record(ExecutionRecord::from_result(command, Utc::now(), &result)).unwrap();
+        };
+        run(&build, 2, "old failure");
+        run(&build, 0, "");
+        run(&test, 0, "");
+        run(&test, 1, &(1..=8).map(|i| format!("error {}\n", i)).collect::<String>());
+        history.record(ExecutionRecord::from_error(&deploy, Utc::now(), "deploy: not found".to_string())).unwrap();
+        run(&deleted, 1, "");
+
+        let failures = history.list_recent_failures(&commands, 10).unwrap();
+        let names: Vec<&str> = failures.iter().map(|f| f.command.name.as_str()).collect();
+        assert_eq!(names, vec!["Deploy", "Test"]);
+        assert_eq!(failures[0].stderr_snippet, "deploy: not found");
+        assert_eq!(failures[1].execution.exit_code, Some(1));
+        assert_eq!(failures[1].stderr_snippet, "error 4\nerror 5\nerror 6\nerror 7\nerror 8");
+        assert_eq!(history.list_recent_failures(&commands, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_command_health() {
+        use CommandHealth::*;
+        assert_eq!(CommandHealth::from_outcomes([]), Unknown);
+        assert_eq!(CommandHealth::from_outcomes([true, true]), Healthy);
+        assert_eq!(CommandHealth::from_outcomes([false]), Broken);
+        assert_eq!(CommandHealth::from_outcomes([false, false, false, true]), Broken);
+        assert_eq!(CommandHealth::from_outcomes([false, false, true]), Flaky);
+        assert_eq!(CommandHealth::from_outcomes([true, false, true]), Flaky);
+        // Failures older than the window are forgotten
+        let outcomes = std::iter::repeat_n(true, HEALTH_WINDOW).chain([false]);
+        assert_eq!(CommandHealth::from_outcomes(outcomes), Healthy);
+
+        let (history, _temp) = temp_history();
+        let echo = Command::new("Echo".to_string(), "echo".to_string());
+        let ls = Command::new("Ls".to_string(), "ls".to_string());
+        history.record(ExecutionRecord::from_error(&echo, Utc::now(), String::new())).unwrap();
+        let health = history.health_by_command().unwrap();
+        assert_eq!(health.get(&echo.id), Some(&Broken));
+        assert_eq!(health.get(&ls.id), None);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let (history, _temp) = temp_history();
+        let echo = Command::new("Echo".to_string(), "echo".to_string());
+        for _ in 0..3 {
+            history.record(ExecutionRecord::from_error(&echo, Utc::now(), String::new())).unwrap();
+        }
+        let newest = history.list().unwrap()[2].id;
+        assert_eq!(history.prune(1).unwrap(), 2);
+        assert_eq!(history.list().unwrap()[0].id, newest);
+    }
+
+    #[test]
+    fn test_artifacts_are_kept_with_the_run() {
+        let (history, temp) = temp_history();
+        let work = temp.path().join("work");
+        fs::create_dir_all(work.join("reports/unit")).unwrap();
+        fs::write(work.join("reports/unit/junit.xml"), "<testsuites/>").unwrap();
+        fs::write(work.join("app.tar.gz"), "archive").unwrap();
+        fs::write(work.join("notes.txt"), "").unwrap();
+
+        let mut command = Command::new("Build".to_string(), "true".to_string())
+            .with_working_directory(work.to_string_lossy().to_string());
+        command.collect_artifacts = vec!["*.tar.gz".to_string(), "reports".to_string()];
+        let run_id = Uuid::new_v4();
+        let executor = crate::executor::CommandExecutor::new().with_artifacts_dir(history.artifacts_dir(run_id));
+        let result = executor.execute(&command).unwrap();
+        let paths: Vec<&str> = result.artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["app.tar.gz", "reports/unit/junit.xml"]);
+        assert_eq!(result.artifacts[0].bytes, 7);
+
+        let mut record = ExecutionRecord::from_result(&command, Utc::now(), &result);
+        record.id = run_id;
+        history.record(record).unwrap();
+        let stored = history.get(run_id).unwrap();
+        assert_eq!(stored.artifacts, result.artifacts);
+        assert!(history.artifacts_dir(run_id).join("reports/unit/junit.xml").exists());
+
+        history.record(ExecutionRecord::from_error(&command, Utc::now(), String::new())).unwrap();
+        history.prune(1).unwrap();
+        assert!(!history.artifacts_dir(run_id).exists());
+    }
+}
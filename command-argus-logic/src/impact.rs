@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+
+/// How much damage running a command can do, which decides how it must be confirmed.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Impact {
+    #[default]
+    Safe,
+    Caution,
+    Destructive,
+}
+
+/// What the user has to do before a command runs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Confirmation {
+    None,
+    /// Confirm with a click.
+    Click,
+    /// Type the command's name.
+    TypeName,
+}
+
+impl Impact {
+    pub fn confirmation(self) -> Confirmation {
+        match self {
+            Impact::Safe => Confirmation::None,
+            Impact::Caution => Confirmation::Click,
+            Impact::Destructive => Confirmation::TypeName,
+        }
+    }
+}
+
+/// Checks the confirmation sent along with a run request. `confirmation` is `None` when
+/// the user did not confirm; for `TypeName` it must be exactly the command's name.
+pub fn ensure_confirmed(command: &Command, confirmation: Option<&str>) -> Result<()> {
+    match (command.impact.confirmation(), confirmation) {
+        (Confirmation::None, _) | (Confirmation::Click, Some(_)) => Ok(()),
+        (Confirmation::TypeName, Some(typed)) if typed == command.name => Ok(()),
+        (Confirmation::Click, None) => Err(CommandArgusError::ConfirmationRequired(format!(
+            "'{}' must be confirmed before it runs",
+            command.name
+        ))),
+        (Confirmation::TypeName, _) => Err(CommandArgusError::ConfirmationRequired(format!(
+            "'{}' is destructive; type its name to confirm",
+            command.name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmation_tiers() {
+        let mut cmd = Command::new("Drop database".to_string(), "dropdb".to_string());
+        assert!(ensure_confirmed(&cmd, None).is_ok());
+
+        cmd.impact = Impact::Caution;
+        assert!(ensure_confirmed(&cmd, None).is_err());
+        assert!(ensure_confirmed(&cmd, Some("")).is_ok());
+
+        cmd.impact = Impact::Destructive;
+        assert!(ensure_confirmed(&cmd, Some("")).is_err());
+        assert!(ensure_confirmed(&cmd, Some("drop database")).is_err());
+        assert!(ensure_confirmed(&cmd, Some("Drop database")).is_ok());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Impact::Destructive > Impact::Caution && Impact::Caution > Impact::Safe);
+        assert_eq!(serde_json::to_string(&Impact::Destructive).unwrap(), "\"destructive\"");
+    }
+}
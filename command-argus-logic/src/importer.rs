@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::audit::current_user_name;
+use crate::command::{Command, CommandSource};
+use crate::error::{CommandArgusError, Result};
+use crate::problems::ProblemMatcher;
+
+/// Reads `<workspace>/.vscode/tasks.json` and converts its tasks into commands.
+///
+/// `${workspaceFolder}` is expanded to the workspace path and every command is tagged
+/// with the workspace directory name. Tasks without a command of their own (e.g.
+/// compound tasks made of `dependsOn`) are skipped. Problem matchers are kept when
+/// they are one of the built-in ones or have a single-line pattern of their own.
+pub fn import_vscode_tasks(workspace: &Path) -> Result<Vec<Command>> {
+    let tasks_path = workspace.join(".vscode").join("tasks.json");
+    if !tasks_path.exists() {
+        return Err(CommandArgusError::InvalidPath(tasks_path.display().to_string()));
+    }
+    let content = fs::read_to_string(&tasks_path)?;
+    parse_vscode_tasks(&content, workspace)
+}
+
+pub fn parse_vscode_tasks(content: &str, workspace: &Path) -> Result<Vec<Command>> {
+    let root: Value = serde_json::from_str(&strip_jsonc(content))?;
+    let workspace_name = workspace
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "workspace".to_string());
+    let expand = |value: &str| {
+        value
+            .replace("${workspaceFolder}", &workspace.to_string_lossy())
+            .replace("${workspaceFolderBasename}", &workspace_name)
+    };
+
+    let tasks = root
+        .get("tasks")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut commands = Vec::new();
+    for task in &tasks {
+        let Some(program) = task.get("command").and_then(string_value) else {
+            continue;
+        };
+        let label = task
+            .get("label")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| program.clone());
+        let args = task
+            .get("args")
+            .and_then(Value::as_array)
+            .map(|args| args.iter().filter_map(string_value).map(|a| expand(&a)).collect())
+            .unwrap_or_default();
+
+        let mut command = Command::new(label, expand(&program)).with_args(args);
+        if let Some(detail) = task.get("detail").and_then(Value::as_str) {
+            command = command.with_description(detail.to_string());
+        }
+
+        let options = task.get("options");
+        if let Some(cwd) = options.and_then(|o| o.get("cwd")).and_then(Value::as_str) {
+            command = command.with_working_directory(expand(cwd));
+        }
+        if let Some(env) = options.and_then(|o| o.get("env")).and_then(Value::as_object) {
+            for (key, value) in env {
+                if let Some(value) = value.as_str() {
+                    command.add_environment_variable(key.clone(), expand(value));
+                }
+            }
+        }
+
+        command.problem_matchers = task.get("problemMatcher").map(problem_matchers).unwrap_or_default();
+
+        command.add_tag(workspace_name.clone());
+        command.author = Some(current_user_name());
+        command.source = CommandSource::Imported {
+            from: workspace.join(".vscode").join("tasks.json").to_string_lossy().to_string(),
+        };
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+/// `command` and `args` entries may be plain strings or `{ "value": ..., "quoting": ... }`.
+fn string_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(map) => map.get("value").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// `problemMatcher` is a name such as `$tsc`, a matcher object, or a list of either.
+/// Multi-line patterns and named matchers from extensions have no counterpart.
+fn problem_matchers(value: &Value) -> Vec<ProblemMatcher> {
+    let matcher = |value: &Value| match value {
+        Value::String(name) => ProblemMatcher::builtin(name),
+        Value::Object(map) => {
+            let pattern = map.get("pattern")?;
+            let group = |key: &str| pattern.get(key).and_then(Value::as_u64).map(|i| i as usize);
+            Some(ProblemMatcher {
+                pattern: pattern.get("regexp")?.as_str()?.to_string(),
+                file: group("file").unwrap_or(1),
+                line: group("line"),
+                column: group("column"),
+                severity: group("severity"),
+                message: group("message")?,
+            })
+        }
+        _ => None,
+    };
+    match value {
+        Value::Array(values) => values.iter().filter_map(matcher).collect(),
+        value => matcher(value).into_iter().collect(),
+    }
+}
+
+/// Removes comments and trailing commas so that VS Code's JSONC can be parsed as JSON.
+fn strip_jsonc(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut out = String::with_capacity(content.len());
+    let mut i = 0;
+    let mut in_string = false;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+
+        match (c, chars.get(i + 1)) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            ('}', _) | (']', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.remove(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASKS: &str = r#"{
+        // See https://go.microsoft.com/fwlink/?LinkId=733558
+        "version": "2.0.0",
+        "tasks": [
+            {
+                "type": "shell",
+                "label": "Build GUI",
+                "command": "npm",
+                "args": ["run", { "value": "tauri build", "quoting": "strong" }],
+                "problemMatcher": ["$tsc"],
+                "options": {
+                    "cwd": "${workspaceFolder}/gui",
+                    "env": { "NODE_ENV": "production", },
+                },
+            },
+            /* compound task */
+            { "label": "All", "dependsOn": ["Build GUI"] },
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_tasks() {
+        let commands = parse_vscode_tasks(TASKS, Path::new("/work/my-app")).unwrap();
+        assert_eq!(commands.len(), 1);
+
+        let build = &commands[0];
+        assert_eq!(build.name, "Build GUI");
+        assert_eq!(build.command, "npm");
+        assert_eq!(build.args, vec!["run", "tauri build"]);
+        assert_eq!(build.working_directory.as_deref(), Some("/work/my-app/gui"));
+        assert_eq!(build.environment_variables[0].key, "NODE_ENV");
+        assert_eq!(build.tags, vec!["my-app"]);
+        assert_eq!(build.source.kind(), "imported");
+        assert!(build.author.is_some());
+        assert_eq!(build.problem_matchers, vec![ProblemMatcher::builtin("$tsc").unwrap()]);
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_strings() {
+        let stripped = strip_jsonc(r#"{"url": "http://x//y", "a": [1, 2,], /* c */ }"#);
+        let value: Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value["url"], "http://x//y");
+        assert_eq!(value["a"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_tasks_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(matches!(
+            import_vscode_tasks(temp.path()),
+            Err(CommandArgusError::InvalidPath(_))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::agent::{find_agent, AgentInfo};
+use crate::error::{CommandArgusError, Result};
+use crate::history::{ExecutionSummary, OutputStream};
+use crate::shutdown::RunningJob;
+
+/// Version of the messages below. The agent refuses clients of another version, so a
+/// GUI and an agent from different releases fail to connect rather than misunderstand
+/// each other. Messages may gain optional fields without a new version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What the GUI asks the background agent. Messages travel as one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// The first message of every connection, with the token from `agent.json`.
+    Hello { version: u32, token: String },
+    /// Run a saved command. The agent answers `Accepted`, then streams `Output` and
+    /// ends with `Finished`, or `Error` with the run's id.
+    Submit { request: RunRequest },
+    /// The commands the agent is running; answered with `Jobs`.
+    ListJobs,
+    /// A message of a newer protocol version, answered with `Error`.
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunRequest {
+    pub command_id: Uuid,
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+    #[serde(default)]
+    pub use_shell: bool,
+    /// The confirmation the command's impact asks for, as in `ensure_confirmed`.
+    #[serde(default)]
+    pub confirmation: Option<String>,
+}
+
+/// What the background agent sends back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentMessage {
+    Welcome { version: u32, agent: AgentInfo },
+    /// The client speaks another version or has the wrong token; the connection is closed.
+    Refused { version: u32, reason: String },
+    Accepted { run_id: Uuid },
+    /// A line of a run's output, secrets scrubbed.
+    Output { run_id: Uuid, stream: OutputStream, text: String },
+    /// The run ended and was recorded in the agent's history.
+    Finished { run_id: Uuid, summary: ExecutionSummary },
+    Jobs { jobs: Vec<RunningJob> },
+    /// A request failed; `run_id` is set when a submitted run could not be run.
+    Error {
+        message: String,
+        #[serde(default)]
+        run_id: Option<Uuid>,
+    },
+    /// A message of a newer protocol version, ignored.
+    #[serde(other)]
+    Unsupported,
+}
+
+/// The local connection to the agent: a Unix domain socket next to `agent.json`, or
+/// on Windows a TCP connection on the loopback interface, as std has no named pipes.
+#[cfg(unix)]
+pub type AgentStream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+pub type AgentStream = std::net::TcpStream;
+
+pub fn write_message(writer: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads messages line by line. A partly read line survives read timeouts, so the
+/// reader can wake up now and then to check whether it should stop.
+pub struct MessageReader<R> {
+    reader: BufReader<R>,
+    line: Vec<u8>,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader: BufReader::new(reader), line: Vec::new() }
+    }
+
+    /// The next message, or `None` once the other side has closed the connection or
+    /// `stop` is set.
+    pub fn next<M: DeserializeOwned>(&mut self, stop: Option<&AtomicBool>) -> Result<Option<M>> {
+        loop {
+            if stop.is_some_and(|stop| stop.load(Ordering::SeqCst)) {
+                return Ok(None);
+            }
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return Ok(None),
+                Ok(_) if self.line.ends_with(b"\n") => {
+                    let message = serde_json::from_slice(&self.line);
+                    self.line.clear();
+                    return Ok(Some(message?));
+                }
+                // The connection closed in the middle of a line
+                Ok(_) => return Ok(None),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// A connection from the GUI, or any other client, to the background agent.
+pub struct AgentClient {
+    reader: MessageReader<AgentStream>,
+    writer: AgentStream,
+    agent: AgentInfo,
+}
+
+impl AgentClient {
+    /// Connects to the agent running for this user.
+    pub fn connect() -> Result<Self> {
+        let info = find_agent()?.ok_or_else(|| CommandArgusError::Agent("not running".to_string()))?;
+        Self::connect_to(&info)
+    }
+
+    /// Connects to the agent `info` announces and checks it speaks this version.
+    pub fn connect_to(info: &AgentInfo) -> Result<Self> {
+        let stream = AgentStream::connect(&info.endpoint)?;
+        let mut client = Self {
+            reader: MessageReader::new(stream.try_clone()?),
+            writer: stream,
+            agent: info.clone(),
+        };
+        client.send(&ClientMessage::Hello { version: PROTOCOL_VERSION, token: info.token.clone() })?;
+        match client.recv()? {
+            AgentMessage::Welcome { agent, .. } => {
+                client.agent = agent;
+                Ok(client)
+            }
+            AgentMessage::Refused { reason, .. } => Err(CommandArgusError::Agent(reason)),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    pub fn agent(&self) -> &AgentInfo {
+        &self.agent
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        write_message(&mut self.writer, message)
+    }
+
+    /// The next message from the agent. Fails once the agent has closed the connection.
+    pub fn recv(&mut self) -> Result<AgentMessage> {
+        self.reader
+            .next(None)?
+            .ok_or_else(|| CommandArgusError::Agent("the agent closed the connection".to_string()))
+    }
+
+    pub fn list_jobs(&mut self) -> Result<Vec<RunningJob>> {
+        self.send(&ClientMessage::ListJobs)?;
+        match self.recv()? {
+            AgentMessage::Jobs { jobs } => Ok(jobs),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Runs a command in the agent and waits for it to finish, handing each line of
+    /// output to `on_output`. Other runs of this connection must not be in flight.
+    pub fn run(
+        &mut self,
+        request: RunRequest,
+        mut on_output: impl FnMut(Uuid, OutputStream, &str),
+    ) -> Result<ExecutionSummary> {
+        self.send(&ClientMessage::Submit { request })?;
+        loop {
+            match self.recv()? {
+                AgentMessage::Accepted { .. } | AgentMessage::Unsupported => {}
+                AgentMessage::Output { run_id, stream, text } => on_output(run_id, stream, &text),
+                AgentMessage::Finished { summary, .. } => return Ok(summary),
+                AgentMessage::Error { message, .. } => return Err(CommandArgusError::Agent(message)),
+                other => return Err(unexpected(&other)),
+            }
+        }
+    }
+}
+
+fn unexpected(message: &AgentMessage) -> CommandArgusError {
+    CommandArgusError::Agent(format!("unexpected answer: {:?}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use crate::agent::Agent;
+    use crate::command::{Command, CommandParameter, ParameterType};
+    use crate::impact::Impact;
+    use crate::settings::SettingsStorage;
+    use crate::storage::CommandStorage;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_messages_are_versioned_lines() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &ClientMessage::ListJobs).unwrap();
+        write_message(&mut buffer, &ClientMessage::Hello { version: 1, token: "t".to_string() }).unwrap();
+        buffer.extend_from_slice(b"{\"type\":\"cancel\",\"run_id\":\"x\"}\n{\"type\":\"list_jobs\"");
+        assert_eq!(String::from_utf8_lossy(&buffer).lines().next(), Some("{\"type\":\"list_jobs\"}"));
+
+        let mut reader = MessageReader::new(buffer.as_slice());
+        assert_eq!(reader.next::<ClientMessage>(None).unwrap(), Some(ClientMessage::ListJobs));
+        assert!(matches!(reader.next::<ClientMessage>(None).unwrap(), Some(ClientMessage::Hello { version: 1, .. })));
+        // From a newer client
+        assert_eq!(reader.next::<ClientMessage>(None).unwrap(), Some(ClientMessage::Unsupported));
+        // Cut short
+        assert_eq!(reader.next::<ClientMessage>(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_run_in_agent() {
+        let temp = TempDir::new().unwrap();
+        let storage = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let mut command = Command::new("Greet".to_string(), "echo".to_string());
+        command.args = vec!["hello".to_string(), "{name}".to_string()];
+        command.add_parameter(CommandParameter {
+            name: "name".to_string(),
+            placeholder: "Name".to_string(),
+            parameter_type: ParameterType::Text,
+            required: true,
+            default_value: None,
+            options: None,
+            secret: false,
+        });
+        let command = storage.create(EditAccess::unrestricted(), command).unwrap();
+
+        let agent = Arc::new(Agent::with_dir(temp.path()).unwrap());
+        agent.start().unwrap();
+        let info = agent.running().unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = {
+            let (agent, stop) = (agent.clone(), stop.clone());
+            std::thread::spawn(move || agent.serve(&stop))
+        };
+
+        let mut client = AgentClient::connect_to(&info).unwrap();
+        assert_eq!(client.agent().pid, std::process::id());
+        assert_eq!(client.list_jobs().unwrap(), vec![]);
+        let mut lines = Vec::new();
+        let request = RunRequest {
+            command_id: command.id,
+            parameters: HashMap::from([("name".to_string(), "agent".to_string())]),
+            use_shell: false,
+            confirmation: None,
+        };
+        let summary = client.run(request.clone(), |_, stream, text| lines.push((stream, text.to_string()))).unwrap();
+        assert!(summary.success);
+        assert_eq!(lines, vec![(OutputStream::Stdout, "hello agent\n".to_string())]);
+        assert_eq!(summary.parameters.get("name").map(String::as_str), Some("agent"));
+
+        // Checked like runs in the app
+        storage.update(EditAccess::unrestricted(), command.id, |command| command.impact = Impact::Caution).unwrap();
+        assert!(client.run(request.clone(), |_, _, _| {}).is_err());
+        let confirmed = RunRequest { confirmation: Some("yes".to_string()), ..request };
+        assert!(client.run(confirmed.clone(), |_, _, _| {}).unwrap().success);
+
+        let missing = RunRequest { command_id: Uuid::new_v4(), parameters: HashMap::new(), use_shell: false, confirmation: None };
+        assert!(client.run(missing, |_, _, _| {}).is_err());
+
+        // Restricted mode, read for every run, holds in the agent too
+        let settings = SettingsStorage::with_path(temp.path().join("settings.json")).unwrap();
+        settings.update(EditAccess::unrestricted(), |s| s.restricted_mode.enable("admin")).unwrap();
+        let refused = client.run(confirmed, |_, _, _| panic!("refused runs have no output"));
+        assert!(matches!(refused, Err(CommandArgusError::Agent(message)) if message.contains("approved")));
+
+        let wrong_token = AgentInfo { token: "guess".to_string(), ..info.clone() };
+        assert!(matches!(AgentClient::connect_to(&wrong_token), Err(CommandArgusError::Agent(_))));
+
+        stop.store(true, Ordering::SeqCst);
+        server.join().unwrap().unwrap();
+        assert!(agent.running().is_none());
+        assert!(AgentClient::connect_to(&info).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Scheme of the links that start the GUI with a request, e.g. `command-argus://run/Deploy`.
+pub const DEEP_LINK_SCHEME: &str = "command-argus";
+
+/// What a launch of the GUI asks the running instance to do.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LaunchRequest {
+    /// Run a command, given by its name or id.
+    Run { command: String },
+}
+
+/// The request in a launch's arguments: `--run <command>`, `--run=<command>` or a
+/// `command-argus://run/<command>` link with the command percent-encoded. The first
+/// argument, the program, is skipped.
+pub fn parse_launch_args(args: &[String]) -> Option<LaunchRequest> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let command = if arg == "--run" {
+            args.next().cloned()
+        } else if let Some(command) = arg.strip_prefix("--run=") {
+            Some(command.to_string())
+        } else if let Some(path) = arg.strip_prefix(&format!("{}://run/", DEEP_LINK_SCHEME)) {
+            percent_decode(path.trim_end_matches('/'))
+        } else {
+            None
+        };
+        if let Some(command) = command.filter(|c| !c.trim().is_empty()) {
+            return Some(LaunchRequest::Run { command });
+        }
+    }
+    None
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_launch_args() {
+        let run = |command: &str| Some(LaunchRequest::Run { command: command.to_string() });
+        assert_eq!(parse_launch_args(&args(&["argus-gui", "--run", "Deploy API"])), run("Deploy API"));
+        assert_eq!(parse_launch_args(&args(&["argus-gui", "--run=build"])), run("build"));
+        assert_eq!(parse_launch_args(&args(&["argus-gui", "command-argus://run/Deploy%20API/"])), run("Deploy API"));
+        assert_eq!(parse_launch_args(&args(&["command-argus://run/skipped"])), None);
+        assert_eq!(parse_launch_args(&args(&["argus-gui", "command-argus://run/%zz"])), None);
+        assert_eq!(parse_launch_args(&args(&["argus-gui", "--run"])), None);
+    }
+}
@@ -2,8 +2,142 @@ pub mod command;
 pub mod error;
 pub mod storage;
 pub mod executor;
+pub mod batch;
+pub mod ci;
+pub mod importer;
+pub mod project;
+pub mod sync;
+pub mod merge;
+pub mod settings;
+pub mod restricted;
+pub mod audit;
+pub mod approval;
+pub mod scrub;
+pub mod history;
+pub mod sandbox;
+pub mod expand;
+pub mod encoding;
+pub mod shell;
+pub mod platform;
+pub mod executable;
+pub mod tools;
+pub mod preflight;
+pub mod assertions;
+pub mod report;
+pub mod queue;
+pub mod ratelimit;
+pub mod impact;
+pub mod bundle;
+pub mod query;
+pub mod saved_search;
+pub mod search_index;
+pub mod usage;
+pub mod onboarding;
+pub mod notify;
+pub mod runbook;
+pub mod variables;
+pub mod profile;
+pub mod foreach;
+pub mod glob;
+pub mod artifacts;
+pub mod scratch;
+pub mod open_output;
+pub mod links;
+pub mod highlight;
+pub mod problems;
+pub mod share;
+pub mod launch;
+pub mod shutdown;
+pub mod agent;
+pub mod ipc;
+pub mod benchmark;
+pub mod lint;
+pub mod parameterize;
+pub mod explain;
+pub mod help;
+pub mod aliases;
+pub mod validation;
+pub mod builders;
+pub mod replace;
+pub mod stats;
+pub mod migrate;
+pub mod remap;
+pub mod dead_paths;
+pub mod args;
+pub mod env_preview;
+pub mod replay;
+pub mod tail;
+pub mod timeline;
+pub mod guard;
 
 pub use command::*;
 pub use error::*;
 pub use storage::*;
 pub use executor::*;
+pub use batch::*;
+pub use ci::*;
+pub use importer::*;
+pub use project::*;
+pub use sync::*;
+pub use merge::*;
+pub use settings::*;
+pub use restricted::*;
+pub use audit::*;
+pub use approval::*;
+pub use scrub::*;
+pub use history::*;
+pub use sandbox::*;
+pub use expand::*;
+pub use encoding::*;
+pub use shell::*;
+pub use platform::*;
+pub use executable::*;
+pub use tools::*;
+pub use preflight::*;
+pub use assertions::*;
+pub use report::*;
+pub use queue::*;
+pub use ratelimit::*;
+pub use impact::*;
+pub use bundle::*;
+pub use query::*;
+pub use saved_search::*;
+pub use search_index::*;
+pub use usage::*;
+pub use onboarding::*;
+pub use notify::*;
+pub use runbook::*;
+pub use variables::*;
+pub use profile::*;
+pub use foreach::*;
+pub use glob::*;
+pub use artifacts::*;
+pub use scratch::*;
+pub use open_output::*;
+pub use links::*;
+pub use highlight::*;
+pub use problems::*;
+pub use share::*;
+pub use launch::*;
+pub use shutdown::*;
+pub use agent::*;
+pub use ipc::*;
+pub use benchmark::*;
+pub use lint::*;
+pub use parameterize::*;
+pub use explain::*;
+pub use help::*;
+pub use aliases::*;
+pub use validation::*;
+pub use builders::*;
+pub use replace::*;
+pub use stats::*;
+pub use migrate::*;
+pub use remap::*;
+pub use dead_paths::*;
+pub use args::*;
+pub use env_preview::*;
+pub use replay::*;
+pub use tail::*;
+pub use timeline::*;
+pub use guard::*;
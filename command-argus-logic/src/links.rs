@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::expand::expand_env;
+use crate::history::OutputStream;
+
+/// Links beyond this many are left out, so a crawler's log does not flood the result.
+pub const MAX_OUTPUT_LINKS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Url,
+    Path,
+}
+
+/// A URL or file mentioned in a command's output, e.g. a deploy preview or a report.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutputLink {
+    pub kind: LinkKind,
+    /// The URL, or the absolute path of the file.
+    pub target: String,
+    /// The text as it appeared in the output.
+    pub text: String,
+    pub stream: OutputStream,
+    /// 1-based line of the output the link was found on.
+    pub line: usize,
+}
+
+/// The http(s) URLs and existing file paths in `text`, in order of appearance and each
+/// only once. Relative paths count only when `base_dir` is given and they exist below
+/// it; `file:line:col` locations point at the file.
+pub fn extract_links(text: &str, stream: OutputStream, base_dir: Option<&Path>) -> Vec<OutputLink> {
+    let urls = Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap();
+    let paths = Regex::new(r#"(?:^|[\s'"(=\[])((?:~/|\.{1,2}/|/|[A-Za-z]:\\)[^\s'"()<>\[\]]+)"#).unwrap();
+    let mut links: Vec<OutputLink> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let mut found: Vec<(usize, LinkKind, String, String)> = Vec::new();
+        for url in urls.find_iter(line) {
+            let text = trim_trailing_punctuation(url.as_str());
+            found.push((url.start(), LinkKind::Url, text.to_string(), text.to_string()));
+        }
+        for cap in paths.captures_iter(line) {
+            let path = cap.get(1).unwrap();
+            // The path part of a URL is not a file
+            if found.iter().any(|(start, _, text, _)| *start <= path.start() && path.start() < start + text.len()) {
+                continue;
+            }
+            let text = trim_trailing_punctuation(path.as_str());
+            if let Some(file) = existing_file(text, base_dir) {
+                found.push((path.start(), LinkKind::Path, text.to_string(), file.to_string_lossy().to_string()));
+            }
+        }
+        found.sort_by_key(|(start, ..)| *start);
+        for (_, kind, text, target) in found {
+            if links.len() == MAX_OUTPUT_LINKS {
+                return links;
+            }
+            if !links.iter().any(|link| link.target == target) {
+                links.push(OutputLink { kind, target, text, stream, line: index + 1 });
+            }
+        }
+    }
+    links
+}
+
+fn trim_trailing_punctuation(text: &str) -> &str {
+    text.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '\''])
+}
+
+// The file a path in the output refers to, if it exists. A trailing `:line` or
+// `:line:col` is dropped first.
+fn existing_file(text: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
+    let location = Regex::new(r"(:\d+)+$").unwrap();
+    let text = location.replace(text, "");
+    let path = match text.starts_with("~/") {
+        true => PathBuf::from(expand_env(&text)),
+        false => PathBuf::from(text.as_ref()),
+    };
+    let path = match (path.is_absolute(), base_dir) {
+        (true, _) => path,
+        (false, Some(base)) => base.join(path),
+        (false, None) => return None,
+    };
+    path.exists().then(|| path.canonicalize().unwrap_or(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_links() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("coverage")).unwrap();
+        fs::write(root.join("coverage/index.html"), "").unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+
+        let output = format!(
+            "Preview: https://preview-42.example.com/app?x=1.\n\
+             Report written to ./coverage/index.html\n\
+             error at {}:12:5 (see https://docs.example.com/E0308)\n\
+             missing /no/such/file and https://preview-42.example.com/app?x=1\n",
+            root.join("main.rs").display()
+        );
+        let links = extract_links(&output, OutputStream::Stderr, Some(&root));
+        let found: Vec<(LinkKind, &str, usize)> =
+            links.iter().map(|link| (link.kind, link.text.as_str(), link.line)).collect();
+        let main = format!("{}:12:5", root.join("main.rs").display());
+        assert_eq!(
+            found,
+            vec![
+                (LinkKind::Url, "https://preview-42.example.com/app?x=1", 1),
+                (LinkKind::Path, "./coverage/index.html", 2),
+                (LinkKind::Path, main.as_str(), 3),
+                (LinkKind::Url, "https://docs.example.com/E0308", 3),
+            ]
+        );
+        assert_eq!(links[1].target, root.join("coverage/index.html").to_string_lossy());
+        assert_eq!(links[2].target, root.join("main.rs").to_string_lossy());
+        assert_eq!(links[0].stream, OutputStream::Stderr);
+
+        assert_eq!(extract_links("see ./coverage/index.html", OutputStream::Stdout, None), vec![]);
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::command::{Command, CommandParameter, EnvironmentVariable};
+
+/// A field that was changed differently on both sides. Values are JSON so the GUI
+/// can render any field the same way; `None` means the entry doesn't exist on that side.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldConflict {
+    /// Field name, e.g. `command`, or `environment_variables.KEY` / `parameters.NAME`.
+    pub field: String,
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    Merged {
+        command: Command,
+    },
+    /// `command` holds everything that merged cleanly; conflicting fields keep the local value.
+    Conflict {
+        command: Command,
+        conflicts: Vec<FieldConflict>,
+    },
+}
+
+/// Three-way merge of two versions of the same command against their common ancestor.
+///
+/// Scalar fields and the ordered `args` list take whichever side changed them and
+/// conflict when both changed them differently. Tags are merged as a set, so adding
+/// and removing tags never conflicts. Environment variables and parameters are
+/// merged per key/name. Usage counters add up the runs made on each side.
+pub fn merge_command_versions(base: &Command, local: &Command, remote: &Command) -> MergeOutcome {
+    let mut conflicts = Vec::new();
+    let mut merged = local.clone();
+
+    merged.name = merge_field("name", &base.name, &local.name, &remote.name, &mut conflicts);
+    merged.command = merge_field("command", &base.command, &local.command, &remote.command, &mut conflicts);
+    merged.args = merge_field("args", &base.args, &local.args, &remote.args, &mut conflicts);
+    merged.description = merge_field(
+        "description",
+        &base.description,
+        &local.description,
+        &remote.description,
+        &mut conflicts,
+    );
+    merged.working_directory = merge_field(
+        "working_directory",
+        &base.working_directory,
+        &local.working_directory,
+        &remote.working_directory,
+        &mut conflicts,
+    );
+    merged.mise_enabled = merge_field(
+        "mise_enabled",
+        &base.mise_enabled,
+        &local.mise_enabled,
+        &remote.mise_enabled,
+        &mut conflicts,
+    );
+    merged.pinned_executable = merge_field(
+        "pinned_executable",
+        &base.pinned_executable,
+        &local.pinned_executable,
+        &remote.pinned_executable,
+        &mut conflicts,
+    );
+    merged.author = merge_field("author", &base.author, &local.author, &remote.author, &mut conflicts);
+    merged.source = merge_field("source", &base.source, &local.source, &remote.source, &mut conflicts);
+    merged.maintainer_note = merge_field(
+        "maintainer_note",
+        &base.maintainer_note,
+        &local.maintainer_note,
+        &remote.maintainer_note,
+        &mut conflicts,
+    );
+    merged.profiles = merge_field("profiles", &base.profiles, &local.profiles, &remote.profiles, &mut conflicts);
+    merged.forbidden_profiles = merge_field(
+        "forbidden_profiles",
+        &base.forbidden_profiles,
+        &local.forbidden_profiles,
+        &remote.forbidden_profiles,
+        &mut conflicts,
+    );
+    merged.collect_artifacts = merge_field(
+        "collect_artifacts",
+        &base.collect_artifacts,
+        &local.collect_artifacts,
+        &remote.collect_artifacts,
+        &mut conflicts,
+    );
+    merged.scratch_dir = merge_field("scratch_dir", &base.scratch_dir, &local.scratch_dir, &remote.scratch_dir, &mut conflicts);
+    merged.stdin_template = merge_field(
+        "stdin_template",
+        &base.stdin_template,
+        &local.stdin_template,
+        &remote.stdin_template,
+        &mut conflicts,
+    );
+    merged.record_stdin = merge_field("record_stdin", &base.record_stdin, &local.record_stdin, &remote.record_stdin, &mut conflicts);
+    merged.capture_timeline = merge_field("capture_timeline", &base.capture_timeline, &local.capture_timeline, &remote.capture_timeline, &mut conflicts);
+    merged.phase_marker = merge_field("phase_marker", &base.phase_marker, &local.phase_marker, &remote.phase_marker, &mut conflicts);
+    merged.open_output_with = merge_field(
+        "open_output_with",
+        &base.open_output_with,
+        &local.open_output_with,
+        &remote.open_output_with,
+        &mut conflicts,
+    );
+    merged.problem_matchers = merge_field(
+        "problem_matchers",
+        &base.problem_matchers,
+        &local.problem_matchers,
+        &remote.problem_matchers,
+        &mut conflicts,
+    );
+    merged.impact = merge_field("impact", &base.impact, &local.impact, &remote.impact, &mut conflicts);
+    merged.rate_limit = merge_field("rate_limit", &base.rate_limit, &local.rate_limit, &remote.rate_limit, &mut conflicts);
+    merged.notifications = merge_field("notifications", &base.notifications, &local.notifications, &remote.notifications, &mut conflicts);
+    merged.mutex_group = merge_field(
+        "mutex_group",
+        &base.mutex_group,
+        &local.mutex_group,
+        &remote.mutex_group,
+        &mut conflicts,
+    );
+    merged.priority = merge_field("priority", &base.priority, &local.priority, &remote.priority, &mut conflicts);
+    merged.assertions = merge_field("assertions", &base.assertions, &local.assertions, &remote.assertions, &mut conflicts);
+    merged.preconditions = merge_field(
+        "preconditions",
+        &base.preconditions,
+        &local.preconditions,
+        &remote.preconditions,
+        &mut conflicts,
+    );
+    merged.tool_checks = merge_field("tool_checks", &base.tool_checks, &local.tool_checks, &remote.tool_checks, &mut conflicts);
+    merged.platforms = merge_field("platforms", &base.platforms, &local.platforms, &remote.platforms, &mut conflicts);
+    merged.platform_overrides = merge_field(
+        "platform_overrides",
+        &base.platform_overrides,
+        &local.platform_overrides,
+        &remote.platform_overrides,
+        &mut conflicts,
+    );
+    merged.shell = merge_field("shell", &base.shell, &local.shell, &remote.shell, &mut conflicts);
+    merged.load_shell_profile = merge_field(
+        "load_shell_profile",
+        &base.load_shell_profile,
+        &local.load_shell_profile,
+        &remote.load_shell_profile,
+        &mut conflicts,
+    );
+    merged.default_execution_mode = merge_field(
+        "default_execution_mode",
+        &base.default_execution_mode,
+        &local.default_execution_mode,
+        &remote.default_execution_mode,
+        &mut conflicts,
+    );
+    merged.output_encoding = merge_field(
+        "output_encoding",
+        &base.output_encoding,
+        &local.output_encoding,
+        &remote.output_encoding,
+        &mut conflicts,
+    );
+    merged.no_network = merge_field("no_network", &base.no_network, &local.no_network, &remote.no_network, &mut conflicts);
+    merged.sandbox = merge_field("sandbox", &base.sandbox, &local.sandbox, &remote.sandbox, &mut conflicts);
+    merged.requires_approval = merge_field(
+        "requires_approval",
+        &base.requires_approval,
+        &local.requires_approval,
+        &remote.requires_approval,
+        &mut conflicts,
+    );
+
+    merged.tags = merge_tags(&base.tags, &local.tags, &remote.tags);
+    merged.environment_variables = merge_keyed(
+        "environment_variables",
+        &base.environment_variables,
+        &local.environment_variables,
+        &remote.environment_variables,
+        |v: &EnvironmentVariable| v.key.clone(),
+        &mut conflicts,
+    );
+    merged.parameters = merge_keyed(
+        "parameters",
+        &base.parameters,
+        &local.parameters,
+        &remote.parameters,
+        |p: &CommandParameter| p.name.clone(),
+        &mut conflicts,
+    );
+
+    let local_runs = local.use_count.saturating_sub(base.use_count);
+    let remote_runs = remote.use_count.saturating_sub(base.use_count);
+    merged.use_count = base.use_count + local_runs + remote_runs;
+    merged.last_used_at = local.last_used_at.max(remote.last_used_at);
+    merged.updated_at = local.updated_at.max(remote.updated_at);
+
+    if conflicts.is_empty() {
+        MergeOutcome::Merged { command: merged }
+    } else {
+        MergeOutcome::Conflict {
+            command: merged,
+            conflicts,
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn merge_field<T: Clone + PartialEq + Serialize>(
+    field: &str,
+    base: &T,
+    local: &T,
+    remote: &T,
+    conflicts: &mut Vec<FieldConflict>,
+) -> T {
+    if local == remote || remote == base {
+        local.clone()
+    } else if local == base {
+        remote.clone()
+    } else {
+        conflicts.push(FieldConflict {
+            field: field.to_string(),
+            base: Some(to_value(base)),
+            local: Some(to_value(local)),
+            remote: Some(to_value(remote)),
+        });
+        local.clone()
+    }
+}
+
+fn merge_tags(base: &[String], local: &[String], remote: &[String]) -> Vec<String> {
+    let base_set: BTreeSet<&String> = base.iter().collect();
+    let removed: BTreeSet<&String> = base
+        .iter()
+        .filter(|t| !local.contains(t) || !remote.contains(t))
+        .collect();
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in local.iter().chain(remote.iter()) {
+        let keep = !base_set.contains(tag) || !removed.contains(tag);
+        if keep && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// Merges lists whose entries are identified by a key, keeping the local order and
+/// appending entries that only the remote side added.
+fn merge_keyed<T: Clone + PartialEq + Serialize>(
+    field: &str,
+    base: &[T],
+    local: &[T],
+    remote: &[T],
+    key: impl Fn(&T) -> String,
+    conflicts: &mut Vec<FieldConflict>,
+) -> Vec<T> {
+    let find = |list: &[T], k: &str| list.iter().find(|item| key(item) == k).cloned();
+
+    let mut keys: Vec<String> = Vec::new();
+    for item in local.iter().chain(remote.iter()).chain(base.iter()) {
+        let k = key(item);
+        if !keys.contains(&k) {
+            keys.push(k);
+        }
+    }
+
+    let mut merged = Vec::new();
+    for k in keys {
+        let (b, l, r) = (find(base, &k), find(local, &k), find(remote, &k));
+        let value = if l == r || r == b {
+            l
+        } else if l == b {
+            r
+        } else {
+            conflicts.push(FieldConflict {
+                field: format!("{}.{}", field, k),
+                base: b.as_ref().map(to_value),
+                local: l.as_ref().map(to_value),
+                remote: r.as_ref().map(to_value),
+            });
+            l
+        };
+        if let Some(value) = value {
+            merged.push(value);
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Command {
+        let mut cmd = Command::new("Deploy".to_string(), "kubectl".to_string())
+            .with_args(vec!["apply".to_string(), "-f".to_string(), "app.yaml".to_string()]);
+        cmd.add_tag("k8s".to_string());
+        cmd.add_tag("prod".to_string());
+        cmd.add_environment_variable("KUBECONFIG".to_string(), "~/.kube/config".to_string());
+        cmd.add_environment_variable("NAMESPACE".to_string(), "default".to_string());
+        cmd
+    }
+
+    fn env(cmd: &Command, key: &str) -> Option<String> {
+        cmd.environment_variables.iter().find(|v| v.key == key).map(|v| v.value.clone())
+    }
+
+    #[test]
+    fn test_non_overlapping_changes_merge() {
+        let base = base();
+        let mut local = base.clone();
+        local.description = Some("Roll out the app".to_string());
+        local.use_count = 3;
+        let mut remote = base.clone();
+        remote.command = "kubectl-1.29".to_string();
+        remote.use_count = 2;
+
+        let MergeOutcome::Merged { command } = merge_command_versions(&base, &local, &remote) else {
+            panic!("expected clean merge");
+        };
+        assert_eq!(command.description.as_deref(), Some("Roll out the app"));
+        assert_eq!(command.command, "kubectl-1.29");
+        assert_eq!(command.use_count, 5);
+    }
+
+    #[test]
+    fn test_tags_merge_as_set() {
+        let base = base();
+        let mut local = base.clone();
+        local.remove_tag("prod");
+        local.add_tag("deploy".to_string());
+        let mut remote = base.clone();
+        remote.add_tag("ops".to_string());
+
+        let MergeOutcome::Merged { command } = merge_command_versions(&base, &local, &remote) else {
+            panic!("expected clean merge");
+        };
+        assert_eq!(command.tags, vec!["k8s", "deploy", "ops"]);
+    }
+
+    #[test]
+    fn test_args_conflict_when_both_change() {
+        let base = base();
+        let local = base.clone().with_args(vec!["apply".to_string(), "-k".to_string(), ".".to_string()]);
+        let remote = base.clone().with_args(vec!["apply".to_string(), "-f".to_string(), "prod.yaml".to_string()]);
+
+        let MergeOutcome::Conflict { command, conflicts } = merge_command_versions(&base, &local, &remote) else {
+            panic!("expected conflict");
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].field, "args");
+        assert_eq!(command.args, local.args);
+    }
+
+    #[test]
+    fn test_env_merges_per_key() {
+        let base = base();
+        let mut local = base.clone();
+        local.environment_variables.retain(|v| v.key != "KUBECONFIG");
+        local.environment_variables[0].value = "staging".to_string();
+        let mut remote = base.clone();
+        remote.add_environment_variable("DRY_RUN".to_string(), "1".to_string());
+        remote.environment_variables[1].value = "prod".to_string();
+
+        let MergeOutcome::Conflict { command, conflicts } = merge_command_versions(&base, &local, &remote) else {
+            panic!("expected conflict");
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].field, "environment_variables.NAMESPACE");
+        assert_eq!(conflicts[0].local, Some(serde_json::json!({"key": "NAMESPACE", "value": "staging", "secret": false})));
+        assert_eq!(env(&command, "KUBECONFIG"), None);
+        assert_eq!(env(&command, "DRY_RUN").as_deref(), Some("1"));
+    }
+}
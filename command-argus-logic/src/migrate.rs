@@ -0,0 +1,596 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use directories::BaseDirs;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::settings::SettingsStorage;
+use crate::storage::data_dir;
+
+/// Version of the archive layout; archives from a newer version are refused.
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+/// The commands, variables, profiles (with their environment), settings (including
+/// notification rules and share targets) and saved searches.
+const STATE_FILES: &[&str] = &["commands.json", "variables.json", "profiles.json", "settings.json", "saved_searches.json"];
+const HISTORY_FILE: &str = "history.json";
+/// Compressed outputs and collected artifacts of the history.
+const HISTORY_DIRS: &[&str] = &["history-outputs", "history-artifacts"];
+
+// Files of a data directory: the path relative to it and the content
+type StateFiles = Vec<(String, Vec<u8>)>;
+
+/// A file in a state archive.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestEntry {
+    /// Relative to the data directory, with `/` separators.
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// What a state archive holds, stored in it as `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StateManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: DateTime<Utc>,
+    pub includes_history: bool,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// The whole application state packed into one zip file, to move it to another machine.
+/// Secret values are exported as they are stored, so the archive must be kept safe.
+pub struct StateArchive {
+    dir: PathBuf,
+}
+
+impl StateArchive {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_dir(data_dir()?))
+    }
+
+    /// Works on the data directory `dir` instead of the default one.
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Writes the state, and the execution history with its outputs and artifacts if
+    /// `include_history` is set, to the zip file `path`.
+    pub fn export_everything(&self, path: &Path, include_history: bool) -> Result<StateManifest> {
+        let mut files: Vec<String> = STATE_FILES.iter().map(|f| f.to_string()).collect();
+        if include_history {
+            files.push(HISTORY_FILE.to_string());
+            for dir in HISTORY_DIRS {
+                collect_files(&self.dir, &self.dir.join(dir), &mut files)?;
+            }
+        }
+
+        let mut writer = ZipWriter::new(File::create(path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut manifest = StateManifest {
+            format_version: STATE_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Utc::now(),
+            includes_history: include_history,
+            files: Vec::new(),
+        };
+        for file in files {
+            let Ok(content) = fs::read(self.dir.join(&file)) else { continue };
+            writer.start_file(file.as_str(), options).map_err(archive_error)?;
+            writer.write_all(&content)?;
+            manifest.files.push(ManifestEntry { path: file, bytes: content.len() as u64, sha256: sha256(&content) });
+        }
+        writer.start_file(MANIFEST, options).map_err(archive_error)?;
+        writer.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+        writer.finish().map_err(archive_error)?;
+        Ok(manifest)
+    }
+
+    /// Replaces the state with the one in the archive at `path`, and the history too if
+    /// `include_history` is set and the archive has it. Every file is checked against the
+    /// manifest before anything is written, and what gets replaced is moved to
+    /// `backups/import-<time>/` first.
+    pub fn import_everything(&self, _access: EditAccess, path: &Path, include_history: bool) -> Result<StateManifest> {
+        let (manifest, contents) = read_archive(path, |category| include_history || category != StateCategory::History)?;
+        self.replace_with(contents, None)?;
+        Ok(manifest)
+    }
+
+    /// Brings the chosen `categories` over from an archive or the data directory of an
+    /// old installation, rewriting paths under the old home directory if `remap_home`
+    /// says so. Returns the categories that were found and imported.
+    pub fn migrate(&self, _access: EditAccess, source: &MigrationSource, options: &MigrationOptions) -> Result<Vec<StateCategory>> {
+        if let MigrationSource::DataDir { path } = source {
+            if path.canonicalize().ok() == self.dir.canonicalize().ok() {
+                return Err(CommandArgusError::InvalidPath("that is the data directory in use".to_string()));
+            }
+        }
+        let wanted = |category: StateCategory| options.categories.contains(&category);
+        let contents = match source {
+            MigrationSource::Archive { path } => read_archive(path, wanted)?.1,
+            MigrationSource::DataDir { path } => read_data_dir(path, wanted)?,
+        };
+        let mut imported: Vec<StateCategory> = Vec::new();
+        for (path, _) in &contents {
+            if let Some(category) = StateCategory::of(path).filter(|c| !imported.contains(c)) {
+                imported.push(category);
+            }
+        }
+        self.replace_with(contents, options.remap_home.as_ref())?;
+        Ok(imported)
+    }
+
+    /// Writes `contents` into the data directory after moving what they replace to a
+    /// backup. Replacing any of the history replaces all of it. While restricted mode is
+    /// on, imported settings keep the current restricted mode, so it can only be turned
+    /// off with the admin passphrase.
+    fn replace_with(&self, mut contents: StateFiles, remap_home: Option<&HomeRemap>) -> Result<()> {
+        let restricted_mode = SettingsStorage::with_path(self.dir.join("settings.json"))?.load()?.restricted_mode;
+        if restricted_mode.enabled {
+            for (_, content) in contents.iter_mut().filter(|(path, _)| path == "settings.json") {
+                let mut settings: serde_json::Value = serde_json::from_slice(content)?;
+                if let Some(settings) = settings.as_object_mut() {
+                    settings.insert("restricted_mode".to_string(), serde_json::to_value(&restricted_mode)?);
+                }
+                *content = serde_json::to_vec_pretty(&settings)?;
+            }
+        }
+        let backup = self.dir.join("backups").join(format!("import-{}", Utc::now().format("%Y%m%d-%H%M%S%3f")));
+        let mut replaced: Vec<&str> = contents.iter().map(|(path, _)| path.as_str()).filter(|p| !p.contains('/')).collect();
+        if contents.iter().any(|(path, _)| StateCategory::of(path) == Some(StateCategory::History)) {
+            replaced.extend(HISTORY_DIRS);
+        }
+        for name in replaced {
+            let current = self.dir.join(name);
+            if current.exists() {
+                fs::create_dir_all(&backup)?;
+                fs::rename(&current, backup.join(name))?;
+            }
+        }
+        for (path, content) in &contents {
+            let target = self.dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match remap_home {
+                Some(remap) if STATE_FILES.contains(&path.as_str()) => fs::write(target, remap.apply_to_json(content)?)?,
+                _ => fs::write(target, content)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A part of the application state that can be migrated on its own.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateCategory {
+    Commands,
+    Variables,
+    Profiles,
+    Settings,
+    SavedSearches,
+    History,
+}
+
+impl StateCategory {
+    pub const ALL: [StateCategory; 6] = [
+        StateCategory::Commands,
+        StateCategory::Variables,
+        StateCategory::Profiles,
+        StateCategory::Settings,
+        StateCategory::SavedSearches,
+        StateCategory::History,
+    ];
+
+    /// The category of a file of the data directory, if it belongs to one.
+    fn of(path: &str) -> Option<Self> {
+        match path {
+            "commands.json" => Some(StateCategory::Commands),
+            "variables.json" => Some(StateCategory::Variables),
+            "profiles.json" => Some(StateCategory::Profiles),
+            "settings.json" => Some(StateCategory::Settings),
+            "saved_searches.json" => Some(StateCategory::SavedSearches),
+            HISTORY_FILE => Some(StateCategory::History),
+            _ if HISTORY_DIRS.iter().any(|d| path.starts_with(&format!("{}/", d))) => Some(StateCategory::History),
+            _ => None,
+        }
+    }
+}
+
+/// Where the state of an old installation is.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MigrationSource {
+    /// A zip made by `export_everything`.
+    Archive { path: PathBuf },
+    /// A copied data directory of an old installation.
+    DataDir { path: PathBuf },
+}
+
+impl MigrationSource {
+    /// An archive if `path` is a file, a data directory if it is a directory with a
+    /// `commands.json` in it.
+    pub fn detect(path: &Path) -> Result<Self> {
+        if path.is_file() {
+            return Ok(MigrationSource::Archive { path: path.to_path_buf() });
+        }
+        if path.join("commands.json").is_file() {
+            return Ok(MigrationSource::DataDir { path: path.to_path_buf() });
+        }
+        Err(CommandArgusError::InvalidPath(format!(
+            "'{}' is neither an exported archive nor a Command Argus data directory",
+            path.display()
+        )))
+    }
+}
+
+/// How much of a category a migration source holds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationItem {
+    pub category: StateCategory,
+    pub files: usize,
+    pub bytes: u64,
+    /// Entries in the category's list, e.g. the number of commands.
+    pub entries: Option<usize>,
+}
+
+/// What a migration source holds, shown before anything is imported.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationPreview {
+    pub source: MigrationSource,
+    /// When the archive was made; `None` for a data directory.
+    pub exported_at: Option<DateTime<Utc>>,
+    pub items: Vec<MigrationItem>,
+    /// The old home directory the state's paths point into, when it differs from this
+    /// machine's.
+    pub old_home: Option<String>,
+    pub new_home: Option<String>,
+}
+
+/// Rewrites paths under `from` to be under `to`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HomeRemap {
+    pub from: String,
+    pub to: String,
+}
+
+impl HomeRemap {
+    /// `text` with every `from` that is followed by a path separator or ends the text
+    /// replaced, so `/home/al` doesn't touch `/home/alice`.
+    pub fn apply(&self, text: &str) -> String {
+        if self.from.is_empty() {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(pos) = rest.find(&self.from) {
+            let after = &rest[pos + self.from.len()..];
+            out.push_str(&rest[..pos]);
+            if after.is_empty() || after.starts_with(['/', '\\']) {
+                out.push_str(&self.to);
+            } else {
+                out.push_str(&self.from);
+            }
+            rest = after;
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn apply_to_json(&self, content: &[u8]) -> Result<Vec<u8>> {
+        let mut value: serde_json::Value = serde_json::from_slice(content)?;
+        let mut changed = false;
+        visit_strings(&mut value, &mut |text| {
+            let remapped = self.apply(text);
+            if remapped != *text {
+                *text = remapped;
+                changed = true;
+            }
+        });
+        Ok(if changed { serde_json::to_vec_pretty(&value)? } else { content.to_vec() })
+    }
+}
+
+/// What should be brought over by `StateArchive::migrate`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationOptions {
+    pub categories: Vec<StateCategory>,
+    #[serde(default)]
+    pub remap_home: Option<HomeRemap>,
+}
+
+/// What `source` holds per category, and the old home directory its paths use.
+pub fn preview_migration(source: &MigrationSource) -> Result<MigrationPreview> {
+    let (exported_at, contents) = match source {
+        MigrationSource::Archive { path } => {
+            let (manifest, contents) = read_archive(path, |category| category != StateCategory::History)?;
+            let mut contents: Vec<(String, u64, Option<Vec<u8>>)> =
+                contents.into_iter().map(|(path, content)| (path, content.len() as u64, Some(content))).collect();
+            // The history is only counted, not read, as it may be large
+            contents.extend(
+                manifest
+                    .files
+                    .iter()
+                    .filter(|f| StateCategory::of(&f.path) == Some(StateCategory::History))
+                    .map(|f| (f.path.clone(), f.bytes, None)),
+            );
+            (Some(manifest.exported_at), contents)
+        }
+        MigrationSource::DataDir { path } => {
+            let contents = read_data_dir(path, |_| true)?;
+            (None, contents.into_iter().map(|(path, content)| (path.clone(), content.len() as u64, Some(content))).collect())
+        }
+    };
+
+    let mut items: Vec<MigrationItem> = Vec::new();
+    let mut homes: Vec<(String, usize)> = Vec::new();
+    let home_pattern = Regex::new(r#"(?:/Users|/home)/[^/\\\s"']+|[A-Za-z]:\\Users\\[^/\\\s"']+"#).unwrap();
+    for (path, bytes, content) in &contents {
+        let Some(category) = StateCategory::of(path) else { continue };
+        let value = content.as_ref().and_then(|c| serde_json::from_slice::<serde_json::Value>(c).ok());
+        let index = match items.iter().position(|item| item.category == category) {
+            Some(index) => index,
+            None => {
+                items.push(MigrationItem { category, files: 0, bytes: 0, entries: None });
+                items.len() - 1
+            }
+        };
+        let item = &mut items[index];
+        item.files += 1;
+        item.bytes += bytes;
+        if let Some(entries) = value.as_ref().and_then(|v| v.as_array()).map(|a| a.len()) {
+            item.entries = Some(item.entries.unwrap_or(0) + entries);
+        }
+
+        if let Some(mut value) = value.filter(|_| category != StateCategory::History) {
+            visit_strings(&mut value, &mut |text| {
+                for found in home_pattern.find_iter(text) {
+                    match homes.iter_mut().find(|(home, _)| home == found.as_str()) {
+                        Some((_, count)) => *count += 1,
+                        None => homes.push((found.as_str().to_string(), 1)),
+                    }
+                }
+            });
+        }
+    }
+    items.sort_by_key(|item| StateCategory::ALL.iter().position(|c| *c == item.category));
+
+    let new_home = BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().to_string());
+    let old_home = homes
+        .into_iter()
+        .filter(|(home, _)| Some(home) != new_home.as_ref())
+        .max_by_key(|(_, count)| *count)
+        .map(|(home, _)| home);
+    Ok(MigrationPreview { source: source.clone(), exported_at, items, old_home, new_home })
+}
+
+/// The archive's manifest and its files in the `wanted` categories, each checked against
+/// the manifest.
+fn read_archive(path: &Path, wanted: impl Fn(StateCategory) -> bool) -> Result<(StateManifest, StateFiles)> {
+    let mut archive = ZipArchive::new(File::open(path)?).map_err(archive_error)?;
+    let manifest: StateManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST)?)?;
+    if manifest.format_version > STATE_FORMAT_VERSION {
+        return Err(CommandArgusError::Archive(format!(
+            "the archive was made by a newer version ({}); update Command Argus first",
+            manifest.app_version
+        )));
+    }
+
+    let mut contents = Vec::new();
+    for entry in &manifest.files {
+        let category = StateCategory::of(&entry.path)
+            .filter(|_| is_safe_path(&entry.path))
+            .ok_or_else(|| CommandArgusError::Archive(format!("unexpected file '{}' in the archive", entry.path)))?;
+        if !wanted(category) {
+            continue;
+        }
+        let content = read_entry(&mut archive, &entry.path)?;
+        if content.len() as u64 != entry.bytes || sha256(&content) != entry.sha256 {
+            return Err(CommandArgusError::Archive(format!("'{}' is damaged", entry.path)));
+        }
+        if entry.path.ends_with(".json") && !entry.path.contains('/') {
+            serde_json::from_slice::<serde_json::Value>(&content)
+                .map_err(|e| CommandArgusError::Archive(format!("'{}' is not valid JSON: {}", entry.path, e)))?;
+        }
+        contents.push((entry.path.clone(), content));
+    }
+    Ok((manifest, contents))
+}
+
+/// The files of the `wanted` categories in the data directory `dir`.
+fn read_data_dir(dir: &Path, wanted: impl Fn(StateCategory) -> bool) -> Result<StateFiles> {
+    let mut files: Vec<String> = STATE_FILES.iter().map(|f| f.to_string()).collect();
+    files.push(HISTORY_FILE.to_string());
+    for history_dir in HISTORY_DIRS {
+        collect_files(dir, &dir.join(history_dir), &mut files)?;
+    }
+
+    let mut contents = Vec::new();
+    for file in files {
+        if !StateCategory::of(&file).is_some_and(&wanted) {
+            continue;
+        }
+        let Ok(content) = fs::read(dir.join(&file)) else { continue };
+        if file.ends_with(".json") && !file.contains('/') {
+            serde_json::from_slice::<serde_json::Value>(&content)
+                .map_err(|e| CommandArgusError::Archive(format!("'{}' is not valid JSON: {}", file, e)))?;
+        }
+        contents.push((file, content));
+    }
+    Ok(contents)
+}
+
+fn visit_strings(value: &mut serde_json::Value, visit: &mut impl FnMut(&mut String)) {
+    match value {
+        serde_json::Value::String(text) => visit(text),
+        serde_json::Value::Array(values) => values.iter_mut().for_each(|v| visit_strings(v, visit)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| visit_strings(v, visit)),
+        _ => {}
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>> {
+    let mut file = archive.by_name(name).map_err(|e| CommandArgusError::Archive(format!("'{}': {}", name, e)))?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// A relative path that stays inside the data directory.
+fn is_safe_path(path: &str) -> bool {
+    Path::new(path).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn archive_error(error: zip::result::ZipError) -> CommandArgusError {
+    CommandArgusError::Archive(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_and_import_everything() {
+        let old = TempDir::new().unwrap();
+        fs::write(old.path().join("commands.json"), r#"[{"moved": true}]"#).unwrap();
+        fs::write(old.path().join("settings.json"), "{}").unwrap();
+        fs::write(old.path().join("history.json"), "[]").unwrap();
+        fs::create_dir_all(old.path().join("history-outputs")).unwrap();
+        fs::write(old.path().join("history-outputs/run.gz"), b"gz").unwrap();
+        let archive_path = old.path().join("state.zip");
+        let manifest = StateArchive::with_dir(old.path().to_path_buf()).export_everything(&archive_path, true).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["commands.json", "settings.json", "history.json", "history-outputs/run.gz"]);
+
+        let new = TempDir::new().unwrap();
+        fs::write(new.path().join("commands.json"), "[]").unwrap();
+        fs::write(new.path().join("variables.json"), "[]").unwrap();
+        let target = StateArchive::with_dir(new.path().to_path_buf());
+        target.import_everything(EditAccess::unrestricted(), &archive_path, false).unwrap();
+        assert_eq!(fs::read_to_string(new.path().join("commands.json")).unwrap(), r#"[{"moved": true}]"#);
+        assert_eq!(fs::read_to_string(new.path().join("variables.json")).unwrap(), "[]");
+        assert!(!new.path().join("history.json").exists());
+        let backups: Vec<_> = fs::read_dir(new.path().join("backups")).unwrap().collect();
+        assert_eq!(backups.len(), 1);
+
+        target.import_everything(EditAccess::unrestricted(), &archive_path, true).unwrap();
+        assert_eq!(fs::read(new.path().join("history-outputs/run.gz")).unwrap(), b"gz");
+
+        fs::write(new.path().join("bogus.zip"), b"not a zip").unwrap();
+        assert!(target.import_everything(EditAccess::unrestricted(), &new.path().join("bogus.zip"), true).is_err());
+    }
+
+    #[test]
+    fn test_preview_and_migrate_data_dir() {
+        let old = TempDir::new().unwrap();
+        fs::write(
+            old.path().join("commands.json"),
+            r#"[{"working_directory": "/home/olduser/src/api"}, {"args": ["/home/olduser", "/home/olduserx/other"]}]"#,
+        )
+        .unwrap();
+        fs::write(old.path().join("variables.json"), "[]").unwrap();
+        fs::write(old.path().join("history.json"), "[{}]").unwrap();
+
+        let source = MigrationSource::detect(old.path()).unwrap();
+        let preview = preview_migration(&source).unwrap();
+        let items: Vec<(StateCategory, Option<usize>)> = preview.items.iter().map(|i| (i.category, i.entries)).collect();
+        assert_eq!(
+            items,
+            vec![(StateCategory::Commands, Some(2)), (StateCategory::Variables, Some(0)), (StateCategory::History, Some(1))]
+        );
+        assert_eq!(preview.old_home.as_deref(), Some("/home/olduser"));
+
+        let new = TempDir::new().unwrap();
+        let options = MigrationOptions {
+            categories: vec![StateCategory::Commands],
+            remap_home: Some(HomeRemap { from: "/home/olduser".to_string(), to: "/Users/new".to_string() }),
+        };
+        let imported = StateArchive::with_dir(new.path().to_path_buf()).migrate(EditAccess::unrestricted(), &source, &options).unwrap();
+        assert_eq!(imported, vec![StateCategory::Commands]);
+        let commands: serde_json::Value = serde_json::from_str(&fs::read_to_string(new.path().join("commands.json")).unwrap()).unwrap();
+        assert_eq!(commands[0]["working_directory"], "/Users/new/src/api");
+        assert_eq!(commands[1]["args"], serde_json::json!(["/Users/new", "/home/olduserx/other"]));
+        assert!(!new.path().join("variables.json").exists());
+        assert!(MigrationSource::detect(new.path().join("missing").as_path()).is_err());
+    }
+
+    #[test]
+    fn test_migration_errors() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("commands.json"), "[]").unwrap();
+        let archive = StateArchive::with_dir(dir.path().to_path_buf());
+        let options = MigrationOptions { categories: vec![StateCategory::Commands], remap_home: None };
+        let own = MigrationSource::detect(dir.path()).unwrap();
+        assert!(matches!(
+            archive.migrate(EditAccess::unrestricted(), &own, &options),
+            Err(CommandArgusError::InvalidPath(_))
+        ));
+
+        // Nothing is written when a file of the source is broken
+        let old = TempDir::new().unwrap();
+        fs::write(old.path().join("commands.json"), "[{\"name\": ").unwrap();
+        let source = MigrationSource::detect(old.path()).unwrap();
+        assert!(matches!(
+            archive.migrate(EditAccess::unrestricted(), &source, &options),
+            Err(CommandArgusError::Archive(_))
+        ));
+        assert_eq!(fs::read_to_string(dir.path().join("commands.json")).unwrap(), "[]");
+        assert!(!dir.path().join("backups").exists());
+    }
+
+    #[test]
+    fn test_migration_keeps_restricted_mode() {
+        let old = TempDir::new().unwrap();
+        fs::write(old.path().join("commands.json"), "[]").unwrap();
+        fs::write(old.path().join("settings.json"), r#"{"restricted_mode": {"enabled": false}, "share_targets": []}"#).unwrap();
+        let new = TempDir::new().unwrap();
+        let settings = SettingsStorage::with_path(new.path().join("settings.json")).unwrap();
+        settings.update(EditAccess::unrestricted(), |s| s.restricted_mode.enable("letmein")).unwrap();
+
+        let source = MigrationSource::detect(old.path()).unwrap();
+        let options = MigrationOptions { categories: vec![StateCategory::Settings], remap_home: None };
+        let archive = StateArchive::with_dir(new.path().to_path_buf());
+        assert_eq!(archive.migrate(EditAccess::unrestricted(), &source, &options).unwrap(), vec![StateCategory::Settings]);
+        let restricted_mode = settings.load().unwrap().restricted_mode;
+        assert!(restricted_mode.enabled);
+        assert!(restricted_mode.verify("letmein").is_ok());
+
+        // Once it is turned off, migrated settings are taken as they are
+        settings.update(EditAccess::unrestricted(), |s| s.restricted_mode.disable("letmein")).unwrap();
+        fs::write(old.path().join("settings.json"), r#"{"max_concurrent_executions": 5}"#).unwrap();
+        archive.migrate(EditAccess::unrestricted(), &source, &options).unwrap();
+        assert_eq!(settings.load().unwrap().max_concurrent_executions, 5);
+    }
+}
@@ -0,0 +1,478 @@
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::history::ExecutionRecord;
+
+/// When a notification rule fires, checked against every finished execution.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationTrigger {
+    /// The command with this id failed.
+    CommandFailed { command_id: Uuid },
+    /// Any command failed.
+    AnyFailure,
+    /// A command carrying this tag ran, whatever the outcome.
+    TagRan { tag: String },
+    /// A run took longer than this many seconds.
+    DurationExceeded { seconds: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    Desktop,
+    /// The notification is POSTed as JSON.
+    Webhook { url: String },
+    /// A Slack incoming webhook; gets the rendered message.
+    Slack { url: String },
+    /// A Discord channel webhook; gets the rendered message.
+    Discord { url: String },
+}
+
+/// Which runs of a command its own notifications report.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyOn {
+    #[default]
+    Failure,
+    Always,
+}
+
+/// Notifications configured on a command itself, sent on top of the matching rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CommandNotifications {
+    #[serde(default)]
+    pub on: NotifyOn,
+    pub targets: Vec<NotificationTarget>,
+}
+
+impl CommandNotifications {
+    pub fn matches(&self, record: &ExecutionRecord) -> bool {
+        self.on == NotifyOn::Always || !record.success
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationRule {
+    pub id: Uuid,
+    pub name: String,
+    pub trigger: NotificationTrigger,
+    pub targets: Vec<NotificationTarget>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl NotificationRule {
+    pub fn new(name: String, trigger: NotificationTrigger, targets: Vec<NotificationTarget>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            trigger,
+            targets,
+            enabled: true,
+        }
+    }
+
+    pub fn matches(&self, command: &Command, record: &ExecutionRecord) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match &self.trigger {
+            NotificationTrigger::CommandFailed { command_id } => record.command_id == *command_id && !record.success,
+            NotificationTrigger::AnyFailure => !record.success,
+            NotificationTrigger::TagRan { tag } => command.tags.contains(tag),
+            NotificationTrigger::DurationExceeded { seconds } => record.duration_ms() > (*seconds as i64) * 1000,
+        }
+    }
+}
+
+/// Used for Slack and Discord messages unless the settings have their own template.
+pub const DEFAULT_MESSAGE_TEMPLATE: &str = "{icon} {command} {status} in {duration}\n{link}";
+
+/// Notification rules and chat message formatting, stored in the settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+    /// Template for Slack and Discord messages; see `Notification::render`.
+    #[serde(default)]
+    pub message_template: Option<String>,
+    /// Where a run's output can be viewed, with `{execution_id}` in place of its id,
+    /// e.g. `https://argus.example.com/executions/{execution_id}`.
+    #[serde(default)]
+    pub output_link: Option<String>,
+}
+
+impl NotificationSettings {
+    pub fn add_rule(&mut self, rule: NotificationRule) -> Result<NotificationRule> {
+        if self.rules.iter().any(|r| r.name == rule.name) {
+            return Err(CommandArgusError::DuplicateName(rule.name));
+        }
+        self.rules.push(rule.clone());
+        Ok(rule)
+    }
+
+    /// Replaces the rule with the same id.
+    pub fn update_rule(&mut self, rule: NotificationRule) -> Result<NotificationRule> {
+        if self.rules.iter().any(|r| r.id != rule.id && r.name == rule.name) {
+            return Err(CommandArgusError::DuplicateName(rule.name));
+        }
+        let existing = self
+            .rules
+            .iter_mut()
+            .find(|r| r.id == rule.id)
+            .ok_or(CommandArgusError::NotFound(rule.id))?;
+        *existing = rule.clone();
+        Ok(rule)
+    }
+
+    pub fn remove_rule(&mut self, id: Uuid) -> Result<()> {
+        let initial_len = self.rules.len();
+        self.rules.retain(|r| r.id != id);
+        if self.rules.len() == initial_len {
+            return Err(CommandArgusError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    pub fn message_template(&self) -> &str {
+        self.message_template.as_deref().unwrap_or(DEFAULT_MESSAGE_TEMPLATE)
+    }
+
+    pub fn output_link(&self, execution_id: Uuid) -> Option<String> {
+        self.output_link
+            .as_ref()
+            .map(|link| link.replace("{execution_id}", &execution_id.to_string()))
+    }
+}
+
+/// What is shown on the desktop and POSTed to webhooks when a rule fires, or when a
+/// command's own notifications are due (then without a rule).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Notification {
+    pub rule_id: Option<Uuid>,
+    pub rule_name: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub command_id: Uuid,
+    pub command_name: String,
+    pub execution_id: Uuid,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: i64,
+}
+
+impl Notification {
+    pub fn new(record: &ExecutionRecord, rule: Option<&NotificationRule>) -> Self {
+        let duration = format_duration(record.duration_ms());
+        Self {
+            rule_id: rule.map(|r| r.id),
+            rule_name: rule.map(|r| r.name.clone()),
+            title: format!("{} {}", record.command_name, outcome(record.success, record.exit_code)),
+            body: match rule {
+                Some(rule) => format!("Took {} · {}", duration, rule.name),
+                None => format!("Took {}", duration),
+            },
+            command_id: record.command_id,
+            command_name: record.command_name.clone(),
+            execution_id: record.id,
+            success: record.success,
+            exit_code: record.exit_code,
+            duration_ms: record.duration_ms(),
+        }
+    }
+
+    /// Fills in `template`. Known placeholders are `{icon}`, `{command}`, `{status}`,
+    /// `{exit_code}`, `{duration}`, `{rule}` and `{link}`; the result is trimmed, so a
+    /// trailing `{link}` without an output link leaves no blank line.
+    pub fn render(&self, template: &str, link: Option<&str>) -> String {
+        template
+            .replace("{icon}", if self.success { "✅" } else { "❌" })
+            .replace("{command}", &self.command_name)
+            .replace("{status}", &outcome(self.success, self.exit_code))
+            .replace("{exit_code}", &self.exit_code.map(|c| c.to_string()).unwrap_or_default())
+            .replace("{duration}", &format_duration(self.duration_ms))
+            .replace("{rule}", self.rule_name.as_deref().unwrap_or_default())
+            .replace("{link}", link.unwrap_or_default())
+            .trim()
+            .to_string()
+    }
+}
+
+fn outcome(success: bool, exit_code: Option<i32>) -> String {
+    match (success, exit_code) {
+        (true, _) => "succeeded".to_string(),
+        (false, Some(code)) => format!("failed with exit code {}", code),
+        (false, None) => "failed to run".to_string(),
+    }
+}
+
+fn format_duration(ms: i64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+/// Delivers notifications. Desktop notifications are up to the application; webhook
+/// payloads are POSTed with `post_json` unless overridden.
+pub trait Notifier {
+    fn desktop(&self, notification: &Notification) -> Result<()>;
+
+    fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<()> {
+        post_json(url, body)
+    }
+}
+
+/// How often a webhook is tried before its notification is given up.
+pub const WEBHOOK_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// POSTs `body` to `url`, retrying connection errors, rate limiting and server errors.
+pub fn post_json(url: &str, body: &serde_json::Value) -> Result<()> {
+    let send = || {
+        ureq::post(url)
+            .send_json(body)
+            .map(|_| ())
+            .map_err(|e| (is_transient(&e), e.to_string()))
+    };
+    retry(WEBHOOK_ATTEMPTS, RETRY_DELAY, send, |(transient, _)| *transient)
+        .map_err(|(_, message)| CommandArgusError::Notification(message))
+}
+
+fn is_transient(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+// Calls `f` until it succeeds, fails for good or `attempts` are used up, doubling the
+// delay between attempts
+fn retry<T, E>(
+    attempts: u32,
+    delay: Duration,
+    mut f: impl FnMut() -> std::result::Result<T, E>,
+    transient: impl Fn(&E) -> bool,
+) -> std::result::Result<T, E> {
+    let mut delay = delay;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < attempts && transient(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Evaluates the rules in `settings` and the command's own notifications against a
+/// finished execution and delivers what is due. A failed delivery does not stop the
+/// others; the errors are returned.
+pub fn notify_execution(
+    settings: &NotificationSettings,
+    command: &Command,
+    record: &ExecutionRecord,
+    notifier: &impl Notifier,
+) -> Vec<CommandArgusError> {
+    let mut due: Vec<(Notification, &[NotificationTarget])> = settings
+        .rules
+        .iter()
+        .filter(|rule| rule.matches(command, record))
+        .map(|rule| (Notification::new(record, Some(rule)), rule.targets.as_slice()))
+        .collect();
+    if let Some(own) = command.notifications.as_ref().filter(|own| own.matches(record)) {
+        due.push((Notification::new(record, None), own.targets.as_slice()));
+    }
+
+    let link = settings.output_link(record.id);
+    let mut errors = Vec::new();
+    for (notification, targets) in due {
+        let message = notification.render(settings.message_template(), link.as_deref());
+        for target in targets {
+            let delivered = match target {
+                NotificationTarget::Desktop => notifier.desktop(&notification),
+                NotificationTarget::Webhook { url } => serde_json::to_value(&notification)
+                    .map_err(CommandArgusError::from)
+                    .and_then(|body| notifier.post_json(url, &body)),
+                NotificationTarget::Slack { url } => notifier.post_json(url, &json!({ "text": message })),
+                NotificationTarget::Discord { url } => notifier.post_json(url, &json!({ "content": message })),
+            };
+            if let Err(e) = delivered {
+                errors.push(e);
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: RefCell<Vec<String>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn desktop(&self, notification: &Notification) -> Result<()> {
+            self.sent.borrow_mut().push(format!("desktop: {}", notification.title));
+            Ok(())
+        }
+
+        fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<()> {
+            if url.contains("unreachable") {
+                return Err(CommandArgusError::Notification(format!("{} unreachable", url)));
+            }
+            self.sent.borrow_mut().push(format!("{}: {}", url, body));
+            Ok(())
+        }
+    }
+
+    fn run(command: &Command, success: bool, seconds: i64) -> ExecutionRecord {
+        let mut record = ExecutionRecord::from_error(command, Utc::now() - Duration::seconds(seconds), String::new());
+        record.success = success;
+        record.exit_code = Some(if success { 0 } else { 3 });
+        record
+    }
+
+    #[test]
+    fn test_triggers() {
+        let mut deploy = Command::new("Deploy".to_string(), "deploy".to_string());
+        deploy.add_tag("prod".to_string());
+        let lint = Command::new("Lint".to_string(), "lint".to_string());
+        let rule = |trigger| NotificationRule::new("Rule".to_string(), trigger, vec![NotificationTarget::Desktop]);
+
+        let failed = rule(NotificationTrigger::CommandFailed { command_id: lint.id });
+        assert!(failed.matches(&lint, &run(&lint, false, 0)));
+        assert!(!failed.matches(&lint, &run(&lint, true, 0)));
+        assert!(!failed.matches(&deploy, &run(&deploy, false, 0)));
+
+        let prod = rule(NotificationTrigger::TagRan { tag: "prod".to_string() });
+        assert!(prod.matches(&deploy, &run(&deploy, true, 0)));
+        assert!(!prod.matches(&lint, &run(&lint, false, 0)));
+
+        let mut slow = rule(NotificationTrigger::DurationExceeded { seconds: 60 });
+        assert!(slow.matches(&lint, &run(&lint, true, 120)));
+        assert!(!slow.matches(&lint, &run(&lint, true, 10)));
+        slow.enabled = false;
+        assert!(!slow.matches(&lint, &run(&lint, true, 120)));
+    }
+
+    #[test]
+    fn test_notify_execution() {
+        let lint = Command::new("Lint".to_string(), "lint".to_string());
+        let settings = NotificationSettings {
+            rules: vec![
+                NotificationRule::new(
+                    "Failures".to_string(),
+                    NotificationTrigger::AnyFailure,
+                    vec![NotificationTarget::Desktop, NotificationTarget::Webhook { url: "http://unreachable".to_string() }],
+                ),
+                NotificationRule::new("Slow".to_string(), NotificationTrigger::DurationExceeded { seconds: 60 }, vec![NotificationTarget::Desktop]),
+            ],
+            ..NotificationSettings::default()
+        };
+        let notifier = RecordingNotifier::default();
+
+        let errors = notify_execution(&settings, &lint, &run(&lint, false, 1), &notifier);
+        assert_eq!(*notifier.sent.borrow(), vec!["desktop: Lint failed with exit code 3"]);
+        assert_eq!(errors.len(), 1);
+
+        notifier.sent.borrow_mut().clear();
+        assert!(notify_execution(&settings, &lint, &run(&lint, true, 1), &notifier).is_empty());
+        assert!(notifier.sent.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_chat_notifications() {
+        let mut lint = Command::new("Lint".to_string(), "lint".to_string());
+        lint.notifications = Some(CommandNotifications {
+            on: NotifyOn::Failure,
+            targets: vec![
+                NotificationTarget::Slack { url: "http://slack".to_string() },
+                NotificationTarget::Discord { url: "http://discord".to_string() },
+            ],
+        });
+        let settings = NotificationSettings {
+            output_link: Some("https://argus.example.com/runs/{execution_id}".to_string()),
+            ..NotificationSettings::default()
+        };
+        let notifier = RecordingNotifier::default();
+
+        let record = run(&lint, false, 2);
+        assert!(notify_execution(&settings, &lint, &record, &notifier).is_empty());
+        let message = format!("❌ Lint failed with exit code 3 in 2.0s\\nhttps://argus.example.com/runs/{}", record.id);
+        assert_eq!(
+            *notifier.sent.borrow(),
+            vec![
+                format!("http://slack: {{\"text\":\"{}\"}}", message),
+                format!("http://discord: {{\"content\":\"{}\"}}", message),
+            ]
+        );
+
+        // Successful runs are only reported with `NotifyOn::Always`
+        notifier.sent.borrow_mut().clear();
+        notify_execution(&settings, &lint, &run(&lint, true, 2), &notifier);
+        assert!(notifier.sent.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let lint = Command::new("Lint".to_string(), "lint".to_string());
+        let notification = Notification::new(&run(&lint, true, 3), None);
+        assert_eq!(notification.render(DEFAULT_MESSAGE_TEMPLATE, None), "✅ Lint succeeded in 3.0s");
+        assert_eq!(notification.render("{command}: {exit_code} ({rule})", None), "Lint: 0 ()");
+    }
+
+    #[test]
+    fn test_retry() {
+        let calls = std::cell::Cell::new(0);
+        let flaky = || {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 { Err(503) } else { Ok(calls.get()) }
+        };
+        assert_eq!(retry(3, std::time::Duration::ZERO, flaky, |code| *code >= 500), Ok(3));
+
+        calls.set(0);
+        let rejected = || {
+            calls.set(calls.get() + 1);
+            Err::<(), _>(400)
+        };
+        assert_eq!(retry(3, std::time::Duration::ZERO, rejected, |code| *code >= 500), Err(400));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_rule_crud() {
+        let mut settings = NotificationSettings::default();
+        let rule = settings
+            .add_rule(NotificationRule::new("Failures".to_string(), NotificationTrigger::AnyFailure, vec![]))
+            .unwrap();
+        assert!(settings
+            .add_rule(NotificationRule::new("Failures".to_string(), NotificationTrigger::AnyFailure, vec![]))
+            .is_err());
+
+        let updated = settings
+            .update_rule(NotificationRule { enabled: false, ..rule.clone() })
+            .unwrap();
+        assert!(!updated.enabled);
+        assert_eq!(settings.rules, vec![updated]);
+
+        settings.remove_rule(rule.id).unwrap();
+        assert!(matches!(settings.remove_rule(rule.id), Err(CommandArgusError::NotFound(_))));
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::executable::find_executable;
+use crate::impact::Impact;
+use crate::restricted::EditAccess;
+use crate::settings::Settings;
+use crate::storage::CommandStorage;
+
+/// A curated command offered to new users who have its tool installed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StarterCommand {
+    /// Stable key the command is selected by, e.g. `git-status`.
+    pub key: String,
+    /// Program that has to be on the PATH for the command to be offered.
+    pub tool: String,
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub description: String,
+    pub impact: Impact,
+}
+
+// key, tool, name, command line, description, impact
+const STARTERS: &[(&str, &str, &str, &str, &str, Impact)] = &[
+    ("git-status", "git", "Git status", "git status", "Show changed and untracked files", Impact::Safe),
+    ("git-log", "git", "Git log", "git log --oneline -20", "The last 20 commits, one per line", Impact::Safe),
+    ("git-pull", "git", "Git pull", "git pull --rebase", "Fetch and rebase onto the upstream branch", Impact::Safe),
+    ("git-branches", "git", "Git branches", "git branch -vv", "Local branches with their upstreams", Impact::Safe),
+    ("docker-ps", "docker", "Docker containers", "docker ps", "Running containers", Impact::Safe),
+    ("docker-images", "docker", "Docker images", "docker images", "Images available locally", Impact::Safe),
+    ("docker-prune", "docker", "Docker prune", "docker system prune -f", "Remove stopped containers, unused networks and dangling images", Impact::Caution),
+    ("npm-install", "npm", "npm install", "npm install", "Install the project's dependencies", Impact::Safe),
+    ("npm-test", "npm", "npm test", "npm test", "Run the project's tests", Impact::Safe),
+    ("npm-dev", "npm", "npm run dev", "npm run dev", "Start the development server", Impact::Safe),
+    ("npm-outdated", "npm", "npm outdated", "npm outdated", "Dependencies with newer versions available", Impact::Safe),
+    ("kubectl-pods", "kubectl", "Kubernetes pods", "kubectl get pods", "Pods in the current namespace", Impact::Safe),
+    ("kubectl-context", "kubectl", "Kubernetes context", "kubectl config current-context", "The cluster kubectl talks to", Impact::Safe),
+    ("kubectl-events", "kubectl", "Kubernetes events", "kubectl get events --sort-by=.lastTimestamp", "Recent events in the current namespace", Impact::Safe),
+    ("cargo-build", "cargo", "Cargo build", "cargo build", "Build the current crate", Impact::Safe),
+    ("cargo-test", "cargo", "Cargo test", "cargo test", "Run the crate's tests", Impact::Safe),
+    ("cargo-clippy", "cargo", "Cargo clippy", "cargo clippy --all-targets", "Lint the crate with clippy", Impact::Safe),
+    ("terraform-plan", "terraform", "Terraform plan", "terraform plan", "Preview infrastructure changes", Impact::Safe),
+    ("terraform-fmt", "terraform", "Terraform fmt", "terraform fmt -recursive", "Format the configuration files", Impact::Safe),
+    ("terraform-apply", "terraform", "Terraform apply", "terraform apply", "Apply infrastructure changes", Impact::Destructive),
+];
+
+/// The tools `detect_environment` looks for, in the order they are reported.
+pub const DETECTED_TOOLS: &[&str] = &["git", "docker", "kubectl", "cargo", "npm", "terraform"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectedTool {
+    pub name: String,
+    /// Where the tool was found, or None if it is not installed.
+    pub path: Option<String>,
+}
+
+/// The tools installed on this machine and the starter commands suggested for them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnvironmentReport {
+    pub tools: Vec<DetectedTool>,
+    pub suggestions: Vec<StarterCommand>,
+}
+
+impl EnvironmentReport {
+    pub fn is_installed(&self, tool: &str) -> bool {
+        self.tools.iter().any(|t| t.name == tool && t.path.is_some())
+    }
+}
+
+impl StarterCommand {
+    pub fn to_command(&self) -> Command {
+        let mut command = Command::new(self.name.clone(), self.command.clone())
+            .with_args(self.args.clone())
+            .with_description(self.description.clone());
+        command.add_tag(self.tool.clone());
+        command.impact = self.impact;
+        command
+    }
+}
+
+/// Every starter command, whether its tool is installed or not.
+pub fn starter_catalog() -> Vec<StarterCommand> {
+    STARTERS
+        .iter()
+        .map(|&(key, tool, name, command_line, description, impact)| {
+            let mut parts = command_line.split_whitespace().map(String::from);
+            StarterCommand {
+                key: key.to_string(),
+                tool: tool.to_string(),
+                name: name.to_string(),
+                command: parts.next().unwrap_or_default(),
+                args: parts.collect(),
+                description: description.to_string(),
+                impact,
+            }
+        })
+        .collect()
+}
+
+/// The starter commands whose tools are found on the PATH.
+pub fn available_starters() -> Vec<StarterCommand> {
+    detect_environment().suggestions
+}
+
+/// Probes the PATH for the `DETECTED_TOOLS` and suggests the starter commands for
+/// the ones installed.
+pub fn detect_environment() -> EnvironmentReport {
+    detect_environment_with(find_executable)
+}
+
+fn detect_environment_with(find: impl Fn(&str) -> Option<PathBuf>) -> EnvironmentReport {
+    let tools: Vec<DetectedTool> = DETECTED_TOOLS
+        .iter()
+        .map(|&name| DetectedTool {
+            name: name.to_string(),
+            path: find(name).map(|path| path.to_string_lossy().to_string()),
+        })
+        .collect();
+    let mut report = EnvironmentReport { tools, suggestions: Vec::new() };
+    report.suggestions = starter_catalog()
+        .into_iter()
+        .filter(|starter| report.is_installed(&starter.tool))
+        .collect();
+    report
+}
+
+/// Starter commands are offered on first launch: until onboarding was completed or
+/// dismissed, and only while the library is still empty.
+pub fn needs_onboarding(storage: &CommandStorage, settings: &Settings) -> Result<bool> {
+    Ok(!settings.onboarding_completed && storage.list()?.is_empty())
+}
+
+/// Adds the starter commands with the given keys to `storage`. Commands whose name is
+/// already taken are skipped; returns the ones created.
+pub fn seed_starter_commands(storage: &CommandStorage, access: EditAccess, selection: &[String]) -> Result<Vec<Command>> {
+    let catalog = starter_catalog();
+    let starters = selection
+        .iter()
+        .map(|key| {
+            catalog
+                .iter()
+                .find(|starter| starter.key == *key)
+                .ok_or_else(|| CommandArgusError::InvalidCommand(format!("Unknown starter command '{}'", key)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut created = Vec::new();
+    for starter in starters {
+        match storage.create(access, starter.to_command()) {
+            Ok(command) => created.push(command),
+            Err(CommandArgusError::DuplicateName(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let catalog = starter_catalog();
+        let log = catalog.iter().find(|s| s.key == "git-log").unwrap();
+        assert_eq!(log.command, "git");
+        assert_eq!(log.args, vec!["log", "--oneline", "-20"]);
+        assert_eq!(log.to_command().tags, vec!["git"]);
+
+        let mut keys: Vec<&str> = catalog.iter().map(|s| s.key.as_str()).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), catalog.len());
+        assert!(catalog.iter().all(|s| DETECTED_TOOLS.contains(&s.tool.as_str())));
+    }
+
+    #[test]
+    fn test_detect_environment() {
+        let report = detect_environment_with(|name| match name {
+            "git" | "cargo" => Some(PathBuf::from("/usr/bin").join(name)),
+            _ => None,
+        });
+        assert_eq!(report.tools.len(), DETECTED_TOOLS.len());
+        assert!(report.is_installed("cargo"));
+        assert!(!report.is_installed("kubectl"));
+        assert_eq!(report.tools[0].path.as_deref(), Some("/usr/bin/git"));
+
+        let mut tools: Vec<&str> = report.suggestions.iter().map(|s| s.tool.as_str()).collect();
+        tools.dedup();
+        assert_eq!(tools, vec!["git", "cargo"]);
+    }
+
+    #[test]
+    fn test_seed() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let storage = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let mut settings = Settings::default();
+        assert!(needs_onboarding(&storage, &settings).unwrap());
+
+        let selection = vec!["git-status".to_string(), "docker-prune".to_string()];
+        let created = seed_starter_commands(&storage, EditAccess::unrestricted(), &selection).unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[1].impact, Impact::Caution);
+        assert!(!needs_onboarding(&storage, &settings).unwrap());
+
+        // Seeding again is harmless
+        assert!(seed_starter_commands(&storage, EditAccess::unrestricted(), &selection).unwrap().is_empty());
+        assert!(seed_starter_commands(&storage, EditAccess::unrestricted(), &["rm-rf".to_string()]).is_err());
+
+        storage.delete(EditAccess::unrestricted(), created[0].id).unwrap();
+        storage.delete(EditAccess::unrestricted(), created[1].id).unwrap();
+        settings.onboarding_completed = true;
+        assert!(!needs_onboarding(&storage, &settings).unwrap());
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{CommandArgusError, Result};
+
+/// Opens the output of a successful run as a file, e.g. an HTML report in the browser or
+/// a generated image in a viewer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OpenOutput {
+    /// Program the file is opened with. Without one the system's default application
+    /// for the extension is used.
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Extension of the file, e.g. `html` or `png`.
+    #[serde(default = "default_extension")]
+    pub extension: String,
+    /// The output is base64 encoded, as binary output such as an image usually is,
+    /// and is decoded before it is written.
+    #[serde(default)]
+    pub decode_base64: bool,
+}
+
+fn default_extension() -> String {
+    "txt".to_string()
+}
+
+impl Default for OpenOutput {
+    fn default() -> Self {
+        Self {
+            program: None,
+            extension: default_extension(),
+            decode_base64: false,
+        }
+    }
+}
+
+impl OpenOutput {
+    /// Writes `stdout` to `output-<run id>.<extension>` in `dir`, ready to be opened.
+    pub fn write_output(&self, stdout: &str, dir: &Path, run_id: Uuid) -> Result<PathBuf> {
+        let contents = if self.decode_base64 {
+            let encoded: String = stdout.split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| CommandArgusError::ExecutionFailed(format!("the output is not valid base64: {}", e)))?
+        } else {
+            stdout.as_bytes().to_vec()
+        };
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("output-{}.{}", run_id, self.extension.trim_start_matches('.')));
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_output() {
+        let temp = TempDir::new().unwrap();
+        let run_id = Uuid::new_v4();
+        let report = OpenOutput {
+            extension: ".html".to_string(),
+            ..OpenOutput::default()
+        };
+        let path = report.write_output("<h1>Report</h1>\n", temp.path(), run_id).unwrap();
+        assert_eq!(path, temp.path().join(format!("output-{}.html", run_id)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<h1>Report</h1>\n");
+
+        let image = OpenOutput {
+            extension: "png".to_string(),
+            decode_base64: true,
+            ..OpenOutput::default()
+        };
+        let path = image.write_output("iVBORw0K\nGgo=\n", temp.path(), run_id).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"\x89PNG\r\n\x1a\n");
+        assert!(image.write_output("not base64!", temp.path(), run_id).is_err());
+    }
+}
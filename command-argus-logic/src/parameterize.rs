@@ -0,0 +1,294 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::command::{Command, CommandParameter, ParameterType};
+use crate::error::{CommandArgusError, Result};
+
+/// What a likely-variable part of a command looks like.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    Path,
+    Hostname,
+    Version,
+}
+
+/// A part of a command that probably changes from run to run.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ParameterCandidate {
+    /// The text as it is written in the command.
+    pub text: String,
+    pub kind: ValueKind,
+    /// A free name for the parameter, from the flag the value belongs to if any.
+    pub name: String,
+    pub parameter_type: ParameterType,
+}
+
+/// Text of a command to turn into the parameter `name`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParameterSelection {
+    pub text: String,
+    pub name: String,
+    /// `Text` when not given.
+    #[serde(default)]
+    pub parameter_type: Option<ParameterType>,
+}
+
+/// Top-level domains a dotted word must end in to be taken for a hostname, so that
+/// `notes.txt` is not one.
+const HOST_SUFFIXES: &[&str] = &[
+    "com", "org", "net", "io", "dev", "app", "local", "internal", "lan", "cloud", "co", "jp", "de", "uk",
+];
+
+/// The paths, hostnames and version numbers in the arguments of `command`, each once,
+/// in the order they appear. The program itself and placeholders are left alone.
+pub fn suggest_parameters(command: &Command) -> Vec<ParameterCandidate> {
+    let host = Regex::new(r"(?i)^(?:localhost|\d{1,3}(?:\.\d{1,3}){3}|(?:[a-z0-9-]+\.)+([a-z]{2,}))$").unwrap();
+    let url_host = Regex::new(r"(?i)^[a-z][a-z0-9+.-]*://(?:[^/@\s]*@)?([^/:?#\s]+)").unwrap();
+    let version = Regex::new(r"^v?\d+\.\d+(?:\.\d+)?(?:-[0-9A-Za-z.]+)?$").unwrap();
+    let tagged_version = Regex::new(r"[:@]v?(\d+\.\d+(?:\.\d+)?(?:-[0-9A-Za-z.]+)?)$").unwrap();
+
+    let mut candidates: Vec<ParameterCandidate> = Vec::new();
+    let mut add = |text: &str, kind: ValueKind, flag: Option<&str>| {
+        if candidates.iter().any(|c| c.text == text) {
+            return;
+        }
+        let base = flag
+            .map(|flag| flag.trim_start_matches('-').replace('-', "_"))
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| match kind {
+                ValueKind::Path => "path".to_string(),
+                ValueKind::Hostname => "host".to_string(),
+                ValueKind::Version => "version".to_string(),
+            });
+        let taken = |name: &str| command.get_parameter(name).is_some() || candidates.iter().any(|c| c.name == name);
+        let name = (1..)
+            .map(|n| if n == 1 { base.clone() } else { format!("{}_{}", base, n) })
+            .find(|name| !taken(name))
+            .unwrap();
+        let parameter_type = match kind {
+            ValueKind::Path if text.ends_with('/') || !has_extension(text) => ParameterType::Directory,
+            ValueKind::Path => ParameterType::File,
+            _ => ParameterType::Text,
+        };
+        candidates.push(ParameterCandidate { text: text.to_string(), kind, name, parameter_type });
+    };
+
+    let mut previous_flag: Option<&str> = None;
+    for arg in &command.args {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => (Some(flag), value),
+            // A short flag is too often a switch to name the next argument after
+            _ if arg.starts_with('-') => {
+                previous_flag = Some(arg.as_str()).filter(|arg| arg.starts_with("--"));
+                continue;
+            }
+            _ => (previous_flag.take(), arg.as_str()),
+        };
+        if value.is_empty() || value.contains('{') || value.contains('$') {
+            continue;
+        }
+
+        if let Some(found) = url_host.captures(value) {
+            add(&found[1], ValueKind::Hostname, None);
+        } else if let Some(found) = host.captures(value) {
+            let suffix = found.get(1).map(|m| m.as_str().to_lowercase());
+            if suffix.is_none_or(|suffix| HOST_SUFFIXES.contains(&suffix.as_str())) {
+                add(value, ValueKind::Hostname, flag);
+            } else if value.contains('.') && has_extension(value) {
+                add(value, ValueKind::Path, flag);
+            }
+        } else if version.is_match(value) {
+            add(value, ValueKind::Version, flag);
+        } else if let Some(found) = tagged_version.captures(value) {
+            // An image or package tag, e.g. node:18.17.1 or react@18.2.0
+            add(&found[1], ValueKind::Version, None);
+        } else if value.contains('/') || value.contains('\\') || value.starts_with('~') {
+            add(value, ValueKind::Path, flag);
+        }
+    }
+    candidates
+}
+
+fn has_extension(text: &str) -> bool {
+    let name = text.rsplit(['/', '\\']).next().unwrap_or(text);
+    name.rsplit_once('.').is_some_and(|(stem, extension)| !stem.is_empty() && !extension.is_empty())
+}
+
+/// Turns each selected text into a `{name}` placeholder wherever it appears in the
+/// program, the arguments and the working directory, and adds a parameter for it whose
+/// default is the text, so the command runs as before. Fails, changing nothing, if a
+/// name is invalid or taken or a text is not in the command.
+pub fn parameterize_command(command: &mut Command, selections: &[ParameterSelection]) -> Result<()> {
+    let valid_name = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    let mut changed = command.clone();
+    for selection in selections {
+        if !valid_name.is_match(&selection.name) {
+            return Err(CommandArgusError::InvalidCommand(format!("'{}' is not a valid parameter name", selection.name)));
+        }
+        if changed.get_parameter(&selection.name).is_some() {
+            return Err(CommandArgusError::InvalidCommand(format!("'{}' is already a parameter", selection.name)));
+        }
+
+        let placeholder = format!("{{{}}}", selection.name);
+        let mut found = false;
+        let texts = std::iter::once(&mut changed.command)
+            .chain(changed.args.iter_mut())
+            .chain(changed.working_directory.iter_mut());
+        for text in texts {
+            if !selection.text.is_empty() && text.contains(&selection.text) {
+                *text = text.replace(&selection.text, &placeholder);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(CommandArgusError::InvalidCommand(format!("'{}' is not in the command", selection.text)));
+        }
+        changed.add_parameter(CommandParameter {
+            name: selection.name.clone(),
+            placeholder: selection.text.clone(),
+            parameter_type: selection.parameter_type.clone().unwrap_or(ParameterType::Text),
+            required: false,
+            default_value: Some(selection.text.clone()),
+            options: None,
+            secret: false,
+        });
+    }
+    *command = changed;
+    Ok(())
+}
+
+/// Renames the parameter `old` to `new` along with every `{old}` placeholder in the
+/// program, arguments, environment variable values, working directory, standard input
+/// template and platform variants, so none is left behind. Works for a placeholder that
+/// has no parameter defined too. Fails, changing nothing, if `new` is invalid or taken
+/// or `old` is not used.
+pub fn rename_parameter(command: &mut Command, old: &str, new: &str) -> Result<()> {
+    let valid_name = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    if !valid_name.is_match(new) {
+        return Err(CommandArgusError::InvalidCommand(format!("'{}' is not a valid parameter name", new)));
+    }
+    let placeholders = command.detect_placeholders();
+    if command.get_parameter(new).is_some() || placeholders.iter().any(|p| p == new) {
+        return Err(CommandArgusError::InvalidCommand(format!("'{}' is already a parameter", new)));
+    }
+    if command.get_parameter(old).is_none() && !placeholders.iter().any(|p| p == old) {
+        return Err(CommandArgusError::InvalidCommand(format!("'{}' is not a parameter of the command", old)));
+    }
+
+    let mut changed = command.clone();
+    let (from, to) = (format!("{{{}}}", old), format!("{{{}}}", new));
+    let texts = std::iter::once(&mut changed.command)
+        .chain(changed.args.iter_mut())
+        .chain(changed.environment_variables.iter_mut().map(|v| &mut v.value))
+        .chain(changed.working_directory.iter_mut())
+        .chain(changed.stdin_template.iter_mut())
+        .chain(changed.platform_overrides.iter_mut().flat_map(|o| {
+            std::iter::once(&mut o.command)
+                .chain(o.args.iter_mut().flatten())
+                .chain(o.working_directory.iter_mut())
+                .chain(o.environment_variables.iter_mut().map(|v| &mut v.value))
+        }));
+    for text in texts {
+        if text.contains(&from) {
+            *text = text.replace(&from, &to);
+        }
+    }
+    if let Some(parameter) = changed.parameters.iter_mut().find(|p| p.name == old) {
+        parameter.name = new.to_string();
+    }
+    *command = changed;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_suggest_and_parameterize() {
+        let mut command = Command::new("Deploy".to_string(), "scp".to_string()).with_args(
+            [
+                "--host",
+                "staging.example.com",
+                "-v",
+                "./dist/app-1.4.2.tar.gz",
+                "node:18.17.1",
+                "https://registry.internal/api",
+                "--out=build/",
+                "notes.txt",
+                "{target}",
+            ]
+            .iter()
+            .map(|a| a.to_string())
+            .collect(),
+        );
+        let candidates = suggest_parameters(&command);
+        let summary: Vec<(&str, ValueKind, &str)> =
+            candidates.iter().map(|c| (c.text.as_str(), c.kind, c.name.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("staging.example.com", ValueKind::Hostname, "host"),
+                ("./dist/app-1.4.2.tar.gz", ValueKind::Path, "path"),
+                ("18.17.1", ValueKind::Version, "version"),
+                ("registry.internal", ValueKind::Hostname, "host_2"),
+                ("build/", ValueKind::Path, "out"),
+                ("notes.txt", ValueKind::Path, "path_2"),
+            ]
+        );
+        assert_eq!(candidates[1].parameter_type, ParameterType::File);
+        assert_eq!(candidates[4].parameter_type, ParameterType::Directory);
+
+        let selections = vec![
+            ParameterSelection { text: "staging.example.com".to_string(), name: "host".to_string(), parameter_type: None },
+            ParameterSelection { text: "18.17.1".to_string(), name: "node".to_string(), parameter_type: None },
+        ];
+        parameterize_command(&mut command, &selections).unwrap();
+        assert_eq!(command.args[1], "{host}");
+        assert_eq!(command.args[4], "node:{node}");
+        assert_eq!(command.detect_placeholders(), vec!["host", "node", "target"]);
+        let defaults: HashMap<String, String> =
+            command.parameters.iter().map(|p| (p.name.clone(), p.default_value.clone().unwrap())).collect();
+        assert_eq!(command.replace_placeholders(&defaults).1[1], "staging.example.com");
+
+        let before = command.clone();
+        let taken = ParameterSelection { text: "notes.txt".to_string(), name: "host".to_string(), parameter_type: None };
+        assert!(parameterize_command(&mut command, &[taken]).is_err());
+        let missing = ParameterSelection { text: "nowhere".to_string(), name: "other".to_string(), parameter_type: None };
+        assert!(parameterize_command(&mut command, &[missing]).is_err());
+        assert_eq!(command, before);
+    }
+
+    #[test]
+    fn test_rename_parameter() {
+        let mut command = Command::new("Logs".to_string(), "kubectl".to_string()).with_args(vec![
+            "logs".to_string(),
+            "-n".to_string(),
+            "{ns}".to_string(),
+            "deploy/{app}".to_string(),
+        ]);
+        command.working_directory = Some("~/src/{app}".to_string());
+        command.stdin_template = Some("${app}".to_string());
+        parameterize_command(
+            &mut command,
+            &[ParameterSelection { text: "logs".to_string(), name: "verb".to_string(), parameter_type: None }],
+        )
+        .unwrap();
+
+        rename_parameter(&mut command, "app", "service").unwrap();
+        assert_eq!(command.args[3], "deploy/{service}");
+        assert_eq!(command.working_directory.as_deref(), Some("~/src/{service}"));
+        assert_eq!(command.stdin_template.as_deref(), Some("${service}"));
+        rename_parameter(&mut command, "verb", "action").unwrap();
+        assert_eq!(command.parameters[0].name, "action");
+        assert_eq!(command.detect_placeholders(), vec!["action", "ns", "service"]);
+
+        let before = command.clone();
+        assert!(rename_parameter(&mut command, "ns", "service").is_err());
+        assert!(rename_parameter(&mut command, "missing", "other").is_err());
+        assert!(rename_parameter(&mut command, "ns", "not valid").is_err());
+        assert_eq!(command, before);
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::{Command, EnvironmentVariable};
+use crate::error::{CommandArgusError, Result};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    Macos,
+    Linux,
+    Windows,
+}
+
+impl Platform {
+    /// The platform this binary was built for.
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::Macos
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Platform::Macos => "macOS",
+            Platform::Linux => "Linux",
+            Platform::Windows => "Windows",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The variant of a command used on one platform, e.g. `xdg-open` on Linux for a
+/// command that runs `open` on macOS.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlatformOverride {
+    pub platform: Platform,
+    pub command: String,
+    /// Arguments for this platform; the command's own arguments are kept when `None`.
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    /// Working directory for this platform; the command's own is kept when `None`.
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    /// Added to the command's variables, replacing any with the same key.
+    #[serde(default)]
+    pub environment_variables: Vec<EnvironmentVariable>,
+}
+
+impl PlatformOverride {
+    pub fn new(platform: Platform, command: String, args: Option<Vec<String>>) -> Self {
+        Self {
+            platform,
+            command,
+            args,
+            working_directory: None,
+            environment_variables: Vec::new(),
+        }
+    }
+}
+
+impl Command {
+    /// Whether the command can run on `platform`. No declared platforms means all.
+    pub fn supports_platform(&self, platform: Platform) -> bool {
+        self.platforms.is_empty() || self.platforms.contains(&platform)
+    }
+
+    /// Adds the variant for its platform, replacing an existing one.
+    pub fn set_platform_override(&mut self, platform_override: PlatformOverride) {
+        self.platform_overrides.retain(|o| o.platform != platform_override.platform);
+        self.platform_overrides.push(platform_override);
+    }
+
+    /// The command as it should run on `platform`, with any override applied.
+    pub fn for_platform(&self, platform: Platform) -> Result<Command> {
+        if !self.supports_platform(platform) {
+            let supported: Vec<String> = self.platforms.iter().map(|p| p.to_string()).collect();
+            return Err(CommandArgusError::UnsupportedPlatform(format!(
+                "'{}' is only available on {}, not {}",
+                self.name,
+                supported.join(", "),
+                platform
+            )));
+        }
+
+        let mut resolved = self.clone();
+        if let Some(platform_override) = self.platform_overrides.iter().find(|o| o.platform == platform) {
+            resolved.command = platform_override.command.clone();
+            // The pinned path belongs to the default program
+            resolved.pinned_executable = None;
+            if let Some(args) = &platform_override.args {
+                resolved.args = args.clone();
+            }
+            if let Some(dir) = &platform_override.working_directory {
+                resolved.working_directory = Some(dir.clone());
+            }
+            for var in &platform_override.environment_variables {
+                resolved.environment_variables.retain(|v| v.key != var.key);
+                resolved.environment_variables.push(var.clone());
+            }
+        }
+        resolved.platform_overrides.clear();
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_constraints() {
+        let mut cmd = Command::new("Brew Update".to_string(), "brew".to_string());
+        assert!(cmd.supports_platform(Platform::Windows));
+
+        cmd.platforms = vec![Platform::Macos];
+        assert!(cmd.for_platform(Platform::Macos).is_ok());
+        let err = cmd.for_platform(Platform::Linux).unwrap_err().to_string();
+        assert!(err.contains("only available on macOS, not Linux"), "{}", err);
+    }
+
+    #[test]
+    fn test_platform_override() {
+        let mut cmd = Command::new("Open Docs".to_string(), "open".to_string())
+            .with_args(vec!["docs/index.html".to_string()]);
+        cmd.set_platform_override(PlatformOverride::new(Platform::Linux, "xdg-open".to_string(), None));
+
+        let linux = cmd.for_platform(Platform::Linux).unwrap();
+        assert_eq!(linux.full_command(), "xdg-open docs/index.html");
+        assert!(linux.platform_overrides.is_empty());
+        assert_eq!(cmd.for_platform(Platform::Macos).unwrap().command, "open");
+    }
+
+    #[test]
+    fn test_platform_variant_env_and_directory() {
+        let mut cmd = Command::new("Build".to_string(), "make".to_string())
+            .with_working_directory("/src".to_string());
+        cmd.add_environment_variable("CC".to_string(), "clang".to_string());
+        cmd.add_environment_variable("JOBS".to_string(), "4".to_string());
+
+        let mut windows = PlatformOverride::new(Platform::Windows, "nmake".to_string(), Some(vec![]));
+        windows.working_directory = Some("C:\\src".to_string());
+        windows.environment_variables.push(EnvironmentVariable {
+            key: "CC".to_string(),
+            value: "cl".to_string(),
+            secret: false,
+        });
+        cmd.set_platform_override(windows.clone());
+        cmd.set_platform_override(windows);
+        assert_eq!(cmd.platform_overrides.len(), 1);
+
+        let resolved = cmd.for_platform(Platform::Windows).unwrap();
+        assert_eq!(resolved.working_directory.as_deref(), Some("C:\\src"));
+        let env: Vec<(&str, &str)> = resolved
+            .environment_variables
+            .iter()
+            .map(|v| (v.key.as_str(), v.value.as_str()))
+            .collect();
+        assert_eq!(env, vec![("JOBS", "4"), ("CC", "cl")]);
+    }
+}
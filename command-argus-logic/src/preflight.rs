@@ -0,0 +1,230 @@
+use std::env;
+use std::fmt;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::expand::expand_env;
+
+/// A condition that must hold before a command is run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Precondition {
+    /// A file or directory exists. Relative paths are taken from the working directory.
+    PathExists { path: String },
+    /// An environment variable is set and not empty, either in the command's own
+    /// environment or in the environment it inherits.
+    EnvSet { name: String },
+    /// Nothing is listening on the TCP port.
+    PortFree {
+        port: u16,
+        #[serde(default = "default_host")]
+        host: String,
+    },
+    /// The filesystem holding `path` has at least `min_bytes` available.
+    DiskSpace { path: String, min_bytes: u64 },
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+impl fmt::Display for Precondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Precondition::PathExists { path } => write!(f, "path {} exists", path),
+            Precondition::EnvSet { name } => write!(f, "${} is set", name),
+            Precondition::PortFree { port, host } => write!(f, "port {}:{} is free", host, port),
+            Precondition::DiskSpace { path, min_bytes } => {
+                write!(f, "{} bytes free on {}", min_bytes, path)
+            }
+        }
+    }
+}
+
+/// A precondition that did not hold, and why.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PreflightFailure {
+    pub check: Precondition,
+    pub message: String,
+}
+
+impl fmt::Display for PreflightFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Evaluates a command's preconditions.
+#[derive(Debug, Default)]
+pub struct PreflightChecker {
+    base_dir: Option<PathBuf>,
+}
+
+impl PreflightChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory that relative paths are resolved against, normally the command's
+    /// working directory.
+    pub fn with_base_dir(mut self, base_dir: PathBuf) -> Self {
+        self.base_dir = Some(base_dir);
+        self
+    }
+
+    /// Returns every precondition of `command` that does not hold; empty if it may run.
+    pub fn check(&self, command: &Command) -> Vec<PreflightFailure> {
+        command
+            .preconditions
+            .iter()
+            .filter_map(|check| {
+                self.evaluate(command, check).err().map(|message| PreflightFailure {
+                    check: check.clone(),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    fn evaluate(&self, command: &Command, check: &Precondition) -> std::result::Result<(), String> {
+        match check {
+            Precondition::PathExists { path } => {
+                let resolved = self.resolve(path);
+                if resolved.exists() {
+                    Ok(())
+                } else {
+                    Err(format!("{} does not exist", resolved.display()))
+                }
+            }
+            Precondition::EnvSet { name } => {
+                let own = command
+                    .environment_variables
+                    .iter()
+                    .any(|var| &var.key == name && !expand_env(&var.value).is_empty());
+                if own || env::var_os(name).is_some_and(|value| !value.is_empty()) {
+                    Ok(())
+                } else {
+                    Err(format!("environment variable {} is not set", name))
+                }
+            }
+            Precondition::PortFree { port, host } => match TcpListener::bind((host.as_str(), *port)) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("port {} on {} is not free: {}", port, host, e)),
+            },
+            Precondition::DiskSpace { path, min_bytes } => {
+                let resolved = self.resolve(path);
+                match available_space(&resolved) {
+                    Ok(available) if available >= *min_bytes => Ok(()),
+                    Ok(available) => Err(format!(
+                        "only {} bytes available on {}, {} required",
+                        available,
+                        resolved.display(),
+                        min_bytes
+                    )),
+                    Err(e) => Err(format!("cannot determine free space on {}: {}", resolved.display(), e)),
+                }
+            }
+        }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        let expanded = PathBuf::from(expand_env(path));
+        match &self.base_dir {
+            Some(base) if expanded.is_relative() => base.join(expanded),
+            _ => expanded,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    if unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::EnvironmentVariable;
+
+    fn command_with(preconditions: Vec<Precondition>) -> Command {
+        let mut cmd = Command::new("Test".to_string(), "true".to_string());
+        cmd.preconditions = preconditions;
+        cmd
+    }
+
+    #[test]
+    fn test_paths_and_disk_space() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("present.txt"), "").unwrap();
+        let checker = PreflightChecker::new().with_base_dir(temp.path().to_path_buf());
+
+        let cmd = command_with(vec![
+            Precondition::PathExists { path: "present.txt".to_string() },
+            Precondition::PathExists { path: "missing.txt".to_string() },
+            Precondition::DiskSpace { path: ".".to_string(), min_bytes: 1 },
+            Precondition::DiskSpace { path: ".".to_string(), min_bytes: u64::MAX },
+        ]);
+        let failures = checker.check(&cmd);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].check, Precondition::PathExists { path: "missing.txt".to_string() });
+        assert!(failures[0].message.contains("missing.txt"));
+        assert!(failures[1].message.contains("required"), "{}", failures[1].message);
+    }
+
+    #[test]
+    fn test_env_and_port() {
+        let mut cmd = command_with(vec![
+            Precondition::EnvSet { name: "ARGUS_PREFLIGHT_TOKEN".to_string() },
+            Precondition::EnvSet { name: "ARGUS_PREFLIGHT_UNSET".to_string() },
+        ]);
+        cmd.environment_variables.push(EnvironmentVariable {
+            key: "ARGUS_PREFLIGHT_TOKEN".to_string(),
+            value: "abc".to_string(),
+            secret: false,
+        });
+        let failures = PreflightChecker::new().check(&cmd);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].message.contains("ARGUS_PREFLIGHT_UNSET"));
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let cmd = command_with(vec![Precondition::PortFree { port, host: default_host() }]);
+        assert_eq!(PreflightChecker::new().check(&cmd).len(), 1);
+        drop(listener);
+        assert!(PreflightChecker::new().check(&cmd).is_empty());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = r#"[{"kind":"port_free","port":8080},{"kind":"env_set","name":"TOKEN"}]"#;
+        let checks: Vec<Precondition> = serde_json::from_str(json).unwrap();
+        assert_eq!(checks[0], Precondition::PortFree { port: 8080, host: "127.0.0.1".to_string() });
+        assert_eq!(checks[1].to_string(), "$TOKEN is set");
+    }
+}
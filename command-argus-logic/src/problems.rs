@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandArgusError, Result};
+use crate::highlight::Severity;
+use crate::history::OutputStream;
+
+/// Problems beyond this many are left out, like a compiler's "too many errors".
+pub const MAX_PROBLEMS: usize = 500;
+
+/// A VS Code style problem matcher: a regex applied to each line of output and the
+/// indexes of the capture groups holding the parts of a problem.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProblemMatcher {
+    pub pattern: String,
+    #[serde(default = "default_file_group")]
+    pub file: usize,
+    #[serde(default)]
+    pub line: Option<usize>,
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// Group holding `error`, `warning`, `info` or `note`. Without one every match
+    /// is an error.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    pub message: usize,
+}
+
+fn default_file_group() -> usize {
+    1
+}
+
+impl ProblemMatcher {
+    /// The matchers VS Code ships as `$tsc`, `$gcc` and `$go`, for tasks that name them.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (pattern, file, line, column, severity, message) = match name {
+            "$tsc" => (r"^([^\s].*)[\(:](\d+)[,:](\d+)(?:\):\s+|\s+-\s+)(error|warning|info)\s+TS\d+\s*:\s*(.*)$", 1, 2, 3, Some(4), 5),
+            "$gcc" => (r"^(.*?):(\d+):(\d*):?\s+(?:fatal\s+)?(warning|error|note):\s+(.*)$", 1, 2, 3, Some(4), 5),
+            "$go" => (r"^([^:]*: )?((.:)?[^:]*):(\d+)(:(\d+))?: (.*)$", 2, 4, 6, None, 7),
+            _ => return None,
+        };
+        Some(Self {
+            pattern: pattern.to_string(),
+            file,
+            line: Some(line),
+            column: Some(column),
+            severity,
+            message,
+        })
+    }
+}
+
+/// An error or warning reported by a tool, pointing at a place in a file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Problem {
+    /// The file as the tool named it.
+    pub file: String,
+    /// Absolute path of the file, when it exists.
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+    pub stream: OutputStream,
+    /// 1-based line of the output the problem was reported on.
+    pub output_line: usize,
+}
+
+/// Applies problem matchers to captured output.
+pub struct ProblemFinder {
+    matchers: Vec<(Regex, ProblemMatcher)>,
+}
+
+impl ProblemFinder {
+    /// Fails on the first matcher whose pattern is not a valid regex.
+    pub fn new(matchers: &[ProblemMatcher]) -> Result<Self> {
+        let matchers = matchers
+            .iter()
+            .map(|matcher| {
+                Regex::new(&matcher.pattern)
+                    .map(|regex| (regex, matcher.clone()))
+                    .map_err(|e| {
+                        CommandArgusError::InvalidQuery(format!("Invalid problem pattern '{}': {}", matcher.pattern, e))
+                    })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { matchers })
+    }
+
+    /// The problems reported in `text`. A line is matched by the first matcher that
+    /// finds a file and a message in it; relative files are looked up in `base_dir`.
+    pub fn find(&self, text: &str, stream: OutputStream, base_dir: Option<&Path>) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if problems.len() == MAX_PROBLEMS {
+                break;
+            }
+            let found = self.matchers.iter().find_map(|(regex, matcher)| {
+                let captures = regex.captures(line)?;
+                let group = |index: usize| captures.get(index).map(|m| m.as_str().trim()).filter(|s| !s.is_empty());
+                let file = group(matcher.file)?.to_string();
+                let message = group(matcher.message)?.to_string();
+                Some(Problem {
+                    path: resolve_file(&file, base_dir),
+                    file,
+                    line: number(&captures, matcher.line),
+                    column: number(&captures, matcher.column),
+                    severity: matcher.severity.and_then(group).map_or(Severity::Error, parse_severity),
+                    message,
+                    stream,
+                    output_line: index + 1,
+                })
+            });
+            problems.extend(found);
+        }
+        problems
+    }
+}
+
+fn number(captures: &Captures, group: Option<usize>) -> Option<usize> {
+    group.and_then(|i| captures.get(i)).and_then(|m| m.as_str().parse().ok())
+}
+
+fn parse_severity(text: &str) -> Severity {
+    match text.to_lowercase().as_str() {
+        "warning" | "warn" => Severity::Warning,
+        "info" | "note" | "hint" => Severity::Info,
+        _ => Severity::Error,
+    }
+}
+
+fn resolve_file(file: &str, base_dir: Option<&Path>) -> Option<String> {
+    let path = Path::new(file);
+    let path = match (path.is_absolute(), base_dir) {
+        (true, _) => path.to_path_buf(),
+        (false, Some(base)) => base.join(path),
+        (false, None) => return None,
+    };
+    path.is_file()
+        .then(|| path.canonicalize().unwrap_or(path).to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_problems() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/app.ts"), "").unwrap();
+
+        let finder = ProblemFinder::new(&[
+            ProblemMatcher::builtin("$tsc").unwrap(),
+            ProblemMatcher::builtin("$gcc").unwrap(),
+        ])
+        .unwrap();
+        let output = "src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+                      Found 1 error.\n\
+                      main.c:3:10: warning: unused variable 'x'\n";
+        let problems = finder.find(output, OutputStream::Stdout, Some(&root));
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].file, "src/app.ts");
+        assert_eq!(problems[0].path.as_deref(), Some(root.join("src/app.ts").to_string_lossy().as_ref()));
+        assert_eq!((problems[0].line, problems[0].column), (Some(12), Some(5)));
+        assert_eq!(problems[0].severity, Severity::Error);
+        assert_eq!(problems[0].message, "Type 'string' is not assignable to type 'number'.");
+        assert_eq!((problems[1].file.as_str(), problems[1].output_line), ("main.c", 3));
+        assert_eq!(problems[1].severity, Severity::Warning);
+        assert_eq!(problems[1].path, None);
+
+        let custom = ProblemFinder::new(&[ProblemMatcher {
+            pattern: r"^(\S+) line (\d+): (.*)$".to_string(),
+            file: 1,
+            line: Some(2),
+            column: None,
+            severity: None,
+            message: 3,
+        }])
+        .unwrap();
+        let problems = custom.find("lint.sh line 7: missing quote", OutputStream::Stderr, None);
+        assert_eq!((problems[0].line, problems[0].column), (Some(7), None));
+        assert_eq!(problems[0].severity, Severity::Error);
+
+        let invalid = ProblemMatcher {
+            pattern: "(".to_string(),
+            ..ProblemMatcher::builtin("$gcc").unwrap()
+        };
+        assert!(ProblemFinder::new(&[invalid]).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::command::{Command, EnvironmentVariable};
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::storage::data_dir;
+
+/// A named environment such as `dev`, `staging` or `prod`, chosen when a command is run.
+/// Its environment variables are added to the command's and its variables override
+/// workspace and global ones, so one saved command can target several environments.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Added to the command's variables, replacing any with the same key.
+    #[serde(default)]
+    pub environment_variables: Vec<EnvironmentVariable>,
+    /// Values for `{{var:NAME}}` references.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Profile {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            description: None,
+            environment_variables: Vec::new(),
+            variables: BTreeMap::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// `command` with the profile's environment variables applied.
+    pub fn apply(&self, command: &Command) -> Command {
+        let mut applied = command.clone();
+        for var in &self.environment_variables {
+            applied.environment_variables.retain(|v| v.key != var.key);
+            applied.environment_variables.push(var.clone());
+        }
+        applied
+    }
+}
+
+/// Fails if `command` declares the profiles it runs with and `profile` is not one of them,
+/// or if `profile` is forbidden for the command. A forbidden profile is allowed when
+/// `override_confirmation` is exactly the command's name, as for destructive commands.
+pub fn check_profile(command: &Command, profile: Option<&Profile>, override_confirmation: Option<&str>) -> Result<()> {
+    if let Some(profile) = profile.filter(|p| command.forbidden_profiles.contains(&p.name)) {
+        if override_confirmation == Some(command.name.as_str()) {
+            return Ok(());
+        }
+        return Err(CommandArgusError::WrongProfile(format!(
+            "'{}' must not run with the profile '{}'; type its name to override",
+            command.name, profile.name
+        )));
+    }
+    if command.profiles.is_empty() || profile.is_some_and(|p| command.profiles.contains(&p.name)) {
+        return Ok(());
+    }
+    let running = match profile {
+        Some(profile) => format!("not with '{}'", profile.name),
+        None => "but none was chosen".to_string(),
+    };
+    Err(CommandArgusError::WrongProfile(format!(
+        "'{}' runs with the profile {}, {}",
+        command.name,
+        command.profiles.join(" or "),
+        running
+    )))
+}
+
+/// Environment profiles, persisted as `profiles.json` in the data directory.
+pub struct ProfileStorage {
+    path: PathBuf,
+}
+
+impl ProfileStorage {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("profiles.json"),
+        })
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn list(&self) -> Result<Vec<Profile>> {
+        self.load_all()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Profile> {
+        self.load_all()?
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| CommandArgusError::WrongProfile(format!("there is no profile named '{}'", name)))
+    }
+
+    /// Creates the profile, or replaces the one with the same name.
+    pub fn save(&self, _access: EditAccess, mut profile: Profile) -> Result<Profile> {
+        if profile.name.trim().is_empty() {
+            return Err(CommandArgusError::InvalidCommand("profiles need a name".to_string()));
+        }
+        let mut profiles = self.load_all()?;
+        profiles.retain(|p| p.name != profile.name);
+        profile.updated_at = Utc::now();
+        profiles.push(profile.clone());
+        self.save_all(&profiles)?;
+        Ok(profile)
+    }
+
+    /// Returns whether a profile named `name` existed.
+    pub fn delete(&self, _access: EditAccess, name: &str) -> Result<bool> {
+        let mut profiles = self.load_all()?;
+        let initial_len = profiles.len();
+        profiles.retain(|p| p.name != name);
+        if profiles.len() == initial_len {
+            return Ok(false);
+        }
+        self.save_all(&profiles)?;
+        Ok(true)
+    }
+
+    fn load_all(&self) -> Result<Vec<Profile>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_all(&self, profiles: &[Profile]) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(profiles)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profiles() {
+        let temp = TempDir::new().unwrap();
+        let storage = ProfileStorage::with_path(temp.path().join("profiles.json")).unwrap();
+        let mut prod = Profile::new("prod".to_string());
+        prod.environment_variables.push(EnvironmentVariable {
+            key: "AWS_PROFILE".to_string(),
+            value: "prod-admin".to_string(),
+            secret: false,
+        });
+        storage.save(EditAccess::unrestricted(), prod).unwrap();
+        storage.save(EditAccess::unrestricted(), Profile::new("dev".to_string())).unwrap();
+        assert!(storage.save(EditAccess::unrestricted(), Profile::new(" ".to_string())).is_err());
+        assert_eq!(storage.list().unwrap().len(), 2);
+
+        let prod = storage.get("prod").unwrap();
+        let mut command = Command::new("Deploy".to_string(), "deploy.sh".to_string());
+        command.environment_variables.push(EnvironmentVariable {
+            key: "AWS_PROFILE".to_string(),
+            value: "default".to_string(),
+            secret: false,
+        });
+        let applied = prod.apply(&command);
+        assert_eq!(applied.environment_variables.len(), 1);
+        assert_eq!(applied.environment_variables[0].value, "prod-admin");
+
+        assert!(check_profile(&command, None, None).is_ok());
+        command.profiles = vec!["staging".to_string(), "prod".to_string()];
+        assert!(check_profile(&command, Some(&prod), None).is_ok());
+        assert!(matches!(
+            check_profile(&command, Some(&storage.get("dev").unwrap()), None),
+            Err(CommandArgusError::WrongProfile(message))
+                if message == "'Deploy' runs with the profile staging or prod, not with 'dev'"
+        ));
+        assert!(check_profile(&command, None, None).is_err());
+
+        command.forbidden_profiles = vec!["prod".to_string()];
+        assert!(matches!(
+            check_profile(&command, Some(&prod), None),
+            Err(CommandArgusError::WrongProfile(message))
+                if message == "'Deploy' must not run with the profile 'prod'; type its name to override"
+        ));
+        assert!(check_profile(&command, Some(&prod), Some("deploy")).is_err());
+        assert!(check_profile(&command, Some(&prod), Some("Deploy")).is_ok());
+
+        assert!(storage.delete(EditAccess::unrestricted(), "dev").unwrap());
+        assert!(!storage.delete(EditAccess::unrestricted(), "dev").unwrap());
+        assert!(storage.get("dev").is_err());
+    }
+}
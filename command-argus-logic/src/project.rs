@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::{Command, CommandParameter, CommandSource, EnvironmentVariable};
+use crate::error::{CommandArgusError, Result};
+use crate::open_output::OpenOutput;
+use crate::problems::ProblemMatcher;
+use crate::platform::{Platform, PlatformOverride};
+use crate::scratch::ScratchDirPolicy;
+
+pub const PROJECT_FILE_NAME: &str = ".argus.yaml";
+
+/// Files and folders whose presence marks the root of a project.
+pub const PROJECT_ROOT_MARKERS: &[&str] = &[".git", "Cargo.toml", "package.json"];
+
+/// Built-in placeholder for the root of the project a command runs in. It is filled in
+/// when the command runs rather than asked for like a parameter.
+pub const PROJECT_ROOT_PLACEHOLDER: &str = "project_root";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ProjectFile {
+    #[serde(default)]
+    commands: Vec<ProjectCommandSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ProjectCommandSpec {
+    name: String,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+    /// Relative paths are resolved against the project root.
+    #[serde(default)]
+    working_directory: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    parameters: Vec<CommandParameter>,
+    #[serde(default)]
+    mise_enabled: bool,
+    #[serde(default)]
+    platforms: Vec<Platform>,
+    /// Per-platform program and arguments, e.g. `windows: { command: start }`.
+    #[serde(default)]
+    variants: BTreeMap<Platform, ProjectVariantSpec>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    maintainer_note: Option<String>,
+    #[serde(default)]
+    profiles: Vec<String>,
+    #[serde(default)]
+    forbidden_profiles: Vec<String>,
+    #[serde(default)]
+    collect_artifacts: Vec<String>,
+    #[serde(default)]
+    scratch_dir: ScratchDirPolicy,
+    #[serde(default)]
+    stdin_template: Option<String>,
+    #[serde(default)]
+    record_stdin: bool,
+    #[serde(default)]
+    capture_timeline: bool,
+    #[serde(default)]
+    phase_marker: Option<String>,
+    #[serde(default)]
+    open_output_with: Option<OpenOutput>,
+    #[serde(default)]
+    problem_matchers: Vec<ProblemMatcher>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ProjectVariantSpec {
+    command: String,
+    #[serde(default)]
+    args: Option<Vec<String>>,
+    /// Relative paths are resolved against the project root.
+    #[serde(default)]
+    working_directory: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// Commands defined in a repository's `.argus.yaml`. They are overlaid on the
+/// library while the project is open and are never written to the command store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectCommandSet {
+    pub root: PathBuf,
+    pub commands: Vec<Command>,
+}
+
+impl ProjectCommandSet {
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.commands.iter().any(|c| c.id == id)
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&Command> {
+        self.commands.iter().find(|c| c.id == id)
+    }
+}
+
+/// Loads the project-local commands of the folder at `path`.
+///
+/// Ids are derived from the project root and command name, so they stay the same
+/// every time the project is opened.
+pub fn load_project_commands(path: &Path) -> Result<ProjectCommandSet> {
+    let file = path.join(PROJECT_FILE_NAME);
+    if !file.exists() {
+        return Err(CommandArgusError::InvalidPath(file.display().to_string()));
+    }
+    let content = fs::read_to_string(&file)?;
+    parse_project_commands(&content, path)
+}
+
+pub fn parse_project_commands(content: &str, root: &Path) -> Result<ProjectCommandSet> {
+    let project: ProjectFile = serde_yaml::from_str(content)?;
+    let root_key = root.to_string_lossy();
+
+    let mut commands: Vec<Command> = Vec::new();
+    for spec in project.commands {
+        if commands.iter().any(|c| c.name == spec.name) {
+            return Err(CommandArgusError::DuplicateName(spec.name));
+        }
+
+        let mut command = Command::new(spec.name, spec.command)
+            .with_args(spec.args)
+            .with_mise_enabled(spec.mise_enabled);
+        command.id = Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            format!("{}#{}", root_key, command.name).as_bytes(),
+        );
+        if let Some(description) = spec.description {
+            command = command.with_description(description);
+        }
+        let working_directory = match spec.working_directory {
+            Some(dir) => root.join(dir),
+            None => root.to_path_buf(),
+        };
+        command = command.with_working_directory(working_directory.to_string_lossy().to_string());
+        for (key, value) in spec.env {
+            command.add_environment_variable(key, value);
+        }
+        for tag in spec.tags {
+            command.add_tag(tag);
+        }
+        command.parameters = spec.parameters;
+        command.platforms = spec.platforms;
+        command.author = spec.author;
+        command.maintainer_note = spec.maintainer_note;
+        command.profiles = spec.profiles;
+        command.forbidden_profiles = spec.forbidden_profiles;
+        command.collect_artifacts = spec.collect_artifacts;
+        command.scratch_dir = spec.scratch_dir;
+        command.stdin_template = spec.stdin_template;
+        command.record_stdin = spec.record_stdin;
+        command.capture_timeline = spec.capture_timeline;
+        command.phase_marker = spec.phase_marker;
+        command.open_output_with = spec.open_output_with;
+        command.problem_matchers = spec.problem_matchers;
+        command.source = CommandSource::Imported {
+            from: root.join(PROJECT_FILE_NAME).to_string_lossy().to_string(),
+        };
+        for (platform, variant) in spec.variants {
+            let mut platform_override = PlatformOverride::new(platform, variant.command, variant.args);
+            platform_override.working_directory = variant
+                .working_directory
+                .map(|dir| root.join(dir).to_string_lossy().to_string());
+            platform_override.environment_variables = variant
+                .env
+                .into_iter()
+                .map(|(key, value)| EnvironmentVariable { key, value, secret: false })
+                .collect();
+            command.set_platform_override(platform_override);
+        }
+        commands.push(command);
+    }
+
+    Ok(ProjectCommandSet {
+        root: root.to_path_buf(),
+        commands,
+    })
+}
+
+/// The nearest folder at or above `path`, a file or a folder, that contains one of the
+/// [`PROJECT_ROOT_MARKERS`]. In a Cargo or npm workspace that is the member's folder.
+pub fn find_project_root(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    let start = if path.is_file() { path.parent()? } else { &path };
+    start
+        .ancestors()
+        .find(|dir| PROJECT_ROOT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = "
+commands:
+  - name: Test
+    command: cargo
+    args: [test]
+    working_directory: crates/core
+    env:
+      RUST_BACKTRACE: '1'
+    tags: [rust]
+  - name: Serve
+    command: npm
+    args: [run, dev]
+  - name: Docs
+    command: open
+    args: [target/doc/index.html]
+    variants:
+      linux: {command: xdg-open}
+      windows: {command: cmd, args: [/C, start, target\\doc\\index.html]}
+";
+
+    #[test]
+    fn test_parse_project_commands() {
+        let set = parse_project_commands(PROJECT, Path::new("/repo")).unwrap();
+        assert_eq!(set.commands.len(), 3);
+
+        let test = &set.commands[0];
+        assert_eq!(test.full_command(), "cargo test");
+        assert_eq!(test.working_directory.as_deref(), Some("/repo/crates/core"));
+        assert_eq!(test.environment_variables[0].value, "1");
+        assert_eq!(test.tags, vec!["rust"]);
+        assert_eq!(set.commands[1].working_directory.as_deref(), Some("/repo"));
+        assert!(set.contains(test.id));
+        assert_eq!(test.source, CommandSource::Imported { from: "/repo/.argus.yaml".to_string() });
+    }
+
+    #[test]
+    fn test_platform_variants() {
+        let set = parse_project_commands(PROJECT, Path::new("/repo")).unwrap();
+        let docs = &set.commands[2];
+        assert_eq!(
+            docs.for_platform(Platform::Linux).unwrap().full_command(),
+            "xdg-open target/doc/index.html"
+        );
+        assert_eq!(
+            docs.for_platform(Platform::Windows).unwrap().full_command(),
+            "cmd /C start target\\doc\\index.html"
+        );
+        assert_eq!(docs.for_platform(Platform::Macos).unwrap().command, "open");
+    }
+
+    #[test]
+    fn test_ids_are_stable() {
+        let first = parse_project_commands(PROJECT, Path::new("/repo")).unwrap();
+        let second = parse_project_commands(PROJECT, Path::new("/repo")).unwrap();
+        let other = parse_project_commands(PROJECT, Path::new("/other")).unwrap();
+        assert_eq!(first.commands[0].id, second.commands[0].id);
+        assert_ne!(first.commands[0].id, other.commands[0].id);
+    }
+
+    #[test]
+    fn test_duplicate_names_rejected() {
+        let content = "commands:\n  - {name: A, command: ls}\n  - {name: A, command: pwd}\n";
+        assert!(matches!(
+            parse_project_commands(content, Path::new("/repo")),
+            Err(CommandArgusError::DuplicateName(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_project_root() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("web/src")).unwrap();
+        fs::write(root.join("web/package.json"), "{}").unwrap();
+        fs::write(root.join("web/src/main.ts"), "").unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+
+        assert_eq!(find_project_root(&root.join("web/src/main.ts")), Some(root.join("web")));
+        assert_eq!(find_project_root(&root.join("web")), Some(root.join("web")));
+        assert_eq!(find_project_root(&root.join("docs")), Some(root.clone()));
+        assert_eq!(find_project_root(&root.join("missing")), None);
+    }
+}
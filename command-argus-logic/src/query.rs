@@ -0,0 +1,367 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::impact::Impact;
+use crate::storage::CommandStorage;
+
+/// A parsed search query such as `tag:deploy env:staging used:>10 updated:<30d name:~build`.
+///
+/// Terms are separated by whitespace and all have to match. Supported terms:
+///
+/// - `tag:x` has the tag `x`
+/// - `env:KEY` / `env:KEY=VALUE` sets the environment variable, `env:value` also matches
+///   a variable with that value
+/// - `name:x` / `command:x` is exactly `x`, `name:~x` / `command:~x` contains `x`;
+///   `command` is matched against the full command line
+/// - `used:>10` compares the use count with `>`, `>=`, `<`, `<=` or `=` (the default)
+/// - `updated:<30d`, `created:>1w` and `last_used:>90d` compare the age with units
+///   `h`, `d` or `w`; a command that was never used is older than any age
+/// - `impact:caution`, `source:synced` and `author:alice` match those fields
+/// - any other word is searched for in the name
+///
+/// Values containing spaces are quoted (`name:"db backup"`), a leading `-` negates a
+/// term, and matching is case-insensitive throughout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Text(String),
+    Tag(String),
+    Env { key: String, value: Option<String> },
+    Name(TextMatch),
+    Command(TextMatch),
+    Used(Comparison, u32),
+    Updated(Comparison, Duration),
+    Created(Comparison, Duration),
+    LastUsed(Comparison, Duration),
+    Impact(Impact),
+    Source(String),
+    Author(String),
+}
+
+/// A term every match must satisfy, which a `SearchIndex` can look up.
+pub(crate) enum IndexHint<'a> {
+    /// The name contains this lowercase text.
+    NameContains(&'a str),
+    /// The command has this lowercase tag.
+    Tag(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TextMatch {
+    Exact(String),
+    Contains(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self> {
+        let terms = tokenize(query)?
+            .into_iter()
+            .map(|(token, quoted)| parse_term(&token, quoted))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { terms })
+    }
+
+    /// True for the empty query, which matches everything.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, command: &Command) -> bool {
+        self.matches_at(command, Utc::now())
+    }
+
+    /// Like `matches`, with ages measured from `now`.
+    pub fn matches_at(&self, command: &Command, now: DateTime<Utc>) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(command, now) != term.negated)
+    }
+
+    pub fn filter(&self, commands: Vec<Command>) -> Vec<Command> {
+        let now = Utc::now();
+        commands.into_iter().filter(|c| self.matches_at(c, now)).collect()
+    }
+
+    pub(crate) fn index_hints(&self) -> Vec<IndexHint<'_>> {
+        self.terms
+            .iter()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.filter {
+                Filter::Text(text)
+                | Filter::Name(TextMatch::Exact(text))
+                | Filter::Name(TextMatch::Contains(text)) => Some(IndexHint::NameContains(text)),
+                Filter::Tag(tag) => Some(IndexHint::Tag(tag)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl CommandStorage {
+    pub fn search_by_query(&self, query: &str) -> Result<Vec<Command>> {
+        let query = Query::parse(query)?;
+        self.with_index(|index| index.search(&query))
+    }
+}
+
+impl Filter {
+    fn matches(&self, command: &Command, now: DateTime<Utc>) -> bool {
+        match self {
+            Filter::Text(text) => command.name.to_lowercase().contains(text),
+            Filter::Tag(tag) => command.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Filter::Env { key, value } => command.environment_variables.iter().any(|var| {
+                let var_value = var.value.to_lowercase();
+                match value {
+                    Some(value) => var.key.to_lowercase() == *key && var_value == *value,
+                    // A bare `env:staging` is either the variable's name or its value
+                    None => var.key.to_lowercase() == *key || var_value == *key,
+                }
+            }),
+            Filter::Name(text) => text.matches(&command.name),
+            Filter::Command(text) => text.matches(&command.full_command()),
+            Filter::Used(comparison, count) => comparison.holds(command.use_count.cmp(count)),
+            Filter::Updated(comparison, age) => comparison.holds(compare_age(now, command.updated_at, age)),
+            Filter::Created(comparison, age) => comparison.holds(compare_age(now, command.created_at, age)),
+            Filter::LastUsed(comparison, age) => match command.last_used_at {
+                Some(last_used_at) => comparison.holds(compare_age(now, last_used_at, age)),
+                None => comparison.holds(std::cmp::Ordering::Greater),
+            },
+            Filter::Impact(impact) => command.impact == *impact,
+            Filter::Source(kind) => command.source.kind() == kind,
+            Filter::Author(author) => command.author.as_deref().is_some_and(|a| a.to_lowercase() == *author),
+        }
+    }
+}
+
+// How long ago `at` was compared to `age`; an age reaching back past the earliest
+// representable time is longer than anything
+fn compare_age(now: DateTime<Utc>, at: DateTime<Utc>, age: &Duration) -> std::cmp::Ordering {
+    match now.checked_sub_signed(*age) {
+        Some(cutoff) => cutoff.cmp(&at),
+        None => std::cmp::Ordering::Less,
+    }
+}
+
+impl TextMatch {
+    fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        match self {
+            TextMatch::Exact(expected) => text == *expected,
+            TextMatch::Contains(part) => text.contains(part),
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            Comparison::Less => ordering == Less,
+            Comparison::LessOrEqual => ordering != Greater,
+            Comparison::Equal => ordering == Equal,
+            Comparison::GreaterOrEqual => ordering != Less,
+            Comparison::Greater => ordering == Greater,
+        }
+    }
+
+    // Splits the operator off the front of `value`
+    fn split(value: &str) -> (Self, &str) {
+        for (operator, comparison) in [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+        ] {
+            if let Some(rest) = value.strip_prefix(operator) {
+                return (comparison, rest);
+            }
+        }
+        (Comparison::Equal, value)
+    }
+}
+
+// Splits the query at whitespace outside double quotes. Each token is returned
+// unquoted, with whether it was quoted as a whole.
+pub(crate) fn tokenize(query: &str) -> Result<Vec<(String, bool)>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                if !in_quotes && current.is_empty() {
+                    quoted = true;
+                }
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() || quoted {
+                    tokens.push((std::mem::take(&mut current), quoted));
+                }
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(CommandArgusError::InvalidQuery("unterminated quote".to_string()));
+    }
+    if !current.is_empty() || quoted {
+        tokens.push((current, quoted));
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str, quoted: bool) -> Result<Term> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) if !quoted && !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let token = token.to_lowercase();
+    let filter = match token.split_once(':') {
+        Some((key, value)) if !quoted => parse_filter(key, value)?,
+        _ => Filter::Text(token),
+    };
+    Ok(Term { negated, filter })
+}
+
+fn parse_filter(key: &str, value: &str) -> Result<Filter> {
+    if value.is_empty() {
+        return Err(CommandArgusError::InvalidQuery(format!("'{}:' needs a value", key)));
+    }
+    let text_match = || match value.strip_prefix('~') {
+        Some(part) => TextMatch::Contains(part.to_string()),
+        None => TextMatch::Exact(value.to_string()),
+    };
+    Ok(match key {
+        "tag" => Filter::Tag(value.to_string()),
+        "env" => match value.split_once('=') {
+            Some((key, value)) => Filter::Env { key: key.to_string(), value: Some(value.to_string()) },
+            None => Filter::Env { key: value.to_string(), value: None },
+        },
+        "name" => Filter::Name(text_match()),
+        "command" | "cmd" => Filter::Command(text_match()),
+        "used" => {
+            let (comparison, count) = Comparison::split(value);
+            let count = count
+                .parse()
+                .map_err(|_| CommandArgusError::InvalidQuery(format!("'{}' is not a use count", count)))?;
+            Filter::Used(comparison, count)
+        }
+        "updated" | "created" | "last_used" => {
+            let (comparison, age) = Comparison::split(value);
+            let age = parse_age(age)?;
+            match key {
+                "updated" => Filter::Updated(comparison, age),
+                "created" => Filter::Created(comparison, age),
+                _ => Filter::LastUsed(comparison, age),
+            }
+        }
+        "impact" => Filter::Impact(
+            serde_json::from_value(serde_json::Value::String(value.to_string()))
+                .map_err(|_| CommandArgusError::InvalidQuery(format!("unknown impact '{}'", value)))?,
+        ),
+        "source" => Filter::Source(value.to_string()),
+        "author" => Filter::Author(value.to_string()),
+        _ => return Err(CommandArgusError::InvalidQuery(format!("unknown filter '{}'", key))),
+    })
+}
+
+// Parses an age such as `30d`, `12h` or `2w`
+fn parse_age(age: &str) -> Result<Duration> {
+    let invalid = || CommandArgusError::InvalidQuery(format!("'{}' is not an age like 30d, 12h or 2w", age));
+    let split = age.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = age[..split].parse().map_err(|_| invalid())?;
+    let duration = match &age[split..] {
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => return Err(invalid()),
+    };
+    duration.ok_or_else(|| CommandArgusError::InvalidQuery(format!("'{}' is too long ago", age)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Command {
+        let mut cmd = Command::new("Build Frontend".to_string(), "npm".to_string())
+            .with_args(vec!["run".to_string(), "build".to_string()]);
+        cmd.add_tag("Deploy".to_string());
+        cmd.add_environment_variable("TARGET".to_string(), "staging".to_string());
+        cmd.use_count = 12;
+        cmd.updated_at = Utc::now() - Duration::days(3);
+        cmd
+    }
+
+    fn matches(query: &str, cmd: &Command) -> bool {
+        Query::parse(query).unwrap().matches(cmd)
+    }
+
+    #[test]
+    fn test_example_query() {
+        let cmd = sample();
+        assert!(matches("tag:deploy env:staging used:>10 updated:<30d name:~build", &cmd));
+        assert!(!matches("used:>12", &cmd));
+        assert!(matches("used:>=12 env:target=staging", &cmd));
+        assert!(!matches("updated:>30d", &cmd));
+        assert!(!matches("name:build", &cmd));
+        assert!(matches("name:\"build frontend\" command:~\"run build\"", &cmd));
+        assert!(Query::parse("").unwrap().matches(&cmd));
+    }
+
+    #[test]
+    fn test_negation_text_and_never_used() {
+        let cmd = sample();
+        assert!(matches("front -tag:prod", &cmd));
+        assert!(!matches("-tag:deploy", &cmd));
+        assert!(matches("last_used:>90d impact:safe source:manual", &cmd));
+        assert!(!matches("last_used:<90d", &cmd));
+        // A quoted term is plain text, even with a colon in it
+        assert!(!matches("\"tag:deploy\"", &cmd));
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        for query in ["colour:red", "used:>many", "updated:<30y", "tag:", "name:\"open", "impact:scary"] {
+            assert!(
+                matches!(Query::parse(query), Err(CommandArgusError::InvalidQuery(_))),
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_huge_ages() {
+        // Too large for a duration
+        assert!(matches!(Query::parse("updated:<99999999999999w"), Err(CommandArgusError::InvalidQuery(_))));
+        // A duration, but reaching back before the earliest representable time
+        let cmd = sample();
+        assert!(matches("updated:<10000000000w created:<10000000000w", &cmd));
+        assert!(!matches("updated:>10000000000w", &cmd));
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+
+/// An execution waiting for a free slot, as reported to listeners.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuedExecution {
+    pub id: Uuid,
+    pub command_id: Uuid,
+    pub command_name: String,
+    pub priority: i32,
+    /// Zero-based position; 0 runs next.
+    pub position: usize,
+    pub mutex_group: Option<String>,
+    /// Why the execution cannot start although a slot may be free, e.g.
+    /// "waiting on lock held by Apply staging".
+    pub waiting_on: Option<String>,
+}
+
+type Listener = Box<dyn Fn(&[QueuedExecution]) + Send + Sync>;
+
+struct QueueState {
+    max_concurrent: usize,
+    running: usize,
+    /// Ordered by descending priority, then arrival.
+    waiting: Vec<QueuedExecution>,
+    cancelled: HashSet<Uuid>,
+    /// Mutex groups of running executions, with the name of the command holding each.
+    held_groups: HashMap<String, String>,
+}
+
+impl QueueState {
+    /// The first waiting execution whose mutex group is free. Executions blocked on a
+    /// group do not hold up the ones behind them.
+    fn next_runnable(&self) -> Option<&QueuedExecution> {
+        self.waiting.iter().find(|entry| {
+            entry.mutex_group.as_ref().is_none_or(|group| !self.held_groups.contains_key(group))
+        })
+    }
+}
+
+/// Limits how many commands run at once. Executions wait for a slot in priority order,
+/// first come first served within the same priority, and can be cancelled while waiting.
+/// Commands sharing a `mutex_group` never run at the same time.
+pub struct ExecutionQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    listener: Mutex<Option<Listener>>,
+}
+
+/// A running execution's slot, released when dropped.
+pub struct ExecutionSlot<'a> {
+    queue: &'a ExecutionQueue,
+    mutex_group: Option<String>,
+}
+
+impl ExecutionQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                max_concurrent: max_concurrent.max(1),
+                running: 0,
+                waiting: Vec::new(),
+                cancelled: HashSet::new(),
+                held_groups: HashMap::new(),
+            }),
+            changed: Condvar::new(),
+            listener: Mutex::new(None),
+        }
+    }
+
+    /// Called with the waiting executions whenever the queue changes.
+    pub fn set_listener(&self, listener: impl Fn(&[QueuedExecution]) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Box::new(listener));
+    }
+
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.lock().max_concurrent = max_concurrent.max(1);
+        self.changed.notify_all();
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.lock().max_concurrent
+    }
+
+    pub fn running(&self) -> usize {
+        self.lock().running
+    }
+
+    pub fn waiting(&self) -> Vec<QueuedExecution> {
+        self.lock().waiting.clone()
+    }
+
+    /// Adds `command` to the queue and returns the id to wait on or cancel with.
+    pub fn enqueue(&self, command: &Command) -> Uuid {
+        let id = Uuid::new_v4();
+        let snapshot = {
+            let mut state = self.lock();
+            // Insert after everything with the same or a higher priority
+            let index = state
+                .waiting
+                .iter()
+                .position(|entry| entry.priority < command.priority)
+                .unwrap_or(state.waiting.len());
+            state.waiting.insert(
+                index,
+                QueuedExecution {
+                    id,
+                    command_id: command.id,
+                    command_name: command.name.clone(),
+                    priority: command.priority,
+                    position: index,
+                    mutex_group: command.mutex_group.clone(),
+                    waiting_on: None,
+                },
+            );
+            Self::renumber(&mut state)
+        };
+        self.notify(&snapshot);
+        id
+    }
+
+    /// Blocks until the queued execution `id` may run. Fails if it was cancelled meanwhile.
+    pub fn wait(&self, id: Uuid) -> Result<ExecutionSlot<'_>> {
+        let mut state = self.lock();
+        loop {
+            if state.cancelled.remove(&id) {
+                return Err(CommandArgusError::Cancelled("execution was removed from the queue".to_string()));
+            }
+            let Some(index) = state.waiting.iter().position(|entry| entry.id == id) else {
+                return Err(CommandArgusError::Cancelled(format!("no queued execution {}", id)));
+            };
+            let is_next = state.next_runnable().is_some_and(|entry| entry.id == id);
+            if is_next && state.running < state.max_concurrent {
+                let entry = state.waiting.remove(index);
+                state.running += 1;
+                if let Some(group) = &entry.mutex_group {
+                    state.held_groups.insert(group.clone(), entry.command_name.clone());
+                }
+                let snapshot = Self::renumber(&mut state);
+                drop(state);
+                // Others may be able to start as well if several slots are free
+                self.changed.notify_all();
+                self.notify(&snapshot);
+                return Ok(ExecutionSlot {
+                    queue: self,
+                    mutex_group: entry.mutex_group,
+                });
+            }
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    /// Enqueues `command` and waits for its turn.
+    pub fn acquire(&self, command: &Command) -> Result<ExecutionSlot<'_>> {
+        let id = self.enqueue(command);
+        self.wait(id)
+    }
+
+    /// Removes a waiting execution; its `wait` returns a `Cancelled` error. Returns false
+    /// if it is not waiting (already running, finished or unknown).
+    pub fn cancel(&self, id: Uuid) -> bool {
+        let snapshot = {
+            let mut state = self.lock();
+            let Some(index) = state.waiting.iter().position(|entry| entry.id == id) else {
+                return false;
+            };
+            state.waiting.remove(index);
+            state.cancelled.insert(id);
+            Self::renumber(&mut state)
+        };
+        self.changed.notify_all();
+        self.notify(&snapshot);
+        true
+    }
+
+    fn release(&self, mutex_group: Option<&str>) {
+        let snapshot = {
+            let mut state = self.lock();
+            state.running -= 1;
+            if let Some(group) = mutex_group {
+                state.held_groups.remove(group);
+            }
+            Self::renumber(&mut state)
+        };
+        self.changed.notify_all();
+        self.notify(&snapshot);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap()
+    }
+
+    fn renumber(state: &mut QueueState) -> Vec<QueuedExecution> {
+        let held_groups = &state.held_groups;
+        for (position, entry) in state.waiting.iter_mut().enumerate() {
+            entry.position = position;
+            entry.waiting_on = entry
+                .mutex_group
+                .as_ref()
+                .and_then(|group| held_groups.get(group))
+                .map(|holder| format!("waiting on lock held by {}", holder));
+        }
+        state.waiting.clone()
+    }
+
+    fn notify(&self, snapshot: &[QueuedExecution]) {
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(snapshot);
+        }
+    }
+}
+
+impl Drop for ExecutionSlot<'_> {
+    fn drop(&mut self) {
+        self.queue.release(self.mutex_group.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn command(name: &str, priority: i32) -> Command {
+        let mut cmd = Command::new(name.to_string(), "true".to_string());
+        cmd.priority = priority;
+        cmd
+    }
+
+    // Polls until `condition` holds, failing the test after a few seconds.
+    fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("condition not reached");
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let queue = ExecutionQueue::new(1);
+        let _running = queue.acquire(&command("first", 0)).unwrap();
+
+        queue.enqueue(&command("low", 0));
+        queue.enqueue(&command("high", 5));
+        queue.enqueue(&command("low again", 0));
+        let names: Vec<String> = queue.waiting().into_iter().map(|e| e.command_name).collect();
+        assert_eq!(names, vec!["high", "low", "low again"]);
+        assert_eq!(queue.waiting()[2].position, 2);
+    }
+
+    #[test]
+    fn test_limits_concurrency() {
+        let queue = Arc::new(ExecutionQueue::new(2));
+        let a = queue.acquire(&command("a", 0)).unwrap();
+        let _b = queue.acquire(&command("b", 0)).unwrap();
+        assert_eq!(queue.running(), 2);
+
+        let waiter = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.acquire(&command("c", 0)).map(|_| ()))
+        };
+        wait_until(|| queue.waiting().len() == 1);
+        assert_eq!(queue.running(), 2);
+
+        drop(a);
+        waiter.join().unwrap().unwrap();
+        assert!(queue.waiting().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_listener() {
+        let queue = Arc::new(ExecutionQueue::new(1));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        {
+            let events = events.clone();
+            queue.set_listener(move |waiting| events.lock().unwrap().push(waiting.len()));
+        }
+        let _running = queue.acquire(&command("running", 0)).unwrap();
+
+        let id = queue.enqueue(&command("queued", 0));
+        let waiter = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.wait(id).map(|_| ()))
+        };
+        assert!(queue.cancel(id));
+        assert!(!queue.cancel(id));
+        assert!(matches!(waiter.join().unwrap(), Err(CommandArgusError::Cancelled(_))));
+        drop(_running);
+        assert_eq!(*events.lock().unwrap(), vec![1, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_wait_after_cancel() {
+        let queue = ExecutionQueue::new(1);
+        assert!(matches!(queue.wait(Uuid::new_v4()), Err(CommandArgusError::Cancelled(_))));
+
+        // A cancelled execution gives up its turn, and only fails its own wait
+        let running = queue.acquire(&command("running", 0)).unwrap();
+        let cancelled = queue.enqueue(&command("cancelled", 5));
+        let next = queue.enqueue(&command("next", 0));
+        assert!(queue.cancel(cancelled));
+        drop(running);
+        assert!(queue.wait(next).is_ok());
+        assert!(matches!(queue.wait(cancelled), Err(CommandArgusError::Cancelled(_))));
+        assert_eq!(queue.running(), 0);
+    }
+
+    #[test]
+    fn test_mutex_groups() {
+        let queue = Arc::new(ExecutionQueue::new(4));
+        let grouped = |name: &str| {
+            let mut cmd = command(name, 0);
+            cmd.mutex_group = Some("terraform".to_string());
+            cmd
+        };
+        let apply = queue.acquire(&grouped("Apply staging")).unwrap();
+
+        let waiter = {
+            let queue = queue.clone();
+            let cmd = grouped("Apply prod");
+            thread::spawn(move || queue.acquire(&cmd).map(|_| ()))
+        };
+        wait_until(|| queue.waiting().len() == 1);
+        let waiting = queue.waiting();
+        assert_eq!(waiting[0].waiting_on.as_deref(), Some("waiting on lock held by Apply staging"));
+
+        // Ungrouped commands are not held up by the blocked one ahead of them
+        drop(queue.acquire(&command("Lint", 0)).unwrap());
+        assert_eq!(queue.waiting().len(), 1);
+
+        drop(apply);
+        waiter.join().unwrap().unwrap();
+        assert_eq!(queue.running(), 0);
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::history::HistoryStorage;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RatePeriod {
+    Minute,
+    Hour,
+}
+
+impl RatePeriod {
+    fn duration(self) -> Duration {
+        match self {
+            RatePeriod::Minute => Duration::minutes(1),
+            RatePeriod::Hour => Duration::hours(1),
+        }
+    }
+}
+
+/// At most `max_runs` executions within any sliding window of one `per`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RateLimit {
+    pub max_runs: u32,
+    pub per: RatePeriod,
+}
+
+impl RateLimit {
+    /// Fails if another run at `now` would exceed the limit, given the start times of
+    /// earlier runs in any order.
+    pub fn check(&self, command_name: &str, runs: &[DateTime<Utc>], now: DateTime<Utc>) -> Result<()> {
+        self.check_planned(command_name, runs, now, 1)
+    }
+
+    /// Fails if `planned` more runs from `now` on, e.g. the iterations of a benchmark,
+    /// would exceed the limit.
+    pub fn check_planned(&self, command_name: &str, runs: &[DateTime<Utc>], now: DateTime<Utc>, planned: usize) -> Result<()> {
+        let window_start = now - self.per.duration();
+        let mut recent: Vec<DateTime<Utc>> = runs.iter().copied().filter(|t| *t > window_start).collect();
+        if recent.len() + planned <= self.max_runs as usize {
+            return Ok(());
+        }
+        let mut message = format!(
+            "'{}' may run at most {} times per {}",
+            command_name,
+            self.max_runs,
+            match self.per {
+                RatePeriod::Minute => "minute",
+                RatePeriod::Hour => "hour",
+            }
+        );
+        if planned > self.max_runs as usize {
+            if planned > 1 {
+                message.push_str(&format!(", not {} times", planned));
+            }
+        } else {
+            // The runs are allowed once enough of the recent runs have left the window
+            recent.sort();
+            let frees_up_at = recent[recent.len() + planned - 1 - self.max_runs as usize] + self.per.duration();
+            message.push_str(&format!("; try again in {}s", (frees_up_at - now).num_seconds().max(1)));
+        }
+        Err(CommandArgusError::RateLimited(message))
+    }
+}
+
+impl HistoryStorage {
+    /// Checks `command`'s rate limit against its recorded executions.
+    pub fn check_rate_limit(&self, command: &Command) -> Result<()> {
+        self.check_rate_limit_for(command, 1)
+    }
+
+    /// Checks that `command` may run `planned` more times before any of them is recorded.
+    pub fn check_rate_limit_for(&self, command: &Command, planned: usize) -> Result<()> {
+        let Some(limit) = command.rate_limit else {
+            return Ok(());
+        };
+        let since = Utc::now() - limit.per.duration();
+        let runs: Vec<DateTime<Utc>> = self
+            .list()?
+            .into_iter()
+            .filter(|r| r.command_id == command.id && r.started_at > since)
+            .map(|r| r.started_at)
+            .collect();
+        limit.check_planned(&command.name, &runs, Utc::now(), planned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ExecutionRecord;
+
+    #[test]
+    fn test_sliding_window() {
+        let now = Utc::now();
+        let limit = RateLimit { max_runs: 2, per: RatePeriod::Minute };
+        let ago = |secs| now - Duration::seconds(secs);
+
+        assert!(limit.check("Deploy", &[ago(10)], now).is_ok());
+        assert!(limit.check("Deploy", &[ago(90), ago(70), ago(10)], now).is_ok());
+
+        let err = limit.check("Deploy", &[ago(10), ago(45)], now).unwrap_err().to_string();
+        assert!(err.contains("at most 2 times per minute"), "{}", err);
+        assert!(err.contains("try again in 15s"), "{}", err);
+
+        let blocked = RateLimit { max_runs: 0, per: RatePeriod::Hour };
+        assert!(blocked.check("Deploy", &[], now).is_err());
+
+        // Planned runs count together with the recent ones
+        let limit = RateLimit { max_runs: 3, per: RatePeriod::Minute };
+        assert!(limit.check_planned("Deploy", &[ago(10)], now, 2).is_ok());
+        let err = limit.check_planned("Deploy", &[ago(10), ago(30)], now, 2).unwrap_err().to_string();
+        assert!(err.contains("try again in 30s"), "{}", err);
+        let err = limit.check_planned("Deploy", &[], now, 4).unwrap_err().to_string();
+        assert!(err.contains("at most 3 times per minute, not 4 times"), "{}", err);
+    }
+
+    #[test]
+    fn test_history_check() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+        let mut cmd = Command::new("Deploy".to_string(), "true".to_string());
+        let other = Command::new("Other".to_string(), "true".to_string());
+
+        history.record(ExecutionRecord::from_error(&cmd, Utc::now(), String::new())).unwrap();
+        history.record(ExecutionRecord::from_error(&other, Utc::now(), String::new())).unwrap();
+        assert!(history.check_rate_limit(&cmd).is_ok());
+
+        cmd.rate_limit = Some(RateLimit { max_runs: 2, per: RatePeriod::Hour });
+        assert!(history.check_rate_limit(&cmd).is_ok());
+        assert!(history.check_rate_limit_for(&cmd, 2).is_err());
+        history.record(ExecutionRecord::from_error(&cmd, Utc::now(), String::new())).unwrap();
+        assert!(matches!(history.check_rate_limit(&cmd), Err(CommandArgusError::RateLimited(_))));
+    }
+}
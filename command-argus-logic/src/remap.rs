@@ -0,0 +1,225 @@
+use std::path::Path;
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+
+/// Rewrites paths starting with `from` to start with `to` instead, e.g. `/Users/bob`
+/// to `/home/alice`. A `to` starting with `~` is this machine's home directory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PathRemapRule {
+    pub from: String,
+    pub to: String,
+}
+
+/// The part of a command a path was found in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum PathField {
+    Program,
+    Argument { index: usize },
+    Environment { key: String },
+    WorkingDirectory,
+}
+
+impl PathField {
+    /// Whether a leading `~` in the field is expanded when the command runs.
+    fn expands_tilde(&self) -> bool {
+        matches!(self, PathField::Environment { .. } | PathField::WorkingDirectory)
+    }
+}
+
+/// A value a rule rewrote.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RemappedPath {
+    pub command_name: String,
+    #[serde(flatten)]
+    pub field: PathField,
+    pub before: String,
+    pub after: String,
+}
+
+/// An absolute path no rule matched and that doesn't exist on this machine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UnmappedPath {
+    pub command_name: String,
+    #[serde(flatten)]
+    pub field: PathField,
+    pub path: String,
+}
+
+/// What remapping did to a set of imported commands.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PathRemapReport {
+    pub remapped: Vec<RemappedPath>,
+    pub unmapped: Vec<UnmappedPath>,
+}
+
+/// Applies [`PathRemapRule`]s to commands coming from another machine.
+pub struct PathRemapper {
+    rules: Vec<PathRemapRule>,
+    home: Option<String>,
+}
+
+impl PathRemapper {
+    pub fn new(rules: Vec<PathRemapRule>) -> Self {
+        let home = BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().to_string());
+        Self::with_home(rules, home)
+    }
+
+    pub fn with_home(rules: Vec<PathRemapRule>, home: Option<String>) -> Self {
+        Self { rules, home }
+    }
+
+    /// Remaps the program, arguments, environment variable values and working directory
+    /// of `commands`. A path is recognised as the whole value or what follows the first
+    /// `=`, as in `--config=/etc/app.toml`. Platform variants are left alone, as their
+    /// paths belong to other systems anyway.
+    pub fn remap_commands(&self, commands: &mut [Command]) -> PathRemapReport {
+        let mut report = PathRemapReport::default();
+        for command in commands {
+            let name = command.name.clone();
+            let mut visit = |field: PathField, value: &mut String| self.remap_value(&name, field, value, &mut report);
+            visit(PathField::Program, &mut command.command);
+            for (index, arg) in command.args.iter_mut().enumerate() {
+                visit(PathField::Argument { index }, arg);
+            }
+            for variable in &mut command.environment_variables {
+                visit(PathField::Environment { key: variable.key.clone() }, &mut variable.value);
+            }
+            if let Some(dir) = &mut command.working_directory {
+                visit(PathField::WorkingDirectory, dir);
+            }
+        }
+        report
+    }
+
+    fn remap_value(&self, command_name: &str, field: PathField, value: &mut String, report: &mut PathRemapReport) {
+        let start = match value.find('=') {
+            Some(pos) if !is_absolute(value) => pos + 1,
+            _ => 0,
+        };
+        let path = &value[start..];
+        if !is_absolute(path) {
+            return;
+        }
+
+        let remapped = self.rules.iter().find_map(|rule| {
+            let from = rule.from.trim_end_matches(['/', '\\']);
+            let rest = path.strip_prefix(from).filter(|rest| !from.is_empty() && (rest.is_empty() || rest.starts_with(['/', '\\', ':'])))?;
+            Some(format!("{}{}", self.target(&rule.to, &field), rest))
+        });
+        match remapped {
+            Some(path) => {
+                let after = format!("{}{}", &value[..start], path);
+                report.remapped.push(RemappedPath {
+                    command_name: command_name.to_string(),
+                    field,
+                    before: std::mem::replace(value, after.clone()),
+                    after,
+                });
+            }
+            None if !exists_locally(path) => report.unmapped.push(UnmappedPath {
+                command_name: command_name.to_string(),
+                field,
+                path: path.to_string(),
+            }),
+            None => {}
+        }
+    }
+
+    /// A rule's target for `field`: a leading `~` stays where it is expanded at run time
+    /// and becomes this machine's home directory elsewhere.
+    fn target(&self, to: &str, field: &PathField) -> String {
+        let to = to.trim_end_matches(['/', '\\']);
+        match (to.strip_prefix('~'), &self.home) {
+            (Some(rest), Some(home)) if !field.expands_tilde() && (rest.is_empty() || rest.starts_with('/')) => {
+                format!("{}{}", home, rest)
+            }
+            _ => to.to_string(),
+        }
+    }
+}
+
+/// A Unix path or one starting with a Windows drive letter.
+fn is_absolute(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    text.starts_with('/') && !text.starts_with("//")
+        || bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'/' | b'\\')
+}
+
+/// Whether the path, up to any placeholder or `:`-joined second path, exists here.
+fn exists_locally(path: &str) -> bool {
+    let end = path.find('{').unwrap_or(path.len());
+    let end = match path[..end].rfind(':') {
+        Some(pos) if pos > 1 => pos,
+        _ => end,
+    };
+    Path::new(&path[..end]).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_commands() {
+        let mut command = Command::new("Build".to_string(), "/Users/bob/bin/build".to_string()).with_args(vec![
+            "--config=/Users/bob/work/app.toml".to_string(),
+            "/Users/bobby/notes".to_string(),
+            "/opt/author-only-tool-5c1d/run".to_string(),
+            "-v".to_string(),
+            "/tmp:/data".to_string(),
+        ]);
+        command.add_environment_variable("CACHE".to_string(), "/Users/bob/.cache".to_string());
+        command.working_directory = Some("/Users/bob/work".to_string());
+
+        let rules = vec![
+            PathRemapRule { from: "/Users/bob/".to_string(), to: "~".to_string() },
+            PathRemapRule { from: "/Users/bobby".to_string(), to: "/srv/bobby".to_string() },
+        ];
+        let remapper = PathRemapper::with_home(rules, Some("/home/alice".to_string()));
+        let mut commands = vec![command];
+        let report = remapper.remap_commands(&mut commands);
+        let command = &commands[0];
+
+        assert_eq!(command.command, "/home/alice/bin/build");
+        assert_eq!(command.args[0], "--config=/home/alice/work/app.toml");
+        assert_eq!(command.args[1], "/srv/bobby/notes");
+        assert_eq!(command.environment_variables[0].value, "~/.cache");
+        assert_eq!(command.working_directory.as_deref(), Some("~/work"));
+        assert_eq!(report.remapped.len(), 5);
+        assert_eq!(report.remapped[1].before, "--config=/Users/bob/work/app.toml");
+        assert_eq!(
+            report.unmapped,
+            vec![UnmappedPath {
+                command_name: "Build".to_string(),
+                field: PathField::Argument { index: 2 },
+                path: "/opt/author-only-tool-5c1d/run".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_remap_without_a_matching_rule() {
+        let mut command = Command::new("Deploy".to_string(), "./deploy.sh".to_string()).with_args(vec![
+            "C:\\Users\\bob\\deploy.toml".to_string(),
+            "/Users/bobby/notes".to_string(),
+            "{project_root}/out".to_string(),
+        ]);
+        command.working_directory = Some("relative/dir".to_string());
+        let rules = vec![
+            PathRemapRule { from: String::new(), to: "/anywhere".to_string() },
+            PathRemapRule { from: "/Users/bob".to_string(), to: "~".to_string() },
+        ];
+        let mut commands = vec![command.clone()];
+        let report = PathRemapper::with_home(rules, None).remap_commands(&mut commands);
+
+        // Nothing changes; only the absolute paths missing here are reported
+        assert_eq!(commands[0], command);
+        assert!(report.remapped.is_empty());
+        let unmapped: Vec<&str> = report.unmapped.iter().map(|u| u.path.as_str()).collect();
+        assert_eq!(unmapped, vec!["C:\\Users\\bob\\deploy.toml", "/Users/bobby/notes"]);
+    }
+}
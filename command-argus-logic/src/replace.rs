@@ -0,0 +1,147 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::storage::CommandStorage;
+
+/// Which commands a bulk replace touches.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReplaceScope {
+    All,
+    Commands { ids: Vec<Uuid> },
+    Tag { tag: String },
+}
+
+impl ReplaceScope {
+    fn contains(&self, command: &Command) -> bool {
+        match self {
+            ReplaceScope::All => true,
+            ReplaceScope::Commands { ids } => ids.contains(&command.id),
+            ReplaceScope::Tag { tag } => command.tags.iter().any(|t| t == tag),
+        }
+    }
+}
+
+/// The part of a command a replacement changed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum ReplaceField {
+    Program,
+    Argument { index: usize },
+    Environment { key: String },
+}
+
+/// One value a bulk replace changes, before and after.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplaceChange {
+    pub command_id: Uuid,
+    pub command_name: String,
+    #[serde(flatten)]
+    pub field: ReplaceField,
+    pub before: String,
+    pub after: String,
+}
+
+/// Replaces every match of the regex `pattern` with `replacement` (which may refer to
+/// groups as `$1` or `${name}`) in the program, arguments and environment variable
+/// values of the commands in `scope`, and returns what changed. The changes are only
+/// saved with `save`; without it this is a dry run. Secret environment variables are
+/// left alone, as their values are never shown for review. All changes are written at
+/// once.
+pub fn bulk_replace(
+    storage: &CommandStorage,
+    pattern: &str,
+    replacement: &str,
+    scope: &ReplaceScope,
+    save: Option<EditAccess>,
+) -> Result<Vec<ReplaceChange>> {
+    let mut commands = storage.list()?;
+    let changes = replace_in(&mut commands, pattern, replacement, scope)?;
+    if let Some(access) = save.filter(|_| !changes.is_empty()) {
+        for command in commands.iter_mut().filter(|c| changes.iter().any(|change| change.command_id == c.id)) {
+            command.update();
+        }
+        storage.replace_all(access, &commands)?;
+    }
+    Ok(changes)
+}
+
+/// Applies the replacement to `commands` in place and lists the changes.
+fn replace_in(commands: &mut [Command], pattern: &str, replacement: &str, scope: &ReplaceScope) -> Result<Vec<ReplaceChange>> {
+    if pattern.is_empty() {
+        return Err(CommandArgusError::InvalidQuery("the pattern is empty".to_string()));
+    }
+    let regex = Regex::new(pattern).map_err(|e| CommandArgusError::InvalidQuery(format!("invalid pattern: {}", e)))?;
+
+    let mut changes = Vec::new();
+    for command in commands.iter_mut().filter(|c| scope.contains(c)) {
+        let (id, name) = (command.id, command.name.clone());
+        let mut replace = |field: ReplaceField, value: &mut String| {
+            let after = regex.replace_all(value, replacement);
+            if after != value.as_str() {
+                let after = after.into_owned();
+                changes.push(ReplaceChange {
+                    command_id: id,
+                    command_name: name.clone(),
+                    field,
+                    before: std::mem::replace(value, after.clone()),
+                    after,
+                });
+            }
+        };
+
+        replace(ReplaceField::Program, &mut command.command);
+        for (index, arg) in command.args.iter_mut().enumerate() {
+            replace(ReplaceField::Argument { index }, arg);
+        }
+        for variable in command.environment_variables.iter_mut().filter(|v| !v.secret) {
+            replace(ReplaceField::Environment { key: variable.key.clone() }, &mut variable.value);
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::EnvironmentVariable;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bulk_replace_dry_run_and_apply() {
+        let temp = TempDir::new().unwrap();
+        let storage = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let mut deploy = Command::new("Deploy".to_string(), "docker".to_string())
+            .with_args(vec!["push".to_string(), "registry.old.example.com/app:1.0".to_string()]);
+        deploy.tags = vec!["infra".to_string()];
+        deploy.environment_variables = vec![
+            EnvironmentVariable { key: "API".to_string(), value: "https://api.old.example.com".to_string(), secret: false },
+            EnvironmentVariable { key: "DSN".to_string(), value: "postgres://u:p@db.old.example.com".to_string(), secret: true },
+        ];
+        let deploy = storage.create(EditAccess::unrestricted(), deploy).unwrap();
+        let other = storage
+            .create(EditAccess::unrestricted(), Command::new("Ping".to_string(), "ping".to_string()).with_args(vec!["old.example.com".to_string()]))
+            .unwrap();
+
+        let scope = ReplaceScope::Tag { tag: "infra".to_string() };
+        let preview = bulk_replace(&storage, r"(\w+)\.old\.example\.com", "$1.new.example.com", &scope, None).unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[0].field, ReplaceField::Argument { index: 1 });
+        assert_eq!(preview[0].after, "registry.new.example.com/app:1.0");
+        assert_eq!(preview[1].field, ReplaceField::Environment { key: "API".to_string() });
+        assert_eq!(storage.read(deploy.id).unwrap(), deploy);
+
+        let applied = bulk_replace(&storage, r"(\w+)\.old\.example\.com", "$1.new.example.com", &scope, Some(EditAccess::unrestricted())).unwrap();
+        assert_eq!(applied, preview);
+        let updated = storage.read(deploy.id).unwrap();
+        assert_eq!(updated.args[1], "registry.new.example.com/app:1.0");
+        assert_eq!(updated.environment_variables[1].value, "postgres://u:p@db.old.example.com");
+        assert_eq!(storage.read(other.id).unwrap().args[0], "old.example.com");
+
+        assert!(bulk_replace(&storage, "(", "", &ReplaceScope::All, None).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::resolve_parameters;
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::history::ExecutionRecord;
+
+/// What to change when running a command again the way a past run did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReplayOverrides {
+    /// Parameter values replacing the recorded ones.
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+    /// Arguments added after the command's own, like `--force`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+/// The command and parameter values to run `command` again like the past run `record`:
+/// the recorded values with `overrides` applied, defaults filled in for parameters added
+/// since, and the extra arguments appended. Secret values are never recorded, so they
+/// have to be given again in the overrides.
+pub fn prepare_replay(
+    record: &ExecutionRecord,
+    command: &Command,
+    overrides: &ReplayOverrides,
+) -> Result<(Command, HashMap<String, String>)> {
+    if record.command_id != command.id {
+        return Err(CommandArgusError::InvalidCommand(format!(
+            "the run {} is of another command, '{}'",
+            record.id, record.command_name
+        )));
+    }
+    if let Some(name) = overrides.parameters.keys().find(|name| !command.parameters.iter().any(|p| &p.name == *name)) {
+        return Err(CommandArgusError::InvalidCommand(format!(
+            "'{}' has no parameter '{}'",
+            command.name, name
+        )));
+    }
+
+    let mut values: HashMap<String, String> = record
+        .parameters
+        .iter()
+        .filter(|(name, _)| command.parameters.iter().any(|p| &p.name == *name && !p.secret))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    values.extend(overrides.parameters.clone());
+    if let Some(parameter) = command.parameters.iter().find(|p| p.secret && !values.contains_key(&p.name)) {
+        return Err(CommandArgusError::InvalidCommand(format!(
+            "the secret parameter '{}' isn't kept in the history; give its value again",
+            parameter.name
+        )));
+    }
+    let values = resolve_parameters(command, &values)?;
+
+    let mut command = command.clone();
+    command.args.extend(overrides.extra_args.iter().cloned());
+    Ok((command, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::command::{CommandParameter, ParameterType};
+
+    #[test]
+    fn test_prepare_replay() {
+        let parameter = |name: &str, secret: bool, default: Option<&str>| CommandParameter {
+            name: name.to_string(),
+            placeholder: format!("{{{}}}", name),
+            parameter_type: ParameterType::Text,
+            required: true,
+            default_value: default.map(str::to_string),
+            options: None,
+            secret,
+        };
+        let mut command = Command::new("Deploy".to_string(), "deploy".to_string());
+        command.args = vec!["{stage}".to_string(), "{branch}".to_string()];
+        command.parameters = vec![parameter("stage", false, None), parameter("branch", false, None), parameter("token", true, None)];
+        let values: HashMap<String, String> = [("stage", "prod"), ("branch", "main"), ("token", "s3cret")]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into();
+        let record = ExecutionRecord::from_error(&command, Utc::now(), String::new()).with_parameters(&command, &values);
+        assert!(!record.parameters.contains_key("token"));
+
+        let mut overrides = ReplayOverrides { parameters: HashMap::new(), extra_args: vec!["--force".to_string()] };
+        let err = prepare_replay(&record, &command, &overrides).unwrap_err().to_string();
+        assert!(err.contains("secret parameter 'token'"), "{}", err);
+
+        overrides.parameters.insert("token".to_string(), "t0ken".to_string());
+        overrides.parameters.insert("branch".to_string(), "hotfix".to_string());
+        command.parameters.push(parameter("region", false, Some("eu")));
+        let (replayed, values) = prepare_replay(&record, &command, &overrides).unwrap();
+        assert_eq!(replayed.args, vec!["{stage}", "{branch}", "--force"]);
+        assert_eq!(values["stage"], "prod");
+        assert_eq!(values["branch"], "hotfix");
+        assert_eq!(values["region"], "eu");
+
+        overrides.parameters.insert("missing".to_string(), String::new());
+        assert!(prepare_replay(&record, &command, &overrides).is_err());
+        let other = Command::new("Other".to_string(), "true".to_string());
+        assert!(prepare_replay(&record, &other, &ReplayOverrides::default()).is_err());
+
+        // A required parameter added since the run, without a default, needs a value
+        command.parameters.push(parameter("zone", false, None));
+        let overrides = ReplayOverrides { parameters: HashMap::from([("token".to_string(), "t0ken".to_string())]), extra_args: Vec::new() };
+        let err = prepare_replay(&record, &command, &overrides).unwrap_err().to_string();
+        assert!(err.contains("zone"), "{}", err);
+    }
+}
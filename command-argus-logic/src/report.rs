@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{BatchReport, StepOutcome};
+use crate::error::Result;
+
+/// File formats a batch or suite report can be exported to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// JUnit XML, as understood by most CI dashboards.
+    Junit,
+    /// A standalone HTML page with inline styles.
+    Html,
+}
+
+impl ReportFormat {
+    /// Picks the format from a file extension: `.html`/`.htm` is HTML, anything else JUnit.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => ReportFormat::Html,
+            _ => ReportFormat::Junit,
+        }
+    }
+}
+
+impl BatchReport {
+    /// Renders the report as a JUnit XML document with one test case per step.
+    pub fn to_junit_xml(&self, suite_name: &str) -> String {
+        let errors = self.outcomes.iter().filter(|o| o.error.is_some()).count();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+            self.outcomes.len(),
+            self.failed() - errors,
+            errors,
+            self.duration.as_secs_f64()
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+            escape(suite_name),
+            self.outcomes.len(),
+            self.failed() - errors,
+            errors,
+            self.duration.as_secs_f64()
+        ));
+        for outcome in &self.outcomes {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&outcome.command),
+                escape(suite_name),
+                outcome.duration.as_secs_f64()
+            ));
+            if let Some(reason) = &outcome.skipped {
+                xml.push_str(&format!(">\n      <skipped message=\"{}\"/>\n    </testcase>\n", escape(reason)));
+                continue;
+            }
+            if outcome.success && outcome.stdout.is_empty() && outcome.stderr.is_empty() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            if let Some(error) = &outcome.error {
+                xml.push_str(&format!("      <error message=\"{}\"/>\n", escape(error)));
+            } else if !outcome.success {
+                let message = failure_message(outcome);
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    escape(&message),
+                    escape(&message)
+                ));
+            }
+            if !outcome.stdout.is_empty() {
+                xml.push_str(&format!("      <system-out>{}</system-out>\n", escape(&outcome.stdout)));
+            }
+            if !outcome.stderr.is_empty() {
+                xml.push_str(&format!("      <system-err>{}</system-err>\n", escape(&outcome.stderr)));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Renders the report as a self-contained HTML page, suitable for attaching to a ticket.
+    pub fn to_html(&self, title: &str) -> String {
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape(title)));
+        html.push_str(
+            "<style>\n\
+             body { font-family: -apple-system, sans-serif; margin: 2em; color: #222; }\n\
+             table { border-collapse: collapse; width: 100%; }\n\
+             th, td { text-align: left; padding: 6px 10px; border-bottom: 1px solid #ddd; vertical-align: top; }\n\
+             .pass { color: #15803d; font-weight: bold; }\n\
+             .fail { color: #b91c1c; font-weight: bold; }\n\
+             .skip { color: #71717a; font-weight: bold; }\n\
+             pre { background: #f4f4f5; padding: 6px; white-space: pre-wrap; margin: 4px 0; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        html.push_str(&format!("<h1>{}</h1>\n", escape(title)));
+        html.push_str(&format!(
+            "<p>{} passed, {} failed in {:.2}s</p>\n",
+            self.passed(),
+            self.failed(),
+            self.duration.as_secs_f64()
+        ));
+        html.push_str("<table>\n<tr><th>Status</th><th>Command</th><th>Duration</th><th>Details</th></tr>\n");
+        for outcome in &self.outcomes {
+            let (class, status) = match (&outcome.skipped, outcome.success) {
+                (Some(_), _) => ("skip", "SKIP"),
+                (None, true) => ("pass", "PASS"),
+                (None, false) => ("fail", "FAIL"),
+            };
+            html.push_str(&format!(
+                "<tr><td class=\"{}\">{}</td><td>{}</td><td>{:.2}s</td><td>",
+                class,
+                status,
+                escape(&outcome.command),
+                outcome.duration.as_secs_f64()
+            ));
+            if let Some(reason) = &outcome.skipped {
+                html.push_str(&format!("<div>{}</div>", escape(reason)));
+            } else if !outcome.success {
+                html.push_str(&format!("<div>{}</div>", escape(&failure_message(outcome))));
+            }
+            for output in [&outcome.stdout, &outcome.stderr] {
+                if !output.is_empty() {
+                    html.push_str(&format!("<pre>{}</pre>", escape(output)));
+                }
+            }
+            html.push_str("</td></tr>\n");
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Writes the report to `path` in the given format.
+    pub fn write_to(&self, path: &Path, format: ReportFormat, name: &str) -> Result<()> {
+        let content = match format {
+            ReportFormat::Junit => self.to_junit_xml(name),
+            ReportFormat::Html => self.to_html(name),
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+fn failure_message(outcome: &StepOutcome) -> String {
+    if let Some(error) = &outcome.error {
+        return error.clone();
+    }
+    let failed: Vec<String> = outcome
+        .assertion_results
+        .iter()
+        .filter(|r| !r.passed)
+        .map(|r| format!("{}: {}", r.assertion, r.message.as_deref().unwrap_or("failed")))
+        .collect();
+    if !failed.is_empty() {
+        return failed.join("; ");
+    }
+    match outcome.exit_code {
+        Some(code) => format!("exited with code {}", code),
+        None => "terminated".to_string(),
+    }
+}
+
+// Escapes text for both XML and HTML. Control characters other than tab and newline are
+// not allowed in XML 1.0 at all, so they are dropped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn outcome(command: &str, success: bool, error: Option<&str>) -> StepOutcome {
+        StepOutcome {
+            step_id: String::new(),
+            command: command.to_string(),
+            success,
+            exit_code: if error.is_some() { None } else { Some(if success { 0 } else { 2 }) },
+            duration: Duration::from_millis(250),
+            stdout: if success { "all <good>\n".to_string() } else { String::new() },
+            stderr: String::new(),
+            error: error.map(String::from),
+            assertion_results: Vec::new(),
+            skipped: None,
+        }
+    }
+
+    fn sample_report() -> BatchReport {
+        BatchReport {
+            run_id: uuid::Uuid::nil(),
+            outcomes: vec![
+                outcome("Health", true, None),
+                outcome("Deploy \"prod\"", false, None),
+                outcome("Backup", false, Some("missing value for required parameter 'bucket'")),
+            ],
+            duration: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let xml = sample_report().to_junit_xml("smoke");
+        assert!(xml.contains("<testsuite name=\"smoke\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"1.000\">"));
+        assert!(xml.contains("<system-out>all &lt;good&gt;\n</system-out>"));
+        assert!(xml.contains("<testcase name=\"Deploy &quot;prod&quot;\""));
+        assert!(xml.contains("<failure message=\"exited with code 2\">"));
+        assert!(xml.contains("<error message=\"missing value for required parameter &#39;bucket&#39;\"/>"));
+
+        let mut report = sample_report();
+        report.outcomes[0].skipped = Some("condition not met".to_string());
+        assert!(report.to_junit_xml("smoke").contains("<skipped message=\"condition not met\"/>"));
+    }
+
+    #[test]
+    fn test_html_and_write() {
+        let report = sample_report();
+        let html = report.to_html("Nightly <checks>");
+        assert!(html.contains("<title>Nightly &lt;checks&gt;</title>"));
+        assert!(html.contains("1 passed, 2 failed"));
+        assert!(html.contains("<td class=\"fail\">FAIL</td><td>Backup</td>"));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("report.html");
+        assert_eq!(ReportFormat::from_path(&path), ReportFormat::Html);
+        assert_eq!(ReportFormat::from_path(Path::new("junit.xml")), ReportFormat::Junit);
+        report.write_to(&path, ReportFormat::from_path(&path), "nightly").unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("<!DOCTYPE html>"));
+    }
+}
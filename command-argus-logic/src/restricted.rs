@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+
+/// Only commands carrying this tag may run while restricted mode is on.
+pub const APPROVED_TAG: &str = "approved";
+
+/// Shows that the library and the settings may be changed: restricted mode was off, or
+/// the admin passphrase was given. Only `RestrictedMode::check_edit` hands it out and the
+/// stores take it for every change, so no caller can change them without the check.
+#[derive(Debug, Clone, Copy)]
+pub struct EditAccess(());
+
+impl EditAccess {
+    /// For tests, which run without restricted mode.
+    #[cfg(test)]
+    pub(crate) fn unrestricted() -> Self {
+        EditAccess(())
+    }
+}
+
+/// Locked-down profile for shared machines (kiosks, ops desks). While enabled, only
+/// commands tagged `approved` can be executed and the library can only be changed
+/// by someone who knows the admin passphrase.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RestrictedMode {
+    pub enabled: bool,
+    #[serde(default)]
+    passphrase_salt: Option<String>,
+    #[serde(default)]
+    passphrase_hash: Option<String>,
+}
+
+impl RestrictedMode {
+    pub fn enable(&mut self, passphrase: &str) -> Result<()> {
+        if self.enabled {
+            return Err(CommandArgusError::AccessDenied(
+                "Restricted mode is already enabled".to_string(),
+            ));
+        }
+        if passphrase.is_empty() {
+            return Err(CommandArgusError::InvalidCommand(
+                "Admin passphrase must not be empty".to_string(),
+            ));
+        }
+        let salt = Uuid::new_v4().to_string();
+        self.passphrase_hash = Some(hash_passphrase(&salt, passphrase));
+        self.passphrase_salt = Some(salt);
+        self.enabled = true;
+        Ok(())
+    }
+
+    pub fn disable(&mut self, passphrase: &str) -> Result<()> {
+        self.verify(passphrase)?;
+        *self = Self::default();
+        Ok(())
+    }
+
+    pub fn check_execute(&self, command: &Command) -> Result<()> {
+        if self.enabled && !command.tags.iter().any(|t| t == APPROVED_TAG) {
+            return Err(CommandArgusError::AccessDenied(format!(
+                "'{}' is not tagged '{}' and cannot run in restricted mode",
+                command.name, APPROVED_TAG
+            )));
+        }
+        Ok(())
+    }
+
+    /// Edits are always allowed when restricted mode is off.
+    pub fn check_edit(&self, passphrase: Option<&str>) -> Result<EditAccess> {
+        if !self.enabled {
+            return Ok(EditAccess(()));
+        }
+        match passphrase {
+            Some(passphrase) => self.verify(passphrase).map(|()| EditAccess(())),
+            None => Err(CommandArgusError::AccessDenied(
+                "Editing commands requires the admin passphrase in restricted mode".to_string(),
+            )),
+        }
+    }
+
+    pub fn verify(&self, passphrase: &str) -> Result<()> {
+        match (&self.passphrase_salt, &self.passphrase_hash) {
+            (Some(salt), Some(hash)) if &hash_passphrase(salt, passphrase) == hash => Ok(()),
+            _ => Err(CommandArgusError::AccessDenied("Wrong admin passphrase".to_string())),
+        }
+    }
+}
+
+fn hash_passphrase(salt: &str, passphrase: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}", salt, passphrase).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_approved_commands_run() {
+        let mut mode = RestrictedMode::default();
+        let mut approved = Command::new("Status".to_string(), "git".to_string());
+        approved.add_tag(APPROVED_TAG.to_string());
+        let other = Command::new("Reset".to_string(), "git".to_string());
+
+        assert!(mode.check_execute(&other).is_ok());
+        mode.enable("letmein").unwrap();
+        assert!(mode.check_execute(&approved).is_ok());
+        assert!(matches!(
+            mode.check_execute(&other),
+            Err(CommandArgusError::AccessDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_edits_need_passphrase() {
+        let mut mode = RestrictedMode::default();
+        assert!(mode.check_edit(None).is_ok());
+
+        mode.enable("letmein").unwrap();
+        assert!(mode.check_edit(None).is_err());
+        assert!(mode.check_edit(Some("wrong")).is_err());
+        assert!(mode.check_edit(Some("letmein")).is_ok());
+
+        assert!(mode.disable("wrong").is_err());
+        mode.disable("letmein").unwrap();
+        assert!(!mode.enabled);
+        assert!(mode.check_edit(None).is_ok());
+    }
+}
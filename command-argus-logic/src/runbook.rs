@@ -0,0 +1,211 @@
+use crate::batch::{BatchPlan, BatchStep, JoinPolicy, PromptKind, Trim};
+use crate::ci::shell_line;
+use crate::command::Command;
+use crate::error::Result;
+use crate::impact::Impact;
+use crate::platform::Platform;
+use crate::storage::CommandStorage;
+
+/// Renders a batch plan as a Markdown runbook: every step with its command line,
+/// placeholders left in, the conditions it runs under and what a successful run looks
+/// like, so the plan can be followed by hand during an incident.
+///
+/// Commands are shown as they would run on this machine.
+pub fn export_runbook(plan: &BatchPlan, storage: &CommandStorage, title: &str) -> Result<String> {
+    plan.validate()?;
+    let mut markdown = format!("# {}\n\n", title);
+    markdown.push_str(&match plan.parallelism {
+        1 => format!("{} steps, run one after the other.\n", plan.steps.len()),
+        n => format!("{} steps, up to {} running at the same time.\n", plan.steps.len(), n),
+    });
+    for (index, step) in plan.steps.iter().enumerate() {
+        let number = (index + 1).to_string();
+        render_step(&mut markdown, storage, step, &number, &format!("{}.", number), 2)?;
+    }
+    Ok(markdown)
+}
+
+// Renders the step under a heading starting with `prefix`, e.g. "2." or "Otherwise:".
+// `number` is the step's id, which members of a parallel group extend.
+fn render_step(
+    markdown: &mut String,
+    storage: &CommandStorage,
+    step: &BatchStep,
+    number: &str,
+    prefix: &str,
+    level: usize,
+) -> Result<()> {
+    let heading = "#".repeat(level);
+    if let Some(ref prompt) = step.prompt {
+        markdown.push_str(&format!("\n{} {} Ask: {}\n\n", heading, prefix, prompt.question));
+        if let Some(ref condition) = step.condition {
+            markdown.push_str(&format!("- Only if {}\n", condition));
+        }
+        markdown.push_str(&match prompt.kind {
+            PromptKind::Confirm => "- Answer yes to go on; no fails the step\n".to_string(),
+            PromptKind::Choose { ref options } => format!(
+                "- Choose one of {}\n",
+                options.iter().map(|option| format!("`{}`", option)).collect::<Vec<_>>().join(", ")
+            ),
+            PromptKind::Input { default: Some(ref default) } => format!("- Enter a value, `{}` if left empty\n", default),
+            PromptKind::Input { default: None } => "- Enter a value\n".to_string(),
+        });
+        markdown.push_str("- The answer is passed on as the step's output\n");
+    } else if step.is_group() {
+        let join = match step.join {
+            JoinPolicy::WaitAll => "all of them run, even if one fails",
+            JoinPolicy::FailFast => "once one fails, the ones not started yet are skipped",
+        };
+        markdown.push_str(&format!("\n{} {} In parallel\n\n", heading, prefix));
+        if let Some(ref condition) = step.condition {
+            markdown.push_str(&format!("- Only if {}\n", condition));
+        }
+        markdown.push_str(&format!("- These run at the same time; {}\n", join));
+        for (position, member) in step.parallel.iter().enumerate() {
+            let member_number = format!("{}.{}", number, position + 1);
+            render_step(markdown, storage, member, &member_number, &format!("{}.", member_number), level + 1)?;
+        }
+    } else {
+        let command = storage.read_by_name(&step.command)?;
+        markdown.push_str(&format!("\n{} {} {}\n\n", heading, prefix, command.name));
+        render_command(markdown, &command, step)?;
+    }
+
+    if let Some(ref otherwise) = step.otherwise {
+        render_step(markdown, storage, otherwise, number, "Otherwise:", level + 1)?;
+    }
+    Ok(())
+}
+
+fn render_command(markdown: &mut String, command: &Command, step: &BatchStep) -> Result<()> {
+    if let Some(ref description) = command.description {
+        markdown.push_str(&format!("{}\n\n", description));
+    }
+    let variant = command.for_platform(Platform::current())?;
+    markdown.push_str(&format!("```sh\n{}\n```\n\n", shell_line(&variant)));
+
+    let mut notes = Vec::new();
+    if let Some(ref condition) = step.condition {
+        notes.push(format!("Only if {}", condition));
+    }
+    let mut parameters: Vec<_> = step.parameters.iter().collect();
+    parameters.sort();
+    for (name, value) in parameters {
+        notes.push(format!("`{{{}}}`: `{}`", name, value));
+    }
+    for parameter in command.parameters.iter().filter(|p| !step.parameters.contains_key(&p.name)) {
+        match parameter.default_value {
+            Some(ref default) => notes.push(format!("`{{{}}}`: `{}` (default)", parameter.name, default)),
+            None if parameter.required => notes.push(format!("`{{{}}}`: {} (required)", parameter.name, parameter.placeholder)),
+            None => {}
+        }
+    }
+    if step.uses_prev_output(command) {
+        let trim = match step.prev_output.trim {
+            Trim::None => "as is",
+            Trim::Whitespace => "trimmed",
+            Trim::FirstLine => "first line only",
+            Trim::LastLine => "last line only",
+        };
+        let used_as = match step.stdin_from_prev {
+            true => "on stdin",
+            false => "as `{prev.stdout}`",
+        };
+        notes.push(format!(
+            "Takes the previous step's output {}, {}, at most {} bytes",
+            used_as, trim, step.prev_output.max_bytes
+        ));
+    }
+    match command.impact {
+        Impact::Safe => {}
+        Impact::Caution => notes.push("Impact: **caution**".to_string()),
+        Impact::Destructive => notes.push("Impact: **destructive**".to_string()),
+    }
+    let mut expected = vec!["exits with code 0".to_string()];
+    expected.extend(command.assertions.iter().map(|assertion| assertion.to_string()));
+    notes.push(format!("Expected: {}", expected.join("; ")));
+
+    for note in notes {
+        markdown.push_str(&format!("- {}\n", note));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use crate::assertions::Assertion;
+    use crate::command::{CommandParameter, ParameterType};
+
+    #[test]
+    fn test_export_runbook() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let storage = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Pods".to_string(), "kubectl".to_string())
+            .with_args(vec!["get".to_string(), "pods".to_string(), "-o".to_string(), "name".to_string()])
+            .with_description("Lists the pods".to_string())).unwrap();
+        let mut describe = Command::new("Describe".to_string(), "kubectl".to_string())
+            .with_args(vec!["describe".to_string(), "{pod}".to_string(), "-n".to_string(), "{ns}".to_string()]);
+        for (name, default_value) in [("pod", None), ("ns", Some("default".to_string()))] {
+            describe.add_parameter(CommandParameter {
+                name: name.to_string(),
+                placeholder: "Pod".to_string(),
+                parameter_type: ParameterType::Text,
+                required: true,
+                default_value,
+                options: None,
+                secret: false,
+            });
+        }
+        describe.assertions = vec![Assertion::StdoutContains { text: "Running".to_string() }];
+        storage.create(EditAccess::unrestricted(), describe).unwrap();
+        let mut restart = Command::new("Restart".to_string(), "kubectl".to_string())
+            .with_args(vec!["rollout".to_string(), "restart".to_string()]);
+        restart.impact = Impact::Destructive;
+        storage.create(EditAccess::unrestricted(), restart).unwrap();
+        storage.create(EditAccess::unrestricted(), Command::new("Logs".to_string(), "kubectl".to_string())
+            .with_args(vec!["logs".to_string(), "-l".to_string(), "app=web".to_string()])).unwrap();
+
+        let plan = BatchPlan::from_yaml_str(
+            "steps:
+  - command: Pods
+  - command: Describe
+    parameters:
+      pod: '{prev.stdout}'
+    prev_output:
+      trim: first_line
+  - command: Restart
+    if: {kind: stdout_matches, pattern: CrashLoop}
+    else:
+      command: Logs
+  - parallel:
+      - command: Pods
+      - command: Logs
+    join: fail_fast
+",
+        )
+        .unwrap();
+        let markdown = export_runbook(&plan, &storage, "Web pod triage").unwrap();
+        assert!(markdown.starts_with("# Web pod triage\n\n4 steps, run one after the other.\n"));
+        assert!(markdown.contains("## 1. Pods\n\nLists the pods\n\n```sh\nkubectl get pods -o name\n```\n"));
+        assert!(markdown.contains("```sh\nkubectl describe {pod} -n {ns}\n```"));
+        assert!(markdown.contains("- `{pod}`: `{prev.stdout}`\n- `{ns}`: `default` (default)\n"));
+        assert!(markdown.contains("Takes the previous step's output as `{prev.stdout}`, first line only, at most 65536 bytes"));
+        assert!(markdown.contains("- Expected: exits with code 0; stdout contains \"Running\"\n"));
+        assert!(markdown.contains("- Only if the previous step's stdout matches /CrashLoop/\n- Impact: **destructive**\n"));
+        assert!(markdown.contains("### Otherwise: Logs\n\n```sh\nkubectl logs -l app=web\n```"));
+        assert!(markdown.contains("## 4. In parallel\n\n- These run at the same time; once one fails"));
+        assert!(markdown.contains("### 4.2. Logs\n"));
+
+        let prompted = BatchPlan::from_yaml_str(
+            "steps:\n  - prompt: {question: Which cluster?, kind: choose, options: [staging, prod]}\n",
+        )
+        .unwrap();
+        let markdown = export_runbook(&prompted, &storage, "x").unwrap();
+        assert!(markdown.contains("## 1. Ask: Which cluster?\n\n- Choose one of `staging`, `prod`\n"));
+
+        let unknown = BatchPlan::from_yaml_str("steps:\n  - command: Nope\n").unwrap();
+        assert!(export_runbook(&unknown, &storage, "x").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandArgusError, Result};
+use crate::executable::find_executable;
+
+/// What a sandboxed command is allowed to do. Everything not declared here is denied:
+/// the filesystem is read-only apart from `writable_paths`, and the network is cut off
+/// unless `network` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SandboxPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub network: bool,
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+}
+
+impl SandboxPolicy {
+    /// Sandbox that may only write below `path` and has no network access, the
+    /// default for commands imported from an untrusted project.
+    pub fn confined_to(path: &Path) -> Self {
+        Self {
+            enabled: true,
+            network: false,
+            writable_paths: vec![path.to_string_lossy().to_string()],
+        }
+    }
+
+    /// Human readable list of the capabilities granted to the command.
+    pub fn capabilities(&self) -> Vec<String> {
+        if !self.enabled {
+            return vec!["unrestricted".to_string()];
+        }
+        let mut capabilities = vec!["read filesystem".to_string()];
+        for path in &self.writable_paths {
+            capabilities.push(format!("write {}", path));
+        }
+        if self.network {
+            capabilities.push("network".to_string());
+        }
+        capabilities
+    }
+}
+
+/// The OS facility used to confine a process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SandboxBackend {
+    /// Linux namespaces through bubblewrap (`bwrap`).
+    Bubblewrap(PathBuf),
+    /// macOS Seatbelt profiles through `sandbox-exec`.
+    SandboxExec(PathBuf),
+}
+
+impl SandboxBackend {
+    /// Finds the sandboxing tool for the current platform.
+    pub fn detect() -> Result<Self> {
+        if cfg!(target_os = "linux") {
+            find_executable("bwrap").map(SandboxBackend::Bubblewrap).ok_or_else(|| {
+                CommandArgusError::SandboxUnavailable(
+                    "bubblewrap (bwrap) is not installed".to_string(),
+                )
+            })
+        } else if cfg!(target_os = "macos") {
+            let path = PathBuf::from("/usr/bin/sandbox-exec");
+            if path.exists() {
+                Ok(SandboxBackend::SandboxExec(path))
+            } else {
+                Err(CommandArgusError::SandboxUnavailable(
+                    "sandbox-exec was not found".to_string(),
+                ))
+            }
+        } else {
+            Err(CommandArgusError::SandboxUnavailable(
+                "sandboxing is not supported on this platform".to_string(),
+            ))
+        }
+    }
+
+    /// Program and arguments that run `program args` confined by `policy`.
+    pub fn wrap(&self, policy: &SandboxPolicy, program: &str, args: &[String]) -> Result<(String, Vec<String>)> {
+        let writable = writable_paths(policy)?;
+        match self {
+            SandboxBackend::Bubblewrap(bwrap) => {
+                let mut wrapped: Vec<String> = [
+                    "--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp",
+                ]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+                for path in &writable {
+                    wrapped.extend(["--bind".to_string(), path.clone(), path.clone()]);
+                }
+                if !policy.network {
+                    wrapped.push("--unshare-net".to_string());
+                }
+                wrapped.extend(["--die-with-parent", "--new-session", "--"].iter().map(|s| s.to_string()));
+                wrapped.push(program.to_string());
+                wrapped.extend(args.iter().cloned());
+                Ok((bwrap.to_string_lossy().to_string(), wrapped))
+            }
+            SandboxBackend::SandboxExec(sandbox_exec) => {
+                let mut wrapped = vec!["-p".to_string(), seatbelt_profile(policy, &writable)];
+                wrapped.push(program.to_string());
+                wrapped.extend(args.iter().cloned());
+                Ok((sandbox_exec.to_string_lossy().to_string(), wrapped))
+            }
+        }
+    }
+}
+
+// Writable paths must exist so they can be bound; they are canonicalized because both
+// backends match on the real path (e.g. /tmp is /private/tmp on macOS).
+fn writable_paths(policy: &SandboxPolicy) -> Result<Vec<String>> {
+    policy
+        .writable_paths
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .canonicalize()
+                .map(|p| p.to_string_lossy().to_string())
+                .map_err(|_| CommandArgusError::InvalidPath(path.clone()))
+        })
+        .collect()
+}
+
+fn seatbelt_profile(policy: &SandboxPolicy, writable: &[String]) -> String {
+    let mut profile = String::from("(version 1)\n(allow default)\n(deny file-write*)\n");
+    profile.push_str("(allow file-write* (subpath \"/dev\")");
+    for path in writable {
+        profile.push_str(&format!(" (subpath \"{}\")", path.replace('\\', "\\\\").replace('"', "\\\"")));
+    }
+    profile.push_str(")\n");
+    if !policy.network {
+        profile.push_str("(deny network*)\n");
+    }
+    profile
+}
+
+/// Builds a process for `program args` that cannot reach the network.
+///
+/// On Linux the child is moved into a fresh network namespace (inside a new user
+/// namespace when it lacks the privilege to do so directly), so it only sees an
+/// unconfigured loopback interface. On macOS it runs under `sandbox-exec` with a
+/// profile that denies all network access. Elsewhere the run is refused instead of
+/// silently going ahead with network access.
+#[cfg(target_os = "linux")]
+pub fn network_isolated_process(program: &str, args: &[String]) -> Result<ProcessCommand> {
+    use std::io;
+    use std::os::unix::process::CommandExt;
+
+    // Prepared up front: only async-signal-safe calls are allowed after fork
+    let uid_map = format!("{0} {0} 1", unsafe { libc::getuid() });
+    let gid_map = format!("{0} {0} 1", unsafe { libc::getgid() });
+
+    fn write_proc_file(path: &std::ffi::CStr, content: &[u8]) -> io::Result<()> {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let written = libc::write(fd, content.as_ptr().cast(), content.len());
+            libc::close(fd);
+            if written < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    let mut process = ProcessCommand::new(program);
+    process.args(args);
+    unsafe {
+        process.pre_exec(move || {
+            if libc::unshare(libc::CLONE_NEWNET) == 0 {
+                return Ok(());
+            }
+            if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Older kernels have no setgroups file; the gid_map write reports real failures
+            let _ = write_proc_file(c"/proc/self/setgroups", b"deny");
+            write_proc_file(c"/proc/self/uid_map", uid_map.as_bytes())?;
+            write_proc_file(c"/proc/self/gid_map", gid_map.as_bytes())
+        });
+    }
+    Ok(process)
+}
+
+#[cfg(target_os = "macos")]
+pub fn network_isolated_process(program: &str, args: &[String]) -> Result<ProcessCommand> {
+    let mut process = ProcessCommand::new("/usr/bin/sandbox-exec");
+    process.args(["-p", "(version 1)\n(allow default)\n(deny network*)\n", program]);
+    process.args(args);
+    Ok(process)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn network_isolated_process(_program: &str, _args: &[String]) -> Result<ProcessCommand> {
+    Err(CommandArgusError::SandboxUnavailable(
+        "network isolation is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bubblewrap_arguments() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path().canonicalize().unwrap().to_string_lossy().to_string();
+        let policy = SandboxPolicy::confined_to(temp.path());
+        let backend = SandboxBackend::Bubblewrap(PathBuf::from("/usr/bin/bwrap"));
+
+        let (program, args) = backend.wrap(&policy, "make", &["build".to_string()]).unwrap();
+        assert_eq!(program, "/usr/bin/bwrap");
+        let bind = args.iter().position(|a| a == "--bind").unwrap();
+        assert_eq!(args[bind + 1], workspace);
+        assert!(args.contains(&"--unshare-net".to_string()));
+        assert_eq!(&args[args.len() - 3..], ["--", "make", "build"]);
+    }
+
+    #[test]
+    fn test_seatbelt_profile() {
+        let temp = TempDir::new().unwrap();
+        let mut policy = SandboxPolicy::confined_to(temp.path());
+        policy.network = true;
+        let backend = SandboxBackend::SandboxExec(PathBuf::from("/usr/bin/sandbox-exec"));
+
+        let (_, args) = backend.wrap(&policy, "make", &[]).unwrap();
+        assert_eq!(args[0], "-p");
+        assert!(args[1].contains("(deny file-write*)"));
+        assert!(!args[1].contains("(deny network*)"));
+        assert_eq!(args[2], "make");
+    }
+
+    #[test]
+    fn test_missing_writable_path_rejected() {
+        let policy = SandboxPolicy {
+            enabled: true,
+            network: false,
+            writable_paths: vec!["/nonexistent/directory".to_string()],
+        };
+        let backend = SandboxBackend::Bubblewrap(PathBuf::from("bwrap"));
+        assert!(backend.wrap(&policy, "true", &[]).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_network_isolated_process_sees_only_loopback() {
+        let output = network_isolated_process("cat", &["/proc/net/dev".to_string()])
+            .unwrap()
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let interfaces: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(2)
+            .filter_map(|line| line.split(':').next().map(|name| name.trim().to_string()))
+            .collect();
+        assert_eq!(interfaces, vec!["lo"]);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let policy = SandboxPolicy::confined_to(Path::new("/work"));
+        assert_eq!(policy.capabilities(), vec!["read filesystem", "write /work"]);
+        assert_eq!(SandboxPolicy::default().capabilities(), vec!["unrestricted"]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::query::Query;
+use crate::storage::{data_dir, CommandStorage};
+
+/// A named query, shown as a virtual folder whose contents are computed whenever it
+/// is opened, e.g. "Stale kubectl" = `command:~kubectl last_used:>90d`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub name: String,
+    pub query: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedSearch {
+    /// Fails if `query` does not parse, so broken searches are never stored.
+    pub fn new(name: String, query: String) -> Result<Self> {
+        Query::parse(&query)?;
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            query,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+/// Saved searches, persisted as `saved_searches.json` in the data directory.
+pub struct SavedSearchStorage {
+    path: PathBuf,
+}
+
+impl SavedSearchStorage {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("saved_searches.json"),
+        })
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn create(&self, _access: EditAccess, search: SavedSearch) -> Result<SavedSearch> {
+        let mut searches = self.load_all()?;
+        if searches.iter().any(|s| s.name == search.name) {
+            return Err(CommandArgusError::DuplicateName(search.name));
+        }
+        searches.push(search.clone());
+        self.save_all(&searches)?;
+        Ok(search)
+    }
+
+    pub fn read(&self, id: Uuid) -> Result<SavedSearch> {
+        self.load_all()?
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or(CommandArgusError::NotFound(id))
+    }
+
+    pub fn list(&self) -> Result<Vec<SavedSearch>> {
+        self.load_all()
+    }
+
+    /// Renames the search and/or replaces its query. A new query must parse.
+    pub fn update(&self, _access: EditAccess, id: Uuid, name: Option<String>, query: Option<String>) -> Result<SavedSearch> {
+        let mut searches = self.load_all()?;
+        if let Some(name) = &name {
+            if searches.iter().any(|s| s.id != id && s.name == *name) {
+                return Err(CommandArgusError::DuplicateName(name.clone()));
+            }
+        }
+        if let Some(query) = &query {
+            Query::parse(query)?;
+        }
+
+        let search = searches
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or(CommandArgusError::NotFound(id))?;
+        if let Some(name) = name {
+            search.name = name;
+        }
+        if let Some(query) = query {
+            search.query = query;
+        }
+        search.updated_at = Utc::now();
+        let updated = search.clone();
+        self.save_all(&searches)?;
+        Ok(updated)
+    }
+
+    pub fn delete(&self, _access: EditAccess, id: Uuid) -> Result<()> {
+        let mut searches = self.load_all()?;
+        let initial_len = searches.len();
+        searches.retain(|s| s.id != id);
+        if searches.len() == initial_len {
+            return Err(CommandArgusError::NotFound(id));
+        }
+        self.save_all(&searches)
+    }
+
+    /// The commands in `commands` currently matching the saved search `id`.
+    pub fn run_saved_search(&self, id: Uuid, commands: &CommandStorage) -> Result<Vec<Command>> {
+        let search = self.read(id)?;
+        commands.search_by_query(&search.query)
+    }
+
+    fn load_all(&self) -> Result<Vec<SavedSearch>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_all(&self, searches: &[SavedSearch]) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(searches)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_crud() {
+        let temp = TempDir::new().unwrap();
+        let storage = SavedSearchStorage::with_path(temp.path().join("saved_searches.json")).unwrap();
+
+        assert!(matches!(
+            SavedSearch::new("Broken".to_string(), "used:>lots".to_string()),
+            Err(CommandArgusError::InvalidQuery(_))
+        ));
+
+        let search = storage
+            .create(EditAccess::unrestricted(), SavedSearch::new("Deploys".to_string(), "tag:deploy".to_string()).unwrap())
+            .unwrap();
+        assert!(storage
+            .create(EditAccess::unrestricted(), SavedSearch::new("Deploys".to_string(), "tag:prod".to_string()).unwrap())
+            .is_err());
+
+        let updated = storage.update(EditAccess::unrestricted(), search.id, None, Some("tag:prod".to_string())).unwrap();
+        assert_eq!(updated.query, "tag:prod");
+        assert!(storage.update(EditAccess::unrestricted(), search.id, None, Some("colour:red".to_string())).is_err());
+        assert_eq!(storage.read(search.id).unwrap().query, "tag:prod");
+
+        storage.delete(EditAccess::unrestricted(), search.id).unwrap();
+        assert!(storage.list().unwrap().is_empty());
+        assert!(matches!(storage.delete(EditAccess::unrestricted(), search.id), Err(CommandArgusError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_run_saved_search() {
+        let temp = TempDir::new().unwrap();
+        let commands = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let storage = SavedSearchStorage::with_path(temp.path().join("saved_searches.json")).unwrap();
+
+        let mut stale = Command::new("Pods".to_string(), "kubectl".to_string());
+        stale.last_used_at = Some(Utc::now() - chrono::Duration::days(120));
+        commands.create(EditAccess::unrestricted(), stale).unwrap();
+        let mut recent = Command::new("Logs".to_string(), "kubectl".to_string());
+        recent.mark_as_used();
+        commands.create(EditAccess::unrestricted(), recent).unwrap();
+        commands.create(EditAccess::unrestricted(), Command::new("List".to_string(), "ls".to_string())).unwrap();
+
+        let search = storage
+            .create(EditAccess::unrestricted(), SavedSearch::new("Stale kubectl".to_string(), "command:~kubectl last_used:>90d".to_string()).unwrap())
+            .unwrap();
+        let found = storage.run_saved_search(search.id, &commands).unwrap();
+        assert_eq!(found.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["Pods"]);
+    }
+}
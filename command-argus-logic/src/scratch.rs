@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Built-in placeholder replaced with the scratch directory of the current run.
+pub const RUN_TMP_PLACEHOLDER: &str = "run_tmp";
+
+/// Whether a command gets a fresh temporary directory for each run, available as
+/// `{run_tmp}`. The directory is removed when the run ends, unless the run failed and
+/// `keep_on_failure` is set so its contents can be inspected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScratchDirPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub keep_on_failure: bool,
+}
+
+/// A temporary directory that lives as long as one run, removed when dropped unless kept.
+pub struct ScratchDir {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl ScratchDir {
+    /// Creates a new, empty directory in the system temporary directory. Every call
+    /// gets its own, so parallel runs of one command do not share their scratch space.
+    pub fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("argus-run-{}", Uuid::new_v4()));
+        fs::create_dir_all(&path)?;
+        Ok(Self { path, keep: false })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Leaves the directory and its contents in place when dropped.
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::command::Command;
+use crate::query::{IndexHint, Query};
+
+/// In-memory inverted index over a command library.
+///
+/// Names are indexed by lowercase character trigrams, so a substring search only
+/// checks the commands that contain every trigram of the search text. Tags are
+/// indexed by their lowercase form. Results keep the order of the library.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    commands: HashMap<Uuid, Command>,
+    order: HashMap<Uuid, u64>,
+    next_order: u64,
+    trigrams: HashMap<[char; 3], HashSet<Uuid>>,
+    tags: HashMap<String, HashSet<Uuid>>,
+}
+
+impl SearchIndex {
+    pub fn build(commands: impl IntoIterator<Item = Command>) -> Self {
+        let mut index = Self::default();
+        for command in commands {
+            index.insert(command);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Adds `command`, replacing an indexed command with the same id in place.
+    pub fn insert(&mut self, command: Command) {
+        let id = command.id;
+        if self.remove_postings(id).is_none() {
+            self.order.insert(id, self.next_order);
+            self.next_order += 1;
+        }
+        for trigram in trigrams(&command.name.to_lowercase()) {
+            self.trigrams.entry(trigram).or_default().insert(id);
+        }
+        for tag in &command.tags {
+            self.tags.entry(tag.to_lowercase()).or_default().insert(id);
+        }
+        self.commands.insert(id, command);
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> Option<Command> {
+        let command = self.remove_postings(id)?;
+        self.order.remove(&id);
+        Some(command)
+    }
+
+    /// Commands whose name contains `text`, ignoring case.
+    pub fn search_name(&self, text: &str) -> Vec<Command> {
+        let text = text.to_lowercase();
+        let ids = self.name_candidates(&text).unwrap_or_else(|| self.commands.keys().copied().collect());
+        self.collect(ids, |command| command.name.to_lowercase().contains(&text))
+    }
+
+    /// Commands having any of `tags`, compared exactly.
+    pub fn search_tags(&self, tags: &[String]) -> Vec<Command> {
+        let ids: HashSet<Uuid> = tags
+            .iter()
+            .filter_map(|tag| self.tags.get(&tag.to_lowercase()))
+            .flatten()
+            .copied()
+            .collect();
+        self.collect(ids, |command| tags.iter().any(|tag| command.tags.contains(tag)))
+    }
+
+    /// Commands matching `query`. Only the commands the index cannot rule out are
+    /// checked against the whole query.
+    pub fn search(&self, query: &Query) -> Vec<Command> {
+        let mut candidates: Option<HashSet<Uuid>> = None;
+        for hint in query.index_hints() {
+            let ids = match hint {
+                IndexHint::NameContains(text) => self.name_candidates(text),
+                IndexHint::Tag(tag) => Some(self.tags.get(tag).cloned().unwrap_or_default()),
+            };
+            if let Some(ids) = ids {
+                candidates = Some(match candidates {
+                    Some(candidates) => candidates.intersection(&ids).copied().collect(),
+                    None => ids,
+                });
+            }
+        }
+        let ids = candidates.unwrap_or_else(|| self.commands.keys().copied().collect());
+        let now = Utc::now();
+        self.collect(ids, |command| query.matches_at(command, now))
+    }
+
+    // Commands that may contain the lowercase `text` in their name, or None if the
+    // text is too short to narrow anything down
+    fn name_candidates(&self, text: &str) -> Option<HashSet<Uuid>> {
+        let mut candidates: Option<HashSet<Uuid>> = None;
+        for trigram in trigrams(text) {
+            let ids = self.trigrams.get(&trigram).cloned().unwrap_or_default();
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        candidates
+    }
+
+    fn collect(&self, ids: HashSet<Uuid>, keep: impl Fn(&Command) -> bool) -> Vec<Command> {
+        let mut ids: Vec<Uuid> = ids.into_iter().collect();
+        ids.sort_by_key(|id| self.order.get(id).copied());
+        ids.iter()
+            .filter_map(|id| self.commands.get(id))
+            .filter(|command| keep(command))
+            .cloned()
+            .collect()
+    }
+
+    fn remove_postings(&mut self, id: Uuid) -> Option<Command> {
+        let command = self.commands.remove(&id)?;
+        for trigram in trigrams(&command.name.to_lowercase()) {
+            if let Some(ids) = self.trigrams.get_mut(&trigram) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.trigrams.remove(&trigram);
+                }
+            }
+        }
+        for tag in &command.tags {
+            let tag = tag.to_lowercase();
+            if let Some(ids) = self.tags.get_mut(&tag) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.tags.remove(&tag);
+                }
+            }
+        }
+        Some(command)
+    }
+}
+
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str, tags: &[&str]) -> Command {
+        let mut cmd = Command::new(name.to_string(), "true".to_string());
+        for tag in tags {
+            cmd.add_tag(tag.to_string());
+        }
+        cmd
+    }
+
+    fn names(commands: Vec<Command>) -> Vec<String> {
+        commands.into_iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = SearchIndex::build(vec![
+            command("Build frontend", &["deploy"]),
+            command("Rebuild cache", &[]),
+            command("Deploy", &["Deploy", "prod"]),
+            command("Lint", &[]),
+        ]);
+        assert_eq!(names(index.search_name("BUILD")), vec!["Build frontend", "Rebuild cache"]);
+        assert_eq!(names(index.search_name("nt")), vec!["Build frontend", "Lint"]);
+        assert_eq!(names(index.search_tags(&["Deploy".to_string()])), vec!["Deploy"]);
+
+        let query = Query::parse("tag:deploy name:~build").unwrap();
+        assert_eq!(names(index.search(&query)), vec!["Build frontend"]);
+        let query = Query::parse("uild -tag:deploy").unwrap();
+        assert_eq!(names(index.search(&query)), vec!["Rebuild cache"]);
+
+        let lint = index.search_name("lint").remove(0);
+        index.insert(Command { name: "Lint all".to_string(), ..lint.clone() });
+        assert_eq!(index.len(), 4);
+        assert_eq!(names(index.search_name("all")), vec!["Lint all"]);
+        index.remove(lint.id);
+        assert!(index.search_name("lint").is_empty());
+        assert!(!index.trigrams.contains_key(&['l', 'i', 'n']));
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::executor::DEFAULT_CONTEXT_PREFIX;
+use crate::highlight::{default_highlight_rules, HighlightRule};
+use crate::notify::NotificationSettings;
+use crate::remap::PathRemapRule;
+use crate::restricted::{EditAccess, RestrictedMode};
+use crate::share::ShareTarget;
+use crate::shutdown::ShutdownSettings;
+use crate::storage::data_dir;
+
+/// Application-wide settings, persisted as `settings.json` in the data directory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Settings {
+    #[serde(default)]
+    pub restricted_mode: RestrictedMode,
+    /// Extra regexes whose matches are redacted from captured output.
+    #[serde(default)]
+    pub secret_patterns: Vec<String>,
+    /// What relative working directories are resolved against.
+    #[serde(default)]
+    pub working_directory_base: WorkingDirectoryBase,
+    /// Root of the most recently opened project.
+    #[serde(default)]
+    pub last_project_root: Option<String>,
+    /// How many commands the GUI runs at the same time; further ones are queued.
+    #[serde(default = "default_max_concurrent_executions")]
+    pub max_concurrent_executions: usize,
+    /// Set once the first-run starter commands were seeded or dismissed.
+    #[serde(default)]
+    pub onboarding_completed: bool,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Prefix of the variables telling commands how they were invoked, e.g.
+    /// `ARGUS_COMMAND_NAME`. Empty turns them off.
+    #[serde(default = "default_context_env_prefix")]
+    pub context_env_prefix: String,
+    /// How errors, warnings and passes stand out in captured output, in order of precedence.
+    #[serde(default = "default_highlight_rules")]
+    pub highlight_rules: Vec<HighlightRule>,
+    /// Gists and paste services runs can be shared to.
+    #[serde(default)]
+    pub share_targets: Vec<ShareTarget>,
+    /// What happens to running commands when the app quits.
+    #[serde(default)]
+    pub shutdown: ShutdownSettings,
+    /// Rewrites absolute paths of imported commands, e.g. another user's home directory.
+    #[serde(default)]
+    pub path_remap_rules: Vec<PathRemapRule>,
+}
+
+fn default_max_concurrent_executions() -> usize {
+    2
+}
+
+fn default_context_env_prefix() -> String {
+    DEFAULT_CONTEXT_PREFIX.to_string()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            restricted_mode: RestrictedMode::default(),
+            secret_patterns: Vec::new(),
+            working_directory_base: WorkingDirectoryBase::default(),
+            last_project_root: None,
+            max_concurrent_executions: default_max_concurrent_executions(),
+            onboarding_completed: false,
+            notifications: NotificationSettings::default(),
+            context_env_prefix: default_context_env_prefix(),
+            highlight_rules: default_highlight_rules(),
+            share_targets: Vec::new(),
+            shutdown: ShutdownSettings::default(),
+            path_remap_rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkingDirectoryBase {
+    #[default]
+    Home,
+    /// The last opened project, falling back to home when none was opened yet.
+    LastProject,
+}
+
+impl Settings {
+    /// The directory relative working directories are resolved against.
+    pub fn working_directory_base_dir(&self) -> Option<PathBuf> {
+        let home = || BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+        match (self.working_directory_base, &self.last_project_root) {
+            (WorkingDirectoryBase::LastProject, Some(root)) => Some(PathBuf::from(root)),
+            _ => home(),
+        }
+    }
+}
+
+pub struct SettingsStorage {
+    path: PathBuf,
+}
+
+impl SettingsStorage {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("settings.json"),
+        })
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn load(&self) -> Result<Settings> {
+        if !self.path.exists() {
+            return Ok(Settings::default());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Access for changing the library and the settings, as the current restricted mode
+    /// allows with `passphrase`.
+    pub fn edit_access(&self, passphrase: Option<&str>) -> Result<EditAccess> {
+        self.load()?.restricted_mode.check_edit(passphrase)
+    }
+
+    pub fn save(&self, _access: EditAccess, settings: &Settings) -> Result<()> {
+        self.write(settings)
+    }
+
+    /// Loads the settings, applies `update_fn` and saves them if it succeeds.
+    pub fn update<T>(&self, _access: EditAccess, update_fn: impl FnOnce(&mut Settings) -> Result<T>) -> Result<T> {
+        self.write_with(update_fn)
+    }
+
+    /// Remembers the project opened last. Like `complete_onboarding` it needs no
+    /// `EditAccess`: the app records how it is used, also in restricted mode.
+    pub fn remember_project_root(&self, root: String) -> Result<()> {
+        self.write_with(|settings| {
+            settings.last_project_root = Some(root);
+            Ok(())
+        })
+    }
+
+    pub fn complete_onboarding(&self) -> Result<()> {
+        self.write_with(|settings| {
+            settings.onboarding_completed = true;
+            Ok(())
+        })
+    }
+
+    fn write_with<T>(&self, update_fn: impl FnOnce(&mut Settings) -> Result<T>) -> Result<T> {
+        let mut settings = self.load()?;
+        let value = update_fn(&mut settings)?;
+        self.write(&settings)?;
+        Ok(value)
+    }
+
+    fn write(&self, settings: &Settings) -> Result<()> {
+        let content = serde_json::to_string_pretty(settings)?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CommandArgusError;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_defaults_and_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let storage = SettingsStorage::with_path(temp.path().join("settings.json")).unwrap();
+        assert_eq!(storage.load().unwrap(), Settings::default());
+
+        storage
+            .update(EditAccess::unrestricted(), |settings| settings.restricted_mode.enable("secret"))
+            .unwrap();
+        assert!(storage.load().unwrap().restricted_mode.enabled);
+    }
+
+    #[test]
+    fn test_edit_access_in_restricted_mode() {
+        let temp = TempDir::new().unwrap();
+        let storage = SettingsStorage::with_path(temp.path().join("settings.json")).unwrap();
+        let access = storage.edit_access(None).unwrap();
+        storage.update(access, |settings| settings.restricted_mode.enable("secret")).unwrap();
+
+        assert!(matches!(storage.edit_access(None), Err(CommandArgusError::AccessDenied(_))));
+        assert!(storage.edit_access(Some("wrong")).is_err());
+        let access = storage.edit_access(Some("secret")).unwrap();
+        storage.update(access, |settings| settings.restricted_mode.disable("secret")).unwrap();
+        assert!(storage.edit_access(None).is_ok());
+    }
+
+    #[test]
+    fn test_working_directory_base_dir() {
+        let mut settings = Settings {
+            working_directory_base: WorkingDirectoryBase::LastProject,
+            ..Settings::default()
+        };
+        assert_eq!(settings.working_directory_base_dir(), Settings::default().working_directory_base_dir());
+
+        settings.last_project_root = Some("/repo".to_string());
+        assert_eq!(settings.working_directory_base_dir(), Some(PathBuf::from("/repo")));
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+
+/// Shell used when a command is run in shell mode.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellKind {
+    /// zsh, or cmd.exe on Windows.
+    #[default]
+    System,
+    /// PowerShell 7 (`pwsh`), falling back to Windows PowerShell where it is missing.
+    #[serde(rename = "powershell")]
+    PowerShell,
+}
+
+/// How a command is run when the caller doesn't choose.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// The program is started with its arguments, without a shell.
+    Direct,
+    /// The command line is run by the command's shell.
+    #[default]
+    Shell,
+    /// The command line is opened in a terminal window; its output is not captured.
+    Terminal,
+    /// The program runs attached to a pseudo-terminal, for tools that only print colors
+    /// or progress to one. Not available on Windows.
+    Pty,
+}
+
+impl ExecutionMode {
+    /// The mode a caller's `use_shell` choice stands for.
+    pub fn from_use_shell(use_shell: bool) -> Self {
+        if use_shell {
+            ExecutionMode::Shell
+        } else {
+            ExecutionMode::Direct
+        }
+    }
+}
+
+/// The program and arguments running `line` attached to a pseudo-terminal, using
+/// `script` from util-linux or BSD.
+pub fn pty_invocation(line: &str) -> Option<(String, Vec<String>)> {
+    let args = if cfg!(target_os = "macos") {
+        vec!["-q", "/dev/null", "sh", "-c", line]
+    } else if cfg!(unix) {
+        vec!["-qec", line, "/dev/null"]
+    } else {
+        return None;
+    };
+    Some(("script".to_string(), args.into_iter().map(String::from).collect()))
+}
+
+/// The program and arguments opening `line` in a new terminal window. Terminal.app
+/// starts its shell in the home directory with its own environment, so `prelude` (e.g.
+/// `cd` and loading the variables) is run first there; elsewhere the window inherits the process's
+/// directory and environment. On Linux, `$TERMINAL` is preferred over
+/// `x-terminal-emulator`.
+pub fn terminal_invocation(line: &str, prelude: &str) -> (String, Vec<String>) {
+    if cfg!(target_os = "macos") {
+        let script = format!("{}{}", prelude, line).replace('\\', "\\\\").replace('"', "\\\"");
+        let apple_script = format!("tell application \"Terminal\" to do script \"{}\"", script);
+        ("osascript".to_string(), vec!["-e".to_string(), apple_script])
+    } else if cfg!(windows) {
+        let args = ["/C", "start", "", "cmd", "/K", line];
+        ("cmd".to_string(), args.into_iter().map(String::from).collect())
+    } else {
+        let terminal = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty()).unwrap_or_else(|| "x-terminal-emulator".to_string());
+        let keep_open = format!("{}; printf '\\n[exited with %s] ' \"$?\"; read -r _", line);
+        (terminal, vec!["-e".to_string(), "sh".to_string(), "-c".to_string(), keep_open])
+    }
+}
+
+/// Quotes `value` as a PowerShell verbatim string: single quotes, with embedded single
+/// quotes (including the typographic ones PowerShell also accepts) doubled.
+pub fn powershell_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// The script passed to `-Command`. The program text is used as written so it may be a
+/// pipeline, unless it is a path to an executable; every argument is quoted. PowerShell only reports success or failure of the
+/// last statement, so the native `$LASTEXITCODE` is passed on as the exit code.
+pub fn powershell_script(command: &Command) -> String {
+    let is_pipeline = command.command.contains(char::is_whitespace) && !Path::new(&command.command).is_absolute();
+    let mut invocation = if is_pipeline {
+        command.command.clone()
+    } else {
+        format!("& {}", powershell_quote(&command.command))
+    };
+    for arg in &command.args {
+        invocation.push(' ');
+        invocation.push_str(&powershell_quote(arg));
+    }
+
+    let mut script = String::new();
+    if command.mise_enabled {
+        script.push_str("mise activate pwsh | Out-String | Invoke-Expression; ");
+    }
+    script.push_str("$global:LASTEXITCODE = 0; ");
+    script.push_str(&invocation);
+    script.push_str("; if (-not $?) { if ($LASTEXITCODE) { exit $LASTEXITCODE } else { exit 1 } }; exit $LASTEXITCODE");
+    script
+}
+
+/// Arguments for the PowerShell executable. The user profile is skipped unless
+/// `load_profile` is set, as it slows down every run and may print to the output.
+pub fn powershell_args(script: &str, load_profile: bool) -> Vec<String> {
+    let mut args = vec!["-NoLogo".to_string(), "-NonInteractive".to_string()];
+    if !load_profile {
+        args.push("-NoProfile".to_string());
+    }
+    args.push("-Command".to_string());
+    args.push(script.to_string());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_powershell_quote() {
+        assert_eq!(powershell_quote("plain"), "'plain'");
+        assert_eq!(powershell_quote("it's $HOME"), "'it''s $HOME'");
+        assert_eq!(powershell_quote("a\u{2019}b"), "'a\u{2019}\u{2019}b'");
+    }
+
+    #[test]
+    fn test_powershell_script() {
+        let cmd = Command::new("Copy".to_string(), "Copy-Item".to_string())
+            .with_args(vec!["my file.txt".to_string(), "dest".to_string()]);
+        let script = powershell_script(&cmd);
+        assert!(script.contains("& 'Copy-Item' 'my file.txt' 'dest';"));
+        assert!(script.ends_with("exit $LASTEXITCODE"));
+
+        let pipeline = Command::new("Procs".to_string(), "Get-Process | Select-Object -First".to_string())
+            .with_args(vec!["5".to_string()]);
+        assert!(powershell_script(&pipeline).contains("Get-Process | Select-Object -First '5';"));
+    }
+
+    #[test]
+    fn test_powershell_args() {
+        assert_eq!(
+            powershell_args("exit 0", false),
+            vec!["-NoLogo", "-NonInteractive", "-NoProfile", "-Command", "exit 0"]
+        );
+        assert!(!powershell_args("exit 0", true).contains(&"-NoProfile".to_string()));
+    }
+}
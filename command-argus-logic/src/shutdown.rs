@@ -0,0 +1,420 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::history::{ExecutionRecord, ExecutionSummary, HistoryStorage};
+use crate::storage::data_dir;
+
+/// What happens to commands still running when the app quits.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownPolicy {
+    /// Ask which of the others to do, each time.
+    #[default]
+    Ask,
+    /// Quit once every command has finished.
+    Wait,
+    /// Ask the commands to stop (SIGTERM), kill those still running after the grace
+    /// period (SIGKILL), then quit.
+    Terminate,
+    /// Quit and leave the commands running, without recording how they end.
+    Detach,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShutdownSettings {
+    #[serde(default)]
+    pub policy: ShutdownPolicy,
+    /// How long terminated commands get to exit before they are killed.
+    #[serde(default = "default_grace_secs")]
+    pub grace_secs: u64,
+}
+
+fn default_grace_secs() -> u64 {
+    10
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self { policy: ShutdownPolicy::default(), grace_secs: default_grace_secs() }
+    }
+}
+
+/// Why a run ended without its outcome, as recorded in history.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Interruption {
+    /// Stopped because the app quit.
+    Terminated,
+    /// Left running when the app quit.
+    Detached,
+    /// Running when the app ended without shutting down, e.g. on a crash.
+    Lost,
+}
+
+impl Interruption {
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            Interruption::Terminated => "Terminated when the app quit",
+            Interruption::Detached => "Left running when the app quit; how it ended is unknown",
+            Interruption::Lost => "Still running when the app last exited unexpectedly; how it ended is unknown",
+        }
+    }
+}
+
+/// A command running in a child process.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunningJob {
+    pub run_id: Uuid,
+    pub command_id: Uuid,
+    pub command_name: String,
+    pub command_line: String,
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    /// Set once the job is terminated or detached at shutdown.
+    #[serde(default)]
+    pub interruption: Option<Interruption>,
+}
+
+/// The commands running right now, persisted as `running.json` in the data directory
+/// while any are, so the runs a quit or crash cut short can be recorded on the next
+/// start with `reconcile_interrupted`.
+pub struct RunningJobs {
+    path: PathBuf,
+    jobs: Mutex<Vec<RunningJob>>,
+    changed: Condvar,
+    closed: AtomicBool,
+}
+
+impl RunningJobs {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_path(data_dir()?.join("running.json")))
+    }
+
+    /// Starts empty; whatever `path` holds is left for `take_leftovers`.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self { path, jobs: Mutex::new(Vec::new()), changed: Condvar::new(), closed: AtomicBool::new(false) }
+    }
+
+    /// The jobs that were running when the previous session ended, removed from the
+    /// file. Jobs never terminated or detached were lost. A file that cannot be read is
+    /// removed too, so it fails one start rather than all of them.
+    pub fn take_leftovers(&self) -> Result<Vec<RunningJob>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let leftovers = fs::read_to_string(&self.path)
+            .map_err(Into::into)
+            .and_then(|text| serde_json::from_str::<Vec<RunningJob>>(&text).map_err(Into::into));
+        let mut leftovers = match leftovers {
+            Ok(leftovers) => leftovers,
+            Err(e) => {
+                let _ = fs::remove_file(&self.path);
+                return Err(e);
+            }
+        };
+        for job in &mut leftovers {
+            job.interruption.get_or_insert(Interruption::Lost);
+        }
+        self.save(&self.lock())?;
+        Ok(leftovers)
+    }
+
+    pub fn list(&self) -> Vec<RunningJob> {
+        self.lock().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Refuses new jobs from now on, as the app is quitting. Executors tracking their runs
+    /// here fail them instead of starting the process.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    pub fn start(&self, job: RunningJob) -> Result<()> {
+        let mut jobs = self.lock();
+        jobs.push(job);
+        self.save(&jobs)
+    }
+
+    /// Removes the job once its process has exited, returning how it was interrupted,
+    /// if it was.
+    pub fn finish(&self, run_id: Uuid) -> Option<Interruption> {
+        let mut jobs = self.lock();
+        let index = jobs.iter().position(|job| job.run_id == run_id)?;
+        let job = jobs.remove(index);
+        // A stale entry is recorded as lost on the next start, which beats failing the run
+        let _ = self.save(&jobs);
+        self.changed.notify_all();
+        job.interruption
+    }
+
+    /// Blocks until no job is running, or `timeout` passes. Returns whether all finished.
+    pub fn wait_idle(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut jobs = self.lock();
+        while !jobs.is_empty() {
+            jobs = match deadline {
+                Some(deadline) => {
+                    let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                        return false;
+                    };
+                    self.changed.wait_timeout(jobs, left).unwrap().0
+                }
+                None => self.changed.wait(jobs).unwrap(),
+            };
+        }
+        true
+    }
+
+    /// Asks every job to stop and kills those still running after `grace`. Returns
+    /// once all have exited.
+    pub fn terminate_all(&self, grace: Duration) {
+        for pid in self.interrupt_all(Interruption::Terminated) {
+            signal_process(pid, false);
+        }
+        if !self.wait_idle(Some(grace)) {
+            for job in self.list() {
+                signal_process(job.pid, true);
+            }
+            self.wait_idle(None);
+        }
+    }
+
+    /// Marks every job as left running, so the next start records them as detached
+    /// rather than lost.
+    pub fn detach_all(&self) {
+        self.interrupt_all(Interruption::Detached);
+    }
+
+    fn interrupt_all(&self, interruption: Interruption) -> Vec<u32> {
+        let mut jobs = self.lock();
+        for job in jobs.iter_mut() {
+            job.interruption = Some(interruption);
+        }
+        let _ = self.save(&jobs);
+        jobs.iter().map(|job| job.pid).collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<RunningJob>> {
+        self.jobs.lock().unwrap()
+    }
+
+    fn save(&self, jobs: &[RunningJob]) -> Result<()> {
+        if jobs.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside and renamed over, so a crash mid-write leaves the previous list
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(jobs)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Records the jobs left over from the previous session in history. Called on start,
+/// before anything runs.
+pub fn reconcile_interrupted(jobs: &RunningJobs, history: &HistoryStorage) -> Result<Vec<ExecutionRecord>> {
+    jobs.take_leftovers()?
+        .into_iter()
+        .map(|job| history.record(interrupted_record(&job)))
+        .collect()
+}
+
+/// Runs the previous session left behind, for the GUI to point out once on start.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecoveryNotice {
+    /// Whether the session ended without shutting down, e.g. on a crash, rather than
+    /// only leaving detached commands behind.
+    pub crashed: bool,
+    pub runs: Vec<ExecutionSummary>,
+}
+
+impl RecoveryNotice {
+    /// The notice for the records `reconcile_interrupted` made, if it made any.
+    pub fn from_records(records: &[ExecutionRecord]) -> Option<Self> {
+        if records.is_empty() {
+            return None;
+        }
+        Some(Self {
+            crashed: records.iter().any(|record| record.interrupted == Some(Interruption::Lost)),
+            runs: records.iter().map(ExecutionRecord::summary).collect(),
+        })
+    }
+}
+
+fn interrupted_record(job: &RunningJob) -> ExecutionRecord {
+    let interruption = job.interruption.unwrap_or(Interruption::Lost);
+    ExecutionRecord {
+        id: job.run_id,
+        command_id: job.command_id,
+        command_name: job.command_name.clone(),
+        command_line: job.command_line.clone(),
+        started_at: job.started_at,
+        finished_at: Utc::now(),
+        exit_code: None,
+        success: false,
+        stdout: String::new(),
+        stderr: String::new(),
+        error: Some(interruption.describe().to_string()),
+        compressed_outputs: Vec::new(),
+        artifacts: Vec::new(),
+        stdin: None,
+        interrupted: Some(interruption),
+        parameters: Default::default(),
+        note: None,
+        tags: Vec::new(),
+        phases: Vec::new(),
+    }
+}
+
+// Jobs run in process groups of their own, so the whole group gets the signal,
+// including whatever a shell started.
+#[cfg(unix)]
+fn signal_process(pid: u32, force: bool) {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+#[cfg(windows)]
+fn signal_process(pid: u32, force: bool) {
+    let mut taskkill = std::process::Command::new("taskkill");
+    taskkill.args(["/PID", &pid.to_string(), "/T"]);
+    if force {
+        taskkill.arg("/F");
+    }
+    let _ = taskkill.output();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+    use crate::executor::CommandExecutor;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_running_jobs_and_reconcile() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("running.json");
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+        let job = |name: &str| RunningJob {
+            run_id: Uuid::new_v4(),
+            command_id: Uuid::new_v4(),
+            command_name: name.to_string(),
+            command_line: format!("{} --watch", name),
+            pid: u32::MAX,
+            started_at: Utc::now(),
+            interruption: None,
+        };
+
+        // A session that crashed with one job and had detached another
+        let previous = RunningJobs::with_path(path.clone());
+        let (lost, detached) = (job("lost"), job("detached"));
+        previous.start(lost.clone()).unwrap();
+        previous.start(detached.clone()).unwrap();
+        previous.finish(lost.run_id);
+        previous.start(lost.clone()).unwrap();
+        previous.lock().iter_mut().find(|j| j.run_id == detached.run_id).unwrap().interruption = Some(Interruption::Detached);
+        previous.save(&previous.lock()).unwrap();
+
+        let jobs = RunningJobs::with_path(path.clone());
+        let records = reconcile_interrupted(&jobs, &history).unwrap();
+        let found: Vec<(&str, Option<Interruption>)> =
+            records.iter().map(|r| (r.command_name.as_str(), r.interrupted)).collect();
+        assert_eq!(found, vec![("detached", Some(Interruption::Detached)), ("lost", Some(Interruption::Lost))]);
+        assert_eq!(history.get(lost.run_id).unwrap().exit_code, None);
+        assert!(!path.exists());
+        let notice = RecoveryNotice::from_records(&records).unwrap();
+        assert!(notice.crashed);
+        assert_eq!(notice.runs[1].interrupted, Some(Interruption::Lost));
+        assert!(reconcile_interrupted(&jobs, &history).unwrap().is_empty());
+        assert_eq!(RecoveryNotice::from_records(&[]), None);
+
+        // A file cut short fails this start only
+        fs::write(&path, "[{\"run_id\":").unwrap();
+        assert!(reconcile_interrupted(&jobs, &history).is_err());
+        assert!(reconcile_interrupted(&jobs, &history).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wait_idle_and_failed_saves() {
+        let temp = TempDir::new().unwrap();
+        let jobs = RunningJobs::with_path(temp.path().join("running.json"));
+        assert!(jobs.wait_idle(Some(Duration::ZERO)));
+        let job = RunningJob {
+            run_id: Uuid::new_v4(),
+            command_id: Uuid::new_v4(),
+            command_name: "watch".to_string(),
+            command_line: "watch date".to_string(),
+            pid: u32::MAX,
+            started_at: Utc::now(),
+            interruption: None,
+        };
+        jobs.start(job.clone()).unwrap();
+        assert!(!jobs.wait_idle(Some(Duration::from_millis(50))));
+        assert_eq!(jobs.finish(Uuid::new_v4()), None);
+        jobs.detach_all();
+        assert_eq!(jobs.finish(job.run_id), Some(Interruption::Detached));
+        assert!(jobs.wait_idle(Some(Duration::ZERO)));
+
+        // Saving fails when the registry's folder cannot be created
+        fs::write(temp.path().join("file"), "").unwrap();
+        let unsaved = RunningJobs::with_path(temp.path().join("file").join("running.json"));
+        assert!(unsaved.start(job).is_err());
+        assert!(unsaved.take_leftovers().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate_running_job() {
+        let temp = TempDir::new().unwrap();
+        let jobs = Arc::new(RunningJobs::with_path(temp.path().join("running.json")));
+        let executor = CommandExecutor::new().with_jobs(jobs.clone());
+        let mut command = Command::new("sleeper".to_string(), "sleep".to_string());
+        command.args = vec!["30".to_string()];
+        let run = std::thread::spawn(move || executor.execute(&command).unwrap());
+
+        let started = Instant::now();
+        while jobs.is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(jobs.list()[0].command_name, "sleeper");
+        assert!(temp.path().join("running.json").exists());
+
+        jobs.close();
+        jobs.terminate_all(Duration::from_secs(5));
+        let result = run.join().unwrap();
+        assert_eq!(result.interrupted, Some(Interruption::Terminated));
+        assert!(!result.success);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(!temp.path().join("running.json").exists());
+
+        let command = Command::new("late".to_string(), "true".to_string());
+        assert!(CommandExecutor::new().with_jobs(jobs).execute(&command).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::history::HistoryStorage;
+use crate::storage::CommandStorage;
+
+/// How much a command of the library is used and how its runs go.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandStats {
+    pub id: Uuid,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub use_count: u32,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Runs still in the history, which may have been pruned.
+    pub runs: usize,
+    pub successes: usize,
+}
+
+impl CommandStats {
+    /// The share of the recorded runs that succeeded, if there are any.
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.runs > 0).then(|| self.successes as f64 / self.runs as f64)
+    }
+}
+
+/// Statistics for every command of the library, most used first.
+pub fn library_stats(commands: &CommandStorage, history: &HistoryStorage) -> Result<Vec<CommandStats>> {
+    let mut outcomes: HashMap<Uuid, (usize, usize)> = HashMap::new();
+    for record in history.list()? {
+        let entry = outcomes.entry(record.command_id).or_default();
+        entry.0 += 1;
+        entry.1 += usize::from(record.success);
+    }
+
+    let mut stats: Vec<CommandStats> = commands
+        .list()?
+        .into_iter()
+        .map(|command| {
+            let (runs, successes) = outcomes.get(&command.id).copied().unwrap_or_default();
+            CommandStats {
+                id: command.id,
+                name: command.name,
+                tags: command.tags,
+                use_count: command.use_count,
+                last_used_at: command.last_used_at,
+                runs,
+                successes,
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| b.use_count.cmp(&a.use_count).then_with(|| a.name.cmp(&b.name)));
+    Ok(stats)
+}
+
+/// The statistics as CSV with a header row. Tags are separated by `;` and the success
+/// rate is a percentage, empty for commands without recorded runs.
+pub fn stats_to_csv(stats: &[CommandStats]) -> String {
+    let mut csv = String::from("id,name,tags,use_count,last_used_at,runs,successes,success_rate\n");
+    for entry in stats {
+        let fields = [
+            entry.id.to_string(),
+            entry.name.clone(),
+            entry.tags.join(";"),
+            entry.use_count.to_string(),
+            entry.last_used_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            entry.runs.to_string(),
+            entry.successes.to_string(),
+            entry.success_rate().map(|rate| format!("{:.1}", rate * 100.0)).unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Writes the library's statistics to `path` as CSV and returns how many commands it has.
+pub fn export_stats_csv(commands: &CommandStorage, history: &HistoryStorage, path: &Path) -> Result<usize> {
+    let stats = library_stats(commands, history)?;
+    fs::write(path, stats_to_csv(&stats))?;
+    Ok(stats.len())
+}
+
+/// Quotes a field when it needs it, and defuses text a spreadsheet would take for a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) { format!("'{}", value) } else { value.to_string() };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use crate::command::Command;
+    use crate::history::ExecutionRecord;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_stats_csv() {
+        let temp = TempDir::new().unwrap();
+        let commands = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+
+        let mut deploy = Command::new("Deploy, prod".to_string(), "make".to_string());
+        deploy.tags = vec!["ops".to_string(), "release".to_string()];
+        deploy.use_count = 3;
+        let deploy = commands.create(EditAccess::unrestricted(), deploy).unwrap();
+        commands.create(EditAccess::unrestricted(), Command::new("=cmd".to_string(), "true".to_string())).unwrap();
+        for success in [true, true, false] {
+            let now = Utc::now();
+            history
+                .record(ExecutionRecord {
+                    id: Uuid::new_v4(),
+                    command_id: deploy.id,
+                    command_name: deploy.name.clone(),
+                    command_line: "make".to_string(),
+                    started_at: now,
+                    finished_at: now,
+                    exit_code: Some(if success { 0 } else { 1 }),
+                    success,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    error: None,
+                    compressed_outputs: Vec::new(),
+                    artifacts: Vec::new(),
+                    stdin: None,
+                    interrupted: None,
+                    parameters: Default::default(),
+                    note: None,
+                    tags: Vec::new(),
+                    phases: Vec::new(),
+                })
+                .unwrap();
+        }
+
+        let path = temp.path().join("stats.csv");
+        assert_eq!(export_stats_csv(&commands, &history, &path).unwrap(), 2);
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,name,tags,use_count,last_used_at,runs,successes,success_rate");
+        assert_eq!(lines[1], format!("{},\"Deploy, prod\",ops;release,3,,3,2,66.7", deploy.id));
+        assert!(lines[2].contains(",'=cmd,,0,,0,0,"));
+    }
+}
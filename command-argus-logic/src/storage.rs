@@ -1,34 +1,51 @@
-use crate::{Command, CommandArgusError, Result};
+use crate::{Command, CommandArgusError, EditAccess, Result, SearchIndex};
 use directories::ProjectDirs;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
 use uuid::Uuid;
 
+/// Returns the application data directory, creating it if needed.
+pub fn data_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "command-argus", "command-argus")
+        .ok_or_else(|| CommandArgusError::Storage("Failed to get project directories".to_string()))?;
+    
+    let storage_dir = proj_dirs.data_dir();
+    fs::create_dir_all(storage_dir)?;
+    Ok(storage_dir.to_path_buf())
+}
+
 pub struct CommandStorage {
     storage_path: PathBuf,
+    // Built on the first search and updated by every write. Rebuilt if the file
+    // was changed behind our back, e.g. by another process.
+    index: Mutex<Option<CachedIndex>>,
+}
+
+struct CachedIndex {
+    stamp: Option<FileStamp>,
+    index: SearchIndex,
 }
 
+// Modification time and length of the storage file
+type FileStamp = (SystemTime, u64);
+
 impl CommandStorage {
     pub fn new() -> Result<Self> {
-        let proj_dirs = ProjectDirs::from("com", "command-argus", "command-argus")
-            .ok_or_else(|| CommandArgusError::Storage("Failed to get project directories".to_string()))?;
-        
-        let storage_dir = proj_dirs.data_dir();
-        fs::create_dir_all(storage_dir)?;
+        let storage_path = data_dir()?.join("commands.json");
         
-        let storage_path = storage_dir.join("commands.json");
-        
-        Ok(Self { storage_path })
+        Ok(Self { storage_path, index: Mutex::new(None) })
     }
 
     pub fn with_path(path: PathBuf) -> Result<Self> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        Ok(Self { storage_path: path })
+        Ok(Self { storage_path: path, index: Mutex::new(None) })
     }
 
-    pub fn create(&self, command: Command) -> Result<Command> {
+    pub fn create(&self, _access: EditAccess, command: Command) -> Result<Command> {
         let mut commands = self.load_all()?;
         
         // Check for duplicate names
@@ -37,7 +54,8 @@ impl CommandStorage {
         }
         
         commands.push(command.clone());
-        self.save_all(&commands)?;
+        let indexed = command.clone();
+        self.write_all(&commands, |index| index.insert(indexed))?;
         
         Ok(command)
     }
@@ -56,7 +74,17 @@ impl CommandStorage {
             .ok_or_else(|| CommandArgusError::Storage(format!("Command with name '{}' not found", name)))
     }
 
-    pub fn update(&self, id: Uuid, mut update_fn: impl FnMut(&mut Command)) -> Result<Command> {
+    pub fn update(&self, _access: EditAccess, id: Uuid, update_fn: impl FnMut(&mut Command)) -> Result<Command> {
+        self.write_command(id, update_fn)
+    }
+
+    /// Counts a run of the command, which needs no `EditAccess`: it is no edit, and
+    /// commands still run in restricted mode.
+    pub fn record_use(&self, id: Uuid) -> Result<Command> {
+        self.write_command(id, Command::mark_as_used)
+    }
+
+    fn write_command(&self, id: Uuid, mut update_fn: impl FnMut(&mut Command)) -> Result<Command> {
         let mut commands = self.load_all()?;
         
         let command = commands.iter_mut()
@@ -67,12 +95,13 @@ impl CommandStorage {
         command.update();
         
         let updated_command = command.clone();
-        self.save_all(&commands)?;
+        let indexed = updated_command.clone();
+        self.write_all(&commands, |index| index.insert(indexed))?;
         
         Ok(updated_command)
     }
 
-    pub fn delete(&self, id: Uuid) -> Result<()> {
+    pub fn delete(&self, _access: EditAccess, id: Uuid) -> Result<()> {
         let mut commands = self.load_all()?;
         let initial_len = commands.len();
         
@@ -82,27 +111,57 @@ impl CommandStorage {
             return Err(CommandArgusError::NotFound(id));
         }
         
-        self.save_all(&commands)?;
-        Ok(())
+        self.write_all(&commands, |index| {
+            index.remove(id);
+        })
     }
 
     pub fn list(&self) -> Result<Vec<Command>> {
         self.load_all()
     }
 
+    /// Replaces the whole library, e.g. after merging with a remote copy.
+    pub fn replace_all(&self, _access: EditAccess, commands: &[Command]) -> Result<()> {
+        self.write_all(commands, |index| *index = SearchIndex::build(commands.iter().cloned()))
+    }
+
     pub fn search_by_tags(&self, tags: &[String]) -> Result<Vec<Command>> {
-        let commands = self.load_all()?;
-        Ok(commands.into_iter()
-            .filter(|c| tags.iter().any(|tag| c.tags.contains(tag)))
-            .collect())
+        self.with_index(|index| index.search_tags(tags))
     }
 
     pub fn search_by_name(&self, query: &str) -> Result<Vec<Command>> {
-        let commands = self.load_all()?;
-        let query_lower = query.to_lowercase();
-        Ok(commands.into_iter()
-            .filter(|c| c.name.to_lowercase().contains(&query_lower))
-            .collect())
+        self.with_index(|index| index.search_name(query))
+    }
+
+    /// Runs `search` against the index, building it first if needed.
+    pub(crate) fn with_index<T>(&self, search: impl FnOnce(&SearchIndex) -> T) -> Result<T> {
+        let mut cache = self.index.lock().unwrap();
+        let stamp = self.stamp();
+        if cache.as_ref().is_none_or(|cached| cached.stamp != stamp) {
+            *cache = Some(CachedIndex { stamp, index: SearchIndex::build(self.load_all()?) });
+        }
+        Ok(search(&cache.as_ref().unwrap().index))
+    }
+
+    // Saves `commands` and applies the same change to the index. An index that was
+    // already out of date is dropped instead, to be rebuilt by the next search.
+    fn write_all(&self, commands: &[Command], change: impl FnOnce(&mut SearchIndex)) -> Result<()> {
+        let mut cache = self.index.lock().unwrap();
+        let before = self.stamp();
+        self.save_all(commands)?;
+        match cache.as_mut() {
+            Some(cached) if cached.stamp == before => {
+                change(&mut cached.index);
+                cached.stamp = self.stamp();
+            }
+            _ => *cache = None,
+        }
+        Ok(())
+    }
+
+    fn stamp(&self) -> Option<FileStamp> {
+        let metadata = fs::metadata(&self.storage_path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
     }
 
     fn load_all(&self) -> Result<Vec<Command>> {
@@ -147,7 +206,7 @@ mod tests {
         let cmd = Command::new("Test Command".to_string(), "echo".to_string())
             .with_args(vec!["hello".to_string()]);
         
-        let created = storage.create(cmd.clone()).unwrap();
+        let created = storage.create(EditAccess::unrestricted(), cmd.clone()).unwrap();
         assert_eq!(created.name, "Test Command");
         
         let read = storage.read(created.id).unwrap();
@@ -162,8 +221,8 @@ mod tests {
         let cmd1 = Command::new("Duplicate".to_string(), "echo".to_string());
         let cmd2 = Command::new("Duplicate".to_string(), "ls".to_string());
         
-        storage.create(cmd1).unwrap();
-        let result = storage.create(cmd2);
+        storage.create(EditAccess::unrestricted(), cmd1).unwrap();
+        let result = storage.create(EditAccess::unrestricted(), cmd2);
         
         assert!(matches!(result, Err(CommandArgusError::DuplicateName(_))));
     }
@@ -173,9 +232,9 @@ mod tests {
         let (storage, _temp) = temp_storage();
         
         let cmd = Command::new("Original".to_string(), "echo".to_string());
-        let created = storage.create(cmd).unwrap();
+        let created = storage.create(EditAccess::unrestricted(), cmd).unwrap();
         
-        let updated = storage.update(created.id, |c| {
+        let updated = storage.update(EditAccess::unrestricted(), created.id, |c| {
             c.name = "Updated".to_string();
             c.add_tag("test".to_string());
         }).unwrap();
@@ -189,9 +248,9 @@ mod tests {
         let (storage, _temp) = temp_storage();
         
         let cmd = Command::new("To Delete".to_string(), "echo".to_string());
-        let created = storage.create(cmd).unwrap();
+        let created = storage.create(EditAccess::unrestricted(), cmd).unwrap();
         
-        storage.delete(created.id).unwrap();
+        storage.delete(EditAccess::unrestricted(), created.id).unwrap();
         
         let result = storage.read(created.id);
         assert!(matches!(result, Err(CommandArgusError::NotFound(_))));
@@ -205,8 +264,8 @@ mod tests {
         let mut cmd2 = Command::new("Second Command".to_string(), "ls".to_string());
         cmd2.add_tag("filesystem".to_string());
         
-        storage.create(cmd1).unwrap();
-        storage.create(cmd2).unwrap();
+        storage.create(EditAccess::unrestricted(), cmd1).unwrap();
+        storage.create(EditAccess::unrestricted(), cmd2).unwrap();
         
         let all = storage.list().unwrap();
         assert_eq!(all.len(), 2);
@@ -219,4 +278,23 @@ mod tests {
         assert_eq!(by_tag.len(), 1);
         assert_eq!(by_tag[0].name, "Second Command");
     }
+
+    #[test]
+    fn test_search_index_follows_changes() {
+        let (storage, temp) = temp_storage();
+        let created = storage.create(EditAccess::unrestricted(), Command::new("Build".to_string(), "make".to_string())).unwrap();
+        assert_eq!(storage.search_by_name("build").unwrap().len(), 1);
+
+        storage.update(EditAccess::unrestricted(), created.id, |c| c.name = "Compile".to_string()).unwrap();
+        assert!(storage.search_by_name("build").unwrap().is_empty());
+        assert_eq!(storage.search_by_name("compile").unwrap().len(), 1);
+
+        // Changes made by someone else are picked up as well
+        let other = CommandStorage::with_path(temp.path().join("commands.json")).unwrap();
+        other.create(EditAccess::unrestricted(), Command::new("Build docs".to_string(), "make".to_string())).unwrap();
+        assert_eq!(storage.search_by_name("build").unwrap().len(), 1);
+
+        storage.delete(EditAccess::unrestricted(), created.id).unwrap();
+        assert!(storage.search_by_name("compile").unwrap().is_empty());
+    }
 }
\ No newline at end of file
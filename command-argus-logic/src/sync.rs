@@ -0,0 +1,399 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command::{Command, CommandSource};
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::storage::CommandStorage;
+
+/// Connection settings for a team sync server.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncConfig {
+    pub url: String,
+    pub token: String,
+    /// Identity of this user; new commands pushed from here are owned by it.
+    pub user: String,
+}
+
+impl SyncConfig {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self, _access: EditAccess, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A command as stored on the sync server, together with the user who owns it.
+/// Only the owner's changes are accepted by `SyncClient::sync`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RemoteCommand {
+    pub owner: String,
+    pub command: Command,
+}
+
+pub trait SyncTransport {
+    fn fetch(&self) -> Result<Vec<RemoteCommand>>;
+    fn push(&self, commands: &[RemoteCommand]) -> Result<()>;
+}
+
+/// Talks to `GET/PUT <url>/commands` with a bearer token.
+pub struct HttpTransport {
+    config: SyncConfig,
+}
+
+impl HttpTransport {
+    pub fn new(config: SyncConfig) -> Self {
+        Self { config }
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/commands", self.config.url.trim_end_matches('/'))
+    }
+
+    fn authorization(&self) -> String {
+        format!("Bearer {}", self.config.token)
+    }
+}
+
+impl SyncTransport for HttpTransport {
+    fn fetch(&self) -> Result<Vec<RemoteCommand>> {
+        ureq::get(&self.endpoint())
+            .set("Authorization", &self.authorization())
+            .call()
+            .map_err(|e| CommandArgusError::Sync(e.to_string()))?
+            .into_json()
+            .map_err(|e| CommandArgusError::Sync(e.to_string()))
+    }
+
+    fn push(&self, commands: &[RemoteCommand]) -> Result<()> {
+        ureq::put(&self.endpoint())
+            .set("Authorization", &self.authorization())
+            .send_json(commands)
+            .map_err(|e| CommandArgusError::Sync(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct SyncEntry {
+    updated_at: DateTime<Utc>,
+    owner: String,
+}
+
+/// What both sides looked like after the previous sync; used to tell which side changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct SyncState {
+    entries: HashMap<Uuid, SyncEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeptLocal,
+    TookRemote,
+}
+
+/// A command that was modified on both sides since the last sync. The newer
+/// modification wins, unless the local user doesn't own the command.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SyncConflict {
+    pub id: Uuid,
+    pub name: String,
+    pub owner: String,
+    pub local_updated_at: DateTime<Utc>,
+    pub remote_updated_at: DateTime<Utc>,
+    pub resolution: ConflictResolution,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SyncReport {
+    pub pulled: Vec<Uuid>,
+    pub pushed: Vec<Uuid>,
+    /// Local edits to commands owned by someone else; they were replaced by the remote copy.
+    pub rejected: Vec<Uuid>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+pub struct SyncClient<T: SyncTransport> {
+    transport: T,
+    user: String,
+    state_path: PathBuf,
+}
+
+impl<T: SyncTransport> SyncClient<T> {
+    pub fn new(transport: T, user: String, state_path: PathBuf) -> Self {
+        Self {
+            transport,
+            user,
+            state_path,
+        }
+    }
+
+    /// Pulls remote changes into `storage` and pushes local changes owned by this user.
+    /// Deletions are not propagated in either direction.
+    pub fn sync(&self, storage: &CommandStorage, access: EditAccess) -> Result<SyncReport> {
+        let state = self.load_state()?;
+        let local_commands = storage.list()?;
+        let local: HashMap<Uuid, Command> =
+            local_commands.iter().map(|c| (c.id, c.clone())).collect();
+        let remote: HashMap<Uuid, RemoteCommand> = self
+            .transport
+            .fetch()?
+            .into_iter()
+            .map(|r| (r.command.id, self.with_provenance(r)))
+            .collect();
+
+        let mut report = SyncReport::default();
+        let mut merged: Vec<Command> = Vec::new();
+        let mut to_push: Vec<RemoteCommand> = Vec::new();
+        let mut new_state = SyncState::default();
+
+        // Keep the local order, then append commands that only exist remotely
+        let mut ids: Vec<Uuid> = local_commands.iter().map(|c| c.id).collect();
+        let remote_only: BTreeSet<Uuid> = remote
+            .keys()
+            .filter(|id| !local.contains_key(id))
+            .copied()
+            .collect();
+        ids.extend(remote_only);
+
+        for id in ids {
+            let base = state.entries.get(&id);
+            let (command, owner) = match (local.get(&id), remote.get(&id)) {
+                (Some(local), None) => {
+                    let owner = base.map(|b| b.owner.clone()).unwrap_or_else(|| self.user.clone());
+                    if owner == self.user {
+                        report.pushed.push(id);
+                        to_push.push(RemoteCommand {
+                            owner: owner.clone(),
+                            command: local.clone(),
+                        });
+                    }
+                    (local.clone(), owner)
+                }
+                (None, Some(remote)) => {
+                    report.pulled.push(id);
+                    (remote.command.clone(), remote.owner.clone())
+                }
+                (Some(local), Some(remote)) => {
+                    self.merge_one(local, remote, base, &mut report, &mut to_push)
+                }
+                (None, None) => continue,
+            };
+
+            new_state.entries.insert(
+                id,
+                SyncEntry {
+                    updated_at: command.updated_at,
+                    owner: owner.clone(),
+                },
+            );
+            merged.push(command);
+        }
+
+        if !to_push.is_empty() {
+            self.transport.push(&to_push)?;
+        }
+        storage.replace_all(access, &merged)?;
+        self.save_state(&new_state)?;
+        Ok(report)
+    }
+
+    fn merge_one(
+        &self,
+        local: &Command,
+        remote: &RemoteCommand,
+        base: Option<&SyncEntry>,
+        report: &mut SyncReport,
+        to_push: &mut Vec<RemoteCommand>,
+    ) -> (Command, String) {
+        let owner = remote.owner.clone();
+        if local == &remote.command {
+            return (local.clone(), owner);
+        }
+
+        let local_changed = base.is_none_or(|b| local.updated_at > b.updated_at);
+        let remote_changed = base.is_none_or(|b| remote.command.updated_at > b.updated_at);
+        let is_owner = owner == self.user;
+
+        let keep_local = match (local_changed, remote_changed) {
+            (true, false) => is_owner,
+            (false, _) => false,
+            (true, true) => {
+                let keep_local = is_owner && local.updated_at >= remote.command.updated_at;
+                report.conflicts.push(SyncConflict {
+                    id: local.id,
+                    name: local.name.clone(),
+                    owner: owner.clone(),
+                    local_updated_at: local.updated_at,
+                    remote_updated_at: remote.command.updated_at,
+                    resolution: if keep_local {
+                        ConflictResolution::KeptLocal
+                    } else {
+                        ConflictResolution::TookRemote
+                    },
+                });
+                keep_local
+            }
+        };
+
+        if keep_local {
+            report.pushed.push(local.id);
+            to_push.push(RemoteCommand {
+                owner: owner.clone(),
+                command: local.clone(),
+            });
+            (local.clone(), owner)
+        } else {
+            if local_changed && !is_owner {
+                report.rejected.push(local.id);
+            }
+            report.pulled.push(local.id);
+            (remote.command.clone(), owner)
+        }
+    }
+
+    // Commands owned by someone else are marked as synced from their owner. This is
+    // applied to every fetched copy so it does not count as a local change.
+    fn with_provenance(&self, mut remote: RemoteCommand) -> RemoteCommand {
+        if remote.owner != self.user {
+            if remote.command.source == CommandSource::Manual {
+                remote.command.source = CommandSource::Synced { from: remote.owner.clone() };
+            }
+            if remote.command.author.is_none() {
+                remote.command.author = Some(remote.owner.clone());
+            }
+        }
+        remote
+    }
+
+    fn load_state(&self) -> Result<SyncState> {
+        if !self.state_path.exists() {
+            return Ok(SyncState::default());
+        }
+        let content = fs::read_to_string(&self.state_path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_state(&self, state: &SyncState) -> Result<()> {
+        fs::write(&self.state_path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::cell::RefCell;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct MemoryTransport {
+        commands: RefCell<HashMap<Uuid, RemoteCommand>>,
+    }
+
+    impl SyncTransport for &MemoryTransport {
+        fn fetch(&self) -> Result<Vec<RemoteCommand>> {
+            Ok(self.commands.borrow().values().cloned().collect())
+        }
+
+        fn push(&self, commands: &[RemoteCommand]) -> Result<()> {
+            let mut stored = self.commands.borrow_mut();
+            for command in commands {
+                stored.insert(command.command.id, command.clone());
+            }
+            Ok(())
+        }
+    }
+
+    fn local_storage(user: &str, temp: &TempDir) -> CommandStorage {
+        CommandStorage::with_path(temp.path().join(format!("{}.json", user))).unwrap()
+    }
+
+    fn client<'a>(user: &str, server: &'a MemoryTransport, temp: &TempDir) -> SyncClient<&'a MemoryTransport> {
+        SyncClient::new(server, user.to_string(), temp.path().join(format!("{}-state.json", user)))
+    }
+
+    #[test]
+    fn test_push_then_pull() {
+        let temp = TempDir::new().unwrap();
+        let server = MemoryTransport::default();
+        let alice = local_storage("alice", &temp);
+        let bob = local_storage("bob", &temp);
+
+        let created = alice.create(EditAccess::unrestricted(), Command::new("Deploy".to_string(), "make".to_string())).unwrap();
+        let report = client("alice", &server, &temp).sync(&alice, EditAccess::unrestricted()).unwrap();
+        assert_eq!(report.pushed, vec![created.id]);
+        assert_eq!(server.commands.borrow()[&created.id].owner, "alice");
+
+        let report = client("bob", &server, &temp).sync(&bob, EditAccess::unrestricted()).unwrap();
+        assert_eq!(report.pulled, vec![created.id]);
+        let pulled = bob.read(created.id).unwrap();
+        assert_eq!(pulled.name, "Deploy");
+        assert_eq!(pulled.source, CommandSource::Synced { from: "alice".to_string() });
+        assert_eq!(pulled.author.as_deref(), Some("alice"));
+
+        // Marking the copy as synced is not a local change
+        let report = client("bob", &server, &temp).sync(&bob, EditAccess::unrestricted()).unwrap();
+        assert!(report.pulled.is_empty() && report.pushed.is_empty());
+        assert_eq!(server.commands.borrow()[&created.id].command.source, CommandSource::Manual);
+    }
+
+    #[test]
+    fn test_non_owner_edits_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        let server = MemoryTransport::default();
+        let alice = local_storage("alice", &temp);
+        let bob = local_storage("bob", &temp);
+
+        let created = alice.create(EditAccess::unrestricted(), Command::new("Deploy".to_string(), "make".to_string())).unwrap();
+        client("alice", &server, &temp).sync(&alice, EditAccess::unrestricted()).unwrap();
+        client("bob", &server, &temp).sync(&bob, EditAccess::unrestricted()).unwrap();
+
+        bob.update(EditAccess::unrestricted(), created.id, |c| c.command = "rm".to_string()).unwrap();
+        let report = client("bob", &server, &temp).sync(&bob, EditAccess::unrestricted()).unwrap();
+        assert_eq!(report.rejected, vec![created.id]);
+        assert_eq!(bob.read(created.id).unwrap().command, "make");
+        assert_eq!(server.commands.borrow()[&created.id].command.command, "make");
+    }
+
+    #[test]
+    fn test_conflict_last_write_wins() {
+        let temp = TempDir::new().unwrap();
+        let server = MemoryTransport::default();
+        let alice = local_storage("alice", &temp);
+
+        let created = alice.create(EditAccess::unrestricted(), Command::new("Deploy".to_string(), "make".to_string())).unwrap();
+        client("alice", &server, &temp).sync(&alice, EditAccess::unrestricted()).unwrap();
+
+        // Alice edits the command here and, slightly earlier, on another machine
+        let mut local = created.clone();
+        local.command = "make deploy".to_string();
+        local.updated_at = created.updated_at + Duration::seconds(2);
+        alice.replace_all(EditAccess::unrestricted(), &[local]).unwrap();
+        {
+            let mut stored = server.commands.borrow_mut();
+            let remote = stored.get_mut(&created.id).unwrap();
+            remote.command.command = "make release".to_string();
+            remote.command.updated_at = created.updated_at + Duration::seconds(1);
+        }
+
+        let report = client("alice", &server, &temp).sync(&alice, EditAccess::unrestricted()).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].resolution, ConflictResolution::KeptLocal);
+        assert_eq!(server.commands.borrow()[&created.id].command.command, "make deploy");
+    }
+}
@@ -0,0 +1,239 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::history::{OutputChunk, OutputStream, COMPRESSION_THRESHOLD};
+
+/// Most text [`OutputSpool::read_from`] returns at once. A follower that falls further
+/// behind skips ahead to the end, as it is the end that is being watched.
+pub const FOLLOW_LIMIT: u64 = 64 * 1024;
+
+/// The output of a run while it runs, for watching its end. Each stream is kept in
+/// memory until it grows past `COMPRESSION_THRESHOLD`, then spilled to
+/// `<dir>/<run id>.<stream>.log`. Spilled files are removed when the spool is dropped,
+/// by which time the run is in the history.
+pub struct OutputSpool {
+    dir: PathBuf,
+    run_id: Uuid,
+    streams: Mutex<[SpooledStream; 2]>,
+}
+
+#[derive(Default)]
+struct SpooledStream {
+    buffer: String,
+    file: Option<BufWriter<File>>,
+    bytes: u64,
+    newlines: usize,
+    ends_with_newline: bool,
+}
+
+impl SpooledStream {
+    fn lines(&self) -> usize {
+        self.newlines + usize::from(self.bytes > 0 && !self.ends_with_newline)
+    }
+}
+
+fn index(stream: OutputStream) -> usize {
+    match stream {
+        OutputStream::Stdout => 0,
+        OutputStream::Stderr => 1,
+    }
+}
+
+impl OutputSpool {
+    pub fn new(dir: PathBuf, run_id: Uuid) -> Self {
+        Self { dir, run_id, streams: Mutex::default() }
+    }
+
+    pub fn run_id(&self) -> Uuid {
+        self.run_id
+    }
+
+    /// Where `stream` goes once it is spilled.
+    pub fn path(&self, stream: OutputStream) -> PathBuf {
+        self.dir.join(format!("{}.{}.log", self.run_id, stream.name()))
+    }
+
+    /// Adds output the run wrote, as passed to an `OutputListener`. A stream that can't
+    /// be spilled stays in memory.
+    pub fn write(&self, stream: OutputStream, text: &str) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let spooled = &mut streams[index(stream)];
+        spooled.bytes += text.len() as u64;
+        spooled.newlines += text.matches('\n').count();
+        if !text.is_empty() {
+            spooled.ends_with_newline = text.ends_with('\n');
+        }
+        if let Some(file) = &mut spooled.file {
+            let _ = file.write_all(text.as_bytes());
+            return;
+        }
+        spooled.buffer.push_str(text);
+        if spooled.buffer.len() > COMPRESSION_THRESHOLD {
+            if let Ok(file) = self.spill(stream, &spooled.buffer) {
+                spooled.file = Some(file);
+                spooled.buffer = String::new();
+            }
+        }
+    }
+
+    fn spill(&self, stream: OutputStream, buffered: &str) -> std::io::Result<BufWriter<File>> {
+        fs::create_dir_all(&self.dir)?;
+        let mut file = BufWriter::new(File::create(self.path(stream))?);
+        file.write_all(buffered.as_bytes())?;
+        Ok(file)
+    }
+
+    pub fn is_spilled(&self, stream: OutputStream) -> bool {
+        let streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        streams[index(stream)].file.is_some()
+    }
+
+    /// The last `lines` lines of `stream` so far.
+    pub fn tail(&self, stream: OutputStream, lines: usize) -> Result<OutputChunk> {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let spooled = &mut streams[index(stream)];
+        let total_lines = spooled.lines();
+        let offset = total_lines.saturating_sub(lines);
+        match &mut spooled.file {
+            None => Ok(OutputChunk::from_text(&spooled.buffer, offset, lines)),
+            Some(file) => {
+                file.flush()?;
+                let lines = tail_lines(&self.path(stream), lines)?;
+                Ok(OutputChunk { offset: total_lines - lines.len(), lines, total_lines })
+            }
+        }
+    }
+
+    /// The output of `stream` from byte `offset` on, up to the last complete line, and
+    /// the offset to continue from. Skips ahead when more than `FOLLOW_LIMIT` is waiting.
+    pub fn read_from(&self, stream: OutputStream, offset: u64) -> Result<(String, u64)> {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let spooled = &mut streams[index(stream)];
+        let start = offset.max(spooled.bytes.saturating_sub(FOLLOW_LIMIT));
+        let mut bytes = Vec::new();
+        match &mut spooled.file {
+            None => bytes.extend_from_slice(&spooled.buffer.as_bytes()[start as usize..]),
+            Some(file) => {
+                file.flush()?;
+                let mut file = File::open(self.path(stream))?;
+                file.seek(SeekFrom::Start(start))?;
+                file.take(spooled.bytes - start).read_to_end(&mut bytes)?;
+            }
+        }
+        // A skip lands mid-line; start at the next one
+        let skip = if start > offset { bytes.iter().position(|b| *b == b'\n').map_or(bytes.len(), |i| i + 1) } else { 0 };
+        let end = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1).max(skip);
+        let text = String::from_utf8_lossy(&bytes[skip..end]).to_string();
+        Ok((text, start + end as u64))
+    }
+}
+
+impl Drop for OutputSpool {
+    fn drop(&mut self) {
+        for stream in OutputStream::ALL {
+            let _ = fs::remove_file(self.path(stream));
+        }
+    }
+}
+
+/// The last `count` lines of the file at `path`, read from its end so the size of the
+/// file doesn't matter.
+pub(crate) fn tail_lines(path: &Path, count: usize) -> Result<Vec<String>> {
+    const BLOCK: u64 = 8 * 1024;
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut bytes = Vec::new();
+    // One newline more than lines wanted marks where they start; a trailing one doesn't count
+    while start > 0 && bytes.iter().filter(|b| **b == b'\n').count() <= count + usize::from(bytes.ends_with(b"\n")) {
+        let read = BLOCK.min(start);
+        start -= read;
+        let mut block = vec![0; read as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&bytes);
+        bytes = block;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    // Unless the file was read from its start, the first line may be cut off
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CommandArgusError;
+    use crate::history::HistoryStorage;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spool_spills_and_tails() {
+        let temp = TempDir::new().unwrap();
+        let spool = OutputSpool::new(temp.path().join("live"), Uuid::new_v4());
+        spool.write(OutputStream::Stdout, "first\n");
+        spool.write(OutputStream::Stderr, "oops\n");
+        assert!(!spool.is_spilled(OutputStream::Stdout));
+        assert_eq!(spool.tail(OutputStream::Stdout, 5).unwrap().lines, vec!["first"]);
+        let (text, offset) = spool.read_from(OutputStream::Stdout, 0).unwrap();
+        assert_eq!((text.as_str(), offset), ("first\n", 6));
+
+        for i in 0..20_000 {
+            spool.write(OutputStream::Stdout, &format!("line {}\n", i));
+        }
+        spool.write(OutputStream::Stdout, "partial");
+        assert!(spool.is_spilled(OutputStream::Stdout));
+        assert!(spool.path(OutputStream::Stdout).exists());
+        let tail = spool.tail(OutputStream::Stdout, 3).unwrap();
+        assert_eq!(tail.lines, vec!["line 19998", "line 19999", "partial"]);
+        assert_eq!((tail.offset, tail.total_lines), (19_999, 20_002));
+
+        // A follower far behind skips to the end, and only gets complete lines
+        let (text, offset) = spool.read_from(OutputStream::Stdout, 6).unwrap();
+        assert!((text.len() as u64) < FOLLOW_LIMIT);
+        assert!(text.starts_with("line ") && text.ends_with("line 19999\n"));
+        spool.write(OutputStream::Stdout, " line\nnext\n");
+        let (text, _) = spool.read_from(OutputStream::Stdout, offset).unwrap();
+        assert_eq!(text, "partial line\nnext\n");
+
+        let path = spool.path(OutputStream::Stdout);
+        drop(spool);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_tail_errors() {
+        let temp = TempDir::new().unwrap();
+        let line = format!("{}\n", "x".repeat(1000));
+        let fill = |spool: &OutputSpool| {
+            for _ in 0..COMPRESSION_THRESHOLD / line.len() + 2 {
+                spool.write(OutputStream::Stdout, &line);
+            }
+        };
+
+        // A stream that cannot be spilled stays in memory
+        fs::write(temp.path().join("blocked"), "").unwrap();
+        let spool = OutputSpool::new(temp.path().join("blocked"), Uuid::new_v4());
+        fill(&spool);
+        assert!(!spool.is_spilled(OutputStream::Stdout));
+        assert_eq!(spool.tail(OutputStream::Stdout, 1).unwrap().lines, vec!["x".repeat(1000)]);
+
+        // A spilled stream whose file is gone can't be read any more
+        let spool = OutputSpool::new(temp.path().join("live"), Uuid::new_v4());
+        fill(&spool);
+        assert!(spool.is_spilled(OutputStream::Stdout));
+        fs::remove_file(spool.path(OutputStream::Stdout)).unwrap();
+        assert!(spool.tail(OutputStream::Stdout, 1).is_err());
+        assert!(spool.read_from(OutputStream::Stdout, 0).is_err());
+
+        let history = HistoryStorage::with_path(temp.path().join("history.json")).unwrap();
+        let unknown = history.tail_output(Uuid::new_v4(), OutputStream::Stdout, 10);
+        assert!(matches!(unknown, Err(CommandArgusError::NotFound(_))));
+    }
+}
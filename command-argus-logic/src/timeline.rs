@@ -0,0 +1,173 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandArgusError, Result};
+use crate::history::OutputStream;
+
+/// One line a process wrote, with when and to which stream.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutputEvent {
+    /// Milliseconds since the process was started.
+    pub at_ms: u64,
+    pub stream: OutputStream,
+    /// The line without its line ending.
+    pub line: String,
+}
+
+/// A stretch of a run between two phase markers, or the last marker and the exit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    /// Milliseconds since the process was started.
+    pub started_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Collects the lines of both streams in the order they were read, for commands that
+/// capture a timeline, and the phases lines matching a phase marker start. Lines written
+/// within the same instant to different streams may still swap places, as the pipes are
+/// read separately.
+pub struct Timeline {
+    started: Instant,
+    capture_lines: bool,
+    phase_marker: Option<Regex>,
+    events: Mutex<Vec<OutputEvent>>,
+    // When each phase started, and its name
+    markers: Mutex<Vec<(u64, String)>>,
+}
+
+impl Timeline {
+    pub fn new(capture_lines: bool, phase_marker: Option<Regex>) -> Self {
+        Self {
+            started: Instant::now(),
+            capture_lines,
+            phase_marker,
+            events: Mutex::default(),
+            markers: Mutex::default(),
+        }
+    }
+
+    /// Records a line as passed to an `OutputListener`.
+    pub fn push(&self, stream: OutputStream, text: &str) {
+        let line = text.strip_suffix('\n').unwrap_or(text);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let at_ms = self.started.elapsed().as_millis() as u64;
+        if let Some(name) = self.phase_marker.as_ref().and_then(|marker| phase_name(marker, line)) {
+            self.markers.lock().unwrap_or_else(|e| e.into_inner()).push((at_ms, name));
+        }
+        if self.capture_lines {
+            let line = line.to_string();
+            self.events.lock().unwrap_or_else(|e| e.into_inner()).push(OutputEvent { at_ms, stream, line });
+        }
+    }
+
+    /// The captured lines and the phases, the last one ending now.
+    pub fn finish(self) -> (Vec<OutputEvent>, Vec<Phase>) {
+        let ended_ms = self.started.elapsed().as_millis() as u64;
+        let markers = self.markers.into_inner().unwrap_or_else(|e| e.into_inner());
+        let phases = markers
+            .iter()
+            .enumerate()
+            .map(|(i, (started_ms, name))| {
+                let next_ms = markers.get(i + 1).map_or(ended_ms, |(at_ms, _)| *at_ms);
+                Phase { name: name.clone(), started_ms: *started_ms, duration_ms: next_ms - started_ms }
+            })
+            .collect();
+        (self.events.into_inner().unwrap_or_else(|e| e.into_inner()), phases)
+    }
+}
+
+/// Compiles a command's phase marker.
+pub fn phase_marker_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| CommandArgusError::InvalidQuery(format!("Invalid phase marker '{}': {}", pattern, e)))
+}
+
+// The phase a line starts: the marker's first capture group, or the whole match
+fn phase_name(marker: &Regex, line: &str) -> Option<String> {
+    let captures = marker.captures(line)?;
+    let name = captures.get(1).or_else(|| captures.get(0))?.as_str().trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The phases in a line, like `compile 2m 5s, test 5m, package 30s`.
+pub fn describe_phases(phases: &[Phase]) -> String {
+    phases
+        .iter()
+        .map(|phase| format!("{} {}", phase.name, describe_duration(phase.duration_ms)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_duration(ms: u64) -> String {
+    let seconds = ms / 1000;
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, 0) => format!("{}ms", ms),
+        (0, 0, s) => format!("{}s", s),
+        (0, m, 0) => format!("{}m", m),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+/// The events as text, one line each, prefixed with the time and stream, like
+/// `[   1.250s] err | message`.
+pub fn render_timeline(events: &[OutputEvent]) -> String {
+    events
+        .iter()
+        .map(|event| {
+            let stream = match event.stream {
+                OutputStream::Stdout => "out",
+                OutputStream::Stderr => "err",
+            };
+            format!("[{:>8.3}s] {} | {}\n", event.at_ms as f64 / 1000.0, stream, event.line)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_keeps_order_and_streams() {
+        let timeline = Timeline::new(true, None);
+        timeline.push(OutputStream::Stdout, "building\n");
+        timeline.push(OutputStream::Stderr, "warning: unused\r\n");
+        timeline.push(OutputStream::Stdout, "done");
+        let (events, phases) = timeline.finish();
+        assert!(phases.is_empty());
+        let lines: Vec<(OutputStream, &str)> = events.iter().map(|e| (e.stream, e.line.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![(OutputStream::Stdout, "building"), (OutputStream::Stderr, "warning: unused"), (OutputStream::Stdout, "done")]
+        );
+        assert!(events.windows(2).all(|pair| pair[0].at_ms <= pair[1].at_ms));
+        assert!(render_timeline(&events).lines().nth(1).unwrap().ends_with("s] err | warning: unused"));
+    }
+
+    #[test]
+    fn test_phases() {
+        let timeline = Timeline::new(false, Some(phase_marker_regex(r"^==> (.*)").unwrap()));
+        timeline.push(OutputStream::Stdout, "preparing\n");
+        timeline.push(OutputStream::Stdout, "==> compile\n");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        timeline.push(OutputStream::Stderr, "==>   \n");
+        timeline.push(OutputStream::Stderr, "==> test\n");
+        let (events, phases) = timeline.finish();
+        assert!(events.is_empty());
+        let names: Vec<&str> = phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["compile", "test"]);
+        assert!(phases[0].duration_ms >= 50);
+        assert_eq!(phases[1].started_ms, phases[0].started_ms + phases[0].duration_ms);
+
+        let phase = |name: &str, duration_ms| Phase { name: name.to_string(), started_ms: 0, duration_ms };
+        assert_eq!(
+            describe_phases(&[phase("compile", 125_000), phase("test", 300_000), phase("package", 30_500), phase("lint", 40)]),
+            "compile 2m 5s, test 5m, package 30s, lint 40ms"
+        );
+        assert!(phase_marker_regex("(").is_err());
+    }
+}
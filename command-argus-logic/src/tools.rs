@@ -0,0 +1,233 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::process::Command as ProcessCommand;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandArgusError, Result};
+
+/// A required version of a tool the command depends on, e.g. `terraform >= 1.5`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCheck {
+    pub tool: String,
+    /// Comma-separated constraints such as `>= 1.5, < 2`.
+    pub requirement: String,
+    /// Arguments that make the tool print its version.
+    #[serde(default = "default_version_args")]
+    pub version_args: Vec<String>,
+    /// Refuse to run on a mismatch instead of only warning.
+    #[serde(default)]
+    pub block: bool,
+}
+
+fn default_version_args() -> Vec<String> {
+    vec!["--version".to_string()]
+}
+
+/// The result of running one `ToolCheck`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCheckOutcome {
+    pub tool: String,
+    pub requirement: String,
+    /// The version reported by the tool, if it could be run and parsed.
+    pub found: Option<String>,
+    pub satisfied: bool,
+    pub block: bool,
+    pub message: String,
+}
+
+impl ToolCheck {
+    /// Parses an expression like `terraform >= 1.5` or `node >=18, <21`.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        let (tool, requirement) = expression
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid_requirement(expression))?;
+        VersionReq::parse(requirement)?;
+        Ok(Self {
+            tool: tool.to_string(),
+            requirement: requirement.trim().to_string(),
+            version_args: default_version_args(),
+            block: false,
+        })
+    }
+
+    /// Runs the tool to find its version and compares it with the requirement.
+    pub fn run(&self) -> Result<ToolCheckOutcome> {
+        let requirement = VersionReq::parse(&self.requirement)?;
+        let output = ProcessCommand::new(&self.tool).args(&self.version_args).output();
+        let found = output.ok().and_then(|output| {
+            // Some tools (java, older gcc) print their version on stderr
+            let text = format!(
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            Version::find_in(&text)
+        });
+
+        let (satisfied, message) = match &found {
+            None => (false, format!("{} is not installed or did not report a version", self.tool)),
+            Some(version) if requirement.matches(version) => {
+                (true, format!("{} {} satisfies {}", self.tool, version, self.requirement))
+            }
+            Some(version) => (
+                false,
+                format!("{} {} does not satisfy {}", self.tool, version, self.requirement),
+            ),
+        };
+        Ok(ToolCheckOutcome {
+            tool: self.tool.clone(),
+            requirement: self.requirement.clone(),
+            found: found.map(|v| v.to_string()),
+            satisfied,
+            block: self.block,
+            message,
+        })
+    }
+}
+
+/// Runs all checks. Fails with the messages of every blocking mismatch; otherwise
+/// returns warnings for the non-blocking ones.
+pub fn run_tool_checks(checks: &[ToolCheck]) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+    let mut blocking = Vec::new();
+    for check in checks {
+        let outcome = check.run()?;
+        if outcome.satisfied {
+            continue;
+        }
+        if outcome.block {
+            blocking.push(outcome.message);
+        } else {
+            warnings.push(outcome.message);
+        }
+    }
+    if !blocking.is_empty() {
+        return Err(CommandArgusError::CheckFailed(blocking.join("; ")));
+    }
+    Ok(warnings)
+}
+
+fn invalid_requirement(requirement: &str) -> CommandArgusError {
+    CommandArgusError::InvalidCommand(format!("Invalid version requirement '{}'", requirement))
+}
+
+/// A dotted numeric version. Missing components compare as zero, so `1.5` == `1.5.0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches('v');
+        let parts: Option<Vec<u64>> = text.split('.').map(|p| p.parse().ok()).collect();
+        parts.filter(|p| !p.is_empty()).map(Version)
+    }
+
+    /// The first version-looking token in a tool's `--version` output.
+    pub fn find_in(text: &str) -> Option<Self> {
+        let re = Regex::new(r"\d+(?:\.\d+)+|\d+").unwrap();
+        re.find(text).and_then(|m| Version::parse(m.as_str()))
+    }
+
+    fn compare(&self, other: &Version) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| self.0.get(i).unwrap_or(&0).cmp(other.0.get(i).unwrap_or(&0)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// A set of constraints that must all hold, e.g. `>= 1.5, < 2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionReq(Vec<(String, Version)>);
+
+impl VersionReq {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut constraints = Vec::new();
+        for part in text.split(',') {
+            let part = part.trim();
+            let op_len = part.find(|c: char| c.is_ascii_digit() || c == 'v').unwrap_or(part.len());
+            let (op, version) = part.split_at(op_len);
+            let op = match op.trim() {
+                "" | "=" | "==" => "=",
+                op @ (">=" | ">" | "<=" | "<" | "!=") => op,
+                _ => return Err(invalid_requirement(text)),
+            };
+            let version = Version::parse(version).ok_or_else(|| invalid_requirement(text))?;
+            constraints.push((op.to_string(), version));
+        }
+        Ok(VersionReq(constraints))
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.0.iter().all(|(op, required)| {
+            let ordering = version.compare(required);
+            match op.as_str() {
+                ">=" => ordering != Ordering::Less,
+                ">" => ordering == Ordering::Greater,
+                "<=" => ordering != Ordering::Greater,
+                "<" => ordering == Ordering::Less,
+                "!=" => ordering != Ordering::Equal,
+                _ => ordering == Ordering::Equal,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_requirements() {
+        let req = VersionReq::parse(">= 1.5, < 2").unwrap();
+        assert!(req.matches(&Version::parse("1.5").unwrap()));
+        assert!(req.matches(&Version::parse("1.9.3").unwrap()));
+        assert!(!req.matches(&Version::parse("1.4.9").unwrap()));
+        assert!(!req.matches(&Version::parse("2.0.0").unwrap()));
+        assert!(VersionReq::parse("1.2").unwrap().matches(&Version::parse("1.2.0").unwrap()));
+        assert!(VersionReq::parse("~> 1").is_err());
+    }
+
+    #[test]
+    fn test_find_version_in_output() {
+        let version = Version::find_in("Terraform v1.6.2\non linux_amd64").unwrap();
+        assert_eq!(version.to_string(), "1.6.2");
+        assert!(Version::find_in("no digits here").is_none());
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        let check = ToolCheck::parse("terraform >= 1.5").unwrap();
+        assert_eq!(check.tool, "terraform");
+        assert_eq!(check.requirement, ">= 1.5");
+        assert!(ToolCheck::parse("terraform").is_err());
+    }
+
+    #[test]
+    fn test_run_checks() {
+        // `echo 3.2.1` stands in for a tool printing its version
+        let mut check = ToolCheck::parse("echo >= 3").unwrap();
+        check.version_args = vec!["3.2.1".to_string()];
+        assert!(check.run().unwrap().satisfied);
+        assert!(run_tool_checks(std::slice::from_ref(&check)).unwrap().is_empty());
+
+        check.requirement = "< 3".to_string();
+        assert_eq!(run_tool_checks(std::slice::from_ref(&check)).unwrap().len(), 1);
+        check.block = true;
+        assert!(matches!(run_tool_checks(&[check]), Err(CommandArgusError::CheckFailed(_))));
+
+        let missing = ToolCheck::parse("definitely-not-a-real-tool >= 1").unwrap();
+        assert!(missing.run().unwrap().found.is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::history::{HistoryStats, HistoryStorage};
+use crate::storage::CommandStorage;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageCategory {
+    Commands,
+    History,
+    /// Compressed execution outputs.
+    Outputs,
+    /// The audit log and any other `*.log` / `*.jsonl` files.
+    Logs,
+    Backups,
+    Artifacts,
+    /// Settings, approvals, saved searches and anything else.
+    Other,
+}
+
+/// Disk usage of one category. `items` counts commands, history records, lines of a
+/// log or files, depending on the category.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageEntry {
+    pub category: UsageCategory,
+    pub bytes: u64,
+    pub items: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageStats {
+    pub data_dir: String,
+    pub total_bytes: u64,
+    /// One entry per category, in the order of `UsageCategory`.
+    pub entries: Vec<UsageEntry>,
+    pub history: HistoryStats,
+}
+
+impl StorageStats {
+    pub fn entry(&self, category: UsageCategory) -> &UsageEntry {
+        self.entries.iter().find(|e| e.category == category).unwrap()
+    }
+}
+
+/// Measures the data directory `dir`, as laid out by the stores of this crate.
+pub fn storage_stats(dir: &Path) -> Result<StorageStats> {
+    let categories = [
+        UsageCategory::Commands,
+        UsageCategory::History,
+        UsageCategory::Outputs,
+        UsageCategory::Logs,
+        UsageCategory::Backups,
+        UsageCategory::Artifacts,
+        UsageCategory::Other,
+    ];
+    let mut entries: Vec<UsageEntry> = categories
+        .iter()
+        .map(|&category| UsageEntry { category, bytes: 0, items: 0 })
+        .collect();
+    let mut add = |category: UsageCategory, bytes: u64, items: usize| {
+        let entry = entries.iter_mut().find(|e| e.category == category).unwrap();
+        entry.bytes += bytes;
+        entry.items += items;
+    };
+
+    let history = HistoryStorage::with_path(dir.join("history.json"))?.stats()?;
+    if dir.exists() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            if entry.metadata()?.is_dir() {
+                let (bytes, files) = dir_usage(&path)?;
+                let category = match name.as_str() {
+                    "history-outputs" => UsageCategory::Outputs,
+                    "backups" => UsageCategory::Backups,
+                    "artifacts" => UsageCategory::Artifacts,
+                    "logs" => UsageCategory::Logs,
+                    _ => UsageCategory::Other,
+                };
+                add(category, bytes, files);
+                continue;
+            }
+
+            let bytes = entry.metadata()?.len();
+            match name.as_str() {
+                "commands.json" => add(UsageCategory::Commands, bytes, CommandStorage::with_path(path)?.list()?.len()),
+                "history.json" => add(UsageCategory::History, bytes, history.records),
+                _ if name.ends_with(".log") || name.ends_with(".jsonl") => {
+                    let lines = fs::read_to_string(&path).map(|c| c.lines().count()).unwrap_or(0);
+                    add(UsageCategory::Logs, bytes, lines);
+                }
+                _ => add(UsageCategory::Other, bytes, 1),
+            }
+        }
+    }
+
+    Ok(StorageStats {
+        data_dir: dir.to_string_lossy().to_string(),
+        total_bytes: entries.iter().map(|e| e.bytes).sum(),
+        entries,
+        history,
+    })
+}
+
+// Total size and number of files below `dir`
+fn dir_usage(dir: &Path) -> Result<(u64, usize)> {
+    let mut usage = (0, 0);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            let (bytes, files) = dir_usage(&entry.path())?;
+            usage.0 += bytes;
+            usage.1 += files;
+        } else {
+            usage.0 += metadata.len();
+            usage.1 += 1;
+        }
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restricted::EditAccess;
+    use crate::audit::{AuditEvent, AuditLog};
+    use crate::command::Command;
+    use crate::history::ExecutionRecord;
+    use chrono::Utc;
+
+    #[test]
+    fn test_storage_stats() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        let storage = CommandStorage::with_path(dir.join("commands.json")).unwrap();
+        let cmd = storage.create(EditAccess::unrestricted(), Command::new("Echo".to_string(), "echo".to_string())).unwrap();
+        let history = HistoryStorage::with_path(dir.join("history.json")).unwrap();
+        let mut record = ExecutionRecord::from_error(&cmd, Utc::now(), String::new());
+        record.stdout = "x".repeat(crate::history::COMPRESSION_THRESHOLD + 1);
+        history.record(record).unwrap();
+        let audit = AuditLog::with_path(dir.join("audit.jsonl")).unwrap();
+        audit.record(&AuditEvent::new("alice", "test", None, String::new())).unwrap();
+        fs::create_dir_all(dir.join("backups")).unwrap();
+        fs::write(dir.join("backups").join("commands-1.json"), "[]").unwrap();
+        fs::write(dir.join("settings.json"), "{}").unwrap();
+
+        let stats = storage_stats(dir).unwrap();
+        assert_eq!(stats.entry(UsageCategory::Commands).items, 1);
+        assert_eq!(stats.entry(UsageCategory::History).items, 1);
+        assert_eq!(stats.entry(UsageCategory::Outputs).items, 1);
+        assert_eq!(stats.entry(UsageCategory::Logs).items, 1);
+        assert_eq!(stats.entry(UsageCategory::Backups).bytes, 2);
+        assert_eq!(stats.entry(UsageCategory::Other).items, 1);
+        assert_eq!(stats.entry(UsageCategory::Artifacts).bytes, 0);
+        assert_eq!(stats.total_bytes, stats.entries.iter().map(|e| e.bytes).sum::<u64>());
+        assert_eq!(stats.history.compressed_outputs, 1);
+    }
+}
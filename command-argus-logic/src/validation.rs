@@ -0,0 +1,165 @@
+use std::process::{Command as ProcessCommand, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::executable::find_executable;
+use crate::shell::ShellKind;
+
+/// A problem with the line a command runs as in shell mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyntaxWarning {
+    pub message: String,
+    /// Byte offset into the line, when the problem is at a known place.
+    pub offset: Option<usize>,
+}
+
+impl SyntaxWarning {
+    fn at(offset: usize, message: impl Into<String>) -> Self {
+        Self { message: message.into(), offset: Some(offset) }
+    }
+}
+
+/// Warnings for the line `command` runs as in shell mode: first the mistakes that are
+/// easy to point at (an unclosed quote or parenthesis, a pipe or `&&` with nothing on
+/// one side), then, on unix when there are none, whatever `zsh -n` (or `sh -n`) finds.
+/// PowerShell commands are quoted argument by argument, so they are not checked.
+pub fn validate_command(command: &Command) -> Vec<SyntaxWarning> {
+    if command.shell == ShellKind::PowerShell {
+        return Vec::new();
+    }
+    let line = command.full_command();
+    let warnings = validate_shell_line(&line);
+    if !warnings.is_empty() || cfg!(windows) {
+        return warnings;
+    }
+    check_with_shell(&line).into_iter().collect()
+}
+
+/// The syntax mistakes in `line` that don't need a shell to find.
+pub fn validate_shell_line(line: &str) -> Vec<SyntaxWarning> {
+    let mut warnings = Vec::new();
+    let bytes = line.as_bytes();
+    let mut quote: Option<(u8, usize)> = None;
+    let mut parens: Vec<usize> = Vec::new();
+    // Where the current command started and whether anything was written since
+    let mut segment_empty = true;
+    let mut last_operator: Option<(&str, usize)> = None;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match quote {
+            Some((q, _)) if c == q => quote = None,
+            Some((q, _)) if c == b'\\' && q != b'\'' => i += 1,
+            Some(_) => {}
+            None => match c {
+                b'\\' if i + 1 == bytes.len() => {
+                    warnings.push(SyntaxWarning::at(i, "the line ends with a backslash that continues it onto nothing"));
+                }
+                b'\\' => {
+                    i += 1;
+                    segment_empty = false;
+                }
+                b'\'' | b'"' | b'`' => {
+                    quote = Some((c, i));
+                    segment_empty = false;
+                }
+                b'#' if i == 0 || bytes[i - 1].is_ascii_whitespace() => break,
+                b'(' => {
+                    parens.push(i);
+                    segment_empty = true;
+                    last_operator = None;
+                }
+                b')' => {
+                    if parens.pop().is_none() {
+                        warnings.push(SyntaxWarning::at(i, "')' has no matching '('"));
+                    }
+                    segment_empty = false;
+                }
+                b'|' | b'&' | b';' => {
+                    let operator = match (c, bytes.get(i + 1)) {
+                        (b'|', Some(b'|')) => "||",
+                        (b'&', Some(b'&')) => "&&",
+                        (b'|', _) => "|",
+                        (b'&', _) => "&",
+                        _ => ";",
+                    };
+                    // `&` and `;` may end a line, and `2>&1` is a redirection
+                    let redirection = c == b'&' && i > 0 && bytes[i - 1] == b'>';
+                    if !redirection {
+                        if segment_empty && operator != "&" && operator != ";" {
+                            let message = match last_operator {
+                                Some((previous, _)) => format!("nothing between '{}' and '{}'", previous, operator),
+                                None => format!("the line starts with '{}'", operator),
+                            };
+                            warnings.push(SyntaxWarning::at(i, message));
+                        }
+                        segment_empty = true;
+                        last_operator = Some((operator, i));
+                    }
+                    i += operator.len() - 1;
+                }
+                c if c.is_ascii_whitespace() => {}
+                _ => segment_empty = false,
+            },
+        }
+        i += 1;
+    }
+
+    if let Some((q, start)) = quote {
+        let name = match q {
+            b'\'' => "single quote",
+            b'"' => "double quote",
+            _ => "backtick",
+        };
+        warnings.push(SyntaxWarning::at(start, format!("unclosed {}", name)));
+    } else {
+        for start in parens {
+            warnings.push(SyntaxWarning::at(start, "'(' is never closed"));
+        }
+        if let Some((operator, offset)) = last_operator.filter(|(op, _)| segment_empty && *op != "&" && *op != ";") {
+            warnings.push(SyntaxWarning::at(offset, format!("the line ends with '{}', which needs a command after it", operator)));
+        }
+    }
+    warnings
+}
+
+/// What the shell says about `line` when asked only to parse it.
+fn check_with_shell(line: &str) -> Option<SyntaxWarning> {
+    let shell = if find_executable("zsh").is_some() { "zsh" } else { "sh" };
+    let output = ProcessCommand::new(shell)
+        .args(["-n", "-c", line])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if output.status.success() {
+        return None;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("the shell can't parse this line");
+    Some(SyntaxWarning { message: format!("{}: {}", shell, message.trim()), offset: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_shell_line() {
+        let messages = |line: &str| -> Vec<String> { validate_shell_line(line).into_iter().map(|w| w.message).collect() };
+
+        assert!(messages("cat {file} | grep -v '#' > out.txt 2>&1 && echo done &").is_empty());
+        assert!(messages("echo \"it's $(date)\" # trailing comment |").is_empty());
+        assert_eq!(messages("echo 'unterminated"), vec!["unclosed single quote"]);
+        assert_eq!(messages("ls -l |"), vec!["the line ends with '|', which needs a command after it"]);
+        assert_eq!(messages("make && && make install"), vec!["nothing between '&&' and '&&'"]);
+        assert_eq!(messages("| sort"), vec!["the line starts with '|'"]);
+        assert_eq!(messages("echo $(date"), vec!["'(' is never closed"]);
+
+        let mut command = Command::new("Bad".to_string(), "echo".to_string()).with_args(vec!["\"oops".to_string()]);
+        assert_eq!(validate_command(&command).len(), 1);
+        command.shell = ShellKind::PowerShell;
+        assert!(validate_command(&command).is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::error::{CommandArgusError, Result};
+use crate::restricted::EditAccess;
+use crate::profile::Profile;
+use crate::storage::data_dir;
+
+/// Where a variable is defined. Workspace variables belong to a project root and
+/// override global variables of the same name for commands run in that workspace;
+/// the variables of the profile a command runs with override both.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VariableScope {
+    Global,
+    Workspace { root: String },
+    /// Kept with the profile rather than in this store.
+    Profile { name: String },
+}
+
+impl VariableScope {
+    fn applies_to(&self, workspace: Option<&Path>) -> bool {
+        match self {
+            VariableScope::Global => true,
+            VariableScope::Workspace { root } => workspace == Some(Path::new(root)),
+            // Only present while running with the profile
+            VariableScope::Profile { .. } => true,
+        }
+    }
+}
+
+/// A value referenced as `{{var:NAME}}` in a command's program, arguments, working
+/// directory or environment variables.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    pub scope: VariableScope,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How a `{{var:NAME}}` reference resolves in a workspace.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct VariableTrace {
+    pub name: String,
+    /// `None` when the variable is not defined in any scope that applies.
+    pub value: Option<String>,
+    /// The scope the value came from.
+    pub scope: Option<VariableScope>,
+    /// Definitions hidden by the one used, i.e. the global value when a workspace
+    /// overrides it.
+    pub shadowed: Vec<Variable>,
+}
+
+/// The `{{var:NAME}}` references in `text`, in order of first appearance.
+pub fn variable_references(text: &str) -> Vec<String> {
+    let re = Regex::new(r"\{\{var:([A-Za-z_][A-Za-z0-9_.-]*)\}\}").unwrap();
+    let mut names: Vec<String> = Vec::new();
+    for cap in re.captures_iter(text) {
+        let name = cap[1].to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn is_variable_name(name: &str) -> bool {
+    variable_references(&format!("{{{{var:{}}}}}", name)).len() == 1
+}
+
+/// The texts of a command that may reference variables.
+pub(crate) fn texts_mut(command: &mut Command) -> Vec<&mut String> {
+    let mut texts = vec![&mut command.command];
+    texts.extend(command.args.iter_mut());
+    texts.extend(command.working_directory.iter_mut());
+    texts.extend(command.stdin_template.iter_mut());
+    texts.extend(command.environment_variables.iter_mut().map(|var| &mut var.value));
+    for platform_override in &mut command.platform_overrides {
+        texts.push(&mut platform_override.command);
+        texts.extend(platform_override.args.iter_mut().flatten());
+        texts.extend(platform_override.working_directory.iter_mut());
+        texts.extend(platform_override.environment_variables.iter_mut().map(|var| &mut var.value));
+    }
+    texts
+}
+
+/// Template variables, persisted as `variables.json` in the data directory.
+pub struct VariableStorage {
+    path: PathBuf,
+}
+
+impl VariableStorage {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            path: data_dir()?.join("variables.json"),
+        })
+    }
+
+    pub fn with_path(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn list(&self) -> Result<Vec<Variable>> {
+        self.load_all()
+    }
+
+    /// Creates the variable `name` in `scope`, or replaces its value.
+    pub fn set(&self, _access: EditAccess, name: &str, value: &str, scope: VariableScope) -> Result<Variable> {
+        if !is_variable_name(name) {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "'{}' is not a valid variable name; use letters, digits, '_', '.' and '-'",
+                name
+            )));
+        }
+        if let VariableScope::Profile { name: profile } = &scope {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "variables of the profile '{}' are set in the profile",
+                profile
+            )));
+        }
+        let mut variables = self.load_all()?;
+        variables.retain(|v| !(v.name == name && v.scope == scope));
+        let variable = Variable {
+            name: name.to_string(),
+            value: value.to_string(),
+            scope,
+            updated_at: Utc::now(),
+        };
+        variables.push(variable.clone());
+        self.save_all(&variables)?;
+        Ok(variable)
+    }
+
+    /// Removes the variable `name` from `scope`. Returns whether it was defined there.
+    pub fn remove(&self, _access: EditAccess, name: &str, scope: &VariableScope) -> Result<bool> {
+        let mut variables = self.load_all()?;
+        let initial_len = variables.len();
+        variables.retain(|v| !(v.name == name && v.scope == *scope));
+        if variables.len() == initial_len {
+            return Ok(false);
+        }
+        self.save_all(&variables)?;
+        Ok(true)
+    }
+
+    /// Where the value of each variable referenced in `text` comes from when used in
+    /// `workspace` (or outside any workspace when `None`) with `profile`.
+    pub fn trace(&self, text: &str, workspace: Option<&Path>, profile: Option<&Profile>) -> Result<Vec<VariableTrace>> {
+        let mut variables = self.load_all()?;
+        if let Some(profile) = profile {
+            variables.extend(profile.variables.iter().map(|(name, value)| Variable {
+                name: name.clone(),
+                value: value.clone(),
+                scope: VariableScope::Profile { name: profile.name.clone() },
+                updated_at: profile.updated_at,
+            }));
+        }
+        Ok(variable_references(text)
+            .into_iter()
+            .map(|name| trace_in(&variables, name, workspace))
+            .collect())
+    }
+
+    /// Like [`trace`](Self::trace), for every reference in `command`.
+    pub fn trace_command(
+        &self,
+        command: &Command,
+        workspace: Option<&Path>,
+        profile: Option<&Profile>,
+    ) -> Result<Vec<VariableTrace>> {
+        let mut command = command.clone();
+        let text = texts_mut(&mut command)
+            .into_iter()
+            .map(|text| text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.trace(&text, workspace, profile)
+    }
+
+    /// Replaces the `{{var:NAME}}` references in `command`. Fails if any of them is not
+    /// defined, rather than running the command with a hole in it.
+    pub fn resolve_command(&self, command: &Command, workspace: Option<&Path>, profile: Option<&Profile>) -> Result<Command> {
+        let traces = self.trace_command(command, workspace, profile)?;
+        let undefined: Vec<&str> = traces.iter().filter(|t| t.value.is_none()).map(|t| t.name.as_str()).collect();
+        if !undefined.is_empty() {
+            return Err(CommandArgusError::InvalidCommand(format!(
+                "undefined variables: {}",
+                undefined.join(", ")
+            )));
+        }
+
+        let mut resolved = command.clone();
+        for text in texts_mut(&mut resolved) {
+            for trace in &traces {
+                let value = trace.value.as_deref().unwrap_or_default();
+                *text = text.replace(&format!("{{{{var:{}}}}}", trace.name), value);
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn load_all(&self) -> Result<Vec<Variable>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_all(&self, variables: &[Variable]) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(variables)?)?;
+        Ok(())
+    }
+}
+
+/// Resolves `name`, preferring a definition in the profile, then the workspace, then
+/// a global one. `variables` includes those of the profile.
+fn trace_in(variables: &[Variable], name: String, workspace: Option<&Path>) -> VariableTrace {
+    let mut candidates: Vec<&Variable> = variables
+        .iter()
+        .filter(|v| v.name == name && v.scope.applies_to(workspace))
+        .collect();
+    candidates.sort_by_key(|v| match v.scope {
+        VariableScope::Profile { .. } => 0,
+        VariableScope::Workspace { .. } => 1,
+        VariableScope::Global => 2,
+    });
+
+    let mut candidates = candidates.into_iter();
+    let used = candidates.next();
+    VariableTrace {
+        name,
+        value: used.map(|v| v.value.clone()),
+        scope: used.map(|v| v.scope.clone()),
+        shadowed: candidates.cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_workspace_overrides_global() {
+        let temp = TempDir::new().unwrap();
+        let storage = VariableStorage::with_path(temp.path().join("variables.json")).unwrap();
+        let workspace = VariableScope::Workspace { root: "/work/shop".to_string() };
+        storage.set(EditAccess::unrestricted(), "cluster", "staging", VariableScope::Global).unwrap();
+        storage.set(EditAccess::unrestricted(), "cluster", "prod-eu", workspace.clone()).unwrap();
+        storage.set(EditAccess::unrestricted(), "namespace", "default", VariableScope::Global).unwrap();
+        assert!(storage.set(EditAccess::unrestricted(), "bad name", "x", VariableScope::Global).is_err());
+
+        let mut command = Command::new("Pods".to_string(), "kubectl".to_string());
+        command.args = vec![
+            "--context={{var:cluster}}".to_string(),
+            "-n".to_string(),
+            "{{var:namespace}}".to_string(),
+        ];
+        assert!(command.detect_placeholders().is_empty());
+
+        let traces = storage.trace_command(&command, Some(Path::new("/work/shop/")), None).unwrap();
+        assert_eq!(traces[0].value.as_deref(), Some("prod-eu"));
+        assert_eq!(traces[0].scope, Some(workspace.clone()));
+        assert_eq!(traces[0].shadowed.len(), 1);
+        assert_eq!(traces[0].shadowed[0].value, "staging");
+        assert_eq!(traces[1].scope, Some(VariableScope::Global));
+
+        let resolved = storage.resolve_command(&command, Some(Path::new("/work/shop")), None).unwrap();
+        assert_eq!(resolved.args, vec!["--context=prod-eu", "-n", "default"]);
+        let resolved = storage.resolve_command(&command, None, None).unwrap();
+        assert_eq!(resolved.args[0], "--context=staging");
+
+        let mut dev = Profile::new("dev".to_string());
+        dev.variables.insert("cluster".to_string(), "kind-local".to_string());
+        let traces = storage.trace_command(&command, Some(Path::new("/work/shop")), Some(&dev)).unwrap();
+        assert_eq!(traces[0].value.as_deref(), Some("kind-local"));
+        assert_eq!(traces[0].scope, Some(VariableScope::Profile { name: "dev".to_string() }));
+        assert_eq!(traces[0].shadowed.len(), 2);
+        assert!(storage.set(EditAccess::unrestricted(), "cluster", "x", VariableScope::Profile { name: "dev".to_string() }).is_err());
+
+        assert!(storage.remove(EditAccess::unrestricted(), "cluster", &VariableScope::Global).unwrap());
+        assert!(!storage.remove(EditAccess::unrestricted(), "cluster", &VariableScope::Global).unwrap());
+        let traces = storage.trace("{{var:cluster}}", None, None).unwrap();
+        assert_eq!(traces[0].value, None);
+        assert!(matches!(
+            storage.resolve_command(&command, None, None),
+            Err(CommandArgusError::InvalidCommand(message)) if message == "undefined variables: cluster"
+        ));
+    }
+}